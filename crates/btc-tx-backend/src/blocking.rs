@@ -0,0 +1,94 @@
+//! Synchronous facade over an async backend
+
+use std::future::Future;
+use std::time::Duration;
+use btc_tx_parser::{AddressActivity, ChainTip, OutputSpend, Transaction};
+use tokio::runtime::{Builder, Runtime};
+use crate::cancel::{cancellable, CancellationToken};
+use crate::error::BackendError;
+use crate::{AddressTx, ChainBackend};
+
+// Drives a backend on a private current-thread runtime; must not be used
+// from inside another tokio runtime
+pub struct BlockingBackend<B> {
+    inner: B,
+    runtime: Runtime,
+    cancel: Option<CancellationToken>,
+    // limit on each call, however many requests it makes
+    timeout: Option<Duration>,
+}
+
+impl<B: ChainBackend> BlockingBackend<B> {
+    pub fn new(inner: B) -> Result<Self, BackendError> {
+        let runtime = Builder::new_current_thread().enable_all().build()?;
+        Ok(Self { inner, runtime, cancel: None, timeout: None })
+    }
+
+    // Calls fail with `BackendError::Cancelled` once `token` is cancelled
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    // Calls fail with `BackendError::Timeout` after `timeout`
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    fn block_on<T>(&self, operation: impl Future<Output = Result<T, BackendError>>) -> Result<T, BackendError> {
+        self.runtime.block_on(cancellable(operation, self.cancel.as_ref(), self.timeout))
+    }
+
+    pub fn tx_hex(&self, txid: &str) -> Result<String, BackendError> {
+        self.block_on(self.inner.tx_hex(txid))
+    }
+
+    pub fn tip(&self) -> Result<ChainTip, BackendError> {
+        self.block_on(self.inner.tip())
+    }
+
+    pub fn confirmation_height(&self, txid: &str) -> Result<Option<u32>, BackendError> {
+        self.block_on(self.inner.confirmation_height(txid))
+    }
+
+    pub fn outspends(&self, txid: &str) -> Result<Vec<OutputSpend>, BackendError> {
+        self.block_on(self.inner.outspends(txid))
+    }
+
+    pub fn fetch_spends(&self, tx: &mut Transaction) -> Result<(), BackendError> {
+        self.block_on(self.inner.fetch_spends(tx))
+    }
+
+    pub fn address_txs(&self, address: &str, last_seen: Option<&str>) -> Result<Vec<AddressTx>, BackendError> {
+        self.block_on(self.inner.address_txs(address, last_seen))
+    }
+
+    pub fn address_history(&self, address: &str, limit: usize) -> Result<Vec<AddressActivity>, BackendError> {
+        self.block_on(self.inner.address_history(address, limit))
+    }
+
+    pub fn transaction(&self, txid: &str) -> Result<Transaction, BackendError> {
+        self.block_on(self.inner.transaction(txid))
+    }
+
+    pub fn fetch_prevout_values(&self, tx: &mut Transaction) -> Result<(), BackendError> {
+        self.block_on(self.inner.fetch_prevout_values(tx))
+    }
+
+    pub fn fetch_prevout_values_with_progress(
+        &self,
+        tx: &mut Transaction,
+        progress: &(dyn Fn(usize, usize) + Send + Sync),
+    ) -> Result<(), BackendError> {
+        self.block_on(self.inner.fetch_prevout_values_with_progress(tx, progress))
+    }
+
+    pub fn fetch_prevout_heights(&self, tx: &Transaction) -> Result<Vec<Option<u32>>, BackendError> {
+        self.block_on(self.inner.fetch_prevout_heights(tx))
+    }
+}
@@ -0,0 +1,41 @@
+//! On-disk response cache
+
+use std::path::PathBuf;
+
+// One file per request path; cache failures never fail the request
+pub(crate) struct DiskCache {
+    dir: PathBuf,
+}
+
+impl DiskCache {
+    pub(crate) fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    // $XDG_CACHE_HOME/btc-tx-inspector, falling back to ~/.cache
+    pub(crate) fn default_dir() -> Option<PathBuf> {
+        if let Some(dir) = std::env::var_os("XDG_CACHE_HOME") {
+            return Some(PathBuf::from(dir).join("btc-tx-inspector"));
+        }
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache").join("btc-tx-inspector"))
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        let name: String = key
+            .trim_start_matches('/')
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        self.dir.join(name)
+    }
+
+    pub(crate) async fn load(&self, key: &str) -> Option<String> {
+        tokio::fs::read_to_string(self.entry_path(key)).await.ok()
+    }
+
+    pub(crate) async fn store(&self, key: &str, body: &str) {
+        if tokio::fs::create_dir_all(&self.dir).await.is_ok() {
+            let _ = tokio::fs::write(self.entry_path(key), body).await;
+        }
+    }
+}
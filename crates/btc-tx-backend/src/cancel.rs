@@ -0,0 +1,68 @@
+//! Cancellation and deadlines for backend operations
+
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use crate::error::BackendError;
+
+// Shared flag that stops backend operations; clones cancel together, and
+// `cancel` may be called from any thread, e.g. a Ctrl-C handler
+#[derive(Debug, Clone)]
+pub struct CancellationToken {
+    flag: Arc<watch::Sender<bool>>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self { flag: Arc::new(watch::Sender::new(false)) }
+    }
+
+    pub fn cancel(&self) {
+        self.flag.send_replace(true);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.flag.borrow()
+    }
+
+    // Resolves once the token is cancelled
+    pub async fn cancelled(&self) {
+        let mut flag = self.flag.subscribe();
+        let _ = flag.wait_for(|&cancelled| cancelled).await;
+    }
+}
+
+impl Default for CancellationToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Run `operation` until it finishes, `token` is cancelled or `timeout`
+// passes, whichever comes first. Dropping the operation drops its in-flight
+// requests; whatever it already wrote through `&mut` arguments stays.
+pub async fn cancellable<T>(
+    operation: impl Future<Output = Result<T, BackendError>>,
+    token: Option<&CancellationToken>,
+    timeout: Option<Duration>,
+) -> Result<T, BackendError> {
+    let cancelled = async {
+        match token {
+            Some(token) => token.cancelled().await,
+            None => std::future::pending().await,
+        }
+    };
+    let expired = async {
+        match timeout {
+            Some(timeout) => tokio::time::sleep(timeout).await,
+            None => std::future::pending().await,
+        }
+    };
+    tokio::select! {
+        biased;
+        _ = cancelled => Err(BackendError::Cancelled),
+        _ = expired => Err(BackendError::Timeout(timeout.unwrap_or_default())),
+        result = operation => result,
+    }
+}
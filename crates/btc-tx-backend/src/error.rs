@@ -0,0 +1,32 @@
+//! Backend error types
+
+use btc_tx_parser::ParseError;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum BackendError {
+    #[error("Request to {url} failed: {message}")]
+    Http { url: String, message: String },
+
+    #[error("Unexpected response from {url}: {message}")]
+    InvalidResponse { url: String, message: String },
+
+    #[error("Failed to parse previous transaction {txid}: {source}")]
+    Parse {
+        txid: String,
+        #[source]
+        source: ParseError,
+    },
+
+    #[error("Previous transaction {txid} has no output {vout}")]
+    MissingOutput { txid: String, vout: u32 },
+
+    #[error("Cancelled")]
+    Cancelled,
+
+    #[error("Timed out after {}s", .0.as_secs_f64())]
+    Timeout(std::time::Duration),
+
+    #[error("Failed to start async runtime: {0}")]
+    Runtime(#[from] std::io::Error),
+}
@@ -0,0 +1,223 @@
+//! Esplora HTTP backend with on-disk caching and rate limiting
+
+use async_trait::async_trait;
+use btc_tx_parser::{ChainTip, OutputSpend, Transaction};
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+use crate::cache::DiskCache;
+use crate::error::BackendError;
+use crate::{AddressTx, ChainBackend};
+
+pub const DEFAULT_ESPLORA_URL: &str = "https://blockstream.info/api";
+
+// Backend settings
+#[derive(Debug, Clone)]
+pub struct BackendConfig {
+    pub base_url: String,
+    pub use_cache: bool,
+    // defaults to $XDG_CACHE_HOME/btc-tx-inspector
+    pub cache_dir: Option<PathBuf>,
+    // 0 disables rate limiting
+    pub requests_per_second: f64,
+}
+
+impl Default for BackendConfig {
+    fn default() -> Self {
+        Self {
+            base_url: DEFAULT_ESPLORA_URL.to_string(),
+            use_cache: true,
+            cache_dir: None,
+            requests_per_second: 2.0,
+        }
+    }
+}
+
+// Esplora REST client (blockstream.info, mempool.space or self-hosted)
+pub struct EsploraBackend {
+    base_url: String,
+    client: reqwest::Client,
+    cache: Option<DiskCache>,
+    limiter: RateLimiter,
+}
+
+impl EsploraBackend {
+    pub fn new(config: &BackendConfig) -> Result<Self, BackendError> {
+        let cache = if config.use_cache {
+            config.cache_dir.clone()
+                .or_else(DiskCache::default_dir)
+                .map(DiskCache::new)
+        } else {
+            None
+        };
+
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .map_err(|e| BackendError::Http { url: config.base_url.clone(), message: e.to_string() })?;
+
+        Ok(Self {
+            base_url: config.base_url.trim_end_matches('/').to_string(),
+            client,
+            cache,
+            limiter: RateLimiter::new(config.requests_per_second),
+        })
+    }
+
+    async fn get(&self, path: &str, cacheable: bool) -> Result<String, BackendError> {
+        self.get_checked(path, cacheable, |_| Ok(())).await
+    }
+
+    // `check` vets every body, fresh or cached, before it is used or
+    // cached; a cached body that fails it is fetched again
+    async fn get_checked(
+        &self,
+        path: &str,
+        cacheable: bool,
+        check: impl Fn(&str) -> Result<(), String>,
+    ) -> Result<String, BackendError> {
+        if cacheable {
+            if let Some(cache) = &self.cache {
+                if let Some(body) = cache.load(path).await.filter(|body| check(body).is_ok()) {
+                    return Ok(body);
+                }
+            }
+        }
+
+        self.limiter.wait().await;
+        let url = format!("{}{}", self.base_url, path);
+        let http_error = |e: reqwest::Error| BackendError::Http { url: url.clone(), message: e.to_string() };
+        let body = self.client.get(&url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(http_error)?
+            .text()
+            .await
+            .map_err(http_error)?;
+        check(&body).map_err(|message| BackendError::InvalidResponse { url: url.clone(), message })?;
+
+        if cacheable {
+            if let Some(cache) = &self.cache {
+                cache.store(path, &body).await;
+            }
+        }
+        Ok(body)
+    }
+}
+
+#[async_trait]
+impl ChainBackend for EsploraBackend {
+    // Transactions are immutable, so always cacheable once the body is
+    // known to be the transaction asked for
+    async fn tx_hex(&self, txid: &str) -> Result<String, BackendError> {
+        let check = |body: &str| match Transaction::from_hex(body.trim()) {
+            Ok(tx) if tx.txid.eq_ignore_ascii_case(txid) => Ok(()),
+            Ok(tx) => Err(format!("body hashes to {}", tx.txid)),
+            Err(e) => Err(format!("body does not decode: {}", e)),
+        };
+        self.get_checked(&format!("/tx/{}/hex", txid), true, check).await
+    }
+
+    // Height and time both come from the tip block, so a block found
+    // between two requests can't pair one tip's height with another's time
+    async fn tip(&self) -> Result<ChainTip, BackendError> {
+        let hash = self.get("/blocks/tip/hash", false).await?;
+        let path = format!("/block/{}", hash.trim());
+        let block = self.get(&path, true).await?;
+        let invalid = |message: String| BackendError::InvalidResponse {
+            url: format!("{}{}", self.base_url, path),
+            message,
+        };
+        let block = serde_json::from_str::<serde_json::Value>(&block).map_err(|e| invalid(e.to_string()))?;
+        let height = block["height"].as_u64()
+            .and_then(|height| u32::try_from(height).ok())
+            .ok_or_else(|| invalid("block has no height".to_string()))?;
+        let time = block["timestamp"].as_u64().ok_or_else(|| invalid("block has no timestamp".to_string()))?;
+        Ok(ChainTip { height: Some(height), time })
+    }
+
+    // Not cached: an unconfirmed transaction's status changes
+    async fn confirmation_height(&self, txid: &str) -> Result<Option<u32>, BackendError> {
+        let path = format!("/tx/{}/status", txid);
+        let status = self.get(&path, false).await?;
+        let invalid = |message: String| BackendError::InvalidResponse {
+            url: format!("{}{}", self.base_url, path),
+            message,
+        };
+        let status = serde_json::from_str::<serde_json::Value>(&status).map_err(|e| invalid(e.to_string()))?;
+        match &status["block_height"] {
+            serde_json::Value::Null => Ok(None),
+            height => height.as_u64()
+                .and_then(|height| u32::try_from(height).ok())
+                .map(Some)
+                .ok_or_else(|| invalid(format!("block height {} out of range", height))),
+        }
+    }
+
+    // Not cached: an unspent output can be spent at any time
+    async fn outspends(&self, txid: &str) -> Result<Vec<OutputSpend>, BackendError> {
+        let path = format!("/tx/{}/outspends", txid);
+        let spends = self.get(&path, false).await?;
+        serde_json::from_str(&spends).map_err(|e| BackendError::InvalidResponse {
+            url: format!("{}{}", self.base_url, path),
+            message: e.to_string(),
+        })
+    }
+
+    // Not cached: new transactions are added to the front
+    async fn address_txs(&self, address: &str, last_seen: Option<&str>) -> Result<Vec<AddressTx>, BackendError> {
+        let path = match last_seen {
+            Some(txid) => format!("/address/{}/txs/chain/{}", address, txid),
+            None => format!("/address/{}/txs", address),
+        };
+        let page = self.get(&path, false).await?;
+        let invalid = |message: String| BackendError::InvalidResponse {
+            url: format!("{}{}", self.base_url, path),
+            message,
+        };
+        let page = serde_json::from_str::<Vec<serde_json::Value>>(&page).map_err(|e| invalid(e.to_string()))?;
+        page.iter()
+            .map(|tx| {
+                let txid = tx["txid"].as_str().ok_or_else(|| invalid("transaction has no txid".to_string()))?;
+                let height = match &tx["status"]["block_height"] {
+                    serde_json::Value::Null => None,
+                    height => Some(height.as_u64()
+                        .and_then(|height| u32::try_from(height).ok())
+                        .ok_or_else(|| invalid(format!("block height {} out of range", height)))?),
+                };
+                Ok(AddressTx { txid: txid.to_string(), height })
+            })
+            .collect()
+    }
+}
+
+// Slower limits than one request an hour are treated as one an hour
+const MAX_INTERVAL: Duration = Duration::from_secs(3600);
+
+// Spaces requests evenly so public instances are not hammered
+pub(crate) struct RateLimiter {
+    pub(crate) interval: Duration,
+    last: Mutex<Option<Instant>>,
+}
+
+impl RateLimiter {
+    // Zero, negative and NaN rates disable limiting
+    pub(crate) fn new(requests_per_second: f64) -> Self {
+        let interval = if requests_per_second > 0.0 {
+            Duration::try_from_secs_f64(1.0 / requests_per_second).map_or(MAX_INTERVAL, |interval| interval.min(MAX_INTERVAL))
+        } else {
+            Duration::ZERO
+        };
+        Self { interval, last: Mutex::new(None) }
+    }
+
+    async fn wait(&self) {
+        let mut last = self.last.lock().await;
+        if let Some(previous) = *last {
+            tokio::time::sleep_until(previous + self.interval).await;
+        }
+        *last = Some(Instant::now());
+    }
+}
@@ -0,0 +1,168 @@
+//! Chain data backends for enriching parsed transactions
+//!
+//! `ChainBackend` is the async interface used by servers; `EsploraBackend`
+//! implements it over HTTP on tokio, and `BlockingBackend` drives any backend
+//! from synchronous code such as the CLI. Operations can be bounded by a
+//! `CancellationToken` and a timeout, through `cancellable` in async code or
+//! `BlockingBackend::with_cancellation`/`with_timeout`.
+
+mod blocking;
+mod cache;
+mod cancel;
+mod error;
+mod esplora;
+
+#[cfg(test)]
+mod tests;
+
+pub use blocking::BlockingBackend;
+pub use cancel::{cancellable, CancellationToken};
+pub use error::BackendError;
+pub use esplora::{BackendConfig, EsploraBackend, DEFAULT_ESPLORA_URL};
+
+use async_trait::async_trait;
+use btc_tx_parser::{AddressActivity, ChainTip, OutputSpend, Transaction, TxOutput};
+use std::collections::hash_map::{Entry, HashMap};
+
+// A transaction touching an address, as the backend lists it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressTx {
+    pub txid: String,
+    // None while unconfirmed
+    pub height: Option<u32>,
+}
+
+#[async_trait]
+pub trait ChainBackend: Send + Sync {
+    // Raw transaction hex by txid
+    async fn tx_hex(&self, txid: &str) -> Result<String, BackendError>;
+
+    // Current chain tip height and block time
+    async fn tip(&self) -> Result<ChainTip, BackendError>;
+
+    // Height of the block that confirmed a transaction; None while unconfirmed
+    async fn confirmation_height(&self, txid: &str) -> Result<Option<u32>, BackendError>;
+
+    // Whether each output of a transaction is spent, in output order
+    async fn outspends(&self, txid: &str) -> Result<Vec<OutputSpend>, BackendError>;
+
+    // One page of the transactions touching an address, newest first: the
+    // first page when `last_seen` is None, otherwise the confirmed ones
+    // after that txid. An empty page ends the history.
+    async fn address_txs(&self, address: &str, last_seen: Option<&str>) -> Result<Vec<AddressTx>, BackendError>;
+
+    async fn transaction(&self, txid: &str) -> Result<Transaction, BackendError> {
+        let hex = self.tx_hex(txid).await?;
+        Transaction::from_hex(&hex).map_err(|source| BackendError::Parse {
+            txid: txid.to_string(),
+            source,
+        })
+    }
+
+    // Fill in input values from the previous outputs they spend
+    async fn fetch_prevout_values(&self, tx: &mut Transaction) -> Result<(), BackendError> {
+        self.fetch_prevout_values_with_progress(tx, &|_, _| {}).await
+    }
+
+    // Same, calling `progress(done, total)` after each input is valued
+    async fn fetch_prevout_values_with_progress(
+        &self,
+        tx: &mut Transaction,
+        progress: &(dyn Fn(usize, usize) + Send + Sync),
+    ) -> Result<(), BackendError> {
+        let total = tx.inputs.iter().filter(|i| !i.is_coinbase).count();
+        for (done, input) in tx.inputs.iter_mut().filter(|i| !i.is_coinbase).enumerate() {
+            let prev_tx = self.transaction(&input.txid.to_string()).await?;
+            let prevout = prev_tx.outputs.get(input.vout as usize).ok_or_else(|| BackendError::MissingOutput {
+                txid: input.txid.to_string(),
+                vout: input.vout,
+            })?;
+            input.value = Some(prevout.value);
+            progress(done + 1, total);
+        }
+        Ok(())
+    }
+
+    // Fill in each output's `spend`
+    async fn fetch_spends(&self, tx: &mut Transaction) -> Result<(), BackendError> {
+        let spends = self.outspends(&tx.txid).await?;
+        if spends.len() != tx.outputs.len() {
+            return Err(BackendError::InvalidResponse {
+                url: format!("/tx/{}/outspends", tx.txid),
+                message: format!("{} spends for {} outputs", spends.len(), tx.outputs.len()),
+            });
+        }
+        for (output, spend) in tx.outputs.iter_mut().zip(spends) {
+            output.spend = Some(spend);
+        }
+        Ok(())
+    }
+
+    // Confirmation heights of the transactions each input spends, in input
+    // order; coinbase inputs get None
+    async fn fetch_prevout_heights(&self, tx: &Transaction) -> Result<Vec<Option<u32>>, BackendError> {
+        let mut heights = Vec::with_capacity(tx.inputs.len());
+        for input in &tx.inputs {
+            let height = if input.is_coinbase {
+                None
+            } else {
+                self.confirmation_height(&input.txid.to_string()).await?
+            };
+            heights.push(height);
+        }
+        Ok(heights)
+    }
+
+    // What each of the `limit` newest transactions touching `address` paid
+    // into and spent from it, newest first. Inputs are matched against the
+    // outputs they spend, fetched from the backend when they are not in the
+    // history already.
+    async fn address_history(&self, address: &str, limit: usize) -> Result<Vec<AddressActivity>, BackendError> {
+        let mut entries: Vec<AddressTx> = Vec::new();
+        while entries.len() < limit {
+            let last_seen = entries.iter().rev().find(|entry| entry.height.is_some()).map(|entry| entry.txid.clone());
+            // with no confirmed transaction to page after, the first page was all
+            if last_seen.is_none() && !entries.is_empty() {
+                break;
+            }
+            let page = self.address_txs(address, last_seen.as_deref()).await?;
+            if page.is_empty() {
+                break;
+            }
+            entries.extend(page);
+        }
+        entries.truncate(limit);
+
+        // the history's own transactions, then the ones they spend from
+        let mut txs: HashMap<String, Transaction> = HashMap::new();
+        for entry in &entries {
+            txs.insert(entry.txid.clone(), self.transaction(&entry.txid).await?);
+        }
+        for entry in &entries {
+            let prev_txids: Vec<String> = txs[&entry.txid].inputs.iter()
+                .filter(|input| !input.is_coinbase)
+                .map(|input| input.txid.to_string())
+                .collect();
+            for prev_txid in prev_txids {
+                if let Entry::Vacant(slot) = txs.entry(prev_txid) {
+                    let prev_tx = self.transaction(slot.key()).await?;
+                    slot.insert(prev_tx);
+                }
+            }
+        }
+
+        let history = entries.iter()
+            .map(|entry| {
+                let tx = &txs[&entry.txid];
+                let prevouts: Vec<Option<&TxOutput>> = tx.inputs.iter()
+                    .map(|input| match input.is_coinbase {
+                        true => None,
+                        false => txs[&input.txid.to_string()].outputs.get(input.vout as usize),
+                    })
+                    .collect();
+                tx.address_activity(address, entry.height, &prevouts)
+            })
+            .collect();
+        Ok(history)
+    }
+}
@@ -0,0 +1,272 @@
+//Tests for btc-tx-backend crate
+
+use crate::{AddressTx, BackendConfig, BackendError, BlockingBackend, CancellationToken, ChainBackend, EsploraBackend};
+use crate::esplora::RateLimiter;
+use async_trait::async_trait;
+use btc_tx_parser::{Amount, ChainTip, OutputSpend, Transaction, Txid};
+use std::collections::HashMap;
+use std::time::Duration;
+
+// Two outputs: 1,000,000,000 and 4,000,000,000 sats
+const PREV_TX: &str = "0100000001c997a5e56e104102fa209c6a852dd90660a20b2d9c352423edce25857fcd3704000000004847304402204e45e16932b8af514961a1d3a1a25fdf3f4f7732e9d624c6c61548ab5fb8cd410220181522ec8eca07de4860a4acdd12909d831cc56cbbac4622082221a8768d1d0901ffffffff0200ca9a3b00000000434104ae1a62fe09c5f51b13905f07f06b99a2f7159b2225f374cd378d71302fa28414e7aab37397f554a7df5f142c21c1b7303b8a0626f1baded5c72a704f7e6cd84cac00286bee0000000043410411db93e1dcdb8a016b49840f8c53bc1eb68a382e97b1482ecad7b148a6909a5cb2e0eaddfb84ccf9744464f82e160bfa9b8b64f9d4c03f999b8643f656b412a3ac00000000";
+
+// Block 9's coinbase, paying 50 BTC to the key PREV_TX spends from
+const COINBASE_TX: &str = "01000000010000000000000000000000000000000000000000000000000000000000000000ffffffff0704ffff001d0134ffffffff0100f2052a0100000043410411db93e1dcdb8a016b49840f8c53bc1eb68a382e97b1482ecad7b148a6909a5cb2e0eaddfb84ccf9744464f82e160bfa9b8b64f9d4c03f999b8643f656b412a3ac00000000";
+
+struct MockBackend {
+    txs: HashMap<String, String>,
+}
+
+#[async_trait]
+impl ChainBackend for MockBackend {
+    async fn tx_hex(&self, txid: &str) -> Result<String, BackendError> {
+        self.txs.get(txid).cloned().ok_or_else(|| BackendError::Http {
+            url: format!("mock:/tx/{}/hex", txid),
+            message: "404".to_string(),
+        })
+    }
+
+    async fn tip(&self) -> Result<ChainTip, BackendError> {
+        Ok(ChainTip { height: Some(850_000), time: 1_700_000_000 })
+    }
+
+    // Every known transaction confirmed at 840,000
+    async fn confirmation_height(&self, txid: &str) -> Result<Option<u32>, BackendError> {
+        self.tx_hex(txid).await.map(|_| Some(840_000))
+    }
+
+    // Output 0 of every known transaction spent by input 1 of SPENDER
+    async fn outspends(&self, txid: &str) -> Result<Vec<OutputSpend>, BackendError> {
+        let tx = self.transaction(txid).await?;
+        Ok(tx.outputs.iter()
+            .map(|output| match output.index {
+                0 => OutputSpend { spent: true, txid: Some(SPENDER.parse().unwrap()), vin: Some(1) },
+                _ => OutputSpend { spent: false, txid: None, vin: None },
+            })
+            .collect())
+    }
+
+    // Known transactions paying the address or spending from it, all on
+    // one confirmed page
+    async fn address_txs(&self, address: &str, last_seen: Option<&str>) -> Result<Vec<AddressTx>, BackendError> {
+        if last_seen.is_some() {
+            return Ok(Vec::new());
+        }
+        let known: HashMap<String, Transaction> = self.txs.values()
+            .map(|hex| Transaction::from_hex(hex).unwrap())
+            .map(|tx| (tx.txid.clone(), tx))
+            .collect();
+        let touches = |tx: &Transaction| {
+            tx.outputs.iter().any(|o| o.pays(address))
+                || tx.inputs.iter().any(|i| known.get(&i.txid.to_string())
+                    .and_then(|prev| prev.outputs.get(i.vout as usize))
+                    .is_some_and(|o| o.pays(address)))
+        };
+        let mut page: Vec<AddressTx> = known.values()
+            .filter(|tx| touches(tx))
+            .map(|tx| AddressTx { txid: tx.txid.clone(), height: Some(840_000) })
+            .collect();
+        page.sort_by(|a, b| a.txid.cmp(&b.txid));
+        Ok(page)
+    }
+}
+
+const SPENDER: &str = "cccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccc";
+
+// Answers for one transaction and hangs on everything else
+struct StalledBackend {
+    known: String,
+}
+
+#[async_trait]
+impl ChainBackend for StalledBackend {
+    async fn tx_hex(&self, txid: &str) -> Result<String, BackendError> {
+        if txid != self.known {
+            std::future::pending::<()>().await;
+        }
+        Ok(PREV_TX.to_string())
+    }
+
+    async fn tip(&self) -> Result<ChainTip, BackendError> {
+        std::future::pending().await
+    }
+
+    async fn confirmation_height(&self, _txid: &str) -> Result<Option<u32>, BackendError> {
+        std::future::pending().await
+    }
+
+    async fn outspends(&self, _txid: &str) -> Result<Vec<OutputSpend>, BackendError> {
+        std::future::pending().await
+    }
+
+    async fn address_txs(&self, _address: &str, _last_seen: Option<&str>) -> Result<Vec<AddressTx>, BackendError> {
+        std::future::pending().await
+    }
+}
+
+// Spends `prev`:0 and `prev`:`vout`
+fn spending_tx(prev: &str, vout: u8) -> Transaction {
+    let input = |vout: u8| format!("{}{:02x}00000000ffffffff", prev, vout);
+    let hex = format!("0100000002{}{}0100e1f505000000000000000000", input(0), input(vout));
+    Transaction::from_hex(&hex).unwrap()
+}
+
+#[test]
+fn test_blocking_prevout_enrichment() {
+    let prev = "aa".repeat(32);
+    let backend = BlockingBackend::new(MockBackend {
+        txs: HashMap::from([(prev.clone(), PREV_TX.to_string())]),
+    })
+    .unwrap();
+
+    let mut tx = spending_tx(&prev, 1);
+    backend.fetch_prevout_values(&mut tx).unwrap();
+    assert_eq!(tx.inputs[0].value, Some(Amount::from_sat(1_000_000_000)));
+    assert_eq!(tx.inputs[1].value, Some(Amount::from_sat(4_000_000_000)));
+    assert_eq!(tx.calculate_fee(), Some(Amount::from_sat(4_900_000_000)));
+    assert_eq!(backend.tip().unwrap().height, Some(850_000));
+
+    let mut tx = spending_tx(&prev, 5);
+    assert!(matches!(
+        backend.fetch_prevout_values(&mut tx),
+        Err(BackendError::MissingOutput { vout: 5, .. })
+    ));
+
+    let mut tx = spending_tx(&"bb".repeat(32), 0);
+    assert!(matches!(backend.fetch_prevout_values(&mut tx), Err(BackendError::Http { .. })));
+}
+
+#[test]
+fn test_blocking_prevout_heights() {
+    let prev = "aa".repeat(32);
+    let backend = BlockingBackend::new(MockBackend {
+        txs: HashMap::from([(prev.clone(), PREV_TX.to_string())]),
+    })
+    .unwrap();
+
+    let mut tx = spending_tx(&prev, 1);
+    let heights = backend.fetch_prevout_heights(&tx).unwrap();
+    assert_eq!(heights, vec![Some(840_000), Some(840_000)]);
+    backend.fetch_prevout_values(&mut tx).unwrap();
+    let age = tx.coin_age(850_000, &heights);
+    assert_eq!(age.inputs[1].age_blocks, Some(10_000));
+    // 50 BTC aged 10,000 blocks
+    assert!((age.value_days_destroyed.unwrap() - 50.0 * 10_000.0 / 144.0).abs() < 1e-6);
+
+    let tx = spending_tx(&"bb".repeat(32), 0);
+    assert!(matches!(backend.fetch_prevout_heights(&tx), Err(BackendError::Http { .. })));
+}
+
+#[test]
+fn test_blocking_timeout_and_cancellation() {
+    let prev = "aa".repeat(32);
+    let stalled = || StalledBackend { known: prev.clone() };
+
+    let backend = BlockingBackend::new(stalled()).unwrap().with_timeout(Duration::from_millis(50));
+    assert!(matches!(backend.tip(), Err(BackendError::Timeout(timeout)) if timeout == Duration::from_millis(50)));
+
+    // the first input is valued before the second stalls, and keeps its value
+    let hex = format!("0100000002{}0000000000ffffffff{}0000000000ffffffff0100e1f505000000000000000000", prev, "bb".repeat(32));
+    let mut tx = Transaction::from_hex(&hex).unwrap();
+    assert!(matches!(backend.fetch_prevout_values(&mut tx), Err(BackendError::Timeout(_))));
+    assert_eq!(tx.inputs[0].value, Some(Amount::from_sat(1_000_000_000)));
+    assert_eq!(tx.inputs[1].value, None);
+
+    // cancelled from another thread while a request is in flight
+    let token = CancellationToken::new();
+    let backend = BlockingBackend::new(stalled()).unwrap().with_cancellation(token.clone());
+    let canceller = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(20));
+        token.cancel();
+    });
+    assert!(matches!(backend.confirmation_height(&prev), Err(BackendError::Cancelled)));
+    canceller.join().unwrap();
+    // and every later call fails straight away
+    assert!(matches!(backend.tx_hex(&prev), Err(BackendError::Cancelled)));
+}
+
+#[test]
+fn test_rate_limiter_intervals() {
+    assert_eq!(RateLimiter::new(2.0).interval, Duration::from_millis(500));
+    assert_eq!(RateLimiter::new(0.0).interval, Duration::ZERO);
+    assert_eq!(RateLimiter::new(f64::NAN).interval, Duration::ZERO);
+    // too slow to represent, or just too slow: capped rather than a panic
+    assert_eq!(RateLimiter::new(1e-320).interval, Duration::from_secs(3600));
+    assert_eq!(RateLimiter::new(1e-6).interval, Duration::from_secs(3600));
+}
+
+#[test]
+fn test_blocking_output_spends() {
+    let mut tx = Transaction::from_hex(PREV_TX).unwrap();
+    let backend = BlockingBackend::new(MockBackend {
+        txs: HashMap::from([(tx.txid.clone(), PREV_TX.to_string())]),
+    })
+    .unwrap();
+
+    backend.fetch_spends(&mut tx).unwrap();
+    let spender: Txid = SPENDER.parse().unwrap();
+    assert_eq!(tx.outputs[0].spend, Some(OutputSpend { spent: true, txid: Some(spender), vin: Some(1) }));
+    assert_eq!(tx.outputs[1].spend.as_ref().map(|spend| spend.spent), Some(false));
+
+    // a backend that answers for a different output count is rejected
+    let mut other = spending_tx(&"aa".repeat(32), 1);
+    let backend = BlockingBackend::new(MockBackend {
+        txs: HashMap::from([(other.txid.clone(), PREV_TX.to_string())]),
+    })
+    .unwrap();
+    assert!(matches!(backend.fetch_spends(&mut other), Err(BackendError::InvalidResponse { .. })));
+    assert!(other.outputs[0].spend.is_none());
+}
+
+#[test]
+fn test_blocking_address_history() {
+    let prev = Transaction::from_hex(PREV_TX).unwrap();
+    // spending_tx takes the txid in wire byte order
+    let prev_wire: Vec<u8> = btc_tx_parser::decode_hex(&prev.txid).unwrap().into_iter().rev().collect();
+    let spender = spending_tx(&btc_tx_parser::encode_hex(&prev_wire), 1);
+    let spender_hex = btc_tx_parser::encode_hex(&spender.to_bytes());
+    let backend = BlockingBackend::new(MockBackend {
+        txs: [COINBASE_TX, PREV_TX, &spender_hex].iter()
+            .map(|hex| (Transaction::from_hex(hex).unwrap().txid, hex.to_string()))
+            .collect(),
+    })
+    .unwrap();
+
+    // the coinbase funds the key, PREV_TX spends it and pays 40 BTC back,
+    // and the spender spends that
+    let history = backend.address_history("12cbQLTFMXRnSzktFkuoG3eHoMeFtpTu3S", 10).unwrap();
+    assert_eq!(history.len(), 3);
+    let activity = |txid: &str| history.iter().find(|a| a.txid == txid).unwrap();
+    let coinbase = Transaction::from_hex(COINBASE_TX).unwrap();
+    assert_eq!(activity(&coinbase.txid).funded, Amount::from_sat(5_000_000_000));
+    assert_eq!(activity(&prev.txid).funded_outputs, vec![1]);
+    assert_eq!(activity(&prev.txid).net_sats(), -1_000_000_000);
+    assert_eq!(activity(&spender.txid).spent_inputs, vec![1]);
+    assert_eq!(activity(&spender.txid).spent, Amount::from_sat(4_000_000_000));
+    assert!(history.iter().all(|a| a.height == Some(840_000)));
+
+    assert_eq!(backend.address_history("12cbQLTFMXRnSzktFkuoG3eHoMeFtpTu3S", 1).unwrap().len(), 1);
+}
+
+#[test]
+fn test_esplora_cached_tx_is_verified() {
+    let dir = std::env::temp_dir().join(format!("btc-tx-backend-verify-{}", std::process::id()));
+    let prev = Transaction::from_hex(PREV_TX).unwrap();
+    let coinbase = Transaction::from_hex(COINBASE_TX).unwrap();
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join(format!("tx_{}_hex", prev.txid)), PREV_TX).unwrap();
+    // a body for another transaction under this txid's entry
+    std::fs::write(dir.join(format!("tx_{}_hex", coinbase.txid)), PREV_TX).unwrap();
+
+    // nothing listens on the discard port, so only the cache can answer
+    let backend = BlockingBackend::new(EsploraBackend::new(&BackendConfig {
+        base_url: "http://127.0.0.1:9".to_string(),
+        use_cache: true,
+        cache_dir: Some(dir.clone()),
+        requests_per_second: 0.0,
+    }).unwrap()).unwrap();
+    assert_eq!(backend.tx_hex(&prev.txid).unwrap(), PREV_TX);
+    assert!(matches!(backend.tx_hex(&coinbase.txid), Err(BackendError::Http { .. })));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,100 @@
+//! On-disk cache of `pipe` output, so re-running a report over the same
+//! dataset skips parsing, analysis and serialization of every line it has
+//! seen. Entries are keyed by the transaction's wtxid (the double SHA-256 of
+//! its raw bytes), the input values given on the line and every option that
+//! changes the rendered line (findings, exact BTC strings); a cache written
+//! by another release is cleared on open.
+
+use std::path::{Path, PathBuf};
+use btc_tx_parser::{decode_hex, sha256d};
+use serde::Serialize;
+
+// Holds the release that wrote the entries; no transaction key is this short
+const VERSION_KEY: &[u8] = b"version";
+
+#[derive(Clone)]
+pub struct SummaryCache {
+    db: sled::Db,
+    dir: PathBuf,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CacheStats {
+    pub path: PathBuf,
+    pub entries: usize,
+    // size of the database files, which sled frees lazily
+    pub bytes_on_disk: u64,
+}
+
+impl SummaryCache {
+    pub fn open(dir: &Path) -> Result<Self, String> {
+        let db = sled::open(dir).map_err(|e| format!("Failed to open cache '{}': {}", dir.display(), e))?;
+        let cache = Self { db, dir: dir.to_path_buf() };
+        let version = env!("CARGO_PKG_VERSION").as_bytes();
+        let current = cache.db.get(VERSION_KEY).map_err(|e| e.to_string())?;
+        if current.as_deref() != Some(version) {
+            cache.clear()?;
+        }
+        Ok(cache)
+    }
+
+    // `summaries` under --cache-dir or $XDG_CACHE_HOME/btc-tx-inspector,
+    // falling back to ~/.cache
+    pub fn default_dir(cache_dir: Option<&Path>) -> Option<PathBuf> {
+        let base = match cache_dir {
+            Some(dir) => dir.to_path_buf(),
+            None => match std::env::var_os("XDG_CACHE_HOME") {
+                Some(dir) => PathBuf::from(dir).join("btc-tx-inspector"),
+                None => PathBuf::from(std::env::var_os("HOME")?).join(".cache").join("btc-tx-inspector"),
+            },
+        };
+        Some(base.join("summaries"))
+    }
+
+    // Key for a `<hex> [values]` line, or None if the hex doesn't decode
+    // (the line then fails to parse anyway)
+    pub fn key(line: &str, analyze: bool, exact_btc: bool) -> Option<Vec<u8>> {
+        let mut fields = line.split_whitespace();
+        let bytes = decode_hex(fields.next()?).ok()?;
+        let mut key = sha256d(&bytes).to_vec();
+        key.push(analyze as u8);
+        key.push(exact_btc as u8);
+        if let Some(values) = fields.next() {
+            key.extend_from_slice(values.as_bytes());
+        }
+        Some(key)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn get(&self, key: &[u8]) -> Option<String> {
+        let value = self.db.get(key).ok()??;
+        String::from_utf8(value.to_vec()).ok()
+    }
+
+    // Cache failures never fail the line
+    pub fn insert(&self, key: &[u8], json: &str) {
+        if let Err(e) = self.db.insert(key, json.as_bytes()) {
+            tracing::warn!("Failed to cache summary: {}", e);
+        }
+    }
+
+    pub fn stats(&self) -> Result<CacheStats, String> {
+        Ok(CacheStats {
+            path: self.dir.clone(),
+            entries: self.db.len().saturating_sub(1),
+            bytes_on_disk: self.db.size_on_disk().map_err(|e| e.to_string())?,
+        })
+    }
+
+    // Remove every entry, returning how many there were
+    pub fn clear(&self) -> Result<usize, String> {
+        let entries = self.db.iter().keys().filter(|key| key.as_deref().is_ok_and(|key| key != VERSION_KEY)).count();
+        self.db.clear().map_err(|e| e.to_string())?;
+        self.db.insert(VERSION_KEY, env!("CARGO_PKG_VERSION").as_bytes()).map_err(|e| e.to_string())?;
+        self.db.flush().map_err(|e| e.to_string())?;
+        Ok(entries)
+    }
+}
@@ -0,0 +1,44 @@
+//! User configuration file (`$XDG_CONFIG_HOME/btc-tx-inspector/config.toml`)
+
+use btc_tx_render::Theme;
+use serde::Deserialize;
+use std::path::PathBuf;
+
+// Settings that apply to every invocation; command-line flags override them
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    // color theme ("default", "high-contrast", "colorblind-safe", "monochrome")
+    #[serde(default)]
+    pub theme: Option<Theme>,
+    // hide heuristic findings below this confidence (0.0-1.0)
+    #[serde(default)]
+    pub min_confidence: Option<f64>,
+}
+
+impl Config {
+    pub fn from_toml(text: &str) -> Result<Self, String> {
+        toml::from_str(text).map_err(|e| format!("Invalid config: {}", e))
+    }
+
+    // A missing file is an empty config; an unreadable or invalid one is an error
+    pub fn load() -> Result<Self, String> {
+        let Some(path) = default_path() else {
+            return Ok(Self::default());
+        };
+        match std::fs::read_to_string(&path) {
+            Ok(text) => Self::from_toml(&text).map_err(|e| format!("{}: {}", path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+        }
+    }
+}
+
+// $XDG_CONFIG_HOME/btc-tx-inspector/config.toml, falling back to ~/.config
+fn default_path() -> Option<PathBuf> {
+    let dir = match std::env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(dir.join("btc-tx-inspector").join("config.toml"))
+}
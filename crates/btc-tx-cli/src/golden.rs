@@ -0,0 +1,55 @@
+//! Golden output files for the transaction renderers
+//!
+//! `tests/golden/transactions.txt` lists the curated transactions as
+//! `<name> <raw_tx_hex> [input values]`; each one has a checked-in rendering
+//! per output format in `tests/golden/<name>.<format>.txt`. The unit tests fail
+//! when output drifts, and `btc-tx-inspector golden --bless` rewrites the files
+//! after a deliberate format change. Colors must be disabled by the caller.
+
+use std::path::{Path, PathBuf};
+use btc_tx_render::{Format, JsonStyle};
+use crate::parse_batch_line;
+
+const FORMATS: &[(&str, Format)] = &[
+    ("pretty", Format::Pretty),
+    ("json", Format::Json),
+    ("summary", Format::Summary),
+    ("ascii", Format::Ascii),
+];
+
+pub fn golden_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("golden")
+}
+
+// Render every fixture in every format; returns the golden files that differ
+// (or, with `bless`, the ones rewritten)
+pub fn check(bless: bool) -> Result<Vec<String>, String> {
+    let dir = golden_dir();
+    let list = dir.join("transactions.txt");
+    let fixtures = std::fs::read_to_string(&list)
+        .map_err(|e| format!("Failed to read '{}': {}", list.display(), e))?;
+
+    let mut changed = Vec::new();
+    for line in fixtures.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+        let (name, tx_line) = line.split_once(char::is_whitespace)
+            .ok_or_else(|| format!("Fixture line without a transaction: {}", line))?;
+        let tx = parse_batch_line(tx_line.trim()).map_err(|e| format!("{}: {}", name, e))?;
+
+        for (extension, format) in FORMATS {
+            let mut rendered = Vec::new();
+            btc_tx_render::write_transaction(&mut rendered, &tx, *format, false, JsonStyle::default())
+                .map_err(|e| format!("{}: {}", name, e))?;
+
+            let file = format!("{}.{}.txt", name, extension);
+            let path = dir.join(&file);
+            if std::fs::read(&path).ok().as_deref() == Some(rendered.as_slice()) {
+                continue;
+            }
+            if bless {
+                std::fs::write(&path, &rendered).map_err(|e| format!("Failed to write '{}': {}", path.display(), e))?;
+            }
+            changed.push(file);
+        }
+    }
+    Ok(changed)
+}
@@ -1,37 +1,570 @@
 // BTC Transaction CLI
 
-use btc_tx_parser::Transaction;
-use clap::{Parser, ValueEnum};
+#[cfg(feature = "cache")]
+mod cache;
+mod config;
+mod golden;
+mod pipe;
+mod progress;
+mod wizard;
+#[cfg(feature = "serve")]
+mod serve;
+
+#[cfg(test)]
+mod tests;
+
+use btc_tx_backend::{BackendConfig, BackendError, BlockingBackend, CancellationToken, EsploraBackend};
+use btc_tx_parser::{
+    byte_diff, check_canonical, Amount, BlockFileCheckpoint, AnalyzerRegistry, Finding, format_hex, Network, normalize_hex, parse_messages, parse_batch_line, OtsProof, ScriptTemplate, BatchStats, Block, BlockFiles, BlockHeader, BundleNote, CompactBlock, BlockTemplate, ChainTip, StratumJob, FetchStatus, Psbt, Pseudonymizer, Redaction,
+    PolicyProfile, StreamParser, StressReport, Transaction, TxBundle, WirePayload, MAX_ENTROPY_INPUTS, MAX_ENTROPY_OUTPUTS,
+};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use btc_tx_render::{paint, JsonStyle, Role};
+#[cfg(feature = "mmap")]
+use btc_tx_parser::MappedFile;
 use colored::Colorize;
-use std::io::{self, Read};
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::Duration;
 
 #[derive(Parser)]
 #[command(name = "btc-tx-inspector")]
 #[command(author = "Bitcoin Transaction Visualizer Contributors")]
 #[command(version)]
 #[command(about = "Parse and inspect raw Bitcoin transactions")]
+#[command(args_conflicts_with_subcommands = true)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    source: TxSource,
+
+    #[arg(short, long, value_enum, default_value = "pretty", global = true)]
+    output: OutputFormat,
+
+    #[arg(long, global = true)]
+    raw_scripts: bool,
+
+    #[arg(long, global = true)]
+    compact: bool,
+
+    #[arg(long, global = true)]
+    exact_btc: bool, // Write value_btc, total_output_btc and fee_btc as exact 8-decimal strings instead of floats
+
+    #[arg(long, global = true)]
+    analyze: bool, // Run the built-in analyzers and include their findings
+
+    #[arg(long, value_name = "0-1", global = true, value_parser = parse_confidence)]
+    min_confidence: Option<f64>, // Hide heuristic findings below this confidence; overrides the config file
+
+    #[arg(long, value_name = "DIR", global = true)]
+    dump_data: Option<PathBuf>, // Write large witness items and pushes to files in DIR
+
+    #[arg(long, value_enum, value_name = "WHAT", global = true)]
+    redact: Option<RedactMode>, // Mask addresses and txids, bucket values, or both, for sharing output publicly
+
+    #[arg(long, value_name = "KEY", global = true)]
+    pseudonymize: Option<String>, // Replace txids, keys and addresses with stable HMAC pseudonyms under KEY
+
+    #[arg(short, long, global = true)]
+    quiet: bool, // Hide progress bars
+
+    #[arg(long, value_enum, global = true)]
+    theme: Option<ThemeName>, // Color theme; overrides the config file
+
+    #[arg(long, value_name = "LEVEL", global = true)]
+    log_level: Option<tracing::Level>, // Parser diagnostics on stderr (error, warn, info, debug, trace)
+
+    #[command(flatten)]
+    backend: BackendArgs,
+}
+
+impl Cli {
+    fn json_style(&self) -> JsonStyle {
+        JsonStyle { compact: self.compact, exact_btc: self.exact_btc }
+    }
+
+    // One object per line, for NDJSON streams
+    fn json_line_style(&self) -> JsonStyle {
+        JsonStyle { compact: true, ..self.json_style() }
+    }
+}
+
+// Where the transaction comes from and how its inputs are valued
+#[derive(Args)]
+struct TxSource {
     #[arg(value_name = "TX_HEX")]
     tx_hex: Option<String>, // Transaction hex input
 
     #[arg(short, long, value_name = "FILE")]
     file: Option<String>, // File input option
 
-    #[arg(short, long, value_enum, default_value = "pretty")]
-    output: OutputFormat,
+    #[arg(long, value_delimiter = ',')]
+    input_values: Option<Vec<u64>>, // Input values for fee calculation
 
     #[arg(long)]
-    raw_scripts: bool,
+    fetch_prevouts: bool, // Look up input values from the backend
 
     #[arg(long)]
-    compact: bool,
+    check_spent: bool, // Look up whether each output has been spent
 
-    #[arg(long, value_delimiter = ',')]
-    input_values: Option<Vec<u64>>, // Input values for fee calculation
+    #[arg(long)]
+    strict: bool, // Reject trailing bytes after the locktime
+}
+
+impl TxSource {
+    // "-", or no hex and no file
+    fn reads_stdin(&self) -> bool {
+        self.file.is_none() && self.tx_hex.as_deref().is_none_or(|hex| hex == "-")
+    }
+}
+
+// Backend options shared by every network-enabled command
+#[derive(Args)]
+struct BackendArgs {
+    #[arg(long, value_name = "URL", default_value = btc_tx_backend::DEFAULT_ESPLORA_URL, global = true)]
+    esplora_url: String,
+
+    #[arg(long, global = true)]
+    no_cache: bool, // Bypass the on-disk response cache
+
+    #[arg(long, value_name = "DIR", global = true)]
+    cache_dir: Option<PathBuf>,
+
+    #[arg(long, value_name = "REQ_PER_SEC", default_value_t = 2.0, value_parser = parse_rate_limit, global = true)]
+    rate_limit: f64, // 0 disables rate limiting
+
+    #[arg(long, value_name = "SECS", value_parser = parse_timeout, global = true)]
+    timeout: Option<Duration>, // Give up on each backend lookup after SECS
+}
+
+impl BackendArgs {
+    fn connect(&self) -> BlockingBackend<EsploraBackend> {
+        EsploraBackend::new(&BackendConfig {
+            base_url: self.esplora_url.clone(),
+            use_cache: !self.no_cache,
+            cache_dir: self.cache_dir.clone(),
+            requests_per_second: self.rate_limit,
+        })
+        .and_then(BlockingBackend::new)
+        .map(|backend| match self.timeout {
+            Some(timeout) => backend.with_timeout(timeout),
+            None => backend,
+        })
+        .map(|backend| backend.with_cancellation(interrupt_token()))
+        .unwrap_or_else(|e| exit_with_error(&e.to_string()))
+    }
+}
+
+fn parse_timeout(text: &str) -> Result<Duration, String> {
+    match text.parse::<f64>().ok().filter(|secs| *secs > 0.0).map(Duration::try_from_secs_f64) {
+        Some(Ok(timeout)) => Ok(timeout),
+        _ => Err(format!("'{}' is not a positive number of seconds", text)),
+    }
+}
+
+// 0, or between one request every 100 seconds and 1000 a second
+fn parse_rate_limit(text: &str) -> Result<f64, String> {
+    match text.parse::<f64>() {
+        Ok(rate) if rate == 0.0 || (0.01..=1000.0).contains(&rate) => Ok(rate),
+        _ => Err(format!("'{}' is not 0 or a rate between 0.01 and 1000 requests per second", text)),
+    }
+}
+
+// Cancelled by the first Ctrl-C, so backend lookups stop and what they got so
+// far is still shown; a second Ctrl-C exits at once
+fn interrupt_token() -> CancellationToken {
+    static TOKEN: OnceLock<CancellationToken> = OnceLock::new();
+    TOKEN.get_or_init(|| {
+        let token = CancellationToken::new();
+        let handler = token.clone();
+        let installed = ctrlc::set_handler(move || {
+            if handler.is_cancelled() {
+                std::process::exit(130);
+            }
+            handler.cancel();
+            eprintln!("Cancelling backend lookups (Ctrl-C again to quit)");
+        });
+        if let Err(e) = installed {
+            tracing::warn!("Ctrl-C cancellation unavailable: {}", e);
+        }
+        token
+    }).clone()
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Save or load offline transaction bundles (.txbundle)
+    Bundle {
+        #[command(subcommand)]
+        action: BundleAction,
+    },
+    /// Look up an address's transactions through the backend
+    Address {
+        #[command(subcommand)]
+        action: AddressAction,
+    },
+    /// Decode a PSBT and show multisig signing progress
+    Psbt {
+        #[arg(value_name = "PSBT")]
+        psbt: Option<String>, // Base64 or hex; "-" reads stdin
+
+        #[arg(short, long, value_name = "FILE")]
+        file: Option<PathBuf>, // Binary or text .psbt file
+
+        #[arg(long)]
+        extract: bool, // Report finalization readiness and extract the network transaction
+    },
+    /// Show when each timelock in the transaction becomes spendable
+    Timelocks {
+        #[command(flatten)]
+        source: TxSource,
+
+        #[arg(long, value_name = "HEIGHT")]
+        height: Option<u32>, // Current block height
+
+        #[arg(long, value_name = "UNIX_TIME")]
+        time: Option<u64>, // Current time (defaults to now)
+
+        #[arg(long)]
+        fetch_tip: bool, // Read height and time from the backend's chain tip
+    },
+    /// Compute coin age per input and the value-days the transaction destroys
+    CoinAge {
+        #[command(flatten)]
+        source: TxSource,
+
+        #[arg(long, value_name = "HEIGHT")]
+        height: Option<u32>, // Height the transaction confirmed at (defaults to the tip with --fetch-heights)
+
+        #[arg(long, value_delimiter = ',', value_name = "HEIGHTS")]
+        prevout_heights: Option<Vec<u32>>, // Confirmation height of each input's prevout, in input order
+
+        #[arg(long, conflicts_with = "prevout_heights")]
+        fetch_heights: bool, // Look up prevout confirmation heights from the backend
+    },
+    /// Validate a transaction against a policy profile (TOML)
+    Check {
+        #[command(flatten)]
+        source: TxSource,
+
+        #[arg(long, value_name = "PROFILE")]
+        policy: PathBuf,
+    },
+    /// Write a self-contained HTML report of the transaction
+    Report {
+        #[command(flatten)]
+        source: TxSource,
+
+        #[arg(long, value_name = "FILE", conflicts_with = "tx_hex")]
+        bundle: Option<PathBuf>, // Build the report from a saved bundle
+
+        #[arg(long, value_name = "FILE", default_value = "report.html")]
+        out: PathBuf,
+
+        #[arg(long = "label", value_name = "TEXT")]
+        labels: Vec<String>,
+    },
+    /// Write the body of every inscription revealed by a transaction to files
+    ExtractInscriptions {
+        #[command(flatten)]
+        source: TxSource,
+
+        #[arg(long, value_name = "DIR")]
+        out: PathBuf,
+    },
+    /// Classify a Lightning channel close and list its pending HTLCs
+    ChannelClose {
+        #[command(flatten)]
+        source: TxSource,
+
+        #[arg(long = "script", value_name = "HEX")]
+        scripts: Vec<String>, // Witness scripts of the commitment's outputs, to resolve them
+
+        #[arg(long, value_name = "HEX", requires = "accepter_basepoint")]
+        opener_basepoint: Option<String>, // Payment basepoints of the channel's opener and accepter, to decode the commitment number
+
+        #[arg(long, value_name = "HEX", requires = "opener_basepoint")]
+        accepter_basepoint: Option<String>,
+    },
+    /// Verify an OpenTimestamps proof against the transaction's OP_RETURN commitment
+    Timestamp {
+        #[command(flatten)]
+        source: TxSource,
+
+        #[arg(long, value_name = "FILE")]
+        ots: PathBuf, // Detached .ots proof of the timestamped file
+    },
+    /// Show which inputs could have funded each output (needs input values)
+    Flows {
+        #[command(flatten)]
+        source: TxSource,
+    },
+    /// List the public keys revealed by the inputs with every address of each
+    Keys {
+        #[command(flatten)]
+        source: TxSource,
+    },
+    /// List every public key in the transaction and check its encoding
+    Pubkeys {
+        #[command(flatten)]
+        source: TxSource,
+    },
+    /// Walk through the transaction one part at a time, with explanations
+    Wizard {
+        #[command(flatten)]
+        source: TxSource,
+    },
+    /// Aggregate statistics and key/script reuse across many transactions
+    Stats {
+        #[arg(value_name = "FILE")]
+        file: Option<PathBuf>, // One transaction hex per line; stdin if omitted
+    },
+    /// Decode hex lines from stdin to NDJSON until EOF, for long-running pipelines
+    Pipe {
+        #[arg(long, value_name = "SECS", default_value_t = 10)]
+        stats_interval: u64, // Throughput report interval on stderr; 0 disables
+
+        #[arg(long, value_name = "BYTES", default_value_t = 8_000_000)]
+        max_line_bytes: usize, // Longer lines are reported as errors and skipped
+
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        parse_workers: usize, // Parser threads; 0 for one per CPU
+
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        analyze_workers: usize, // Analysis threads; 0 for one per CPU
+
+        #[arg(long, value_name = "N", default_value_t = 1)]
+        render_workers: usize, // JSON serializer threads; 0 for one per CPU
+
+        #[arg(long, value_name = "LINES", default_value_t = 1024)]
+        queue_depth: usize, // Lines read but not yet written, at most
+
+        #[cfg(feature = "cache")]
+        #[arg(long)]
+        cache: bool, // Reuse output for transactions seen in earlier runs, and store new output
+    },
+    /// Parse and re-serialize seeded random transactions as a self-check
+    Stress {
+        #[arg(long, value_name = "N", default_value_t = 100_000)]
+        count: u64, // Transactions to generate
+
+        #[arg(long, value_name = "S", default_value_t = 0)]
+        seed: u64, // The same seed always generates the same transactions
+    },
+    /// Compare renderer output with the golden files (development only)
+    #[command(hide = true)]
+    Golden {
+        #[arg(long)]
+        bless: bool, // Rewrite the golden files from the current output
+    },
+    /// Decode a getblocktemplate result and report fees, weight and sigops
+    Template {
+        #[arg(value_name = "FILE")]
+        file: Option<PathBuf>, // getblocktemplate JSON; stdin if omitted
+    },
+    /// Decode an 80-byte block header: hash, target and difficulty
+    Header {
+        #[arg(value_name = "HEADER_HEX")]
+        header: Option<String>, // Header hex (or a full block); "-" or omitted reads stdin
+    },
+    /// Read blocks straight from Bitcoin Core's blocks/blk*.dat files
+    Blocks {
+        #[arg(value_name = "PATH")]
+        path: PathBuf, // Blocks directory or a single blk*.dat file
+
+        #[arg(long, value_enum, default_value = "mainnet")]
+        chain: Chain, // Selects the magic bytes framing each block
+
+        #[arg(long, value_name = "N")]
+        limit: Option<usize>, // Stop after N blocks
+
+        #[arg(long)]
+        txs: bool, // Also list every transaction (one JSON object per line with --output json)
+
+        #[arg(long, value_name = "FILE")]
+        checkpoint: Option<PathBuf>, // Resume from this state file if it exists, and keep it updated
+    },
+    /// Find outputs whose script matches a template in blk*.dat files, as NDJSON
+    ScanBlockfile {
+        #[arg(value_name = "PATH")]
+        path: PathBuf, // Blocks directory or a single blk*.dat file
+
+        #[arg(long, value_name = "TEMPLATE")]
+        match_script: ScriptTemplate, // e.g. "OP_DUP OP_HASH160 <hash160> OP_EQUALVERIFY OP_CHECKSIG"
+
+        #[arg(long, value_name = "N")]
+        from_height: Option<u32>, // Skip blocks below this BIP34 height (and version 1 blocks, which have none)
+
+        #[arg(long, value_enum, default_value = "mainnet")]
+        chain: Chain,
+
+        #[arg(long, value_name = "N")]
+        limit: Option<usize>, // Stop after N blocks
+
+        #[arg(long, value_name = "FILE")]
+        checkpoint: Option<PathBuf>, // Resume from this state file if it exists, and keep it updated
+    },
+    /// Decode raw binary transactions (or one block) from a file or stdin as they arrive
+    Stream {
+        #[arg(value_name = "FILE")]
+        file: Option<PathBuf>, // Concatenated raw transactions; stdin if omitted
+
+        #[arg(long)]
+        block: bool, // The stream is a serialized block: header, count, then transactions
+    },
+    /// Decode captured P2P wire messages and the transactions or blocks they carry
+    P2p {
+        #[arg(value_name = "HEX")]
+        hex: Option<String>, // One or more concatenated messages; "-" or omitted reads stdin
+
+        #[arg(short, long, value_name = "FILE")]
+        file: Option<PathBuf>, // Raw bytes (e.g. a TCP stream export) or hex text
+    },
+    /// Decode a BIP-152 cmpctblock payload and optionally rebuild the block from a mempool
+    CompactBlock {
+        #[arg(value_name = "HEX")]
+        hex: Option<String>, // Payload without the P2P header; "-" or omitted reads stdin
+
+        #[arg(short, long, value_name = "FILE")]
+        file: Option<PathBuf>,
+
+        #[arg(long, value_name = "FILE")]
+        mempool: Option<PathBuf>, // Candidate transactions, one hex per line
+
+        #[arg(long)]
+        txid_short_ids: bool, // Short ids hash txids (compact block version 1) instead of wtxids
+    },
+    /// Rebuild the coinbase and merkle root of a stratum mining job
+    Stratum {
+        #[arg(long, value_name = "HEX")]
+        coinb1: String,
+
+        #[arg(long, value_name = "HEX")]
+        coinb2: String,
+
+        #[arg(long, value_name = "HEX")]
+        extranonce1: String,
+
+        #[arg(long, value_name = "HEX")]
+        extranonce2: String,
+
+        #[arg(long, value_name = "HASH", value_delimiter = ',')]
+        merkle_branch: Vec<String>, // Branch hashes as sent in mining.notify
+    },
+    /// Byte-level diff of two raw transactions, labelled by field
+    Diff {
+        #[arg(value_name = "A")]
+        a: String, // Hex or a file containing it
+
+        #[arg(value_name = "B")]
+        b: String,
+    },
+    /// Check that the hex is the canonical serialization of the transaction
+    Canonical {
+        #[command(flatten)]
+        source: TxSource,
+    },
+    /// Decode an Elements/Liquid transaction, listing what stays confidential
+    #[cfg(feature = "elements")]
+    Elements {
+        #[command(flatten)]
+        source: TxSource,
+    },
+    /// Run an HTTP service exposing decode/analyze/script endpoints
+    #[cfg(feature = "serve")]
+    Serve {
+        #[arg(long, value_name = "ADDR", default_value = "127.0.0.1:8080")]
+        bind: String,
+    },
+    /// Hex utilities
+    Hex {
+        #[command(subcommand)]
+        action: HexAction,
+    },
+    /// Inspect or empty the `pipe --cache` summary cache
+    #[cfg(feature = "cache")]
+    Cache {
+        #[command(subcommand)]
+        action: CacheAction,
+    },
+}
+
+#[derive(Subcommand)]
+enum HexAction {
+    /// Clean up pasted hex or hexdump output and reflow it
+    Fmt {
+        #[arg(value_name = "HEX")]
+        hex: Option<String>, // "-" or omitted reads stdin
+
+        #[arg(short, long, value_name = "FILE")]
+        file: Option<PathBuf>,
+
+        #[arg(short, long, value_name = "BYTES", default_value_t = 32)]
+        width: usize, // Bytes per line; 0 for a single line
+
+        #[arg(long)]
+        spaced: bool, // Separate bytes with spaces
+    },
+}
+
+#[derive(Subcommand)]
+enum AddressAction {
+    /// Funding and spending timeline of an address, newest first
+    History {
+        #[arg(value_name = "ADDRESS")]
+        address: String,
+
+        #[arg(long, value_name = "N", default_value_t = 25)]
+        limit: usize, // Newest N transactions
+    },
+}
+
+#[cfg(feature = "cache")]
+#[derive(Subcommand)]
+enum CacheAction {
+    /// Show where the cache is, its entries and its size on disk
+    Stats,
+    /// Remove every cached summary
+    Clear,
+}
+
+#[derive(Subcommand)]
+enum BundleAction {
+    /// Package a transaction and its prevout data into a bundle file
+    Save {
+        #[command(flatten)]
+        source: TxSource,
+
+        #[arg(long, value_name = "FILE")]
+        out: PathBuf,
+
+        #[arg(long = "label", value_name = "TEXT")]
+        labels: Vec<String>,
+
+        #[arg(long = "note", value_name = "input:N=TEXT", value_parser = parse_note)]
+        notes: Vec<BundleNote>, // Note on an input or output, e.g. output:1=change
+    },
+    /// Render a previously saved bundle without network access
+    Load {
+        #[arg(value_name = "BUNDLE")]
+        path: PathBuf,
+    },
+    /// Add or replace notes on inputs and outputs of a saved bundle
+    Annotate {
+        #[arg(value_name = "BUNDLE")]
+        path: PathBuf,
+
+        #[arg(long = "note", value_name = "input:N=TEXT", value_parser = parse_note, required = true)]
+        notes: Vec<BundleNote>,
+    },
 }
 
 // Output formats
-#[derive(Clone, ValueEnum)]
+#[derive(Clone, Copy, ValueEnum)]
 enum OutputFormat {
     Pretty,
     Json,
@@ -39,297 +572,1072 @@ enum OutputFormat {
     Ascii,
 }
 
+impl From<OutputFormat> for btc_tx_render::Format {
+    fn from(format: OutputFormat) -> Self {
+        match format {
+            OutputFormat::Pretty => btc_tx_render::Format::Pretty,
+            OutputFormat::Json => btc_tx_render::Format::Json,
+            OutputFormat::Summary => btc_tx_render::Format::Summary,
+            OutputFormat::Ascii => btc_tx_render::Format::Ascii,
+        }
+    }
+}
+
+// What `--redact` masks
+#[derive(Clone, Copy, ValueEnum)]
+enum RedactMode {
+    Addresses,
+    Values,
+    All,
+}
+
+impl From<RedactMode> for Redaction {
+    fn from(mode: RedactMode) -> Self {
+        match mode {
+            RedactMode::Addresses => Redaction::Addresses,
+            RedactMode::Values => Redaction::Values,
+            RedactMode::All => Redaction::All,
+        }
+    }
+}
+
+// Networks whose block files can be read
+#[derive(Clone, Copy, ValueEnum)]
+enum Chain {
+    Mainnet,
+    Testnet,
+    Testnet4,
+    Signet,
+    Regtest,
+}
+
+impl From<Chain> for Network {
+    fn from(chain: Chain) -> Self {
+        match chain {
+            Chain::Mainnet => Network::Mainnet,
+            Chain::Testnet => Network::Testnet,
+            Chain::Testnet4 => Network::Testnet4,
+            Chain::Signet => Network::Signet,
+            Chain::Regtest => Network::Regtest,
+        }
+    }
+}
+
+// Color themes
+#[derive(Clone, Copy, ValueEnum)]
+enum ThemeName {
+    Default,
+    HighContrast,
+    ColorblindSafe,
+    Monochrome,
+}
+
+impl From<ThemeName> for btc_tx_render::Theme {
+    fn from(theme: ThemeName) -> Self {
+        match theme {
+            ThemeName::Default => btc_tx_render::Theme::Default,
+            ThemeName::HighContrast => btc_tx_render::Theme::HighContrast,
+            ThemeName::ColorblindSafe => btc_tx_render::Theme::ColorblindSafe,
+            ThemeName::Monochrome => btc_tx_render::Theme::Monochrome,
+        }
+    }
+}
 
 fn main() {
-    let cli = Cli::parse();
+    let mut cli = Cli::parse();
 
-    let tx_hex = match get_tx_hex(&cli) {
-        Ok(hex) => hex,
-        Err(e) => {
-            eprintln!("{}: {}", "Error".red().bold(), e);
-            std::process::exit(1);
+    let config = config::Config::load().unwrap_or_else(|e| exit_with_error(&e));
+    cli.min_confidence = cli.min_confidence.or(config.min_confidence);
+    let theme = cli.theme.map(Into::into).or(config.theme).unwrap_or_default();
+    btc_tx_render::set_theme(theme);
+    progress::set_quiet(cli.quiet);
+
+    if let Some(level) = cli.log_level {
+        tracing_subscriber::fmt()
+            .with_max_level(level)
+            .with_writer(io::stderr)
+            .init();
+    }
+
+    match &cli.command {
+        Some(Command::Bundle { action }) => run_bundle(&cli, action),
+        Some(Command::Address { action: AddressAction::History { address, limit } }) => {
+            let history = cli.backend.connect()
+                .address_history(address, *limit)
+                .unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            match cli.output {
+                OutputFormat::Json => print_json(&history, cli.json_style()),
+                _ => exit_on_write_error(btc_tx_render::write_address_history(&mut io::stdout().lock(), address, &history)),
+            }
         }
-    };
+        Some(Command::Psbt { psbt, file, extract }) => run_psbt(&cli, psbt.as_deref(), file.as_deref(), *extract),
+        Some(Command::Check { source, policy }) => run_check(&cli, source, policy),
+        Some(Command::Timelocks { source, height, time, fetch_tip }) => {
+            let (_, tx) = load_transaction(source, &cli.backend);
+            let mut tip = ChainTip {
+                height: *height,
+                time: time.unwrap_or_else(|| chrono::Utc::now().timestamp() as u64),
+            };
+            if *fetch_tip {
+                tip = cli.backend.connect().tip().unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            }
+            let schedule = tx.unlock_schedule(&tip);
+            match cli.output {
+                OutputFormat::Json => print_json(&schedule, cli.json_style()),
+                _ => exit_on_write_error(btc_tx_render::write_unlock_schedule(&mut io::stdout().lock(), &tx, &tip, &schedule)),
+            }
+        }
+        Some(Command::CoinAge { source, height, prevout_heights, fetch_heights }) => {
+            let (_, tx) = load_transaction(source, &cli.backend);
+            let backend = fetch_heights.then(|| cli.backend.connect());
+            let heights: Vec<Option<u32>> = match (prevout_heights, &backend) {
+                (Some(heights), _) if heights.len() != tx.inputs.len() => exit_with_error(&format!(
+                    "Expected {} prevout heights, got {}",
+                    tx.inputs.len(),
+                    heights.len()
+                )),
+                (Some(heights), _) => heights.iter().map(|&h| Some(h)).collect(),
+                (None, Some(backend)) => backend.fetch_prevout_heights(&tx).unwrap_or_else(|e| exit_with_error(&e.to_string())),
+                (None, None) => exit_with_error("Coin age needs --prevout-heights or --fetch-heights"),
+            };
+            // a fetched transaction is aged at its own block, or the next one while unconfirmed
+            let spend_height = match (height, &backend) {
+                (Some(height), _) => *height,
+                (None, Some(backend)) => match backend.confirmation_height(&tx.txid).unwrap_or_else(|e| exit_with_error(&e.to_string())) {
+                    Some(height) => height,
+                    None => backend.tip().unwrap_or_else(|e| exit_with_error(&e.to_string())).height
+                        .map(|tip| tip + 1)
+                        .unwrap_or_else(|| exit_with_error("The backend did not report a tip height")),
+                },
+                (None, None) => exit_with_error("Coin age needs --height (the height the transaction confirmed at)"),
+            };
+            let age = tx.coin_age(spend_height, &heights);
+            match cli.output {
+                OutputFormat::Json => print_json(&age, cli.json_style()),
+                _ => exit_on_write_error(btc_tx_render::write_coin_age(&mut io::stdout().lock(), &tx, &age)),
+            }
+        }
+        Some(Command::ExtractInscriptions { source, out }) => {
+            let (_, tx) = load_transaction(source, &cli.backend);
+            run_extract_inscriptions(&cli, &tx, out);
+        }
+        Some(Command::Report { source, bundle, out, labels }) => {
+            let (tx, mut all_labels) = match bundle {
+                Some(path) => {
+                    let bundle = read_bundle(path);
+                    let tx = bundle.transaction().unwrap_or_else(|e| exit_with_error(&e.to_string()));
+                    (tx, bundle.labels)
+                }
+                None => (load_transaction(source, &cli.backend).1, Vec::new()),
+            };
+            all_labels.extend(labels.iter().cloned());
+            let tx = shareable(&cli, &tx).unwrap_or(tx);
+
+            let html = btc_tx_render::render_html(&tx, &analyze(&cli, &tx), &all_labels, &chrono::Utc::now().to_rfc3339());
+            if let Err(e) = std::fs::write(out, html) {
+                exit_with_error(&format!("Failed to write '{}': {}", out.display(), e));
+            }
+            eprintln!("Wrote report for {} to {}", tx.txid, out.display());
+        }
+        Some(Command::ChannelClose { source, scripts, opener_basepoint, accepter_basepoint }) => {
+            let (_, tx) = load_transaction(source, &cli.backend);
+            let scripts: Vec<Vec<u8>> = scripts.iter()
+                .map(|script| hex::decode(script).unwrap_or_else(|e| exit_with_error(&format!("Invalid script '{}': {}", script, e))))
+                .collect();
+            let mut close = tx.channel_close_with_scripts(&scripts)
+                .unwrap_or_else(|| exit_with_error("Transaction does not spend a 2-of-2 channel funding output"));
+            if let (Some(opener), Some(accepter)) = (opener_basepoint, accepter_basepoint) {
+                let decode = |key: &String| match hex::decode(key) {
+                    Ok(key) if key.len() == 33 => key,
+                    _ => exit_with_error(&format!("Invalid basepoint '{}': expected a 33-byte public key in hex", key)),
+                };
+                if close.decode_commitment_number(&decode(opener), &decode(accepter)).is_none() {
+                    exit_with_error("Only a commitment transaction (force close) carries a commitment number");
+                }
+            }
+            match cli.output {
+                OutputFormat::Json => print_json(&close, cli.json_style()),
+                _ => exit_on_write_error(btc_tx_render::write_channel_close(&mut io::stdout().lock(), &close)),
+            }
+        }
+        Some(Command::Timestamp { source, ots }) => {
+            let (_, tx) = load_transaction(source, &cli.backend);
+            let bytes = std::fs::read(ots)
+                .unwrap_or_else(|e| exit_with_error(&format!("Failed to read '{}': {}", ots.display(), e)));
+            let proof = OtsProof::from_bytes(&bytes).unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            let Some(anchor) = proof.anchor_in(&tx) else {
+                eprintln!("{} the proof does not commit to any OP_RETURN output of {}", paint("Not anchored:", Role::Bad).bold(), tx.txid);
+                std::process::exit(2);
+            };
+            match cli.output {
+                OutputFormat::Json => print_json(&anchor, cli.json_style()),
+                _ => exit_on_write_error(btc_tx_render::write_timestamp_anchor(&mut io::stdout().lock(), &anchor)),
+            }
+        }
+        Some(Command::Flows { source }) => {
+            let (_, tx) = load_transaction(source, &cli.backend);
+            let flows = tx.plausible_flows().unwrap_or_else(|| exit_with_error(&format!(
+                "Flows need every input value (--input-values or --fetch-prevouts), at most {} inputs and {} outputs",
+                MAX_ENTROPY_INPUTS, MAX_ENTROPY_OUTPUTS
+            )));
+            match cli.output {
+                OutputFormat::Json => print_json(&flows, cli.json_style()),
+                _ => exit_on_write_error(btc_tx_render::write_plausible_flows(&mut io::stdout().lock(), &tx, &flows)),
+            }
+        }
+        Some(Command::Keys { source }) => {
+            let (_, tx) = load_transaction(source, &cli.backend);
+            let keys = tx.key_addresses();
+            match cli.output {
+                OutputFormat::Json => print_json(&keys, cli.json_style()),
+                _ => exit_on_write_error(btc_tx_render::write_key_addresses(&mut io::stdout().lock(), &keys)),
+            }
+        }
+        Some(Command::Pubkeys { source }) => {
+            let (_, tx) = load_transaction(source, &cli.backend);
+            let keys = tx.pubkeys();
+            match cli.output {
+                OutputFormat::Json => print_json(&keys, cli.json_style()),
+                _ => exit_on_write_error(btc_tx_render::write_pubkeys(&mut io::stdout().lock(), &keys)),
+            }
+        }
+        Some(Command::Wizard { source }) => {
+            // the prompts are read from stdin, which a piped transaction would exhaust
+            if source.reads_stdin() {
+                exit_with_error("The wizard reads its prompts from stdin; pass the transaction as an argument or with --file");
+            }
+            let (_, tx) = load_transaction(source, &cli.backend);
+            let stdin = io::stdin();
+            exit_on_write_error(wizard::run_with(&tx, stdin.lock(), &mut io::stdout().lock()).map(|_| ()));
+        }
+        Some(Command::Stats { file }) => run_stats(&cli, file.as_deref()),
+        Some(Command::Pipe {
+            stats_interval, max_line_bytes, parse_workers, analyze_workers, render_workers, queue_depth,
+            #[cfg(feature = "cache")]
+            cache,
+        }) => {
+            let options = pipe::PipeOptions {
+                analyze: cli.analyze,
+                exact_btc: cli.exact_btc,
+                pseudonymizer: pseudonymizer(&cli),
+                max_line_bytes: *max_line_bytes,
+                stats_interval: *stats_interval,
+                workers: pipe::StageWorkers { parse: *parse_workers, analyze: *analyze_workers, render: *render_workers },
+                queue_depth: *queue_depth,
+                #[cfg(feature = "cache")]
+                cache: cache.then(|| {
+                    // pseudonyms depend on the key, which the cache must not hold
+                    if cli.pseudonymize.is_some() {
+                        exit_with_error("--cache can't be combined with --pseudonymize");
+                    }
+                    open_summary_cache(&cli)
+                }),
+            };
+            if let Err(e) = pipe::run(&options) {
+                exit_with_error(&e);
+            }
+        }
+        Some(Command::Stress { count, seed }) => run_stress(&cli, *count, *seed),
+        Some(Command::Golden { bless }) => {
+            colored::control::set_override(false);
+            let changed = golden::check(*bless).unwrap_or_else(|e| exit_with_error(&e));
+            for file in &changed {
+                println!("{} {}", if *bless { "wrote" } else { "differs" }, file);
+            }
+            if !changed.is_empty() && !*bless {
+                std::process::exit(2);
+            }
+        }
+        Some(Command::Template { file }) => run_template(&cli, file.as_deref()),
+        Some(Command::Diff { a, b }) => {
+            let a = read_raw_tx(a);
+            let b = read_raw_tx(b);
+            let diff = byte_diff(&a, &b).unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            match cli.output {
+                OutputFormat::Json => print_json(&diff, cli.json_style()),
+                _ => exit_on_write_error(btc_tx_render::write_byte_diff(&mut io::stdout().lock(), &diff)),
+            }
+        }
+        Some(Command::Canonical { source }) => {
+            let hex = get_tx_hex(source)
+                .and_then(|text| normalize_hex(&text).map_err(|e| e.to_string()))
+                .unwrap_or_else(|e| exit_with_error(&e));
+            let bytes = hex::decode(hex).unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            let report = check_canonical(&bytes).unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            match cli.output {
+                OutputFormat::Json => print_json(&report, cli.json_style()),
+                _ => exit_on_write_error(btc_tx_render::write_canonical_report(&mut io::stdout().lock(), &report)),
+            }
+            if !report.canonical {
+                std::process::exit(2);
+            }
+        }
+        #[cfg(feature = "elements")]
+        Some(Command::Elements { source }) => {
+            let hex = get_tx_hex(source)
+                .and_then(|text| normalize_hex(&text).map_err(|e| e.to_string()))
+                .unwrap_or_else(|e| exit_with_error(&e));
+            let tx = btc_tx_parser::ElementsTransaction::from_hex(&hex).unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            match cli.output {
+                OutputFormat::Json => print_json(&tx, cli.json_style()),
+                _ => exit_on_write_error(btc_tx_render::write_elements(&mut io::stdout().lock(), &tx)),
+            }
+        }
+        #[cfg(feature = "cache")]
+        Some(Command::Cache { action }) => {
+            let cache = open_summary_cache(&cli);
+            match action {
+                CacheAction::Stats => {
+                    let stats = cache.stats().unwrap_or_else(|e| exit_with_error(&e));
+                    match cli.output {
+                        OutputFormat::Json => print_json(&stats, cli.json_style()),
+                        _ => exit_on_write_error(btc_tx_render::write_cache_stats(&mut io::stdout().lock(), &stats.path, stats.entries, stats.bytes_on_disk)),
+                    }
+                }
+                CacheAction::Clear => {
+                    let removed = cache.clear().unwrap_or_else(|e| exit_with_error(&e));
+                    eprintln!("Removed {} cached summaries from {}", removed, cache.path().display());
+                }
+            }
+        }
+        #[cfg(feature = "serve")]
+        Some(Command::Serve { bind }) => {
+            if let Err(e) = serve::run(bind) {
+                exit_with_error(&e);
+            }
+        }
+        Some(Command::Hex { action: HexAction::Fmt { hex, file, width, spaced } }) => {
+            let text = match (file, hex) {
+                (Some(path), _) => read_text(Some(path)),
+                (None, Some(hex)) if hex != "-" => hex.clone(),
+                _ => read_text(None),
+            };
+            let hex = normalize_hex(&text).unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            println!("{}", format_hex(&hex, *width, *spaced));
+        }
+        Some(Command::Header { header }) => {
+            let text = match header.as_deref() {
+                Some(text) if text != "-" => text.to_string(),
+                _ => read_text(None),
+            };
+            let hex = normalize_hex(&text).unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            // a full block starts with its header
+            let header = BlockHeader::from_hex(hex.get(..160).unwrap_or(&hex))
+                .unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            match cli.output {
+                OutputFormat::Json => print_json(&header, cli.json_style()),
+                _ => exit_on_write_error(btc_tx_render::write_block_header(&mut io::stdout().lock(), &header)),
+            }
+        }
+        Some(Command::Blocks { path, chain, limit, txs, checkpoint }) => run_blocks(&cli, path, *chain, *limit, *txs, checkpoint.as_deref()),
+        Some(Command::ScanBlockfile { path, match_script, from_height, chain, limit, checkpoint }) => {
+            run_scan_blockfile(&cli, path, match_script, *from_height, *chain, *limit, checkpoint.as_deref())
+        }
+        Some(Command::Stream { file, block }) => run_stream(&cli, file.as_deref(), *block),
+        Some(Command::P2p { hex, file }) => run_p2p(&cli, hex.as_deref(), file.as_deref()),
+        Some(Command::CompactBlock { hex, file, mempool, txid_short_ids }) => {
+            let text = match (file, hex) {
+                (Some(path), _) => read_text(Some(path)),
+                (None, Some(hex)) if hex != "-" => hex.clone(),
+                _ => read_text(None),
+            };
+            let hex = normalize_hex(&text).unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            let block = CompactBlock::from_hex(&hex).unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            let reconstruction = mempool.as_deref().map(|path| {
+                let pool: Vec<Transaction> = read_text(Some(path)).lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                    .filter_map(|line| parse_batch_line(line).ok())
+                    .collect();
+                block.reconstruct(&pool, *txid_short_ids).unwrap_or_else(|e| exit_with_error(&e.to_string()))
+            });
+            match cli.output {
+                OutputFormat::Json => print_json(&serde_json::json!({ "compact_block": &block, "reconstruction": &reconstruction }), cli.json_style()),
+                _ => exit_on_write_error(btc_tx_render::write_compact_block(&mut io::stdout().lock(), &block, reconstruction.as_ref())),
+            }
+        }
+        Some(Command::Stratum { coinb1, coinb2, extranonce1, extranonce2, merkle_branch }) => {
+            let job = StratumJob {
+                coinb1: coinb1.clone(),
+                coinb2: coinb2.clone(),
+                extranonce1: extranonce1.clone(),
+                extranonce2: extranonce2.clone(),
+                merkle_branch: merkle_branch.clone(),
+            };
+            let result = job.reconstruct().unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            match cli.output {
+                OutputFormat::Json => print_json(&result, cli.json_style()),
+                _ => {
+                    exit_on_write_error(btc_tx_render::write_stratum_coinbase(&mut io::stdout().lock(), &result, job.merkle_branch.len()));
+                    render(&cli, &result.coinbase);
+                }
+            }
+        }
+        None => {
+            let (_, tx) = load_transaction(&cli.source, &cli.backend);
+            render(&cli, &tx);
+        }
+    }
+}
+
+fn exit_with_error(message: &str) -> ! {
+    eprintln!("{}: {}", paint("Error", Role::Bad).bold(), message);
+    std::process::exit(1);
+}
+
+// Read, parse and value a transaction; returns the hex alongside it
+fn load_transaction(source: &TxSource, backend: &BackendArgs) -> (String, Transaction) {
+    let tx_hex = get_tx_hex(source)
+        .and_then(|text| normalize_hex(&text).map_err(|e| e.to_string()))
+        .unwrap_or_else(|e| exit_with_error(&e));
 
-    let mut tx = match Transaction::from_hex(&tx_hex) {
+    let parsed = if source.strict {
+        Transaction::from_hex_strict(&tx_hex)
+    } else {
+        Transaction::from_hex(&tx_hex)
+    };
+    let mut tx = match parsed {
         Ok(tx) => tx,
         Err(e) => {
-            eprintln!("{}: Failed to parse transaction", "Error".red().bold());
+            eprintln!("{}: Failed to parse transaction", paint("Error", Role::Bad).bold());
             eprintln!("  {}", e);
             std::process::exit(1);
         }
     };
 
-    if let Some(values) = &cli.input_values {
+    if let Some(values) = &source.input_values {
         if values.len() != tx.inputs.len() {
             eprintln!(
                 "{}: Provided {} input values but transaction has {} inputs",
-                "Warning".yellow().bold(),
+                paint("Warning", Role::Warn).bold(),
                 values.len(),
                 tx.inputs.len()
             );
         }
         for (i, &value) in values.iter().enumerate() {
             if i < tx.inputs.len() {
-                tx.inputs[i].value = Some(value);
+                tx.inputs[i].value = Some(Amount::from_sat(value));
             }
         }
+    }
+
+    if source.fetch_prevouts {
+        let bar = progress::bar(tx.inputs.len() as u64, "Fetching prevouts");
+        let fetched = backend.connect().fetch_prevout_values_with_progress(&mut tx, &|done, _| bar.set_position(done as u64));
+        bar.finish_and_clear();
+        match fetched {
+            Ok(()) => {}
+            // keep the values fetched before the interruption
+            Err(e @ (BackendError::Cancelled | BackendError::Timeout(_))) => {
+                let valued = tx.inputs.iter().filter(|i| !i.is_coinbase && i.value.is_some()).count();
+                let total = tx.inputs.iter().filter(|i| !i.is_coinbase).count();
+                eprintln!("{}: {}; showing {} of {} input values", paint("Warning", Role::Warn).bold(), e, valued, total);
+            }
+            Err(e) => exit_with_error(&e.to_string()),
+        }
+    }
+
+    if source.check_spent {
+        match backend.connect().fetch_spends(&mut tx) {
+            Ok(()) => {}
+            Err(e @ (BackendError::Cancelled | BackendError::Timeout(_))) => {
+                eprintln!("{}: {}; output spend status not shown", paint("Warning", Role::Warn).bold(), e);
+            }
+            Err(e) => exit_with_error(&e.to_string()),
+        }
+    }
+
+    if source.input_values.is_some() || source.fetch_prevouts {
         if let Some(fee) = tx.calculate_fee() {
-            tx.fee_satoshis = Some(fee);
-            tx.fee_btc = Some(Transaction::satoshis_to_btc(fee));
+            tx.set_fee(Some(fee));
         }
     }
 
-    match cli.output {
-        OutputFormat::Pretty => print_pretty(&tx),
-        OutputFormat::Json => print_json(&tx, cli.compact),
-        OutputFormat::Summary => print_summary(&tx),
-        OutputFormat::Ascii => print_ascii(&tx),
+    (tx_hex, tx)
+}
+
+fn render(cli: &Cli, tx: &Transaction) {
+    let shared = shareable(cli, tx);
+    let tx = shared.as_ref().unwrap_or(tx);
+    if let Some(dir) = &cli.dump_data {
+        dump_payloads(dir, tx).unwrap_or_else(|e| exit_with_error(&e));
     }
+    let findings = cli.analyze.then(|| analyze(cli, tx));
+    let result = btc_tx_render::write_transaction_with_findings(&mut io::stdout().lock(), tx, cli.output.into(), findings.as_deref(), cli.json_style());
+    exit_on_write_error(result);
 }
 
-//transaction hex from CLI, file, or stdin
-fn get_tx_hex(cli: &Cli) -> Result<String, String> {
-    if let Some(file_path) = &cli.file {
-        let content = std::fs::read_to_string(file_path)
-            .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
-        return Ok(content.trim().to_string());
+// The transaction as `--pseudonymize` and `--redact` would have it shown,
+// or None if neither is set
+fn shareable(cli: &Cli, tx: &Transaction) -> Option<Transaction> {
+    let pseudonymized = pseudonymizer(cli).map(|p| p.pseudonymize(tx));
+    match cli.redact {
+        Some(mode) => Some(pseudonymized.as_ref().unwrap_or(tx).redacted(mode.into())),
+        None => pseudonymized,
     }
+}
 
-    match &cli.tx_hex {
-        Some(hex) if hex == "-" => {
-            let mut buffer = String::new();
-            io::stdin()
-                .read_to_string(&mut buffer)
-                .map_err(|e| format!("Failed to read from stdin: {}", e))?;
-            Ok(buffer.trim().to_string())
+fn pseudonymizer(cli: &Cli) -> Option<Pseudonymizer> {
+    cli.pseudonymize.as_deref().map(|key| Pseudonymizer::new(key.as_bytes()))
+}
+
+// Built-in analyzers, keeping heuristic findings above `--min-confidence`
+fn analyze(cli: &Cli, tx: &Transaction) -> Vec<Finding> {
+    AnalyzerRegistry::with_defaults()
+        .with_min_confidence(cli.min_confidence.unwrap_or(0.0))
+        .run(tx)
+}
+
+fn parse_confidence(text: &str) -> Result<f64, String> {
+    match text.parse::<f64>() {
+        Ok(value) if (0.0..=1.0).contains(&value) => Ok(value),
+        _ => Err(format!("'{}' is not a confidence between 0 and 1", text)),
+    }
+}
+
+// Write every large payload to `<dir>/<txid>.<field>.bin`
+fn dump_payloads(dir: &Path, tx: &Transaction) -> Result<(), String> {
+    let payloads = tx.payloads(btc_tx_render::LARGE_DATA_BYTES);
+    if payloads.is_empty() {
+        return Ok(());
+    }
+    std::fs::create_dir_all(dir).map_err(|e| format!("Failed to create '{}': {}", dir.display(), e))?;
+    for payload in &payloads {
+        let field = payload.field.replace('[', "-").replace(']', "");
+        let path = dir.join(format!("{}.{}.bin", tx.txid, field));
+        std::fs::write(&path, &payload.data).map_err(|e| format!("Failed to write '{}': {}", path.display(), e))?;
+    }
+    eprintln!("Wrote {} payload(s) to {}", payloads.len(), dir.display());
+    Ok(())
+}
+
+// A closed pipe (e.g. `| head`) ends output quietly; anything else is an error
+fn exit_on_write_error(result: io::Result<()>) {
+    match result {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => std::process::exit(0),
+        Err(e) => exit_with_error(&format!("Failed to write output: {}", e)),
+    }
+}
+
+fn run_bundle(cli: &Cli, action: &BundleAction) {
+    match action {
+        BundleAction::Save { source, out, labels, notes } => {
+            let (tx_hex, tx) = load_transaction(source, &cli.backend);
+            let mut bundle = TxBundle::new(&tx_hex, &tx);
+            bundle.labels = labels.clone();
+            for note in notes {
+                bundle.set_note(note.target, note.index, &note.text);
+            }
+            // reject notes on inputs or outputs the transaction doesn't have
+            bundle.transaction().unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            if source.fetch_prevouts || source.check_spent {
+                bundle.fetched = Some(FetchStatus {
+                    source: cli.backend.esplora_url.clone(),
+                    fetched_at: chrono::Utc::now().to_rfc3339(),
+                });
+            }
+            if let Err(e) = std::fs::write(out, bundle.to_json()) {
+                exit_with_error(&format!("Failed to write '{}': {}", out.display(), e));
+            }
+            eprintln!("Saved bundle for {} to {}", tx.txid, out.display());
         }
-        Some(hex) => Ok(hex.trim().to_string()),
-        None => {
-            if atty::is(atty::Stream::Stdin) {
-                Err("No transaction provided. Use -h for help.".to_string())
-            } else {
-                let mut buffer = String::new();
-                io::stdin()
-                    .read_to_string(&mut buffer)
-                    .map_err(|e| format!("Failed to read from stdin: {}", e))?;
-                Ok(buffer.trim().to_string())
+        BundleAction::Load { path } => {
+            let tx = read_bundle(path)
+                .transaction()
+                .unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            render(cli, &tx);
+        }
+        BundleAction::Annotate { path, notes } => {
+            let mut bundle = read_bundle(path);
+            for note in notes {
+                bundle.set_note(note.target, note.index, &note.text);
+            }
+            bundle.transaction().unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            if let Err(e) = std::fs::write(path, bundle.to_json()) {
+                exit_with_error(&format!("Failed to write '{}': {}", path.display(), e));
             }
+            eprintln!("Saved {} note(s) to {}", bundle.notes.len(), path.display());
         }
     }
 }
 
-//output
-fn print_pretty(tx: &Transaction) {
-    println!();
-    println!("{}", "═══════════════════════════════════════════════════════════════".bright_blue());
-    println!("{}", "                    BITCOIN TRANSACTION".bright_blue().bold());
-    println!("{}", "═══════════════════════════════════════════════════════════════".bright_blue());
-    println!();
-
-    println!("{}", "Transaction Info".cyan().bold());
-    println!("  {} {}", "TXID:".white().bold(), tx.txid.yellow());
-    if tx.is_segwit {
-        println!("  {} {}", "WTXID:".white().bold(), tx.wtxid.yellow());
-    }
-    println!("  {} {}", "Version:".white().bold(), tx.version);
-    println!("  {} {}", "SegWit:".white().bold(), if tx.is_segwit { "Yes".green() } else { "No".white() });
-    println!("  {} {} bytes", "Size:".white().bold(), tx.raw_size);
-    println!("  {} {} vbytes", "Virtual Size:".white().bold(), tx.vsize());
-    println!("  {} {} WU", "Weight:".white().bold(), tx.weight);
-    println!("  {} {}", "Locktime:".white().bold(), format_locktime(tx.locktime));
-    println!();
-
-    println!("{} ({})", "Inputs".cyan().bold(), tx.inputs.len());
-    println!("{}", "─".repeat(60).bright_black());
-    for input in &tx.inputs {
-        println!("  {} #{}", "Input".white().bold(), input.index);
-        if input.is_coinbase {
-            println!("    {} {}", "Type:".white(), "Coinbase".magenta().bold());
-        } else {
-            println!("    {} {}:{}", "Spends:".white(), input.txid.yellow(), input.vout);
-        }
-        if let Some(value) = input.value {
-            println!("    {} {} sats ({:.8} BTC)", 
-                "Value:".white(), 
-                value.to_string().green(),
-                Transaction::satoshis_to_btc(value)
-            );
+// "input:0=text" or "output:1=text"
+fn parse_note(text: &str) -> Result<BundleNote, String> {
+    let (location, note) = text.split_once('=')
+        .ok_or_else(|| format!("'{}' is not a note, expected input:N=TEXT or output:N=TEXT", text))?;
+    let (target, index) = location.split_once(':')
+        .ok_or_else(|| format!("'{}' is not a note location, expected input:N or output:N", location))?;
+    Ok(BundleNote {
+        target: target.parse()?,
+        index: index.parse().map_err(|_| format!("'{}' is not an input or output index", index))?,
+        text: note.to_string(),
+    })
+}
+
+fn run_psbt(cli: &Cli, text: Option<&str>, file: Option<&Path>, extract: bool) {
+    let parsed = match (file, text) {
+        (Some(path), _) => {
+            let bytes = std::fs::read(path)
+                .unwrap_or_else(|e| exit_with_error(&format!("Failed to read '{}': {}", path.display(), e)));
+            if bytes.starts_with(b"psbt\xff") {
+                Psbt::from_bytes(&bytes)
+            } else {
+                Psbt::parse(&String::from_utf8_lossy(&bytes))
+            }
         }
-        println!("    {} 0x{:08x}", "Sequence:".white(), input.sequence);
-        if !input.script_sig.hex.is_empty() {
-            println!("    {} {} bytes", "ScriptSig:".white(), input.script_sig.size);
-            if input.script_sig.asm.len() < 100 {
-                println!("      {}", input.script_sig.asm.bright_black());
+        (None, Some(text)) if text != "-" => Psbt::parse(text),
+        _ => {
+            let mut buffer = String::new();
+            if let Err(e) = io::stdin().read_to_string(&mut buffer) {
+                exit_with_error(&format!("Failed to read from stdin: {}", e));
             }
+            Psbt::parse(&buffer)
         }
-        if let Some(witness) = &input.witness {
-            println!("    {} {} items", "Witness:".white(), witness.len());
-            for (i, item) in witness.iter().enumerate() {
-                if item.len() < 100 {
-                    println!("      [{}] {}", i, item.bright_black());
-                } else {
-                    println!("      [{}] {}...", i, &item[..64].bright_black());
-                }
+    };
+    let psbt = parsed.unwrap_or_else(|e| exit_with_error(&e.to_string()));
+
+    if extract {
+        let readiness = psbt.readiness().unwrap_or_else(|e| exit_with_error(&e.to_string()));
+        match cli.output {
+            OutputFormat::Json => {
+                let hex = readiness.transaction.to_hex();
+                print_json(&serde_json::json!({ "readiness": &readiness, "hex": hex }), cli.json_style())
             }
+            _ => exit_on_write_error(btc_tx_render::write_psbt_readiness(&mut io::stdout().lock(), &readiness)),
         }
-        println!();
+        return;
+    }
+
+    let progress = psbt.multisig_progress();
+
+    match cli.output {
+        OutputFormat::Json => print_json(&serde_json::json!({
+            "psbt": &psbt,
+            "multisig_progress": progress,
+        }), cli.json_style()),
+        _ => exit_on_write_error(btc_tx_render::write_psbt(&mut io::stdout().lock(), &psbt, progress.as_ref())),
+    }
+}
+
+fn run_stress(cli: &Cli, count: u64, seed: u64) {
+    let bar = progress::bar(count, "Round-tripping");
+    let report = StressReport::run(count, seed, |done| bar.set_position(done));
+    bar.finish_and_clear();
+
+    match cli.output {
+        OutputFormat::Json => print_json(&report, cli.json_style()),
+        _ => exit_on_write_error(btc_tx_render::write_stress_report(&mut io::stdout().lock(), &report)),
+    }
+
+    if !report.passed() {
+        std::process::exit(2);
     }
+}
 
-    println!("{} ({})", "Outputs".cyan().bold(), tx.outputs.len());
-    println!("{}", "─".repeat(60).bright_black());
-    for output in &tx.outputs {
-        println!("  {} #{}", "Output".white().bold(), output.index);
-        println!("    {} {} sats ({:.8} BTC)", 
-            "Value:".white(), 
-            output.value.to_string().green().bold(),
-            output.value_btc
-        );
-        println!("    {} {}", "Type:".white(), format!("{}", output.script_type).cyan());
-        if let Some(addr) = &output.address {
-            println!("    {} {}", "Address:".white(), addr.mainnet.yellow());
-            println!("    {} {}", "Testnet:".white(), addr.testnet.bright_black());
+fn run_check(cli: &Cli, source: &TxSource, policy_path: &Path) {
+    let text = std::fs::read_to_string(policy_path)
+        .unwrap_or_else(|e| exit_with_error(&format!("Failed to read '{}': {}", policy_path.display(), e)));
+    let profile: PolicyProfile = toml::from_str(&text)
+        .unwrap_or_else(|e| exit_with_error(&format!("Invalid policy profile: {}", e)));
+    let (_, tx) = load_transaction(source, &cli.backend);
+    let report = profile.check(&tx);
+
+    match cli.output {
+        OutputFormat::Json => print_json(&report, cli.json_style()),
+        _ => exit_on_write_error(btc_tx_render::write_policy_report(&mut io::stdout().lock(), &report)),
+    }
+
+    if !report.passed {
+        std::process::exit(2);
+    }
+}
+
+// Hex argument, or the contents of the file it names
+fn read_raw_tx(arg: &str) -> Vec<u8> {
+    let path = Path::new(arg);
+    let text = if path.is_file() { read_text(Some(path)) } else { arg.to_string() };
+    let hex = normalize_hex(&text).unwrap_or_else(|e| exit_with_error(&format!("{}: {}", arg, e)));
+    hex::decode(hex).unwrap_or_else(|e| exit_with_error(&e.to_string()))
+}
+
+// Whole file, or stdin when no path is given
+fn read_text(file: Option<&Path>) -> String {
+    match file {
+        Some(path) => std::fs::read_to_string(path)
+            .unwrap_or_else(|e| exit_with_error(&format!("Failed to read '{}': {}", path.display(), e))),
+        None => {
+            let mut buffer = String::new();
+            if let Err(e) = io::stdin().read_to_string(&mut buffer) {
+                exit_with_error(&format!("Failed to read from stdin: {}", e));
+            }
+            buffer
         }
-        println!("    {} {} bytes", "Script:".white(), output.script_pubkey.size);
-        if output.script_pubkey.asm.len() < 100 {
-            println!("      {}", output.script_pubkey.asm.bright_black());
+    }
+}
+
+// Files are named after the inscription id, with an extension from the content type
+fn run_extract_inscriptions(cli: &Cli, tx: &Transaction, dir: &Path) {
+    let inscriptions = tx.inscriptions();
+    if inscriptions.is_empty() {
+        exit_with_error(&format!("Transaction {} reveals no inscriptions", tx.txid));
+    }
+    std::fs::create_dir_all(dir)
+        .unwrap_or_else(|e| exit_with_error(&format!("Failed to create '{}': {}", dir.display(), e)));
+
+    let mut paths = Vec::new();
+    for inscription in &inscriptions {
+        let path = dir.join(format!("{}.{}", inscription.id, inscription.file_extension()));
+        if let Err(e) = std::fs::write(&path, &inscription.body) {
+            exit_with_error(&format!("Failed to write '{}': {}", path.display(), e));
         }
-        println!();
+        paths.push(path);
     }
 
-    println!("{}", "Summary".cyan().bold());
-    println!("{}", "─".repeat(60).bright_black());
-    println!("  {} {} sats ({:.8} BTC)", 
-        "Total Output:".white().bold(),
-        tx.total_output_satoshis.to_string().green(),
-        tx.total_output_btc
-    );
-    if let Some(fee) = tx.fee_satoshis {
-        println!("  {} {} sats ({:.8} BTC)", 
-            "Fee:".white().bold(),
-            fee.to_string().red(),
-            tx.fee_btc.unwrap_or(0.0)
-        );
-        let fee_rate = fee as f64 / tx.vsize() as f64;
-        println!("  {} {:.2} sat/vB", "Fee Rate:".white().bold(), fee_rate);
+    match cli.output {
+        OutputFormat::Json => {
+            let written: Vec<_> = inscriptions.iter().zip(&paths)
+                .map(|(inscription, path)| serde_json::json!({ "inscription": inscription, "path": path }))
+                .collect();
+            print_json(&written, cli.json_style());
+        }
+        _ => exit_on_write_error(btc_tx_render::write_extracted_inscriptions(&mut io::stdout().lock(), &inscriptions, &paths)),
     }
-    println!();
 }
 
-// JSON output
-fn print_json(tx: &Transaction, compact: bool) {
-    let json = if compact {
-        serde_json::to_string(tx)
-    } else {
-        serde_json::to_string_pretty(tx)
+#[cfg(feature = "cache")]
+fn open_summary_cache(cli: &Cli) -> cache::SummaryCache {
+    let dir = cache::SummaryCache::default_dir(cli.backend.cache_dir.as_deref())
+        .unwrap_or_else(|| exit_with_error("No cache directory: set --cache-dir, XDG_CACHE_HOME or HOME"));
+    cache::SummaryCache::open(&dir).unwrap_or_else(|e| exit_with_error(&e))
+}
+
+fn run_stats(cli: &Cli, file: Option<&Path>) {
+    // a mapped file is read in place, however large
+    #[cfg(feature = "mmap")]
+    let mapped = file.map(|path| MappedFile::open(path)
+        .unwrap_or_else(|e| exit_with_error(&format!("Failed to read '{}': {}", path.display(), e))));
+    #[cfg(feature = "mmap")]
+    let text = match &mapped {
+        Some(mapped) => std::borrow::Cow::Borrowed(mapped.text().unwrap_or_else(|e| exit_with_error(&e.to_string()))),
+        None => std::borrow::Cow::Owned(read_text(None)),
     };
+    #[cfg(not(feature = "mmap"))]
+    let text = read_text(file);
 
-    match json {
-        Ok(s) => println!("{}", s),
-        Err(e) => {
-            eprintln!("Error serializing to JSON: {}", e);
-            std::process::exit(1);
+    let lines: Vec<(usize, &str)> = text.lines()
+        .map(str::trim)
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .collect();
+
+    let bar = progress::bar(lines.len() as u64, "Parsing");
+    let mut stats = BatchStats::new();
+    let pseudonymizer = pseudonymizer(cli);
+    for (number, line) in lines {
+        match parse_batch_line(line) {
+            Ok(tx) => match &pseudonymizer {
+                Some(pseudonymizer) => stats.add(&pseudonymizer.pseudonymize(&tx)),
+                None => stats.add(&tx),
+            },
+            Err(e) => bar.suspend(|| eprintln!("{}: line {}: {}", paint("Warning", Role::Warn).bold(), number + 1, e)),
         }
+        bar.inc(1);
+    }
+    bar.finish_and_clear();
+
+    let report = stats.report();
+    match cli.output {
+        OutputFormat::Json => print_json(&report, cli.json_style()),
+        _ => exit_on_write_error(btc_tx_render::write_batch_report(&mut io::stdout().lock(), &report)),
     }
 }
 
-// Human-readable summary
-fn print_summary(tx: &Transaction) {
-    println!("Transaction: {}", tx.txid);
-    println!("  Version: {}, SegWit: {}", tx.version, tx.is_segwit);
-    println!("  {} input(s), {} output(s)", tx.inputs.len(), tx.outputs.len());
-    println!("  Size: {} bytes, vSize: {} vbytes", tx.raw_size, tx.vsize());
-    println!("  Total output: {:.8} BTC ({} sats)", tx.total_output_btc, tx.total_output_satoshis);
-    
-    if let Some(fee) = tx.fee_satoshis {
-        println!("  Fee: {:.8} BTC ({} sats)", tx.fee_btc.unwrap_or(0.0), fee);
-    }
-
-    println!("\nOutputs:");
-    for output in &tx.outputs {
-        let addr = output.address.as_ref()
-            .map(|a| a.mainnet.clone())
-            .unwrap_or_else(|| "[non-standard]".to_string());
-        println!("  #{}: {:.8} BTC -> {} ({})", 
-            output.index, 
-            output.value_btc, 
-            addr,
-            output.script_type
-        );
-    }
-}
-
-// ASCII art visualization
-fn print_ascii(tx: &Transaction) {
-    println!();
-    println!("┌─────────────────────────────────────────────────────────────────────┐");
-    println!("│ TX: {}...{} │", &tx.txid[..16], &tx.txid[tx.txid.len()-8..]);
-    println!("├─────────────────────────────────────────────────────────────────────┤");
-    
-    let input_count = tx.inputs.len();
-    let output_count = tx.outputs.len();
-    let max_rows = input_count.max(output_count);
-
-    for i in 0..max_rows {
-        let input_str = if i < input_count {
-            let input = &tx.inputs[i];
-            if input.is_coinbase {
-                format!("  [COINBASE]")
-            } else {
-                let value_str = input.value
-                    .map(|v| format!("{:.4} BTC", Transaction::satoshis_to_btc(v)))
-                    .unwrap_or_else(|| "? BTC".to_string());
-                format!("  {}:{} ({})", &input.txid[..8], input.vout, value_str)
+// Blocks between checkpoint writes; a resumed scan repeats at most this many
+const CHECKPOINT_INTERVAL: usize = 100;
+
+// Open the block files, continuing from `checkpoint` if the file exists
+fn open_block_files(cli: &Cli, path: &Path, chain: Chain, checkpoint: Option<&Path>) -> BlockFiles {
+    #[cfg(feature = "mmap")]
+    let blocks = BlockFiles::open_mapped(path, Network::from(chain).magic());
+    #[cfg(not(feature = "mmap"))]
+    let blocks = BlockFiles::open(path, Network::from(chain).magic());
+    let blocks = blocks.unwrap_or_else(|e| exit_with_error(&e.to_string()));
+    let Some(state) = checkpoint.filter(|state| state.exists()) else {
+        return blocks;
+    };
+    let saved: BlockFileCheckpoint = std::fs::read_to_string(state)
+        .map_err(|e| e.to_string())
+        .and_then(|text| serde_json::from_str(&text).map_err(|e| e.to_string()))
+        .unwrap_or_else(|e| exit_with_error(&format!("Failed to read checkpoint '{}': {}", state.display(), e)));
+    if !cli.quiet {
+        let height = saved.last_height.map(|h| format!(", last height {}", h)).unwrap_or_default();
+        eprintln!("Resuming at {} offset {} ({} block(s) read{})", saved.file, saved.offset, saved.blocks, height);
+    }
+    blocks.resume(&saved).unwrap_or_else(|e| exit_with_error(&e.to_string()))
+}
+
+// Write through a temporary file, so an interrupted write leaves the old state
+fn save_checkpoint(state: &Path, blocks: &BlockFiles) {
+    let Some(checkpoint) = blocks.checkpoint() else {
+        return;
+    };
+    let temp = state.with_extension("tmp");
+    let result = serde_json::to_string_pretty(&checkpoint).map_err(io::Error::other)
+        .and_then(|json| std::fs::write(&temp, json))
+        .and_then(|()| std::fs::rename(&temp, state));
+    if let Err(e) = result {
+        exit_with_error(&format!("Failed to write checkpoint '{}': {}", state.display(), e));
+    }
+}
+
+fn run_blocks(cli: &Cli, path: &Path, chain: Chain, limit: Option<usize>, txs: bool, checkpoint: Option<&Path>) {
+    let mut blocks = open_block_files(cli, path, chain, checkpoint);
+    let (mut read, mut failed) = (0, 0);
+    let mut out = io::stdout().lock();
+
+    while limit.is_none_or(|limit| read < limit) {
+        let block = match blocks.next() {
+            Some(Ok(block)) => block,
+            Some(Err(e)) => {
+                // the rest of that file is skipped; later files are still read
+                let file = blocks.current_file().map(|f| f.display().to_string()).unwrap_or_default();
+                eprintln!("{}: {}: {}", paint("Warning", Role::Warn).bold(), file, e);
+                failed += 1;
+                continue;
             }
-        } else {
-            String::new()
+            None => break,
         };
+        read += 1;
 
-        let output_str = if i < output_count {
-            let output = &tx.outputs[i];
-            let addr = output.address.as_ref()
-                .map(|a| if a.mainnet.len() > 20 { 
-                    format!("{}...", &a.mainnet[..20]) 
-                } else { 
-                    a.mainnet.clone() 
-                })
-                .unwrap_or_else(|| "[script]".to_string());
-            format!("{:.4} BTC -> {}", output.value_btc, addr)
-        } else {
-            String::new()
+        let result = match (cli.output, txs) {
+            (OutputFormat::Json, true) => block.transactions.iter()
+                .try_for_each(|tx| btc_tx_render::write_json(&mut out, tx, cli.json_line_style())),
+            (OutputFormat::Json, false) => btc_tx_render::write_json(&mut out, &block_summary(&block), cli.json_line_style()),
+            _ => btc_tx_render::write_block_summary(&mut out, &block, txs),
         };
+        exit_on_write_error(result);
+        if let Some(state) = checkpoint.filter(|_| read % CHECKPOINT_INTERVAL == 0) {
+            save_checkpoint(state, &blocks);
+        }
+    }
+    if let Some(state) = checkpoint {
+        save_checkpoint(state, &blocks);
+    }
 
-        let arrow = if i == max_rows / 2 { "═══►" } else { "    " };
-        
-        println!("│ {:30} {} {:34} │", 
-            if input_str.len() > 30 { format!("{}...", &input_str[..27]) } else { input_str },
-            arrow,
-            if output_str.len() > 34 { format!("{}...", &output_str[..31]) } else { output_str }
-        );
-    }
-
-    println!("├─────────────────────────────────────────────────────────────────────┤");
-    
-    let total = format!("Total: {:.8} BTC", tx.total_output_btc);
-    let fee = tx.fee_satoshis
-        .map(|f| format!(" | Fee: {} sats", f))
-        .unwrap_or_default();
-    
-    println!("│ {:<67} │", format!("{}{}", total, fee));
-    println!("└─────────────────────────────────────────────────────────────────────┘");
-    println!();
-}
-
-// Format locktime for display
-fn format_locktime(locktime: u32) -> String {
-    if locktime == 0 {
-        "0 (no lock)".to_string()
-    } else if locktime < 500_000_000 {
-        format!("{} (block height)", locktime)
-    } else {
-        let datetime = chrono::DateTime::from_timestamp(locktime as i64, 0)
-            .map(|dt| dt.format("%Y-%m-%d %H:%M:%S UTC").to_string())
-            .unwrap_or_else(|| "invalid timestamp".to_string());
-        format!("{} ({})", locktime, datetime)
+    if !cli.quiet {
+        eprintln!("Read {} block(s) from {}{}", read, path.display(),
+            if failed > 0 { format!(", {} error(s)", failed) } else { String::new() });
+    }
+}
+
+// One JSON line per matching output; blocks come in file order, not height order
+fn run_scan_blockfile(cli: &Cli, path: &Path, template: &ScriptTemplate, from_height: Option<u32>, chain: Chain, limit: Option<usize>, checkpoint: Option<&Path>) {
+    let mut blocks = open_block_files(cli, path, chain, checkpoint);
+    let (mut read, mut failed, mut matched) = (0, 0, 0);
+    let mut out = io::stdout().lock();
+
+    while limit.is_none_or(|limit| read < limit) {
+        let block = match blocks.next() {
+            Some(Ok(block)) => block,
+            Some(Err(e)) => {
+                let file = blocks.current_file().map(|f| f.display().to_string()).unwrap_or_default();
+                eprintln!("{}: {}: {}", paint("Warning", Role::Warn).bold(), file, e);
+                failed += 1;
+                continue;
+            }
+            None => break,
+        };
+        read += 1;
+
+        let height = block.height();
+        let wanted = from_height.is_none_or(|from| height.is_some_and(|height| height >= from));
+        for tx in block.transactions.iter().filter(|_| wanted) {
+            for output in tx.outputs.iter().filter(|o| template.matches(&o.script_pubkey.bytes)) {
+                matched += 1;
+                let address = output.address.as_ref()
+                    .map(|a| if matches!(chain, Chain::Mainnet) { &a.mainnet } else { &a.testnet });
+                let line = serde_json::json!({
+                    "height": height,
+                    "block_hash": block.header.hash,
+                    "txid": tx.txid,
+                    "vout": output.index,
+                    "value": output.value,
+                    "script_type": output.script_type,
+                    "script_pubkey": output.script_pubkey,
+                    "address": address,
+                });
+                exit_on_write_error(btc_tx_render::write_json(&mut out, &line, cli.json_line_style()));
+            }
+        }
+        if let Some(state) = checkpoint.filter(|_| read % CHECKPOINT_INTERVAL == 0) {
+            save_checkpoint(state, &blocks);
+        }
+    }
+    if let Some(state) = checkpoint {
+        save_checkpoint(state, &blocks);
+    }
+
+    if !cli.quiet {
+        eprintln!("Scanned {} block(s) from {}: {} matching output(s){}", read, path.display(), matched,
+            if failed > 0 { format!(", {} error(s)", failed) } else { String::new() });
+    }
+}
+
+// A block without its transactions, for one-line JSON output
+fn block_summary(block: &Block) -> serde_json::Value {
+    serde_json::json!({
+        "header": block.header,
+        "tx_count": block.transactions.len(),
+        "size": block.size,
+        "weight": block.weight,
+        "merkle_root_valid": block.merkle_root_valid,
+        "witness_commitment_valid": block.witness_commitment_valid,
+    })
+}
+
+fn run_stream(cli: &Cli, file: Option<&Path>, block: bool) {
+    let reader: Box<dyn Read> = match file {
+        Some(path) => Box::new(BufReader::new(File::open(path)
+            .unwrap_or_else(|e| exit_with_error(&format!("Failed to open '{}': {}", path.display(), e))))),
+        None => Box::new(io::stdin().lock()),
+    };
+    let mut stream = StreamParser::new(reader);
+    let mut out = io::stdout().lock();
+
+    let mut expected = None;
+    if block {
+        let (header, count) = match stream.read_block_header() {
+            Ok(Some(header)) => header,
+            Ok(None) => exit_with_error("Empty stream"),
+            Err(e) => exit_with_error(&e.to_string()),
+        };
+        let result = match cli.output {
+            OutputFormat::Json => btc_tx_render::write_json(&mut out, &serde_json::json!({ "header": header, "tx_count": count }), cli.json_line_style()),
+            _ => btc_tx_render::write_block_header(&mut out, &header),
+        };
+        exit_on_write_error(result);
+        expected = Some(count);
+    }
+
+    // JSON is one object per line, so each transaction is printed as it completes
+    let style = match cli.output {
+        OutputFormat::Json => cli.json_line_style(),
+        _ => cli.json_style(),
+    };
+    let mut read = 0u64;
+    while expected.is_none_or(|count| read < count) {
+        match stream.next_transaction() {
+            Ok(Some(tx)) => {
+                read += 1;
+                let findings = cli.analyze.then(|| analyze(cli, &tx));
+                exit_on_write_error(btc_tx_render::write_transaction_with_findings(&mut out, &tx, cli.output.into(), findings.as_deref(), style));
+            }
+            Ok(None) => break,
+            Err(e) => exit_with_error(&format!("{} (after {} transaction(s), offset {})", e, read, stream.position())),
+        }
+    }
+    if let Some(count) = expected.filter(|count| read < *count) {
+        exit_with_error(&format!("Stream ended after {} of {} transaction(s)", read, count));
+    }
+    if !cli.quiet {
+        eprintln!("Read {} transaction(s), {} bytes", read, stream.position());
     }
 }
+
+fn run_p2p(cli: &Cli, text: Option<&str>, file: Option<&Path>) {
+    let bytes = match (file, text) {
+        (Some(path), _) => {
+            let bytes = std::fs::read(path)
+                .unwrap_or_else(|e| exit_with_error(&format!("Failed to read '{}': {}", path.display(), e)));
+            // captures are binary unless the file is all hex text
+            match normalize_hex(&String::from_utf8_lossy(&bytes)) {
+                Ok(hex) => hex::decode(hex).unwrap_or_else(|e| exit_with_error(&e.to_string())),
+                Err(_) => bytes,
+            }
+        }
+        (None, text) => {
+            let text = match text {
+                Some(text) if text != "-" => text.to_string(),
+                _ => read_text(None),
+            };
+            let hex = normalize_hex(&text).unwrap_or_else(|e| exit_with_error(&e.to_string()));
+            hex::decode(hex).unwrap_or_else(|e| exit_with_error(&e.to_string()))
+        }
+    };
+    let messages = parse_messages(&bytes).unwrap_or_else(|e| exit_with_error(&e.to_string()));
+
+    for message in &messages {
+        let decoded = message.decode().unwrap_or_else(|e| exit_with_error(&format!("'{}' payload: {}", message.command, e)));
+        if let OutputFormat::Json = cli.output {
+            let payload = match &decoded {
+                WirePayload::Tx(tx) => serde_json::json!({ "transaction": tx }),
+                WirePayload::Block(block) => serde_json::json!({ "block": block_summary(block) }),
+                WirePayload::CompactBlock(block) => serde_json::json!({ "compact_block": block }),
+                WirePayload::Unsupported => serde_json::json!({}),
+            };
+            print_json(&serde_json::json!({ "message": message, "payload": payload }), cli.json_style());
+            continue;
+        }
+
+        exit_on_write_error(btc_tx_render::write_p2p_message(&mut io::stdout().lock(), message, &decoded));
+        if let WirePayload::Tx(tx) = &decoded {
+            render(cli, tx);
+        }
+    }
+}
+
+fn run_template(cli: &Cli, file: Option<&Path>) {
+    let template = BlockTemplate::from_json(&read_text(file)).unwrap_or_else(|e| exit_with_error(&e.to_string()));
+    let report = template.analyze();
+
+    if let OutputFormat::Json = cli.output {
+        print_json(&report, cli.json_style());
+        return;
+    }
+
+    exit_on_write_error(btc_tx_render::write_template_report(&mut io::stdout().lock(), &report));
+}
+
+fn read_bundle(path: &Path) -> TxBundle {
+    let json = std::fs::read_to_string(path)
+        .unwrap_or_else(|e| exit_with_error(&format!("Failed to read '{}': {}", path.display(), e)));
+    TxBundle::from_json(&json).unwrap_or_else(|e| exit_with_error(&e.to_string()))
+}
+
+//transaction hex from CLI, file, or stdin
+fn get_tx_hex(source: &TxSource) -> Result<String, String> {
+    if let Some(file_path) = &source.file {
+        let content = std::fs::read_to_string(file_path)
+            .map_err(|e| format!("Failed to read file '{}': {}", file_path, e))?;
+        return Ok(content.trim().to_string());
+    }
+
+    match &source.tx_hex {
+        Some(hex) if hex == "-" => {
+            let mut buffer = String::new();
+            io::stdin()
+                .read_to_string(&mut buffer)
+                .map_err(|e| format!("Failed to read from stdin: {}", e))?;
+            Ok(buffer.trim().to_string())
+        }
+        Some(hex) => Ok(hex.trim().to_string()),
+        None => {
+            if atty::is(atty::Stream::Stdin) {
+                Err("No transaction provided. Use -h for help.".to_string())
+            } else {
+                let mut buffer = String::new();
+                io::stdin()
+                    .read_to_string(&mut buffer)
+                    .map_err(|e| format!("Failed to read from stdin: {}", e))?;
+                Ok(buffer.trim().to_string())
+            }
+        }
+    }
+}
+
+// JSON output
+fn print_json<T: serde::Serialize>(value: &T, style: JsonStyle) {
+    exit_on_write_error(btc_tx_render::write_json(&mut io::stdout().lock(), value, style));
+}
+
@@ -0,0 +1,355 @@
+//! Long-running stdin to NDJSON bridge for pipelines
+//!
+//! Reads one transaction per line (`<hex> [v1,v2,...]`, as for `stats`) until
+//! EOF and writes one JSON object per line. Lines that fail to decode produce
+//! `{"line": N, "error": "..."}` instead of stopping the stream. Lines are
+//! parsed, analyzed and rendered on worker pools joined by bounded channels,
+//! and written in input order; memory use is bounded by the queue depth times
+//! the longest accepted line. With a summary cache, lines seen before are
+//! answered from it and skip the later stages' work.
+
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, Write};
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, Scope};
+use std::time::Duration;
+use btc_tx_parser::{ExactBtc, Finding, PrivacyReport, Pseudonymizer, Transaction, TxShape};
+use btc_tx_render::AnalyzedTransaction;
+use serde::Serialize;
+use crate::parse_batch_line;
+#[cfg(feature = "cache")]
+use crate::cache::SummaryCache;
+
+pub struct PipeOptions {
+    pub analyze: bool,
+    // BTC twins as exact 8-decimal strings (--exact-btc)
+    pub exact_btc: bool,
+    // pseudonymize every transaction under one key, so links survive
+    pub pseudonymizer: Option<Pseudonymizer>,
+    // longer lines are skipped without being buffered
+    pub max_line_bytes: usize,
+    // seconds between throughput reports on stderr; 0 disables them
+    pub stats_interval: u64,
+    // threads per stage; 0 for one per CPU
+    pub workers: StageWorkers,
+    // lines in flight at once, from read to written
+    pub queue_depth: usize,
+    // rendered lines from earlier runs, by wtxid
+    #[cfg(feature = "cache")]
+    pub cache: Option<SummaryCache>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StageWorkers {
+    pub parse: usize,
+    pub analyze: usize,
+    pub render: usize,
+}
+
+#[derive(Default)]
+struct Counters {
+    lines: AtomicU64,
+    decoded: AtomicU64,
+    failed: AtomicU64,
+}
+
+enum Line {
+    Complete,
+    TooLong(usize),
+    Eof,
+}
+
+// A line on its way through the stages, numbered for putting the output
+// back in input order; failures pass through the later stages untouched
+struct Job<T> {
+    seq: u64,
+    line: u64,
+    item: Result<T, String>,
+}
+
+// Output of the parse stage: a transaction, or its output from the cache
+enum Parsed {
+    Tx {
+        tx: Transaction,
+        #[cfg(feature = "cache")]
+        key: Option<Vec<u8>>,
+    },
+    #[cfg(feature = "cache")]
+    Cached(String),
+}
+
+// Output of the analyze stage
+enum Analyzed {
+    Tx {
+        tx: Transaction,
+        #[cfg(feature = "cache")]
+        key: Option<Vec<u8>>,
+        analysis: Option<Box<(Vec<Finding>, TxShape, PrivacyReport)>>,
+    },
+    #[cfg(feature = "cache")]
+    Cached(String),
+}
+
+pub fn run(options: &PipeOptions) -> Result<(), String> {
+    run_with(options, io::stdin().lock(), io::stdout())
+}
+
+// Read lines on this thread, parse, analyze and render them on the stage
+// workers, and write them out, in input order, on a writer thread. A writer
+// credit is taken per line, so at most `queue_depth` lines are in flight
+// however unevenly the workers progress.
+pub fn run_with(options: &PipeOptions, mut reader: impl BufRead, out: impl Write + Send) -> Result<(), String> {
+    let counters = Arc::new(Counters::default());
+    if options.stats_interval > 0 {
+        spawn_reporter(Arc::clone(&counters), Duration::from_secs(options.stats_interval));
+    }
+    let depth = options.queue_depth.max(1);
+    let (credit_tx, credits) = sync_channel::<()>(depth);
+    for _ in 0..depth {
+        let _ = credit_tx.send(());
+    }
+
+    let result = thread::scope(|scope| {
+        let (lines_tx, lines) = sync_channel::<Job<String>>(depth);
+        let parsed = stage(scope, options.workers.parse, depth, lines, |line| decode(&line, options));
+        let analyzed = stage(scope, options.workers.analyze, depth, parsed, |parsed| Ok(analyze(parsed, options.analyze)));
+        let rendered = stage(scope, options.workers.render, depth, analyzed, |analyzed| render(analyzed, options));
+        let writer = scope.spawn(|| write_in_order(rendered, out, credit_tx, &counters));
+
+        let mut buf = Vec::new();
+        let mut number = 0u64;
+        let mut seq = 0u64;
+        loop {
+            buf.clear();
+            let line = read_line(&mut reader, &mut buf, options.max_line_bytes)
+                .map_err(|e| format!("Failed to read from stdin: {}", e))?;
+            number += 1;
+
+            let item = match line {
+                Line::Eof => break,
+                Line::TooLong(len) => Err(format!("Line is {} bytes, over the {} byte limit", len, options.max_line_bytes)),
+                Line::Complete => {
+                    let text = String::from_utf8_lossy(&buf);
+                    let text = text.trim();
+                    if text.is_empty() || text.starts_with('#') {
+                        continue;
+                    }
+                    Ok(text.to_string())
+                }
+            };
+            // both fail only once the writer has stopped
+            if credits.recv().is_err() || lines_tx.send(Job { seq, line: number, item }).is_err() {
+                break;
+            }
+            seq += 1;
+        }
+        drop(lines_tx);
+        writer.join().map_err(|_| "writer thread panicked".to_string())?
+    });
+
+    if options.stats_interval > 0 {
+        report(&counters, None);
+    }
+    result
+}
+
+// Start `workers` threads (one per CPU for 0) mapping jobs from `input`
+fn stage<'scope, I, O>(
+    scope: &'scope Scope<'scope, '_>,
+    workers: usize,
+    depth: usize,
+    input: Receiver<Job<I>>,
+    f: impl Fn(I) -> Result<O, String> + Send + Sync + 'scope,
+) -> Receiver<Job<O>>
+where
+    I: Send + 'scope,
+    O: Send + 'scope,
+{
+    let workers = match workers {
+        0 => thread::available_parallelism().map_or(1, NonZeroUsize::get),
+        n => n,
+    };
+    let (output, results) = sync_channel(depth);
+    let input = Arc::new(Mutex::new(input));
+    let f = Arc::new(f);
+    for _ in 0..workers {
+        let (input, output, f): (_, SyncSender<Job<O>>, _) = (Arc::clone(&input), output.clone(), Arc::clone(&f));
+        scope.spawn(move || loop {
+            // the lock is only held while waiting for the next job
+            let Ok(job) = input.lock().map_err(drop).and_then(|rx| rx.recv().map_err(drop)) else {
+                break;
+            };
+            let item = job.item.and_then(|item| f(item));
+            if output.send(Job { seq: job.seq, line: job.line, item }).is_err() {
+                break;
+            }
+        });
+    }
+    results
+}
+
+fn write_in_order(results: Receiver<Job<String>>, mut out: impl Write, credits: SyncSender<()>, counters: &Counters) -> Result<(), String> {
+    let mut pending = BTreeMap::new();
+    let mut next = 0u64;
+    for job in results {
+        pending.insert(job.seq, job);
+        while let Some(job) = pending.remove(&next) {
+            next += 1;
+            counters.lines.fetch_add(1, Ordering::Relaxed);
+            let json = match job.item {
+                Ok(json) => {
+                    counters.decoded.fetch_add(1, Ordering::Relaxed);
+                    json
+                }
+                Err(error) => {
+                    counters.failed.fetch_add(1, Ordering::Relaxed);
+                    serde_json::json!({ "line": job.line, "error": error }).to_string()
+                }
+            };
+
+            match writeln!(out, "{}", json) {
+                Ok(()) => {}
+                // downstream consumer went away
+                Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+                Err(e) => return Err(format!("Failed to write to stdout: {}", e)),
+            }
+            let _ = credits.send(());
+        }
+    }
+    Ok(())
+}
+
+fn decode(line: &str, options: &PipeOptions) -> Result<Parsed, String> {
+    #[cfg(feature = "cache")]
+    let key = options.cache.as_ref().and_then(|_| SummaryCache::key(line, options.analyze, options.exact_btc));
+    #[cfg(feature = "cache")]
+    if let (Some(cache), Some(key)) = (&options.cache, &key) {
+        if let Some(json) = cache.get(key) {
+            return Ok(Parsed::Cached(json));
+        }
+    }
+
+    let tx = parse_batch_line(line)?;
+    let tx = match &options.pseudonymizer {
+        Some(pseudonymizer) => pseudonymizer.pseudonymize(&tx),
+        None => tx,
+    };
+    Ok(Parsed::Tx {
+        tx,
+        #[cfg(feature = "cache")]
+        key,
+    })
+}
+
+fn analyze(parsed: Parsed, analyze: bool) -> Analyzed {
+    match parsed {
+        Parsed::Tx {
+            tx,
+            #[cfg(feature = "cache")]
+            key,
+        } => {
+            let analysis = analyze.then(|| Box::new((tx.analyze(), tx.shape(), tx.privacy())));
+            Analyzed::Tx {
+                tx,
+                #[cfg(feature = "cache")]
+                key,
+                analysis,
+            }
+        }
+        #[cfg(feature = "cache")]
+        Parsed::Cached(json) => Analyzed::Cached(json),
+    }
+}
+
+fn render(analyzed: Analyzed, options: &PipeOptions) -> Result<String, String> {
+    match analyzed {
+        Analyzed::Tx {
+            tx,
+            #[cfg(feature = "cache")]
+            key,
+            analysis,
+        } => {
+            let json = match analysis {
+                Some(analysis) => {
+                    let (analysis, tx_shape, privacy) = *analysis;
+                    to_json(&AnalyzedTransaction { tx: &tx, analysis: &analysis, tx_shape, privacy }, options.exact_btc)
+                }
+                None => to_json(&tx, options.exact_btc),
+            };
+            let json = json.map_err(|e| e.to_string())?;
+            #[cfg(feature = "cache")]
+            if let (Some(cache), Some(key)) = (&options.cache, key) {
+                cache.insert(&key, &json);
+            }
+            Ok(json)
+        }
+        #[cfg(feature = "cache")]
+        Analyzed::Cached(json) => Ok(json),
+    }
+}
+
+fn to_json<T: Serialize>(value: &T, exact_btc: bool) -> serde_json::Result<String> {
+    if exact_btc {
+        serde_json::to_string(&ExactBtc(value))
+    } else {
+        serde_json::to_string(value)
+    }
+}
+
+// Read up to and including the next newline, but never buffer more than
+// `limit` bytes; the rest of an overlong line is consumed and dropped
+fn read_line(reader: &mut impl BufRead, buf: &mut Vec<u8>, limit: usize) -> io::Result<Line> {
+    let read = io::Read::take(&mut *reader, limit as u64 + 1).read_until(b'\n', buf)?;
+    if read == 0 {
+        return Ok(Line::Eof);
+    }
+    if buf.last() == Some(&b'\n') || buf.len() <= limit {
+        return Ok(Line::Complete);
+    }
+
+    let mut skipped = buf.len();
+    buf.clear();
+    loop {
+        let chunk = reader.fill_buf()?;
+        if chunk.is_empty() {
+            break;
+        }
+        match chunk.iter().position(|&b| b == b'\n') {
+            Some(end) => {
+                skipped += end;
+                reader.consume(end + 1);
+                break;
+            }
+            None => {
+                let len = chunk.len();
+                skipped += len;
+                reader.consume(len);
+            }
+        }
+    }
+    Ok(Line::TooLong(skipped))
+}
+
+fn spawn_reporter(counters: Arc<Counters>, interval: Duration) {
+    thread::spawn(move || {
+        let mut last = 0;
+        loop {
+            thread::sleep(interval);
+            let lines = counters.lines.load(Ordering::Relaxed);
+            report(&counters, Some((lines - last) as f64 / interval.as_secs_f64()));
+            last = lines;
+        }
+    });
+}
+
+fn report(counters: &Counters, rate: Option<f64>) {
+    let rate = rate.map(|r| format!("{:.1} lines/s, ", r)).unwrap_or_else(|| "done, ".to_string());
+    eprintln!("[pipe] {}{} lines: {} decoded, {} failed",
+        rate,
+        counters.lines.load(Ordering::Relaxed),
+        counters.decoded.load(Ordering::Relaxed),
+        counters.failed.load(Ordering::Relaxed));
+}
@@ -0,0 +1,23 @@
+//! Progress bars on stderr for long-running commands
+
+use indicatif::{ProgressBar, ProgressStyle};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+// `--quiet` turns every bar into a no-op
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+// Bar over `len` items, hidden when quiet or stderr is not a terminal.
+// Print through `ProgressBar::suspend` while it is visible.
+pub fn bar(len: u64, message: &'static str) -> ProgressBar {
+    if QUIET.load(Ordering::Relaxed) || !atty::is(atty::Stream::Stderr) {
+        return ProgressBar::hidden();
+    }
+    let style = ProgressStyle::with_template("{msg} [{bar:40.cyan/blue}] {pos}/{len} ({per_sec}, ETA {eta})")
+        .expect("valid progress template")
+        .progress_chars("=> ");
+    ProgressBar::new(len).with_style(style).with_message(message)
+}
@@ -0,0 +1,111 @@
+//! HTTP service exposing the decoder (`serve` feature)
+//!
+//! POST /decode and POST /analyze take raw transaction hex, either as the
+//! plain body or as `{"hex": "...", "input_values": [...]}`; GET /script/{hex}
+//! decodes a single script. Responses use the same JSON as `--output json`.
+
+use axum::extract::Path;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use btc_tx_parser::{derive_address, detect_script_type, normalize_hex, script_to_asm, AddressInfo, Amount, ScriptType, Transaction};
+use serde::{Deserialize, Serialize};
+use btc_tx_render::AnalyzedTransaction;
+
+pub fn run(bind: &str) -> Result<(), String> {
+    let runtime = tokio::runtime::Runtime::new().map_err(|e| format!("Failed to start runtime: {}", e))?;
+    runtime.block_on(async {
+        let listener = tokio::net::TcpListener::bind(bind)
+            .await
+            .map_err(|e| format!("Failed to bind {}: {}", bind, e))?;
+        eprintln!("Listening on http://{}", bind);
+        axum::serve(listener, router())
+            .await
+            .map_err(|e| format!("Server error: {}", e))
+    })
+}
+
+pub fn router() -> Router {
+    Router::new()
+        .route("/decode", post(decode))
+        .route("/analyze", post(analyze))
+        .route("/script/{hex}", get(script))
+}
+
+#[derive(Deserialize)]
+struct DecodeRequest {
+    hex: String,
+    #[serde(default)]
+    input_values: Option<Vec<u64>>,
+}
+
+#[derive(Serialize)]
+struct ScriptResponse {
+    hex: String,
+    asm: String,
+    size: usize,
+    script_type: ScriptType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    address: Option<AddressInfo>,
+}
+
+// 400 with `{"error": "..."}`
+struct ApiError(String);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (StatusCode::BAD_REQUEST, Json(serde_json::json!({ "error": self.0 }))).into_response()
+    }
+}
+
+async fn decode(body: String) -> Result<Json<Transaction>, ApiError> {
+    parse_request(&body).map(Json)
+}
+
+async fn analyze(body: String) -> Result<Response, ApiError> {
+    let tx = parse_request(&body)?;
+    let findings = tx.analyze();
+    Ok(Json(AnalyzedTransaction { tx: &tx, analysis: &findings, tx_shape: tx.shape(), privacy: tx.privacy() }).into_response())
+}
+
+async fn script(Path(hex): Path<String>) -> Result<Json<ScriptResponse>, ApiError> {
+    let hex = normalize_hex(&hex).map_err(|e| ApiError(e.to_string()))?;
+    let bytes = hex::decode(&hex).map_err(|e| ApiError(e.to_string()))?;
+    let script_type = detect_script_type(&bytes);
+    Ok(Json(ScriptResponse {
+        asm: script_to_asm(&bytes),
+        size: bytes.len(),
+        address: derive_address(&bytes, &script_type),
+        script_type,
+        hex,
+    }))
+}
+
+fn parse_request(body: &str) -> Result<Transaction, ApiError> {
+    let request = if body.trim_start().starts_with('{') {
+        serde_json::from_str(body).map_err(|e| ApiError(format!("Invalid request: {}", e)))?
+    } else {
+        DecodeRequest { hex: body.to_string(), input_values: None }
+    };
+
+    let hex = normalize_hex(&request.hex).map_err(|e| ApiError(e.to_string()))?;
+    let mut tx = Transaction::from_hex(&hex).map_err(|e| ApiError(e.to_string()))?;
+
+    if let Some(values) = request.input_values {
+        if values.len() != tx.inputs.len() {
+            return Err(ApiError(format!(
+                "Provided {} input values but transaction has {} inputs",
+                values.len(),
+                tx.inputs.len()
+            )));
+        }
+        for (input, value) in tx.inputs.iter_mut().zip(values) {
+            input.value = Some(Amount::from_sat(value));
+        }
+        if let Some(fee) = tx.calculate_fee() {
+            tx.set_fee(Some(fee));
+        }
+    }
+    Ok(tx)
+}
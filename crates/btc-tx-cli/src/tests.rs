@@ -0,0 +1,180 @@
+//Tests for btc-tx-cli crate
+
+use std::io::Cursor;
+use btc_tx_parser::{StressReport, Transaction, TxGenerator};
+#[cfg(feature = "cache")]
+use crate::cache::SummaryCache;
+use clap::Parser;
+use crate::{golden, pipe, wizard, Cli, Command};
+
+// ============================================================================
+// Golden Output Tests
+// ============================================================================
+
+#[test]
+fn test_golden_outputs() {
+    colored::control::set_override(false);
+    let changed = golden::check(false).unwrap();
+    assert!(changed.is_empty(),
+        "output differs from {:?}: {:?}\nrun `cargo run -p btc-tx-cli -- golden --bless` if the change is intended",
+        golden::golden_dir(), changed);
+}
+
+// ============================================================================
+// Pipe Tests
+// ============================================================================
+
+#[test]
+fn test_pipe_keeps_input_order() {
+    let fixtures = std::fs::read_to_string(golden::golden_dir().join("transactions.txt")).unwrap();
+    let txs: Vec<&str> = fixtures.lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.trim().split_once(char::is_whitespace).map(|(_, tx)| tx.trim()))
+        .collect();
+    assert!(txs.len() >= 2);
+
+    // every fixture many times over, with a comment and bad lines in between
+    let mut input = String::from("# header\n");
+    let mut expected = Vec::new();
+    for round in 0..20 {
+        for tx in &txs {
+            input.push_str(tx);
+            input.push('\n');
+            expected.push(Some(crate::parse_batch_line(tx).unwrap().txid.to_string()));
+        }
+        input.push_str(&format!("zz{}\n\n", round));
+        expected.push(None);
+    }
+
+    let options = pipe::PipeOptions {
+        analyze: true,
+        exact_btc: false,
+        pseudonymizer: None,
+        max_line_bytes: 1_000_000,
+        stats_interval: 0,
+        workers: pipe::StageWorkers { parse: 4, analyze: 3, render: 2 },
+        queue_depth: 8,
+        #[cfg(feature = "cache")]
+        cache: None,
+    };
+    let mut out = Vec::new();
+    pipe::run_with(&options, Cursor::new(input), &mut out).unwrap();
+
+    let lines: Vec<serde_json::Value> = String::from_utf8(out).unwrap().lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), expected.len());
+    for (line, txid) in lines.iter().zip(&expected) {
+        match txid {
+            Some(txid) => {
+                assert_eq!(line["txid"], *txid.as_str());
+                assert!(line["analysis"].is_array());
+            }
+            None => assert!(line["error"].is_string()),
+        }
+    }
+    // line numbers count the comment and blank lines too
+    assert_eq!(lines[txs.len()]["line"], txs.len() + 2);
+}
+
+#[cfg(feature = "cache")]
+#[test]
+fn test_pipe_summary_cache() {
+    let fixtures = std::fs::read_to_string(golden::golden_dir().join("transactions.txt")).unwrap();
+    let txs: Vec<&str> = fixtures.lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.trim().split_once(char::is_whitespace).map(|(_, tx)| tx.trim()))
+        .collect();
+    let input = format!("{}\nzz\n", txs.join("\n"));
+    let dir = std::env::temp_dir().join(format!("btc-tx-summary-cache-{}", std::process::id()));
+    let run = |cache: &SummaryCache, analyze: bool, exact_btc: bool| {
+        let options = pipe::PipeOptions {
+            analyze,
+            exact_btc,
+            pseudonymizer: None,
+            max_line_bytes: 1_000_000,
+            stats_interval: 0,
+            workers: pipe::StageWorkers { parse: 2, analyze: 2, render: 2 },
+            queue_depth: 8,
+            cache: Some(cache.clone()),
+        };
+        let mut out = Vec::new();
+        pipe::run_with(&options, Cursor::new(input.clone()), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    };
+
+    let cache = SummaryCache::open(&dir).unwrap();
+    let first = run(&cache, true, false);
+    // failed lines aren't cached
+    assert_eq!(cache.stats().unwrap().entries, txs.len());
+    assert_eq!(run(&cache, true, false), first);
+    // output without findings, or with exact BTC strings, is a separate entry
+    assert!(!run(&cache, false, false).contains("\"analysis\""));
+    assert!(run(&cache, true, true).contains("\"total_output_btc\":\""));
+    assert_eq!(cache.stats().unwrap().entries, 3 * txs.len());
+
+    // a hit answers without parsing, so a planted entry shows
+    let key = SummaryCache::key(txs[0], true, false).unwrap();
+    cache.insert(&key, "{\"cached\":true}");
+    assert!(run(&cache, true, false).starts_with("{\"cached\":true}\n"));
+    assert_eq!(cache.clear().unwrap(), 3 * txs.len());
+    assert_eq!(run(&cache, true, false), first);
+    drop(cache);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+// ============================================================================
+// Stress Tests
+// ============================================================================
+
+#[test]
+fn test_stress_round_trips() {
+    let report = StressReport::run(500, 1, |_| {});
+    assert!(report.passed(), "{:?}", report.mismatches);
+    assert_eq!(report.count, 500);
+
+    // a seed pins its transactions, across releases too
+    let first: Vec<Vec<u8>> = TxGenerator::new(42).take(50).collect();
+    assert_eq!(first, TxGenerator::new(42).take(50).collect::<Vec<_>>());
+    assert_ne!(first, TxGenerator::new(43).take(50).collect::<Vec<_>>());
+    let tx = Transaction::from_bytes(&first[0]).unwrap();
+    assert_eq!(tx.txid.to_string(), "84f8fa8d78403a03275b945bbe5b43d3ab2a00df475b90b198b514b54630ed30");
+}
+
+// ============================================================================
+// Wizard Tests
+// ============================================================================
+
+#[test]
+fn test_wizard_steps() {
+    use wizard::Step;
+    colored::control::set_override(false);
+    let tx = Transaction::from_bytes(&TxGenerator::new(7).next_tx()).unwrap();
+
+    // forward, back, an unknown key, then to the end
+    let mut out = Vec::new();
+    let shown = wizard::run_with(&tx, Cursor::new("\n\nb\nx\n\n\n\n\n"), &mut out).unwrap();
+    assert_eq!(shown, vec![Step::Version, Step::Inputs, Step::Scripts, Step::Inputs,
+        Step::Scripts, Step::Outputs, Step::Fee]);
+    let text = String::from_utf8(out).unwrap();
+    assert!(text.contains("Step 1/5: Version"));
+    assert!(text.contains("Step 5/5: Fee"));
+    assert!(text.contains("press Enter, b or q"));
+    assert!(text.contains("That's the whole transaction"));
+
+    // back on the first page stays there; q and EOF stop early
+    let shown = wizard::run_with(&tx, Cursor::new("b\nq\n"), &mut Vec::new()).unwrap();
+    assert_eq!(shown, vec![Step::Version, Step::Version]);
+    let shown = wizard::run_with(&tx, Cursor::new("\n"), &mut Vec::new()).unwrap();
+    assert_eq!(shown, vec![Step::Version, Step::Inputs]);
+
+    // a transaction on stdin would leave the prompts nothing to read
+    let reads_stdin = |args: &[&str]| match Cli::parse_from(args).command {
+        Some(Command::Wizard { source }) => source.reads_stdin(),
+        _ => unreachable!(),
+    };
+    assert!(reads_stdin(&["btc-tx-inspector", "wizard"]));
+    assert!(reads_stdin(&["btc-tx-inspector", "wizard", "-"]));
+    assert!(!reads_stdin(&["btc-tx-inspector", "wizard", "0100"]));
+    assert!(!reads_stdin(&["btc-tx-inspector", "wizard", "--file", "tx.hex"]));
+}
@@ -0,0 +1,252 @@
+//! Guided walkthrough for newcomers: one page per part of the transaction
+//! (version → inputs → scripts → outputs → fee), paged forward and back from
+//! the terminal. Descriptions reuse the renderer's `describe_*` helpers so
+//! the wording matches the other views.
+
+use std::io::{self, BufRead, Write};
+use btc_tx_parser::{ScriptType, Transaction};
+use btc_tx_render::{describe_coinbase, describe_sequence, paint, Role};
+use colored::Colorize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step {
+    Version,
+    Inputs,
+    Scripts,
+    Outputs,
+    Fee,
+    Done,
+}
+
+impl Step {
+    const PAGES: usize = 5;
+
+    pub fn next(self) -> Step {
+        match self {
+            Step::Version => Step::Inputs,
+            Step::Inputs => Step::Scripts,
+            Step::Scripts => Step::Outputs,
+            Step::Outputs => Step::Fee,
+            Step::Fee | Step::Done => Step::Done,
+        }
+    }
+
+    // The first page has nowhere to go back to
+    pub fn back(self) -> Step {
+        match self {
+            Step::Version | Step::Inputs => Step::Version,
+            Step::Scripts => Step::Inputs,
+            Step::Outputs => Step::Scripts,
+            Step::Fee => Step::Outputs,
+            Step::Done => Step::Fee,
+        }
+    }
+
+    fn number(self) -> usize {
+        self as usize + 1
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            Step::Version => "Version",
+            Step::Inputs => "Inputs",
+            Step::Scripts => "Scripts",
+            Step::Outputs => "Outputs",
+            Step::Fee => "Fee",
+            Step::Done => "Done",
+        }
+    }
+}
+
+enum Action {
+    Next,
+    Back,
+    Quit,
+}
+
+fn parse_action(line: &str) -> Option<Action> {
+    match line.trim().to_ascii_lowercase().as_str() {
+        "" | "n" | "next" => Some(Action::Next),
+        "b" | "back" => Some(Action::Back),
+        "q" | "quit" => Some(Action::Quit),
+        _ => None,
+    }
+}
+
+// Walk `tx` reading commands from `input` until the last page, `q` or EOF;
+// returns the pages shown, in order
+pub fn run_with(tx: &Transaction, mut input: impl BufRead, out: &mut dyn Write) -> io::Result<Vec<Step>> {
+    let mut shown = Vec::new();
+    let mut step = Step::Version;
+    while step != Step::Done {
+        shown.push(step);
+        write_page(out, tx, step)?;
+        step = loop {
+            write!(out, "{} ", paint("[Enter] next  [b] back  [q] quit >", Role::Muted))?;
+            out.flush()?;
+            let mut line = String::new();
+            if input.read_line(&mut line)? == 0 {
+                writeln!(out)?;
+                return Ok(shown);
+            }
+            match parse_action(&line) {
+                Some(Action::Next) => break step.next(),
+                Some(Action::Back) => break step.back(),
+                Some(Action::Quit) => return Ok(shown),
+                None => writeln!(out, "  {}", paint("press Enter, b or q", Role::Warn))?,
+            }
+        };
+    }
+    writeln!(out)?;
+    writeln!(out, "{}", paint("That's the whole transaction. Drop `wizard` for the full view, or add --analyze for findings.", Role::Good))?;
+    Ok(shown)
+}
+
+fn write_page(out: &mut dyn Write, tx: &Transaction, step: Step) -> io::Result<()> {
+    writeln!(out)?;
+    writeln!(out, "{}", paint(&format!("Step {}/{}: {}", step.number(), Step::PAGES, step.title()), Role::Heading).bold())?;
+    writeln!(out, "{}", paint(&"─".repeat(60), Role::Muted))?;
+    match step {
+        Step::Version => write_version(out, tx),
+        Step::Inputs => write_inputs(out, tx),
+        Step::Scripts => write_scripts(out, tx),
+        Step::Outputs => write_outputs(out, tx),
+        Step::Fee => write_fee(out, tx),
+        Step::Done => Ok(()),
+    }
+}
+
+fn explain(out: &mut dyn Write, text: &str) -> io::Result<()> {
+    writeln!(out, "  {}", paint(text, Role::Muted))
+}
+
+fn write_version(out: &mut dyn Write, tx: &Transaction) -> io::Result<()> {
+    explain(out, "A transaction starts with its version, which decides the rules it follows.")?;
+    writeln!(out)?;
+    let meaning = match tx.version {
+        1 => "the original format; nSequence carries no relative locktime",
+        2 => "enables BIP-68 relative locktimes in each input's nSequence",
+        3 => "opts into TRUC (BIP-431) relay rules, on top of version 2",
+        _ => "nonstandard; nodes won't relay it",
+    };
+    writeln!(out, "  {} {} ({})", paint("Version:", Role::Label).bold(), tx.version, meaning)?;
+    if tx.is_segwit {
+        writeln!(out, "  {} yes", paint("Segwit:", Role::Label).bold())?;
+        explain(out, "The marker (0x00) and flag (0x01) after the version announce a witness section,")?;
+        explain(out, "which holds the signatures at the end and counts a quarter toward the fee.")?;
+    } else {
+        writeln!(out, "  {} no", paint("Segwit:", Role::Label).bold())?;
+        explain(out, "Legacy serialization: the signatures sit inside each input's scriptSig.")?;
+    }
+    writeln!(out)?;
+    writeln!(out, "  {} {}", paint("TXID:", Role::Label).bold(), paint(&tx.txid.to_string(), Role::Hash))?;
+    explain(out, "The txid is the double SHA-256 of everything but the witness, shown byte-reversed.")?;
+    Ok(())
+}
+
+fn write_inputs(out: &mut dyn Write, tx: &Transaction) -> io::Result<()> {
+    explain(out, "Each input spends an output of an earlier transaction, named by its txid and index (vout).")?;
+    writeln!(out)?;
+    for input in &tx.inputs {
+        if input.is_coinbase {
+            writeln!(out, "  #{} {}", input.index, paint("coinbase: new coins created by the miner", Role::Good))?;
+        } else {
+            writeln!(out, "  #{} spends {}:{}", input.index, paint(&input.txid.to_string(), Role::Hash), input.vout)?;
+        }
+        match input.value {
+            _ if input.is_coinbase => {}
+            Some(value) => writeln!(out, "     value {}", paint(&value.to_string(), Role::Amount))?,
+            None => writeln!(out, "     {}", paint("value unknown: it lives in the spent output, not in this transaction", Role::Muted))?,
+        }
+        let sequence = if input.sequence.is_final() {
+            "final".to_string()
+        } else if input.sequence.is_rbf_signaling() {
+            format!("signals replace-by-fee, {}", describe_sequence(tx, input.sequence))
+        } else {
+            describe_sequence(tx, input.sequence)
+        };
+        writeln!(out, "     nSequence 0x{:08x}: {}", input.sequence.0, sequence)?;
+    }
+    Ok(())
+}
+
+fn write_scripts(out: &mut dyn Write, tx: &Transaction) -> io::Result<()> {
+    explain(out, "Inputs prove they may spend their coins: legacy inputs in the scriptSig,")?;
+    explain(out, "segwit inputs in the witness. Each signature commits to the parts named by its sighash.")?;
+    writeln!(out)?;
+    for input in &tx.inputs {
+        let kind = match (&input.script_sig_parts, &input.address) {
+            _ if input.is_coinbase => "coinbase data, free-form up to 100 bytes".to_string(),
+            (Some(parts), _) => parts.describe(),
+            (None, Some(address)) => address.address_type.clone(),
+            (None, None) => "unrecognized".to_string(),
+        };
+        writeln!(out, "  #{} {}", input.index, paint(&kind, Role::Label))?;
+        if let Some(coinbase) = input.coinbase.as_ref().map(describe_coinbase).filter(|text| !text.is_empty()) {
+            writeln!(out, "     {}", coinbase)?;
+            explain(out, "   Since BIP-34 the coinbase starts with the block height; pools add a tag and the extranonce they roll.")?;
+        }
+        if input.script_sig.size > 0 {
+            writeln!(out, "     scriptSig {}", input.script_sig.asm)?;
+        }
+        if let Some(witness) = &input.witness {
+            let sizes: Vec<String> = witness.iter().map(|item| item.len().to_string()).collect();
+            writeln!(out, "     witness items: {} ({} bytes)", witness.len(), sizes.join(", "))?;
+        }
+        for signature in &input.signatures {
+            writeln!(out, "     signature in {}: {:?}, SIGHASH_{}", signature.location, signature.kind, signature.sighash)?;
+        }
+        if let Some(script) = &input.witness_script {
+            writeln!(out, "     witness script: {}", script.script_type)?;
+        }
+        if let Some(taproot) = &input.taproot {
+            writeln!(out, "     taproot script path, leaf at depth {}", taproot.path_length)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_outputs(out: &mut dyn Write, tx: &Transaction) -> io::Result<()> {
+    explain(out, "Each output locks an amount to a script; spending it later means satisfying that script.")?;
+    writeln!(out)?;
+    let messages = tx.op_return_messages();
+    for output in &tx.outputs {
+        writeln!(out, "  #{} {} to {}", output.index, paint(&output.value.to_string(), Role::Amount), output.script_type)?;
+        if let Some(address) = &output.address {
+            writeln!(out, "     address {}", paint(&address.mainnet, Role::Hash))?;
+        }
+        if output.script_type == ScriptType::OpReturn {
+            explain(out, "   OP_RETURN outputs are provably unspendable and only carry data.")?;
+            for message in messages.iter().filter(|m| m.output == output.index) {
+                writeln!(out, "     {}: {}", message.protocol, message.message.summary)?;
+            }
+        }
+    }
+    writeln!(out)?;
+    writeln!(out, "  {} {}", paint("Total:", Role::Label).bold(), paint(&tx.total_output_satoshis.to_string(), Role::Amount))?;
+    Ok(())
+}
+
+fn write_fee(out: &mut dyn Write, tx: &Transaction) -> io::Result<()> {
+    explain(out, "The fee is whatever the inputs hold beyond the outputs; miners rank by fee per vbyte.")?;
+    writeln!(out)?;
+    writeln!(out, "  {} {} bytes, {} weight units, {} vbytes", paint("Size:", Role::Label).bold(), tx.raw_size, tx.weight, tx.vsize())?;
+    match tx.fee_satoshis {
+        Some(fee) => {
+            writeln!(out, "  {} {}", paint("Fee:", Role::Label).bold(), paint(&fee.to_string(), Role::Amount))?;
+            writeln!(out, "  {} {:.2} sat/vB", paint("Fee Rate:", Role::Label).bold(), fee.to_sat() as f64 / tx.vsize() as f64)?;
+        }
+        None if tx.inputs.iter().any(|input| input.is_coinbase) => {
+            writeln!(out, "  {} none", paint("Fee:", Role::Label).bold())?;
+            explain(out, "A coinbase pays no fee: its outputs claim the block subsidy plus the block's fees.")?;
+        }
+        None => {
+            writeln!(out, "  {} unknown", paint("Fee:", Role::Label).bold())?;
+            explain(out, "Input values aren't part of the transaction; pass --input-values or --fetch-prevouts.")?;
+        }
+    }
+    writeln!(out, "  {} {}", paint("Locktime:", Role::Label).bold(), tx.locktime)?;
+    explain(out, "A nonzero locktime keeps the transaction out of blocks until that height or time,")?;
+    explain(out, "unless every input's nSequence is final.")?;
+    Ok(())
+}
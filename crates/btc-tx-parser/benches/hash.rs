@@ -0,0 +1,71 @@
+//! SHA-256 backend throughput. Run once plain and once with
+//! `--features ring-sha256` to compare; the `sha2` baselines are the same in
+//! both runs.
+//!
+//!     cargo bench -p btc-tx-parser --bench hash
+//!     cargo bench -p btc-tx-parser --bench hash --features ring-sha256
+
+use btc_tx_parser::{sha256d, Block, BlockRef};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use sha2::{Digest, Sha256};
+
+// Genesis block header; the merkle root won't match, which parsing only reports
+const HEADER: &str = "0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff001d1dac2b7c";
+
+// A 1-in 2-out P2PKH payment, 225 bytes
+fn payment(n: u32) -> String {
+    let signature = format!("30440220{}0220{}01", "11".repeat(32), "22".repeat(32));
+    let script_sig = format!("47{}2102{}", signature, "33".repeat(32));
+    let p2pkh = format!("1976a914{}88ac", "44".repeat(20));
+    format!("0100000001{}{}6a{}ffffffff02a086010000000000{}50c3000000000000{}00000000",
+        "aa".repeat(32), hex::encode(n.to_le_bytes()), script_sig, p2pkh, p2pkh)
+}
+
+// A block of about `size` bytes of payments
+fn block_bytes(size: usize) -> Vec<u8> {
+    let count = (size / 225) as u16;
+    let mut hex = format!("{}fd{}", HEADER, hex::encode(count.to_le_bytes()));
+    for n in 0..count {
+        hex.push_str(&payment(n.into()));
+    }
+    hex::decode(hex).unwrap()
+}
+
+// Merkle root over raw txids, odd levels pairing the last hash with itself
+fn merkle_root(mut level: Vec<[u8; 32]>) -> [u8; 32] {
+    while level.len() > 1 {
+        level = level.chunks(2).map(|pair| sha256d(&[pair[0], pair[pair.len() - 1]].concat())).collect();
+    }
+    level[0]
+}
+
+fn bench_sha256d(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sha256d");
+    // a merkle node, a typical transaction, a block
+    for size in [64, 225, 1 << 20] {
+        let data: Vec<u8> = (0..size).map(|i| (i * 31 % 251) as u8).collect();
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::new("sha2", size), &data, |b, data| {
+            b.iter(|| Sha256::digest(Sha256::digest(black_box(data))))
+        });
+        group.bench_with_input(BenchmarkId::new("backend", size), &data, |b, data| b.iter(|| sha256d(black_box(data))));
+    }
+    group.finish();
+}
+
+fn bench_block(c: &mut Criterion) {
+    let bytes = block_bytes(1 << 20);
+    let mut group = c.benchmark_group("block");
+    group.throughput(Throughput::Bytes(bytes.len() as u64));
+    group.sample_size(20);
+    // hashing is most of this: a txid per transaction, then the merkle tree
+    group.bench_function("txids_and_merkle_root", |b| b.iter(|| {
+        let block = BlockRef::from_bytes(black_box(&bytes)).unwrap();
+        merkle_root(block.transactions.iter().map(|tx| tx.txid()).collect())
+    }));
+    group.bench_function("from_bytes", |b| b.iter(|| Block::from_bytes(black_box(&bytes)).unwrap()));
+    group.finish();
+}
+
+criterion_group!(benches, bench_sha256d, bench_block);
+criterion_main!(benches);
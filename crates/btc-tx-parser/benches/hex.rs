@@ -0,0 +1,65 @@
+//! Hex throughput on block-sized inputs. Run once plain and once with
+//! `--features simd-hex` to compare; the `hex` crate baselines are the same
+//! in both runs.
+//!
+//!     cargo bench -p btc-tx-parser --bench hex
+//!     cargo bench -p btc-tx-parser --bench hex --features simd-hex
+
+use btc_tx_parser::{decode_hex, encode_hex, Block};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+// Genesis block header; the merkle root won't match, which parsing only reports
+const HEADER: &str = "0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff001d1dac2b7c";
+
+// A 1-in 2-out P2PKH payment, 225 bytes
+fn payment(n: u32) -> String {
+    let signature = format!("30440220{}0220{}01", "11".repeat(32), "22".repeat(32));
+    let script_sig = format!("47{}2102{}", signature, "33".repeat(32));
+    let p2pkh = format!("1976a914{}88ac", "44".repeat(20));
+    format!("0100000001{}{}6a{}ffffffff02a086010000000000{}50c3000000000000{}00000000",
+        "aa".repeat(32), hex::encode(n.to_le_bytes()), script_sig, p2pkh, p2pkh)
+}
+
+// A block of about `size` bytes of payments
+fn block_hex(size: usize) -> String {
+    let count = (size / 225) as u16;
+    let mut hex = format!("{}fd{}", HEADER, hex::encode(count.to_le_bytes()));
+    for n in 0..count {
+        hex.push_str(&payment(n.into()));
+    }
+    hex
+}
+
+fn bench_hex(c: &mut Criterion) {
+    for size in [1 << 20, 4 << 20] {
+        let bytes: Vec<u8> = (0..size).map(|i| (i * 31 % 251) as u8).collect();
+        let hex = hex::encode(&bytes);
+        let label = format!("{} MiB", size >> 20);
+
+        let mut group = c.benchmark_group("decode");
+        group.throughput(Throughput::Bytes(hex.len() as u64));
+        group.bench_with_input(BenchmarkId::new("hex", &label), &hex, |b, hex| b.iter(|| hex::decode(black_box(hex))));
+        group.bench_with_input(BenchmarkId::new("decode_hex", &label), &hex, |b, hex| b.iter(|| decode_hex(black_box(hex))));
+        group.finish();
+
+        let mut group = c.benchmark_group("encode");
+        group.throughput(Throughput::Bytes(bytes.len() as u64));
+        group.bench_with_input(BenchmarkId::new("hex", &label), &bytes, |b, bytes| b.iter(|| hex::encode(black_box(bytes))));
+        group.bench_with_input(BenchmarkId::new("encode_hex", &label), &bytes, |b, bytes| b.iter(|| encode_hex(black_box(bytes))));
+        group.finish();
+    }
+}
+
+fn bench_block(c: &mut Criterion) {
+    let hex = block_hex(1 << 20);
+    let mut group = c.benchmark_group("block");
+    group.throughput(Throughput::Bytes(hex.len() as u64 / 2));
+    group.sample_size(20);
+    group.bench_function("from_hex", |b| b.iter(|| Block::from_hex(black_box(&hex)).unwrap()));
+    let block = Block::from_hex(&hex).unwrap();
+    group.bench_function("to_json", |b| b.iter(|| serde_json::to_string(black_box(&block.transactions)).unwrap()));
+    group.finish();
+}
+
+criterion_group!(benches, bench_hex, bench_block);
+criterion_main!(benches);
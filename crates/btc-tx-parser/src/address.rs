@@ -1,39 +1,17 @@
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use k256::elliptic_curve::PrimeField;
+use k256::{ProjectivePoint, PublicKey, Scalar};
+use serde::{Deserialize, Serialize};
 use sha2::{Sha256, Digest};
-use ripemd::Ripemd160;
-use crate::script::ScriptType;
-use crate::types::AddressInfo;
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum Network {
-    Mainnet,
-    Testnet,
-}
-
-impl Network {
-    fn p2pkh_version(&self) -> u8 {
-        match self {
-            Network::Mainnet => 0x00,
-            Network::Testnet => 0x6f,
-        }
-    }
-
-    fn p2sh_version(&self) -> u8 {
-        match self {
-            Network::Mainnet => 0x05,
-            Network::Testnet => 0xc4,
-        }
-    }
-
-    fn bech32_hrp(&self) -> &'static str {
-        match self {
-            Network::Mainnet => "bc",
-            Network::Testnet => "tb",
-        }
-    }
-}
+use crate::hash::{hash160, sha256, sha256d};
+use crate::network::Network;
+use crate::provenance::spent_script_type;
+use crate::script::{instructions, revealed_pubkeys, Instruction, ScriptType};
+use crate::types::{AddressInfo, Transaction, TxInput};
 
 // Derived addresses from scriptPubKey for all supported script types
 pub fn derive_address(script: &[u8], script_type: &ScriptType) -> Option<AddressInfo> {
+    trace!(?script_type, len = script.len(), "deriving address");
     match script_type {
         ScriptType::P2PKH => {
             if script.len() >= 23 {
@@ -113,21 +91,114 @@ pub fn derive_address(script: &[u8], script_type: &ScriptType) -> Option<Address
     }
 }
 
-pub fn hash160(data: &[u8]) -> [u8; 20] {
-    let sha256_hash = Sha256::digest(data);
-    let ripemd_hash = Ripemd160::digest(sha256_hash);
-    let mut result = [0u8; 20];
-    result.copy_from_slice(&ripemd_hash);
-    result
+// A public key revealed by the inputs, with every address it can be paid at
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyAddresses {
+    pub pubkey: String,
+    // inputs revealing it
+    pub inputs: Vec<usize>,
+    pub addresses: Vec<AddressInfo>,
 }
 
-// Double SHA256 for txid/wtxid calculation
-pub fn sha256d(data: &[u8]) -> [u8; 32] {
-    let first = Sha256::digest(data);
-    let second = Sha256::digest(first);
-    let mut result = [0u8; 32];
-    result.copy_from_slice(&second);
-    result
+impl TxInput {
+    // Address of the output being spent, rebuilt from the key or script the
+    // input reveals. None for coinbases, P2PK and taproot spends, whose
+    // unlocking data doesn't give the output script away.
+    pub fn spent_address(&self) -> Option<AddressInfo> {
+        let (script_pubkey, script_type) = self.spent_script_pubkey()?;
+        derive_address(&script_pubkey, &script_type)
+    }
+
+    pub(crate) fn spent_script_pubkey(&self) -> Option<(Vec<u8>, ScriptType)> {
+        if self.is_coinbase {
+            return None;
+        }
+        let last_push = || match instructions(&self.script_sig.bytes)?.pop()? {
+            Instruction::Push(data) => Some(data),
+            Instruction::Op(_) => None,
+        };
+        let last_item = || self.witness.as_ref()?.last();
+        let script_type = spent_script_type(self)?;
+        let script_pubkey = match script_type {
+            ScriptType::P2PKH => [&[0x76, 0xa9, 0x14][..], &hash160(last_push()?), &[0x88, 0xac]].concat(),
+            // nested segwit included: the last push is the witness program
+            ScriptType::P2SH => [&[0xa9, 0x14][..], &hash160(last_push()?), &[0x87]].concat(),
+            ScriptType::P2WPKH => [&[0x00, 0x14][..], &hash160(last_item()?)].concat(),
+            ScriptType::P2WSH => [&[0x00, 0x20][..], &sha256(last_item()?)[..]].concat(),
+            _ => return None,
+        };
+        Some((script_pubkey, script_type))
+    }
+}
+
+impl Transaction {
+    // Keys pushed in scriptSigs and witnesses, in order of first appearance
+    pub fn key_addresses(&self) -> Vec<KeyAddresses> {
+        let mut keys: Vec<KeyAddresses> = Vec::new();
+        for input in self.inputs.iter().filter(|i| !i.is_coinbase) {
+            for pubkey in revealed_pubkeys(input) {
+                match keys.iter_mut().find(|k| k.pubkey == pubkey) {
+                    Some(key) if key.inputs.contains(&input.index) => {}
+                    Some(key) => key.inputs.push(input.index),
+                    None => {
+                        let addresses = hex::decode(&pubkey).map(|bytes| pubkey_addresses(&bytes)).unwrap_or_default();
+                        keys.push(KeyAddresses { pubkey, inputs: vec![input.index], addresses });
+                    }
+                }
+            }
+        }
+        keys
+    }
+}
+
+// P2PKH, P2WPKH, P2SH-P2WPKH and P2TR (BIP86 key path) addresses of a key.
+// Segwit forms use the compressed encoding; an uncompressed key also gets
+// its own legacy P2PKH. Empty if the key is not on the curve.
+pub fn pubkey_addresses(pubkey: &[u8]) -> Vec<AddressInfo> {
+    let mut addresses = Vec::new();
+    let Ok(key) = PublicKey::from_sec1_bytes(pubkey) else {
+        return addresses;
+    };
+    if pubkey.len() == 65 {
+        let hash = hash160(pubkey);
+        addresses.push(address_info("P2PKH (uncompressed)", |n| encode_base58check(&hash, n.p2pkh_version())));
+    }
+
+    let compressed = key.to_encoded_point(true);
+    let hash = hash160(compressed.as_bytes());
+    addresses.push(address_info("P2PKH", |n| encode_base58check(&hash, n.p2pkh_version())));
+    addresses.push(address_info("P2WPKH", |n| encode_bech32(&hash, n, 0).unwrap_or_default()));
+    let mut redeem_script = vec![0x00, 0x14];
+    redeem_script.extend_from_slice(&hash);
+    let script_hash = hash160(&redeem_script);
+    addresses.push(address_info("P2SH-P2WPKH", |n| encode_base58check(&script_hash, n.p2sh_version())));
+    if let Some(output_key) = taproot_output_key(&key) {
+        addresses.push(address_info("P2TR", |n| encode_bech32m(&output_key, n).unwrap_or_default()));
+    }
+    addresses
+}
+
+fn address_info(address_type: &str, encode: impl Fn(Network) -> String) -> AddressInfo {
+    AddressInfo {
+        mainnet: encode(Network::Mainnet),
+        testnet: encode(Network::Testnet),
+        address_type: address_type.to_string(),
+    }
+}
+
+// BIP86: the even-Y internal key tweaked by its own TapTweak hash
+fn taproot_output_key(key: &PublicKey) -> Option<[u8; 32]> {
+    let encoded = key.to_encoded_point(true);
+    let (parity, x) = encoded.as_bytes().split_first()?;
+    let mut point = ProjectivePoint::from(*key.as_affine());
+    if *parity == 0x03 {
+        point = -point;
+    }
+    let tag = Sha256::digest(b"TapTweak");
+    let tweak = Sha256::new().chain_update(tag).chain_update(tag).chain_update(x).finalize();
+    let tweak = Option::<Scalar>::from(Scalar::from_repr(tweak))?;
+    let output = (point + ProjectivePoint::GENERATOR * tweak).to_affine().to_encoded_point(true);
+    output.as_bytes().get(1..)?.try_into().ok()
 }
 
 fn encode_base58check(data: &[u8], version: u8) -> String {
@@ -0,0 +1,220 @@
+//! Satoshi amounts with exact BTC formatting and parsing
+
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+use std::str::FromStr;
+use serde::{Deserialize, Serialize, Serializer};
+use crate::error::ParseError;
+
+const SATS_PER_BTC: u64 = 100_000_000;
+
+// Whole satoshis; serialized as the plain integer. Display gives BTC with
+// all eight decimals, e.g. "0.00010000 BTC".
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Amount(u64);
+
+impl Amount {
+    pub const ZERO: Amount = Amount(0);
+    pub const ONE_BTC: Amount = Amount(SATS_PER_BTC);
+    pub const MAX_MONEY: Amount = Amount(21_000_000 * SATS_PER_BTC);
+
+    pub const fn from_sat(sats: u64) -> Self {
+        Amount(sats)
+    }
+
+    pub const fn to_sat(self) -> u64 {
+        self.0
+    }
+
+    // Lossy above 2^53 sats; for display and JavaScript only
+    pub fn to_btc(self) -> f64 {
+        self.0 as f64 / SATS_PER_BTC as f64
+    }
+
+    // "1.50000000", exact
+    pub fn to_btc_string(self) -> String {
+        format!("{}.{:08}", self.0 / SATS_PER_BTC, self.0 % SATS_PER_BTC)
+    }
+
+    // Exact decimal BTC ("0.0001", "21000000"); more than eight decimals is an error
+    pub fn from_btc_str(s: &str) -> Result<Self, ParseError> {
+        let invalid = || ParseError::InvalidAmount(s.to_string());
+        let (whole, fraction) = s.trim().split_once('.').unwrap_or((s.trim(), ""));
+        if whole.is_empty() && fraction.is_empty()
+            || fraction.len() > 8
+            || !whole.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+        let whole: u64 = if whole.is_empty() { 0 } else { whole.parse().map_err(|_| invalid())? };
+        let fraction: u64 = format!("{:0<8}", fraction).parse().map_err(|_| invalid())?;
+        whole.checked_mul(SATS_PER_BTC)
+            .and_then(|sats| sats.checked_add(fraction))
+            .map(Amount)
+            .ok_or_else(invalid)
+    }
+
+    pub fn checked_add(self, other: Amount) -> Option<Amount> {
+        self.0.checked_add(other.0).map(Amount)
+    }
+
+    pub fn checked_sub(self, other: Amount) -> Option<Amount> {
+        self.0.checked_sub(other.0).map(Amount)
+    }
+
+    pub fn saturating_sub(self, other: Amount) -> Amount {
+        Amount(self.0.saturating_sub(other.0))
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} BTC", self.to_btc_string())
+    }
+}
+
+impl FromStr for Amount {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_btc_str(s.trim().trim_end_matches("BTC").trim_end())
+    }
+}
+
+// Saturating: amounts come from untrusted transactions, whose values sum past
+// u64::MAX easily and whose outputs can exceed their inputs. Anything that
+// must notice overflow uses checked_add or checked_sub.
+impl Add for Amount {
+    type Output = Amount;
+
+    fn add(self, other: Amount) -> Amount {
+        Amount(self.0.saturating_add(other.0))
+    }
+}
+
+impl AddAssign for Amount {
+    fn add_assign(&mut self, other: Amount) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Amount {
+    type Output = Amount;
+
+    fn sub(self, other: Amount) -> Amount {
+        Amount(self.0.saturating_sub(other.0))
+    }
+}
+
+impl SubAssign for Amount {
+    fn sub_assign(&mut self, other: Amount) {
+        *self = *self - other;
+    }
+}
+
+impl Sum for Amount {
+    fn sum<I: Iterator<Item = Amount>>(iter: I) -> Amount {
+        iter.fold(Amount::ZERO, Add::add)
+    }
+}
+
+impl<'a> Sum<&'a Amount> for Amount {
+    fn sum<I: Iterator<Item = &'a Amount>>(iter: I) -> Amount {
+        iter.copied().sum()
+    }
+}
+
+// Serializes the wrapped value with its float BTC twins (`value_btc`,
+// `total_output_btc`, `fee_btc`) as exact 8-decimal strings such as
+// "0.00010000", as Bitcoin Core writes amounts, e.g.
+// `serde_json::to_string(&ExactBtc(&tx))`. The value is serialized as usual
+// and each twin then rewritten from the satoshi field beside it, so the
+// strings are exact at any amount, and values nested with `json!` are
+// covered by wrapping the outer one.
+pub struct ExactBtc<T>(pub T);
+
+// Each twin and the satoshi field it mirrors
+#[cfg(feature = "float-btc")]
+const BTC_TWINS: [(&str, &str); 3] = [
+    ("value_btc", "value"),
+    ("total_output_btc", "total_output_satoshis"),
+    ("fee_btc", "fee_satoshis"),
+];
+
+impl<T: Serialize> Serialize for ExactBtc<T> {
+    #[cfg(feature = "float-btc")]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut value = serde_json::to_value(&self.0).map_err(serde::ser::Error::custom)?;
+        exact_twins(&mut value);
+        value.serialize(serializer)
+    }
+
+    #[cfg(not(feature = "float-btc"))]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "float-btc")]
+fn exact_twins(value: &mut serde_json::Value) {
+    use serde_json::Value;
+    match value {
+        Value::Object(fields) => {
+            for (twin, sats) in BTC_TWINS {
+                let sats = fields.get(sats).and_then(Value::as_u64);
+                if let (Some(sats), Some(twin)) = (sats, fields.get_mut(twin)) {
+                    *twin = Value::String(Amount(sats).to_btc_string());
+                }
+            }
+            fields.values_mut().for_each(exact_twins);
+        }
+        Value::Array(items) => items.iter_mut().for_each(exact_twins),
+        _ => {}
+    }
+}
+
+// JSON form of the twins: an f64, or the exact string `ExactBtc` writes.
+// Both forms are read back.
+#[cfg(feature = "float-btc")]
+pub(crate) mod btc_json {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Encoded {
+        Float(f64),
+        Exact(String),
+    }
+
+    pub fn serialize<S: Serializer>(btc: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(*btc)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+        match Encoded::deserialize(deserializer)? {
+            Encoded::Float(btc) => Ok(btc),
+            Encoded::Exact(text) => text.parse().map_err(D::Error::custom),
+        }
+    }
+
+    pub mod option {
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        #[derive(Deserialize)]
+        struct Wrapped(#[serde(with = "super")] f64);
+
+        pub fn serialize<S: Serializer>(btc: &Option<f64>, serializer: S) -> Result<S::Ok, S::Error> {
+            match btc {
+                Some(btc) => super::serialize(btc, serializer),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<f64>, D::Error> {
+            Ok(Option::<Wrapped>::deserialize(deserializer)?.map(|Wrapped(btc)| btc))
+        }
+    }
+}
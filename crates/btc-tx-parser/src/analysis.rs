@@ -0,0 +1,234 @@
+//! Pluggable transaction analyzers
+//!
+//! Each analyzer inspects a parsed transaction and reports findings; the
+//! registry runs the built-in set and accepts analyzers from downstream crates.
+
+use serde::{Deserialize, Serialize};
+use crate::script::opcodes::*;
+use crate::script::{decode_script_num, instructions, revealed_scripts, Instruction};
+use crate::types::Transaction;
+
+// Something an analyzer noticed about the transaction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Finding {
+    // analyzer that produced the finding
+    pub analyzer: String,
+    // stable identifier, e.g. "ctv_template"
+    pub kind: String,
+    // human-readable description
+    pub message: String,
+    // input the finding refers to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input: Option<usize>,
+    // output the finding refers to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<usize>,
+    // 0.0-1.0 for heuristic findings; None for facts read off the transaction
+    // (e.g. a decoded CTV template)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f64>,
+    // observations a heuristic finding rests on, for "why" explanations
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub evidence: Vec<String>,
+}
+
+// Analyzers report facts with `Finding::new`; conclusions drawn from patterns
+// that other transactions could match by chance must go through
+// `Finding::heuristic` with a confidence and the evidence behind it
+pub trait Analyzer: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn analyze(&self, tx: &Transaction) -> Vec<Finding>;
+}
+
+// Ordered set of analyzers run over a transaction
+pub struct AnalyzerRegistry {
+    analyzers: Vec<Box<dyn Analyzer>>,
+    // heuristic findings below this confidence are dropped
+    min_confidence: f64,
+}
+
+impl AnalyzerRegistry {
+    pub fn new() -> Self {
+        Self { analyzers: Vec::new(), min_confidence: 0.0 }
+    }
+
+    // Drop heuristic findings less confident than `min` (0.0-1.0)
+    pub fn with_min_confidence(mut self, min: f64) -> Self {
+        self.min_confidence = min;
+        self
+    }
+
+    // Registry with all built-in analyzers
+    pub fn with_defaults() -> Self {
+        let mut registry = Self::new();
+        registry.register(CovenantAnalyzer);
+        registry.register(crate::inscription::InscriptionAnalyzer);
+        registry.register(crate::lightning::LightningAnalyzer);
+        registry.register(crate::htlc::HtlcAnalyzer);
+        registry.register(crate::bond::BondAnalyzer);
+        registry.register(crate::signature::SignatureAnalyzer);
+        registry.register(crate::batching::BatchingAnalyzer);
+        registry.register(crate::batching::ConsolidationAnalyzer);
+        registry.register(crate::ots::TimestampAnalyzer);
+        registry.register(crate::op_return::OpReturnAnalyzer);
+        registry.register(crate::pubkey::PubkeyAnalyzer);
+        registry
+    }
+
+    pub fn register<A: Analyzer + 'static>(&mut self, analyzer: A) {
+        self.analyzers.push(Box::new(analyzer));
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.analyzers.iter().map(|a| a.name()).collect()
+    }
+
+    pub fn run(&self, tx: &Transaction) -> Vec<Finding> {
+        self.analyzers.iter()
+            .flat_map(|a| a.analyze(tx))
+            .filter(|f| f.confidence.is_none_or(|c| c >= self.min_confidence))
+            .collect()
+    }
+}
+
+impl Default for AnalyzerRegistry {
+    fn default() -> Self {
+        Self::with_defaults()
+    }
+}
+
+impl Transaction {
+    // Run the built-in analyzers
+    pub fn analyze(&self) -> Vec<Finding> {
+        AnalyzerRegistry::with_defaults().run(self)
+    }
+}
+
+impl Finding {
+    pub(crate) fn new(analyzer: &str, kind: &str, message: String) -> Self {
+        Self {
+            analyzer: analyzer.to_string(),
+            kind: kind.to_string(),
+            message,
+            input: None,
+            output: None,
+            confidence: None,
+            evidence: Vec::new(),
+        }
+    }
+
+    pub(crate) fn heuristic(analyzer: &str, kind: &str, message: String, confidence: f64, evidence: Vec<String>) -> Self {
+        Self { confidence: Some(confidence.clamp(0.0, 1.0)), evidence, ..Self::new(analyzer, kind, message) }
+    }
+
+    pub(crate) fn on_input(mut self, index: usize) -> Self {
+        self.input = Some(index);
+        self
+    }
+
+    pub(crate) fn on_output(mut self, index: usize) -> Self {
+        self.output = Some(index);
+        self
+    }
+}
+
+// Covenant-like constructions: CTV templates, BIP-345 vault opcodes and
+// CSV-staged vault scripts
+pub struct CovenantAnalyzer;
+
+impl Analyzer for CovenantAnalyzer {
+    fn name(&self) -> &'static str {
+        "covenant"
+    }
+
+    fn analyze(&self, tx: &Transaction) -> Vec<Finding> {
+        let mut findings = Vec::new();
+
+        for output in &tx.outputs {
+            findings.extend(
+                self.scan(&output.script_pubkey.bytes, "scriptPubKey").into_iter().map(|f| f.on_output(output.index)),
+            );
+        }
+
+        for input in &tx.inputs {
+            for (origin, script) in revealed_scripts(input) {
+                findings.extend(self.scan(&script, origin).into_iter().map(|f| f.on_input(input.index)));
+            }
+        }
+
+        findings
+    }
+}
+
+impl CovenantAnalyzer {
+    fn scan(&self, script: &[u8], origin: &str) -> Vec<Finding> {
+        let ins = match instructions(script) {
+            Some(ins) => ins,
+            None => return Vec::new(),
+        };
+        let mut findings = Vec::new();
+
+        for pair in ins.windows(2) {
+            if let (Instruction::Push(hash), Instruction::Op(OP_CHECKTEMPLATEVERIFY)) = (pair[0], pair[1]) {
+                if hash.len() == 32 {
+                    findings.push(Finding::new(
+                        self.name(),
+                        "ctv_template",
+                        format!("{} commits to a CTV (BIP-119) template {}", origin, hex::encode(hash)),
+                    ));
+                }
+            }
+        }
+
+        if ins.iter().any(|i| matches!(i, Instruction::Op(OP_VAULT) | Instruction::Op(OP_VAULT_RECOVER))) {
+            findings.push(Finding::new(
+                self.name(),
+                "op_vault",
+                format!("{} uses BIP-345 OP_VAULT/OP_VAULT_RECOVER", origin),
+            ));
+        }
+
+        if let Some(delay) = csv_staged_branch(&ins) {
+            // Lightning to_local outputs and other delayed-recovery scripts share the shape
+            findings.push(Finding::heuristic(
+                self.name(),
+                "csv_staged_vault",
+                format!(
+                    "{} has a branch delayed by OP_CHECKSEQUENCEVERIFY ({}) next to an immediate branch (staged vault/recovery pattern)",
+                    origin, delay
+                ),
+                0.5,
+                vec![
+                    format!("{} branches with OP_IF/OP_ELSE", origin),
+                    format!("one branch waits for {}", delay),
+                    "the other branch can spend immediately".to_string(),
+                ],
+            ));
+        }
+
+        findings
+    }
+}
+
+// OP_IF a OP_ELSE b OP_ENDIF where exactly one branch carries a CSV delay
+fn csv_staged_branch(ins: &[Instruction]) -> Option<String> {
+    let open = ins.iter().position(|i| matches!(i, Instruction::Op(OP_IF) | Instruction::Op(OP_NOTIF)))?;
+    let split = open + ins[open..].iter().position(|i| *i == Instruction::Op(OP_ELSE))?;
+    let close = split + ins[split..].iter().position(|i| *i == Instruction::Op(OP_ENDIF))?;
+
+    let delay_in = |branch: &[Instruction]| -> Option<String> {
+        let at = branch.iter().position(|i| *i == Instruction::Op(OP_CHECKSEQUENCEVERIFY))?;
+        Some(match at.checked_sub(1).map(|p| branch[p]) {
+            Some(Instruction::Push(data)) => decode_script_num(data)
+                .map(|n| format!("nSequence {}", n))
+                .unwrap_or_else(|| "unknown delay".to_string()),
+            Some(Instruction::Op(op)) if (OP_1..=OP_16).contains(&op) => format!("nSequence {}", op - OP_1 + 1),
+            _ => "unknown delay".to_string(),
+        })
+    };
+
+    match (delay_in(&ins[open + 1..split]), delay_in(&ins[split + 1..close])) {
+        (Some(delay), None) | (None, Some(delay)) => Some(delay),
+        _ => None,
+    }
+}
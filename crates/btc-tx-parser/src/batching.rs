@@ -0,0 +1,240 @@
+//! Transaction shapes by input and output count, and the two lopsided ones
+//! analysts look for: batched payouts, where exchanges and custodians pay
+//! many withdrawals at once from one or a few inputs, and consolidations,
+//! where a wallet sweeps many coins of one type into one or two outputs
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use crate::analysis::{Analyzer, Finding};
+use crate::provenance::spent_script_type;
+use crate::script::ScriptType;
+use crate::types::Transaction;
+
+// A withdrawal batch spends few inputs...
+pub const MAX_BATCH_INPUTS: usize = 3;
+// ...into dozens of payments
+pub const MIN_BATCH_OUTPUTS: usize = 20;
+// Share of payments that must go to scripts no other output pays
+const MIN_DISTINCT_SHARE: f64 = 0.9;
+// Share of the most common script type for the batch to count as uniform
+const UNIFORM_TYPE_SHARE: f64 = 0.8;
+// A consolidation sweeps at least this many inputs into one or two outputs
+pub const MIN_CONSOLIDATION_INPUTS: usize = 5;
+
+// Input and output counts, and the category they fall in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TxShape {
+    pub inputs: usize,
+    pub outputs: usize,
+    pub category: ShapeCategory,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShapeCategory {
+    Coinbase,
+    // one input, one output: a sweep or a self-transfer
+    OneToOne,
+    // a few inputs paying one or two outputs, e.g. a payment and change
+    Simple,
+    // many inputs into one or two outputs
+    FanIn,
+    // a few inputs into more than two outputs
+    FanOut,
+    ManyToMany,
+}
+
+impl fmt::Display for TxShape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let category = match self.category {
+            ShapeCategory::Coinbase => "coinbase",
+            ShapeCategory::OneToOne => "one-to-one",
+            ShapeCategory::Simple => "simple",
+            ShapeCategory::FanIn => "fan-in",
+            ShapeCategory::FanOut => "fan-out",
+            ShapeCategory::ManyToMany => "many-to-many",
+        };
+        let plural = |n: usize| if n == 1 { "" } else { "s" };
+        write!(f, "{} ({} input{}, {} output{})", category, self.inputs, plural(self.inputs), self.outputs, plural(self.outputs))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Consolidation {
+    pub inputs: usize,
+    pub outputs: usize,
+    // most common type among the spent outputs, read from the unlocking
+    // data, and its share of the inputs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dominant_type: Option<ScriptType>,
+    pub dominant_share: f64,
+    // some output has the dominant input type, i.e. the coins stay in the wallet
+    pub same_type_output: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatchPayout {
+    pub inputs: usize,
+    // outputs that pay someone (OP_RETURN aside)
+    pub payments: usize,
+    // payments to a script no other output pays
+    pub distinct_recipients: usize,
+    // most common script type among the payments, and its share of them
+    pub dominant_type: ScriptType,
+    pub dominant_share: f64,
+}
+
+impl BatchPayout {
+    pub fn uniform(&self) -> bool {
+        self.dominant_share >= UNIFORM_TYPE_SHARE
+    }
+}
+
+impl Transaction {
+    pub fn shape(&self) -> TxShape {
+        let (inputs, outputs) = (self.inputs.len(), self.outputs.len());
+        let category = if self.inputs.iter().any(|i| i.is_coinbase) {
+            ShapeCategory::Coinbase
+        } else if inputs >= MIN_CONSOLIDATION_INPUTS && outputs <= 2 {
+            ShapeCategory::FanIn
+        } else if (inputs, outputs) == (1, 1) {
+            ShapeCategory::OneToOne
+        } else if outputs <= 2 {
+            ShapeCategory::Simple
+        } else if inputs <= MAX_BATCH_INPUTS {
+            ShapeCategory::FanOut
+        } else {
+            ShapeCategory::ManyToMany
+        };
+        TxShape { inputs, outputs, category }
+    }
+
+    // Many inputs swept into one or two outputs
+    pub fn consolidation(&self) -> Option<Consolidation> {
+        if self.shape().category != ShapeCategory::FanIn {
+            return None;
+        }
+        let mut types: BTreeMap<ScriptType, usize> = BTreeMap::new();
+        for input in &self.inputs {
+            if let Some(script_type) = spent_script_type(input) {
+                *types.entry(script_type).or_default() += 1;
+            }
+        }
+        let dominant = types.into_iter().rev().max_by_key(|&(_, count)| count);
+        let same_type_output = dominant.as_ref()
+            .is_some_and(|(script_type, _)| self.outputs.iter().any(|o| &o.script_type == script_type));
+        Some(Consolidation {
+            inputs: self.inputs.len(),
+            outputs: self.outputs.len(),
+            dominant_share: dominant.as_ref().map_or(0.0, |&(_, count)| count as f64 / self.inputs.len() as f64),
+            dominant_type: dominant.map(|(script_type, _)| script_type),
+            same_type_output,
+        })
+    }
+
+    // The batch shape, if the transaction has it; coinbases never do
+    pub fn batch_payout(&self) -> Option<BatchPayout> {
+        if self.inputs.iter().any(|i| i.is_coinbase) || self.inputs.len() > MAX_BATCH_INPUTS {
+            return None;
+        }
+        let payments: Vec<_> = self.outputs.iter().filter(|o| o.script_type != ScriptType::OpReturn).collect();
+        if payments.len() < MIN_BATCH_OUTPUTS {
+            return None;
+        }
+
+        let mut uses: HashMap<&[u8], usize> = HashMap::new();
+        for output in &payments {
+            *uses.entry(&output.script_pubkey.bytes).or_default() += 1;
+        }
+        let distinct_recipients = payments.iter().filter(|o| uses[o.script_pubkey.bytes.as_slice()] == 1).count();
+        if (distinct_recipients as f64) < payments.len() as f64 * MIN_DISTINCT_SHARE {
+            return None;
+        }
+
+        let mut types: BTreeMap<&ScriptType, usize> = BTreeMap::new();
+        for output in &payments {
+            *types.entry(&output.script_type).or_default() += 1;
+        }
+        // ties go to the first type in declaration order
+        let (dominant_type, count) = types.into_iter().rev().max_by_key(|&(_, count)| count)?;
+        Some(BatchPayout {
+            inputs: self.inputs.len(),
+            payments: payments.len(),
+            distinct_recipients,
+            dominant_type: dominant_type.clone(),
+            dominant_share: count as f64 / payments.len() as f64,
+        })
+    }
+}
+
+// Labels batched withdrawals; ordinary wallets rarely pay dozens of
+// distinct recipients from a couple of coins
+pub struct BatchingAnalyzer;
+
+impl Analyzer for BatchingAnalyzer {
+    fn name(&self) -> &'static str {
+        "batching"
+    }
+
+    fn analyze(&self, tx: &Transaction) -> Vec<Finding> {
+        let Some(batch) = tx.batch_payout() else {
+            return Vec::new();
+        };
+        let mut evidence = vec![
+            format!("{} input(s) fund {} payments", batch.inputs, batch.payments),
+            format!("{} of the payments go to distinct scripts", batch.distinct_recipients),
+            format!("{:.0}% of the payments are {}", batch.dominant_share * 100.0, batch.dominant_type),
+        ];
+        let mut confidence = 0.6;
+        if batch.uniform() {
+            confidence += 0.2;
+        }
+        if batch.payments >= 2 * MIN_BATCH_OUTPUTS {
+            confidence += 0.1;
+            evidence.push(format!("{} or more payments", 2 * MIN_BATCH_OUTPUTS));
+        }
+        vec![Finding::heuristic(
+            self.name(),
+            "batched_withdrawal",
+            format!("batched withdrawal: {} payments from {} input(s), as exchanges send them", batch.payments, batch.inputs),
+            confidence,
+            evidence,
+        )]
+    }
+}
+
+// Labels consolidations; coinjoins and payments rarely spend this many
+// inputs into one or two outputs
+pub struct ConsolidationAnalyzer;
+
+impl Analyzer for ConsolidationAnalyzer {
+    fn name(&self) -> &'static str {
+        "consolidation"
+    }
+
+    fn analyze(&self, tx: &Transaction) -> Vec<Finding> {
+        let Some(consolidation) = tx.consolidation() else {
+            return Vec::new();
+        };
+        let mut evidence = vec![format!("{} inputs into {} output(s)", consolidation.inputs, consolidation.outputs)];
+        let mut confidence = 0.6;
+        if let Some(script_type) = &consolidation.dominant_type {
+            evidence.push(format!("{:.0}% of the inputs spend {}", consolidation.dominant_share * 100.0, script_type));
+            if consolidation.dominant_share >= UNIFORM_TYPE_SHARE {
+                confidence += 0.15;
+            }
+            if consolidation.same_type_output {
+                evidence.push(format!("an output is {} too", script_type));
+                confidence += 0.15;
+            }
+        }
+        vec![Finding::heuristic(
+            self.name(),
+            "consolidation",
+            format!("consolidation: {} inputs swept into {} output(s)", consolidation.inputs, consolidation.outputs),
+            confidence,
+            evidence,
+        )]
+    }
+}
@@ -0,0 +1,272 @@
+//! Full blocks: 80-byte header followed by the transactions
+
+use serde::{Deserialize, Serialize};
+use crate::hash::sha256d;
+use crate::error::ParseError;
+use crate::hexcodec::decode_hex;
+use crate::network::Network;
+use crate::merkle::{merkle_proof, merkle_root, MerkleProof};
+use crate::parser::Parser;
+use crate::coinbase::bip34_height;
+use crate::types::Transaction;
+
+const HEADER_SIZE: usize = 80;
+
+// OP_RETURN, push 36, then the BIP141 commitment header
+const WITNESS_COMMITMENT_PREFIX: &str = "6a24aa21a9ed";
+
+// Compact bits of the difficulty-1 target
+const MAX_TARGET_BITS: u32 = 0x1d00ffff;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockHeader {
+    pub version: i32,
+    // display order
+    pub prev_block_hash: String,
+    // display order
+    pub merkle_root: String,
+    // unix timestamp
+    pub time: u32,
+    // compact difficulty target
+    pub bits: u32,
+    pub nonce: u32,
+    // block hash (display order)
+    pub hash: String,
+    // target expanded from `bits`, as a 256-bit big-endian number (hex)
+    pub target: String,
+    // target of difficulty 1 divided by this target
+    pub difficulty: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Block {
+    pub header: BlockHeader,
+    pub transactions: Vec<Transaction>,
+    // serialized size in bytes
+    pub size: usize,
+    pub weight: usize,
+    // merkle root recomputed from the txids matches the header
+    pub merkle_root_valid: bool,
+    // BIP141 witness commitment from the coinbase (hex, as committed)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub witness_commitment: Option<String>,
+    // commitment matches the wtxid tree; None for blocks with neither a
+    // commitment nor witness data
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub witness_commitment_valid: Option<bool>,
+}
+
+impl BlockHeader {
+    pub fn from_hex(hex_str: &str) -> Result<Self, ParseError> {
+        Self::from_bytes(&decode_hex(hex_str.trim())?)
+    }
+
+    // Exactly 80 bytes
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+        if bytes.len() != HEADER_SIZE {
+            return Err(ParseError::InvalidBlock(format!("header is {} bytes, expected {}", bytes.len(), HEADER_SIZE)));
+        }
+        parse_header(&mut Parser::new(bytes))
+    }
+
+    // The 80 serialized bytes the hash is computed over
+    pub fn to_bytes(&self) -> Result<Vec<u8>, ParseError> {
+        let hash_bytes = |hash: &str| -> Result<Vec<u8>, ParseError> {
+            let mut bytes = hex::decode(hash)?;
+            bytes.reverse();
+            Ok(bytes)
+        };
+        let mut bytes = Vec::with_capacity(HEADER_SIZE);
+        bytes.extend_from_slice(&self.version.to_le_bytes());
+        bytes.extend(hash_bytes(&self.prev_block_hash)?);
+        bytes.extend(hash_bytes(&self.merkle_root)?);
+        bytes.extend_from_slice(&self.time.to_le_bytes());
+        bytes.extend_from_slice(&self.bits.to_le_bytes());
+        bytes.extend_from_slice(&self.nonce.to_le_bytes());
+        Ok(bytes)
+    }
+
+    // The block hash, read as a number, must not exceed the target
+    pub fn meets_target(&self) -> bool {
+        self.hash <= self.target
+    }
+
+    // Network this header is the genesis block of, if any
+    pub fn genesis_of(&self) -> Option<Network> {
+        Network::ALL.into_iter().find(|network| network.genesis_hash() == self.hash)
+    }
+}
+
+// Expand compact bits (base-256 exponent byte, 23-bit mantissa) into a
+// 32-byte big-endian target
+pub fn bits_to_target(bits: u32) -> Result<[u8; 32], ParseError> {
+    let exponent = (bits >> 24) as usize;
+    let mantissa = bits & 0x007f_ffff;
+    if bits & 0x0080_0000 != 0 && mantissa != 0 {
+        return Err(ParseError::InvalidBlock(format!("bits {:08x} encode a negative target", bits)));
+    }
+
+    let mut target = [0u8; 32];
+    if exponent <= 3 {
+        let value = mantissa >> (8 * (3 - exponent));
+        target[29..].copy_from_slice(&value.to_be_bytes()[1..]);
+        return Ok(target);
+    }
+    // mantissa byte i lands at index 32 - exponent + i of the big-endian target
+    for (i, byte) in mantissa.to_be_bytes()[1..].iter().enumerate() {
+        match (32 + i).checked_sub(exponent) {
+            Some(index) => target[index] = *byte,
+            None if *byte == 0 => {}
+            None => return Err(ParseError::InvalidBlock(format!("bits {:08x} overflow a 256-bit target", bits))),
+        }
+    }
+    Ok(target)
+}
+
+// Difficulty relative to the difficulty-1 target (as reported by bitcoind)
+pub fn bits_to_difficulty(bits: u32) -> f64 {
+    let as_float = |bits: u32| (bits & 0x007f_ffff) as f64 * 256f64.powi((bits >> 24) as i32 - 3);
+    as_float(MAX_TARGET_BITS) / as_float(bits)
+}
+
+impl Block {
+    pub fn from_hex(hex_str: &str) -> Result<Self, ParseError> {
+        Self::from_bytes(&decode_hex(hex_str.trim())?)
+    }
+
+    // Header, transaction count and every transaction; bytes left over are an error
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+        let mut parser = Parser::new(bytes);
+        let header = parse_header(&mut parser)?;
+
+        let tx_count = parser.read_varint()?;
+        if tx_count == 0 {
+            return Err(ParseError::InvalidBlock("block has no transactions".to_string()));
+        }
+        debug!(hash = %header.hash, tx_count, "parsing block");
+
+        // every transaction takes at least 60 bytes, so a bogus count cannot
+        // make us reserve gigabytes
+        let mut transactions = Vec::with_capacity((tx_count as usize).min(parser.remaining() / 60));
+        let mut tx_weight = 0;
+        for i in 0..tx_count {
+            let tx = parser.parse_transaction()
+                .map_err(|e| ParseError::InvalidBlock(format!("transaction #{}: {}", i, e)))?;
+            tx_weight += tx.weight;
+            transactions.push(tx);
+        }
+        if parser.remaining() > 0 {
+            return Err(ParseError::TrailingData(parser.remaining()));
+        }
+
+        let overhead = HEADER_SIZE + Parser::varint_size(tx_count);
+        let mut block = Block {
+            header,
+            transactions,
+            size: bytes.len(),
+            weight: overhead * 4 + tx_weight,
+            merkle_root_valid: false,
+            witness_commitment: None,
+            witness_commitment_valid: None,
+        };
+        block.merkle_root_valid = block.compute_merkle_root()? == block.header.merkle_root;
+        block.witness_commitment = block.find_witness_commitment();
+        block.witness_commitment_valid = match &block.witness_commitment {
+            Some(committed) => Some(block.compute_witness_commitment()?.as_ref() == Some(committed)),
+            // witness data must be committed to
+            None if block.transactions.iter().any(|tx| tx.is_segwit) => Some(false),
+            None => None,
+        };
+        Ok(block)
+    }
+
+    // BIP34 height, the first push of the coinbase scriptSig; None for
+    // version 1 blocks, which don't commit to one
+    pub fn height(&self) -> Option<u32> {
+        if self.header.version < 2 {
+            return None;
+        }
+        let coinbase = self.transactions.first()?.inputs.first().filter(|input| input.is_coinbase)?;
+        bip34_height(&coinbase.script_sig.bytes)
+    }
+
+    // Merkle root over the transactions' txids (display order), as the
+    // header should commit to it
+    pub fn compute_merkle_root(&self) -> Result<String, ParseError> {
+        Ok(merkle_root(&self.txids())?.unwrap_or_default())
+    }
+
+    // Proof that `txid` is in this block; None if it is not
+    pub fn merkle_proof(&self, txid: &str) -> Result<Option<MerkleProof>, ParseError> {
+        let txids = self.txids();
+        match txids.iter().position(|id| id == txid) {
+            Some(index) => merkle_proof(&txids, index),
+            None => Ok(None),
+        }
+    }
+
+    // Merkle root over the wtxids (display order), with the coinbase's
+    // wtxid taken as all zeros
+    pub fn compute_witness_root(&self) -> Result<String, ParseError> {
+        let wtxids: Vec<String> = self.transactions.iter().enumerate()
+            .map(|(i, tx)| if i == 0 { "0".repeat(64) } else { tx.wtxid.clone() })
+            .collect();
+        Ok(merkle_root(&wtxids)?.unwrap_or_default())
+    }
+
+    // sha256d(witness root || witness reserved value), as the coinbase should
+    // commit to it; None if the coinbase has no 32-byte reserved value
+    pub fn compute_witness_commitment(&self) -> Result<Option<String>, ParseError> {
+        let reserved = self.transactions.first()
+            .and_then(|coinbase| coinbase.inputs.first())
+            .and_then(|input| input.witness.as_ref())
+            .filter(|witness| witness.len() == 1 && witness[0].len() == 32)
+            .map(|witness| witness[0].clone());
+        let Some(reserved) = reserved else {
+            return Ok(None);
+        };
+        let mut root = hex::decode(self.compute_witness_root()?)?;
+        root.reverse();
+        Ok(Some(hex::encode(sha256d(&[root, reserved].concat()))))
+    }
+
+    // Commitment in the coinbase's last output carrying the BIP141 header
+    fn find_witness_commitment(&self) -> Option<String> {
+        self.transactions.first()?.outputs.iter().rev()
+            .map(|output| hex::encode(&output.script_pubkey.bytes))
+            .find(|script| script.len() >= 76 && script.starts_with(WITNESS_COMMITMENT_PREFIX))
+            .map(|script| script[WITNESS_COMMITMENT_PREFIX.len()..76].to_string())
+    }
+
+    fn txids(&self) -> Vec<String> {
+        self.transactions.iter().map(|tx| tx.txid.clone()).collect()
+    }
+
+    pub fn vsize(&self) -> usize {
+        self.weight.div_ceil(4)
+    }
+}
+
+pub(crate) fn parse_header(parser: &mut Parser) -> Result<BlockHeader, ParseError> {
+    let raw = parser.read_bytes(HEADER_SIZE)?;
+    let mut fields = Parser::new(&raw);
+    let mut hash = sha256d(&raw);
+    hash.reverse();
+
+    let version = fields.read_u32_le()? as i32;
+    let prev_block_hash = fields.read_hash()?;
+    let merkle_root = fields.read_hash()?;
+    let time = fields.read_u32_le()?;
+    let bits = fields.read_u32_le()?;
+    Ok(BlockHeader {
+        version,
+        prev_block_hash,
+        merkle_root,
+        time,
+        bits,
+        nonce: fields.read_u32_le()?,
+        hash: hex::encode(hash),
+        target: hex::encode(bits_to_target(bits)?),
+        difficulty: bits_to_difficulty(bits),
+    })
+}
@@ -0,0 +1,297 @@
+//! Bitcoin Core block storage: `blocks/blk*.dat` files of
+//! `<magic> <u32 length> <block>` records, optionally XOR-obfuscated with the
+//! key in `blocks/xor.dat`. Long scans can checkpoint their place and resume.
+
+use std::fs::File;
+use std::io::{BufReader, ErrorKind, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+use crate::block::Block;
+use crate::error::ParseError;
+#[cfg(feature = "mmap")]
+use crate::mmap::{MappedBlockReader, MappedFile};
+
+// Message start bytes that prefix every record
+pub const MAINNET_MAGIC: [u8; 4] = [0xf9, 0xbe, 0xb4, 0xd9];
+pub const TESTNET_MAGIC: [u8; 4] = [0x0b, 0x11, 0x09, 0x07];
+pub const TESTNET4_MAGIC: [u8; 4] = [0x1c, 0x16, 0x3f, 0x28];
+pub const SIGNET_MAGIC: [u8; 4] = [0x0a, 0x03, 0xcf, 0x40];
+pub const REGTEST_MAGIC: [u8; 4] = [0xfa, 0xbf, 0xb5, 0xda];
+
+// A block can serialize to at most 4 MB (all of its weight as witness data),
+// so a larger length means the framing is corrupt
+pub(crate) const MAX_BLOCK_SIZE: usize = 4_000_000;
+
+// Blocks from one blk*.dat stream, parsed one at a time. Stops at the end of
+// the data or at the zero padding Core preallocates; stops after an error.
+pub struct BlockFileReader<R> {
+    reader: R,
+    magic: [u8; 4],
+    xor_key: Option<[u8; 8]>,
+    // bytes consumed so far, which also indexes the XOR key
+    position: u64,
+    done: bool,
+}
+
+impl<R: Read> BlockFileReader<R> {
+    pub fn new(reader: R, magic: [u8; 4]) -> Self {
+        BlockFileReader { reader, magic, xor_key: None, position: 0, done: false }
+    }
+
+    // Undo the obfuscation of Core 28+ (an all-zero key is a no-op)
+    pub fn with_xor_key(mut self, key: [u8; 8]) -> Self {
+        self.xor_key = Some(key).filter(|key| key.iter().any(|b| *b != 0));
+        self
+    }
+
+    // File offset of the next record
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    fn next_block(&mut self) -> Result<Option<Block>, ParseError> {
+        let start = self.position;
+        let mut magic = [0u8; 4];
+        if !self.read_exact_or_eof(&mut magic)? || magic == [0; 4] {
+            return Ok(None);
+        }
+        if magic != self.magic {
+            return Err(ParseError::InvalidBlock(format!(
+                "unexpected magic {} at offset {}, expected {}", hex::encode(magic), start, hex::encode(self.magic)
+            )));
+        }
+
+        let mut length = [0u8; 4];
+        self.read_record(&mut length)?;
+        let length = u32::from_le_bytes(length) as usize;
+        if length > MAX_BLOCK_SIZE {
+            return Err(ParseError::InvalidBlock(format!("record at offset {} claims {} bytes", start, length)));
+        }
+
+        let mut bytes = vec![0u8; length];
+        self.read_record(&mut bytes)?;
+        debug!(offset = start, length, "read block record");
+        Block::from_bytes(&bytes).map(Some)
+    }
+
+    // Fill `buf`; false if the stream ended before its first byte
+    fn read_exact_or_eof(&mut self, buf: &mut [u8]) -> Result<bool, ParseError> {
+        let mut filled = 0;
+        while filled < buf.len() {
+            match self.reader.read(&mut buf[filled..]) {
+                Ok(0) if filled == 0 => return Ok(false),
+                Ok(0) => {
+                    return Err(ParseError::UnexpectedEof {
+                        position: (self.position + filled as u64) as usize,
+                        expected: buf.len() - filled,
+                    })
+                }
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        if let Some(key) = self.xor_key {
+            for (i, byte) in buf.iter_mut().enumerate() {
+                *byte ^= key[((self.position + i as u64) % 8) as usize];
+            }
+        }
+        self.position += buf.len() as u64;
+        Ok(true)
+    }
+
+    // Like `read_exact_or_eof`, inside a record where the end is an error
+    fn read_record(&mut self, buf: &mut [u8]) -> Result<(), ParseError> {
+        if self.read_exact_or_eof(buf)? {
+            Ok(())
+        } else {
+            Err(ParseError::UnexpectedEof { position: self.position as usize, expected: buf.len() })
+        }
+    }
+}
+
+impl<R: Read> Iterator for BlockFileReader<R> {
+    type Item = Result<Block, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let next = self.next_block().transpose();
+        if !matches!(next, Some(Ok(_))) {
+            self.done = true;
+        }
+        next
+    }
+}
+
+// Where a scan of block files stopped: the record after the last block read
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockFileCheckpoint {
+    // file name (e.g. "blk00042.dat") and offset of the next record
+    pub file: String,
+    pub offset: u64,
+    // blocks read before this point, over every run
+    pub blocks: u64,
+    // BIP34 height of the last block read, to show how far the scan got
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_height: Option<u32>,
+}
+
+// Blocks from every blk*.dat file of a blocks directory (or a single file),
+// in file order, opening each file only when the previous one is exhausted
+pub struct BlockFiles {
+    files: std::vec::IntoIter<PathBuf>,
+    magic: [u8; 4],
+    xor_key: [u8; 8],
+    #[cfg(feature = "mmap")]
+    mapped: bool,
+    current: Option<(PathBuf, Source)>,
+    blocks: u64,
+    last_height: Option<u32>,
+}
+
+// The open file: read through a buffer, or mapped
+enum Source {
+    Stream(BlockFileReader<BufReader<File>>),
+    #[cfg(feature = "mmap")]
+    Mapped(MappedBlockReader),
+}
+
+impl Source {
+    fn position(&self) -> u64 {
+        match self {
+            Source::Stream(reader) => reader.position(),
+            #[cfg(feature = "mmap")]
+            Source::Mapped(reader) => reader.position,
+        }
+    }
+
+    fn next(&mut self) -> Option<Result<Block, ParseError>> {
+        match self {
+            Source::Stream(reader) => reader.next(),
+            #[cfg(feature = "mmap")]
+            Source::Mapped(reader) => reader.next(),
+        }
+    }
+}
+
+impl BlockFiles {
+    // `path` is a blocks directory or one blk*.dat file; xor.dat next to the
+    // files is picked up automatically
+    pub fn open(path: &Path, magic: [u8; 4]) -> Result<Self, ParseError> {
+        let (dir, files) = if path.is_dir() {
+            (path.to_path_buf(), block_file_paths(path)?)
+        } else {
+            (path.parent().unwrap_or(Path::new(".")).to_path_buf(), vec![path.to_path_buf()])
+        };
+        let xor_key = match std::fs::read(dir.join("xor.dat")) {
+            Ok(bytes) => bytes.try_into()
+                .map_err(|_| ParseError::InvalidBlock("xor.dat is not 8 bytes".to_string()))?,
+            Err(e) if e.kind() == ErrorKind::NotFound => [0; 8],
+            Err(e) => return Err(e.into()),
+        };
+        Ok(BlockFiles {
+            files: files.into_iter(),
+            magic,
+            xor_key,
+            #[cfg(feature = "mmap")]
+            mapped: false,
+            current: None,
+            blocks: 0,
+            last_height: None,
+        })
+    }
+
+    // Like `open`, but each file is memory-mapped and its blocks parsed in place
+    #[cfg(feature = "mmap")]
+    pub fn open_mapped(path: &Path, magic: [u8; 4]) -> Result<Self, ParseError> {
+        Ok(BlockFiles { mapped: true, ..Self::open(path, magic)? })
+    }
+
+    // Open `path` for reading from `offset`
+    fn open_source(&self, path: &Path, offset: u64) -> Result<Source, ParseError> {
+        #[cfg(feature = "mmap")]
+        if self.mapped {
+            let mut reader = MappedBlockReader::new(MappedFile::open(path)?, self.magic, self.xor_key);
+            reader.position = offset;
+            return Ok(Source::Mapped(reader));
+        }
+        let mut file = File::open(path)?;
+        file.seek(SeekFrom::Start(offset))?;
+        let mut reader = BlockFileReader::new(BufReader::new(file), self.magic).with_xor_key(self.xor_key);
+        reader.position = offset;
+        Ok(Source::Stream(reader))
+    }
+
+    // Continue from a checkpoint: earlier files are skipped and the named
+    // one is read from the recorded offset
+    pub fn resume(mut self, checkpoint: &BlockFileCheckpoint) -> Result<Self, ParseError> {
+        let path = self.files.find(|path| path.file_name().is_some_and(|name| *name == *checkpoint.file))
+            .ok_or_else(|| ParseError::InvalidBlock(format!("checkpoint file {} is not among the block files", checkpoint.file)))?;
+        let source = self.open_source(&path, checkpoint.offset)?;
+        self.current = Some((path, source));
+        self.blocks = checkpoint.blocks;
+        self.last_height = checkpoint.last_height;
+        Ok(self)
+    }
+
+    // The place after the most recent block; None before the first file is opened
+    pub fn checkpoint(&self) -> Option<BlockFileCheckpoint> {
+        let (path, reader) = self.current.as_ref()?;
+        Some(BlockFileCheckpoint {
+            file: path.file_name()?.to_string_lossy().into_owned(),
+            offset: reader.position(),
+            blocks: self.blocks,
+            last_height: self.last_height,
+        })
+    }
+
+    // File the most recent block (or error) came from
+    pub fn current_file(&self) -> Option<&Path> {
+        self.current.as_ref().map(|(path, _)| path.as_path())
+    }
+}
+
+impl Iterator for BlockFiles {
+    type Item = Result<Block, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((_, reader)) = &mut self.current {
+                if let Some(next) = reader.next() {
+                    if let Ok(block) = &next {
+                        self.blocks += 1;
+                        self.last_height = block.height().or(self.last_height);
+                    }
+                    return Some(next);
+                }
+            }
+            let path = self.files.next()?;
+            let source = match self.open_source(&path, 0) {
+                Ok(source) => source,
+                Err(e) => {
+                    self.current = None;
+                    return Some(Err(e));
+                }
+            };
+            self.current = Some((path, source));
+        }
+    }
+}
+
+// blk00000.dat, blk00001.dat, ... in numeric order
+pub fn block_file_paths(dir: &Path) -> Result<Vec<PathBuf>, ParseError> {
+    let mut files: Vec<(u32, PathBuf)> = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let number = path.file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix("blk")?.strip_suffix(".dat")?.parse().ok());
+        if let Some(number) = number {
+            files.push((number, path));
+        }
+    }
+    files.sort();
+    Ok(files.into_iter().map(|(_, path)| path).collect())
+}
@@ -0,0 +1,180 @@
+//! Fidelity bonds (Joinmarket's timelocked P2WSH outputs) and other long
+//! timelocks, with the height or date they unlock at
+
+use serde::{Deserialize, Serialize};
+use crate::hash::sha256;
+use crate::analysis::{Analyzer, Finding};
+use crate::script::opcodes::*;
+use crate::script::{decode_script_num, instructions, revealed_scripts, Instruction, ScriptType};
+use crate::timelock::{LockKind, LockUnit, LOCKTIME_THRESHOLD};
+use crate::types::Transaction;
+
+// Locks at least this long are reported as long timelocks
+const LONG_LOCK_BLOCKS: u64 = 52_560;
+const LONG_LOCK_SECS: u64 = 365 * 24 * 60 * 60;
+
+// <locktime> OP_CHECKLOCKTIMEVERIFY OP_DROP <pubkey> OP_CHECKSIG
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FidelityBond {
+    // input spending the bond (the script is revealed)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input: Option<usize>,
+    // output creating the bond (matched against a supplied script)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<usize>,
+    pub pubkey: String,
+    // block height or unix time the coins unlock at
+    pub locktime: u64,
+    pub unit: LockUnit,
+    // Joinmarket only locks until midnight UTC on the first of a month
+    pub joinmarket: bool,
+}
+
+impl Transaction {
+    // Bonds spent by this transaction
+    pub fn fidelity_bonds(&self) -> Vec<FidelityBond> {
+        self.fidelity_bonds_with_scripts(&[])
+    }
+
+    // Like `fidelity_bonds`, also matching P2WSH outputs against the given
+    // witness scripts (e.g. from the wallet that created the bond)
+    pub fn fidelity_bonds_with_scripts(&self, witness_scripts: &[Vec<u8>]) -> Vec<FidelityBond> {
+        let mut bonds = Vec::new();
+        for output in self.outputs.iter().filter(|o| o.script_type == ScriptType::P2WSH) {
+            let program = output.script_pubkey.bytes.get(2..).unwrap_or_default();
+            let script = witness_scripts.iter().find(|s| sha256(s).as_slice() == program);
+            if let Some(mut bond) = script.and_then(|s| parse_bond(s)) {
+                bond.output = Some(output.index);
+                bonds.push(bond);
+            }
+        }
+        for input in &self.inputs {
+            for (origin, script) in revealed_scripts(input) {
+                if let Some(mut bond) = parse_bond(&script).filter(|_| origin == "witness script") {
+                    bond.input = Some(input.index);
+                    bonds.push(bond);
+                }
+            }
+        }
+        bonds
+    }
+}
+
+fn parse_bond(script: &[u8]) -> Option<FidelityBond> {
+    let ins = instructions(script)?;
+    let (locktime, pubkey) = match ins.as_slice() {
+        [Instruction::Push(locktime), Instruction::Op(OP_CHECKLOCKTIMEVERIFY), Instruction::Op(OP_DROP),
+            Instruction::Push(pubkey), Instruction::Op(OP_CHECKSIG)] if pubkey.len() == 33 => (locktime, pubkey),
+        _ => return None,
+    };
+    let locktime = u64::try_from(decode_script_num(locktime)?).ok()?;
+    let unit = if locktime < LOCKTIME_THRESHOLD { LockUnit::Blocks } else { LockUnit::Seconds };
+    let joinmarket = unit == LockUnit::Seconds && locktime.is_multiple_of(86_400) && civil_date(locktime).2 == 1;
+    Some(FidelityBond { input: None, output: None, pubkey: hex::encode(pubkey), locktime, unit, joinmarket })
+}
+
+// Height, or the UTC date for time locks
+fn describe_lock(value: u64, unit: LockUnit) -> String {
+    match unit {
+        LockUnit::Blocks => format!("block {}", value),
+        LockUnit::Seconds => {
+            let (year, month, day) = civil_date(value);
+            format!("{:04}-{:02}-{:02}", year, month, day)
+        }
+    }
+}
+
+// (year, month, day) of a unix time (Howard Hinnant's civil_from_days)
+fn civil_date(time: u64) -> (i64, u64, u64) {
+    let days = (time / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097) as u64;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era as i64 + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+// Fidelity bonds, and CLTV/CSV locks of a year or more: relative locks in
+// revealed scripts, and bare output scripts locked a year past the
+// transaction's own nLockTime (timelocked burns)
+pub struct BondAnalyzer;
+
+impl Analyzer for BondAnalyzer {
+    fn name(&self) -> &'static str {
+        "bond"
+    }
+
+    fn analyze(&self, tx: &Transaction) -> Vec<Finding> {
+        let mut findings: Vec<Finding> = tx.fidelity_bonds().into_iter()
+            .filter_map(|bond| {
+                let kind = if bond.joinmarket { "Joinmarket fidelity bond" } else { "fidelity bond" };
+                let message = format!("spends a {} of {} locked until {}", kind, bond.pubkey, describe_lock(bond.locktime, bond.unit));
+                let mut evidence = vec!["witness script is <locktime> OP_CHECKLOCKTIMEVERIFY OP_DROP <pubkey> OP_CHECKSIG".to_string()];
+                if bond.joinmarket {
+                    evidence.push("locked until midnight UTC on the first of a month".to_string());
+                }
+                // the bare template is also a plain timelocked wallet output
+                let confidence = if bond.joinmarket { 0.9 } else { 0.6 };
+                Some(Finding::heuristic(self.name(), "fidelity_bond", message, confidence, evidence).on_input(bond.input?))
+            })
+            .collect();
+
+        for lock in tx.timelocks() {
+            let long = match lock.unit {
+                LockUnit::Blocks => lock.value >= LONG_LOCK_BLOCKS,
+                LockUnit::Seconds => lock.value >= LONG_LOCK_SECS,
+            };
+            if lock.kind == LockKind::Relative && lock.source != "nSequence" && long {
+                let finding = Finding::new(
+                    self.name(),
+                    "long_timelock",
+                    format!("{} delays spending by {} {}", lock.source, lock.value,
+                        if lock.unit == LockUnit::Blocks { "blocks" } else { "seconds" }),
+                );
+                findings.push(match lock.input {
+                    Some(input) => finding.on_input(input),
+                    None => finding,
+                });
+            }
+        }
+
+        let reference = tx.locktime.to_consensus_u32() as u64;
+        for output in &tx.outputs {
+            let Some(locktime) = bare_cltv(&output.script_pubkey.bytes) else {
+                continue;
+            };
+            let unit = if locktime < LOCKTIME_THRESHOLD { LockUnit::Blocks } else { LockUnit::Seconds };
+            // without a chain tip, the transaction's nLockTime stands in for "now"
+            let long = reference != 0 && (reference < LOCKTIME_THRESHOLD) == (unit == LockUnit::Blocks) && match unit {
+                LockUnit::Blocks => locktime >= reference + LONG_LOCK_BLOCKS,
+                LockUnit::Seconds => locktime >= reference + LONG_LOCK_SECS,
+            };
+            if long {
+                findings.push(Finding::heuristic(
+                    self.name(),
+                    "long_timelock",
+                    format!("output script can't be spent before {} (timelocked burn or long-term lock)", describe_lock(locktime, unit)),
+                    0.6,
+                    vec![
+                        format!("bare output script with OP_CHECKLOCKTIMEVERIFY {}", locktime),
+                        format!("over a year past this transaction's nLockTime {}", reference),
+                    ],
+                ).on_output(output.index));
+            }
+        }
+
+        findings
+    }
+}
+
+// CLTV value in a bare output script
+fn bare_cltv(script: &[u8]) -> Option<u64> {
+    instructions(script)?.windows(2).find_map(|pair| match pair {
+        [Instruction::Push(data), Instruction::Op(OP_CHECKLOCKTIMEVERIFY)] => u64::try_from(decode_script_num(data)?).ok(),
+        _ => None,
+    })
+}
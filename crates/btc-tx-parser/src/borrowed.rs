@@ -0,0 +1,180 @@
+//! Zero-copy parse mode: scripts, witness items and hashes are slices of the
+//! input buffer, so walking a large block allocates no hex strings. Convert
+//! to the owned `Transaction` for addresses, ASM and analysis.
+
+use crate::hash::{sha256, Sha256Engine};
+use crate::block::{parse_header, Block, BlockHeader};
+use crate::error::ParseError;
+use crate::parser::Parser;
+use crate::types::Transaction;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionRef<'a> {
+    pub version: i32,
+    pub is_segwit: bool,
+    pub inputs: Vec<TxInputRef<'a>>,
+    pub outputs: Vec<TxOutputRef<'a>>,
+    pub locktime: u32,
+    // the whole serialization
+    pub raw: &'a [u8],
+    // offset of the witness data within `raw` (the locktime's without witnesses)
+    witness_start: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxInputRef<'a> {
+    // previous txid in internal byte order (reversed for display)
+    pub prev_txid: &'a [u8],
+    pub vout: u32,
+    pub script_sig: &'a [u8],
+    pub sequence: u32,
+    // empty for legacy transactions
+    pub witness: Vec<&'a [u8]>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TxOutputRef<'a> {
+    pub value: u64,
+    pub script_pubkey: &'a [u8],
+}
+
+// Block whose transactions borrow from the block bytes
+#[derive(Debug, Clone)]
+pub struct BlockRef<'a> {
+    pub header: BlockHeader,
+    pub transactions: Vec<TransactionRef<'a>>,
+    pub raw: &'a [u8],
+}
+
+impl<'a> TransactionRef<'a> {
+    // Like `Transaction::from_bytes`, bytes after the locktime are ignored
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<Self, ParseError> {
+        Self::parse(&mut Parser::new(bytes))
+    }
+
+    pub fn from_bytes_strict(bytes: &'a [u8]) -> Result<Self, ParseError> {
+        let mut parser = Parser::new(bytes);
+        let tx = Self::parse(&mut parser)?;
+        match parser.remaining() {
+            0 => Ok(tx),
+            trailing => Err(ParseError::TrailingData(trailing)),
+        }
+    }
+
+    pub(crate) fn parse(parser: &mut Parser<'a>) -> Result<Self, ParseError> {
+        let start = parser.position();
+        let version = parser.read_u32_le()? as i32;
+        let (is_segwit, _) = parser.check_segwit()?;
+
+        let input_count = parser.read_varint()?;
+        if input_count == 0 && !is_segwit {
+            return Err(ParseError::InvalidTransaction("Transaction has no inputs".to_string()));
+        }
+        // an input takes at least 41 bytes, so a bogus count can't reserve much
+        let mut inputs = Vec::with_capacity((input_count as usize).min(parser.remaining() / 41));
+        for _ in 0..input_count {
+            inputs.push(TxInputRef {
+                prev_txid: parser.read_slice(32)?,
+                vout: parser.read_u32_le()?,
+                script_sig: read_sized(parser)?,
+                sequence: parser.read_u32_le()?,
+                witness: Vec::new(),
+            });
+        }
+
+        let output_count = parser.read_varint()?;
+        if output_count == 0 {
+            return Err(ParseError::InvalidTransaction("Transaction has no outputs".to_string()));
+        }
+        let mut outputs = Vec::with_capacity((output_count as usize).min(parser.remaining() / 9));
+        for _ in 0..output_count {
+            outputs.push(TxOutputRef { value: parser.read_u64_le()?, script_pubkey: read_sized(parser)? });
+        }
+
+        let witness_start = parser.position() - start;
+        if is_segwit {
+            for input in &mut inputs {
+                let items = parser.read_varint()?;
+                for _ in 0..items {
+                    input.witness.push(read_sized(parser)?);
+                }
+            }
+        }
+        let locktime = parser.read_u32_le()?;
+        let raw = parser.slice_from(start);
+
+        Ok(TransactionRef { version, is_segwit, inputs, outputs, locktime, raw, witness_start })
+    }
+
+    // sha256d of the serialization without witness data, in internal byte order
+    pub fn txid(&self) -> [u8; 32] {
+        if !self.is_segwit {
+            return sha256d(&[self.raw]);
+        }
+        // skip the marker and flag, and the witnesses before the locktime
+        sha256d(&[&self.raw[..4], &self.raw[6..self.witness_start], &self.raw[self.raw.len() - 4..]])
+    }
+
+    // sha256d of the full serialization, in internal byte order
+    pub fn wtxid(&self) -> [u8; 32] {
+        sha256d(&[self.raw])
+    }
+
+    pub fn size(&self) -> usize {
+        self.raw.len()
+    }
+
+    pub fn weight(&self) -> usize {
+        if !self.is_segwit {
+            return self.raw.len() * 4;
+        }
+        let witness_size = self.raw.len() - 4 - self.witness_start;
+        let base_size = self.raw.len() - 2 - witness_size;
+        base_size * 3 + self.raw.len()
+    }
+
+    // Owned transaction with addresses, ASM and hex fields
+    pub fn to_transaction(&self) -> Result<Transaction, ParseError> {
+        Transaction::from_bytes(self.raw)
+    }
+}
+
+impl<'a> BlockRef<'a> {
+    pub fn from_bytes(bytes: &'a [u8]) -> Result<Self, ParseError> {
+        let mut parser = Parser::new(bytes);
+        let header = parse_header(&mut parser)?;
+        let tx_count = parser.read_varint()?;
+        if tx_count == 0 {
+            return Err(ParseError::InvalidBlock("block has no transactions".to_string()));
+        }
+        let mut transactions = Vec::with_capacity((tx_count as usize).min(parser.remaining() / 60));
+        for i in 0..tx_count {
+            transactions.push(TransactionRef::parse(&mut parser)
+                .map_err(|e| ParseError::InvalidBlock(format!("transaction #{}: {}", i, e)))?);
+        }
+        if parser.remaining() > 0 {
+            return Err(ParseError::TrailingData(parser.remaining()));
+        }
+        Ok(BlockRef { header, transactions, raw: bytes })
+    }
+
+    // Owned block with validated merkle root and owned transactions
+    pub fn to_block(&self) -> Result<Block, ParseError> {
+        Block::from_bytes(self.raw)
+    }
+}
+
+// Varint length followed by that many bytes
+fn read_sized<'a>(parser: &mut Parser<'a>) -> Result<&'a [u8], ParseError> {
+    let len = parser.read_varint()?;
+    parser.read_slice(usize::try_from(len).unwrap_or(usize::MAX))
+}
+
+// sha256d over the concatenation of `parts`
+fn sha256d(parts: &[&[u8]]) -> [u8; 32] {
+    let mut engine = Sha256Engine::default();
+    for part in parts {
+        engine.update(part);
+    }
+    sha256(&engine.finish())
+}
@@ -0,0 +1,180 @@
+//! Offline `.txbundle` format: a transaction plus everything needed to re-render it
+
+use std::fmt;
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+use crate::error::ParseError;
+use crate::amount::Amount;
+use crate::types::{Transaction, Txid};
+
+pub const BUNDLE_FORMAT: &str = "txbundle";
+pub const BUNDLE_VERSION: u32 = 1;
+
+// Saved analysis bundle
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TxBundle {
+    // format marker ("txbundle")
+    pub format: String,
+    // format version
+    pub version: u32,
+    // raw transaction hex
+    pub tx_hex: String,
+    // one entry per input, in input order
+    pub prevouts: Vec<BundlePrevout>,
+    // free-form labels attached to the transaction
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    // analyst notes on inputs and outputs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<BundleNote>,
+    // where prevout data came from, if it was fetched
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fetched: Option<FetchStatus>,
+}
+
+// Previous output spent by an input
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundlePrevout {
+    // previous txid
+    pub txid: Txid,
+    // previous output index
+    pub vout: u32,
+    // value in satoshis, if known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<Amount>,
+}
+
+// Which side of the transaction a note is attached to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NoteTarget {
+    Input,
+    Output,
+}
+
+impl fmt::Display for NoteTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            NoteTarget::Input => "input",
+            NoteTarget::Output => "output",
+        })
+    }
+}
+
+impl FromStr for NoteTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "input" => Ok(NoteTarget::Input),
+            "output" => Ok(NoteTarget::Output),
+            other => Err(format!("Unknown note target '{}', expected input or output", other)),
+        }
+    }
+}
+
+// Free-text note on one input or output
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleNote {
+    pub target: NoteTarget,
+    // input or output index
+    pub index: usize,
+    pub text: String,
+}
+
+// Provenance of fetched data
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FetchStatus {
+    // backend the data was fetched from
+    pub source: String,
+    // fetch time (RFC 3339)
+    pub fetched_at: String,
+}
+
+impl TxBundle {
+    // Bundle a parsed transaction; input values become the prevout values
+    pub fn new(tx_hex: &str, tx: &Transaction) -> Self {
+        let prevouts = tx.inputs.iter()
+            .map(|input| BundlePrevout {
+                txid: input.txid,
+                vout: input.vout,
+                value: input.value,
+            })
+            .collect();
+
+        Self {
+            format: BUNDLE_FORMAT.to_string(),
+            version: BUNDLE_VERSION,
+            tx_hex: tx_hex.trim().to_string(),
+            prevouts,
+            labels: Vec::new(),
+            notes: Vec::new(),
+            fetched: None,
+        }
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, ParseError> {
+        let bundle: TxBundle = serde_json::from_str(json)
+            .map_err(|e| ParseError::InvalidBundle(e.to_string()))?;
+
+        if bundle.format != BUNDLE_FORMAT {
+            return Err(ParseError::InvalidBundle(format!("unknown format '{}'", bundle.format)));
+        }
+        if bundle.version > BUNDLE_VERSION {
+            return Err(ParseError::InvalidBundle(format!("unsupported version {}", bundle.version)));
+        }
+        Ok(bundle)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("bundle serialization cannot fail")
+    }
+
+    // Attach a note, replacing any earlier note on the same input or output
+    pub fn set_note(&mut self, target: NoteTarget, index: usize, text: &str) {
+        self.notes.retain(|n| (n.target, n.index) != (target, index));
+        self.notes.push(BundleNote { target, index, text: text.to_string() });
+        self.notes.sort_by_key(|n| (n.target == NoteTarget::Output, n.index));
+    }
+
+    // Re-parse the transaction and restore input values, fee and notes
+    pub fn transaction(&self) -> Result<Transaction, ParseError> {
+        let mut tx = Transaction::from_hex(&self.tx_hex)?;
+
+        if self.prevouts.len() != tx.inputs.len() {
+            return Err(ParseError::InvalidBundle(format!(
+                "{} prevouts for {} inputs",
+                self.prevouts.len(),
+                tx.inputs.len()
+            )));
+        }
+
+        for (input, prevout) in tx.inputs.iter_mut().zip(&self.prevouts) {
+            if input.txid != prevout.txid || input.vout != prevout.vout {
+                return Err(ParseError::InvalidBundle(format!(
+                    "prevout {}:{} does not match input #{}",
+                    prevout.txid, prevout.vout, input.index
+                )));
+            }
+            input.value = prevout.value;
+        }
+
+        for note in &self.notes {
+            let slot = match note.target {
+                NoteTarget::Input => tx.inputs.get_mut(note.index).map(|i| &mut i.note),
+                NoteTarget::Output => tx.outputs.get_mut(note.index).map(|o| &mut o.note),
+            };
+            let Some(slot) = slot else {
+                return Err(ParseError::InvalidBundle(format!(
+                    "note on missing {} #{}",
+                    note.target, note.index
+                )));
+            };
+            *slot = Some(note.text.clone());
+        }
+
+        let fee = tx.calculate_fee();
+        tx.set_fee(fee);
+        Ok(tx)
+    }
+}
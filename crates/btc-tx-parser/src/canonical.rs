@@ -0,0 +1,67 @@
+//! Canonical serialization checks: minimal varints, no trailing data, and a
+//! witness marker only when some input actually has witness data
+
+use serde::{Deserialize, Serialize};
+use crate::diff::{byte_diff, ByteDiff};
+use crate::error::ParseError;
+use crate::parser::Parser;
+use crate::serialize::serialize;
+use crate::spans::field_spans;
+use crate::types::Transaction;
+
+// Outcome of re-serializing a transaction canonically
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CanonicalReport {
+    pub canonical: bool,
+    // human-readable reasons the input is not canonical
+    pub issues: Vec<String>,
+    // bytes left after the locktime
+    pub trailing_bytes: usize,
+    // canonical serialization, when it differs from the input
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub canonical_hex: Option<String>,
+    // field-level changes between the input and its canonical form
+    pub diff: ByteDiff,
+}
+
+pub fn check_canonical(bytes: &[u8]) -> Result<CanonicalReport, ParseError> {
+    let tx = Transaction::from_bytes(bytes)?;
+    let provided = &bytes[..tx.raw_size];
+    let mut issues = Vec::new();
+
+    let trailing_bytes = bytes.len() - tx.raw_size;
+    if trailing_bytes > 0 {
+        issues.push(format!("{} bytes of trailing data after the locktime", trailing_bytes));
+    }
+
+    for span in field_spans(provided)? {
+        if !(span.field.ends_with("_count") || span.field.ends_with("_len")) {
+            continue;
+        }
+        let value = Parser::new(&provided[span.start..span.end]).read_varint()?;
+        let minimal = Parser::varint_size(value);
+        if span.end - span.start != minimal {
+            issues.push(format!(
+                "{} encodes {} in {} bytes instead of {}",
+                span.field, value, span.end - span.start, minimal
+            ));
+        }
+    }
+
+    let has_witness = tx.inputs.iter().any(|i| i.witness.as_ref().is_some_and(|w| !w.is_empty()));
+    if tx.is_segwit && !has_witness {
+        issues.push("witness marker and flag present but every witness is empty".to_string());
+    }
+
+    let canonical = serialize(&tx, has_witness);
+    let diff = byte_diff(bytes, &canonical)?;
+    let is_canonical = issues.is_empty() && canonical == bytes;
+
+    Ok(CanonicalReport {
+        canonical: is_canonical,
+        issues,
+        trailing_bytes,
+        canonical_hex: (!is_canonical).then(|| hex::encode(&canonical)),
+        diff,
+    })
+}
@@ -0,0 +1,76 @@
+//! Coin age: how long each spent output sat unspent, and the value-days
+//! it destroys. Ages are counted in blocks between the prevout's
+//! confirmation and the spend, at 144 blocks to the day.
+
+use serde::{Deserialize, Serialize};
+use crate::amount::Amount;
+use crate::types::Transaction;
+
+pub const BLOCKS_PER_DAY: f64 = 144.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputCoinAge {
+    pub index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<Amount>,
+    // height the spent output was confirmed at
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confirmed_height: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub age_blocks: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub age_days: Option<f64>,
+    // value in BTC times age in days
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value_days_destroyed: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoinAge {
+    // height the transaction is (or would be) confirmed at
+    pub spend_height: u32,
+    pub inputs: Vec<InputCoinAge>,
+    // sum over inputs; only when every input's value and height are known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value_days_destroyed: Option<f64>,
+    // oldest input with a known height, in days
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_age_days: Option<f64>,
+}
+
+impl Transaction {
+    // `confirmed_heights` holds one entry per input, in order; inputs
+    // without an entry, and coinbase inputs, get no age. A prevout
+    // confirmed after `spend_height` counts as age zero.
+    pub fn coin_age(&self, spend_height: u32, confirmed_heights: &[Option<u32>]) -> CoinAge {
+        let inputs: Vec<InputCoinAge> = self.inputs.iter()
+            .enumerate()
+            .map(|(i, input)| {
+                let confirmed_height = confirmed_heights.get(i).copied().flatten().filter(|_| !input.is_coinbase);
+                let age_blocks = confirmed_height.map(|height| spend_height.saturating_sub(height));
+                let age_days = age_blocks.map(|blocks| blocks as f64 / BLOCKS_PER_DAY);
+                InputCoinAge {
+                    index: input.index,
+                    value: input.value,
+                    confirmed_height,
+                    age_blocks,
+                    age_days,
+                    value_days_destroyed: input.value.zip(age_days).map(|(value, days)| value.to_btc() * days),
+                }
+            })
+            .collect();
+
+        let value_days_destroyed = inputs.iter()
+            .zip(&self.inputs)
+            .filter(|(_, input)| !input.is_coinbase)
+            .map(|(age, _)| age.value_days_destroyed)
+            .sum();
+        let max_age_days = inputs.iter().filter_map(|input| input.age_days).reduce(f64::max);
+        CoinAge {
+            spend_height,
+            inputs,
+            value_days_destroyed,
+            max_age_days,
+        }
+    }
+}
@@ -0,0 +1,92 @@
+//! Coinbase scriptSigs: the BIP-34 block height, the pool's ASCII tag and
+//! the extranonce miners roll. Only the height is a consensus rule; the rest
+//! of the scriptSig is free-form, so tag and extranonce are read by the
+//! conventions pools follow.
+
+use serde::{Deserialize, Serialize};
+use crate::script::{decode_script_num, instructions, Instruction};
+use crate::script::opcodes::{OP_1, OP_16};
+use crate::types::TxInput;
+
+// Shorter printable runs turn up by chance in extranonces
+const MIN_TAG_LEN: usize = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoinbaseInfo {
+    // BIP-34 height, from blocks 227,931 (mainnet) on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    // longest printable ASCII run after the height, e.g. "/ViaBTC/Mined by x/"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pool_tag: Option<String>,
+    // size of the first binary push after the height, where Stratum pools
+    // put extranonce1 + extranonce2
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extranonce_size: Option<usize>,
+}
+
+impl TxInput {
+    pub fn coinbase_info(&self) -> Option<CoinbaseInfo> {
+        if !self.is_coinbase {
+            return None;
+        }
+        let script = &self.script_sig.bytes;
+        let height = bip34_height(script);
+        // the tag and extranonce come after the height push
+        let rest = match (height, script.first()) {
+            (Some(_), Some(&len @ 0x01..=0x03)) => &script[1 + len as usize..],
+            (Some(_), _) => &script[1..],
+            (None, _) => &script[..],
+        };
+        let pool_tag = printable_runs(rest)
+            .into_iter()
+            .filter(|run| run.len() >= MIN_TAG_LEN)
+            .max_by_key(|run| run.len())
+            .map(|run| String::from_utf8_lossy(&run).trim().to_string());
+        // before BIP-34 the first push was usually nBits, not a height
+        let extranonce_size = height.and_then(|_| instructions(rest)).and_then(|ins| {
+            ins.iter().find_map(|ins| match *ins {
+                Instruction::Push(data) if !data.is_empty() && !data.iter().all(is_printable) => Some(data.len()),
+                _ => None,
+            })
+        });
+        Some(CoinbaseInfo { height, pool_tag, extranonce_size })
+    }
+}
+
+// Printable stretches of the pushed data, with push opcodes left out so
+// they don't run into the text; raw bytes when the script doesn't parse
+fn printable_runs(script: &[u8]) -> Vec<Vec<u8>> {
+    let data = match instructions(script) {
+        Some(ins) => ins.iter().fold(Vec::new(), |mut data, ins| {
+            match *ins {
+                // a separator, so adjacent pushes don't merge
+                Instruction::Push(push) => {
+                    data.extend_from_slice(push);
+                    data.push(0);
+                }
+                Instruction::Op(op) => data.push(op),
+            }
+            data
+        }),
+        None => script.to_vec(),
+    };
+    data.split(|b| !is_printable(b)).map(<[u8]>::to_vec).collect()
+}
+
+fn is_printable(b: &u8) -> bool {
+    (0x20..=0x7e).contains(b)
+}
+
+// The first push as a script number, or OP_1..OP_16 for the first blocks
+// of a regtest chain. Pushes past 3 bytes are not taken: heights need them
+// only from block 8,388,608, and early coinbases pushed nBits (4 bytes) there.
+pub(crate) fn bip34_height(script: &[u8]) -> Option<u32> {
+    // only the first push is read; the free-form bytes after it needn't parse
+    let height = match *script.first()? {
+        op @ OP_1..=OP_16 => (op - OP_1 + 1) as i64,
+        len @ 0x01..=0x03 => decode_script_num(script.get(1..1 + len as usize)?)?,
+        _ => return None,
+    };
+    u32::try_from(height).ok()
+}
@@ -0,0 +1,180 @@
+//! BIP-152 compact blocks (`cmpctblock` payloads): header, nonce, 6-byte
+//! short transaction ids and prefilled transactions
+
+use serde::{Deserialize, Serialize};
+use crate::hash::sha256;
+use crate::block::{parse_header, BlockHeader};
+use crate::error::ParseError;
+use crate::parser::Parser;
+use crate::types::Transaction;
+
+const SHORT_ID_SIZE: usize = 6;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompactBlock {
+    pub header: BlockHeader,
+    // salts the short ids
+    pub nonce: u64,
+    // 48-bit SipHash of each non-prefilled transaction's wtxid (txid in version 1)
+    pub short_ids: Vec<u64>,
+    pub prefilled: Vec<PrefilledTransaction>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PrefilledTransaction {
+    // absolute position in the block (decoded from the differential encoding)
+    pub index: usize,
+    pub transaction: Transaction,
+}
+
+// Block rebuilt from a compact block and a pool of candidate transactions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Reconstruction {
+    // block order; None where no candidate matched
+    pub transactions: Vec<Option<Transaction>>,
+    // positions still to request with getblocktxn
+    pub missing: Vec<usize>,
+    // positions matched by more than one candidate (left unfilled)
+    pub collisions: Vec<usize>,
+}
+
+impl CompactBlock {
+    pub fn from_hex(hex_str: &str) -> Result<Self, ParseError> {
+        Self::from_bytes(&hex::decode(hex_str.trim())?)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+        let mut parser = Parser::new(bytes);
+        let header = parse_header(&mut parser)?;
+        let nonce = parser.read_u64_le()?;
+
+        let count = parser.read_varint()? as usize;
+        if count > parser.remaining() / SHORT_ID_SIZE {
+            return Err(ParseError::InvalidBlock(format!("{} short ids do not fit in the payload", count)));
+        }
+        let mut short_ids = Vec::with_capacity(count);
+        for _ in 0..count {
+            let mut id = [0u8; 8];
+            id[..SHORT_ID_SIZE].copy_from_slice(&parser.read_bytes(SHORT_ID_SIZE)?);
+            short_ids.push(u64::from_le_bytes(id));
+        }
+
+        let count = parser.read_varint()?;
+        let mut prefilled = Vec::new();
+        let mut next_index = 0u64;
+        for _ in 0..count {
+            // each index is stored as the gap since the previous one
+            let index = next_index.checked_add(parser.read_varint()?)
+                .filter(|index| *index <= u16::MAX as u64)
+                .ok_or_else(|| ParseError::InvalidBlock("prefilled transaction index overflows".to_string()))?;
+            let transaction = parser.parse_transaction()?;
+            prefilled.push(PrefilledTransaction { index: index as usize, transaction });
+            next_index = index + 1;
+        }
+        if parser.remaining() > 0 {
+            return Err(ParseError::TrailingData(parser.remaining()));
+        }
+        debug!(hash = %header.hash, short_ids = short_ids.len(), prefilled = prefilled.len(), "parsed compact block");
+
+        Ok(CompactBlock { header, nonce, short_ids, prefilled })
+    }
+
+    pub fn transaction_count(&self) -> usize {
+        self.short_ids.len() + self.prefilled.len()
+    }
+
+    // SipHash keys: the first two little-endian words of sha256(header || nonce)
+    pub fn short_id_keys(&self) -> Result<(u64, u64), ParseError> {
+        let mut preimage = self.header.to_bytes()?;
+        preimage.extend_from_slice(&self.nonce.to_le_bytes());
+        let hash = sha256(&preimage);
+        let word = |i: usize| u64::from_le_bytes(hash[i * 8..i * 8 + 8].try_into().unwrap_or_default());
+        Ok((word(0), word(1)))
+    }
+
+    // Short id of a txid or wtxid (display order, as in `Transaction`)
+    pub fn short_id(&self, hash: &str) -> Result<u64, ParseError> {
+        let (k0, k1) = self.short_id_keys()?;
+        let mut bytes = hex::decode(hash)?;
+        bytes.reverse();
+        Ok(siphash24(k0, k1, &bytes) & 0xffff_ffff_ffff)
+    }
+
+    // Fill the block from prefilled transactions and `pool` (e.g. a mempool
+    // capture), matching short ids on wtxids, or txids when `use_txid` (version 1)
+    pub fn reconstruct(&self, pool: &[Transaction], use_txid: bool) -> Result<Reconstruction, ParseError> {
+        let mut transactions: Vec<Option<Transaction>> = vec![None; self.transaction_count()];
+        for prefilled in &self.prefilled {
+            let slot = transactions.get_mut(prefilled.index)
+                .ok_or_else(|| ParseError::InvalidBlock(format!("prefilled index {} is past the end of the block", prefilled.index)))?;
+            *slot = Some(prefilled.transaction.clone());
+        }
+
+        // short ids fill the remaining positions in order
+        let positions: Vec<usize> = (0..transactions.len()).filter(|i| transactions[*i].is_none()).collect();
+        let mut candidates: Vec<Vec<&Transaction>> = vec![Vec::new(); positions.len()];
+        for tx in pool {
+            let id = self.short_id(if use_txid { &tx.txid } else { &tx.wtxid })?;
+            for (slot, short_id) in self.short_ids.iter().enumerate() {
+                if *short_id == id {
+                    candidates[slot].push(tx);
+                }
+            }
+        }
+
+        let (mut missing, mut collisions) = (Vec::new(), Vec::new());
+        for (position, matches) in positions.into_iter().zip(candidates) {
+            match matches.as_slice() {
+                [tx] => transactions[position] = Some((*tx).clone()),
+                [] => missing.push(position),
+                _ => collisions.push(position),
+            }
+        }
+        Ok(Reconstruction { transactions, missing, collisions })
+    }
+}
+
+// SipHash-2-4 over `data` with the 128-bit key (k0, k1)
+pub(crate) fn siphash24(k0: u64, k1: u64, data: &[u8]) -> u64 {
+    let mut v = [
+        0x736f6d6570736575 ^ k0,
+        0x646f72616e646f6d ^ k1,
+        0x6c7967656e657261 ^ k0,
+        0x7465646279746573 ^ k1,
+    ];
+    let round = |v: &mut [u64; 4]| {
+        v[0] = v[0].wrapping_add(v[1]);
+        v[1] = v[1].rotate_left(13) ^ v[0];
+        v[0] = v[0].rotate_left(32);
+        v[2] = v[2].wrapping_add(v[3]);
+        v[3] = v[3].rotate_left(16) ^ v[2];
+        v[0] = v[0].wrapping_add(v[3]);
+        v[3] = v[3].rotate_left(21) ^ v[0];
+        v[2] = v[2].wrapping_add(v[1]);
+        v[1] = v[1].rotate_left(17) ^ v[2];
+        v[2] = v[2].rotate_left(32);
+    };
+
+    let compress = |v: &mut [u64; 4], word: u64| {
+        v[3] ^= word;
+        round(v);
+        round(v);
+        v[0] ^= word;
+    };
+    let chunks = data.chunks_exact(8);
+    let tail = chunks.remainder();
+    for chunk in chunks {
+        compress(&mut v, u64::from_le_bytes(chunk.try_into().unwrap_or_default()));
+    }
+    // the last word carries the leftover bytes and the length's low byte
+    let mut last = [0u8; 8];
+    last[..tail.len()].copy_from_slice(tail);
+    last[7] = data.len() as u8;
+    compress(&mut v, u64::from_le_bytes(last));
+
+    v[2] ^= 0xff;
+    for _ in 0..4 {
+        round(&mut v);
+    }
+    v[0] ^ v[1] ^ v[2] ^ v[3]
+}
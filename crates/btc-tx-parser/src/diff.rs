@@ -0,0 +1,97 @@
+//! Byte-level comparison of two serializations, aligned by field
+
+use serde::{Deserialize, Serialize};
+use crate::error::ParseError;
+use crate::spans::{field_spans, FieldSpan};
+
+// Result of comparing two raw transactions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ByteDiff {
+    pub a_size: usize,
+    pub b_size: usize,
+    // fields whose bytes differ, in serialization order
+    pub changes: Vec<FieldChange>,
+}
+
+// One field that differs between the two serializations
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldChange {
+    // e.g. "inputs[0].script_sig"
+    pub field: String,
+    // bytes in the first transaction (absent if the field only exists in the second)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub a: Option<FieldBytes>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub b: Option<FieldBytes>,
+    // offsets within the field that differ, when both sides have the same length
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub differing_offsets: Vec<usize>,
+}
+
+// Raw bytes of a field and where they sit in the transaction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FieldBytes {
+    pub offset: usize,
+    pub hex: String,
+}
+
+impl ByteDiff {
+    pub fn is_identical(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+// Compare two raw transactions field by field, so a length change in one
+// field (e.g. a 71 vs 72 byte signature) does not misalign the rest
+pub fn byte_diff(a: &[u8], b: &[u8]) -> Result<ByteDiff, ParseError> {
+    let a_spans = field_spans(a)?;
+    let b_spans = field_spans(b)?;
+    let mut changes = Vec::new();
+
+    for span in &a_spans {
+        let a_bytes = &a[span.start..span.end];
+        match b_spans.iter().find(|s| s.field == span.field) {
+            Some(other) => {
+                let b_bytes = &b[other.start..other.end];
+                if a_bytes == b_bytes {
+                    continue;
+                }
+                let differing_offsets = if a_bytes.len() == b_bytes.len() {
+                    (0..a_bytes.len()).filter(|&i| a_bytes[i] != b_bytes[i]).collect()
+                } else {
+                    Vec::new()
+                };
+                changes.push(FieldChange {
+                    field: span.field.clone(),
+                    a: Some(field_bytes(a, span)),
+                    b: Some(field_bytes(b, other)),
+                    differing_offsets,
+                });
+            }
+            None => changes.push(FieldChange {
+                field: span.field.clone(),
+                a: Some(field_bytes(a, span)),
+                b: None,
+                differing_offsets: Vec::new(),
+            }),
+        }
+    }
+
+    for span in b_spans.iter().filter(|s| !a_spans.iter().any(|o| o.field == s.field)) {
+        changes.push(FieldChange {
+            field: span.field.clone(),
+            a: None,
+            b: Some(field_bytes(b, span)),
+            differing_offsets: Vec::new(),
+        });
+    }
+
+    Ok(ByteDiff { a_size: a.len(), b_size: b.len(), changes })
+}
+
+fn field_bytes(data: &[u8], span: &FieldSpan) -> FieldBytes {
+    FieldBytes {
+        offset: span.start,
+        hex: hex::encode(&data[span.start..span.end]),
+    }
+}
@@ -0,0 +1,312 @@
+//! Elements/Liquid transactions (`elements` feature): outputs carry an asset
+//! next to the value, either of which may be a confidential commitment, and
+//! inputs may issue assets or peg coins in from the main chain. Nothing is
+//! unblinded and no proof is checked; what isn't decoded is listed in
+//! `unsupported` instead of failing the parse.
+
+use serde::{Deserialize, Serialize};
+use crate::hash::sha256d;
+use crate::amount::Amount;
+use crate::error::ParseError;
+use crate::locktime::LockTime;
+use crate::parser::Parser;
+use crate::script::{detect_script_type, script_to_asm, ScriptType};
+use crate::sequence::Sequence;
+use crate::types::Script;
+
+// Flags in the high bits of an outpoint's index
+const ISSUANCE_FLAG: u32 = 1 << 31;
+const PEGIN_FLAG: u32 = 1 << 30;
+const INDEX_MASK: u32 = 0x3fff_ffff;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ElementsTransaction {
+    pub version: i32,
+    pub inputs: Vec<ElementsInput>,
+    pub outputs: Vec<ElementsOutput>,
+    pub locktime: LockTime,
+    // txid (hex); the wtxid is a merkle root over the witnesses and isn't computed
+    pub txid: String,
+    // raw size in bytes
+    pub size: usize,
+    pub weight: usize,
+    // explicit values of the fee outputs, in the fee asset (L-BTC on Liquid)
+    pub fee: Amount,
+    // fields shown raw or left out, e.g. "outputs[0].value: confidential ..."
+    pub unsupported: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ElementsInput {
+    pub index: usize,
+    // previous txid (display order); a mainchain txid for peg-ins
+    pub txid: String,
+    // output index with the issuance and peg-in flags cleared
+    pub vout: u32,
+    pub is_coinbase: bool,
+    pub is_pegin: bool,
+    pub script_sig: Script,
+    pub sequence: Sequence,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub issuance: Option<AssetIssuance>,
+    // witness stack (hex)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub witness: Vec<String>,
+    // peg-in proof: value, asset, genesis hash, claim script, mainchain
+    // transaction and merkle proof (hex, as pushed)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pegin_witness: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AssetIssuance {
+    // zero for a new asset; the asset's blinding nonce for a reissuance
+    pub blinding_nonce: String,
+    // contract hash for a new asset; the asset entropy for a reissuance
+    pub entropy: String,
+    pub is_reissuance: bool,
+    pub amount: ConfidentialValue,
+    pub inflation_keys: ConfidentialValue,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ElementsOutput {
+    pub index: usize,
+    pub asset: ConfidentialAsset,
+    pub value: ConfidentialValue,
+    // commitment to the receiver's ECDH nonce (hex), for blinded outputs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+    pub script_pubkey: Script,
+    pub script_type: ScriptType,
+    // an empty scriptPubKey pays the fee
+    pub is_fee: bool,
+    // witness proofs, by size only
+    pub surjection_proof_size: usize,
+    pub range_proof_size: usize,
+}
+
+// "null", {"explicit": 1000} or {"confidential": "08..."} (a Pedersen commitment)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfidentialValue {
+    Null,
+    Explicit(Amount),
+    Confidential(String),
+}
+
+// "null", {"explicit": "<asset id>"} (display order) or {"confidential": "0a..."}
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConfidentialAsset {
+    Null,
+    Explicit(String),
+    Confidential(String),
+}
+
+impl ElementsTransaction {
+    pub fn from_hex(hex_str: &str) -> Result<Self, ParseError> {
+        Self::from_bytes(&hex::decode(hex_str.trim())?)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+        let mut parser = Parser::new(bytes);
+        let version = parser.read_u32_le()? as i32;
+        // unlike Bitcoin, the flags byte is always present
+        let flags = parser.read_u8()?;
+        if flags > 1 {
+            return Err(ParseError::InvalidTransaction(format!("unknown Elements flags {:#04x}", flags)));
+        }
+
+        let body_start = parser.position();
+        let input_count = parser.read_varint()? as usize;
+        let mut inputs = (0..input_count)
+            .map(|index| parse_input(&mut parser, index))
+            .collect::<Result<Vec<_>, _>>()?;
+        let output_count = parser.read_varint()? as usize;
+        let mut outputs = (0..output_count)
+            .map(|index| parse_output(&mut parser, index))
+            .collect::<Result<Vec<_>, _>>()?;
+        let locktime = LockTime::from_consensus(parser.read_u32_le()?);
+        // the txid covers everything but the witness, with the flags zeroed
+        let stripped = [&bytes[..4], &[0], parser.slice_from(body_start)].concat();
+
+        let mut unsupported = Vec::new();
+        if flags == 1 {
+            for input in &mut inputs {
+                let issuance_proofs = read_sized(&mut parser)?.len() + read_sized(&mut parser)?.len();
+                if issuance_proofs > 0 {
+                    unsupported.push(format!("inputs[{}].issuance: range proofs ({} bytes) not verified", input.index, issuance_proofs));
+                }
+                input.witness = read_stack(&mut parser)?;
+                input.pegin_witness = read_stack(&mut parser)?;
+            }
+            for output in &mut outputs {
+                output.surjection_proof_size = read_sized(&mut parser)?.len();
+                output.range_proof_size = read_sized(&mut parser)?.len();
+            }
+        }
+        if parser.remaining() > 0 {
+            return Err(ParseError::TrailingData(parser.remaining()));
+        }
+
+        for input in &inputs {
+            if let Some(issuance) = &input.issuance {
+                for (field, value) in [("amount", &issuance.amount), ("inflation_keys", &issuance.inflation_keys)] {
+                    if matches!(value, ConfidentialValue::Confidential(_)) {
+                        unsupported.push(format!("inputs[{}].issuance.{}: confidential, not unblinded", input.index, field));
+                    }
+                }
+            }
+            if input.is_pegin {
+                unsupported.push(format!("inputs[{}].pegin_witness: mainchain transaction and merkle proof shown raw", input.index));
+            }
+        }
+        for output in &outputs {
+            if matches!(output.asset, ConfidentialAsset::Confidential(_)) {
+                unsupported.push(format!("outputs[{}].asset: confidential, not unblinded", output.index));
+            }
+            if matches!(output.value, ConfidentialValue::Confidential(_)) {
+                unsupported.push(format!("outputs[{}].value: confidential, not unblinded", output.index));
+            }
+        }
+        if outputs.iter().any(|o| o.surjection_proof_size + o.range_proof_size > 0) {
+            unsupported.push("outputs: surjection and range proofs not verified".to_string());
+        }
+        if outputs.iter().any(|o| !o.is_fee) {
+            unsupported.push("addresses: Elements address encodings not derived".to_string());
+        }
+        if flags == 1 {
+            unsupported.push("wtxid: not computed".to_string());
+        }
+
+        let fee = outputs.iter()
+            .filter(|o| o.is_fee)
+            .filter_map(|o| match o.value {
+                ConfidentialValue::Explicit(value) => Some(value),
+                _ => None,
+            })
+            .sum();
+        let mut txid = sha256d(&stripped);
+        txid.reverse();
+
+        Ok(ElementsTransaction {
+            version,
+            inputs,
+            outputs,
+            locktime,
+            txid: hex::encode(txid),
+            size: bytes.len(),
+            weight: stripped.len() * 3 + bytes.len(),
+            fee,
+            unsupported,
+        })
+    }
+}
+
+fn parse_input(parser: &mut Parser, index: usize) -> Result<ElementsInput, ParseError> {
+    let txid = parser.read_hash()?;
+    let raw_index = parser.read_u32_le()?;
+    let script_bytes = read_sized(parser)?;
+    let sequence = Sequence(parser.read_u32_le()?);
+
+    let is_coinbase = raw_index == u32::MAX && txid.bytes().all(|c| c == b'0');
+    let flags = if is_coinbase { 0 } else { raw_index };
+    let issuance = if flags & ISSUANCE_FLAG != 0 {
+        let blinding_nonce = parser.read_hash()?;
+        Some(AssetIssuance {
+            is_reissuance: blinding_nonce.bytes().any(|c| c != b'0'),
+            blinding_nonce,
+            entropy: parser.read_hash()?,
+            amount: read_value(parser)?,
+            inflation_keys: read_value(parser)?,
+        })
+    } else {
+        None
+    };
+
+    Ok(ElementsInput {
+        index,
+        txid,
+        vout: if is_coinbase { raw_index } else { raw_index & INDEX_MASK },
+        is_coinbase,
+        is_pegin: flags & PEGIN_FLAG != 0,
+        script_sig: Script {
+            asm: if is_coinbase {
+                format!("[coinbase] {}", hex::encode(&script_bytes))
+            } else {
+                script_to_asm(&script_bytes)
+            },
+            size: script_bytes.len(),
+            bytes: script_bytes,
+        },
+        sequence,
+        issuance,
+        witness: Vec::new(),
+        pegin_witness: Vec::new(),
+    })
+}
+
+fn parse_output(parser: &mut Parser, index: usize) -> Result<ElementsOutput, ParseError> {
+    let asset = match parser.read_u8()? {
+        0x00 => ConfidentialAsset::Null,
+        0x01 => {
+            let mut id = parser.read_bytes(32)?;
+            id.reverse();
+            ConfidentialAsset::Explicit(hex::encode(id))
+        }
+        prefix @ (0x0a | 0x0b) => ConfidentialAsset::Confidential(commitment(parser, prefix)?),
+        prefix => return Err(ParseError::InvalidTransaction(format!("outputs[{}]: unknown asset prefix {:#04x}", index, prefix))),
+    };
+    let value = read_value(parser)?;
+    let nonce = match parser.read_u8()? {
+        0x00 => None,
+        prefix @ (0x01..=0x03) => Some(commitment(parser, prefix)?),
+        prefix => return Err(ParseError::InvalidTransaction(format!("outputs[{}]: unknown nonce prefix {:#04x}", index, prefix))),
+    };
+    let script_bytes = read_sized(parser)?;
+
+    Ok(ElementsOutput {
+        index,
+        asset,
+        value,
+        nonce,
+        script_type: detect_script_type(&script_bytes),
+        is_fee: script_bytes.is_empty(),
+        script_pubkey: Script {
+            asm: script_to_asm(&script_bytes),
+            size: script_bytes.len(),
+            bytes: script_bytes,
+        },
+        surjection_proof_size: 0,
+        range_proof_size: 0,
+    })
+}
+
+// Explicit values are 8 bytes big-endian, commitments 33 bytes
+fn read_value(parser: &mut Parser) -> Result<ConfidentialValue, ParseError> {
+    match parser.read_u8()? {
+        0x00 => Ok(ConfidentialValue::Null),
+        0x01 => {
+            let bytes: [u8; 8] = parser.read_slice(8)?.try_into().unwrap();
+            Ok(ConfidentialValue::Explicit(Amount::from_sat(u64::from_be_bytes(bytes))))
+        }
+        prefix @ (0x08 | 0x09) => Ok(ConfidentialValue::Confidential(commitment(parser, prefix)?)),
+        prefix => Err(ParseError::InvalidTransaction(format!("unknown value prefix {:#04x}", prefix))),
+    }
+}
+
+// Prefix byte and 32 bytes, as hex
+fn commitment(parser: &mut Parser, prefix: u8) -> Result<String, ParseError> {
+    Ok(format!("{:02x}{}", prefix, hex::encode(parser.read_slice(32)?)))
+}
+
+fn read_sized(parser: &mut Parser) -> Result<Vec<u8>, ParseError> {
+    let len = parser.read_varint()? as usize;
+    parser.read_bytes(len)
+}
+
+fn read_stack(parser: &mut Parser) -> Result<Vec<String>, ParseError> {
+    let count = parser.read_varint()? as usize;
+    (0..count).map(|_| read_sized(parser).map(hex::encode)).collect()
+}
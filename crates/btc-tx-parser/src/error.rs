@@ -30,4 +30,31 @@ pub enum ParseError {
 
     #[error("Data remaining after parsing: {0} bytes")]
     TrailingData(usize),
+
+    #[error("Invalid bundle: {0}")]
+    InvalidBundle(String),
+
+    #[error("Invalid PSBT: {0}")]
+    InvalidPsbt(String),
+
+    #[error("Invalid block template: {0}")]
+    InvalidTemplate(String),
+
+    #[error("Invalid block: {0}")]
+    InvalidBlock(String),
+
+    #[error("Invalid stratum job: {0}")]
+    InvalidStratumJob(String),
+
+    #[error("Invalid amount: {0}")]
+    InvalidAmount(String),
+
+    #[error("Invalid P2P message: {0}")]
+    InvalidMessage(String),
+
+    #[error("Invalid timestamp proof: {0}")]
+    InvalidTimestampProof(String),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
 }
@@ -0,0 +1,67 @@
+//! SHA-256 for txids, merkle roots, script hashes and addresses, from a
+//! backend picked at compile time: the sha2 crate by default (which switches
+//! to the CPU's SHA extensions when it finds them at runtime), or ring's
+//! assembly with the `ring-sha256` feature. Both give the same digests.
+
+use ripemd::{Digest, Ripemd160};
+
+#[cfg(not(feature = "ring-sha256"))]
+mod backend {
+    use sha2::{Digest, Sha256};
+
+    #[derive(Clone, Default)]
+    pub struct Sha256Engine(Sha256);
+
+    impl Sha256Engine {
+        pub fn update(&mut self, data: &[u8]) {
+            self.0.update(data);
+        }
+
+        pub fn finish(self) -> [u8; 32] {
+            self.0.finalize().into()
+        }
+    }
+}
+
+#[cfg(feature = "ring-sha256")]
+mod backend {
+    use ring::digest::{Context, SHA256};
+
+    #[derive(Clone)]
+    pub struct Sha256Engine(Context);
+
+    impl Default for Sha256Engine {
+        fn default() -> Self {
+            Self(Context::new(&SHA256))
+        }
+    }
+
+    impl Sha256Engine {
+        pub fn update(&mut self, data: &[u8]) {
+            self.0.update(data);
+        }
+
+        pub fn finish(self) -> [u8; 32] {
+            self.0.finish().as_ref().try_into().expect("SHA-256 digests are 32 bytes")
+        }
+    }
+}
+
+// Incremental SHA-256, for hashing data in pieces
+pub(crate) use backend::Sha256Engine;
+
+pub fn sha256(data: &[u8]) -> [u8; 32] {
+    let mut engine = Sha256Engine::default();
+    engine.update(data);
+    engine.finish()
+}
+
+// Double SHA256 for txid/wtxid calculation
+pub fn sha256d(data: &[u8]) -> [u8; 32] {
+    sha256(&sha256(data))
+}
+
+// RIPEMD160 of SHA256, as P2PKH, P2SH and P2WPKH commit to
+pub fn hash160(data: &[u8]) -> [u8; 20] {
+    Ripemd160::digest(sha256(data)).into()
+}
@@ -0,0 +1,79 @@
+//! Hex encoding and decoding on the hot paths: transaction and block hex in,
+//! txids, script disassembly and JSON out. With the `simd-hex` feature this
+//! goes through faster-hex's SSE4.1/AVX2/NEON routines, otherwise through
+//! the `hex` crate; both give the same output and the same errors.
+
+use std::fmt;
+
+// Bytes encoded per round trip through the stack buffer
+const CHUNK: usize = 256;
+
+// Like `hex::decode`, either case
+#[cfg(feature = "simd-hex")]
+pub fn decode_hex(hex: &str) -> Result<Vec<u8>, hex::FromHexError> {
+    if !hex.len().is_multiple_of(2) {
+        return Err(hex::FromHexError::OddLength);
+    }
+    let mut bytes = vec![0; hex.len() / 2];
+    faster_hex::hex_decode(hex.as_bytes(), &mut bytes).map_err(|_| {
+        // only the error path pays for finding the culprit
+        match hex.char_indices().find(|(_, c)| !c.is_ascii_hexdigit()) {
+            Some((index, c)) => hex::FromHexError::InvalidHexCharacter { c, index },
+            None => hex::FromHexError::OddLength,
+        }
+    })?;
+    Ok(bytes)
+}
+
+#[cfg(not(feature = "simd-hex"))]
+pub fn decode_hex(hex: &str) -> Result<Vec<u8>, hex::FromHexError> {
+    hex::decode(hex)
+}
+
+// Lowercase, like `hex::encode`
+#[cfg(feature = "simd-hex")]
+pub fn encode_hex(bytes: &[u8]) -> String {
+    faster_hex::hex_string(bytes)
+}
+
+#[cfg(not(feature = "simd-hex"))]
+pub fn encode_hex(bytes: &[u8]) -> String {
+    hex::encode(bytes)
+}
+
+// Hashes as block explorers show them: byte-reversed
+pub(crate) fn encode_hex_reversed(bytes: &[u8]) -> String {
+    let mut reversed = bytes.to_vec();
+    reversed.reverse();
+    encode_hex(&reversed)
+}
+
+// Write `bytes` as hex without building the whole string first
+pub(crate) fn write_hex(out: &mut dyn fmt::Write, bytes: &[u8]) -> fmt::Result {
+    let mut buf = [0; 2 * CHUNK];
+    for chunk in bytes.chunks(CHUNK) {
+        out.write_str(encode_chunk(chunk, &mut buf[..2 * chunk.len()]))?;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "simd-hex")]
+fn encode_chunk<'a>(chunk: &[u8], dst: &'a mut [u8]) -> &'a str {
+    faster_hex::hex_encode(chunk, dst).expect("buffer is twice the chunk")
+}
+
+#[cfg(not(feature = "simd-hex"))]
+fn encode_chunk<'a>(chunk: &[u8], dst: &'a mut [u8]) -> &'a str {
+    hex::encode_to_slice(chunk, dst).expect("buffer is twice the chunk");
+    std::str::from_utf8(dst).expect("hex digits are ASCII")
+}
+
+// Bytes shown as hex, encoded only when formatted; serializers given
+// `collect_str(&Hex(..))` write it straight into their output
+pub(crate) struct Hex<'a>(pub &'a [u8]);
+
+impl fmt::Display for Hex<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_hex(f, self.0)
+    }
+}
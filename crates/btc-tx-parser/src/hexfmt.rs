@@ -0,0 +1,96 @@
+//! Cleaning up pasted hex: `0x` prefixes, separators, hexdump offsets and ASCII columns
+
+use crate::error::ParseError;
+
+// Reduce pasted text to a plain lowercase hex string.
+//
+// Understands `xxd` (`00000010: 0100 0000  ....`), `hexdump -C`
+// (`00000010  01 00 00 00  |....|`), `0x01, 0x02` lists and plain hex
+// split across lines.
+pub fn normalize_hex(input: &str) -> Result<String, ParseError> {
+    let lines: Vec<Vec<&str>> = input.lines().map(line_tokens).collect();
+    let offsets = has_offset_column(&lines);
+
+    let mut hex = String::new();
+    for tokens in &lines {
+        let tokens = if offsets { tokens.get(1..).unwrap_or_default() } else { tokens.as_slice() };
+        for token in tokens {
+            let token = token.strip_prefix("0x").or_else(|| token.strip_prefix("0X")).unwrap_or(token);
+            hex.push_str(token);
+        }
+    }
+
+    if let Some((index, c)) = hex.char_indices().find(|(_, c)| !c.is_ascii_hexdigit()) {
+        return Err(hex::FromHexError::InvalidHexCharacter { c, index }.into());
+    }
+    if !hex.len().is_multiple_of(2) {
+        return Err(hex::FromHexError::OddLength.into());
+    }
+    Ok(hex.to_ascii_lowercase())
+}
+
+// The tokens of one line, without a hexdump -C ASCII column or an
+// "offset:" prefix
+fn line_tokens(line: &str) -> Vec<&str> {
+    let mut line = line.trim();
+    if let Some(pos) = line.find('|') {
+        line = &line[..pos];
+    }
+    // xxd and numbered listings; xxd's ASCII column follows the first
+    // double space after the offset
+    if let Some((offset, rest)) = line.split_once(':') {
+        if !offset.is_empty() && !offset.contains(char::is_whitespace) {
+            let rest = rest.trim_start();
+            return rest.split_once("  ").map_or(rest, |(hex, _)| hex).split_whitespace().collect();
+        }
+    }
+    line.split(|c: char| c.is_whitespace() || c == ',').filter(|t| !t.is_empty()).collect()
+}
+// Reflow hex into lines of `width` bytes (0 keeps a single line)
+pub fn format_hex(hex: &str, width: usize, spaced: bool) -> String {
+    let bytes: Vec<&str> = (0..hex.len() / 2).map(|i| &hex[i * 2..i * 2 + 2]).collect();
+    let separator = if spaced { " " } else { "" };
+    if width == 0 {
+        return bytes.join(separator);
+    }
+    bytes.chunks(width)
+        .map(|line| line.join(separator))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// hexdump -C shape: every line starts with a fixed-width hex offset, wider
+// than the byte groups after it, that advances by the previous line's
+// bytes; only the last line may hold the final offset alone. A single line
+// counts only from offset 0, so "deadbeef 0001" stays data.
+fn has_offset_column(lines: &[Vec<&str>]) -> bool {
+    let lines: Vec<&[&str]> = lines.iter().map(Vec::as_slice).filter(|tokens| !tokens.is_empty()).collect();
+    let Some(width) = lines.first().map(|tokens| tokens[0].len()) else {
+        return false;
+    };
+    let mut expected = None;
+    for (i, tokens) in lines.iter().enumerate() {
+        let (offset, data) = (tokens[0], &tokens[1..]);
+        if offset.len() != width || !offset.chars().all(|c| c.is_ascii_hexdigit()) {
+            return false;
+        }
+        let Ok(offset) = u64::from_str_radix(offset, 16) else {
+            return false;
+        };
+        if expected.is_some_and(|expected| expected != offset) {
+            return false;
+        }
+        let Some(group) = data.first().map(|t| t.len()) else {
+            return i > 0 && i == lines.len() - 1;
+        };
+        if group >= width || data.iter().any(|t| t.len() > group || !t.chars().all(|c| c.is_ascii_hexdigit())) {
+            return false;
+        }
+        let bytes = data.iter().map(|t| t.len() as u64).sum::<u64>() / 2;
+        expected = offset.checked_add(bytes);
+        if expected.is_none() {
+            return false;
+        }
+    }
+    lines.len() > 1 || lines[0][0].chars().all(|c| c == '0')
+}
@@ -0,0 +1,63 @@
+//! Address history: what each transaction touching an address paid into
+//! it and spent from it, read off the decoded transactions
+
+use serde::{Deserialize, Serialize};
+use crate::amount::Amount;
+use crate::types::{Transaction, TxOutput};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AddressActivity {
+    pub txid: String,
+    // None while unconfirmed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u32>,
+    // outputs paying the address, and their total
+    pub funded_outputs: Vec<usize>,
+    pub funded: Amount,
+    // inputs spending the address's coins, and their total
+    pub spent_inputs: Vec<usize>,
+    pub spent: Amount,
+}
+
+impl AddressActivity {
+    // Funded minus spent, in sats
+    pub fn net_sats(&self) -> i64 {
+        self.funded.to_sat() as i64 - self.spent.to_sat() as i64
+    }
+}
+
+impl TxOutput {
+    // Whether the output pays `address`, on mainnet or testnet
+    pub fn pays(&self, address: &str) -> bool {
+        self.address.as_ref().is_some_and(|a| {
+            [&a.mainnet, &a.testnet].iter().any(|candidate| match candidate.get(..3) {
+                // bech32 is case-insensitive
+                Some("bc1" | "tb1") => candidate.eq_ignore_ascii_case(address),
+                _ => *candidate == address,
+            })
+        })
+    }
+}
+
+impl Transaction {
+    // `prevouts` holds, per input in order, the output it spends when known;
+    // inputs without one are not counted as spending the address
+    pub fn address_activity(&self, address: &str, height: Option<u32>, prevouts: &[Option<&TxOutput>]) -> AddressActivity {
+        let funded_outputs: Vec<usize> = self.outputs.iter()
+            .filter(|output| output.pays(address))
+            .map(|output| output.index)
+            .collect();
+        let spent_inputs: Vec<usize> = self.inputs.iter()
+            .filter(|input| prevouts.get(input.index).copied().flatten().is_some_and(|prevout| prevout.pays(address)))
+            .map(|input| input.index)
+            .collect();
+        AddressActivity {
+            txid: self.txid.clone(),
+            height,
+            funded: funded_outputs.iter().map(|&i| self.outputs[i].value).sum(),
+            funded_outputs,
+            spent: spent_inputs.iter().filter_map(|&i| prevouts[i]).map(|prevout| prevout.value).sum(),
+            spent_inputs,
+        }
+    }
+}
@@ -0,0 +1,149 @@
+//! Hash-timelock contracts (atomic swaps, Lightning HTLCs): scripts paying to
+//! whoever reveals a preimage, with a CLTV/CSV refund branch
+
+use serde::{Deserialize, Serialize};
+use crate::analysis::{Analyzer, Finding};
+use crate::script::opcodes::*;
+use crate::script::{decode_script_num, instructions, revealed_scripts, Instruction};
+use crate::timelock::{absolute, relative, LockKind, LockUnit, Timelock};
+use crate::types::Transaction;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashLock {
+    Sha256,
+    Hash256,
+    Hash160,
+    Ripemd160,
+}
+
+impl std::fmt::Display for HashLock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HashLock::Sha256 => write!(f, "SHA256"),
+            HashLock::Hash256 => write!(f, "HASH256"),
+            HashLock::Hash160 => write!(f, "HASH160"),
+            HashLock::Ripemd160 => write!(f, "RIPEMD160"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Htlc {
+    // "scriptPubKey", "redeem script" or "witness script"
+    pub origin: String,
+    // input revealing the script
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input: Option<usize>,
+    // output whose scriptPubKey is the contract (bare scripts)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<usize>,
+    pub hash_lock: HashLock,
+    // hash the preimage must match (hex, as pushed)
+    pub payment_hash: String,
+    // refund branch lock
+    pub timeout: Timelock,
+}
+
+impl Transaction {
+    // HTLC scripts in bare outputs and in the scripts revealed by inputs
+    pub fn htlcs(&self) -> Vec<Htlc> {
+        let mut htlcs = Vec::new();
+        for output in &self.outputs {
+            if let Some(mut htlc) = parse_htlc(&output.script_pubkey.bytes, "scriptPubKey", None) {
+                htlc.output = Some(output.index);
+                htlcs.push(htlc);
+            }
+        }
+        for input in &self.inputs {
+            htlcs.extend(
+                revealed_scripts(input).into_iter()
+                    .filter_map(|(origin, script)| parse_htlc(&script, origin, Some(input.index))),
+            );
+        }
+        htlcs
+    }
+}
+
+// A preimage hash lock and a CLTV/CSV refund lock in a branching script
+fn parse_htlc(script: &[u8], origin: &str, input: Option<usize>) -> Option<Htlc> {
+    let ins = instructions(script)?;
+    if !ins.iter().any(|i| matches!(i, Instruction::Op(OP_IF) | Instruction::Op(OP_NOTIF))) {
+        return None;
+    }
+
+    // OP_DUP OP_HASH160 <h> OP_EQUAL... checks a key hash (P2PKH-style or a
+    // Lightning revocation key), not a preimage
+    let (hash_lock, payment_hash) = ins.windows(3).enumerate().find_map(|(at, window)| {
+        let (op, hash) = match (window[0], window[1], window[2]) {
+            (Instruction::Op(op), Instruction::Push(hash), Instruction::Op(OP_EQUAL | OP_EQUALVERIFY)) => (op, hash),
+            _ => return None,
+        };
+        if at > 0 && ins[at - 1] == Instruction::Op(OP_DUP) {
+            return None;
+        }
+        let hash_lock = match (op, hash.len()) {
+            (OP_SHA256, 32) => HashLock::Sha256,
+            (OP_HASH256, 32) => HashLock::Hash256,
+            (OP_HASH160, 20) => HashLock::Hash160,
+            (OP_RIPEMD160, 20) => HashLock::Ripemd160,
+            _ => return None,
+        };
+        Some((hash_lock, hex::encode(hash)))
+    })?;
+
+    let timeout = ins.windows(2).find_map(|pair| {
+        let value = match pair[0] {
+            Instruction::Push(data) => decode_script_num(data)?,
+            Instruction::Op(op) if (OP_1..=OP_16).contains(&op) => (op - OP_1 + 1) as i64,
+            _ => return None,
+        };
+        match pair[1] {
+            Instruction::Op(OP_CHECKLOCKTIMEVERIFY) if value >= 0 => {
+                Some(absolute(format!("{} OP_CHECKLOCKTIMEVERIFY", origin), input, value as u64))
+            }
+            Instruction::Op(OP_CHECKSEQUENCEVERIFY) => relative(format!("{} OP_CHECKSEQUENCEVERIFY", origin), input, value),
+            _ => None,
+        }
+    })?;
+
+    Some(Htlc { origin: origin.to_string(), input, output: None, hash_lock, payment_hash, timeout })
+}
+
+// Labels HTLC outputs and HTLC spends with their payment hash and timeout
+pub struct HtlcAnalyzer;
+
+impl Analyzer for HtlcAnalyzer {
+    fn name(&self) -> &'static str {
+        "htlc"
+    }
+
+    fn analyze(&self, tx: &Transaction) -> Vec<Finding> {
+        tx.htlcs().into_iter()
+            .map(|htlc| {
+                let timeout = match (htlc.timeout.kind, htlc.timeout.unit) {
+                    (LockKind::Absolute, LockUnit::Blocks) => format!("refundable from block {}", htlc.timeout.value),
+                    (LockKind::Absolute, LockUnit::Seconds) => format!("refundable from unix time {}", htlc.timeout.value),
+                    (LockKind::Relative, LockUnit::Blocks) => format!("refundable {} blocks after confirmation", htlc.timeout.value),
+                    (LockKind::Relative, LockUnit::Seconds) => format!("refundable {}s after confirmation", htlc.timeout.value),
+                };
+                let finding = Finding::heuristic(
+                    self.name(),
+                    "htlc",
+                    format!("HTLC in {}: {} payment hash {}, {}", htlc.origin, htlc.hash_lock, htlc.payment_hash, timeout),
+                    0.85,
+                    vec![
+                        format!("{} branches with OP_IF", htlc.origin),
+                        format!("one path checks an OP_{} preimage", htlc.hash_lock),
+                        format!("a refund lock via {}", htlc.timeout.source),
+                    ],
+                );
+                match (htlc.input, htlc.output) {
+                    (Some(input), _) => finding.on_input(input),
+                    (_, Some(output)) => finding.on_output(output),
+                    _ => finding,
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,191 @@
+//! Ordinals inscriptions: `OP_FALSE OP_IF "ord" ... OP_ENDIF` envelopes in
+//! revealed tapscripts
+
+use serde::{Deserialize, Serialize};
+use crate::analysis::{Analyzer, Finding};
+use crate::script::opcodes::*;
+use crate::script::{instructions, revealed_scripts, Instruction};
+use crate::types::Transaction;
+
+const PROTOCOL_ID: &[u8] = b"ord";
+const TAG_CONTENT_TYPE: u8 = 1;
+const TAG_CONTENT_ENCODING: u8 = 9;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Inscription {
+    // "<txid>i<n>", numbered across the whole transaction
+    pub id: String,
+    // input whose tapscript carries the envelope
+    pub input: usize,
+    // MIME type from the content-type field
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+    // e.g. "br" for brotli-compressed bodies
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_encoding: Option<String>,
+    // body size in bytes
+    pub size: usize,
+    // body, concatenated from its pushes
+    #[serde(skip)]
+    pub body: Vec<u8>,
+}
+
+impl Inscription {
+    // File extension for the content type; "bin" when unknown
+    pub fn file_extension(&self) -> &'static str {
+        let mime = self.content_type.as_deref().unwrap_or_default();
+        let mime = mime.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+        match mime.as_str() {
+            "text/plain" => "txt",
+            "text/html" => "html",
+            "text/css" => "css",
+            "text/markdown" => "md",
+            "text/javascript" | "application/javascript" => "js",
+            "application/json" => "json",
+            "application/yaml" => "yaml",
+            "application/pdf" => "pdf",
+            "image/png" => "png",
+            "image/jpeg" => "jpg",
+            "image/gif" => "gif",
+            "image/webp" => "webp",
+            "image/avif" => "avif",
+            "image/svg+xml" => "svg",
+            "audio/mpeg" => "mp3",
+            "audio/wav" => "wav",
+            "video/mp4" => "mp4",
+            "video/webm" => "webm",
+            "model/gltf-binary" => "glb",
+            "font/woff2" => "woff2",
+            _ => "bin",
+        }
+    }
+}
+
+impl Transaction {
+    // Every inscription envelope revealed by this transaction's inputs
+    pub fn inscriptions(&self) -> Vec<Inscription> {
+        let mut inscriptions = Vec::new();
+        for input in &self.inputs {
+            for (origin, script) in revealed_scripts(input) {
+                if origin != "tapscript" {
+                    continue;
+                }
+                for envelope in envelopes(&script) {
+                    inscriptions.push(Inscription {
+                        id: format!("{}i{}", self.txid, inscriptions.len()),
+                        input: input.index,
+                        size: envelope.body.len(),
+                        ..envelope
+                    });
+                }
+            }
+        }
+        inscriptions
+    }
+}
+
+// Parse envelopes out of a tapscript; malformed ones are skipped
+pub(crate) fn envelopes(script: &[u8]) -> Vec<Inscription> {
+    let ins = match instructions(script) {
+        Some(ins) => ins,
+        None => return Vec::new(),
+    };
+    let mut found = Vec::new();
+
+    let mut i = 0;
+    while i + 2 < ins.len() {
+        let is_start = is_empty_push(&ins[i])
+            && ins[i + 1] == Instruction::Op(OP_IF)
+            && ins[i + 2] == Instruction::Push(PROTOCOL_ID);
+        if !is_start {
+            i += 1;
+            continue;
+        }
+        let (envelope, end) = read_envelope(&ins, i + 3);
+        found.extend(envelope);
+        i = end;
+    }
+    found
+}
+
+// Fields and body after the protocol id; returns the envelope (if well formed)
+// and the position to resume scanning from
+fn read_envelope(ins: &[Instruction], mut i: usize) -> (Option<Inscription>, usize) {
+    let mut inscription = Inscription {
+        id: String::new(),
+        input: 0,
+        content_type: None,
+        content_encoding: None,
+        size: 0,
+        body: Vec::new(),
+    };
+
+    // tag/value pairs until the empty body tag or OP_ENDIF
+    while i < ins.len() {
+        if ins[i] == Instruction::Op(OP_ENDIF) {
+            return (Some(inscription), i + 1);
+        }
+        if is_empty_push(&ins[i]) {
+            i += 1;
+            break;
+        }
+        let (tag, value) = match (push_data(&ins[i]), ins.get(i + 1).and_then(push_data)) {
+            (Some(tag), Some(value)) => (tag, value),
+            _ => return (None, i + 1),
+        };
+        let text = || String::from_utf8_lossy(&value).into_owned();
+        match tag.as_slice() {
+            [TAG_CONTENT_TYPE] if inscription.content_type.is_none() => inscription.content_type = Some(text()),
+            [TAG_CONTENT_ENCODING] if inscription.content_encoding.is_none() => inscription.content_encoding = Some(text()),
+            _ => {}
+        }
+        i += 2;
+    }
+
+    // body pushes until OP_ENDIF
+    while i < ins.len() {
+        match &ins[i] {
+            Instruction::Op(OP_ENDIF) => return (Some(inscription), i + 1),
+            Instruction::Push(data) => inscription.body.extend_from_slice(data),
+            _ => return (None, i + 1),
+        }
+        i += 1;
+    }
+    (None, i)
+}
+
+fn is_empty_push(ins: &Instruction) -> bool {
+    matches!(ins, Instruction::Op(OP_0)) || matches!(ins, Instruction::Push(data) if data.is_empty())
+}
+
+// Data pushes, with OP_1..OP_16 read as one-byte pushes
+fn push_data(ins: &Instruction) -> Option<Vec<u8>> {
+    match *ins {
+        Instruction::Push(data) => Some(data.to_vec()),
+        Instruction::Op(op) if (OP_1..=OP_16).contains(&op) => Some(vec![op - OP_1 + 1]),
+        _ => None,
+    }
+}
+
+// Reports inscriptions revealed by the transaction
+pub struct InscriptionAnalyzer;
+
+impl Analyzer for InscriptionAnalyzer {
+    fn name(&self) -> &'static str {
+        "inscription"
+    }
+
+    fn analyze(&self, tx: &Transaction) -> Vec<Finding> {
+        tx.inscriptions().into_iter()
+            .map(|inscription| {
+                let content = inscription.content_type.as_deref().unwrap_or("no content type");
+                Finding::new(
+                    self.name(),
+                    "inscription",
+                    format!("tapscript reveals inscription {} ({}, {} bytes)", inscription.id, content, inscription.size),
+                )
+                .on_input(inscription.input)
+            })
+            .collect()
+    }
+}
@@ -1,39 +1,208 @@
 // BTC Transaction Parser Library
+
+// Log through `tracing` when the feature is enabled, compile to nothing otherwise
+#[cfg(feature = "tracing")]
+macro_rules! debug {
+    ($($arg:tt)*) => { tracing::debug!($($arg)*) };
+}
+#[cfg(not(feature = "tracing"))]
+macro_rules! debug {
+    ($($arg:tt)*) => {};
+}
+#[cfg(feature = "tracing")]
+macro_rules! trace {
+    ($($arg:tt)*) => { tracing::trace!($($arg)*) };
+}
+#[cfg(not(feature = "tracing"))]
+macro_rules! trace {
+    ($($arg:tt)*) => {};
+}
+
 mod error;
+mod amount;
 mod parser;
+mod borrowed;
 mod script;
 mod address;
+mod network;
 mod types;
+mod bundle;
+mod psbt;
+mod readiness;
+mod timelock;
+mod coinage;
+mod history;
+mod locktime;
+mod sequence;
+mod analysis;
+mod stats;
+mod template;
+mod policy;
+mod stress;
+mod stratum;
+mod hexfmt;
+mod hexcodec;
+mod hash;
+mod spans;
+mod diff;
+mod serialize;
+mod canonical;
+mod block;
+mod blockfile;
+#[cfg(feature = "mmap")]
+mod mmap;
+mod stream;
+mod p2p;
+mod compact;
+mod merkle;
+mod payload;
+mod inscription;
+mod privacy;
+mod provenance;
+mod redact;
+mod pseudonym;
+mod lightning;
+mod htlc;
+mod bond;
+mod batching;
+mod ots;
+mod op_return;
+mod signature;
+mod taproot;
+mod script_sig;
+mod coinbase;
+mod multisig;
+mod pubkey;
+mod script_template;
+mod witness;
+mod sigops;
+#[cfg(feature = "testutil")]
+mod testutil;
+#[cfg(feature = "elements")]
+mod elements;
 
 #[cfg(test)]
 mod tests;
 
 pub use error::ParseError;
+pub use amount::{Amount, ExactBtc};
 pub use types::*;
-pub use script::ScriptType;
-pub use address::Network;
+pub use borrowed::{TransactionRef, TxInputRef, TxOutputRef, BlockRef};
+pub use script::{ScriptType, detect_script_type, script_to_asm};
+pub use network::Network;
+pub use address::{KeyAddresses, derive_address, pubkey_addresses};
+pub use analysis::{Analyzer, AnalyzerRegistry, Finding, CovenantAnalyzer};
+pub use locktime::LockTime;
+pub use sequence::{RelativeLockTime, Sequence};
+pub use timelock::{Timelock, LockKind, LockUnit, ChainTip, UnlockStatus, ScheduleEntry};
+pub use coinage::{CoinAge, InputCoinAge, BLOCKS_PER_DAY};
+pub use history::AddressActivity;
+pub use stats::{parse_batch_line, BatchStats, BatchReport, DataCarrierStats, CarrierUsage, FeeratePercentiles, InputRef, NonceReuse, ReusedItem};
+pub use template::{BlockTemplate, TemplateTransaction, TemplateReport, TemplateEntry};
+pub use policy::{PolicyProfile, DustRules, PolicyReport, RuleResult, RuleStatus, standard_dust_threshold};
+pub use stress::{StressReport, Mismatch};
+pub use stratum::{StratumJob, StratumCoinbase};
+pub use coinbase::CoinbaseInfo;
+pub use hexfmt::{normalize_hex, format_hex};
+pub use hexcodec::{decode_hex, encode_hex};
+pub use hash::{hash160, sha256, sha256d};
+pub use spans::FieldSpan;
+pub use diff::{byte_diff, ByteDiff, FieldChange, FieldBytes};
+pub use canonical::{check_canonical, CanonicalReport};
+pub use payload::Payload;
+pub use inscription::{Inscription, InscriptionAnalyzer};
+pub use lightning::{ChannelClose, ChannelFunding, ChannelOutput, ChannelOutputRole, CloseKind, InteractiveTx, InteractiveTxKind, LightningAnalyzer, LightningSpend, SpendPath};
+pub use bond::{FidelityBond, BondAnalyzer};
+pub use batching::{BatchPayout, BatchingAnalyzer, Consolidation, ConsolidationAnalyzer, ShapeCategory, TxShape, MAX_BATCH_INPUTS, MIN_BATCH_OUTPUTS, MIN_CONSOLIDATION_INPUTS};
+pub use ots::{OtsProof, OtsAttestation, TimestampAnchor, TimestampAnalyzer};
+pub use op_return::{OpReturnAnalyzer, OpReturnMessage, OpReturnRegistry, ProtocolMessage};
+pub use htlc::{Htlc, HashLock, HtlcAnalyzer};
+pub use signature::{InputSignature, SighashType, SignatureKind, SignatureSummary, SignatureAnalyzer};
+#[cfg(feature = "elements")]
+pub use elements::{AssetIssuance, ConfidentialAsset, ConfidentialValue, ElementsInput, ElementsOutput, ElementsTransaction};
+pub use privacy::{PrivacyReport, RoundOutput, EqualOutputs, Entropy, PlausibleFlows, OutputFunders, Funder, FlowAnalyzer, MAX_ENTROPY_INPUTS, MAX_ENTROPY_OUTPUTS};
+pub use provenance::{LinkKind, ProvenanceLink};
+pub use taproot::{TaprootSpendInfo, TapscriptKind};
+pub use script_sig::ScriptSigParts;
+pub use multisig::{MultisigInfo, MultisigScript};
+pub use pubkey::{check_pubkey, KeyEncoding, PubkeyInfo, PubkeyAnalyzer};
+pub use script_template::ScriptTemplate;
+pub use witness::{WitnessItem, WitnessItemKind, WitnessScriptInfo};
+pub use redact::Redaction;
+pub use pseudonym::Pseudonymizer;
+#[cfg(feature = "testutil")]
+pub use testutil::TxGenerator;
+pub use merkle::{merkle_root, merkle_proof, MerkleProof};
+pub use block::{Block, BlockHeader, bits_to_target, bits_to_difficulty};
+pub use compact::{CompactBlock, PrefilledTransaction, Reconstruction};
+pub use stream::StreamParser;
+#[cfg(feature = "mmap")]
+pub use mmap::MappedFile;
+pub use p2p::{P2pMessage, WirePayload, parse_messages};
+pub use blockfile::{
+    BlockFileCheckpoint, BlockFileReader, BlockFiles, block_file_paths,
+    MAINNET_MAGIC, TESTNET_MAGIC, TESTNET4_MAGIC, SIGNET_MAGIC, REGTEST_MAGIC,
+};
+pub use readiness::{PsbtReadiness, InputReadiness, InputStatus, MissingSignature};
+pub use bundle::{TxBundle, BundleNote, BundlePrevout, FetchStatus, NoteTarget};
+pub use psbt::{
+    Psbt, PsbtInput, PsbtOutput, PsbtUtxo, PsbtModifiable, PartialSig, KeyOrigin,
+    MultisigProgress, MultisigInputProgress, CosignerProgress, CosignerInputStatus, SigningStatus,
+};
 
 use parser::Parser;
 
 impl Transaction {
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(len = hex_str.len()), err(level = "debug")))]
     pub fn from_hex(hex_str: &str) -> Result<Self, ParseError> {
-        let bytes = hex::decode(hex_str.trim())?;
+        let bytes = decode_hex(hex_str.trim())?;
         Self::from_bytes(&bytes)
     }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(len = bytes.len()), err(level = "debug")))]
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
         let mut parser = Parser::new(bytes);
         parser.parse_transaction()
     }
 
-    pub fn total_output_value(&self) -> u64 {
+    // Like `from_hex`, but rejects bytes left after the locktime
+    pub fn from_hex_strict(hex_str: &str) -> Result<Self, ParseError> {
+        let bytes = decode_hex(hex_str.trim())?;
+        Self::from_bytes_strict(&bytes)
+    }
+
+    // Like `from_bytes`, but rejects bytes left after the locktime; the lenient
+    // form suits reading one transaction out of a longer stream (e.g. a block)
+    pub fn from_bytes_strict(bytes: &[u8]) -> Result<Self, ParseError> {
+        let mut parser = Parser::new(bytes);
+        let tx = parser.parse_transaction()?;
+        match parser.remaining() {
+            0 => Ok(tx),
+            trailing => Err(ParseError::TrailingData(trailing)),
+        }
+    }
+
+    // Parse and also return the byte range of every field, for highlighting
+    // the raw hex; spans are in serialization order
+    pub fn from_hex_with_spans(hex_str: &str) -> Result<(Self, Vec<FieldSpan>), ParseError> {
+        let bytes = decode_hex(hex_str.trim())?;
+        Self::from_bytes_with_spans(&bytes)
+    }
+
+    pub fn from_bytes_with_spans(bytes: &[u8]) -> Result<(Self, Vec<FieldSpan>), ParseError> {
+        let mut parser = Parser::with_spans(bytes);
+        let tx = parser.parse_transaction()?;
+        Ok((tx, parser.take_spans()))
+    }
+
+    pub fn total_output_value(&self) -> Amount {
         self.outputs.iter().map(|o| o.value).sum()
     }
 
-    pub fn calculate_fee(&self) -> Option<u64> {
-        let total_input: Option<u64> = self.inputs.iter()
-            .map(|i| i.value)
-            .try_fold(0u64, |acc, v| v.map(|val| acc + val));
+    // None if an input value is unknown or the values overflow
+    pub fn calculate_fee(&self) -> Option<Amount> {
+        let total_input: Option<Amount> = self.inputs.iter()
+            .try_fold(Amount::ZERO, |acc, i| acc.checked_add(i.value?));
 
         total_input.map(|input| input.saturating_sub(self.total_output_value()))
     }
@@ -44,9 +213,14 @@ impl Transaction {
 
     pub fn vsize(&self) -> usize {
         if self.is_segwit {
-            (self.weight + 3) / 4
+            self.weight.div_ceil(4)
         } else {
             self.raw_size
         }
     }
+
+    // BIP-125 opt-in replaceability: any input sequence below 0xfffffffe
+    pub fn is_rbf(&self) -> bool {
+        self.inputs.iter().any(|i| i.sequence.is_rbf_signaling())
+    }
 }
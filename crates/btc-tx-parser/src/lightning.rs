@@ -0,0 +1,558 @@
+//! Lightning channel closes: cooperative closes and commitment transactions
+//! (force closes) spending a 2-of-2 funding output, with the commitment's
+//! outputs classified by their BOLT 3 scripts; inputs spending those outputs
+//! (sweeps, penalties, HTLC claims); transactions likely to open a channel;
+//! and transactions built with the interactive-tx protocol (dual-funded
+//! opens, splices)
+
+use serde::{Deserialize, Serialize};
+use crate::amount::Amount;
+use crate::hash::sha256;
+use crate::analysis::{Analyzer, Finding};
+use crate::script::opcodes::*;
+use crate::script::{decode_script_num, instructions, parse_multisig, Instruction, ScriptType};
+use crate::types::{Transaction, TxInput, TxOutput};
+
+// Value of the anchor outputs in anchor-output channels
+const ANCHOR_VALUE: Amount = Amount::from_sat(330);
+
+// Smallest channel LND opens by default; smaller P2WSH/P2TR outputs are
+// unlikely to be channels
+const MIN_CAPACITY: Amount = Amount::from_sat(20_000);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CloseKind {
+    // both parties signed a closing transaction
+    Cooperative,
+    // one party broadcast its commitment transaction
+    ForceClose,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChannelClose {
+    pub kind: CloseKind,
+    // input spending the funding output
+    pub funding_input: usize,
+    // the two funding public keys (hex); empty for a simple taproot
+    // channel, whose MuSig2 key spend hides them
+    pub funding_pubkeys: Vec<String>,
+    // the funding output's value, when the prevout is known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub capacity: Option<Amount>,
+    // commitment number XORed with a per-channel factor, split across the
+    // input's sequence and the locktime (force closes only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub obscured_commitment_number: Option<u64>,
+    // the commitment number itself, once `decode_commitment_number` has the
+    // payment basepoints
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commitment_number: Option<u64>,
+    pub outputs: Vec<ChannelOutput>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChannelOutput {
+    pub output: usize,
+    pub value: Amount,
+    #[serde(flatten)]
+    pub role: ChannelOutputRole,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "role", rename_all = "snake_case")]
+pub enum ChannelOutputRole {
+    // payout of a cooperative close
+    Payout,
+    // broadcaster's balance, claimable after `to_self_delay` blocks
+    ToLocal { to_self_delay: u32 },
+    // counterparty's balance
+    ToRemote,
+    // 330-sat output for fee bumping
+    Anchor,
+    // HTLC paid by the broadcaster; it reclaims it with an HTLC-timeout
+    // transaction whose locktime is the expiry
+    OfferedHtlc { payment_hash160: String },
+    // HTLC paid to the broadcaster; the counterparty reclaims it after `cltv_expiry`
+    ReceivedHtlc { payment_hash160: String, cltv_expiry: u32 },
+    // P2WSH output whose witness script was not supplied: to_local, an
+    // anchor-channel to_remote, or an HTLC
+    Unresolved,
+}
+
+impl ChannelOutputRole {
+    pub fn is_htlc(&self) -> bool {
+        matches!(self, ChannelOutputRole::OfferedHtlc { .. } | ChannelOutputRole::ReceivedHtlc { .. })
+    }
+}
+
+// A transaction shaped like a single-funded channel open: the funding output
+// is only a script hash until the channel closes, so this is a heuristic
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelFunding {
+    pub funding_output: usize,
+    pub capacity: Amount,
+    // a simple taproot channel (P2TR) rather than a P2WSH 2-of-2
+    pub taproot: bool,
+    // the funder's change, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub change_output: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InteractiveTxKind {
+    // both peers contribute inputs to a new channel's funding output
+    DualFundedOpen,
+    // an open channel's funding output is spent into a new one, with funds
+    // added (splice-in) or removed (splice-out)
+    Splice,
+}
+
+// Structure matching the interactive-tx protocol (BOLT 2); these are
+// heuristics, since the new funding output is only a script hash
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InteractiveTx {
+    pub kind: InteractiveTxKind,
+    // input spending the previous funding output (splices)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub funding_input: Option<usize>,
+    // P2WSH/P2TR outputs that could be the new funding output
+    pub funding_outputs: Vec<usize>,
+    // inputs other than the previous funding output
+    pub contributed_inputs: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpendPath {
+    // to_local swept by the broadcaster once the delay passed
+    Delayed,
+    // penalty: a revoked commitment's output taken with the revocation key
+    Revocation,
+    // HTLC claimed with the payment preimage
+    Preimage,
+    // HTLC reclaimed after its expiry
+    Timeout,
+    // to_remote or anchor spent with its owner's key
+    Key,
+    // anchor swept by anyone 16 blocks after confirmation
+    Anyone,
+}
+
+// An input spending a commitment output, recognized by its witness script
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LightningSpend {
+    pub input: usize,
+    // the spent output's role; never `Payout` or `Unresolved`
+    #[serde(flatten)]
+    pub role: ChannelOutputRole,
+    pub path: SpendPath,
+}
+
+impl LightningSpend {
+    // "sweeps a to_local output after its 144-block delay"
+    pub fn describe(&self) -> String {
+        let output = match &self.role {
+            // second-stage HTLC transactions pay to the same script
+            ChannelOutputRole::ToLocal { .. } => "a to_local (or second-stage HTLC) output".to_string(),
+            ChannelOutputRole::ToRemote => "a to_remote output".to_string(),
+            ChannelOutputRole::Anchor => "an anchor output".to_string(),
+            ChannelOutputRole::OfferedHtlc { payment_hash160 } => format!("an offered HTLC (payment hash160 {})", payment_hash160),
+            ChannelOutputRole::ReceivedHtlc { payment_hash160, .. } => format!("a received HTLC (payment hash160 {})", payment_hash160),
+            ChannelOutputRole::Payout | ChannelOutputRole::Unresolved => "a channel output".to_string(),
+        };
+        match (self.path, &self.role) {
+            (SpendPath::Delayed, ChannelOutputRole::ToLocal { to_self_delay }) => {
+                format!("sweeps {} after its {}-block delay", output, to_self_delay)
+            }
+            (SpendPath::Delayed, _) => format!("sweeps {} after its delay", output),
+            (SpendPath::Revocation, _) => format!("penalty: takes {} of a revoked commitment with the revocation key", output),
+            (SpendPath::Preimage, _) => format!("claims {} with the payment preimage", output),
+            (SpendPath::Timeout, ChannelOutputRole::ReceivedHtlc { cltv_expiry, .. }) => {
+                format!("reclaims {} after its expiry at block {}", output, cltv_expiry)
+            }
+            (SpendPath::Timeout, _) => format!("reclaims {} after its expiry", output),
+            (SpendPath::Key, _) => format!("spends {} with its owner's key", output),
+            (SpendPath::Anyone, _) => format!("sweeps {} that nobody claimed within 16 blocks", output),
+        }
+    }
+}
+
+impl ChannelClose {
+    // HTLC outputs still to be resolved on chain
+    pub fn pending_htlcs(&self) -> Vec<&ChannelOutput> {
+        self.outputs.iter().filter(|o| o.role.is_htlc()).collect()
+    }
+
+    // The broadcaster's `to_self_delay`, from a resolved to_local output
+    pub fn to_self_delay(&self) -> Option<u32> {
+        self.outputs.iter().find_map(|o| match o.role {
+            ChannelOutputRole::ToLocal { to_self_delay } => Some(to_self_delay),
+            _ => None,
+        })
+    }
+
+    // Whether the channel uses anchor outputs (option_anchors)
+    pub fn has_anchors(&self) -> bool {
+        self.outputs.iter().any(|o| o.role == ChannelOutputRole::Anchor)
+    }
+
+    // Recover the commitment number from the two payment basepoints (from a
+    // channel backup or the node's logs): BOLT 3 obscures it with the lower
+    // 48 bits of SHA256(opener's basepoint || accepter's basepoint)
+    pub fn decode_commitment_number(&mut self, opener_payment_basepoint: &[u8], accepter_payment_basepoint: &[u8]) -> Option<u64> {
+        let obscured = self.obscured_commitment_number?;
+        let digest = sha256(&[opener_payment_basepoint, accepter_payment_basepoint].concat());
+        let mut factor = [0u8; 8];
+        factor[2..].copy_from_slice(&digest[26..]);
+        self.commitment_number = Some(obscured ^ u64::from_be_bytes(factor));
+        self.commitment_number
+    }
+}
+
+impl Transaction {
+    // Channel close, if the transaction spends a Lightning funding output
+    pub fn channel_close(&self) -> Option<ChannelClose> {
+        self.channel_close_with_scripts(&[])
+    }
+
+    // Like `channel_close`, resolving P2WSH outputs of a commitment with the
+    // given witness scripts (e.g. from the node's channel backup or logs)
+    pub fn channel_close_with_scripts(&self, witness_scripts: &[Vec<u8>]) -> Option<ChannelClose> {
+        // commitment and closing transactions have exactly the funding input
+        let [input] = self.inputs.as_slice() else {
+            return None;
+        };
+        // BOLT 3 sets the top byte of the sequence to 0x80 and of the locktime to 0x20
+        let is_commitment = input.sequence.0 >> 24 == 0x80 && self.locktime.to_consensus_u32() >> 24 == 0x20;
+        let funding_pubkeys = match funding_pubkeys(input) {
+            Some(keys) => keys,
+            // a simple taproot channel's commitment: a lone key-spend
+            // signature, known only by the markers
+            None if is_commitment && matches!(input.witness.as_deref(), Some([sig]) if sig.len() == 64) => Vec::new(),
+            None => return None,
+        };
+        let outputs = self.outputs.iter()
+            .map(|output| {
+                let role = if !is_commitment {
+                    ChannelOutputRole::Payout
+                } else {
+                    commitment_output_role(output, witness_scripts)
+                };
+                ChannelOutput { output: output.index, value: output.value, role }
+            })
+            .collect();
+
+        Some(ChannelClose {
+            kind: if is_commitment { CloseKind::ForceClose } else { CloseKind::Cooperative },
+            funding_input: input.index,
+            funding_pubkeys,
+            capacity: input.value,
+            obscured_commitment_number: is_commitment
+                .then_some(((input.sequence.0 as u64 & 0xff_ffff) << 24) | (self.locktime.to_consensus_u32() as u64 & 0xff_ffff)),
+            commitment_number: None,
+            outputs,
+        })
+    }
+
+    // Inputs whose witness script is a BOLT 3 commitment output script, with
+    // the path the witness takes through it
+    pub fn lightning_spends(&self) -> Vec<LightningSpend> {
+        self.inputs.iter()
+            .filter_map(|input| {
+                let script = &input.witness_script.as_ref()?.script.bytes;
+                let role = classify_script(script)?;
+                // the stack the script runs on: the witness minus the script
+                let stack = input.witness.as_deref()?.split_last()?.1;
+                Some(LightningSpend { input: input.index, path: spend_path(&role, stack)?, role })
+            })
+            .collect()
+    }
+
+    // Single-funded channel open, if the shape fits: version 2 and only segwit
+    // inputs (BOLT 2 requires them so the txid can't change), one P2WSH or
+    // P2TR output large enough for a channel, and at most a change output
+    pub fn channel_funding(&self) -> Option<ChannelFunding> {
+        let shaped = self.version == 2
+            && self.outputs.len() <= 2
+            && self.inputs.iter().all(|i| !i.is_coinbase && i.witness.is_some() && funding_pubkeys(i).is_none());
+        if !shaped {
+            return None;
+        }
+        let is_candidate = |o: &&TxOutput| matches!(o.script_type, ScriptType::P2WSH | ScriptType::P2TR);
+        let [funding] = self.outputs.iter().filter(is_candidate).collect::<Vec<_>>()[..] else {
+            return None;
+        };
+        if funding.value < MIN_CAPACITY {
+            return None;
+        }
+        Some(ChannelFunding {
+            funding_output: funding.index,
+            capacity: funding.value,
+            taproot: funding.script_type == ScriptType::P2TR,
+            change_output: self.outputs.iter().find(|o| o.index != funding.index).map(|o| o.index),
+        })
+    }
+
+    // Dual-funded open or splice, if the structure matches interactive-tx:
+    // version 2, every input segwit and signalling RBF, and a P2WSH/P2TR output
+    // for the (new) channel
+    pub fn interactive_tx(&self) -> Option<InteractiveTx> {
+        let interactive = self.version == 2
+            && self.inputs.iter().all(|i| i.witness.is_some() && i.sequence.is_rbf_signaling());
+        if !interactive {
+            return None;
+        }
+        let funding_outputs: Vec<usize> = self.outputs.iter()
+            .filter(|o| matches!(o.script_type, ScriptType::P2WSH | ScriptType::P2TR) && o.value > ANCHOR_VALUE)
+            .map(|o| o.index)
+            .collect();
+        if funding_outputs.is_empty() {
+            return None;
+        }
+
+        let funding_input = self.inputs.iter().find(|i| funding_pubkeys(i).is_some()).map(|i| i.index);
+        let contributed_inputs = self.inputs.len() - funding_input.iter().count();
+        // commitments and closes also spend the funding output, but only a
+        // splice adds inputs or (being interactive) sets a current locktime
+        let is_commitment = self.inputs.iter().any(|i| i.sequence.0 >> 24 == 0x80) && self.locktime.to_consensus_u32() >> 24 == 0x20;
+        let kind = match funding_input {
+            Some(_) if is_commitment => return None,
+            Some(_) if contributed_inputs == 0 && self.locktime.is_zero() => return None,
+            Some(_) => InteractiveTxKind::Splice,
+            // a single-funded open needs nothing interactive; both peers'
+            // inputs and change outputs point to dual funding
+            None if contributed_inputs >= 2 && self.outputs.len() >= 3 => InteractiveTxKind::DualFundedOpen,
+            None => return None,
+        };
+        Some(InteractiveTx { kind, funding_input, funding_outputs, contributed_inputs })
+    }
+}
+
+// Funding pubkeys if the input spends a 2-of-2 channel funding output
+fn funding_pubkeys(input: &TxInput) -> Option<Vec<String>> {
+    let script = match input.witness.as_ref()?.as_slice() {
+        [dummy, _, _, script] if dummy.is_empty() => script,
+        _ => return None,
+    };
+    match parse_multisig(script)? {
+        (2, keys) if keys.len() == 2 && keys.iter().all(|k| k.len() == 33) => Some(keys.iter().map(hex::encode).collect()),
+        _ => None,
+    }
+}
+
+fn commitment_output_role(output: &TxOutput, witness_scripts: &[Vec<u8>]) -> ChannelOutputRole {
+    match output.script_type {
+        // static_remotekey channels pay the counterparty to a plain key
+        ScriptType::P2WPKH => return ChannelOutputRole::ToRemote,
+        ScriptType::P2WSH => {}
+        _ => return ChannelOutputRole::Unresolved,
+    }
+    let script = witness_scripts.iter()
+        .find(|script| output.script_pubkey.bytes == [&[0x00, 0x20], sha256(script).as_slice()].concat());
+    match script.and_then(|script| classify_script(script)) {
+        Some(role) => role,
+        None if output.value == ANCHOR_VALUE => ChannelOutputRole::Anchor,
+        None => ChannelOutputRole::Unresolved,
+    }
+}
+
+// Match a witness script against the BOLT 3 output templates
+fn classify_script(script: &[u8]) -> Option<ChannelOutputRole> {
+    let ins = instructions(script)?;
+    let op = |i: usize, code: u8| ins.get(i) == Some(&Instruction::Op(code));
+    let push_len = |i: usize, len: usize| matches!(ins.get(i), Some(Instruction::Push(data)) if data.len() == len);
+
+    // OP_IF <revocation> OP_ELSE <delay> OP_CSV OP_DROP <delayed key> OP_ENDIF OP_CHECKSIG
+    if ins.len() == 9 && op(0, OP_IF) && push_len(1, 33) && op(2, OP_ELSE) && op(4, OP_CHECKSEQUENCEVERIFY)
+        && op(5, OP_DROP) && push_len(6, 33) && op(7, OP_ENDIF) && op(8, OP_CHECKSIG)
+    {
+        return Some(ChannelOutputRole::ToLocal { to_self_delay: script_number(&ins[3])? as u32 });
+    }
+    // <key> OP_CHECKSIGVERIFY 1 OP_CSV
+    if ins.len() == 4 && push_len(0, 33) && op(1, OP_CHECKSIGVERIFY) && op(2, OP_1) && op(3, OP_CHECKSEQUENCEVERIFY) {
+        return Some(ChannelOutputRole::ToRemote);
+    }
+    // <key> OP_CHECKSIG OP_IFDUP OP_NOTIF OP_16 OP_CSV OP_ENDIF
+    if ins.len() == 7 && push_len(0, 33) && op(1, OP_CHECKSIG) && op(2, OP_IFDUP) && op(3, OP_NOTIF) && op(4, OP_16) {
+        return Some(ChannelOutputRole::Anchor);
+    }
+
+    // both HTLC scripts open with the revocation check
+    // OP_DUP OP_HASH160 <revocation hash> OP_EQUAL OP_IF OP_CHECKSIG ...
+    if !(op(0, OP_DUP) && op(1, OP_HASH160) && push_len(2, 20) && op(3, OP_EQUAL) && op(4, OP_IF) && op(5, OP_CHECKSIG)) {
+        return None;
+    }
+    let payment_hash160 = ins[6..].windows(2).find_map(|pair| match pair {
+        [Instruction::Op(OP_HASH160), Instruction::Push(hash)] if hash.len() == 20 => Some(hex::encode(hash)),
+        _ => None,
+    })?;
+    let expiry = ins.windows(2)
+        .find(|pair| pair[1] == Instruction::Op(OP_CHECKLOCKTIMEVERIFY))
+        .and_then(|pair| script_number(&pair[0]));
+    Some(match expiry {
+        Some(expiry) => ChannelOutputRole::ReceivedHtlc { payment_hash160, cltv_expiry: expiry as u32 },
+        None => ChannelOutputRole::OfferedHtlc { payment_hash160 },
+    })
+}
+
+// Which branch a witness stack takes through a BOLT 3 script, from the
+// items the branches tell apart
+fn spend_path(role: &ChannelOutputRole, stack: &[Vec<u8>]) -> Option<SpendPath> {
+    let last = stack.last()?;
+    Some(match role {
+        // <sig> <> takes OP_ELSE (delay), <revocation sig> 1 takes OP_IF
+        ChannelOutputRole::ToLocal { .. } if last.is_empty() => SpendPath::Delayed,
+        ChannelOutputRole::ToLocal { .. } => SpendPath::Revocation,
+        ChannelOutputRole::ToRemote => SpendPath::Key,
+        ChannelOutputRole::Anchor if last.is_empty() => SpendPath::Anyone,
+        ChannelOutputRole::Anchor => SpendPath::Key,
+        // the revocation pubkey, a 32-byte preimage, or an empty item that
+        // fails the size check and leads to the timeout branch
+        ChannelOutputRole::OfferedHtlc { .. } | ChannelOutputRole::ReceivedHtlc { .. } => match last.len() {
+            33 => SpendPath::Revocation,
+            32 => SpendPath::Preimage,
+            0 => SpendPath::Timeout,
+            _ => return None,
+        },
+        ChannelOutputRole::Payout | ChannelOutputRole::Unresolved => return None,
+    })
+}
+
+fn script_number(ins: &Instruction) -> Option<i64> {
+    match *ins {
+        Instruction::Push(data) => decode_script_num(data).filter(|n| *n >= 0),
+        Instruction::Op(op) if (OP_1..=OP_16).contains(&op) => Some((op - OP_1 + 1) as i64),
+        _ => None,
+    }
+}
+
+// Reports Lightning channel opens and closes, spends of commitment outputs,
+// dual-funded opens and splices
+pub struct LightningAnalyzer;
+
+impl Analyzer for LightningAnalyzer {
+    fn name(&self) -> &'static str {
+        "lightning"
+    }
+
+    fn analyze(&self, tx: &Transaction) -> Vec<Finding> {
+        let mut findings = self.structure(tx);
+        findings.extend(tx.lightning_spends().into_iter().map(|spend| {
+            let branch = match spend.path {
+                SpendPath::Delayed => "an empty selector takes the CSV-delayed branch",
+                SpendPath::Revocation => "the witness takes the revocation branch",
+                SpendPath::Preimage => "the witness carries a 32-byte preimage",
+                SpendPath::Timeout => "an empty item where the preimage goes takes the timeout branch",
+                SpendPath::Key => "a signature for the owner's key",
+                SpendPath::Anyone => "an empty signature, valid 16 blocks after confirmation",
+            };
+            let evidence = vec![
+                format!("input #{}'s witness script matches a BOLT 3 output template", spend.input),
+                branch.to_string(),
+            ];
+            Finding::heuristic(self.name(), "lightning_spend", spend.describe(), 0.95, evidence).on_input(spend.input)
+        }));
+        findings
+    }
+}
+
+impl LightningAnalyzer {
+    // The transaction as a whole: a close, an open or a splice
+    fn structure(&self, tx: &Transaction) -> Vec<Finding> {
+        // a splice spends a funding output too, but into a new channel
+        if let Some(interactive) = tx.interactive_tx() {
+            let outputs: Vec<String> = interactive.funding_outputs.iter().map(|o| format!("#{}", o)).collect();
+            let mut evidence = vec![
+                "version 2".to_string(),
+                format!("all {} input(s) are segwit and signal RBF", tx.inputs.len()),
+                format!("P2WSH/P2TR output(s) {} can hold a channel", outputs.join(", ")),
+            ];
+            if !tx.locktime.is_zero() {
+                evidence.push(format!("nLockTime {} set as interactive-tx does", tx.locktime.to_consensus_u32()));
+            }
+            let finding = match interactive.kind {
+                InteractiveTxKind::Splice => {
+                    evidence.insert(0, format!("input #{} spends a 2-of-2 funding output", interactive.funding_input.unwrap_or_default()));
+                    if interactive.contributed_inputs > 0 {
+                        evidence.push(format!("{} more input(s) add funds", interactive.contributed_inputs));
+                    }
+                    Finding::heuristic(
+                        self.name(),
+                        "splice",
+                        format!(
+                            "splices the channel into a new funding output (candidates {}) with {} added input(s)",
+                            outputs.join(", "), interactive.contributed_inputs
+                        ),
+                        0.7,
+                        evidence,
+                    )
+                }
+                InteractiveTxKind::DualFundedOpen => {
+                    evidence.push(format!("{} inputs and {} outputs leave room for both peers' change", tx.inputs.len(), tx.outputs.len()));
+                    Finding::heuristic(
+                        self.name(),
+                        "dual_funded_open",
+                        format!(
+                            "interactive-tx structure with {} inputs: possibly a dual-funded channel open (candidates {})",
+                            interactive.contributed_inputs, outputs.join(", ")
+                        ),
+                        0.4,
+                        evidence,
+                    )
+                }
+            };
+            return vec![match interactive.funding_input {
+                Some(input) => finding.on_input(input),
+                None => finding,
+            }];
+        }
+
+        let Some(close) = tx.channel_close() else {
+            return self.funding(tx);
+        };
+        let spent = if close.funding_pubkeys.is_empty() { "is a taproot key-path spend" } else { "spends a 2-of-2 multisig witness script" };
+        let mut evidence = vec![format!("the only input {}", spent)];
+        if let Some(capacity) = close.capacity {
+            evidence.push(format!("channel capacity {} sats", capacity.to_sat()));
+        }
+        let (message, confidence) = match close.kind {
+            // any 2-of-2 multisig spend looks like this
+            CloseKind::Cooperative => ("spends a 2-of-2 channel funding output: cooperative close".to_string(), 0.6),
+            CloseKind::ForceClose => {
+                let anchors = close.outputs.iter().filter(|o| o.role == ChannelOutputRole::Anchor).count();
+                evidence.push("nSequence and nLockTime carry the BOLT 3 0x80/0x20 markers".to_string());
+                if anchors > 0 {
+                    evidence.push(format!("{} output(s) of {} sats (anchors)", anchors, ANCHOR_VALUE.to_sat()));
+                }
+                if let Some(number) = close.obscured_commitment_number {
+                    evidence.push(format!("obscured commitment number 0x{:012x}", number));
+                }
+                (format!("commitment transaction (force close) with {} anchor output(s)", anchors), 0.95)
+            }
+        };
+        vec![Finding::heuristic(self.name(), "channel_close", message, confidence, evidence).on_input(close.funding_input)]
+    }
+
+    // A likely single-funded channel open
+    fn funding(&self, tx: &Transaction) -> Vec<Finding> {
+        let Some(funding) = tx.channel_funding() else {
+            return Vec::new();
+        };
+        let (script, confidence) = if funding.taproot { ("P2TR", 0.15) } else { ("P2WSH", 0.35) };
+        let mut evidence = vec![
+            format!("version 2 and all {} input(s) segwit, as BOLT 2 requires of funding transactions", tx.inputs.len()),
+            format!("one {} output of {} sats, at least the {}-sat minimum channel size", script, funding.capacity.to_sat(), MIN_CAPACITY.to_sat()),
+        ];
+        match funding.change_output {
+            Some(change) => evidence.push(format!("the only other output, #{}, can be the funder's change", change)),
+            None => evidence.push("no other output".to_string()),
+        }
+        if funding.taproot {
+            evidence.push("a taproot channel's MuSig2 key looks like any other key-path output".to_string());
+        }
+        let message = format!("possibly opens a {}-sat Lightning channel (output #{})", funding.capacity.to_sat(), funding.funding_output);
+        vec![Finding::heuristic(self.name(), "channel_funding", message, confidence, evidence).on_output(funding.funding_output)]
+    }
+}
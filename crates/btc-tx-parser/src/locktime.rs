@@ -0,0 +1,98 @@
+//! nLockTime with its consensus interpretation: a block height below
+//! 500,000,000 and a unix timestamp from there on
+
+use std::fmt;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use crate::timelock::LOCKTIME_THRESHOLD;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LockTime {
+    // 0 means no lock
+    Blocks(u32),
+    Time(DateTime<Utc>),
+}
+
+impl LockTime {
+    pub const ZERO: LockTime = LockTime::Blocks(0);
+
+    pub fn from_consensus(value: u32) -> Self {
+        if (value as u64) < LOCKTIME_THRESHOLD {
+            return LockTime::Blocks(value);
+        }
+        // every u32 is a valid timestamp
+        LockTime::Time(DateTime::from_timestamp(value as i64, 0).unwrap_or_default())
+    }
+
+    // Saturates for times a u32 can't hold
+    pub fn to_consensus_u32(self) -> u32 {
+        match self {
+            LockTime::Blocks(height) => height,
+            LockTime::Time(time) => u32::try_from(time.timestamp()).unwrap_or(u32::MAX),
+        }
+    }
+
+    pub fn is_zero(self) -> bool {
+        self == LockTime::ZERO
+    }
+}
+
+impl Default for LockTime {
+    fn default() -> Self {
+        LockTime::ZERO
+    }
+}
+
+impl From<u32> for LockTime {
+    fn from(value: u32) -> Self {
+        LockTime::from_consensus(value)
+    }
+}
+
+// "0 (no lock)", "840000 (block height)" or "1700000000 (2023-11-14 22:13:20 UTC)"
+impl fmt::Display for LockTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LockTime::Blocks(0) => write!(f, "0 (no lock)"),
+            LockTime::Blocks(height) => write!(f, "{} (block height)", height),
+            LockTime::Time(time) => write!(f, "{} ({})", self.to_consensus_u32(), time.format("%Y-%m-%d %H:%M:%S UTC")),
+        }
+    }
+}
+
+// {"type": "blocks", "height": 840000, "raw": 840000} or
+// {"type": "time", "time": "2023-11-14T22:13:20Z", "raw": 1700000000}
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Tagged {
+    Blocks { height: u32, raw: u32 },
+    Time { time: String, raw: u32 },
+}
+
+// Plain integers are accepted too, as older JSON carried the raw value
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Encoded {
+    Raw(u32),
+    Tagged(Tagged),
+}
+
+impl Serialize for LockTime {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let raw = self.to_consensus_u32();
+        match self {
+            LockTime::Blocks(height) => Tagged::Blocks { height: *height, raw },
+            LockTime::Time(time) => Tagged::Time { time: time.format("%Y-%m-%dT%H:%M:%SZ").to_string(), raw },
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for LockTime {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = match Encoded::deserialize(deserializer)? {
+            Encoded::Raw(raw) | Encoded::Tagged(Tagged::Blocks { raw, .. }) | Encoded::Tagged(Tagged::Time { raw, .. }) => raw,
+        };
+        Ok(LockTime::from_consensus(raw))
+    }
+}
@@ -0,0 +1,96 @@
+//! Transaction merkle trees: roots and inclusion proofs
+
+use serde::{Deserialize, Serialize};
+use crate::hash::sha256d;
+use crate::error::ParseError;
+use crate::hexcodec::encode_hex_reversed;
+
+// Path from one txid up to the merkle root. Hashes are in display order, like
+// txids; `index` is the transaction's position in the block, whose bits pick
+// the side each sibling is on.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleProof {
+    pub txid: String,
+    pub index: usize,
+    // sibling hashes from the leaf level up
+    pub siblings: Vec<String>,
+    pub merkle_root: String,
+}
+
+impl MerkleProof {
+    // Root implied by the txid and the path
+    pub fn compute_root(&self) -> Result<String, ParseError> {
+        let mut hash = internal_order(&self.txid)?;
+        let mut index = self.index;
+        for sibling in &self.siblings {
+            let sibling = internal_order(sibling)?;
+            let pair = if index.is_multiple_of(2) { [hash, sibling] } else { [sibling, hash] };
+            hash = sha256d(&pair.concat());
+            index /= 2;
+        }
+        Ok(display_order(&hash))
+    }
+
+    // Whether the path leads from the txid to `merkle_root`
+    pub fn verify(&self) -> bool {
+        self.compute_root().is_ok_and(|root| root == self.merkle_root)
+    }
+}
+
+// Merkle root over txids (display order in and out); None for an empty list
+pub fn merkle_root(txids: &[String]) -> Result<Option<String>, ParseError> {
+    let mut level = leaves(txids)?;
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    Ok(level.first().map(display_order))
+}
+
+// Inclusion proof for the txid at `index`
+pub fn merkle_proof(txids: &[String], index: usize) -> Result<Option<MerkleProof>, ParseError> {
+    let Some(txid) = txids.get(index) else {
+        return Ok(None);
+    };
+    let mut level = leaves(txids)?;
+    let mut position = index;
+    let mut siblings = Vec::new();
+    while level.len() > 1 {
+        // an odd level pairs its last hash with itself
+        let sibling = level.get(position ^ 1).unwrap_or(&level[position]);
+        siblings.push(display_order(sibling));
+        level = next_level(&level);
+        position /= 2;
+    }
+
+    Ok(Some(MerkleProof {
+        txid: txid.clone(),
+        index,
+        siblings,
+        merkle_root: display_order(&level[0]),
+    }))
+}
+
+fn leaves(txids: &[String]) -> Result<Vec<[u8; 32]>, ParseError> {
+    txids.iter().map(|txid| internal_order(txid)).collect()
+}
+
+fn next_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level.chunks(2)
+        .map(|pair| {
+            let right = pair.get(1).unwrap_or(&pair[0]);
+            sha256d(&[pair[0], *right].concat())
+        })
+        .collect()
+}
+
+fn internal_order(hash: &str) -> Result<[u8; 32], ParseError> {
+    let mut bytes: [u8; 32] = hex::decode(hash)?
+        .try_into()
+        .map_err(|_| ParseError::InvalidBlock(format!("'{}' is not a 32-byte hash", hash)))?;
+    bytes.reverse();
+    Ok(bytes)
+}
+
+fn display_order(hash: &[u8; 32]) -> String {
+    encode_hex_reversed(hash)
+}
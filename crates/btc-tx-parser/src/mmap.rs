@@ -0,0 +1,124 @@
+//! Memory-mapped input (the `mmap` feature): multi-GB block and batch files
+//! are read out of the mapping instead of being read into buffers first.
+//! Block records are parsed in place as `BlockRef`s; obfuscated files are
+//! de-obfuscated a record at a time into one reused buffer, and owned
+//! `Block`s are only built for callers that ask for them.
+
+use std::fs::File;
+use std::path::Path;
+use memmap2::Mmap;
+use crate::block::Block;
+use crate::borrowed::BlockRef;
+use crate::blockfile::MAX_BLOCK_SIZE;
+use crate::error::ParseError;
+
+// A read-only mapping of a whole file
+pub struct MappedFile {
+    map: Mmap,
+}
+
+impl MappedFile {
+    pub fn open(path: &Path) -> Result<Self, ParseError> {
+        let file = File::open(path)?;
+        // SAFETY: the mapping is read-only. If another process truncates the
+        // file meanwhile, reads past the new end fault; block files are only
+        // ever appended to, and batch files are inputs we were handed.
+        let map = unsafe { Mmap::map(&file)? };
+        Ok(MappedFile { map })
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.map
+    }
+
+    // The file as text, e.g. a batch file of one transaction hex per line
+    pub fn text(&self) -> Result<&str, ParseError> {
+        std::str::from_utf8(&self.map).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e).into())
+    }
+}
+
+// Records of one mapped blk*.dat file, like `BlockFileReader`. Each block is
+// parsed from a slice of the mapping, or for obfuscated files from `scratch`,
+// which holds the current record with the XOR undone.
+pub(crate) struct MappedBlockReader {
+    file: MappedFile,
+    magic: [u8; 4],
+    xor_key: Option<[u8; 8]>,
+    scratch: Vec<u8>,
+    // offset of the next record
+    pub(crate) position: u64,
+    done: bool,
+}
+
+impl MappedBlockReader {
+    pub(crate) fn new(file: MappedFile, magic: [u8; 4], xor_key: [u8; 8]) -> Self {
+        let xor_key = Some(xor_key).filter(|key| key.iter().any(|b| *b != 0));
+        MappedBlockReader { file, magic, xor_key, scratch: Vec::new(), position: 0, done: false }
+    }
+
+    // `len` bytes of the mapping at `start`, still obfuscated
+    fn raw(&self, start: usize, len: usize) -> Result<&[u8], ParseError> {
+        let data = self.file.bytes();
+        data.get(start..start.saturating_add(len))
+            .ok_or_else(|| ParseError::UnexpectedEof { position: data.len(), expected: start + len - data.len() })
+    }
+
+    // The next block, borrowed from the mapping (or the scratch buffer)
+    pub(crate) fn next_block(&mut self) -> Result<Option<BlockRef<'_>>, ParseError> {
+        let start = self.position as usize;
+        if start >= self.file.bytes().len() {
+            return Ok(None);
+        }
+        let mut header: [u8; 8] = self.raw(start, 8)?.try_into().expect("8 bytes");
+        if let Some(key) = self.xor_key {
+            for (i, byte) in header.iter_mut().enumerate() {
+                *byte ^= key[(start + i) % 8];
+            }
+        }
+        if header[..4] == [0; 4] {
+            return Ok(None);
+        }
+        if header[..4] != self.magic {
+            return Err(ParseError::InvalidBlock(format!(
+                "unexpected magic {} at offset {}, expected {}", hex::encode(&header[..4]), start, hex::encode(self.magic)
+            )));
+        }
+        let length = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as usize;
+        if length > MAX_BLOCK_SIZE {
+            return Err(ParseError::InvalidBlock(format!("record at offset {} claims {} bytes", start, length)));
+        }
+
+        self.raw(start + 8, length)?;
+        if let Some(key) = self.xor_key {
+            let data = self.file.bytes();
+            self.scratch.clear();
+            self.scratch.extend(data[start + 8..start + 8 + length].iter()
+                .enumerate()
+                .map(|(i, byte)| byte ^ key[(start + 8 + i) % 8]));
+        }
+        self.position = (start + 8 + length) as u64;
+        debug!(offset = start, length, "read mapped block record");
+        let bytes = match self.xor_key {
+            None => &self.file.bytes()[start + 8..start + 8 + length],
+            Some(_) => &self.scratch[..],
+        };
+        BlockRef::from_bytes(bytes).map(Some)
+    }
+}
+
+impl Iterator for MappedBlockReader {
+    type Item = Result<Block, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let next = self.next_block()
+            .and_then(|block| block.map(|block| block.to_block()).transpose())
+            .transpose();
+        if !matches!(next, Some(Ok(_))) {
+            self.done = true;
+        }
+        next
+    }
+}
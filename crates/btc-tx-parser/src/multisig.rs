@@ -0,0 +1,77 @@
+//! Multisig scripts taken apart: the threshold, the key count and the keys,
+//! whether the script is a bare output, a P2SH redeem script or a P2WSH
+//! witness script
+
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use crate::script::parse_multisig;
+use crate::types::{Transaction, TxInput};
+
+// OP_m <pubkey>... OP_n OP_CHECKMULTISIG
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MultisigInfo {
+    // signatures needed (m)
+    pub required: usize,
+    // keys in the script (n)
+    pub total: usize,
+    // public keys (hex), in script order
+    pub pubkeys: Vec<String>,
+}
+
+impl MultisigInfo {
+    pub fn from_script(script: &[u8]) -> Option<Self> {
+        let (required, keys) = parse_multisig(script)?;
+        Some(MultisigInfo {
+            required,
+            total: keys.len(),
+            pubkeys: keys.into_iter().map(hex::encode).collect(),
+        })
+    }
+}
+
+// "2-of-3"
+impl fmt::Display for MultisigInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-of-{}", self.required, self.total)
+    }
+}
+
+impl TxInput {
+    // The multisig script this input reveals: the witness script of a P2WSH
+    // spend (nested or not), else the redeem script of a P2SH one
+    pub fn multisig(&self) -> Option<MultisigInfo> {
+        self.witness_script.as_ref().and_then(|info| info.multisig.clone())
+            .or_else(|| self.script_sig_parts.as_ref().and_then(|parts| parts.multisig.clone()))
+    }
+}
+
+// A multisig script and where it was found
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MultisigScript {
+    // "outputs[0]", "inputs[1].redeem_script" or "inputs[1].witness_script"
+    pub location: String,
+    // "2-of-3"
+    pub label: String,
+    #[serde(flatten)]
+    pub multisig: MultisigInfo,
+}
+
+impl Transaction {
+    // Every multisig script in the transaction, inputs first
+    pub fn multisig_scripts(&self) -> Vec<MultisigScript> {
+        let revealed = self.inputs.iter().flat_map(|input| {
+            let redeem = input.script_sig_parts.as_ref().and_then(|parts| parts.multisig.clone());
+            let witness = input.witness_script.as_ref().and_then(|ws| ws.multisig.clone());
+            [
+                redeem.map(|info| (format!("inputs[{}].redeem_script", input.index), info)),
+                witness.map(|info| (format!("inputs[{}].witness_script", input.index), info)),
+            ]
+        });
+        let outputs = self.outputs.iter()
+            .map(|output| output.multisig.clone().map(|info| (format!("outputs[{}]", output.index), info)));
+        revealed.chain(outputs)
+            .flatten()
+            .map(|(location, multisig)| MultisigScript { location, label: multisig.to_string(), multisig })
+            .collect()
+    }
+}
@@ -0,0 +1,93 @@
+//! Network parameters: message start bytes for block files and P2P framing,
+//! genesis blocks, and address prefixes. Testnet4 (BIP94) shares testnet's
+//! address prefixes and "tb" HRP but has its own magic and genesis block.
+
+use std::fmt;
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+use crate::blockfile::{MAINNET_MAGIC, REGTEST_MAGIC, SIGNET_MAGIC, TESTNET4_MAGIC, TESTNET_MAGIC};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Network {
+    Mainnet,
+    // testnet3
+    Testnet,
+    Testnet4,
+    Signet,
+    Regtest,
+}
+
+impl Network {
+    pub const ALL: [Network; 5] = [Network::Mainnet, Network::Testnet, Network::Testnet4, Network::Signet, Network::Regtest];
+
+    // Message start bytes
+    pub fn magic(self) -> [u8; 4] {
+        match self {
+            Network::Mainnet => MAINNET_MAGIC,
+            Network::Testnet => TESTNET_MAGIC,
+            Network::Testnet4 => TESTNET4_MAGIC,
+            Network::Signet => SIGNET_MAGIC,
+            Network::Regtest => REGTEST_MAGIC,
+        }
+    }
+
+    pub fn from_magic(magic: [u8; 4]) -> Option<Network> {
+        Network::ALL.into_iter().find(|network| network.magic() == magic)
+    }
+
+    // Hash of block 0 (display order); signet's is that of the default signet
+    pub fn genesis_hash(self) -> &'static str {
+        match self {
+            Network::Mainnet => "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f",
+            Network::Testnet => "000000000933ea01ad0ee984209779baaec3ced90fa3f408719526f8d77f4943",
+            Network::Testnet4 => "00000000da84f2bafbbc53dee25a72ae507ff4914b867c565be350b0da8bf043",
+            Network::Signet => "00000008819873e925422c1ff0f99f7cc9bbb232af63a077a480a3633bee1ef6",
+            Network::Regtest => "0f9188f13cb7b2c71f2a335e3a4fc328bf5beb436012afca590b1a11466e2206",
+        }
+    }
+
+    pub(crate) fn p2pkh_version(self) -> u8 {
+        match self {
+            Network::Mainnet => 0x00,
+            _ => 0x6f,
+        }
+    }
+
+    pub(crate) fn p2sh_version(self) -> u8 {
+        match self {
+            Network::Mainnet => 0x05,
+            _ => 0xc4,
+        }
+    }
+
+    pub fn bech32_hrp(self) -> &'static str {
+        match self {
+            Network::Mainnet => "bc",
+            Network::Testnet | Network::Testnet4 | Network::Signet => "tb",
+            Network::Regtest => "bcrt",
+        }
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Network::Mainnet => "mainnet",
+            Network::Testnet => "testnet",
+            Network::Testnet4 => "testnet4",
+            Network::Signet => "signet",
+            Network::Regtest => "regtest",
+        })
+    }
+}
+
+impl FromStr for Network {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Network::ALL.into_iter()
+            .find(|network| network.to_string() == s)
+            .ok_or_else(|| format!("Unknown network '{}' (expected mainnet, testnet, testnet4, signet or regtest)", s))
+    }
+}
@@ -0,0 +1,484 @@
+//! OP_RETURN protocols: decoders registered under the prefix their payload
+//! starts with. The payload is the script after OP_RETURN with each push
+//! replaced by its data, so a tag split across pushes still matches; other
+//! opcodes (such as the OP_13 that marks a runestone) stay as their byte.
+//! The registry holds the built-in protocols and accepts decoders from
+//! downstream crates, e.g. a company's own tag. Counterparty encrypts its
+//! payloads, prefix included, with ARC4 keyed by the first input's txid;
+//! decoding a whole transaction tries that key as well.
+
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use crate::analysis::{Analyzer, Finding};
+use crate::script::opcodes::*;
+use crate::script::{instructions, Instruction, ScriptType};
+use crate::types::Transaction;
+
+// What a decoder read from a payload
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProtocolMessage {
+    // one line, e.g. "Omni Simple Send"
+    pub summary: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub fields: BTreeMap<String, String>,
+}
+
+impl ProtocolMessage {
+    pub fn new(summary: impl Into<String>) -> Self {
+        Self { summary: summary.into(), fields: BTreeMap::new() }
+    }
+
+    pub fn with_field(mut self, name: &str, value: impl ToString) -> Self {
+        self.fields.insert(name.to_string(), value.to_string());
+        self
+    }
+}
+
+// A decoded OP_RETURN output
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpReturnMessage {
+    pub output: usize,
+    // name the decoder was registered under
+    pub protocol: String,
+    #[serde(flatten)]
+    pub message: ProtocolMessage,
+}
+
+type DecodeFn = dyn Fn(&[u8]) -> Option<ProtocolMessage> + Send + Sync;
+
+struct Protocol {
+    name: String,
+    prefix: Vec<u8>,
+    decode: Box<DecodeFn>,
+}
+
+// OP_RETURN decoders by prefix. The longest matching prefix is tried first,
+// and among equal prefixes the latest registration, so a downstream decoder
+// can take over from a built-in one; the first to return Some wins.
+pub struct OpReturnRegistry {
+    protocols: Vec<Protocol>,
+}
+
+impl OpReturnRegistry {
+    pub fn new() -> Self {
+        Self { protocols: Vec::new() }
+    }
+
+    // Registry with all built-in protocols
+    pub fn with_defaults() -> Self {
+        let mut registry = Self::new();
+        registry.register("witness_commitment", WITNESS_COMMITMENT_HEADER, decode_witness_commitment);
+        registry.register("omni", OMNI_PREFIX, decode_omni);
+        registry.register("counterparty", COUNTERPARTY_PREFIX, decode_counterparty);
+        registry.register("runes", [OP_13], decode_runestone);
+        // no prefix: any lone 32-byte payload
+        registry.register("opentimestamps", [], decode_timestamp);
+        registry
+    }
+
+    // `decode` gets the payload after `prefix` and returns None if it isn't
+    // a message of the protocol after all
+    pub fn register(
+        &mut self,
+        name: &str,
+        prefix: impl Into<Vec<u8>>,
+        decode: impl Fn(&[u8]) -> Option<ProtocolMessage> + Send + Sync + 'static,
+    ) {
+        self.protocols.push(Protocol { name: name.to_string(), prefix: prefix.into(), decode: Box::new(decode) });
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.protocols.iter().map(|p| p.name.as_str()).collect()
+    }
+
+    // Protocol name and message for an OP_RETURN script, as it stands
+    pub fn decode(&self, script: &[u8]) -> Option<(&str, ProtocolMessage)> {
+        self.decode_payload(&op_return_payload(script)?, false)
+    }
+
+    // Every OP_RETURN output of `tx` that a registered protocol decodes,
+    // as it stands or decrypted with the first input's txid
+    pub fn decode_transaction(&self, tx: &Transaction) -> Vec<OpReturnMessage> {
+        // the txid in display order, as Counterparty uses it
+        let key: Option<Vec<u8>> = tx.inputs.first().map(|i| i.txid.as_bytes().iter().rev().copied().collect());
+        tx.outputs.iter()
+            .filter(|o| o.script_type == ScriptType::OpReturn)
+            .filter_map(|o| {
+                let payload = op_return_payload(&o.script_pubkey.bytes)?;
+                let (protocol, message) = self.decode_payload(&payload, false)
+                    .or_else(|| self.decode_payload(&arc4(key.as_ref()?, &payload), true))?;
+                Some(OpReturnMessage { output: o.index, protocol: protocol.to_string(), message })
+            })
+            .collect()
+    }
+
+    // Decrypted payloads are only matched by prefix, since any decoder that
+    // takes bytes of the right length would accept the noise of a wrong key
+    fn decode_payload(&self, payload: &[u8], decrypted: bool) -> Option<(&str, ProtocolMessage)> {
+        let mut matching: Vec<&Protocol> = self.protocols.iter().rev()
+            .filter(|p| payload.starts_with(&p.prefix) && !(decrypted && p.prefix.is_empty()))
+            .collect();
+        matching.sort_by_key(|p| std::cmp::Reverse(p.prefix.len()));
+        matching.into_iter().find_map(|p| Some((p.name.as_str(), (p.decode)(&payload[p.prefix.len()..])?)))
+    }
+
+    // Whether `protocol` matches by prefix rather than by shape alone
+    fn has_prefix(&self, protocol: &str) -> bool {
+        self.protocols.iter().any(|p| p.name == protocol && !p.prefix.is_empty())
+    }
+}
+
+impl Default for OpReturnRegistry {
+    fn default() -> Self {
+        Self::with_defaults()
+    }
+}
+
+impl Transaction {
+    // OP_RETURN outputs decoded by the built-in protocols
+    pub fn op_return_messages(&self) -> Vec<OpReturnMessage> {
+        OpReturnRegistry::with_defaults().decode_transaction(self)
+    }
+}
+
+// The script after OP_RETURN with pushes replaced by their data
+fn op_return_payload(script: &[u8]) -> Option<Vec<u8>> {
+    let rest = script.strip_prefix(&[OP_RETURN])?;
+    let mut payload = Vec::new();
+    for ins in instructions(rest)? {
+        match ins {
+            Instruction::Push(data) => payload.extend_from_slice(data),
+            Instruction::Op(OP_0) => {}
+            Instruction::Op(op) => payload.push(op),
+        }
+    }
+    Some(payload)
+}
+
+// BIP141: the coinbase commits to the block's witness merkle root
+const WITNESS_COMMITMENT_HEADER: [u8; 4] = [0xaa, 0x21, 0xa9, 0xed];
+
+fn decode_witness_commitment(data: &[u8]) -> Option<ProtocolMessage> {
+    let commitment = data.get(..32)?;
+    Some(ProtocolMessage::new("BIP141 witness commitment").with_field("commitment", hex::encode(commitment)))
+}
+
+const OMNI_PREFIX: &[u8] = b"omni";
+
+// Omni Layer (class C): big-endian version and message type, then the
+// message; sends and grants carry a property id and an amount in its units
+fn decode_omni(data: &[u8]) -> Option<ProtocolMessage> {
+    let version = u16::from_be_bytes(data.get(..2)?.try_into().ok()?);
+    let kind = u16::from_be_bytes(data.get(2..4)?.try_into().ok()?);
+    let name = match kind {
+        0 => "Simple Send",
+        3 => "Send To Owners",
+        4 => "Send All",
+        20 => "DEx Sell Offer",
+        22 => "DEx Accept",
+        25 => "MetaDEx Trade",
+        50 => "Create Property (fixed)",
+        51 => "Create Property (crowdsale)",
+        53 => "Close Crowdsale",
+        54 => "Create Property (managed)",
+        55 => "Grant Tokens",
+        56 => "Revoke Tokens",
+        70 => "Change Issuer",
+        _ => "Omni message",
+    };
+    let mut message = ProtocolMessage::new(format!("Omni {}", name)).with_field("version", version).with_field("type", kind);
+    // Simple Send, Send To Owners, Grant and Revoke
+    if matches!(kind, 0 | 3 | 55 | 56) {
+        let property = u32::from_be_bytes(data.get(4..8)?.try_into().ok()?);
+        let units = u64::from_be_bytes(data.get(8..16)?.try_into().ok()?);
+        let (asset, amount) = match omni_property(property) {
+            Some((name, true)) => (name.to_string(), format_units(units, 8)),
+            Some((name, false)) => (name.to_string(), units.to_string()),
+            // divisibility is set when the property is created
+            None => (format!("property {}", property), format!("{} units", units)),
+        };
+        message.summary = format!("Omni {}: {} {}", name, amount, asset);
+        message = message.with_field("property", property).with_field("asset", asset).with_field("amount", amount);
+    }
+    if kind == 4 {
+        let ecosystem = match data.get(4)? {
+            1 => "main",
+            2 => "test",
+            _ => return Some(message),
+        };
+        message = message.with_field("ecosystem", ecosystem);
+    }
+    Some(message)
+}
+
+// Name and divisibility of well-known Omni properties
+fn omni_property(id: u32) -> Option<(&'static str, bool)> {
+    match id {
+        1 => Some(("OMNI", true)),
+        2 => Some(("TOMNI", true)),
+        3 => Some(("MaidSafeCoin", false)),
+        31 => Some(("USDT", true)),
+        _ => None,
+    }
+}
+
+// `units` with `decimals` places, trailing zeros trimmed
+fn format_units(units: u64, decimals: u32) -> String {
+    let scale = 10u64.pow(decimals);
+    let fraction = format!("{:0width$}", units % scale, width = decimals as usize);
+    match fraction.trim_end_matches('0') {
+        "" => (units / scale).to_string(),
+        fraction => format!("{}.{}", units / scale, fraction),
+    }
+}
+
+const COUNTERPARTY_PREFIX: &[u8] = b"CNTRPRTY";
+
+// Counterparty: a message type id (one byte, or four in early messages),
+// then the message. Sends and issuances name an asset and a quantity.
+fn decode_counterparty(data: &[u8]) -> Option<ProtocolMessage> {
+    // a four-byte id starts with three zero bytes; a one-byte classic send
+    // (id 0) is told apart by its length of 16 bytes after the id
+    let (id, body) = match data {
+        [0, 0, 0, id, body @ ..] if data.len() != 17 => (*id, body),
+        [id, body @ ..] => (*id, body),
+        [] => return None,
+    };
+    let name = match id {
+        0 => "Send",
+        2 => "Enhanced Send",
+        4 => "Sweep",
+        10 => "Order",
+        11 => "BTC Pay",
+        12 => "Dispenser",
+        20..=22 => "Issuance",
+        30 => "Broadcast",
+        40 => "Bet",
+        50 => "Dividend",
+        70 => "Cancel",
+        110 => "Destroy",
+        _ => "message",
+    };
+    let mut message = ProtocolMessage::new(format!("Counterparty {}", name)).with_field("type", id);
+    if matches!(id, 0 | 2 | 20..=22 | 110) {
+        let asset = counterparty_asset(u64::from_be_bytes(body.get(..8)?.try_into().ok()?));
+        let quantity = u64::from_be_bytes(body.get(8..16)?.try_into().ok()?);
+        // only XCP's divisibility is known without the issuance
+        let amount = if asset == "XCP" { format_units(quantity, 8) } else { quantity.to_string() };
+        message.summary = format!("Counterparty {}: {} {}", name, amount, asset);
+        message = message.with_field("asset", asset).with_field("quantity", amount);
+    }
+    Some(message)
+}
+
+// Asset names are their id in base 26; numeric assets are "A" and the id
+fn counterparty_asset(id: u64) -> String {
+    const NUMERIC_START: u64 = 26u64.pow(12) + 1;
+    match id {
+        0 => "BTC".to_string(),
+        1 => "XCP".to_string(),
+        id if id >= NUMERIC_START => format!("A{}", id),
+        mut id => {
+            let mut name = Vec::new();
+            while id > 0 {
+                name.push(b'A' + (id % 26) as u8);
+                id /= 26;
+            }
+            name.reverse();
+            String::from_utf8(name).unwrap_or_default()
+        }
+    }
+}
+
+// ARC4 (RC4) keystream XORed over `data`
+pub(crate) fn arc4(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut state: Vec<u8> = (0..=255).collect();
+    let mut j = 0u8;
+    for i in 0..256 {
+        j = j.wrapping_add(state[i]).wrapping_add(key[i % key.len()]);
+        state.swap(i, j as usize);
+    }
+    let (mut i, mut j) = (0u8, 0u8);
+    data.iter()
+        .map(|byte| {
+            i = i.wrapping_add(1);
+            j = j.wrapping_add(state[i as usize]);
+            state.swap(i as usize, j as usize);
+            byte ^ state[state[i as usize].wrapping_add(state[j as usize]) as usize]
+        })
+        .collect()
+}
+
+fn decode_timestamp(data: &[u8]) -> Option<ProtocolMessage> {
+    (data.len() == 32).then(|| ProtocolMessage::new("32-byte commitment, as OpenTimestamps calendars make").with_field("commitment", hex::encode(data)))
+}
+
+// Runestone tags; an even tag a reader doesn't know (such as 126, which
+// marks a cenotaph on purpose) makes a cenotaph, while odd ones are ignored
+const TAG_BODY: u128 = 0;
+const TAG_DIVISIBILITY: u128 = 1;
+const TAG_FLAGS: u128 = 2;
+const TAG_SPACERS: u128 = 3;
+const TAG_RUNE: u128 = 4;
+const TAG_SYMBOL: u128 = 5;
+const TAG_PREMINE: u128 = 6;
+const TAG_CAP: u128 = 8;
+const TAG_AMOUNT: u128 = 10;
+const TAG_HEIGHT_START: u128 = 12;
+const TAG_HEIGHT_END: u128 = 14;
+const TAG_OFFSET_START: u128 = 16;
+const TAG_OFFSET_END: u128 = 18;
+const TAG_MINT: u128 = 20;
+const TAG_POINTER: u128 = 22;
+
+const FLAG_ETCHING: u128 = 1;
+const FLAG_TERMS: u128 = 2;
+const FLAG_TURBO: u128 = 4;
+
+// Runes: LEB128 integers after OP_RETURN OP_13, as tag/value pairs and then
+// edicts of four integers each. Malformed runestones (cenotaphs) burn their
+// inputs' runes, so they are reported rather than dropped.
+fn decode_runestone(data: &[u8]) -> Option<ProtocolMessage> {
+    let Some(integers) = leb128_integers(data) else {
+        return Some(cenotaph("truncated or overlong integer"));
+    };
+    // the body tag (at a tag's position, not a value's) starts the edicts
+    let body = (0..integers.len()).step_by(2).find(|&i| integers[i] == TAG_BODY);
+    let (fields, edicts) = match body {
+        Some(body) => (&integers[..body], &integers[body + 1..]),
+        None if integers.len() % 2 == 0 => (&integers[..], &[][..]),
+        None => return Some(cenotaph("a tag without a value")),
+    };
+    if edicts.len() % 4 != 0 {
+        return Some(cenotaph("an edict cut short"));
+    }
+
+    let mut tags: BTreeMap<u128, Vec<u128>> = BTreeMap::new();
+    for pair in fields.chunks(2) {
+        tags.entry(pair[0]).or_default().push(pair[1]);
+    }
+    let first = |tag| tags.get(&tag).map(|values| values[0]);
+    if let Some(tag) = tags.keys().find(|&&tag| tag % 2 == 0 && !KNOWN_EVEN_TAGS.contains(&tag)) {
+        return Some(cenotaph(&format!("unknown even tag {}", tag)));
+    }
+    let flags = first(TAG_FLAGS).unwrap_or(0);
+    if flags & !(FLAG_ETCHING | FLAG_TERMS | FLAG_TURBO) != 0 {
+        return Some(cenotaph("unknown flags"));
+    }
+
+    let mut message = ProtocolMessage::new("");
+    let mut actions = Vec::new();
+    if flags & FLAG_ETCHING != 0 {
+        let name = first(TAG_RUNE).map(|rune| rune_name(rune, first(TAG_SPACERS).unwrap_or(0)));
+        actions.push(match &name {
+            Some(name) => format!("etches {}", name),
+            None => "etches a rune".to_string(),
+        });
+        if let Some(name) = name {
+            message = message.with_field("rune", name);
+        }
+        for (tag, field) in [(TAG_DIVISIBILITY, "divisibility"), (TAG_PREMINE, "premine")] {
+            if let Some(value) = first(tag) {
+                message = message.with_field(field, value);
+            }
+        }
+        if let Some(symbol) = first(TAG_SYMBOL).and_then(|s| char::from_u32(s.try_into().ok()?)) {
+            message = message.with_field("symbol", symbol);
+        }
+        if flags & FLAG_TERMS != 0 {
+            for (tag, field) in [
+                (TAG_CAP, "cap"),
+                (TAG_AMOUNT, "amount"),
+                (TAG_HEIGHT_START, "height_start"),
+                (TAG_HEIGHT_END, "height_end"),
+                (TAG_OFFSET_START, "offset_start"),
+                (TAG_OFFSET_END, "offset_end"),
+            ] {
+                if let Some(value) = first(tag) {
+                    message = message.with_field(field, value);
+                }
+            }
+        }
+    }
+    if let Some(mint) = tags.get(&TAG_MINT).filter(|values| values.len() >= 2) {
+        actions.push(format!("mints {}:{}", mint[0], mint[1]));
+        message = message.with_field("mint", format!("{}:{}", mint[0], mint[1]));
+    }
+    if let Some(pointer) = first(TAG_POINTER) {
+        message = message.with_field("pointer", pointer);
+    }
+    if !edicts.is_empty() {
+        actions.push(format!("{} edict(s)", edicts.len() / 4));
+        message = message.with_field("edicts", edicts.len() / 4);
+    }
+    message.summary = if actions.is_empty() { "Runestone".to_string() } else { format!("Runestone: {}", actions.join(", ")) };
+    Some(message)
+}
+
+const KNOWN_EVEN_TAGS: [u128; 12] = [
+    TAG_BODY, TAG_FLAGS, TAG_RUNE, TAG_PREMINE, TAG_CAP, TAG_AMOUNT,
+    TAG_HEIGHT_START, TAG_HEIGHT_END, TAG_OFFSET_START, TAG_OFFSET_END, TAG_MINT, TAG_POINTER,
+];
+
+fn cenotaph(reason: &str) -> ProtocolMessage {
+    ProtocolMessage::new(format!("Runestone cenotaph ({}): its inputs' runes are burned", reason)).with_field("cenotaph", reason)
+}
+
+// Unsigned LEB128 integers filling `data`, each at most 128 bits
+fn leb128_integers(data: &[u8]) -> Option<Vec<u128>> {
+    let mut integers = Vec::new();
+    let mut bytes = data.iter();
+    while let Some(&byte) = bytes.next() {
+        let mut value = (byte & 0x7f) as u128;
+        let mut shift = 7;
+        let mut last = byte;
+        while last & 0x80 != 0 {
+            last = *bytes.next()?;
+            if shift > 126 || (shift == 126 && last & 0x7c != 0) {
+                return None;
+            }
+            value |= ((last & 0x7f) as u128) << shift;
+            shift += 7;
+        }
+        integers.push(value);
+    }
+    Some(integers)
+}
+
+// Rune names count in bijective base 26 (A, B, ..., Z, AA, ...); bit i of
+// `spacers` puts a • after the i-th letter
+fn rune_name(rune: u128, spacers: u128) -> String {
+    let mut letters = Vec::new();
+    let mut n = rune.saturating_add(1);
+    while n > 0 {
+        letters.push((b'A' + ((n - 1) % 26) as u8) as char);
+        n = (n - 1) / 26;
+    }
+    letters.reverse();
+    let mut name = String::new();
+    for (i, letter) in letters.iter().enumerate() {
+        name.push(*letter);
+        if i + 1 < letters.len() && spacers >> i & 1 == 1 {
+            name.push('•');
+        }
+    }
+    name
+}
+
+// Reports OP_RETURN outputs a protocol decoder recognizes by its prefix;
+// prefix-less matches (a lone 32-byte hash) are left to the timestamp analyzer
+pub struct OpReturnAnalyzer;
+
+impl Analyzer for OpReturnAnalyzer {
+    fn name(&self) -> &'static str {
+        "op_return"
+    }
+
+    fn analyze(&self, tx: &Transaction) -> Vec<Finding> {
+        let registry = OpReturnRegistry::with_defaults();
+        registry.decode_transaction(tx).into_iter()
+            .filter(|m| registry.has_prefix(&m.protocol))
+            .map(|m| Finding::new(self.name(), "op_return_protocol", format!("output #{}: {}", m.output, m.message.summary)).on_output(m.output))
+            .collect()
+    }
+}
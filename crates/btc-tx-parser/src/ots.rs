@@ -0,0 +1,316 @@
+//! OpenTimestamps: calendars aggregate submitted digests into one hash and
+//! commit it in an OP_RETURN. A .ots proof is the list of operations taking
+//! a file's digest to that commitment, through the transaction's txid and up
+//! to a block's merkle root, where a Bitcoin attestation names the height.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use ripemd::Ripemd160;
+use crate::error::ParseError;
+use crate::hexcodec::encode_hex_reversed;
+use crate::parser::Parser;
+use crate::analysis::{Analyzer, Finding};
+use crate::script::ScriptType;
+use crate::types::Transaction;
+
+const MAGIC: &[u8] = b"\x00OpenTimestamps\x00\x00Proof\x00\xbf\x89\xe2\xe8\x84\xe8\x92\x94";
+const BITCOIN_TAG: [u8; 8] = [0x05, 0x88, 0x96, 0x0d, 0x73, 0xd7, 0x19, 0x01];
+const PENDING_TAG: [u8; 8] = [0x83, 0xdf, 0xe3, 0x0d, 0x2e, 0xf9, 0x0c, 0x8e];
+// Limits from the reference implementation: nesting depth, and the size of
+// any message an operation produces
+const MAX_DEPTH: usize = 256;
+const MAX_MESSAGE: usize = 4096;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Op {
+    Sha1,
+    Ripemd160,
+    Sha256,
+    Keccak256,
+    Append(Vec<u8>),
+    Prepend(Vec<u8>),
+    Reverse,
+    Hexlify,
+}
+
+impl Op {
+    fn from_tag(tag: u8, parser: &mut Parser) -> Result<Op, ParseError> {
+        Ok(match tag {
+            0x02 => Op::Sha1,
+            0x03 => Op::Ripemd160,
+            0x08 => Op::Sha256,
+            0x67 => Op::Keccak256,
+            0xf0 => Op::Append(read_varbytes(parser)?),
+            0xf1 => Op::Prepend(read_varbytes(parser)?),
+            0xf2 => Op::Reverse,
+            0xf3 => Op::Hexlify,
+            tag => return Err(ParseError::InvalidTimestampProof(format!("unknown operation 0x{:02x}", tag))),
+        })
+    }
+
+    // The result, or None for the hashes this crate doesn't carry (SHA-1,
+    // Keccak) and results over the size limit
+    fn apply(&self, msg: &[u8]) -> Option<Vec<u8>> {
+        let result = match self {
+            Op::Sha1 | Op::Keccak256 => return None,
+            Op::Ripemd160 => Ripemd160::digest(msg).to_vec(),
+            Op::Sha256 => Sha256::digest(msg).to_vec(),
+            Op::Append(data) => [msg, data].concat(),
+            Op::Prepend(data) => [data, msg].concat(),
+            Op::Reverse => msg.iter().rev().copied().collect(),
+            Op::Hexlify => hex::encode(msg).into_bytes(),
+        };
+        (result.len() <= MAX_MESSAGE).then_some(result)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum OtsAttestation {
+    // the commitment is in the block at this height; its merkle root is the
+    // message at this point of the path
+    Bitcoin { height: u32 },
+    // submitted to a calendar, not yet anchored
+    Pending { uri: String },
+    Unknown { tag: String },
+}
+
+// A node of the proof: attestations of the message here, and operations
+// leading on to further nodes
+#[derive(Debug, Clone, Default)]
+struct Timestamp {
+    attestations: Vec<OtsAttestation>,
+    ops: Vec<(Op, Timestamp)>,
+}
+
+impl Timestamp {
+    fn parse(parser: &mut Parser, depth: usize) -> Result<Timestamp, ParseError> {
+        if depth > MAX_DEPTH {
+            return Err(ParseError::InvalidTimestampProof("proof nested too deeply".to_string()));
+        }
+        let mut stamp = Timestamp::default();
+        // 0xff marks a fork: another branch follows this one
+        let mut tag = parser.read_u8()?;
+        while tag == 0xff {
+            let branch = parser.read_u8()?;
+            stamp.parse_branch(branch, parser, depth)?;
+            tag = parser.read_u8()?;
+        }
+        stamp.parse_branch(tag, parser, depth)?;
+        Ok(stamp)
+    }
+
+    fn parse_branch(&mut self, tag: u8, parser: &mut Parser, depth: usize) -> Result<(), ParseError> {
+        if tag == 0x00 {
+            self.attestations.push(parse_attestation(parser)?);
+        } else {
+            let op = Op::from_tag(tag, parser)?;
+            self.ops.push((op, Timestamp::parse(parser, depth + 1)?));
+        }
+        Ok(())
+    }
+
+    fn attestations(&self, out: &mut Vec<OtsAttestation>) {
+        out.extend(self.attestations.iter().cloned());
+        for (_, next) in &self.ops {
+            next.attestations(out);
+        }
+    }
+
+    // The node where the message becomes `target`, with the messages on the
+    // way there (starting with `msg`)
+    fn find(&self, msg: &[u8], target: &[u8], path: &mut Vec<Vec<u8>>) -> Option<&Timestamp> {
+        path.push(msg.to_vec());
+        if msg == target {
+            return Some(self);
+        }
+        for (op, next) in &self.ops {
+            if let Some(result) = op.apply(msg) {
+                if let Some(found) = next.find(&result, target, path) {
+                    return Some(found);
+                }
+            }
+        }
+        path.pop();
+        None
+    }
+
+    // First Bitcoin attestation below this node, with the attested message
+    fn bitcoin_attestation(&self, msg: &[u8]) -> Option<(u32, Vec<u8>)> {
+        let here = self.attestations.iter().find_map(|a| match a {
+            OtsAttestation::Bitcoin { height } => Some((*height, msg.to_vec())),
+            _ => None,
+        });
+        here.or_else(|| {
+            self.ops.iter().find_map(|(op, next)| next.bitcoin_attestation(&op.apply(msg)?))
+        })
+    }
+}
+
+fn parse_attestation(parser: &mut Parser) -> Result<OtsAttestation, ParseError> {
+    let tag = parser.read_bytes(8)?;
+    let payload = read_varbytes(parser)?;
+    let mut inner = Parser::new(&payload);
+    Ok(match <[u8; 8]>::try_from(tag.as_slice()) {
+        Ok(BITCOIN_TAG) => {
+            let height = read_varuint(&mut inner)?;
+            OtsAttestation::Bitcoin {
+                height: u32::try_from(height).map_err(|_| ParseError::InvalidTimestampProof(format!("block height {} out of range", height)))?,
+            }
+        }
+        Ok(PENDING_TAG) => OtsAttestation::Pending {
+            uri: String::from_utf8(read_varbytes(&mut inner)?)
+                .map_err(|_| ParseError::InvalidTimestampProof("calendar URI is not UTF-8".to_string()))?,
+        },
+        _ => OtsAttestation::Unknown { tag: hex::encode(tag) },
+    })
+}
+
+// LEB128, as every integer in the format
+fn read_varuint(parser: &mut Parser) -> Result<u64, ParseError> {
+    let start = parser.position();
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = parser.read_u8()?;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err(ParseError::InvalidVarInt(start))
+}
+
+fn read_varbytes(parser: &mut Parser) -> Result<Vec<u8>, ParseError> {
+    let len = read_varuint(parser)?;
+    if len > parser.remaining() as u64 {
+        return Err(ParseError::InvalidTimestampProof(format!("{} byte field overruns the proof", len)));
+    }
+    parser.read_bytes(len as usize)
+}
+
+// A detached .ots proof
+#[derive(Debug, Clone)]
+pub struct OtsProof {
+    // hash of the file the proof starts from: "sha256", "sha1", "ripemd160" or "keccak256"
+    pub hash: &'static str,
+    pub digest: Vec<u8>,
+    timestamp: Timestamp,
+}
+
+// An OP_RETURN output that a proof's path goes through
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimestampAnchor {
+    pub output: usize,
+    // the 32-byte OP_RETURN payload (hex)
+    pub commitment: String,
+    // the timestamped file's digest (hex)
+    pub digest: String,
+    // height of the Bitcoin attestation, if the proof has been upgraded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_height: Option<u32>,
+    // the attested merkle root, in display order
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merkle_root: Option<String>,
+}
+
+impl OtsProof {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+        let Some(body) = bytes.strip_prefix(MAGIC) else {
+            return Err(ParseError::InvalidTimestampProof("missing OpenTimestamps header".to_string()));
+        };
+        let mut parser = Parser::new(body);
+        let version = read_varuint(&mut parser)?;
+        if version != 1 {
+            return Err(ParseError::InvalidTimestampProof(format!("unsupported version {}", version)));
+        }
+        let (hash, len) = match parser.read_u8()? {
+            0x02 => ("sha1", 20),
+            0x03 => ("ripemd160", 20),
+            0x08 => ("sha256", 32),
+            0x67 => ("keccak256", 32),
+            tag => return Err(ParseError::InvalidTimestampProof(format!("unknown file hash 0x{:02x}", tag))),
+        };
+        let digest = parser.read_bytes(len)?;
+        let timestamp = Timestamp::parse(&mut parser, 0)?;
+        match parser.remaining() {
+            0 => Ok(OtsProof { hash, digest, timestamp }),
+            trailing => Err(ParseError::TrailingData(trailing)),
+        }
+    }
+
+    // Every attestation in the proof, depth first
+    pub fn attestations(&self) -> Vec<OtsAttestation> {
+        let mut attestations = Vec::new();
+        self.timestamp.attestations(&mut attestations);
+        attestations
+    }
+
+    // Follow the proof from the digest to this transaction; None if no path
+    // reaches it through one of its OP_RETURN commitments
+    pub fn anchor_in(&self, tx: &Transaction) -> Option<TimestampAnchor> {
+        let tx_bytes = tx.to_bytes_without_witness();
+        let mut path = Vec::new();
+        let node = self.timestamp.find(&self.digest, &tx_bytes, &mut path)?;
+        // the last 32-byte message before the transaction that one of its
+        // outputs commits to
+        let (output, commitment) = path.iter().rev().find_map(|msg| {
+            let output = tx.timestamp_commitments().into_iter()
+                .find(|&i| tx.outputs[i].script_pubkey.bytes[2..] == msg[..])?;
+            Some((output, msg))
+        })?;
+        let attestation = node.bitcoin_attestation(&tx_bytes);
+        Some(TimestampAnchor {
+            output,
+            commitment: hex::encode(commitment),
+            digest: hex::encode(&self.digest),
+            block_height: attestation.as_ref().map(|(height, _)| *height),
+            merkle_root: attestation.map(|(_, root)| encode_hex_reversed(&root)),
+        })
+    }
+}
+
+impl Transaction {
+    // OP_RETURN outputs carrying exactly one 32-byte push, the form
+    // OpenTimestamps calendars commit in
+    pub fn timestamp_commitments(&self) -> Vec<usize> {
+        self.outputs.iter()
+            .filter(|o| o.script_type == ScriptType::OpReturn)
+            .filter(|o| o.script_pubkey.bytes.len() == 34 && o.script_pubkey.bytes[1] == 0x20)
+            .map(|o| o.index)
+            .collect()
+    }
+}
+
+// Labels OP_RETURN outputs that look like timestamp commitments; only a
+// proof (see `OtsProof::anchor_in`) shows that one is
+pub struct TimestampAnalyzer;
+
+impl Analyzer for TimestampAnalyzer {
+    fn name(&self) -> &'static str {
+        "timestamp"
+    }
+
+    fn analyze(&self, tx: &Transaction) -> Vec<Finding> {
+        if tx.inputs.iter().any(|i| i.is_coinbase) {
+            return Vec::new();
+        }
+        tx.timestamp_commitments().into_iter()
+            .map(|output| {
+                let mut evidence = vec![format!("output #{} is OP_RETURN with a single 32-byte push", output)];
+                let mut confidence = 0.4;
+                // calendars spend one coin back to themselves beside the commitment
+                if tx.inputs.len() == 1 && tx.outputs.len() == 2 {
+                    evidence.push("one input and one change output beside it".to_string());
+                    confidence += 0.15;
+                }
+                Finding::heuristic(
+                    self.name(),
+                    "timestamp_commitment",
+                    format!("output #{} may be an OpenTimestamps commitment (a 32-byte hash)", output),
+                    confidence,
+                    evidence,
+                )
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,113 @@
+//! P2P wire messages: a 24-byte header (magic, command, payload length,
+//! checksum) followed by the payload, as captured off the network
+
+use serde::{Deserialize, Serialize};
+use crate::hash::sha256d;
+use crate::block::Block;
+use crate::compact::CompactBlock;
+use crate::network::Network;
+use crate::error::ParseError;
+use crate::parser::Parser;
+use crate::types::Transaction;
+
+const HEADER_SIZE: usize = 24;
+
+// Bitcoin Core's MAX_SIZE for a single message
+const MAX_PAYLOAD_SIZE: usize = 32 * 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct P2pMessage {
+    // message start bytes (hex)
+    pub magic: String,
+    // network the magic belongs to, if known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub network: Option<String>,
+    // e.g. "tx", "block"
+    pub command: String,
+    // payload length from the header
+    pub length: usize,
+    // first 4 bytes of sha256d(payload), as sent (hex)
+    pub checksum: String,
+    pub checksum_valid: bool,
+    #[serde(skip)]
+    pub payload: Vec<u8>,
+}
+
+// Decoded payload of the message types the parser understands
+#[derive(Debug, Clone)]
+pub enum WirePayload {
+    Tx(Box<Transaction>),
+    Block(Box<Block>),
+    CompactBlock(Box<CompactBlock>),
+    // any other command; the raw payload is still on the message
+    Unsupported,
+}
+
+impl P2pMessage {
+    pub fn from_hex(hex_str: &str) -> Result<Self, ParseError> {
+        Self::from_bytes(&hex::decode(hex_str.trim())?)
+    }
+
+    // Exactly one message; bytes after the payload are an error
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+        let mut parser = Parser::new(bytes);
+        let message = read_message(&mut parser)?;
+        match parser.remaining() {
+            0 => Ok(message),
+            trailing => Err(ParseError::TrailingData(trailing)),
+        }
+    }
+
+    // Decode `tx`, `block` and `cmpctblock` payloads (a bad checksum does not
+    // stop this, so damaged captures can still be inspected)
+    pub fn decode(&self) -> Result<WirePayload, ParseError> {
+        match self.command.as_str() {
+            "tx" => Ok(WirePayload::Tx(Box::new(Transaction::from_bytes_strict(&self.payload)?))),
+            "block" => Ok(WirePayload::Block(Box::new(Block::from_bytes(&self.payload)?))),
+            "cmpctblock" => Ok(WirePayload::CompactBlock(Box::new(CompactBlock::from_bytes(&self.payload)?))),
+            _ => Ok(WirePayload::Unsupported),
+        }
+    }
+}
+
+// Every message in a concatenated stream, e.g. one direction of a TCP session
+pub fn parse_messages(bytes: &[u8]) -> Result<Vec<P2pMessage>, ParseError> {
+    let mut parser = Parser::new(bytes);
+    let mut messages = Vec::new();
+    while parser.remaining() > 0 {
+        messages.push(read_message(&mut parser)?);
+    }
+    Ok(messages)
+}
+
+fn read_message(parser: &mut Parser) -> Result<P2pMessage, ParseError> {
+    let start = parser.position();
+    let header = parser.read_bytes(HEADER_SIZE)?;
+    let magic: [u8; 4] = header[..4].try_into().unwrap_or_default();
+
+    // NUL-padded ASCII
+    let command_bytes = &header[4..16];
+    let end = command_bytes.iter().position(|b| *b == 0).unwrap_or(command_bytes.len());
+    if !command_bytes[..end].iter().all(|b| b.is_ascii_graphic()) || command_bytes[end..].iter().any(|b| *b != 0) {
+        return Err(ParseError::InvalidMessage(format!("malformed command {} at offset {}", hex::encode(command_bytes), start)));
+    }
+    let command = String::from_utf8_lossy(&command_bytes[..end]).into_owned();
+
+    let length = u32::from_le_bytes(header[16..20].try_into().unwrap_or_default()) as usize;
+    if length > MAX_PAYLOAD_SIZE {
+        return Err(ParseError::InvalidMessage(format!("'{}' message claims a {} byte payload", command, length)));
+    }
+    let checksum = &header[20..24];
+    let payload = parser.read_bytes(length)?;
+    debug!(%command, length, offset = start, "read p2p message");
+
+    Ok(P2pMessage {
+        magic: hex::encode(magic),
+        network: Network::from_magic(magic).map(|network| network.to_string()),
+        command,
+        length,
+        checksum: hex::encode(checksum),
+        checksum_valid: sha256d(&payload)[..4] == *checksum,
+        payload,
+    })
+}
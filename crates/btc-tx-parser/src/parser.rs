@@ -1,29 +1,56 @@
 //! Bitcoin transaction parser
-use crate::address::{derive_address, sha256d};
+use crate::address::derive_address;
+use crate::hash::sha256d;
+use crate::amount::Amount;
 use crate::error::ParseError;
+use crate::hexcodec::{encode_hex, encode_hex_reversed};
+use crate::locktime::LockTime;
+use crate::multisig::MultisigInfo;
+use crate::sequence::Sequence;
 use crate::script::{detect_script_type, script_to_asm};
+use crate::spans::FieldSpan;
 use crate::types::*;
 
 
 pub struct Parser<'a> {
     data: &'a [u8],
     pos: usize,
+    // byte ranges of parsed fields, recorded only when enabled
+    spans: Option<Vec<FieldSpan>>,
 }
 
 impl<'a> Parser<'a> {
     pub fn new(data: &'a [u8]) -> Self {
-        Self { data, pos: 0 }
+        Self { data, pos: 0, spans: None }
     }
 
-    fn position(&self) -> usize {
+    // Parser that also records the byte range of every field it reads
+    pub fn with_spans(data: &'a [u8]) -> Self {
+        Self { data, pos: 0, spans: Some(Vec::new()) }
+    }
+
+    // Spans recorded so far (empty unless created with `with_spans`)
+    pub fn take_spans(&mut self) -> Vec<FieldSpan> {
+        self.spans.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    // Record [start, current position) under a field name; the name is only
+    // built when spans are enabled
+    fn mark(&mut self, start: usize, field: impl FnOnce() -> String) {
+        if let Some(spans) = &mut self.spans {
+            spans.push(FieldSpan { field: field(), start, end: self.pos });
+        }
+    }
+
+    pub(crate) fn position(&self) -> usize {
         self.pos
     }
 
-    fn remaining(&self) -> usize {
+    pub(crate) fn remaining(&self) -> usize {
         self.data.len().saturating_sub(self.pos)
     }
 
-    fn read_u8(&mut self) -> Result<u8, ParseError> {
+    pub(crate) fn read_u8(&mut self) -> Result<u8, ParseError> {
         if self.pos >= self.data.len() {
             return Err(ParseError::UnexpectedEof {
                 position: self.pos,
@@ -47,7 +74,7 @@ impl<'a> Parser<'a> {
         Ok(u16::from_le_bytes(bytes))
     }
 
-    fn read_u32_le(&mut self) -> Result<u32, ParseError> {
+    pub(crate) fn read_u32_le(&mut self) -> Result<u32, ParseError> {
         if self.pos + 4 > self.data.len() {
             return Err(ParseError::UnexpectedEof {
                 position: self.pos,
@@ -81,7 +108,7 @@ impl<'a> Parser<'a> {
         Ok(i32::from_le_bytes(bytes))
     }
 
-    fn read_u64_le(&mut self) -> Result<u64, ParseError> {
+    pub(crate) fn read_u64_le(&mut self) -> Result<u64, ParseError> {
         if self.pos + 8 > self.data.len() {
             return Err(ParseError::UnexpectedEof {
                 position: self.pos,
@@ -112,22 +139,32 @@ impl<'a> Parser<'a> {
         }
     }
 
-    fn read_bytes(&mut self, n: usize) -> Result<Vec<u8>, ParseError> {
-        if self.pos + n > self.data.len() {
+    pub(crate) fn read_bytes(&mut self, n: usize) -> Result<Vec<u8>, ParseError> {
+        self.read_slice(n).map(<[u8]>::to_vec)
+    }
+
+    // Like `read_bytes`, borrowing from the input
+    pub(crate) fn read_slice(&mut self, n: usize) -> Result<&'a [u8], ParseError> {
+        if n > self.remaining() {
             return Err(ParseError::UnexpectedEof {
                 position: self.pos,
                 expected: n,
             });
         }
-        let bytes = self.data[self.pos..self.pos + n].to_vec();
+        let bytes = &self.data[self.pos..self.pos + n];
         self.pos += n;
         Ok(bytes)
     }
 
+    // Input from `start` up to the current position
+    pub(crate) fn slice_from(&self, start: usize) -> &'a [u8] {
+        &self.data[start..self.pos]
+    }
+
     pub(crate) fn read_hash(&mut self) -> Result<String, ParseError> {
         let bytes = self.read_bytes(32)?;
         let reversed: Vec<u8> = bytes.into_iter().rev().collect();
-        Ok(hex::encode(reversed))
+        Ok(encode_hex(&reversed))
     }
 
     // Main transaction parsing function
@@ -135,25 +172,34 @@ impl<'a> Parser<'a> {
         let start_pos = self.position();
 
         let version = self.read_i32_le()?;
+        self.mark(start_pos, || "version".to_string());
 
         let (is_segwit, marker_flag_size) = self.check_segwit()?;
+        trace!(version, is_segwit, "parsed header");
 
         // Number of inputs
+        let count_start = self.position();
         let input_count = self.read_varint()?;
+        self.mark(count_start, || "input_count".to_string());
+        trace!(input_count, "reading inputs");
         if input_count == 0 && !is_segwit {
             return Err(ParseError::InvalidTransaction(
                 "Transaction has no inputs".to_string(),
             ));
         }
 
-        // Parse inputs
-        let mut inputs = Vec::with_capacity(input_count as usize);
+        // Parse inputs; an input takes at least 41 bytes, so a bogus count
+        // can't reserve much
+        let mut inputs = Vec::with_capacity((input_count as usize).min(self.remaining() / 41));
         for i in 0..input_count {
             inputs.push(self.parse_input(i as usize)?);
         }
 
         // Number of outputs
+        let count_start = self.position();
         let output_count = self.read_varint()?;
+        self.mark(count_start, || "output_count".to_string());
+        trace!(output_count, "reading outputs");
         if output_count == 0 {
             return Err(ParseError::InvalidTransaction(
                 "Transaction has no outputs".to_string(),
@@ -161,7 +207,7 @@ impl<'a> Parser<'a> {
         }
 
         // Parse outputs
-        let mut outputs = Vec::with_capacity(output_count as usize);
+        let mut outputs = Vec::with_capacity((output_count as usize).min(self.remaining() / 9));
         for i in 0..output_count {
             outputs.push(self.parse_output(i as usize)?);
         }
@@ -169,22 +215,33 @@ impl<'a> Parser<'a> {
         // Parse witness data if SegWit
         if is_segwit {
             for input in &mut inputs {
-                input.witness = Some(self.parse_witness()?);
+                input.witness = Some(self.parse_witness(input.index)?);
             }
         }
 
+        for input in &mut inputs {
+            input.signatures = input.parse_signatures();
+            input.taproot = input.taproot_spend_info();
+            input.script_sig_parts = input.script_sig_parts();
+            input.witness_script = input.witness_script_info();
+            input.address = input.spent_address();
+            input.coinbase = input.coinbase_info();
+        }
+
+        let locktime_start = self.position();
         let locktime = self.read_u32_le()?;
+        self.mark(locktime_start, || "locktime".to_string());
 
         // Calculate transaction IDs
         let raw_size = self.position() - start_pos;
         let tx_data = &self.data[start_pos..self.position()];
 
         // Calculate txid
-        let txid = self.calculate_txid(tx_data, is_segwit, version, &inputs, &outputs, locktime);
+        let txid = self.calculate_txid(tx_data, version, &inputs, &outputs, locktime);
 
         // wtxid is hash of full serialization
         let wtxid_hash = sha256d(tx_data);
-        let wtxid: String = wtxid_hash.iter().rev().map(|b| format!("{:02x}", b)).collect();
+        let wtxid = encode_hex_reversed(&wtxid_hash);
 
         let weight = if is_segwit {
             let base_size = raw_size - marker_flag_size - self.witness_size(&inputs);
@@ -193,27 +250,29 @@ impl<'a> Parser<'a> {
             raw_size * 4
         };
 
-        let total_output_satoshis = outputs.iter().map(|o| o.value).sum();
-        let total_output_btc = Transaction::satoshis_to_btc(total_output_satoshis);
+        let total_output_satoshis: Amount = outputs.iter().map(|o| o.value).sum();
+        debug!(%txid, inputs = inputs.len(), outputs = outputs.len(), raw_size, weight, "parsed transaction");
 
         Ok(Transaction {
             version,
             is_segwit,
             inputs,
             outputs,
-            locktime,
+            locktime: LockTime::from_consensus(locktime),
             txid,
             wtxid,
             raw_size,
             weight,
             total_output_satoshis,
-            total_output_btc,
+            #[cfg(feature = "float-btc")]
+            total_output_btc: total_output_satoshis.to_btc(),
             fee_satoshis: None,
+            #[cfg(feature = "float-btc")]
             fee_btc: None,
         })
     }
 
-    fn check_segwit(&mut self) -> Result<(bool, usize), ParseError> {
+    pub(crate) fn check_segwit(&mut self) -> Result<(bool, usize), ParseError> {
         // Save position
         let saved_pos = self.pos;
         if self.remaining() >= 2 {
@@ -221,6 +280,7 @@ impl<'a> Parser<'a> {
             let flag = self.read_u8()?;
 
             if marker == 0x00 && flag == 0x01 {
+                self.mark(saved_pos, || "marker_flag".to_string());
                 return Ok((true, 2));
             }
         }
@@ -230,24 +290,27 @@ impl<'a> Parser<'a> {
 
     // Parse single transaction input
     fn parse_input(&mut self, index: usize) -> Result<TxInput, ParseError> {
-        let txid = self.read_hash()?;
+        let start = self.position();
+        let txid = Txid(self.read_bytes(32)?.try_into().unwrap());
+        self.mark(start, || format!("inputs[{}].txid", index));
         let vout = self.read_u32_le()?;
-        let script_len = self.read_varint()? as usize;
-        let script_bytes = self.read_bytes(script_len)?;
+        self.mark(start + 32, || format!("inputs[{}].vout", index));
+        let script_bytes = self.read_sized(|| format!("inputs[{}].script_sig", index))?;
+        let sequence_start = self.position();
         let sequence = self.read_u32_le()?;
+        self.mark(sequence_start, || format!("inputs[{}].sequence", index));
 
         // Check if this is a coinbase input
-        let is_coinbase = txid == "0000000000000000000000000000000000000000000000000000000000000000"
-            && vout == 0xffffffff;
+        let is_coinbase = txid.is_null() && vout == 0xffffffff;
 
         let script_sig = Script {
-            hex: hex::encode(&script_bytes),
             asm: if is_coinbase {
                 format!("[coinbase] {}", hex::encode(&script_bytes))
             } else {
                 script_to_asm(&script_bytes)
             },
             size: script_bytes.len(),
+            bytes: script_bytes,
         };
 
         Ok(TxInput {
@@ -255,59 +318,88 @@ impl<'a> Parser<'a> {
             txid,
             vout,
             script_sig,
-            sequence,
+            sequence: Sequence(sequence),
             witness: None,
             value: None,
             is_coinbase,
+            signatures: Vec::new(),
+            taproot: None,
+            script_sig_parts: None,
+            witness_script: None,
+            address: None,
+            coinbase: None,
+            note: None,
         })
     }
 
     // Parse single transaction output
     fn parse_output(&mut self, index: usize) -> Result<TxOutput, ParseError> {
+        let start = self.position();
         let value = self.read_u64_le()?;
+        self.mark(start, || format!("outputs[{}].value", index));
 
         // ScriptPubKey length and data
-        let script_len = self.read_varint()? as usize;
-        let script_bytes = self.read_bytes(script_len)?;
+        let script_bytes = self.read_sized(|| format!("outputs[{}].script_pubkey", index))?;
 
         // Detect script type
         let script_type = detect_script_type(&script_bytes);
+        trace!(index, value, ?script_type, "parsed output");
 
         let address = derive_address(&script_bytes, &script_type);
+        let multisig = MultisigInfo::from_script(&script_bytes);
 
         let script_pubkey = Script {
-            hex: hex::encode(&script_bytes),
             asm: script_to_asm(&script_bytes),
             size: script_bytes.len(),
+            bytes: script_bytes,
         };
 
         Ok(TxOutput {
             index,
-            value,
-            value_btc: Transaction::satoshis_to_btc(value),
+            value: Amount::from_sat(value),
+            #[cfg(feature = "float-btc")]
+            value_btc: Amount::from_sat(value).to_btc(),
             script_pubkey,
             script_type,
             address,
+            multisig,
+            spend: None,
+            note: None,
         })
     }
 
-    fn parse_witness(&mut self) -> Result<Vec<String>, ParseError> {
+    fn parse_witness(&mut self, index: usize) -> Result<Vec<Vec<u8>>, ParseError> {
+        let start = self.position();
         let stack_items = self.read_varint()? as usize;
-        let mut witness = Vec::with_capacity(stack_items);
+        self.mark(start, || format!("inputs[{}].witness_count", index));
+        let mut witness = Vec::with_capacity(stack_items.min(self.remaining()));
 
-        for _ in 0..stack_items {
-            let item_len = self.read_varint()? as usize;
-            let item = self.read_bytes(item_len)?;
-            witness.push(hex::encode(item));
+        for j in 0..stack_items {
+            let item = self.read_sized(|| format!("inputs[{}].witness[{}]", index, j))?;
+            witness.push(item);
         }
 
         Ok(witness)
     }
 
+    // Varint length prefix followed by that many bytes; spans are recorded as
+    // "<field>_len" and "<field>"
+    fn read_sized(&mut self, field: impl FnOnce() -> String) -> Result<Vec<u8>, ParseError> {
+        let len_start = self.position();
+        let len = self.read_varint()? as usize;
+        let data_start = self.position();
+        let bytes = self.read_bytes(len)?;
+        if let Some(spans) = &mut self.spans {
+            let field = field();
+            spans.push(FieldSpan { field: format!("{}_len", field), start: len_start, end: data_start });
+            spans.push(FieldSpan { field, start: data_start, end: self.pos });
+        }
+        Ok(bytes)
+    }
+
     fn calculate_txid(
         &self,
         _full_data: &[u8],
-        is_segwit: bool,
         version: i32,
         inputs: &[TxInput],
         outputs: &[TxOutput],
@@ -323,23 +415,17 @@ impl<'a> Parser<'a> {
 
         // Inputs (without witness)
         for input in inputs {
-            let txid_bytes: Vec<u8> = hex::decode(&input.txid)
-                .unwrap()
-                .into_iter()
-                .rev()
-                .collect();
-            serialized.extend_from_slice(&txid_bytes);
+            serialized.extend_from_slice(input.txid.as_bytes());
 
             // Vout
             serialized.extend_from_slice(&input.vout.to_le_bytes());
 
             // ScriptSig
-            let script_bytes = hex::decode(&input.script_sig.hex).unwrap();
-            Self::write_varint(&mut serialized, script_bytes.len() as u64);
-            serialized.extend_from_slice(&script_bytes);
+            Self::write_varint(&mut serialized, input.script_sig.bytes.len() as u64);
+            serialized.extend_from_slice(&input.script_sig.bytes);
 
             // Sequence
-            serialized.extend_from_slice(&input.sequence.to_le_bytes());
+            serialized.extend_from_slice(&input.sequence.0.to_le_bytes());
         }
 
         // Output count
@@ -348,27 +434,20 @@ impl<'a> Parser<'a> {
         // Outputs
         for output in outputs {
             // Value
-            serialized.extend_from_slice(&output.value.to_le_bytes());
+            serialized.extend_from_slice(&output.value.to_sat().to_le_bytes());
 
             // ScriptPubKey
-            let script_bytes = hex::decode(&output.script_pubkey.hex).unwrap();
-            Self::write_varint(&mut serialized, script_bytes.len() as u64);
-            serialized.extend_from_slice(&script_bytes);
+            Self::write_varint(&mut serialized, output.script_pubkey.bytes.len() as u64);
+            serialized.extend_from_slice(&output.script_pubkey.bytes);
         }
 
         // Locktime
         serialized.extend_from_slice(&locktime.to_le_bytes());
 
-        let hash = sha256d(&serialized);
-
-        if is_segwit {
-            hash.iter().rev().map(|b| format!("{:02x}", b)).collect()
-        } else {
-            hash.iter().rev().map(|b| format!("{:02x}", b)).collect()
-        }
+        encode_hex_reversed(&sha256d(&serialized))
     }
 
-    fn write_varint(buf: &mut Vec<u8>, n: u64) {
+    pub(crate) fn write_varint(buf: &mut Vec<u8>, n: u64) {
         if n < 0xfd {
             buf.push(n as u8);
         } else if n <= 0xffff {
@@ -390,16 +469,15 @@ impl<'a> Parser<'a> {
                 // Count varint for number of items
                 size += Self::varint_size(witness.len() as u64);
                 for item in witness {
-                    let item_bytes = hex::decode(item).unwrap_or_default();
-                    size += Self::varint_size(item_bytes.len() as u64);
-                    size += item_bytes.len();
+                    size += Self::varint_size(item.len() as u64);
+                    size += item.len();
                 }
             }
         }
         size
     }
 
-    fn varint_size(n: u64) -> usize {
+    pub(crate) fn varint_size(n: u64) -> usize {
         if n < 0xfd {
             1
         } else if n <= 0xffff {
@@ -0,0 +1,63 @@
+//! Large data carried by a transaction: witness items and script pushes such
+//! as inscription envelopes or OP_RETURN blobs
+
+use serde::{Deserialize, Serialize};
+use crate::hash::sha256;
+use crate::script::{instructions, Instruction};
+use crate::types::Transaction;
+
+// One blob of data and where it sits
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Payload {
+    // field path, named like the field spans: "inputs[0].witness[2]",
+    // "outputs[1].script_pubkey.push[0]"
+    pub field: String,
+    pub size: usize,
+    // single SHA-256 of the data (hex)
+    pub sha256: String,
+    #[serde(skip)]
+    pub data: Vec<u8>,
+}
+
+impl Payload {
+    fn new(field: String, data: Vec<u8>) -> Self {
+        Payload { field, size: data.len(), sha256: hex::encode(sha256(&data)), data }
+    }
+}
+
+impl Transaction {
+    // Witness items and script pushes of at least `min_size` bytes, in
+    // serialization order
+    pub fn payloads(&self, min_size: usize) -> Vec<Payload> {
+        let mut payloads = Vec::new();
+
+        for input in &self.inputs {
+            script_pushes(&input.script_sig.bytes, min_size, &format!("inputs[{}].script_sig", input.index), &mut payloads);
+        }
+        for output in &self.outputs {
+            script_pushes(&output.script_pubkey.bytes, min_size, &format!("outputs[{}].script_pubkey", output.index), &mut payloads);
+        }
+        for input in &self.inputs {
+            for (i, item) in input.witness.iter().flatten().enumerate() {
+                if item.len() >= min_size {
+                    payloads.push(Payload::new(format!("inputs[{}].witness[{}]", input.index, i), item.clone()));
+                }
+            }
+        }
+
+        payloads
+    }
+}
+
+fn script_pushes(script: &[u8], min_size: usize, field: &str, payloads: &mut Vec<Payload>) {
+    let pushes = instructions(script).unwrap_or_default().into_iter()
+        .filter_map(|ins| match ins {
+            Instruction::Push(data) => Some(data),
+            Instruction::Op(_) => None,
+        });
+    for (i, data) in pushes.enumerate() {
+        if data.len() >= min_size {
+            payloads.push(Payload::new(format!("{}.push[{}]", field, i), data.to_vec()));
+        }
+    }
+}
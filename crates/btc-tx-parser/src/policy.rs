@@ -0,0 +1,167 @@
+//! Organizational policy profiles (TOML) and transaction checks against them
+
+use crate::script::ScriptType;
+use crate::types::{Transaction, TxOutput};
+use serde::{Deserialize, Serialize};
+
+// Policy profile, as the CLI loads it from TOML
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyProfile {
+    // profile name for reports
+    #[serde(default)]
+    pub name: Option<String>,
+    // output script types that may be paid to (e.g. "p2wpkh", "p2tr")
+    #[serde(default)]
+    pub allowed_script_types: Option<Vec<ScriptType>>,
+    // maximum fee rate in sat/vB
+    #[serde(default)]
+    pub max_feerate: Option<f64>,
+    // minimum fee rate in sat/vB
+    #[serde(default)]
+    pub min_feerate: Option<f64>,
+    // require opt-in RBF signaling
+    #[serde(default)]
+    pub require_rbf: bool,
+    // dust rules
+    #[serde(default)]
+    pub dust: DustRules,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DustRules {
+    // reject outputs below Bitcoin Core's standard dust threshold (3 sat/vB)
+    #[serde(default)]
+    pub standard: bool,
+    // reject outputs below this many satoshis
+    #[serde(default)]
+    pub min_output_value: Option<u64>,
+}
+
+// Outcome of a single rule
+#[derive(Debug, Serialize)]
+pub struct RuleResult {
+    pub rule: String,
+    pub status: RuleStatus,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RuleStatus {
+    Pass,
+    Fail,
+    Skipped,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PolicyReport {
+    pub profile: Option<String>,
+    pub txid: String,
+    pub passed: bool,
+    pub results: Vec<RuleResult>,
+}
+
+impl PolicyProfile {
+    pub fn check(&self, tx: &Transaction) -> PolicyReport {
+        let mut results = Vec::new();
+
+        if let Some(allowed) = &self.allowed_script_types {
+            for output in &tx.outputs {
+                let ok = allowed.contains(&output.script_type);
+                results.push(RuleResult {
+                    rule: "allowed_script_types".to_string(),
+                    status: if ok { RuleStatus::Pass } else { RuleStatus::Fail },
+                    detail: format!("output #{} is {}", output.index, output.script_type),
+                });
+            }
+        }
+
+        if self.max_feerate.is_some() || self.min_feerate.is_some() {
+            match tx.fee_satoshis {
+                Some(fee) => {
+                    let feerate = fee.to_sat() as f64 / tx.vsize() as f64;
+                    if let Some(max) = self.max_feerate {
+                        results.push(RuleResult {
+                            rule: "max_feerate".to_string(),
+                            status: if feerate <= max { RuleStatus::Pass } else { RuleStatus::Fail },
+                            detail: format!("{:.2} sat/vB (max {:.2})", feerate, max),
+                        });
+                    }
+                    if let Some(min) = self.min_feerate {
+                        results.push(RuleResult {
+                            rule: "min_feerate".to_string(),
+                            status: if feerate >= min { RuleStatus::Pass } else { RuleStatus::Fail },
+                            detail: format!("{:.2} sat/vB (min {:.2})", feerate, min),
+                        });
+                    }
+                }
+                None => results.push(RuleResult {
+                    rule: "feerate".to_string(),
+                    status: RuleStatus::Skipped,
+                    detail: "fee unknown; supply --input-values or --fetch-prevouts".to_string(),
+                }),
+            }
+        }
+
+        if self.require_rbf {
+            let signals = tx.is_rbf();
+            results.push(RuleResult {
+                rule: "require_rbf".to_string(),
+                status: if signals { RuleStatus::Pass } else { RuleStatus::Fail },
+                detail: if signals {
+                    "signals opt-in RBF".to_string()
+                } else {
+                    "no input sequence below 0xfffffffe".to_string()
+                },
+            });
+        }
+
+        for output in tx.outputs.iter().filter(|o| o.script_type != ScriptType::OpReturn) {
+            if self.dust.standard {
+                let threshold = standard_dust_threshold(output);
+                results.push(RuleResult {
+                    rule: "dust.standard".to_string(),
+                    status: if output.value.to_sat() >= threshold { RuleStatus::Pass } else { RuleStatus::Fail },
+                    detail: format!("output #{}: {} sats (threshold {})", output.index, output.value.to_sat(), threshold),
+                });
+            }
+            if let Some(min) = self.dust.min_output_value {
+                results.push(RuleResult {
+                    rule: "dust.min_output_value".to_string(),
+                    status: if output.value.to_sat() >= min { RuleStatus::Pass } else { RuleStatus::Fail },
+                    detail: format!("output #{}: {} sats (min {})", output.index, output.value.to_sat(), min),
+                });
+            }
+        }
+
+        PolicyReport {
+            profile: self.name.clone(),
+            txid: tx.txid.clone(),
+            passed: results.iter().all(|r| r.status != RuleStatus::Fail),
+            results,
+        }
+    }
+}
+
+// Bitcoin Core's GetDustThreshold at the default 3 sat/vB dust relay fee
+pub fn standard_dust_threshold(output: &TxOutput) -> u64 {
+    let script_len = output.script_pubkey.size as u64;
+    let output_size = 8 + varint_size(script_len) + script_len;
+    let spend_size = match output.script_type {
+        // outpoint + witness-discounted signature/pubkey + sequence
+        ScriptType::P2WPKH | ScriptType::P2WSH | ScriptType::P2TR | ScriptType::WitnessUnknown => 32 + 4 + 1 + (107 / 4) + 4,
+        _ => 32 + 4 + 1 + 107 + 4,
+    };
+    (output_size + spend_size) * 3
+}
+
+fn varint_size(n: u64) -> u64 {
+    match n {
+        0..=0xfc => 1,
+        0xfd..=0xffff => 3,
+        0x1_0000..=0xffff_ffff => 5,
+        _ => 9,
+    }
+}
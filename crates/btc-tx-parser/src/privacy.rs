@@ -0,0 +1,260 @@
+//! Privacy metrics: round payment amounts, equal-value outputs, change
+//! links and Boltzmann-style entropy over the input/output links
+
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use crate::amount::Amount;
+use crate::analysis::{Analyzer, Finding};
+use crate::provenance::ProvenanceLink;
+use crate::types::Transaction;
+
+// Outputs that are multiples of this many sats (0.0001 BTC) read as round,
+// human-chosen amounts
+const MIN_ROUND_UNIT: u64 = 10_000;
+
+// Entropy is enumerated exhaustively, so it is only computed for small
+// transactions
+pub const MAX_ENTROPY_INPUTS: usize = 6;
+pub const MAX_ENTROPY_OUTPUTS: usize = 6;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrivacyReport {
+    // outputs with a round value, typically payments (the rest is change)
+    pub round_outputs: Vec<RoundOutput>,
+    // two or more outputs sharing a value, as in coinjoins
+    pub equal_outputs: Vec<EqualOutputs>,
+    // needs every input value; None for coinbases and large transactions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entropy: Option<Entropy>,
+    // outputs that pay back to an input's key, script or script type
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub change_links: Vec<ProvenanceLink>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoundOutput {
+    pub output: usize,
+    pub value: Amount,
+    // largest power of ten (in sats) the value is a multiple of
+    pub unit: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EqualOutputs {
+    pub value: Amount,
+    pub outputs: Vec<usize>,
+}
+
+// Number of ways to read the transaction as independent sub-transactions
+// (input groups each paying their own output group, fee shared among them);
+// 1 interpretation means every input is linked to every output
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Entropy {
+    pub interpretations: u64,
+    // log2 of the interpretations
+    pub bits: f64,
+}
+
+// For each output, the inputs that could have paid for it: the share of the
+// interpretations counted by `Entropy` in which they sit in the same group
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlausibleFlows {
+    pub interpretations: u64,
+    // zero-value outputs are left out, any input could "fund" them
+    pub outputs: Vec<OutputFunders>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutputFunders {
+    pub output: usize,
+    pub value: Amount,
+    // inputs linked in at least one interpretation
+    pub funders: Vec<Funder>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Funder {
+    pub input: usize,
+    // 1.0 means the input paid for the output in every interpretation
+    pub probability: f64,
+}
+
+impl Transaction {
+    pub fn privacy(&self) -> PrivacyReport {
+        let round_outputs = self.outputs.iter()
+            .filter_map(|output| round_unit(output.value.to_sat()).map(|unit| RoundOutput { output: output.index, value: output.value, unit }))
+            .collect();
+
+        let mut by_value: BTreeMap<Amount, Vec<usize>> = BTreeMap::new();
+        for output in self.outputs.iter().filter(|o| o.value > Amount::ZERO) {
+            by_value.entry(output.value).or_default().push(output.index);
+        }
+        let equal_outputs = by_value.into_iter()
+            .filter(|(_, outputs)| outputs.len() > 1)
+            .map(|(value, outputs)| EqualOutputs { value, outputs })
+            .collect();
+
+        PrivacyReport { round_outputs, equal_outputs, entropy: self.entropy(), change_links: self.provenance_links() }
+    }
+
+    // Which inputs could have funded each output, under the naive reading
+    // that any value-consistent grouping is as likely as any other. Same
+    // limits as the entropy.
+    pub fn plausible_flows(&self) -> Option<PlausibleFlows> {
+        let (inputs, outputs) = self.entropy_values()?;
+        let mut links = vec![vec![0u64; inputs.len()]; outputs.len()];
+        let mut interpretations = 0;
+        for_each_interpretation(&inputs, &outputs, &mut |group_of, output_group| {
+            interpretations += 1;
+            for (output, group) in output_group.iter().enumerate() {
+                for (input, input_group) in group_of.iter().enumerate() {
+                    links[output][input] += (input_group == group) as u64;
+                }
+            }
+        });
+
+        let outputs = self.outputs.iter().filter(|o| o.value > Amount::ZERO).zip(links)
+            .map(|(output, counts)| OutputFunders {
+                output: output.index,
+                value: output.value,
+                funders: counts.iter().enumerate()
+                    .filter(|(_, count)| **count > 0)
+                    .map(|(input, count)| Funder { input, probability: *count as f64 / interpretations as f64 })
+                    .collect(),
+            })
+            .collect();
+        Some(PlausibleFlows { interpretations, outputs })
+    }
+
+    fn entropy(&self) -> Option<Entropy> {
+        let (inputs, outputs) = self.entropy_values()?;
+        let mut interpretations = 0;
+        for_each_interpretation(&inputs, &outputs, &mut |_, _| interpretations += 1);
+        Some(Entropy { interpretations, bits: (interpretations as f64).log2() })
+    }
+
+    // Input and non-zero output values, if the transaction is small enough to
+    // enumerate
+    fn entropy_values(&self) -> Option<(Vec<u64>, Vec<u64>)> {
+        if self.inputs.iter().any(|i| i.is_coinbase) {
+            return None;
+        }
+        let inputs: Vec<u64> = self.inputs.iter().map(|i| i.value.map(Amount::to_sat)).collect::<Option<_>>()?;
+        // zero-value outputs (OP_RETURN) could sit in any group and say nothing
+        let outputs: Vec<u64> = self.outputs.iter().map(|o| o.value.to_sat()).filter(|v| *v > 0).collect();
+        if inputs.len() > MAX_ENTROPY_INPUTS || outputs.len() > MAX_ENTROPY_OUTPUTS || outputs.is_empty() {
+            return None;
+        }
+        if inputs.iter().sum::<u64>() < outputs.iter().sum::<u64>() {
+            return None;
+        }
+        Some((inputs, outputs))
+    }
+}
+
+fn round_unit(value: u64) -> Option<u64> {
+    if value == 0 || !value.is_multiple_of(MIN_ROUND_UNIT) {
+        return None;
+    }
+    let mut unit = MIN_ROUND_UNIT;
+    while value.is_multiple_of(unit * 10) {
+        unit *= 10;
+    }
+    Some(unit)
+}
+
+// Every partition of the inputs into groups, times the ways to hand each group
+// a non-empty set of outputs it can afford; `visit` gets each input's and each
+// output's group
+fn for_each_interpretation(inputs: &[u64], outputs: &[u64], visit: &mut dyn FnMut(&[usize], &[usize])) {
+    let mut group_of = vec![0usize; inputs.len()];
+    loop {
+        let groups = group_of.iter().max().map_or(0, |g| g + 1);
+        let mut budgets = vec![0u64; groups];
+        for (value, group) in inputs.iter().zip(&group_of) {
+            budgets[*group] += value;
+        }
+        if groups <= outputs.len() {
+            let mut assignment = Vec::with_capacity(outputs.len());
+            assign_outputs(outputs, &mut budgets, &mut vec![0; groups], &mut assignment, &mut |output_group| {
+                visit(&group_of, output_group)
+            });
+        }
+        if !next_partition(&mut group_of) {
+            return;
+        }
+    }
+}
+
+// Assignments of the remaining outputs leaving no group empty
+fn assign_outputs(
+    outputs: &[u64],
+    budgets: &mut [u64],
+    counts: &mut [usize],
+    assignment: &mut Vec<usize>,
+    visit: &mut dyn FnMut(&[usize]),
+) {
+    let Some((&value, rest)) = outputs.split_first() else {
+        if counts.iter().all(|c| *c > 0) {
+            visit(assignment);
+        }
+        return;
+    };
+    let empty = counts.iter().filter(|c| **c == 0).count();
+    if empty > outputs.len() {
+        return;
+    }
+    for group in 0..budgets.len() {
+        if budgets[group] >= value {
+            budgets[group] -= value;
+            counts[group] += 1;
+            assignment.push(group);
+            assign_outputs(rest, budgets, counts, assignment, visit);
+            assignment.pop();
+            counts[group] -= 1;
+            budgets[group] += value;
+        }
+    }
+}
+
+// Advance a restricted growth string (each element at most one more than the
+// maximum before it); false after the last partition
+fn next_partition(group_of: &mut [usize]) -> bool {
+    for i in (1..group_of.len()).rev() {
+        let max_before = group_of[..i].iter().max().copied().unwrap_or(0);
+        if group_of[i] <= max_before {
+            group_of[i] += 1;
+            group_of[i + 1..].iter_mut().for_each(|g| *g = 0);
+            return true;
+        }
+    }
+    false
+}
+
+// Educational: one finding per output listing the inputs that could have
+// funded it. Not in the default set; register it to see every link.
+pub struct FlowAnalyzer;
+
+impl Analyzer for FlowAnalyzer {
+    fn name(&self) -> &'static str {
+        "flows"
+    }
+
+    fn analyze(&self, tx: &Transaction) -> Vec<Finding> {
+        let Some(flows) = tx.plausible_flows() else {
+            return Vec::new();
+        };
+        flows.outputs.iter()
+            .map(|output| {
+                let funders: Vec<String> = output.funders.iter()
+                    .map(|f| format!("#{} ({:.0}%)", f.input, f.probability * 100.0))
+                    .collect();
+                let message = match output.funders.as_slice() {
+                    [only] => format!("only input #{} could have funded this output", only.input),
+                    _ => format!("could have been funded by inputs {}", funders.join(", ")),
+                };
+                Finding::new(self.name(), "plausible_funders", message).on_output(output.output)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,123 @@
+//! Change and self-transfer hints: outputs paying back to a key, script or
+//! script template that one of the inputs spends from
+
+use serde::{Deserialize, Serialize};
+use crate::address::pubkey_addresses;
+use crate::hash::{hash160, sha256};
+use crate::script::opcodes::OP_0;
+use crate::script::{instructions, is_control_block, revealed_pubkeys, revealed_scripts, Instruction, ScriptType};
+use crate::types::{Transaction, TxInput};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkKind {
+    // the output pays to a key the input revealed
+    SameKey,
+    // the output pays to the redeem or witness script the input revealed
+    SameScript,
+    // the output is the only one of the input's script type
+    SameTemplate,
+}
+
+// A probable input -> output link, i.e. the output is change
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProvenanceLink {
+    pub input: usize,
+    pub output: usize,
+    pub kind: LinkKind,
+    pub confidence: f64,
+    pub detail: String,
+}
+
+impl Transaction {
+    // Links strongest first; a pair gets at most one link
+    pub fn provenance_links(&self) -> Vec<ProvenanceLink> {
+        let mut links: Vec<ProvenanceLink> = Vec::new();
+        let spends: Vec<&TxInput> = self.inputs.iter().filter(|i| !i.is_coinbase).collect();
+
+        for input in &spends {
+            for pubkey in revealed_pubkeys(input) {
+                let addresses = hex::decode(&pubkey).map(|key| pubkey_addresses(&key)).unwrap_or_default();
+                for output in &self.outputs {
+                    let Some(address) = output.address.as_ref() else { continue };
+                    if let Some(form) = addresses.iter().find(|a| a.mainnet == address.mainnet) {
+                        links.push(ProvenanceLink {
+                            input: input.index,
+                            output: output.index,
+                            kind: LinkKind::SameKey,
+                            confidence: 0.95,
+                            detail: format!("pays {} of the key the input revealed", form.address_type),
+                        });
+                    }
+                }
+            }
+
+            for (origin, script) in revealed_scripts(input) {
+                let expected = match origin {
+                    "redeem script" => [&[0xa9, 0x14][..], &hash160(&script), &[0x87]].concat(),
+                    "witness script" => [&[0x00, 0x20][..], &sha256(&script)].concat(),
+                    _ => continue,
+                };
+                for output in self.outputs.iter().filter(|o| o.script_pubkey.bytes == expected) {
+                    links.push(ProvenanceLink {
+                        input: input.index,
+                        output: output.index,
+                        kind: LinkKind::SameScript,
+                        confidence: 0.95,
+                        detail: format!("pays to the {} the input revealed", origin),
+                    });
+                }
+            }
+        }
+
+        // a lone output of the type the inputs spend from reads as change
+        // when the other outputs are of another type
+        let payments: Vec<_> = self.outputs.iter().filter(|o| o.script_type != ScriptType::OpReturn).collect();
+        if payments.len() >= 2 {
+            for input in &spends {
+                let Some(spent) = spent_script_type(input) else { continue };
+                let matching: Vec<_> = payments.iter().filter(|o| o.script_type == spent).collect();
+                let [output] = matching.as_slice() else { continue };
+                if links.iter().any(|l| l.input == input.index && l.output == output.index) {
+                    continue;
+                }
+                links.push(ProvenanceLink {
+                    input: input.index,
+                    output: output.index,
+                    kind: LinkKind::SameTemplate,
+                    confidence: 0.6,
+                    detail: format!("only {} output, the type the input spends", spent),
+                });
+            }
+        }
+
+        links.sort_by(|a, b| b.confidence.total_cmp(&a.confidence).then((a.input, a.output).cmp(&(b.input, b.output))));
+        links.dedup_by(|b, a| (a.input, a.output) == (b.input, b.output));
+        links
+    }
+}
+
+// Script type of the output an input spends, read from its unlocking data
+pub(crate) fn spent_script_type(input: &TxInput) -> Option<ScriptType> {
+    let pushes: Vec<&[u8]> = instructions(&input.script_sig.bytes)?.into_iter()
+        .map(|ins| match ins {
+            Instruction::Push(data) => Some(data),
+            Instruction::Op(OP_0) => Some(&[][..]),
+            Instruction::Op(_) => None,
+        })
+        .collect::<Option<_>>()?;
+    let witness = input.witness.as_deref().unwrap_or_default();
+    let is_key = |item: &[u8]| item.len() == 33 && matches!(item[0], 0x02 | 0x03);
+
+    match (pushes.as_slice(), witness) {
+        ([], [_, key]) if is_key(key) => Some(ScriptType::P2WPKH),
+        ([], [item]) if item.len() == 64 || item.len() == 65 => Some(ScriptType::P2TR),
+        ([], [_, .., control]) if is_control_block(control) => Some(ScriptType::P2TR),
+        ([], [_, ..]) => Some(ScriptType::P2WSH),
+        ([_], [_, ..]) => Some(ScriptType::P2SH),
+        ([_, key], []) if is_key(key) || (key.len() == 65 && key[0] == 0x04) => Some(ScriptType::P2PKH),
+        ([.., _], []) if !revealed_scripts(input).is_empty() => Some(ScriptType::P2SH),
+        ([_], []) => Some(ScriptType::P2PK),
+        _ => None,
+    }
+}
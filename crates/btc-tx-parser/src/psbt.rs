@@ -0,0 +1,583 @@
+//! Partially Signed Bitcoin Transactions (BIP-174, and version 2 from BIP-370)
+
+use serde::{Deserialize, Serialize};
+use crate::amount::Amount;
+use crate::error::ParseError;
+use crate::parser::Parser;
+use crate::script::{detect_script_type, parse_multisig, ScriptType};
+use crate::types::Transaction;
+
+const PSBT_MAGIC: &[u8] = b"psbt\xff";
+
+// Global key types
+const PSBT_GLOBAL_UNSIGNED_TX: u8 = 0x00;
+const PSBT_GLOBAL_XPUB: u8 = 0x01;
+const PSBT_GLOBAL_TX_VERSION: u8 = 0x02;
+const PSBT_GLOBAL_FALLBACK_LOCKTIME: u8 = 0x03;
+const PSBT_GLOBAL_INPUT_COUNT: u8 = 0x04;
+const PSBT_GLOBAL_OUTPUT_COUNT: u8 = 0x05;
+const PSBT_GLOBAL_TX_MODIFIABLE: u8 = 0x06;
+const PSBT_GLOBAL_VERSION: u8 = 0xfb;
+
+// Per-input key types
+const PSBT_IN_NON_WITNESS_UTXO: u8 = 0x00;
+const PSBT_IN_WITNESS_UTXO: u8 = 0x01;
+const PSBT_IN_PARTIAL_SIG: u8 = 0x02;
+const PSBT_IN_SIGHASH_TYPE: u8 = 0x03;
+const PSBT_IN_REDEEM_SCRIPT: u8 = 0x04;
+const PSBT_IN_WITNESS_SCRIPT: u8 = 0x05;
+const PSBT_IN_BIP32_DERIVATION: u8 = 0x06;
+const PSBT_IN_FINAL_SCRIPTSIG: u8 = 0x07;
+const PSBT_IN_FINAL_SCRIPTWITNESS: u8 = 0x08;
+const PSBT_IN_PREVIOUS_TXID: u8 = 0x0e;
+const PSBT_IN_OUTPUT_INDEX: u8 = 0x0f;
+const PSBT_IN_SEQUENCE: u8 = 0x10;
+const PSBT_IN_REQUIRED_TIME_LOCKTIME: u8 = 0x11;
+const PSBT_IN_REQUIRED_HEIGHT_LOCKTIME: u8 = 0x12;
+const PSBT_IN_TAP_KEY_SIG: u8 = 0x13;
+
+// Per-output key types
+const PSBT_OUT_REDEEM_SCRIPT: u8 = 0x00;
+const PSBT_OUT_WITNESS_SCRIPT: u8 = 0x01;
+const PSBT_OUT_BIP32_DERIVATION: u8 = 0x02;
+const PSBT_OUT_AMOUNT: u8 = 0x03;
+const PSBT_OUT_SCRIPT: u8 = 0x04;
+
+// Parsed PSBT
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Psbt {
+    // PSBT version (0 unless PSBT_GLOBAL_VERSION is present)
+    pub version: u32,
+    // unsigned transaction; for version 2 it is rebuilt from the per-input
+    // and per-output fields, so both versions display the same way
+    pub unsigned_tx: Transaction,
+    // locktime to use when no input requires one (version 2)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fallback_locktime: Option<u32>,
+    // which parts of the transaction may still change (version 2)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modifiable: Option<PsbtModifiable>,
+    // number of global xpubs
+    pub xpub_count: usize,
+    // per-input maps
+    pub inputs: Vec<PsbtInput>,
+    // per-output maps
+    pub outputs: Vec<PsbtOutput>,
+}
+
+// PSBT_GLOBAL_TX_MODIFIABLE flags
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PsbtModifiable {
+    pub inputs: bool,
+    pub outputs: bool,
+    // some signature uses SIGHASH_SINGLE, pinning its input/output pair
+    pub has_sighash_single: bool,
+}
+
+// Per-input PSBT data
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PsbtInput {
+    // input index
+    pub index: usize,
+    // output being spent (from witness or non-witness UTXO)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub utxo: Option<PsbtUtxo>,
+    // partial signatures
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub partial_sigs: Vec<PartialSig>,
+    // requested sighash type
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sighash_type: Option<u32>,
+    // redeem script (hex)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redeem_script: Option<String>,
+    // witness script (hex)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub witness_script: Option<String>,
+    // BIP32 key origins
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub bip32_derivations: Vec<KeyOrigin>,
+    // finalized scriptSig (hex)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub final_script_sig: Option<String>,
+    // finalized witness stack (hex items)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub final_script_witness: Option<Vec<String>>,
+    // taproot key-path signature (hex)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tap_key_sig: Option<String>,
+    // minimum time-based locktime this input needs (version 2)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub required_time_locktime: Option<u32>,
+    // minimum height-based locktime this input needs (version 2)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub required_height_locktime: Option<u32>,
+}
+
+// Output spent by a PSBT input
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PsbtUtxo {
+    // value in satoshis
+    pub value: Amount,
+    // scriptPubKey (hex)
+    pub script_pubkey: String,
+    // script type
+    pub script_type: ScriptType,
+}
+
+// Signature from one key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartialSig {
+    // public key (hex)
+    pub pubkey: String,
+    // DER signature with sighash byte (hex)
+    pub signature: String,
+}
+
+// BIP32 origin of a public key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyOrigin {
+    // public key (hex)
+    pub pubkey: String,
+    // master key fingerprint (hex)
+    pub fingerprint: String,
+    // derivation path, e.g. m/48'/0'/0'/2'/0/0
+    pub path: String,
+}
+
+// Per-output PSBT data
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PsbtOutput {
+    // output index
+    pub index: usize,
+    // redeem script (hex)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redeem_script: Option<String>,
+    // witness script (hex)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub witness_script: Option<String>,
+    // BIP32 key origins
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub bip32_derivations: Vec<KeyOrigin>,
+}
+
+// m-of-n signing progress across all multisig inputs
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultisigProgress {
+    // per-input signature counts
+    pub inputs: Vec<MultisigInputProgress>,
+    // per-cosigner status, one row of the progress matrix each
+    pub cosigners: Vec<CosignerProgress>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MultisigInputProgress {
+    // input index
+    pub index: usize,
+    // signatures required (m)
+    pub required: usize,
+    // keys in the script (n)
+    pub total: usize,
+    // partial signatures present from script keys
+    pub signatures: usize,
+    // input already has a final scriptSig/witness
+    pub finalized: bool,
+    // enough signatures (or finalized)
+    pub complete: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CosignerProgress {
+    // master fingerprint if known, otherwise the public key
+    pub id: String,
+    // status per input this cosigner's key appears in
+    pub inputs: Vec<CosignerInputStatus>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CosignerInputStatus {
+    // input index
+    pub index: usize,
+    // signing status
+    pub status: SigningStatus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SigningStatus {
+    Signed,
+    Pending,
+    Finalized,
+}
+
+type KeyValue = (Vec<u8>, Vec<u8>);
+
+// Version 2 global fields
+#[derive(Default)]
+struct GlobalsV2 {
+    tx_version: Option<i32>,
+    fallback_locktime: Option<u32>,
+    input_count: Option<u64>,
+    output_count: Option<u64>,
+    modifiable: Option<PsbtModifiable>,
+}
+
+impl GlobalsV2 {
+    fn any_present(&self) -> bool {
+        self.tx_version.is_some() || self.fallback_locktime.is_some() || self.input_count.is_some()
+            || self.output_count.is_some() || self.modifiable.is_some()
+    }
+}
+
+impl Psbt {
+    // Accepts base64 (as exported by wallets) or hex
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let text = text.trim();
+        if text.starts_with("70736274ff") {
+            Self::from_bytes(&hex::decode(text)?)
+        } else {
+            Self::from_base64(text)
+        }
+    }
+
+    pub fn from_base64(text: &str) -> Result<Self, ParseError> {
+        use base64::Engine;
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(text.trim())
+            .map_err(|e| ParseError::InvalidPsbt(format!("invalid base64: {}", e)))?;
+        Self::from_bytes(&bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ParseError> {
+        if !bytes.starts_with(PSBT_MAGIC) {
+            return Err(ParseError::InvalidPsbt("missing psbt magic bytes".to_string()));
+        }
+        let mut parser = Parser::new(&bytes[PSBT_MAGIC.len()..]);
+
+        let mut unsigned_tx = None;
+        let mut version = 0;
+        let mut xpub_count = 0;
+        let mut globals = GlobalsV2::default();
+        for (key, value) in read_map(&mut parser)? {
+            match key[0] {
+                PSBT_GLOBAL_UNSIGNED_TX => unsigned_tx = Some(Transaction::from_bytes(&value)?),
+                PSBT_GLOBAL_XPUB => xpub_count += 1,
+                PSBT_GLOBAL_TX_VERSION => globals.tx_version = Some(le_u32(&value)? as i32),
+                PSBT_GLOBAL_FALLBACK_LOCKTIME => globals.fallback_locktime = Some(le_u32(&value)?),
+                PSBT_GLOBAL_INPUT_COUNT => globals.input_count = Some(Parser::new(&value).read_varint()?),
+                PSBT_GLOBAL_OUTPUT_COUNT => globals.output_count = Some(Parser::new(&value).read_varint()?),
+                PSBT_GLOBAL_TX_MODIFIABLE => {
+                    let flags = Parser::new(&value).read_u8()?;
+                    globals.modifiable = Some(PsbtModifiable {
+                        inputs: flags & 0x01 != 0,
+                        outputs: flags & 0x02 != 0,
+                        has_sighash_single: flags & 0x04 != 0,
+                    });
+                }
+                PSBT_GLOBAL_VERSION => version = le_u32(&value)?,
+                _ => {}
+            }
+        }
+
+        let (unsigned_tx, inputs, outputs) = match version {
+            0 if globals.any_present() => {
+                return Err(ParseError::InvalidPsbt("version 0 PSBT contains version 2 fields".to_string()));
+            }
+            0 => {
+                let unsigned_tx = unsigned_tx
+                    .ok_or_else(|| ParseError::InvalidPsbt("missing unsigned transaction".to_string()))?;
+                read_v0(&mut parser, unsigned_tx)?
+            }
+            2 if unsigned_tx.is_some() => {
+                return Err(ParseError::InvalidPsbt("version 2 PSBT contains an unsigned transaction".to_string()));
+            }
+            2 => read_v2(&mut parser, &globals)?,
+            _ => return Err(ParseError::InvalidPsbt(format!("unsupported PSBT version {}", version))),
+        };
+
+        Ok(Psbt {
+            version,
+            unsigned_tx,
+            fallback_locktime: globals.fallback_locktime,
+            modifiable: globals.modifiable,
+            xpub_count,
+            inputs,
+            outputs,
+        })
+    }
+
+    // m-of-n signing matrix; None when no input spends a multisig script
+    pub fn multisig_progress(&self) -> Option<MultisigProgress> {
+        let mut inputs = Vec::new();
+        let mut cosigners: Vec<CosignerProgress> = Vec::new();
+
+        for input in &self.inputs {
+            let script = match input.witness_script.as_ref().or(input.redeem_script.as_ref()) {
+                Some(script) => hex::decode(script).unwrap_or_default(),
+                None => continue,
+            };
+            let (required, keys) = match parse_multisig(&script) {
+                Some(multisig) => multisig,
+                None => continue,
+            };
+
+            let finalized = input.final_script_sig.is_some() || input.final_script_witness.is_some();
+            let mut signatures = 0;
+
+            for key in &keys {
+                let pubkey = hex::encode(key);
+                let signed = input.partial_sigs.iter().any(|s| s.pubkey == pubkey);
+                if signed {
+                    signatures += 1;
+                }
+                let status = if finalized {
+                    SigningStatus::Finalized
+                } else if signed {
+                    SigningStatus::Signed
+                } else {
+                    SigningStatus::Pending
+                };
+
+                let id = input.bip32_derivations.iter()
+                    .find(|origin| origin.pubkey == pubkey)
+                    .map(|origin| origin.fingerprint.clone())
+                    .unwrap_or(pubkey);
+                let cell = CosignerInputStatus { index: input.index, status };
+                match cosigners.iter_mut().find(|c| c.id == id) {
+                    Some(cosigner) => cosigner.inputs.push(cell),
+                    None => cosigners.push(CosignerProgress { id, inputs: vec![cell] }),
+                }
+            }
+
+            inputs.push(MultisigInputProgress {
+                index: input.index,
+                required,
+                total: keys.len(),
+                signatures,
+                finalized,
+                complete: finalized || signatures >= required,
+            });
+        }
+
+        if inputs.is_empty() {
+            None
+        } else {
+            Some(MultisigProgress { inputs, cosigners })
+        }
+    }
+}
+
+type Maps = (Transaction, Vec<PsbtInput>, Vec<PsbtOutput>);
+
+// Version 0: map counts and outpoints come from the embedded unsigned transaction
+fn read_v0(parser: &mut Parser, unsigned_tx: Transaction) -> Result<Maps, ParseError> {
+    if unsigned_tx.inputs.iter().any(|i| !i.script_sig.bytes.is_empty() || i.witness.is_some()) {
+        return Err(ParseError::InvalidPsbt("unsigned transaction has scriptSig or witness data".to_string()));
+    }
+
+    let mut inputs = Vec::with_capacity(unsigned_tx.inputs.len());
+    for tx_input in &unsigned_tx.inputs {
+        let map = read_map(parser)?;
+        inputs.push(parse_input(tx_input.index, tx_input.vout, map)?);
+    }
+
+    let mut outputs = Vec::with_capacity(unsigned_tx.outputs.len());
+    for tx_output in &unsigned_tx.outputs {
+        let map = read_map(parser)?;
+        outputs.push(parse_output(tx_output.index, map)?);
+    }
+
+    Ok((unsigned_tx, inputs, outputs))
+}
+
+// Version 2: outpoints, amounts and scripts live in the maps; rebuild the
+// unsigned transaction from them
+fn read_v2(parser: &mut Parser, globals: &GlobalsV2) -> Result<Maps, ParseError> {
+    let missing = |what: &str| ParseError::InvalidPsbt(format!("version 2 PSBT is missing {}", what));
+    let tx_version = globals.tx_version.ok_or_else(|| missing("the transaction version"))?;
+    let input_count = globals.input_count.ok_or_else(|| missing("the input count"))?;
+    let output_count = globals.output_count.ok_or_else(|| missing("the output count"))?;
+    if input_count == 0 || output_count == 0 {
+        return Err(ParseError::InvalidPsbt("version 2 PSBT has no inputs or no outputs yet".to_string()));
+    }
+
+    let mut raw = tx_version.to_le_bytes().to_vec();
+    Parser::write_varint(&mut raw, input_count);
+    let mut inputs = Vec::new();
+    for index in 0..input_count as usize {
+        let map = read_map(parser)?;
+        let txid = map_value(&map, PSBT_IN_PREVIOUS_TXID)
+            .filter(|txid| txid.len() == 32)
+            .ok_or_else(|| missing(&format!("the previous txid of input #{}", index)))?;
+        let vout = map_value(&map, PSBT_IN_OUTPUT_INDEX)
+            .map(le_u32)
+            .transpose()?
+            .ok_or_else(|| missing(&format!("the output index of input #{}", index)))?;
+        let sequence = map_value(&map, PSBT_IN_SEQUENCE).map(le_u32).transpose()?.unwrap_or(0xffff_ffff);
+
+        raw.extend_from_slice(txid);
+        raw.extend_from_slice(&vout.to_le_bytes());
+        raw.push(0x00);
+        raw.extend_from_slice(&sequence.to_le_bytes());
+        inputs.push(parse_input(index, vout, map)?);
+    }
+
+    Parser::write_varint(&mut raw, output_count);
+    let mut outputs = Vec::new();
+    for index in 0..output_count as usize {
+        let map = read_map(parser)?;
+        let amount = map_value(&map, PSBT_OUT_AMOUNT)
+            .ok_or_else(|| missing(&format!("the amount of output #{}", index)))?;
+        let script = map_value(&map, PSBT_OUT_SCRIPT)
+            .ok_or_else(|| missing(&format!("the script of output #{}", index)))?;
+
+        raw.extend_from_slice(&Parser::new(amount).read_u64_le()?.to_le_bytes());
+        Parser::write_varint(&mut raw, script.len() as u64);
+        raw.extend_from_slice(script);
+        outputs.push(parse_output(index, map)?);
+    }
+
+    raw.extend_from_slice(&v2_locktime(&inputs, globals.fallback_locktime)?.to_le_bytes());
+    Ok((Transaction::from_bytes(&raw)?, inputs, outputs))
+}
+
+// BIP-370 locktime selection: the largest required height if every input with
+// a requirement accepts a height, else the largest required time if all accept
+// a time, else the fallback
+fn v2_locktime(inputs: &[PsbtInput], fallback: Option<u32>) -> Result<u32, ParseError> {
+    let constrained: Vec<&PsbtInput> = inputs.iter()
+        .filter(|i| i.required_time_locktime.is_some() || i.required_height_locktime.is_some())
+        .collect();
+    if constrained.is_empty() {
+        return Ok(fallback.unwrap_or(0));
+    }
+
+    let heights: Option<Vec<u32>> = constrained.iter().map(|i| i.required_height_locktime).collect();
+    if let Some(heights) = heights {
+        return Ok(heights.into_iter().max().unwrap_or(0));
+    }
+    let times: Option<Vec<u32>> = constrained.iter().map(|i| i.required_time_locktime).collect();
+    times
+        .map(|times| times.into_iter().max().unwrap_or(0))
+        .ok_or_else(|| ParseError::InvalidPsbt("inputs require incompatible height and time locktimes".to_string()))
+}
+
+// Value of a key that has no key data (the key is just its type)
+fn map_value(map: &[KeyValue], key_type: u8) -> Option<&[u8]> {
+    map.iter().find(|(key, _)| key.as_slice() == [key_type]).map(|(_, value)| value.as_slice())
+}
+
+fn read_map(parser: &mut Parser) -> Result<Vec<KeyValue>, ParseError> {
+    let mut map = Vec::new();
+    loop {
+        if parser.remaining() == 0 {
+            return Err(ParseError::InvalidPsbt("map is missing its terminator".to_string()));
+        }
+        let key_len = parser.read_varint()? as usize;
+        if key_len == 0 {
+            return Ok(map);
+        }
+        let key = parser.read_bytes(key_len)?;
+        let value_len = parser.read_varint()? as usize;
+        let value = parser.read_bytes(value_len)?;
+        map.push((key, value));
+    }
+}
+
+fn parse_input(index: usize, vout: u32, map: Vec<KeyValue>) -> Result<PsbtInput, ParseError> {
+    let mut input = PsbtInput { index, ..Default::default() };
+
+    for (key, value) in map {
+        match key[0] {
+            // a witness UTXO, if present, is authoritative for display
+            PSBT_IN_NON_WITNESS_UTXO if input.utxo.is_none() => {
+                let prev_tx = Transaction::from_bytes(&value)?;
+                input.utxo = prev_tx.outputs.get(vout as usize).map(|o| PsbtUtxo {
+                    value: o.value,
+                    script_pubkey: hex::encode(&o.script_pubkey.bytes),
+                    script_type: o.script_type.clone(),
+                });
+            }
+            PSBT_IN_WITNESS_UTXO => {
+                let mut parser = Parser::new(&value);
+                let amount = parser.read_u64_le()?;
+                let script_len = parser.read_varint()? as usize;
+                let script = parser.read_bytes(script_len)?;
+                input.utxo = Some(PsbtUtxo {
+                    value: Amount::from_sat(amount),
+                    script_pubkey: hex::encode(&script),
+                    script_type: detect_script_type(&script),
+                });
+            }
+            PSBT_IN_PARTIAL_SIG => input.partial_sigs.push(PartialSig {
+                pubkey: hex::encode(&key[1..]),
+                signature: hex::encode(&value),
+            }),
+            PSBT_IN_SIGHASH_TYPE => input.sighash_type = Some(le_u32(&value)?),
+            PSBT_IN_REDEEM_SCRIPT => input.redeem_script = Some(hex::encode(&value)),
+            PSBT_IN_WITNESS_SCRIPT => input.witness_script = Some(hex::encode(&value)),
+            PSBT_IN_BIP32_DERIVATION => input.bip32_derivations.push(key_origin(&key[1..], &value)?),
+            PSBT_IN_FINAL_SCRIPTSIG => input.final_script_sig = Some(hex::encode(&value)),
+            PSBT_IN_FINAL_SCRIPTWITNESS => input.final_script_witness = Some(witness_stack(&value)?),
+            PSBT_IN_TAP_KEY_SIG => input.tap_key_sig = Some(hex::encode(&value)),
+            PSBT_IN_REQUIRED_TIME_LOCKTIME => input.required_time_locktime = Some(le_u32(&value)?),
+            PSBT_IN_REQUIRED_HEIGHT_LOCKTIME => input.required_height_locktime = Some(le_u32(&value)?),
+            _ => {}
+        }
+    }
+
+    Ok(input)
+}
+
+fn parse_output(index: usize, map: Vec<KeyValue>) -> Result<PsbtOutput, ParseError> {
+    let mut output = PsbtOutput { index, ..Default::default() };
+
+    for (key, value) in map {
+        match key[0] {
+            PSBT_OUT_REDEEM_SCRIPT => output.redeem_script = Some(hex::encode(&value)),
+            PSBT_OUT_WITNESS_SCRIPT => output.witness_script = Some(hex::encode(&value)),
+            PSBT_OUT_BIP32_DERIVATION => output.bip32_derivations.push(key_origin(&key[1..], &value)?),
+            _ => {}
+        }
+    }
+
+    Ok(output)
+}
+
+fn key_origin(pubkey: &[u8], value: &[u8]) -> Result<KeyOrigin, ParseError> {
+    if value.len() < 4 || !value.len().is_multiple_of(4) {
+        return Err(ParseError::InvalidPsbt("malformed BIP32 derivation".to_string()));
+    }
+
+    let mut path = String::from("m");
+    for chunk in value[4..].chunks(4) {
+        let step = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        if step >= 0x8000_0000 {
+            path.push_str(&format!("/{}'", step - 0x8000_0000));
+        } else {
+            path.push_str(&format!("/{}", step));
+        }
+    }
+
+    Ok(KeyOrigin {
+        pubkey: hex::encode(pubkey),
+        fingerprint: hex::encode(&value[..4]),
+        path,
+    })
+}
+
+fn witness_stack(value: &[u8]) -> Result<Vec<String>, ParseError> {
+    let mut parser = Parser::new(value);
+    let count = parser.read_varint()? as usize;
+    // each item takes at least its length byte, so a bogus count can't reserve much
+    let mut items = Vec::with_capacity(count.min(parser.remaining()));
+    for _ in 0..count {
+        let len = parser.read_varint()? as usize;
+        items.push(hex::encode(parser.read_bytes(len)?));
+    }
+    Ok(items)
+}
+
+fn le_u32(value: &[u8]) -> Result<u32, ParseError> {
+    Parser::new(value).read_u32_le()
+}
@@ -0,0 +1,130 @@
+//! Keyed pseudonyms for sharing batches of transactions: txids, keys, hashes
+//! and addresses are replaced by HMAC-SHA256 outputs under a user key, so the
+//! same value maps to the same pseudonym in every transaction and spends
+//! still point at the transactions they spend
+
+use sha2::{Digest, Sha256};
+use crate::address::derive_address;
+use crate::multisig::MultisigInfo;
+use crate::redact::map_pushes;
+use crate::types::{Script, Transaction, Txid};
+
+// Shorter pushes are numbers (heights, timeouts, multisig counts), not identities
+const MIN_IDENTITY_BYTES: usize = 20;
+
+#[derive(Debug, Clone)]
+pub struct Pseudonymizer {
+    key: Vec<u8>,
+}
+
+impl Pseudonymizer {
+    pub fn new(key: &[u8]) -> Self {
+        Self { key: key.to_vec() }
+    }
+
+    // Same txid, same key -> same pseudonym
+    pub fn txid(&self, txid: Txid) -> Txid {
+        if txid.is_null() {
+            return txid;
+        }
+        Txid(self.mac(b"txid", txid.as_bytes()))
+    }
+
+    // Pseudonym of the same length as `data`
+    pub fn bytes(&self, data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(data.len());
+        let mut counter = 0u32;
+        while out.len() < data.len() {
+            let block = self.mac(b"data", &[&counter.to_be_bytes()[..], data].concat());
+            out.extend_from_slice(&block[..block.len().min(data.len() - out.len())]);
+            counter += 1;
+        }
+        out
+    }
+
+    // Copy with txids, keys, hashes, signatures and addresses replaced;
+    // values, script types, sizes and timelocks are kept
+    pub fn pseudonymize(&self, tx: &Transaction) -> Transaction {
+        let mut tx = tx.clone();
+        tx.txid = self.hex_txid(&tx.txid);
+        tx.wtxid = self.hex_txid(&tx.wtxid);
+
+        for input in &mut tx.inputs {
+            let spent = input.address.as_ref().and_then(|_| input.spent_script_pubkey());
+            input.txid = self.txid(input.txid);
+            map_pushes(&mut input.script_sig, input.is_coinbase, |data| self.replace(data));
+            input.coinbase = input.coinbase_info();
+            for item in input.witness.iter_mut().flatten() {
+                self.replace(item);
+            }
+            for signature in &mut input.signatures {
+                signature.r = self.hex(&signature.r);
+                signature.s = self.hex(&signature.s);
+            }
+            if let Some(taproot) = &mut input.taproot {
+                taproot.internal_key = self.hex(&taproot.internal_key);
+                map_pushes(&mut taproot.leaf_script, false, |data| self.replace(data));
+            }
+            if let Some(witness_script) = &mut input.witness_script {
+                map_pushes(&mut witness_script.script, false, |data| self.replace(data));
+                witness_script.multisig = MultisigInfo::from_script(&witness_script.script.bytes);
+            }
+            if let Some(parts) = &mut input.script_sig_parts {
+                for push in parts.signatures.iter_mut().chain(&mut parts.pubkeys).chain(&mut parts.other) {
+                    *push = self.hex(push);
+                }
+                if let Some(redeem_script) = &mut parts.redeem_script {
+                    map_pushes(redeem_script, false, |data| self.replace(data));
+                    parts.multisig = MultisigInfo::from_script(&redeem_script.bytes);
+                }
+            }
+            // as the pseudonymized output paying it would show it
+            if let Some((bytes, script_type)) = spent {
+                let mut script = Script { asm: String::new(), size: bytes.len(), bytes };
+                map_pushes(&mut script, false, |data| self.replace(data));
+                input.address = derive_address(&script.bytes, &script_type);
+            }
+        }
+        for output in &mut tx.outputs {
+            map_pushes(&mut output.script_pubkey, false, |data| self.replace(data));
+            output.multisig = MultisigInfo::from_script(&output.script_pubkey.bytes);
+            if output.address.is_some() {
+                output.address = derive_address(&output.script_pubkey.bytes, &output.script_type);
+            }
+            if let Some(spend) = &mut output.spend {
+                spend.txid = spend.txid.map(|txid| self.txid(txid));
+            }
+        }
+        tx
+    }
+
+    fn replace(&self, data: &mut [u8]) {
+        if data.len() >= MIN_IDENTITY_BYTES {
+            let pseudonym = self.bytes(data);
+            data.copy_from_slice(&pseudonym);
+        }
+    }
+
+    fn hex(&self, hex: &str) -> String {
+        hex::decode(hex).map(|data| hex::encode(self.bytes(&data))).unwrap_or_else(|_| hex.to_string())
+    }
+
+    fn hex_txid(&self, hex: &str) -> String {
+        hex.parse::<Txid>().map(|txid| self.txid(txid).to_string()).unwrap_or_else(|_| hex.to_string())
+    }
+
+    // HMAC-SHA256 over a domain tag and the message
+    pub(crate) fn mac(&self, domain: &[u8], message: &[u8]) -> [u8; 32] {
+        const BLOCK: usize = 64;
+        let mut key = [0u8; BLOCK];
+        if self.key.len() > BLOCK {
+            key[..32].copy_from_slice(&Sha256::digest(&self.key));
+        } else {
+            key[..self.key.len()].copy_from_slice(&self.key);
+        }
+        let pad = |byte: u8| key.map(|k| k ^ byte);
+
+        let inner = Sha256::new().chain_update(pad(0x36)).chain_update(domain).chain_update(message).finalize();
+        Sha256::new().chain_update(pad(0x5c)).chain_update(inner).finalize().into()
+    }
+}
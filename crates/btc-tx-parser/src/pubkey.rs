@@ -0,0 +1,218 @@
+//! Public keys wherever a transaction carries them: output scripts, scriptSig
+//! pushes, witness items, redeem, witness and tapscript leaf scripts, and
+//! taproot keys. Each is checked against its SEC (or BIP-340 x-only)
+//! encoding, so fake keys such as data stuffed into bare multisig stand out.
+
+use std::fmt;
+use k256::PublicKey;
+use serde::{Deserialize, Serialize};
+use crate::analysis::{Analyzer, Finding};
+use crate::script::opcodes::*;
+use crate::script::{instructions, looks_like_pubkey, parse_multisig, Instruction, ScriptType};
+use crate::types::{Transaction, TxInput};
+use crate::witness::{classify_witness, WitnessItemKind};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyEncoding {
+    // 0x02/0x03 + X
+    Compressed,
+    // 0x04 + X + Y
+    Uncompressed,
+    // 0x06/0x07 + X + Y: valid before segwit, but non-standard
+    Hybrid,
+    // BIP-340 X alone, as in taproot
+    XOnly,
+    // a key slot holding something else
+    Unknown,
+}
+
+impl fmt::Display for KeyEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            KeyEncoding::Compressed => "compressed",
+            KeyEncoding::Uncompressed => "uncompressed",
+            KeyEncoding::Hybrid => "hybrid",
+            KeyEncoding::XOnly => "x-only",
+            KeyEncoding::Unknown => "unknown",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PubkeyInfo {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<usize>,
+    // within the input or output: "script_pubkey", "script_sig.push[1]",
+    // "witness[1]", "redeem_script", "witness_script", "leaf_script",
+    // "internal_key" or "output_key"
+    pub location: String,
+    // as found (hex)
+    pub pubkey: String,
+    pub encoding: KeyEncoding,
+    // why the key can't be used, e.g. "not on the curve"; None when valid
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issue: Option<String>,
+}
+
+impl PubkeyInfo {
+    pub fn is_valid(&self) -> bool {
+        self.issue.is_none()
+    }
+
+    // "inputs[0].witness[1]", "outputs[2].script_pubkey"
+    pub fn field(&self) -> String {
+        match (self.input, self.output) {
+            (Some(input), _) => format!("inputs[{}].{}", input, self.location),
+            (_, Some(output)) => format!("outputs[{}].{}", output, self.location),
+            (None, None) => self.location.clone(),
+        }
+    }
+}
+
+// Encoding of a key and what is wrong with it, if anything
+pub fn check_pubkey(key: &[u8]) -> (KeyEncoding, Option<String>) {
+    let on_curve = |sec: &[u8]| PublicKey::from_sec1_bytes(sec).is_ok();
+    let off_curve = |sec: &[u8]| (!on_curve(sec)).then(|| "not on the curve".to_string());
+    match (key.len(), key.first()) {
+        (33, Some(0x02 | 0x03)) => (KeyEncoding::Compressed, off_curve(key)),
+        (65, Some(0x04)) => (KeyEncoding::Uncompressed, off_curve(key)),
+        (65, Some(&prefix @ (0x06 | 0x07))) => {
+            // the prefix must also match Y's parity
+            let uncompressed = [&[0x04], &key[1..]].concat();
+            let issue = if !on_curve(&uncompressed) {
+                "not on the curve"
+            } else if key[64] & 1 != prefix & 1 {
+                "hybrid prefix doesn't match Y's parity"
+            } else {
+                "hybrid encoding is non-standard"
+            };
+            (KeyEncoding::Hybrid, Some(issue.to_string()))
+        }
+        // lift_x: the even-Y point with this X
+        (32, _) => (KeyEncoding::XOnly, off_curve(&[&[0x02], key].concat())),
+        (len, Some(prefix)) => (KeyEncoding::Unknown, Some(format!("{} bytes with prefix 0x{:02x} is not a SEC key", len, prefix))),
+        (_, None) => (KeyEncoding::Unknown, Some("empty".to_string())),
+    }
+}
+
+impl Transaction {
+    // Every key in the transaction, inputs first, in script order
+    pub fn pubkeys(&self) -> Vec<PubkeyInfo> {
+        let mut keys = Vec::new();
+        for input in self.inputs.iter().filter(|i| !i.is_coinbase) {
+            input_keys(input, &mut |location, key| keys.push(key_info(Some(input.index), None, location, key)));
+        }
+        for output in &self.outputs {
+            let mut push = |location: &str, key: &[u8]| keys.push(key_info(None, Some(output.index), location.to_string(), key));
+            match output.script_type {
+                ScriptType::P2TR => push("output_key", &output.script_pubkey.bytes[2..]),
+                // data carriers push whatever they like
+                ScriptType::OpReturn => {}
+                _ => for key in script_keys(&output.script_pubkey.bytes, false) {
+                    push("script_pubkey", key);
+                },
+            }
+        }
+        keys
+    }
+}
+
+fn key_info(input: Option<usize>, output: Option<usize>, location: String, key: &[u8]) -> PubkeyInfo {
+    let (encoding, issue) = check_pubkey(key);
+    PubkeyInfo { input, output, location, pubkey: hex::encode(key), encoding, issue }
+}
+
+fn input_keys(input: &TxInput, push: &mut dyn FnMut(String, &[u8])) {
+    for (i, ins) in instructions(&input.script_sig.bytes).unwrap_or_default().into_iter().enumerate() {
+        match ins {
+            Instruction::Push(data) if looks_like_pubkey(data) => push(format!("script_sig.push[{}]", i), data),
+            _ => {}
+        }
+    }
+    if let Some(redeem) = input.script_sig_parts.as_ref().and_then(|parts| parts.redeem_script.as_ref()) {
+        for key in script_keys(&redeem.bytes, false) {
+            push("redeem_script".to_string(), key);
+        }
+    }
+
+    let stack = input.witness.as_deref().unwrap_or_default();
+    for (i, (item, kind)) in stack.iter().zip(classify_witness(stack)).enumerate() {
+        if kind == WitnessItemKind::Pubkey {
+            push(format!("witness[{}]", i), item);
+        }
+    }
+    if let Some(witness_script) = &input.witness_script {
+        for key in script_keys(&witness_script.script.bytes, false) {
+            push("witness_script".to_string(), key);
+        }
+    }
+    if let Some(taproot) = &input.taproot {
+        if let Ok(key) = hex::decode(&taproot.internal_key) {
+            push("internal_key".to_string(), &key);
+        }
+        for key in script_keys(&taproot.leaf_script.bytes, true) {
+            push("leaf_script".to_string(), key);
+        }
+    }
+}
+
+// Keys a script checks signatures against: the slots of an OP_CHECKMULTISIG
+// template, pushes right before a signature check, and anything else shaped
+// like a SEC key. In tapscript, keys are 32-byte x-only pushes.
+fn script_keys(script: &[u8], tapscript: bool) -> Vec<&[u8]> {
+    if !tapscript {
+        if let Some((_, keys)) = parse_multisig(script) {
+            return keys;
+        }
+    }
+    let Some(ins) = instructions(script) else {
+        return Vec::new();
+    };
+    let mut keys = Vec::new();
+    for (i, instruction) in ins.iter().enumerate() {
+        let Instruction::Push(data) = instruction else {
+            continue;
+        };
+        let checked = matches!(ins.get(i + 1), Some(Instruction::Op(op)) if matches!(*op, OP_CHECKSIG | OP_CHECKSIGVERIFY)
+            || (tapscript && *op == OP_CHECKSIGADD));
+        let is_key = if tapscript {
+            data.len() == 32 && checked
+        } else {
+            (checked && matches!(data.len(), 33 | 65)) || looks_like_pubkey(data)
+        };
+        if is_key {
+            keys.push(*data);
+        }
+    }
+    keys
+}
+
+// Flags keys that aren't valid public keys
+pub struct PubkeyAnalyzer;
+
+impl Analyzer for PubkeyAnalyzer {
+    fn name(&self) -> &'static str {
+        "pubkey"
+    }
+
+    fn analyze(&self, tx: &Transaction) -> Vec<Finding> {
+        tx.pubkeys().into_iter()
+            .filter_map(|key| {
+                let issue = key.issue.as_ref()?;
+                let finding = Finding::new(
+                    self.name(),
+                    "invalid_pubkey",
+                    format!("{} holds an invalid {} key ({}): {}", key.field(), key.encoding, key.pubkey, issue),
+                );
+                Some(match (key.input, key.output) {
+                    (Some(input), _) => finding.on_input(input),
+                    (_, Some(output)) => finding.on_output(output),
+                    (None, None) => finding,
+                })
+            })
+            .collect()
+    }
+}
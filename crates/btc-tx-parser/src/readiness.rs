@@ -0,0 +1,326 @@
+//! PSBT readiness: finalizing what can be finalized, extracting the network
+//! transaction and reporting what is still missing
+
+use serde::{Deserialize, Serialize};
+use crate::amount::Amount;
+use crate::error::ParseError;
+use crate::psbt::{Psbt, PsbtInput};
+use crate::script::{parse_multisig, ScriptType};
+use crate::types::Transaction;
+
+// Stand-ins for missing data when estimating the final size: a high-s DER
+// signature with sighash byte, a compressed key, and a Schnorr signature
+const PLACEHOLDER_SIG_LEN: usize = 72;
+const PLACEHOLDER_PUBKEY_LEN: usize = 33;
+const PLACEHOLDER_SCHNORR_LEN: usize = 64;
+
+// How close a PSBT is to a broadcastable transaction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PsbtReadiness {
+    // every input is finalized or has enough signatures to be finalized
+    pub complete: bool,
+    // per-input status
+    pub inputs: Vec<InputReadiness>,
+    // size of the final transaction, with placeholders for missing signatures;
+    // inputs of unsupported types contribute only their outpoint and sequence
+    pub expected_weight: usize,
+    pub expected_vsize: usize,
+    // needs the spent output of every input
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee_satoshis: Option<Amount>,
+    // sat/vB at the expected vsize
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feerate: Option<f64>,
+    // extracted transaction; when incomplete, inputs that cannot be finalized
+    // are left unsigned
+    pub transaction: Transaction,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputReadiness {
+    // input index
+    pub index: usize,
+    pub status: InputStatus,
+    // type of the spent output, when known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub script_type: Option<ScriptType>,
+    // signatures still required
+    #[serde(skip_serializing_if = "is_zero")]
+    pub signatures_needed: usize,
+    // keys that have not signed yet
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub missing: Vec<MissingSignature>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InputStatus {
+    // final scriptSig/witness present
+    Finalized,
+    // enough partial signatures to build the final scriptSig/witness
+    Finalizable,
+    // signatures missing
+    Incomplete,
+    // spent output unknown or not a template we can finalize
+    Unsupported,
+}
+
+// Key whose signature is still missing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MissingSignature {
+    // public key (hex; x-only for taproot)
+    pub pubkey: String,
+    // master fingerprint from the BIP32 derivations
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fingerprint: Option<String>,
+}
+
+// Unlocking data for one input, possibly padded with placeholders
+struct Satisfaction {
+    script_sig: Vec<u8>,
+    witness: Vec<Vec<u8>>,
+    needed: usize,
+    missing: Vec<Vec<u8>>,
+}
+
+impl Psbt {
+    // Finalize every input that can be, and report what the rest still need
+    pub fn readiness(&self) -> Result<PsbtReadiness, ParseError> {
+        let mut inputs = Vec::with_capacity(self.inputs.len());
+        let mut final_data = Vec::with_capacity(self.inputs.len());
+        let mut estimate_data = Vec::with_capacity(self.inputs.len());
+
+        for input in &self.inputs {
+            let script_type = input.utxo.as_ref().map(|utxo| utxo.script_type.clone());
+
+            if input.final_script_sig.is_some() || input.final_script_witness.is_some() {
+                let script_sig = hex::decode(input.final_script_sig.as_deref().unwrap_or_default())?;
+                let witness = input.final_script_witness.iter().flatten()
+                    .map(hex::decode)
+                    .collect::<Result<Vec<_>, _>>()?;
+                final_data.push((script_sig.clone(), witness.clone()));
+                estimate_data.push((script_sig, witness));
+                inputs.push(InputReadiness {
+                    index: input.index,
+                    status: InputStatus::Finalized,
+                    script_type,
+                    signatures_needed: 0,
+                    missing: Vec::new(),
+                });
+                continue;
+            }
+
+            let (status, needed, missing) = match (satisfy(input, false), satisfy(input, true)) {
+                (Some(actual), Some(estimate)) => {
+                    let status = if actual.needed == 0 { InputStatus::Finalizable } else { InputStatus::Incomplete };
+                    let missing = actual.missing.iter().map(|key| missing_signature(input, key)).collect();
+                    let needed = actual.needed;
+                    if needed == 0 {
+                        final_data.push((actual.script_sig, actual.witness));
+                    } else {
+                        final_data.push((Vec::new(), Vec::new()));
+                    }
+                    estimate_data.push((estimate.script_sig, estimate.witness));
+                    (status, needed, missing)
+                }
+                _ => {
+                    final_data.push((Vec::new(), Vec::new()));
+                    estimate_data.push((Vec::new(), Vec::new()));
+                    (InputStatus::Unsupported, 0, Vec::new())
+                }
+            };
+            inputs.push(InputReadiness { index: input.index, status, script_type, signatures_needed: needed, missing });
+        }
+
+        let complete = inputs.iter().all(|i| matches!(i.status, InputStatus::Finalized | InputStatus::Finalizable));
+        let mut transaction = self.assemble(&final_data)?;
+        let estimate = self.assemble(&estimate_data)?;
+
+        let input_values: Option<Amount> = self.inputs.iter()
+            .try_fold(Amount::ZERO, |acc, i| acc.checked_add(i.utxo.as_ref()?.value));
+        for (tx_input, input) in transaction.inputs.iter_mut().zip(&self.inputs) {
+            tx_input.value = input.utxo.as_ref().map(|utxo| utxo.value);
+        }
+        let fee_satoshis = input_values.map(|total| total.saturating_sub(transaction.total_output_value()));
+        transaction.set_fee(fee_satoshis);
+
+        Ok(PsbtReadiness {
+            complete,
+            inputs,
+            expected_weight: estimate.weight,
+            expected_vsize: estimate.vsize(),
+            fee_satoshis,
+            feerate: fee_satoshis.map(|fee| fee.to_sat() as f64 / estimate.vsize() as f64),
+            transaction,
+        })
+    }
+
+    // The network transaction, failing unless every input can be finalized
+    pub fn extract_tx(&self) -> Result<Transaction, ParseError> {
+        let readiness = self.readiness()?;
+        if !readiness.complete {
+            let pending: Vec<String> = readiness.inputs.iter()
+                .filter(|i| !matches!(i.status, InputStatus::Finalized | InputStatus::Finalizable))
+                .map(|i| format!("#{}", i.index))
+                .collect();
+            return Err(ParseError::InvalidPsbt(format!("inputs {} are not ready to finalize", pending.join(", "))));
+        }
+        Ok(readiness.transaction)
+    }
+
+    // Unsigned transaction with the given scriptSig/witness per input,
+    // re-parsed so txid, sizes and weight are current
+    fn assemble(&self, unlocking: &[(Vec<u8>, Vec<Vec<u8>>)]) -> Result<Transaction, ParseError> {
+        let mut tx = self.unsigned_tx.clone();
+        for (input, (script_sig, witness)) in tx.inputs.iter_mut().zip(unlocking) {
+            input.script_sig.bytes = script_sig.clone();
+            input.witness = (!witness.is_empty()).then(|| witness.clone());
+        }
+        tx.is_segwit = tx.inputs.iter().any(|i| i.witness.is_some());
+        Transaction::from_bytes(&tx.to_bytes())
+    }
+}
+
+// Build the unlocking data for a standard input from its partial signatures;
+// with `placeholders`, missing signatures and keys are filled with dummies of
+// typical size. None when the input cannot be finalized by template.
+fn satisfy(input: &PsbtInput, placeholders: bool) -> Option<Satisfaction> {
+    let utxo = input.utxo.as_ref()?;
+    match utxo.script_type {
+        ScriptType::P2PKH => {
+            let (sigs, satisfaction) = single_key(input, placeholders);
+            Some(Satisfaction { script_sig: pushes(&sigs), ..satisfaction })
+        }
+        ScriptType::P2WPKH => {
+            let (stack, satisfaction) = single_key(input, placeholders);
+            Some(Satisfaction { witness: stack, ..satisfaction })
+        }
+        ScriptType::P2WSH => {
+            let witness_script = hex::decode(input.witness_script.as_ref()?).ok()?;
+            let (stack, satisfaction) = multisig(input, &witness_script, placeholders)?;
+            Some(Satisfaction { witness: stack, ..satisfaction })
+        }
+        ScriptType::P2SH => {
+            let redeem_script = hex::decode(input.redeem_script.as_ref()?).ok()?;
+            let redeem_push = pushes(std::slice::from_ref(&redeem_script));
+            match redeem_script.as_slice() {
+                // P2SH-P2WPKH
+                [0x00, 0x14, ..] if redeem_script.len() == 22 => {
+                    let (stack, satisfaction) = single_key(input, placeholders);
+                    Some(Satisfaction { script_sig: redeem_push, witness: stack, ..satisfaction })
+                }
+                // P2SH-P2WSH
+                [0x00, 0x20, ..] if redeem_script.len() == 34 => {
+                    let witness_script = hex::decode(input.witness_script.as_ref()?).ok()?;
+                    let (stack, satisfaction) = multisig(input, &witness_script, placeholders)?;
+                    Some(Satisfaction { script_sig: redeem_push, witness: stack, ..satisfaction })
+                }
+                _ => {
+                    let (mut stack, satisfaction) = multisig(input, &redeem_script, placeholders)?;
+                    stack.pop();
+                    let mut script_sig = pushes(&stack);
+                    script_sig.extend_from_slice(&redeem_push);
+                    Some(Satisfaction { script_sig, ..satisfaction })
+                }
+            }
+        }
+        ScriptType::P2TR => {
+            let output_key = hex::decode(&utxo.script_pubkey).ok()?.get(2..)?.to_vec();
+            match &input.tap_key_sig {
+                Some(sig) => Some(Satisfaction {
+                    script_sig: Vec::new(),
+                    witness: vec![hex::decode(sig).ok()?],
+                    needed: 0,
+                    missing: Vec::new(),
+                }),
+                None => Some(Satisfaction {
+                    script_sig: Vec::new(),
+                    witness: if placeholders { vec![vec![0; PLACEHOLDER_SCHNORR_LEN]] } else { Vec::new() },
+                    needed: 1,
+                    missing: vec![output_key],
+                }),
+            }
+        }
+        _ => None,
+    }
+}
+
+// [signature, pubkey] for a single-key spend
+fn single_key(input: &PsbtInput, placeholders: bool) -> (Vec<Vec<u8>>, Satisfaction) {
+    let sig = input.partial_sigs.first();
+    let pubkey = sig.map(|s| s.pubkey.as_str())
+        .or_else(|| input.bip32_derivations.first().map(|origin| origin.pubkey.as_str()))
+        .and_then(|key| hex::decode(key).ok());
+
+    let mut stack = Vec::new();
+    let satisfaction = match sig.and_then(|s| hex::decode(&s.signature).ok()) {
+        Some(sig) => {
+            stack.push(sig);
+            stack.push(pubkey.unwrap_or_default());
+            Satisfaction { script_sig: Vec::new(), witness: Vec::new(), needed: 0, missing: Vec::new() }
+        }
+        None => {
+            if placeholders {
+                stack.push(vec![0; PLACEHOLDER_SIG_LEN]);
+                stack.push(pubkey.clone().unwrap_or_else(|| vec![0; PLACEHOLDER_PUBKEY_LEN]));
+            }
+            Satisfaction { script_sig: Vec::new(), witness: Vec::new(), needed: 1, missing: pubkey.into_iter().collect() }
+        }
+    };
+    (stack, satisfaction)
+}
+
+// [OP_0, signatures in key order..., script] for an m-of-n CHECKMULTISIG script
+fn multisig(input: &PsbtInput, script: &[u8], placeholders: bool) -> Option<(Vec<Vec<u8>>, Satisfaction)> {
+    let (required, keys) = parse_multisig(script)?;
+
+    let mut stack = vec![Vec::new()];
+    let mut missing = Vec::new();
+    for key in &keys {
+        let pubkey = hex::encode(key);
+        match input.partial_sigs.iter().find(|s| s.pubkey == pubkey) {
+            Some(sig) if stack.len() <= required => stack.push(hex::decode(&sig.signature).ok()?),
+            Some(_) => {}
+            None => missing.push(key.to_vec()),
+        }
+    }
+
+    let needed = (required + 1).saturating_sub(stack.len());
+    if needed == 0 {
+        missing.clear();
+    }
+    if placeholders {
+        stack.extend(std::iter::repeat_n(vec![0; PLACEHOLDER_SIG_LEN], needed));
+    }
+    stack.push(script.to_vec());
+    Some((stack, Satisfaction { script_sig: Vec::new(), witness: Vec::new(), needed, missing }))
+}
+
+fn missing_signature(input: &PsbtInput, key: &[u8]) -> MissingSignature {
+    let pubkey = hex::encode(key);
+    let fingerprint = input.bip32_derivations.iter()
+        .find(|origin| origin.pubkey == pubkey)
+        .map(|origin| origin.fingerprint.clone());
+    MissingSignature { pubkey, fingerprint }
+}
+
+// Script pushing each item with the smallest push opcode
+fn pushes(items: &[Vec<u8>]) -> Vec<u8> {
+    let mut script = Vec::new();
+    for item in items {
+        match item.len() {
+            len @ 0..=75 => script.push(len as u8),
+            len @ 76..=255 => script.extend_from_slice(&[0x4c, len as u8]),
+            len => {
+                script.push(0x4d);
+                script.extend_from_slice(&(len as u16).to_le_bytes());
+            }
+        }
+        script.extend_from_slice(item);
+    }
+    script
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
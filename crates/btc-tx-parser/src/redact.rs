@@ -0,0 +1,160 @@
+//! Redacted copies of a transaction that are safe to share in bug reports:
+//! identifying data masked, values bucketed, structure and sizes kept
+
+use crate::amount::Amount;
+use crate::multisig::MultisigInfo;
+use crate::script::script_to_asm;
+use crate::types::{Script, Transaction, Txid};
+
+// What to mask
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Redaction {
+    // addresses, txids, and the keys, hashes and signatures pushed by scripts
+    Addresses,
+    // amounts, rounded down to a power of ten
+    Values,
+    All,
+}
+
+impl Redaction {
+    fn addresses(self) -> bool {
+        matches!(self, Redaction::Addresses | Redaction::All)
+    }
+
+    fn values(self) -> bool {
+        matches!(self, Redaction::Values | Redaction::All)
+    }
+}
+
+impl Transaction {
+    // Copy with the chosen data masked. Script types, sizes, sequences and
+    // notes are kept, so the copy still shows the shape of the transaction.
+    pub fn redacted(&self, redaction: Redaction) -> Transaction {
+        let mut tx = self.clone();
+
+        if redaction.addresses() {
+            tx.txid = mask_hex(&tx.txid);
+            tx.wtxid = mask_hex(&tx.wtxid);
+            for input in &mut tx.inputs {
+                if !input.is_coinbase {
+                    input.txid = mask_txid(input.txid);
+                }
+                map_pushes(&mut input.script_sig, input.is_coinbase, |data| data.fill(0));
+                input.coinbase = input.coinbase_info();
+                for item in input.witness.iter_mut().flatten() {
+                    item.fill(0);
+                }
+                for signature in &mut input.signatures {
+                    signature.r = "0".repeat(signature.r.len());
+                    signature.s = "0".repeat(signature.s.len());
+                }
+                if let Some(taproot) = &mut input.taproot {
+                    taproot.internal_key = "0".repeat(taproot.internal_key.len());
+                    map_pushes(&mut taproot.leaf_script, false, |data| data.fill(0));
+                }
+                if let Some(witness_script) = &mut input.witness_script {
+                    map_pushes(&mut witness_script.script, false, |data| data.fill(0));
+                witness_script.multisig = MultisigInfo::from_script(&witness_script.script.bytes);
+                }
+                if let Some(parts) = &mut input.script_sig_parts {
+                    for push in parts.signatures.iter_mut().chain(&mut parts.pubkeys).chain(&mut parts.other) {
+                        *push = "0".repeat(push.len());
+                    }
+                    if let Some(redeem_script) = &mut parts.redeem_script {
+                        map_pushes(redeem_script, false, |data| data.fill(0));
+                    parts.multisig = MultisigInfo::from_script(&redeem_script.bytes);
+                    }
+                }
+                if let Some(address) = &mut input.address {
+                    address.mainnet = truncate_address(&address.mainnet);
+                    address.testnet = truncate_address(&address.testnet);
+                }
+            }
+            for output in &mut tx.outputs {
+                map_pushes(&mut output.script_pubkey, false, |data| data.fill(0));
+            output.multisig = MultisigInfo::from_script(&output.script_pubkey.bytes);
+                if let Some(address) = &mut output.address {
+                    address.mainnet = truncate_address(&address.mainnet);
+                    address.testnet = truncate_address(&address.testnet);
+                }
+                if let Some(spend) = &mut output.spend {
+                    spend.txid = spend.txid.map(mask_txid);
+                }
+            }
+        }
+
+        if redaction.values() {
+            for input in &mut tx.inputs {
+                input.value = input.value.map(bucket);
+            }
+            for output in &mut tx.outputs {
+                output.set_value(bucket(output.value));
+            }
+            tx.set_total_output(bucket(tx.total_output_satoshis));
+            tx.set_fee(tx.fee_satoshis.map(bucket));
+        }
+
+        tx
+    }
+}
+
+// Largest power of ten not above the amount, e.g. 12,345,678 -> 10,000,000
+fn bucket(amount: Amount) -> Amount {
+    match amount.to_sat() {
+        0 => Amount::ZERO,
+        sats => Amount::from_sat(10u64.pow(sats.ilog10())),
+    }
+}
+
+// Keep the first 8 and last 4 hex digits and star out the rest
+fn mask_hex(hex: &str) -> String {
+    hex.char_indices()
+        .map(|(i, c)| if i < 8 || i + 4 >= hex.len() { c } else { '*' })
+        .collect()
+}
+
+// A txid can't hold '*', so the hidden digits become zeros; bytes are
+// stored reversed, hence the first 2 and last 4 bytes survive
+fn mask_txid(txid: Txid) -> Txid {
+    let mut bytes = txid.0;
+    bytes[2..28].fill(0);
+    Txid(bytes)
+}
+
+// "bc1qar…5mdq"
+fn truncate_address(address: &str) -> String {
+    let chars: Vec<char> = address.chars().collect();
+    if chars.len() <= 10 {
+        return address.to_string();
+    }
+    format!("{}…{}", chars[..6].iter().collect::<String>(), chars[chars.len() - 4..].iter().collect::<String>())
+}
+
+// Rewrite every push in place, keeping the opcodes and push sizes, and
+// refresh the ASM
+pub(crate) fn map_pushes(script: &mut Script, is_coinbase: bool, mut f: impl FnMut(&mut [u8])) {
+    let bytes = &mut script.bytes;
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let opcode = bytes[pos];
+        pos += 1;
+        let (len_bytes, len) = match opcode {
+            0x01..=0x4b => (0, opcode as usize),
+            0x4c..=0x4e => {
+                let width = 1 << (opcode - 0x4c);
+                let Some(field) = bytes.get(pos..pos + width) else { break };
+                (width, field.iter().rev().fold(0usize, |acc, b| (acc << 8) | *b as usize))
+            }
+            _ => continue,
+        };
+        pos += len_bytes;
+        let end = (pos + len).min(bytes.len());
+        f(&mut bytes[pos..end]);
+        pos = end;
+    }
+    script.asm = if is_coinbase {
+        format!("[coinbase] {}", hex::encode(&script.bytes))
+    } else {
+        script_to_asm(&script.bytes)
+    };
+}
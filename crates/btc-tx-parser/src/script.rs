@@ -3,8 +3,10 @@ Bitcoin script type detection and ASM disassembly
 */
 
 use serde::{Deserialize, Serialize};
+use crate::hexcodec::encode_hex;
+use crate::types::TxInput;
 
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum ScriptType {
     #[serde(rename = "p2pkh")]
     P2PKH,
@@ -79,12 +81,29 @@ pub mod opcodes {
     pub const OP_ENDIF: u8 = 0x68;
     pub const OP_VERIFY: u8 = 0x69;
     pub const OP_RETURN: u8 = 0x6a;
+    pub const OP_IFDUP: u8 = 0x73;
+    pub const OP_DROP: u8 = 0x75;
     pub const OP_DUP: u8 = 0x76;
+    pub const OP_SIZE: u8 = 0x82;
     pub const OP_EQUAL: u8 = 0x87;
     pub const OP_EQUALVERIFY: u8 = 0x88;
+    pub const OP_NUMEQUAL: u8 = 0x9c;
+    pub const OP_NUMEQUALVERIFY: u8 = 0x9d;
+    pub const OP_GREATERTHANOREQUAL: u8 = 0xa2;
+    pub const OP_RIPEMD160: u8 = 0xa6;
+    pub const OP_SHA256: u8 = 0xa8;
     pub const OP_HASH160: u8 = 0xa9;
+    pub const OP_HASH256: u8 = 0xaa;
     pub const OP_CHECKSIG: u8 = 0xac;
+    pub const OP_CHECKSIGVERIFY: u8 = 0xad;
     pub const OP_CHECKMULTISIG: u8 = 0xae;
+    pub const OP_CHECKMULTISIGVERIFY: u8 = 0xaf;
+    pub const OP_CHECKLOCKTIMEVERIFY: u8 = 0xb1;
+    pub const OP_CHECKSEQUENCEVERIFY: u8 = 0xb2;
+    pub const OP_CHECKTEMPLATEVERIFY: u8 = 0xb3;
+    pub const OP_VAULT: u8 = 0xbb;
+    pub const OP_VAULT_RECOVER: u8 = 0xbc;
+    pub const OP_CHECKSIGADD: u8 = 0xba;
 }
 
 use opcodes::*;
@@ -146,7 +165,7 @@ pub fn detect_script_type(script: &[u8]) -> ScriptType {
 
     if script.len() >= 2 && script[0] >= OP_1 && script[0] <= OP_16 {
         let push_size = script[1] as usize;
-        if script.len() == 2 + push_size && push_size >= 2 && push_size <= 40 {
+        if script.len() == 2 + push_size && (2..=40).contains(&push_size) {
             return ScriptType::WitnessUnknown;
         }
     }
@@ -168,18 +187,170 @@ fn is_multisig(script: &[u8]) -> bool {
     }
 
     let first = script[0];
-    if first < OP_1 || first > OP_16 {
+    if !(OP_1..=OP_16).contains(&first) {
         return false;
     }
 
     let n_byte = script[script.len() - 2];
-    if n_byte < OP_1 || n_byte > OP_16 {
+    if !(OP_1..=OP_16).contains(&n_byte) {
         return false;
     }
 
     true
 }
 
+// Single script element: a data push or a non-push opcode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Instruction<'a> {
+    Push(&'a [u8]),
+    Op(u8),
+}
+
+// Split a script into instructions; None if a push runs past the end
+pub(crate) fn instructions(script: &[u8]) -> Option<Vec<Instruction<'_>>> {
+    let mut result = Vec::new();
+    let mut i = 0;
+
+    while i < script.len() {
+        let (instruction, next) = next_instruction(script, i)?;
+        result.push(instruction);
+        i = next;
+    }
+
+    Some(result)
+}
+
+// Instruction starting at `i` and the offset after it; None if a push runs
+// past the end
+pub(crate) fn next_instruction(script: &[u8], i: usize) -> Option<(Instruction<'_>, usize)> {
+    let opcode = *script.get(i)?;
+    let (header, n) = match opcode {
+        0x01..=0x4b => (1, opcode as usize),
+        OP_PUSHDATA1 => (2, *script.get(i + 1)? as usize),
+        OP_PUSHDATA2 => (3, u16::from_le_bytes([*script.get(i + 1)?, *script.get(i + 2)?]) as usize),
+        OP_PUSHDATA4 => {
+            let len = script.get(i + 1..i + 5)?;
+            (5, u32::from_le_bytes([len[0], len[1], len[2], len[3]]) as usize)
+        }
+        _ => return Some((Instruction::Op(opcode), i + 1)),
+    };
+    let start = i + header;
+    let end = start.checked_add(n)?;
+    Some((Instruction::Push(script.get(start..end)?), end))
+}
+
+// Decode OP_m <pubkey>... OP_n OP_CHECKMULTISIG into (m, pubkeys)
+pub(crate) fn parse_multisig(script: &[u8]) -> Option<(usize, Vec<&[u8]>)> {
+    let ins = instructions(script)?;
+    if ins.len() < 4 || ins[ins.len() - 1] != Instruction::Op(OP_CHECKMULTISIG) {
+        return None;
+    }
+
+    let small_int = |ins: &Instruction| match *ins {
+        Instruction::Op(op) if (OP_1..=OP_16).contains(&op) => Some((op - OP_1 + 1) as usize),
+        _ => None,
+    };
+    let m = small_int(&ins[0])?;
+    let n = small_int(&ins[ins.len() - 2])?;
+
+    let keys: Vec<&[u8]> = ins[1..ins.len() - 2].iter()
+        .map(|i| match i {
+            Instruction::Push(key) if key.len() == 33 || key.len() == 65 => Some(*key),
+            _ => None,
+        })
+        .collect::<Option<_>>()?;
+
+    if keys.len() != n || m > n {
+        return None;
+    }
+    Some((m, keys))
+}
+
+// Scripts revealed by an input's unlocking data, labelled by where they came from
+pub(crate) fn revealed_scripts(input: &TxInput) -> Vec<(&'static str, Vec<u8>)> {
+    let mut scripts = Vec::new();
+
+    if !input.is_coinbase {
+        if let Some(ins) = instructions(&input.script_sig.bytes) {
+            let push_only = ins.iter().all(|i| matches!(i, Instruction::Push(_) | Instruction::Op(OP_0)));
+            if let Some(Instruction::Push(last)) = ins.last() {
+                if push_only && !last.is_empty() && !looks_like_pubkey(last) && !looks_like_signature(last) {
+                    scripts.push(("redeem script", last.to_vec()));
+                }
+            }
+        }
+    }
+
+    if let Some(witness) = &input.witness {
+        let mut items = witness.clone();
+        // drop the taproot annex
+        if items.len() >= 2 && items.last().is_some_and(|a| a.first() == Some(&0x50)) {
+            items.pop();
+        }
+        if items.len() >= 2 {
+            let last = &items[items.len() - 1];
+            if is_control_block(last) {
+                scripts.push(("tapscript", items[items.len() - 2].clone()));
+            } else if !looks_like_pubkey(last) {
+                scripts.push(("witness script", last.clone()));
+            }
+        }
+    }
+
+    scripts
+}
+
+// Public keys pushed in the scriptSig or witness of an input
+pub(crate) fn revealed_pubkeys(input: &TxInput) -> Vec<String> {
+    let mut keys = Vec::new();
+    for ins in instructions(&input.script_sig.bytes).unwrap_or_default() {
+        if let Instruction::Push(data) = ins {
+            if looks_like_pubkey(data) {
+                keys.push(hex::encode(data));
+            }
+        }
+    }
+    for item in input.witness.iter().flatten() {
+        if looks_like_pubkey(item) {
+            keys.push(hex::encode(item));
+        }
+    }
+    keys
+}
+
+pub(crate) fn looks_like_pubkey(data: &[u8]) -> bool {
+    (data.len() == 33 && (data[0] == 0x02 || data[0] == 0x03)) || (data.len() == 65 && data[0] == 0x04)
+}
+
+// DER signature followed by a sighash byte
+pub(crate) fn looks_like_signature(data: &[u8]) -> bool {
+    data.len() >= 9 && data[0] == 0x30 && data[1] as usize == data.len() - 3
+}
+
+pub(crate) fn is_control_block(data: &[u8]) -> bool {
+    data.len() >= 33 && (data.len() - 33).is_multiple_of(32) && (data.len() - 33) / 32 <= 128 && data[0] & 0xfe == 0xc0
+}
+
+// Minimally-encoded script number (CScriptNum), up to 5 bytes
+pub(crate) fn decode_script_num(data: &[u8]) -> Option<i64> {
+    if data.len() > 5 {
+        return None;
+    }
+    if data.is_empty() {
+        return Some(0);
+    }
+    let mut value: i64 = 0;
+    for (i, byte) in data.iter().enumerate() {
+        value |= (*byte as i64) << (8 * i);
+    }
+    let last = data[data.len() - 1];
+    if last & 0x80 != 0 {
+        value &= !(0x80i64 << (8 * (data.len() - 1)));
+        value = -value;
+    }
+    Some(value)
+}
+
 pub fn script_to_asm(script: &[u8]) -> String {
     if script.is_empty() {
         return String::new();
@@ -196,9 +367,10 @@ pub fn script_to_asm(script: &[u8]) -> String {
                 let n = opcode as usize;
                 if i + 1 + n <= script.len() {
                     let data = &script[i + 1..i + 1 + n];
-                    asm.push(hex::encode(data));
+                    asm.push(encode_hex(data));
                     i += 1 + n;
                 } else {
+                    debug!(offset = i, len = n, "push past end of script");
                     asm.push(format!("[error: push {} bytes past end]", n));
                     break;
                 }
@@ -208,9 +380,10 @@ pub fn script_to_asm(script: &[u8]) -> String {
                     let n = script[i + 1] as usize;
                     if i + 2 + n <= script.len() {
                         let data = &script[i + 2..i + 2 + n];
-                        asm.push(hex::encode(data));
+                        asm.push(encode_hex(data));
                         i += 2 + n;
                     } else {
+                        debug!(offset = i, len = n, "OP_PUSHDATA1 past end of script");
                         asm.push("[error: PUSHDATA1 past end]".to_string());
                         break;
                     }
@@ -223,9 +396,10 @@ pub fn script_to_asm(script: &[u8]) -> String {
                     let n = u16::from_le_bytes([script[i + 1], script[i + 2]]) as usize;
                     if i + 3 + n <= script.len() {
                         let data = &script[i + 3..i + 3 + n];
-                        asm.push(hex::encode(data));
+                        asm.push(encode_hex(data));
                         i += 3 + n;
                     } else {
+                        debug!(offset = i, len = n, "OP_PUSHDATA2 past end of script");
                         asm.push("[error: PUSHDATA2 past end]".to_string());
                         break;
                     }
@@ -243,9 +417,10 @@ pub fn script_to_asm(script: &[u8]) -> String {
                     ]) as usize;
                     if i + 5 + n <= script.len() {
                         let data = &script[i + 5..i + 5 + n];
-                        asm.push(hex::encode(data));
+                        asm.push(encode_hex(data));
                         i += 5 + n;
                     } else {
+                        debug!(offset = i, len = n, "OP_PUSHDATA4 past end of script");
                         asm.push("[error: PUSHDATA4 past end]".to_string());
                         break;
                     }
@@ -263,7 +438,7 @@ pub fn script_to_asm(script: &[u8]) -> String {
     asm.join(" ")
 }
 
-fn opcode_name(opcode: u8) -> String {
+pub(crate) fn opcode_name(opcode: u8) -> String {
     match opcode {
         0x00 => "OP_0".to_string(),
         0x4c => "OP_PUSHDATA1".to_string(),
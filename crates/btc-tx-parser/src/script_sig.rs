@@ -0,0 +1,146 @@
+//! Legacy scriptSigs split into what they push: signatures, public keys and,
+//! for P2SH spends, the redeem script that the remaining pushes satisfy
+
+use serde::{Deserialize, Serialize};
+use crate::provenance::spent_script_type;
+use crate::multisig::MultisigInfo;
+use crate::script::{detect_script_type, instructions, looks_like_pubkey, looks_like_signature, script_to_asm, Instruction, ScriptType};
+use crate::types::{Script, TxInput};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScriptSigParts {
+    // type of the spent output, read from the scriptSig's shape: p2pkh, p2sh or p2pk
+    pub spends: ScriptType,
+    // DER signatures with their sighash byte (hex)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub signatures: Vec<String>,
+    // public keys (hex)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pubkeys: Vec<String>,
+    // the last push of a P2SH spend; a witness program for nested segwit
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redeem_script: Option<Script>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub redeem_script_type: Option<ScriptType>,
+    // inner program type when the redeem script is a witness program and
+    // the input has a witness: a nested segwit (P2SH-P2WPKH/P2WSH) spend
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nested_segwit: Option<ScriptType>,
+    // what the redeem script is, spelled out: "2-of-3 multisig", "P2WPKH",
+    // or "P2WSH wrapping 2-of-3 multisig" when the witness script is known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wraps: Option<String>,
+    // threshold and keys when the redeem script is a multisig
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub multisig: Option<MultisigInfo>,
+    // other pushes, e.g. the CHECKMULTISIG dummy ("") or a preimage (hex)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub other: Vec<String>,
+}
+
+impl TxInput {
+    // Parts of a push-only P2PKH, P2SH or P2PK scriptSig
+    pub fn script_sig_parts(&self) -> Option<ScriptSigParts> {
+        if self.is_coinbase || self.script_sig.bytes.is_empty() {
+            return None;
+        }
+        let spends = spent_script_type(self).filter(|t| matches!(t, ScriptType::P2PKH | ScriptType::P2SH | ScriptType::P2PK))?;
+        let pushes: Vec<&[u8]> = instructions(&self.script_sig.bytes)?.into_iter()
+            .map(|ins| match ins {
+                Instruction::Push(data) => data,
+                // spent_script_type only accepts push-only scripts, so this is OP_0
+                Instruction::Op(_) => &[],
+            })
+            .collect();
+        let (arguments, redeem_script) = match (&spends, pushes.as_slice()) {
+            (ScriptType::P2SH, [arguments @ .., redeem]) => (arguments, Some(*redeem)),
+            _ => (pushes.as_slice(), None),
+        };
+
+        let mut parts = ScriptSigParts {
+            spends,
+            signatures: Vec::new(),
+            pubkeys: Vec::new(),
+            redeem_script_type: redeem_script.map(detect_script_type),
+            nested_segwit: self.nested_segwit(),
+            wraps: redeem_script.map(|script| describe_redeem_script(script, self.witness.as_deref())),
+            multisig: redeem_script.and_then(MultisigInfo::from_script),
+            redeem_script: redeem_script.map(|bytes| Script {
+                asm: script_to_asm(bytes),
+                size: bytes.len(),
+                bytes: bytes.to_vec(),
+            }),
+            other: Vec::new(),
+        };
+        for push in arguments {
+            let list = if looks_like_signature(push) {
+                &mut parts.signatures
+            } else if looks_like_pubkey(push) {
+                &mut parts.pubkeys
+            } else {
+                &mut parts.other
+            };
+            list.push(hex::encode(push));
+        }
+        Some(parts)
+    }
+}
+
+impl TxInput {
+    // Inner program type of a nested segwit spend: the scriptSig pushes only
+    // a witness program, and the witness does the unlocking
+    pub fn nested_segwit(&self) -> Option<ScriptType> {
+        if self.witness.as_ref().is_none_or(|stack| stack.is_empty()) {
+            return None;
+        }
+        let pushes = instructions(&self.script_sig.bytes)?;
+        let [Instruction::Push(program)] = pushes.as_slice() else {
+            return None;
+        };
+        Some(detect_script_type(program))
+            .filter(|t| matches!(t, ScriptType::P2WPKH | ScriptType::P2WSH | ScriptType::P2TR | ScriptType::WitnessUnknown))
+    }
+}
+
+impl ScriptSigParts {
+    // "P2SH wrapping 2-of-3 multisig", "P2SH-P2WPKH (nested segwit)"; the
+    // spent type alone for P2PKH and P2PK
+    pub fn describe(&self) -> String {
+        let spends = short_name(&self.spends);
+        match (&self.nested_segwit, &self.wraps) {
+            (Some(program), wraps) => {
+                let nested = format!("{}-{} (nested segwit)", spends, short_name(program));
+                // for P2WSH, the witness script's kind
+                match wraps.as_deref().and_then(|inner| inner.strip_prefix("P2WSH wrapping ")) {
+                    Some(script) => format!("{} wrapping {}", nested, script),
+                    None => nested,
+                }
+            }
+            (None, Some(inner)) => format!("{} wrapping {}", spends, inner),
+            (None, None) => spends,
+        }
+    }
+}
+
+fn describe_redeem_script(script: &[u8], witness: Option<&[Vec<u8>]>) -> String {
+    if let Some(multisig) = MultisigInfo::from_script(script) {
+        return format!("{} multisig", multisig);
+    }
+    let script_type = detect_script_type(script);
+    // nested P2WSH: the witness script is the stack's last item
+    let witness_script = witness.and_then(|stack| stack.last()).filter(|_| script_type == ScriptType::P2WSH);
+    match witness_script {
+        Some(inner) => format!("{} wrapping {}", short_name(&script_type), describe_redeem_script(inner, None)),
+        None => short_name(&script_type),
+    }
+}
+
+// "P2WPKH" rather than "P2WPKH (Pay to Witness Public Key Hash)"
+fn short_name(script_type: &ScriptType) -> String {
+    match script_type {
+        ScriptType::Multisig => "bare multisig".to_string(),
+        ScriptType::NonStandard => "a non-standard script".to_string(),
+        ScriptType::WitnessUnknown => "an unknown witness program".to_string(),
+        other => other.to_string().split(" (").next().unwrap_or_default().to_string(),
+    }
+}
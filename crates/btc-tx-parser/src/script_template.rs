@@ -0,0 +1,129 @@
+//! Script templates: disassembly with placeholders, for finding scripts of
+//! a given shape. `OP_DUP OP_HASH160 <hash160> OP_EQUALVERIFY OP_CHECKSIG`
+//! matches every P2PKH script, and `OP_RETURN ...` every data carrier.
+
+use std::fmt;
+use std::str::FromStr;
+use crate::script::{decode_script_num, instructions, opcode_name, Instruction};
+use crate::script::opcodes::*;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    // an opcode by name, OP_0 and OP_1..OP_16 included
+    Op(u8),
+    // these exact bytes, written as hex
+    Push(Vec<u8>),
+    // <pubkey>: a 33- or 65-byte push
+    Pubkey,
+    // <xonly>: a 32-byte push, as taproot keys
+    XOnly,
+    // <hash160> and <hash256>: a 20- or 32-byte push
+    Hash160,
+    Hash256,
+    // <num>: OP_0, OP_1NEGATE, OP_1..OP_16 or a push of up to 5 bytes
+    Num,
+    // <data>: any push
+    Data,
+    // <any>: any one element
+    Any,
+    // ...: any run of elements, possibly none
+    Rest,
+}
+
+impl Token {
+    fn matches(&self, ins: &Instruction) -> bool {
+        match (self, ins) {
+            (Token::Op(op), Instruction::Op(o)) => op == o,
+            (Token::Push(data), Instruction::Push(d)) => data.as_slice() == *d,
+            (Token::Pubkey, Instruction::Push(d)) => matches!(d.len(), 33 | 65),
+            (Token::XOnly | Token::Hash256, Instruction::Push(d)) => d.len() == 32,
+            (Token::Hash160, Instruction::Push(d)) => d.len() == 20,
+            (Token::Num, Instruction::Op(op)) => matches!(*op, OP_0 | OP_1NEGATE | OP_1..=OP_16),
+            (Token::Num, Instruction::Push(d)) => decode_script_num(d).is_some(),
+            (Token::Data, Instruction::Push(_)) | (Token::Any | Token::Rest, _) => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Op(op) => f.write_str(&opcode_name(*op)),
+            Token::Push(data) => f.write_str(&hex::encode(data)),
+            Token::Pubkey => f.write_str("<pubkey>"),
+            Token::XOnly => f.write_str("<xonly>"),
+            Token::Hash160 => f.write_str("<hash160>"),
+            Token::Hash256 => f.write_str("<hash256>"),
+            Token::Num => f.write_str("<num>"),
+            Token::Data => f.write_str("<data>"),
+            Token::Any => f.write_str("<any>"),
+            Token::Rest => f.write_str("..."),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptTemplate {
+    tokens: Vec<Token>,
+}
+
+impl ScriptTemplate {
+    // The whole script must match; scripts with a push past the end never do
+    pub fn matches(&self, script: &[u8]) -> bool {
+        instructions(script).is_some_and(|ins| match_from(&self.tokens, &ins))
+    }
+}
+
+fn match_from(tokens: &[Token], ins: &[Instruction]) -> bool {
+    match tokens.split_first() {
+        None => ins.is_empty(),
+        Some((Token::Rest, rest)) => (0..=ins.len()).any(|skip| match_from(rest, &ins[skip..])),
+        Some((token, rest)) => ins.split_first().is_some_and(|(first, others)| token.matches(first) && match_from(rest, others)),
+    }
+}
+
+impl FromStr for ScriptTemplate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens = s.split_whitespace()
+            .map(|word| {
+                Ok(match word {
+                    "<pubkey>" => Token::Pubkey,
+                    "<xonly>" => Token::XOnly,
+                    "<hash160>" => Token::Hash160,
+                    "<hash256>" => Token::Hash256,
+                    "<num>" => Token::Num,
+                    "<data>" => Token::Data,
+                    "<any>" => Token::Any,
+                    "..." => Token::Rest,
+                    "OP_FALSE" => Token::Op(OP_0),
+                    "OP_TRUE" => Token::Op(OP_1),
+                    word if word.starts_with("OP_") => (0..=u8::MAX)
+                        // pushes are written as data, not OP_PUSHBYTES_n
+                        .filter(|&op| !(0x01..=0x4e).contains(&op))
+                        .find(|&op| opcode_name(op) == word)
+                        .map(Token::Op)
+                        .ok_or_else(|| format!("unknown opcode '{}'", word))?,
+                    word if word.starts_with('<') => return Err(format!("unknown placeholder '{}'", word)),
+                    word => match hex::decode(word) {
+                        Ok(data) if !data.is_empty() => Token::Push(data),
+                        _ => return Err(format!("'{}' is neither an opcode, a placeholder nor hex data", word)),
+                    },
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        if tokens.is_empty() {
+            return Err("empty script template".to_string());
+        }
+        Ok(ScriptTemplate { tokens })
+    }
+}
+
+impl fmt::Display for ScriptTemplate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let words: Vec<String> = self.tokens.iter().map(|t| t.to_string()).collect();
+        f.write_str(&words.join(" "))
+    }
+}
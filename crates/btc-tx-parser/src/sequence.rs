@@ -0,0 +1,128 @@
+//! nSequence with its BIP-125 (replace-by-fee) and BIP-68 (relative
+//! locktime) meaning
+
+use std::fmt;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use crate::timelock::{SEQUENCE_DISABLE_FLAG, SEQUENCE_MASK, SEQUENCE_TYPE_FLAG};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Sequence(pub u32);
+
+// BIP-68 lock in blocks or in 512-second intervals
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RelativeLockTime {
+    Blocks(u16),
+    Time(u16),
+}
+
+impl Sequence {
+    // Final: disables nLockTime too, unless another input isn't final
+    pub const MAX: Sequence = Sequence(0xffffffff);
+    // Highest value that still enforces nLockTime without signaling RBF
+    pub const ENABLE_LOCKTIME_NO_RBF: Sequence = Sequence(0xfffffffe);
+    pub const ENABLE_RBF_NO_LOCKTIME: Sequence = Sequence(0xfffffffd);
+
+    pub fn is_final(self) -> bool {
+        self == Sequence::MAX
+    }
+
+    // BIP-125 opt-in: below 0xfffffffe
+    pub fn is_rbf_signaling(self) -> bool {
+        self < Sequence::ENABLE_LOCKTIME_NO_RBF
+    }
+
+    // BIP-68 lock, None when the disable bit is set. Only enforced in
+    // version 2+ transactions.
+    pub fn relative_locktime(self) -> Option<RelativeLockTime> {
+        if self.0 & SEQUENCE_DISABLE_FLAG != 0 {
+            return None;
+        }
+        let value = (self.0 & SEQUENCE_MASK) as u16;
+        Some(if self.0 & SEQUENCE_TYPE_FLAG != 0 { RelativeLockTime::Time(value) } else { RelativeLockTime::Blocks(value) })
+    }
+}
+
+impl From<u32> for Sequence {
+    fn from(value: u32) -> Self {
+        Sequence(value)
+    }
+}
+
+impl fmt::LowerHex for Sequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerHex::fmt(&self.0, f)
+    }
+}
+
+impl RelativeLockTime {
+    pub fn seconds(self) -> Option<u32> {
+        match self {
+            RelativeLockTime::Blocks(_) => None,
+            RelativeLockTime::Time(intervals) => Some(intervals as u32 * 512),
+        }
+    }
+}
+
+// "144 blocks" or "1024 seconds (2 × 512 s)"
+impl fmt::Display for RelativeLockTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RelativeLockTime::Blocks(1) => write!(f, "1 block"),
+            RelativeLockTime::Blocks(blocks) => write!(f, "{} blocks", blocks),
+            RelativeLockTime::Time(intervals) => write!(f, "{} seconds ({} × 512 s)", *intervals as u32 * 512, intervals),
+        }
+    }
+}
+
+// {"raw": 144, "rbf": true, "final": false, "relative_locktime": {"blocks": 144}},
+// with {"seconds": 1024} for time locks and "disabled" when the disable bit is set
+#[derive(Serialize, Deserialize)]
+struct Decoded {
+    raw: u32,
+    rbf: bool,
+    #[serde(rename = "final")]
+    is_final: bool,
+    #[serde(default)]
+    relative_locktime: Lock,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Lock {
+    #[default]
+    Disabled,
+    Blocks(u16),
+    Seconds(u32),
+}
+
+// Plain integers are accepted too, as older JSON carried the raw value
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Encoded {
+    Raw(u32),
+    Decoded(Decoded),
+}
+
+impl Serialize for Sequence {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Decoded {
+            raw: self.0,
+            rbf: self.is_rbf_signaling(),
+            is_final: self.is_final(),
+            relative_locktime: match self.relative_locktime() {
+                None => Lock::Disabled,
+                Some(RelativeLockTime::Blocks(blocks)) => Lock::Blocks(blocks),
+                Some(RelativeLockTime::Time(intervals)) => Lock::Seconds(intervals as u32 * 512),
+            },
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Sequence {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(match Encoded::deserialize(deserializer)? {
+            Encoded::Raw(raw) | Encoded::Decoded(Decoded { raw, .. }) => Sequence(raw),
+        })
+    }
+}
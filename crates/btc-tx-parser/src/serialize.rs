@@ -0,0 +1,67 @@
+//! Consensus serialization of parsed transactions
+
+use crate::parser::Parser;
+use crate::types::Transaction;
+
+impl Transaction {
+    // Consensus serialization, including witness data for segwit transactions.
+    // Only the consensus fields are used; txid, weight and sizes are not recomputed.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        serialize(self, self.is_segwit)
+    }
+
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.to_bytes())
+    }
+
+    // Legacy serialization (the bytes the txid commits to)
+    pub fn to_bytes_without_witness(&self) -> Vec<u8> {
+        serialize(self, false)
+    }
+
+    pub fn to_hex_without_witness(&self) -> String {
+        hex::encode(self.to_bytes_without_witness())
+    }
+}
+
+// Raw transaction bytes; `with_witness` adds the marker, flag and witness stacks
+pub(crate) fn serialize(tx: &Transaction, with_witness: bool) -> Vec<u8> {
+    let mut buf = Vec::with_capacity(tx.raw_size);
+    buf.extend_from_slice(&tx.version.to_le_bytes());
+    if with_witness {
+        buf.extend_from_slice(&[0x00, 0x01]);
+    }
+
+    Parser::write_varint(&mut buf, tx.inputs.len() as u64);
+    for input in &tx.inputs {
+        buf.extend_from_slice(input.txid.as_bytes());
+        buf.extend_from_slice(&input.vout.to_le_bytes());
+        write_bytes(&mut buf, &input.script_sig.bytes);
+        buf.extend_from_slice(&input.sequence.0.to_le_bytes());
+    }
+
+    Parser::write_varint(&mut buf, tx.outputs.len() as u64);
+    for output in &tx.outputs {
+        buf.extend_from_slice(&output.value.to_sat().to_le_bytes());
+        write_bytes(&mut buf, &output.script_pubkey.bytes);
+    }
+
+    if with_witness {
+        for input in &tx.inputs {
+            let witness = input.witness.as_deref().unwrap_or_default();
+            Parser::write_varint(&mut buf, witness.len() as u64);
+            for item in witness {
+                write_bytes(&mut buf, item);
+            }
+        }
+    }
+
+    buf.extend_from_slice(&tx.locktime.to_consensus_u32().to_le_bytes());
+    buf
+}
+
+// Length-prefixed bytes
+fn write_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    Parser::write_varint(buf, bytes.len() as u64);
+    buf.extend_from_slice(bytes);
+}
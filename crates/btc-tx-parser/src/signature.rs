@@ -0,0 +1,360 @@
+//! Input signatures: ECDSA DER decoding with a canonicality report (BIP66
+//! strict DER, BIP146 low S), BIP-340 Schnorr signatures of taproot key-path
+//! spends, and wallet fingerprints such as low-R grinding (Bitcoin Core since
+//! 0.17 grinds the nonce until R fits in 32 bytes)
+
+use std::fmt;
+use std::str::FromStr;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use crate::analysis::{Analyzer, Finding};
+use crate::script::{instructions, Instruction};
+use crate::types::{Transaction, TxInput};
+
+// secp256k1 order / 2; larger S values are non-standard (BIP146)
+const HALF_ORDER: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
+// Sighash flag byte appended to a signature; shown and serialized as
+// "ALL", "SINGLE|ANYONECANPAY" or, for undefined base types, "0x04"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SighashType(pub u8);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureKind {
+    // DER-encoded ECDSA
+    #[default]
+    Ecdsa,
+    // 64-byte BIP-340 signature of a taproot key-path spend
+    Schnorr,
+}
+
+// Signature found in an input, as listed in `TxInput::signatures`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputSignature {
+    // "script_sig.push[0]" or "witness[0]"
+    pub location: String,
+    #[serde(default)]
+    pub kind: SignatureKind,
+    pub sighash: SighashType,
+    // R and S (hex, without DER sign padding); R's x coordinate for Schnorr
+    pub r: String,
+    pub s: String,
+    // as pushed, sighash byte included
+    #[serde(default)]
+    pub length: usize,
+    // ECDSA only: R encoded in at most 32 bytes (no sign padding byte)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub low_r: Option<bool>,
+    // ECDSA only; BIP-340 has no high-S twin
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub low_s: Option<bool>,
+    // ways the encoding breaks strict DER; empty when canonical
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub der_issues: Vec<String>,
+}
+
+// A DER-like ECDSA signature, decoded leniently so that encodings from
+// before BIP66 still yield R and S along with what is wrong with them
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct DerSignature {
+    // as encoded, sign padding included
+    pub r: Vec<u8>,
+    pub s: Vec<u8>,
+    pub sighash: SighashType,
+    pub issues: Vec<String>,
+}
+
+// Per-transaction aggregate of the strict-DER ECDSA signatures
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignatureSummary {
+    pub count: usize,
+    pub low_r: usize,
+    pub high_s: usize,
+    // encoded lengths in input order
+    pub lengths: Vec<usize>,
+}
+
+impl SighashType {
+    // BIP-341: signs like ALL, implied by a 64-byte Schnorr signature
+    pub const DEFAULT: SighashType = SighashType(0x00);
+    pub const ALL: SighashType = SighashType(0x01);
+    pub const NONE: SighashType = SighashType(0x02);
+    pub const SINGLE: SighashType = SighashType(0x03);
+    pub const ANYONECANPAY: u8 = 0x80;
+
+    pub fn anyone_can_pay(self) -> bool {
+        self.0 & Self::ANYONECANPAY != 0
+    }
+}
+
+impl fmt::Display for SighashType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 & 0x7f {
+            0x00 if self.0 == 0 => return write!(f, "DEFAULT"),
+            0x01 => write!(f, "ALL")?,
+            0x02 => write!(f, "NONE")?,
+            0x03 => write!(f, "SINGLE")?,
+            other => write!(f, "0x{:02x}", other)?,
+        }
+        if self.anyone_can_pay() {
+            write!(f, "|ANYONECANPAY")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for SighashType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (base, flag) = match s.strip_suffix("|ANYONECANPAY") {
+            Some(base) => (base, Self::ANYONECANPAY),
+            None => (s, 0),
+        };
+        let base = match base {
+            "DEFAULT" if flag == 0 => 0x00,
+            "ALL" => 0x01,
+            "NONE" => 0x02,
+            "SINGLE" => 0x03,
+            other => other.strip_prefix("0x")
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| format!("Unknown sighash type '{}'", s))?,
+        };
+        Ok(SighashType(base | flag))
+    }
+}
+
+impl Serialize for SighashType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for SighashType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl InputSignature {
+    // An ECDSA signature pushed at `location`, sighash byte last. None unless
+    // it is a SEQUENCE of two INTEGERs; anything looser is listed in `der_issues`.
+    pub fn from_der(location: String, data: &[u8]) -> Option<Self> {
+        let der = DerSignature::decode(data)?;
+        Some(InputSignature {
+            location,
+            kind: SignatureKind::Ecdsa,
+            sighash: der.sighash,
+            r: hex::encode(strip_padding(&der.r)),
+            s: hex::encode(strip_padding(&der.s)),
+            length: data.len(),
+            low_r: Some(der.r.len() <= 32),
+            low_s: Some(is_low_s(&der.s)),
+            der_issues: der.issues,
+        })
+    }
+
+    pub fn is_canonical(&self) -> bool {
+        self.der_issues.is_empty()
+    }
+
+    // ECDSA in the encoding BIP66 requires; the signatures wallet
+    // fingerprints and nonce reuse are read from
+    pub fn is_strict_ecdsa(&self) -> bool {
+        self.kind == SignatureKind::Ecdsa && self.is_canonical()
+    }
+}
+
+impl DerSignature {
+    fn decode(data: &[u8]) -> Option<Self> {
+        let (&sighash, der) = data.split_last()?;
+        let (&[0x30, declared], body) = der.split_first_chunk::<2>()? else {
+            return None;
+        };
+        let mut issues = Vec::new();
+        let (r, rest) = lenient_integer(body, "R", &mut issues)?;
+        let (s, rest) = lenient_integer(rest, "S", &mut issues)?;
+
+        if declared as usize != body.len() {
+            issues.push(format!("sequence length is {} but {} bytes follow", declared, body.len()));
+        }
+        if !rest.is_empty() {
+            issues.push(format!("{} byte(s) after S", rest.len()));
+        }
+        if der.len() > 72 {
+            issues.push(format!("{} bytes, over the 72-byte maximum", der.len()));
+        }
+        Some(DerSignature { r: r.to_vec(), s: s.to_vec(), sighash: SighashType(sighash), issues })
+    }
+}
+
+// 0x02 <len> <value>; a value that is empty, over 33 bytes, negative or
+// padded with a needless zero is not strict DER
+fn lenient_integer<'a>(data: &'a [u8], name: &str, issues: &mut Vec<String>) -> Option<(&'a [u8], &'a [u8])> {
+    let (&[0x02, len], rest) = data.split_first_chunk::<2>()? else {
+        return None;
+    };
+    let len = len as usize;
+    if rest.len() < len {
+        return None;
+    }
+    let (value, rest) = rest.split_at(len);
+    match value {
+        [] => issues.push(format!("{} is empty", name)),
+        [first, ..] if first & 0x80 != 0 => issues.push(format!("{} is negative", name)),
+        [0, second, ..] if second & 0x80 == 0 => issues.push(format!("{} has excess zero padding", name)),
+        _ => {}
+    }
+    if len > 33 {
+        issues.push(format!("{} is {} bytes", name, len));
+    }
+    Some((value, rest))
+}
+
+impl TxInput {
+    // The key-path Schnorr signature, or the DER-like signatures in the
+    // scriptSig and witness, canonical or not, as the parser fills `signatures`
+    pub fn parse_signatures(&self) -> Vec<InputSignature> {
+        if let Some(signature) = key_path_signature(self) {
+            let (rs, sighash) = signature.split_at(64);
+            return vec![InputSignature {
+                location: "witness[0]".to_string(),
+                kind: SignatureKind::Schnorr,
+                sighash: sighash.first().map_or(SighashType::DEFAULT, |&byte| SighashType(byte)),
+                r: hex::encode(&rs[..32]),
+                s: hex::encode(&rs[32..]),
+                length: signature.len(),
+                low_r: None,
+                low_s: None,
+                der_issues: Vec::new(),
+            }];
+        }
+        signature_pushes(self).into_iter()
+            .filter_map(|(location, data)| InputSignature::from_der(location, data))
+            .collect()
+    }
+}
+
+// BIP-341 key-path spend: an empty scriptSig and a single 64- or 65-byte
+// witness item, not counting an annex (a last item starting with 0x50)
+fn key_path_signature(input: &TxInput) -> Option<&[u8]> {
+    if input.is_coinbase || !input.script_sig.bytes.is_empty() {
+        return None;
+    }
+    let items = match input.witness.as_deref()? {
+        [items @ .., annex] if !items.is_empty() && annex.first() == Some(&0x50) => items,
+        items => items,
+    };
+    match items {
+        [signature] if signature.len() == 64 || signature.len() == 65 => Some(signature),
+        _ => None,
+    }
+}
+
+// Pushes and witness items of one input that could be signatures, with
+// their location within it
+fn signature_pushes(input: &TxInput) -> Vec<(String, &[u8])> {
+    if input.is_coinbase {
+        return Vec::new();
+    }
+    let pushes = instructions(&input.script_sig.bytes).unwrap_or_default().into_iter()
+        .filter_map(|ins| match ins {
+            Instruction::Push(data) => Some(data),
+            Instruction::Op(_) => None,
+        });
+    let pushes = pushes.enumerate().map(|(i, data)| (format!("script_sig.push[{}]", i), data));
+    let items = input.witness.iter().flatten().enumerate().map(|(i, item)| (format!("witness[{}]", i), item.as_slice()));
+    pushes.chain(items).collect()
+}
+
+impl Transaction {
+    // Strict-DER ECDSA entries of the inputs' `signatures`, with the index
+    // of the input each signs
+    pub fn ecdsa_signatures(&self) -> Vec<(usize, &InputSignature)> {
+        self.inputs.iter()
+            .flat_map(|input| input.signatures.iter()
+                .filter(|s| s.is_strict_ecdsa())
+                .map(|s| (input.index, s)))
+            .collect()
+    }
+
+    pub fn signature_summary(&self) -> SignatureSummary {
+        let signatures = self.ecdsa_signatures();
+        SignatureSummary {
+            count: signatures.len(),
+            low_r: signatures.iter().filter(|(_, s)| s.low_r == Some(true)).count(),
+            high_s: signatures.iter().filter(|(_, s)| s.low_s == Some(false)).count(),
+            lengths: signatures.iter().map(|(_, s)| s.length).collect(),
+        }
+    }
+}
+
+fn strip_padding(value: &[u8]) -> &[u8] {
+    &value[value.iter().take_while(|b| **b == 0).count()..]
+}
+
+fn is_low_s(s: &[u8]) -> bool {
+    let s = strip_padding(s);
+    s.len() < 32 || (s.len() == 32 && s <= HALF_ORDER.as_slice())
+}
+
+// Wallet fingerprints from the signatures: all-low-R (nonce grinding) and
+// any high-R or high-S signature; also encodings BIP66 would reject
+pub struct SignatureAnalyzer;
+
+impl Analyzer for SignatureAnalyzer {
+    fn name(&self) -> &'static str {
+        "signature"
+    }
+
+    fn analyze(&self, tx: &Transaction) -> Vec<Finding> {
+        let signatures = tx.ecdsa_signatures();
+        let mut findings = Vec::new();
+        for input in &tx.inputs {
+            for signature in input.signatures.iter().filter(|s| !s.is_canonical()) {
+                findings.push(Finding::new(
+                    self.name(),
+                    "non_canonical_der",
+                    format!("inputs[{}].{} is not strict DER: {}", input.index, signature.location, signature.der_issues.join(", ")),
+                ).on_input(input.index));
+            }
+        }
+        if signatures.is_empty() {
+            return findings;
+        }
+
+        let lengths: Vec<String> = signatures.iter().map(|(_, s)| s.length.to_string()).collect();
+        if signatures.iter().all(|(_, s)| s.low_r == Some(true)) {
+            // a non-grinding signer produces low R half the time
+            let chance = 0.5f64.powi(signatures.len() as i32);
+            findings.push(Finding::heuristic(
+                self.name(),
+                "low_r_grinding",
+                format!("all {} signature(s) have a low R, as Bitcoin Core (0.17+) grinds for", signatures.len()),
+                1.0 - chance,
+                vec![
+                    format!("signature lengths {} bytes (at most 71 with low R)", lengths.join(", ")),
+                    format!("{:.0}% chance without grinding", chance * 100.0),
+                ],
+            ));
+        }
+        for (input, signature) in signatures.iter().filter(|(_, s)| s.low_r == Some(false)) {
+            findings.push(Finding::new(
+                self.name(),
+                "high_r",
+                format!("inputs[{}].{} has a 33-byte R ({} bytes), so the signer does not grind nonces", input, signature.location, signature.length),
+            ).on_input(*input));
+        }
+        for (input, signature) in signatures.iter().filter(|(_, s)| s.low_s == Some(false)) {
+            findings.push(Finding::new(
+                self.name(),
+                "high_s",
+                format!("inputs[{}].{} has a high S value, non-standard since BIP146", input, signature.location),
+            ).on_input(*input));
+        }
+        findings
+    }
+}
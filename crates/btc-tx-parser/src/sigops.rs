@@ -0,0 +1,76 @@
+//! Signature operation cost, counted the way Bitcoin Core's
+//! GetTransactionSigOpCost does. The spent outputs are not known, so P2SH
+//! and witness spends are recognized from the scriptSig and witness, as the
+//! rest of the crate does.
+
+use crate::script::opcodes::*;
+use crate::script::{looks_like_pubkey, next_instruction, Instruction, ScriptType};
+use crate::types::{Transaction, TxInput};
+
+const WITNESS_SCALE_FACTOR: u64 = 4;
+// what a CHECKMULTISIG costs when its key count isn't read
+const MAX_PUBKEYS_PER_MULTISIG: u64 = 20;
+
+impl Transaction {
+    // Legacy sigops scaled by the witness factor, plus P2SH redeem script and
+    // witness sigops; a coinbase counts legacy sigops only
+    pub fn sigop_cost(&self) -> u64 {
+        let legacy = self.inputs.iter().map(|input| count_sigops(&input.script_sig.bytes, false))
+            .chain(self.outputs.iter().map(|output| count_sigops(&output.script_pubkey.bytes, false)))
+            .fold(0u64, u64::saturating_add);
+        let mut cost = legacy.saturating_mul(WITNESS_SCALE_FACTOR);
+        if self.inputs.iter().any(|input| input.is_coinbase) {
+            return cost;
+        }
+        for input in &self.inputs {
+            let redeem = input.script_sig_parts().and_then(|parts| parts.redeem_script);
+            if let Some(redeem) = redeem {
+                cost = cost.saturating_add(count_sigops(&redeem.bytes, true).saturating_mul(WITNESS_SCALE_FACTOR));
+            }
+            cost = cost.saturating_add(witness_sigops(input));
+        }
+        cost
+    }
+}
+
+// One for P2WPKH, the witness script's accurate count for P2WSH, nothing
+// for taproot and unknown programs
+fn witness_sigops(input: &TxInput) -> u64 {
+    let Some(stack) = input.witness.as_deref().filter(|stack| !stack.is_empty()) else {
+        return 0;
+    };
+    if let Some(info) = input.witness_script_info() {
+        return count_sigops(&info.script.bytes, true);
+    }
+    let p2wpkh = match input.nested_segwit() {
+        Some(program) => program == ScriptType::P2WPKH,
+        // native P2WPKH: exactly a signature and a pubkey
+        None => input.script_sig.bytes.is_empty() && stack.len() == 2 && looks_like_pubkey(&stack[1]),
+    };
+    u64::from(p2wpkh)
+}
+
+// GetSigOpCount: CHECKSIG(VERIFY) is one; CHECKMULTISIG(VERIFY) is the key
+// count pushed just before it when `accurate` and it is OP_1..OP_16, 20
+// otherwise. Counting stops where a push runs past the end.
+pub(crate) fn count_sigops(script: &[u8], accurate: bool) -> u64 {
+    let mut count = 0u64;
+    let mut last = None;
+    let mut i = 0;
+    while let Some((instruction, next)) = next_instruction(script, i) {
+        match instruction {
+            Instruction::Op(OP_CHECKSIG | OP_CHECKSIGVERIFY) => count = count.saturating_add(1),
+            Instruction::Op(OP_CHECKMULTISIG | OP_CHECKMULTISIGVERIFY) => {
+                let keys = match last {
+                    Some(Instruction::Op(n @ OP_1..=OP_16)) if accurate => u64::from(n - OP_1 + 1),
+                    _ => MAX_PUBKEYS_PER_MULTISIG,
+                };
+                count = count.saturating_add(keys);
+            }
+            _ => {}
+        }
+        last = Some(instruction);
+        i = next;
+    }
+    count
+}
@@ -0,0 +1,21 @@
+//! Byte ranges of each serialized transaction field
+
+use serde::{Deserialize, Serialize};
+use crate::error::ParseError;
+use crate::parser::Parser;
+
+// Field name and its byte range [start, end) in the raw transaction, e.g.
+// "inputs[0].script_sig" or "outputs[1].value"
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FieldSpan {
+    pub field: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+// Walk a serialized transaction and name every byte range
+pub(crate) fn field_spans(bytes: &[u8]) -> Result<Vec<FieldSpan>, ParseError> {
+    let mut parser = Parser::with_spans(bytes);
+    parser.parse_transaction()?;
+    Ok(parser.take_spans())
+}
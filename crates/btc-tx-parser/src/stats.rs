@@ -0,0 +1,319 @@
+//! Aggregate statistics over a batch of transactions, including key,
+//! script and ECDSA nonce reuse across them
+
+use std::collections::{BTreeMap, HashMap};
+use serde::{Deserialize, Serialize};
+use crate::amount::Amount;
+use crate::inscription::envelopes;
+use crate::parser::Parser;
+use crate::script::{revealed_pubkeys, revealed_scripts, ScriptType};
+use crate::types::Transaction;
+
+// Largest standard data push; witness items beyond it are carrying data
+const MAX_STANDARD_PUSH: usize = 520;
+
+// Accumulates transactions one at a time; call `report()` when done
+#[derive(Debug, Default)]
+pub struct BatchStats {
+    transactions: usize,
+    segwit_transactions: usize,
+    inputs: usize,
+    outputs: usize,
+    total_output_satoshis: Amount,
+    total_vsize: usize,
+    script_types: BTreeMap<ScriptType, usize>,
+    feerates: Vec<f64>,
+    data_carriers: DataCarrierStats,
+    pubkey_uses: HashMap<String, Vec<InputRef>>,
+    script_uses: HashMap<String, Vec<InputRef>>,
+    // R value -> signatures using it
+    nonce_uses: HashMap<String, Vec<NonceUse>>,
+}
+
+#[derive(Debug)]
+struct NonceUse {
+    location: InputRef,
+    s: String,
+    pubkeys: Vec<String>,
+}
+
+// Serializable batch summary
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchReport {
+    pub transactions: usize,
+    pub segwit_transactions: usize,
+    pub inputs: usize,
+    pub outputs: usize,
+    pub total_output_satoshis: Amount,
+    pub total_vsize: usize,
+    // output count per script type
+    pub script_types: BTreeMap<ScriptType, usize>,
+    // feerate distribution over transactions with known input values
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feerates: Option<FeeratePercentiles>,
+    // block space taken by data rather than payments
+    pub data_carriers: DataCarrierStats,
+    // public keys revealed by more than one input
+    pub reused_pubkeys: Vec<ReusedItem>,
+    // redeem/witness/tap scripts revealed by more than one input
+    pub reused_scripts: Vec<ReusedItem>,
+    // ECDSA nonces shared by signatures over different messages
+    pub nonce_reuse: Vec<NonceReuse>,
+}
+
+// Feerate percentiles in sat/vB
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FeeratePercentiles {
+    // transactions that contributed a feerate
+    pub samples: usize,
+    pub p10: f64,
+    pub p50: f64,
+    pub p90: f64,
+}
+
+// Block space (weight units) used to carry data, by pattern; whatever is
+// left of `total_weight` went to monetary transfers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DataCarrierStats {
+    pub total_weight: u64,
+    // OP_RETURN outputs
+    pub op_return: CarrierUsage,
+    // tapscripts holding inscription envelopes
+    pub inscriptions: CarrierUsage,
+    // bare multisig outputs, used by Counterparty/Stamps-style encodings
+    pub bare_multisig: CarrierUsage,
+    // other witness items larger than a standard push
+    pub oversized_witness: CarrierUsage,
+    // transactions with at least one of the above
+    pub transactions_with_data: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CarrierUsage {
+    // outputs, inscriptions or witness items
+    pub count: usize,
+    pub weight: u64,
+}
+
+impl DataCarrierStats {
+    pub fn data_weight(&self) -> u64 {
+        self.op_return.weight + self.inscriptions.weight + self.bare_multisig.weight + self.oversized_witness.weight
+    }
+
+    pub fn monetary_weight(&self) -> u64 {
+        self.total_weight.saturating_sub(self.data_weight())
+    }
+
+    fn add(&mut self, tx: &Transaction) {
+        self.total_weight += tx.weight as u64;
+        let before = self.data_weight();
+
+        for output in &tx.outputs {
+            let usage = match output.script_type {
+                ScriptType::OpReturn => &mut self.op_return,
+                ScriptType::Multisig => &mut self.bare_multisig,
+                _ => continue,
+            };
+            let size = output.script_pubkey.size;
+            usage.count += 1;
+            usage.weight += 4 * (8 + Parser::varint_size(size as u64) + size) as u64;
+        }
+
+        for input in &tx.inputs {
+            // the tapscript holding envelopes counts as inscription data, not
+            // as an oversized witness item
+            let inscribed = revealed_scripts(input).into_iter()
+                .filter(|(origin, _)| *origin == "tapscript")
+                .map(|(_, script)| (envelopes(&script).len(), script))
+                .find(|(count, _)| *count > 0);
+            if let Some((count, _)) = inscribed {
+                self.inscriptions.count += count;
+            }
+
+            for item in input.witness.iter().flatten() {
+                let size = item.len();
+                let weight = (Parser::varint_size(size as u64) + size) as u64;
+                if inscribed.as_ref().is_some_and(|(_, script)| script == item) {
+                    self.inscriptions.weight += weight;
+                } else if size > MAX_STANDARD_PUSH {
+                    self.oversized_witness.count += 1;
+                    self.oversized_witness.weight += weight;
+                }
+            }
+        }
+
+        if self.data_weight() > before {
+            self.transactions_with_data += 1;
+        }
+    }
+}
+
+// Input location within the batch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InputRef {
+    pub txid: String,
+    pub input: usize,
+}
+
+// Data seen in several inputs, most-reused first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReusedItem {
+    // pubkey or script (hex)
+    pub hex: String,
+    // inputs that revealed it
+    pub occurrences: Vec<InputRef>,
+}
+
+// An R value shared by signatures over different messages. Two such
+// signatures by the same key give away its private key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NonceReuse {
+    // R (hex)
+    pub r: String,
+    // inputs whose signatures use it
+    pub occurrences: Vec<InputRef>,
+    // public keys revealed by those inputs
+    pub pubkeys: Vec<String>,
+    // every signature came from the one key, so it is recoverable
+    pub same_key: bool,
+}
+
+impl BatchStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, tx: &Transaction) {
+        self.transactions += 1;
+        if tx.is_segwit {
+            self.segwit_transactions += 1;
+        }
+        self.inputs += tx.inputs.len();
+        self.outputs += tx.outputs.len();
+        self.total_output_satoshis += tx.total_output_satoshis;
+        self.total_vsize += tx.vsize();
+
+        self.data_carriers.add(tx);
+
+        if let Some(fee) = tx.calculate_fee() {
+            self.feerates.push(fee.to_sat() as f64 / tx.vsize() as f64);
+        }
+
+        for output in &tx.outputs {
+            *self.script_types.entry(output.script_type.clone()).or_default() += 1;
+        }
+
+        for input in tx.inputs.iter().filter(|i| !i.is_coinbase) {
+            let location = InputRef { txid: tx.txid.clone(), input: input.index };
+
+            let mut pubkeys = revealed_pubkeys(input);
+            pubkeys.sort();
+            pubkeys.dedup();
+            for signature in input.signatures.iter().filter(|s| s.is_strict_ecdsa()) {
+                self.nonce_uses.entry(signature.r.clone()).or_default().push(NonceUse {
+                    location: location.clone(),
+                    s: signature.s.clone(),
+                    pubkeys: pubkeys.clone(),
+                });
+            }
+            for pubkey in pubkeys {
+                self.pubkey_uses.entry(pubkey).or_default().push(location.clone());
+            }
+
+            for (_, script) in revealed_scripts(input) {
+                self.script_uses.entry(hex::encode(script)).or_default().push(location.clone());
+            }
+        }
+    }
+
+    pub fn report(&self) -> BatchReport {
+        BatchReport {
+            transactions: self.transactions,
+            segwit_transactions: self.segwit_transactions,
+            inputs: self.inputs,
+            outputs: self.outputs,
+            total_output_satoshis: self.total_output_satoshis,
+            total_vsize: self.total_vsize,
+            script_types: self.script_types.clone(),
+            feerates: percentiles(&self.feerates),
+            data_carriers: self.data_carriers,
+            reused_pubkeys: reused(&self.pubkey_uses),
+            reused_scripts: reused(&self.script_uses),
+            nonce_reuse: nonce_reuse(&self.nonce_uses),
+        }
+    }
+}
+
+fn reused(uses: &HashMap<String, Vec<InputRef>>) -> Vec<ReusedItem> {
+    let mut items: Vec<ReusedItem> = uses.iter()
+        .filter(|(_, occurrences)| occurrences.len() > 1)
+        .map(|(hex, occurrences)| ReusedItem { hex: hex.clone(), occurrences: occurrences.clone() })
+        .collect();
+    items.sort_by(|a, b| b.occurrences.len().cmp(&a.occurrences.len()).then(a.hex.cmp(&b.hex)));
+    items
+}
+
+// R values with more than one distinct S. The same signature seen twice
+// (a transaction repeated in the batch) is not a reuse.
+fn nonce_reuse(uses: &HashMap<String, Vec<NonceUse>>) -> Vec<NonceReuse> {
+    let mut reuses: Vec<NonceReuse> = uses.iter()
+        .filter(|(_, uses)| uses.iter().any(|u| u.s != uses[0].s))
+        .map(|(r, uses)| {
+            let mut occurrences: Vec<InputRef> = Vec::new();
+            for seen in uses {
+                if !occurrences.contains(&seen.location) {
+                    occurrences.push(seen.location.clone());
+                }
+            }
+            let mut pubkeys: Vec<String> = uses.iter().flat_map(|u| u.pubkeys.clone()).collect();
+            pubkeys.sort();
+            pubkeys.dedup();
+            let same_key = pubkeys.len() == 1 && uses.iter().all(|u| u.pubkeys.len() == 1);
+            NonceReuse { r: r.clone(), occurrences, pubkeys, same_key }
+        })
+        .collect();
+    reuses.sort_by(|a, b| b.occurrences.len().cmp(&a.occurrences.len()).then(a.r.cmp(&b.r)));
+    reuses
+}
+
+// One `<raw_tx_hex> [v1,v2,...]` batch line. Input values, one per input,
+// fill in the fee so the transaction counts towards feerate percentiles.
+pub fn parse_batch_line(line: &str) -> Result<Transaction, String> {
+    let mut fields = line.split_whitespace();
+    let tx_hex = fields.next().unwrap_or_default();
+    let mut tx = Transaction::from_hex(tx_hex).map_err(|e| e.to_string())?;
+
+    if let Some(values) = fields.next() {
+        let values = values.split(',')
+            .map(|v| v.parse::<u64>().map_err(|e| format!("Invalid input value '{}': {}", v, e)))
+            .collect::<Result<Vec<u64>, String>>()?;
+        if values.len() != tx.inputs.len() {
+            return Err(format!("{} input values given for {} inputs", values.len(), tx.inputs.len()));
+        }
+        for (input, value) in tx.inputs.iter_mut().zip(values) {
+            input.value = Some(Amount::from_sat(value));
+        }
+        if let Some(fee) = tx.calculate_fee() {
+            tx.set_fee(Some(fee));
+        }
+    }
+
+    Ok(tx)
+}
+
+// Nearest-rank percentiles
+fn percentiles(feerates: &[f64]) -> Option<FeeratePercentiles> {
+    if feerates.is_empty() {
+        return None;
+    }
+    let mut sorted = feerates.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let rank = |p: usize| sorted[(p * sorted.len()).div_ceil(100).max(1) - 1];
+
+    Some(FeeratePercentiles {
+        samples: sorted.len(),
+        p10: rank(10),
+        p50: rank(50),
+        p90: rank(90),
+    })
+}
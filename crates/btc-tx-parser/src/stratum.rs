@@ -0,0 +1,74 @@
+//! Stratum (v1) job reconstruction: coinbase transaction and merkle root
+
+use serde::{Deserialize, Serialize};
+use crate::hash::sha256d;
+use crate::error::ParseError;
+use crate::hexcodec::encode_hex_reversed;
+use crate::types::Transaction;
+
+// Parts of a `mining.notify` job plus the miner's extranonces (all hex)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StratumJob {
+    pub coinb1: String,
+    pub coinb2: String,
+    // assigned by the pool in `mining.subscribe`
+    pub extranonce1: String,
+    // chosen by the miner
+    pub extranonce2: String,
+    // sibling hashes from the coinbase up to the root
+    #[serde(default)]
+    pub merkle_branch: Vec<String>,
+}
+
+// Reconstructed coinbase and the merkle root it produces
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StratumCoinbase {
+    pub coinbase_hex: String,
+    // display order, as shown by block explorers
+    pub merkle_root: String,
+    // byte order used in the block header
+    pub merkle_root_header: String,
+    pub coinbase: Transaction,
+}
+
+impl StratumJob {
+    pub fn coinbase_hex(&self) -> String {
+        [&self.coinb1, &self.extranonce1, &self.extranonce2, &self.coinb2]
+            .iter()
+            .map(|part| part.trim())
+            .collect()
+    }
+
+    pub fn reconstruct(&self) -> Result<StratumCoinbase, ParseError> {
+        let coinbase_hex = self.coinbase_hex();
+        let bytes = hex::decode(&coinbase_hex)?;
+        let coinbase = Transaction::from_bytes(&bytes)?;
+        if !coinbase.inputs.first().is_some_and(|i| i.is_coinbase) || coinbase.inputs.len() != 1 {
+            return Err(ParseError::InvalidStratumJob(
+                "coinb1 + extranonces + coinb2 is not a coinbase transaction".to_string(),
+            ));
+        }
+
+        let mut root = sha256d(&bytes);
+        for (i, branch) in self.merkle_branch.iter().enumerate() {
+            let sibling = hex::decode(branch.trim())?;
+            if sibling.len() != 32 {
+                return Err(ParseError::InvalidStratumJob(format!(
+                    "merkle branch #{} is {} bytes, expected 32",
+                    i,
+                    sibling.len()
+                )));
+            }
+            let mut pair = root.to_vec();
+            pair.extend_from_slice(&sibling);
+            root = sha256d(&pair);
+        }
+
+        Ok(StratumCoinbase {
+            coinbase_hex,
+            merkle_root: encode_hex_reversed(&root),
+            merkle_root_header: hex::encode(root),
+            coinbase,
+        })
+    }
+}
@@ -0,0 +1,161 @@
+//! Streaming parser over any `io::Read`: transactions are decoded as soon as
+//! their last byte arrives, holding only the transaction being read in memory
+
+use std::io::{ErrorKind, Read};
+use crate::block::{parse_header, BlockHeader};
+use crate::error::ParseError;
+use crate::parser::Parser;
+use crate::types::Transaction;
+
+// Larger than any transaction that fits in a block
+const MAX_TRANSACTION_SIZE: usize = 4_000_000;
+
+// Concatenated raw transactions, or a block's header followed by its
+// transactions. Iterating yields transactions until the end of the stream and
+// stops after an error.
+pub struct StreamParser<R> {
+    reader: R,
+    // bytes consumed so far
+    position: u64,
+    // raw bytes of the transaction being read
+    buf: Vec<u8>,
+    done: bool,
+}
+
+impl<R: Read> StreamParser<R> {
+    pub fn new(reader: R) -> Self {
+        StreamParser { reader, position: 0, buf: Vec::new(), done: false }
+    }
+
+    // Stream offset of the next byte to be read
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    // Block header and transaction count; the transactions follow with
+    // `next_transaction` (the merkle root can't be checked without them all)
+    pub fn read_block_header(&mut self) -> Result<Option<(BlockHeader, u64)>, ParseError> {
+        self.buf.clear();
+        if !self.fill(80, true)? {
+            return Ok(None);
+        }
+        let header = parse_header(&mut Parser::new(&self.buf))?;
+        let count = self.read_varint()?;
+        debug!(hash = %header.hash, count, "read block header");
+        Ok(Some((header, count)))
+    }
+
+    // Next transaction, or None at a clean end of the stream
+    pub fn next_transaction(&mut self) -> Result<Option<Transaction>, ParseError> {
+        self.buf.clear();
+        if !self.fill(4, true)? {
+            return Ok(None);
+        }
+
+        // a zero input count is the segwit marker, which the flag must follow
+        let mut input_count = self.read_varint()?;
+        let is_segwit = input_count == 0;
+        if is_segwit {
+            if self.read_u8()? != 0x01 {
+                return Err(ParseError::InvalidTransaction("Transaction has no inputs".to_string()));
+            }
+            input_count = self.read_varint()?;
+        }
+        for _ in 0..input_count {
+            self.fill(36, false)?;
+            self.read_sized()?;
+            self.fill(4, false)?;
+        }
+        for _ in 0..self.read_varint()? {
+            self.fill(8, false)?;
+            self.read_sized()?;
+        }
+        if is_segwit {
+            for _ in 0..input_count {
+                for _ in 0..self.read_varint()? {
+                    self.read_sized()?;
+                }
+            }
+        }
+        self.fill(4, false)?;
+
+        trace!(len = self.buf.len(), offset = self.position, "read transaction");
+        Transaction::from_bytes_strict(&self.buf).map(Some)
+    }
+
+    // Append `n` bytes to the transaction buffer; false if `eof_ok` and the
+    // stream ended before the first byte
+    fn fill(&mut self, n: usize, eof_ok: bool) -> Result<bool, ParseError> {
+        if self.buf.len() + n > MAX_TRANSACTION_SIZE {
+            return Err(ParseError::InvalidTransaction(format!(
+                "more than {} bytes at offset {}", MAX_TRANSACTION_SIZE, self.position
+            )));
+        }
+        let start = self.buf.len();
+        self.buf.resize(start + n, 0);
+        let mut filled = 0;
+        while filled < n {
+            match self.reader.read(&mut self.buf[start + filled..]) {
+                Ok(0) if filled == 0 && eof_ok => {
+                    self.buf.truncate(start);
+                    return Ok(false);
+                }
+                Ok(0) => {
+                    return Err(ParseError::UnexpectedEof {
+                        position: (self.position + filled as u64) as usize,
+                        expected: n - filled,
+                    })
+                }
+                Ok(read) => filled += read,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        self.position += n as u64;
+        Ok(true)
+    }
+
+    fn read_u8(&mut self) -> Result<u8, ParseError> {
+        self.fill(1, false)?;
+        Ok(self.buf[self.buf.len() - 1])
+    }
+
+    fn read_varint(&mut self) -> Result<u64, ParseError> {
+        let width = match self.read_u8()? {
+            0xfd => 2,
+            0xfe => 4,
+            0xff => 8,
+            n => return Ok(n as u64),
+        };
+        self.fill(width, false)?;
+        let mut bytes = [0u8; 8];
+        bytes[..width].copy_from_slice(&self.buf[self.buf.len() - width..]);
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    // Varint length followed by that many bytes
+    fn read_sized(&mut self) -> Result<(), ParseError> {
+        let len = self.read_varint()?;
+        let len = usize::try_from(len).unwrap_or(usize::MAX).min(MAX_TRANSACTION_SIZE + 1);
+        self.fill(len, false).map(|_| ())
+    }
+}
+
+impl<R: Read> Iterator for StreamParser<R> {
+    type Item = Result<Transaction, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let next = self.next_transaction().transpose();
+        if !matches!(next, Some(Ok(_))) {
+            self.done = true;
+        }
+        next
+    }
+}
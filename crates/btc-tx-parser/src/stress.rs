@@ -0,0 +1,110 @@
+//! Release self-check: parse and re-serialize seeded random transactions,
+//! timing both and collecting every transaction that doesn't round-trip
+
+use serde::Serialize;
+#[cfg(feature = "testutil")]
+use std::time::{Duration, Instant};
+#[cfg(feature = "testutil")]
+use crate::{hexcodec::encode_hex, testutil::TxGenerator, types::Transaction};
+
+// Mismatches kept in full; the rest are only counted
+#[cfg(feature = "testutil")]
+const MAX_REPORTED: usize = 20;
+
+#[derive(Debug, Serialize)]
+pub struct StressReport {
+    pub seed: u64,
+    pub count: u64,
+    // total consensus bytes generated
+    pub bytes: u64,
+    pub parse_secs: f64,
+    pub serialize_secs: f64,
+    // transactions parsed per second
+    pub parse_tx_per_sec: f64,
+    pub parse_mib_per_sec: f64,
+    pub mismatch_count: u64,
+    // the first few, to reproduce from
+    pub mismatches: Vec<Mismatch>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Mismatch {
+    // position in the seed's sequence, from 0
+    pub index: u64,
+    pub problem: String,
+    pub hex: String,
+}
+
+impl StressReport {
+    pub fn passed(&self) -> bool {
+        self.mismatch_count == 0
+    }
+
+    // Generate `count` transactions from `seed`; `progress` is called after each
+    #[cfg(feature = "testutil")]
+    pub fn run(count: u64, seed: u64, progress: impl Fn(u64)) -> StressReport {
+        let mut generator = TxGenerator::new(seed);
+        let mut bytes = 0;
+        let mut parse_time = Duration::ZERO;
+        let mut serialize_time = Duration::ZERO;
+        let mut mismatch_count = 0;
+        let mut mismatches = Vec::new();
+
+        for index in 0..count {
+            let raw = generator.next_tx();
+            bytes += raw.len() as u64;
+
+            let started = Instant::now();
+            let parsed = Transaction::from_bytes_strict(&raw);
+            parse_time += started.elapsed();
+
+            let problem = match parsed {
+                Err(e) => Some(format!("failed to parse: {}", e)),
+                Ok(tx) => {
+                    let started = Instant::now();
+                    let serialized = tx.to_bytes();
+                    serialize_time += started.elapsed();
+                    check_round_trip(&raw, &tx, serialized)
+                }
+            };
+            if let Some(problem) = problem {
+                mismatch_count += 1;
+                if mismatches.len() < MAX_REPORTED {
+                    mismatches.push(Mismatch { index, problem, hex: encode_hex(&raw) });
+                }
+            }
+            progress(index + 1);
+        }
+
+        let parse_secs = parse_time.as_secs_f64();
+        let per_sec = |amount: f64| if parse_secs > 0.0 { amount / parse_secs } else { 0.0 };
+        StressReport {
+            seed,
+            count,
+            bytes,
+            parse_secs,
+            serialize_secs: serialize_time.as_secs_f64(),
+            parse_tx_per_sec: per_sec(count as f64),
+            parse_mib_per_sec: per_sec(bytes as f64 / (1 << 20) as f64),
+            mismatch_count,
+            mismatches,
+        }
+    }
+}
+
+// What differs between the generated bytes and the parsed transaction, if anything
+#[cfg(feature = "testutil")]
+fn check_round_trip(raw: &[u8], tx: &Transaction, serialized: Vec<u8>) -> Option<String> {
+    if serialized != raw {
+        let at = serialized.iter().zip(raw).position(|(a, b)| a != b).unwrap_or(serialized.len().min(raw.len()));
+        return Some(format!("re-serialized bytes differ from byte {} ({} bytes, expected {})", at, serialized.len(), raw.len()));
+    }
+    if tx.raw_size != raw.len() {
+        return Some(format!("size is {}, expected {}", tx.raw_size, raw.len()));
+    }
+    match Transaction::from_hex(&encode_hex(&serialized)) {
+        Ok(again) if again.txid != tx.txid || again.wtxid != tx.wtxid => Some("txid or wtxid changed on reparsing".to_string()),
+        Ok(_) => None,
+        Err(e) => Some(format!("failed to reparse from hex: {}", e)),
+    }
+}
@@ -0,0 +1,156 @@
+//! Taproot script-path spends (BIP-341): the control block that ends the
+//! witness names the leaf version, the internal key and the merkle path
+//! from the spent leaf to the output key. The witness item before it is
+//! the leaf script, disassembled and classified by what it checks.
+
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use crate::inscription::envelopes;
+use crate::script::opcodes::*;
+use crate::script::{decode_script_num, instructions, is_control_block, parse_multisig, script_to_asm, Instruction};
+use crate::types::{Script, TxInput};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TaprootSpendInfo {
+    // 0xc0 for tapscript
+    pub leaf_version: u8,
+    // parity of the output key's Y coordinate (0 even, 1 odd)
+    pub output_key_parity: u8,
+    // x-only internal key (hex)
+    pub internal_key: String,
+    // hashes between the leaf and the root, i.e. the leaf's depth in the tree
+    pub path_length: usize,
+    // the revealed leaf script
+    pub leaf_script: Script,
+    // what the leaf script checks; empty when nothing is recognized
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub leaf_kinds: Vec<TapscriptKind>,
+}
+
+// What a tapscript leaf or a P2WSH witness script checks: "single_key",
+// "hash_lock", ... or {"multisig": {"threshold": 2, "keys": 3}}
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TapscriptKind {
+    // <key> OP_CHECKSIG <key> OP_CHECKSIGADD ... <m> OP_NUMEQUAL, or an
+    // n-of-n chain of OP_CHECKSIGVERIFY ending in OP_CHECKSIG; OP_CHECKMULTISIG
+    // in witness scripts
+    Multisig { threshold: usize, keys: usize },
+    // one key and one signature check
+    SingleKey,
+    // preimage check: OP_SHA256 <hash> OP_EQUAL(VERIFY), or HASH160/RIPEMD160/HASH256
+    HashLock,
+    // OP_CHECKLOCKTIMEVERIFY or OP_CHECKSEQUENCEVERIFY
+    Timelock,
+    // an ordinals envelope
+    Inscription,
+}
+
+impl fmt::Display for TapscriptKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TapscriptKind::Multisig { threshold, keys } => write!(f, "{}-of-{} multisig", threshold, keys),
+            TapscriptKind::SingleKey => f.write_str("single key"),
+            TapscriptKind::HashLock => f.write_str("hash lock"),
+            TapscriptKind::Timelock => f.write_str("timelock"),
+            TapscriptKind::Inscription => f.write_str("inscription"),
+        }
+    }
+}
+
+impl TxInput {
+    // Control block of a script-path spend: the last witness item, annex
+    // aside, after at least the leaf script
+    pub fn taproot_spend_info(&self) -> Option<TaprootSpendInfo> {
+        if self.is_coinbase || !self.script_sig.bytes.is_empty() {
+            return None;
+        }
+        let items = match self.witness.as_deref()? {
+            [items @ .., annex] if items.len() >= 2 && annex.first() == Some(&0x50) => items,
+            items => items,
+        };
+        let [.., leaf_script, control] = items else {
+            return None;
+        };
+        if !is_control_block(control) {
+            return None;
+        }
+        Some(TaprootSpendInfo {
+            leaf_version: control[0] & 0xfe,
+            output_key_parity: control[0] & 1,
+            internal_key: hex::encode(&control[1..33]),
+            path_length: (control.len() - 33) / 32,
+            leaf_script: Script {
+                asm: script_to_asm(leaf_script),
+                size: leaf_script.len(),
+                bytes: leaf_script.clone(),
+            },
+            leaf_kinds: classify_tapscript(leaf_script),
+        })
+    }
+}
+
+// Everything the script is recognized to check, in a fixed order
+pub(crate) fn classify_tapscript(script: &[u8]) -> Vec<TapscriptKind> {
+    classify(script, true)
+}
+
+// The same for segwit v0 witness scripts: compressed keys, and
+// OP_CHECKMULTISIG in place of OP_CHECKSIGADD
+pub(crate) fn classify_witness_script(script: &[u8]) -> Vec<TapscriptKind> {
+    classify(script, false)
+}
+
+fn classify(script: &[u8], tapscript: bool) -> Vec<TapscriptKind> {
+    let Some(ins) = instructions(script) else {
+        return Vec::new();
+    };
+    let mut kinds = Vec::new();
+
+    let key_len = |key: &[u8]| if tapscript { key.len() == 32 } else { matches!(key.len(), 33 | 65) };
+    let key_check = |pair: &[Instruction], op: u8| matches!(pair, [Instruction::Push(key), Instruction::Op(o)] if key_len(key) && *o == op);
+    let checks = ins.iter().filter(|i| matches!(i, Instruction::Op(OP_CHECKSIG | OP_CHECKSIGVERIFY | OP_CHECKSIGADD))).count();
+    let adds = ins.windows(2).filter(|pair| key_check(pair, OP_CHECKSIGADD)).count();
+    if let Some((threshold, keys)) = parse_multisig(script).filter(|_| !tapscript) {
+        kinds.push(TapscriptKind::Multisig { threshold, keys: keys.len() });
+    } else if adds > 0 {
+        let threshold = ins.windows(2).find_map(|pair| {
+            let m = match pair[0] {
+                Instruction::Push(data) => decode_script_num(data)?,
+                Instruction::Op(op) if (OP_1..=OP_16).contains(&op) => (op - OP_1 + 1) as i64,
+                _ => return None,
+            };
+            matches!(pair[1], Instruction::Op(OP_NUMEQUAL | OP_NUMEQUALVERIFY | OP_GREATERTHANOREQUAL)).then_some(m as usize)
+        });
+        if let Some(threshold) = threshold.filter(|&m| m >= 1 && m <= adds + 1) {
+            kinds.push(TapscriptKind::Multisig { threshold, keys: adds + 1 });
+        }
+    } else {
+        let verifies = ins.windows(2).filter(|pair| key_check(pair, OP_CHECKSIGVERIFY)).count();
+        let last = ins.windows(2).filter(|pair| key_check(pair, OP_CHECKSIG)).count();
+        match (verifies, last, checks) {
+            (0, 1, 1) | (1, 0, 1) => kinds.push(TapscriptKind::SingleKey),
+            (n, 1, c) if n > 0 && c == n + 1 => kinds.push(TapscriptKind::Multisig { threshold: n + 1, keys: n + 1 }),
+            _ => {}
+        }
+    }
+
+    let hash_lock = ins.windows(3).enumerate().any(|(at, window)| {
+        let (Instruction::Op(op), Instruction::Push(hash), Instruction::Op(OP_EQUAL | OP_EQUALVERIFY)) = (window[0], window[1], window[2]) else {
+            return false;
+        };
+        // OP_DUP OP_HASH160 <h> OP_EQUALVERIFY checks a key hash
+        let after_dup = at > 0 && ins[at - 1] == Instruction::Op(OP_DUP);
+        !after_dup && matches!((op, hash.len()), (OP_SHA256 | OP_HASH256, 32) | (OP_HASH160 | OP_RIPEMD160, 20))
+    });
+    if hash_lock {
+        kinds.push(TapscriptKind::HashLock);
+    }
+    if ins.iter().any(|i| matches!(i, Instruction::Op(OP_CHECKLOCKTIMEVERIFY | OP_CHECKSEQUENCEVERIFY))) {
+        kinds.push(TapscriptKind::Timelock);
+    }
+    if !envelopes(script).is_empty() {
+        kinds.push(TapscriptKind::Inscription);
+    }
+    kinds
+}
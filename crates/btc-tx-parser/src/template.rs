@@ -0,0 +1,173 @@
+//! `getblocktemplate` decoding: fees, weight and sigop usage of a candidate block
+
+use serde::{Deserialize, Serialize};
+use crate::amount::Amount;
+use crate::error::ParseError;
+use crate::types::Transaction;
+
+// Consensus limits used when the template does not state its own
+pub const MAX_BLOCK_WEIGHT: u64 = 4_000_000;
+pub const MAX_BLOCK_SIGOPS_COST: u64 = 80_000;
+
+// Fields of a getblocktemplate result this crate looks at
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockTemplate {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub height: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previousblockhash: Option<String>,
+    // subsidy plus fees available to the coinbase
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coinbasevalue: Option<Amount>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weightlimit: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sigoplimit: Option<u64>,
+    pub transactions: Vec<TemplateTransaction>,
+}
+
+// Transaction entry as listed by the template
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateTransaction {
+    // raw transaction hex
+    pub data: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub txid: Option<String>,
+    // wtxid
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fee: Option<Amount>,
+    // sigop cost
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sigops: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weight: Option<u64>,
+}
+
+// Decoded template summary
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateReport {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<u64>,
+    pub transactions: usize,
+    // sum of per-transaction fees stated by the template
+    pub total_fees: Amount,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coinbase_value: Option<Amount>,
+    // weight of the listed transactions (header and coinbase excluded)
+    pub weight: u64,
+    pub weight_limit: u64,
+    // percent of the weight limit
+    pub weight_utilization: f64,
+    // sigop cost counted from the decoded transactions
+    pub sigops: u64,
+    pub sigop_limit: u64,
+    // percent of the sigop limit
+    pub sigop_utilization: f64,
+    pub entries: Vec<TemplateEntry>,
+    // decode failures and disagreements between the template and the decoded data
+    pub issues: Vec<String>,
+}
+
+// One decoded template transaction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TemplateEntry {
+    pub index: usize,
+    pub txid: String,
+    pub weight: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee: Option<Amount>,
+    // sat/vB
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feerate: Option<f64>,
+    // sigop cost
+    pub sigops: u64,
+}
+
+impl BlockTemplate {
+    // Accepts the bare result or a full JSON-RPC response (`{"result": {...}}`)
+    pub fn from_json(json: &str) -> Result<Self, ParseError> {
+        let mut value: serde_json::Value = serde_json::from_str(json)
+            .map_err(|e| ParseError::InvalidTemplate(e.to_string()))?;
+        if let Some(result) = value.get_mut("result") {
+            value = result.take();
+        }
+        serde_json::from_value(value).map_err(|e| ParseError::InvalidTemplate(e.to_string()))
+    }
+
+    pub fn analyze(&self) -> TemplateReport {
+        let mut entries = Vec::new();
+        let mut issues = Vec::new();
+
+        for (index, item) in self.transactions.iter().enumerate() {
+            let tx = match Transaction::from_hex(&item.data) {
+                Ok(tx) => tx,
+                Err(e) => {
+                    issues.push(format!("transaction #{} does not decode: {}", index, e));
+                    continue;
+                }
+            };
+
+            if let Some(txid) = item.txid.as_ref().filter(|t| **t != tx.txid) {
+                issues.push(format!("transaction #{} lists txid {} but decodes to {}", index, txid, tx.txid));
+            }
+            if let Some(hash) = item.hash.as_ref().filter(|h| **h != tx.wtxid) {
+                issues.push(format!("transaction #{} lists wtxid {} but decodes to {}", index, hash, tx.wtxid));
+            }
+            if let Some(weight) = item.weight.filter(|w| *w != tx.weight as u64) {
+                issues.push(format!("transaction #{} lists weight {} but decodes to {}", index, weight, tx.weight));
+            }
+            let sigops = tx.sigop_cost();
+            if let Some(listed) = item.sigops.filter(|s| *s != sigops) {
+                issues.push(format!("transaction #{} lists {} sigops but decodes to {}", index, listed, sigops));
+            }
+
+            entries.push(TemplateEntry {
+                index,
+                txid: tx.txid.clone(),
+                weight: tx.weight as u64,
+                fee: item.fee,
+                feerate: item.fee.map(|fee| fee.to_sat() as f64 / tx.vsize() as f64),
+                sigops,
+            });
+        }
+
+        let weight = entries.iter().map(|e| e.weight).fold(0, u64::saturating_add);
+        let sigops = entries.iter().map(|e| e.sigops).fold(0, u64::saturating_add);
+        // Amount addition saturates
+        let total_fees: Amount = entries.iter().filter_map(|e| e.fee).sum();
+        let weight_limit = self.weightlimit.unwrap_or(MAX_BLOCK_WEIGHT);
+        let sigop_limit = self.sigoplimit.unwrap_or(MAX_BLOCK_SIGOPS_COST);
+
+        if weight > weight_limit {
+            issues.push(format!("transactions weigh {} WU, over the {} WU limit", weight, weight_limit));
+        }
+        if sigops > sigop_limit {
+            issues.push(format!("transactions use {} sigops, over the {} limit", sigops, sigop_limit));
+        }
+
+        TemplateReport {
+            height: self.height,
+            transactions: self.transactions.len(),
+            total_fees,
+            coinbase_value: self.coinbasevalue,
+            weight,
+            weight_limit,
+            weight_utilization: percent(weight, weight_limit),
+            sigops,
+            sigop_limit,
+            sigop_utilization: percent(sigops, sigop_limit),
+            entries,
+            issues,
+        }
+    }
+}
+
+fn percent(used: u64, limit: u64) -> f64 {
+    if limit == 0 {
+        0.0
+    } else {
+        used as f64 * 100.0 / limit as f64
+    }
+}
@@ -1,9 +1,15 @@
 //Tests for btc-tx-parser crate
 
-use crate::{Transaction, ScriptType};
-use crate::address::{hash160, sha256d};
+use crate::{Amount, OpReturnRegistry, ProtocolMessage, LinkKind, LockTime, ParseError, RelativeLockTime, Sequence, NoteTarget, Pseudonymizer, InputSignature, SighashType, SignatureKind, TapscriptKind, WitnessItemKind, Redaction, MultisigInfo, Txid, decode_hex, encode_hex, KeyEncoding, check_pubkey, Transaction, ScriptType, TxBundle, Psbt, SigningStatus, InputStatus, ChainTip, CoinAge, LockKind, LockUnit, UnlockStatus, AnalyzerRegistry, Finding, BatchStats, BatchingAnalyzer, MIN_BATCH_OUTPUTS, ConsolidationAnalyzer, ShapeCategory, MIN_CONSOLIDATION_INPUTS, OtsProof, OtsAttestation, TimestampAnalyzer, ScriptTemplate, parse_batch_line, CarrierUsage, RoundOutput, Funder, FlowAnalyzer, ChannelOutputRole, CloseKind, SpendPath, InteractiveTxKind, HashLock, Block, BlockHeader, BlockFileReader, BlockFiles, BlockFileCheckpoint, CompactBlock, MAINNET_MAGIC, TESTNET_MAGIC, TESTNET4_MAGIC, Network, P2pMessage, WirePayload, StreamParser, TransactionRef, BlockRef, parse_messages, BlockTemplate, bits_to_target, bits_to_difficulty, merkle_root, merkle_proof, StratumJob, normalize_hex, format_hex, byte_diff, check_canonical};
+use crate::address::{derive_address, pubkey_addresses};
+use crate::hash::{hash160, sha256, sha256d, Sha256Engine};
+use crate::compact::siphash24;
 use crate::parser::Parser;
 use crate::script::detect_script_type;
+use crate::taproot::classify_tapscript;
+use crate::witness::classify_witness;
+use sha2::{Digest, Sha256};
+use std::io::{Cursor, Read};
 
 // ============================================================================
 // Transaction Parsing Tests
@@ -23,16 +29,1741 @@ fn test_parse_legacy_tx() {
     assert!(!tx.is_segwit);
 }
 
+#[test]
+fn test_strict_parsing_rejects_trailing_data() {
+    let hex = key_reuse_tx();
+    assert!(Transaction::from_hex_strict(&hex).is_ok());
+
+    let padded = format!("{}0000", hex);
+    assert!(Transaction::from_hex(&padded).is_ok());
+    assert!(matches!(Transaction::from_hex_strict(&padded), Err(ParseError::TrailingData(2))));
+}
+
+#[test]
+fn test_bogus_counts_fail_to_parse() {
+    // 2^64-1 inputs, and a segwit input whose witness claims as many items
+    assert!(Transaction::from_hex("01000000ffffffffffffffffff").is_err());
+    let witness = format!("0100000000010111{}00000000{}ffffffff01{}00ffffffffffffffffff00000000",
+        "11".repeat(31), "00", "00".repeat(8));
+    assert!(Transaction::from_hex(&witness).is_err());
+}
+
 #[test]
 fn test_parse_segwit_tx() {
     let hex = "020000000001010000000000000000000000000000000000000000000000000000000000000000ffffffff0502e8030101ffffffff0200f2052a0100000016001496ba8ba89947e739cd4e48507f9d26f47ed31c4e0000000000000000266a24aa21a9ede2f61c3f71d1defd3fa999dfa36953755c690689799962b48bebd836974e8cf90120000000000000000000000000000000000000000000000000000000000000000000000000";
 
-    let tx = Transaction::from_hex(hex);
-    assert!(tx.is_ok(), "Failed to parse segwit transaction: {:?}", tx.err());
+    let tx = Transaction::from_hex(hex);
+    assert!(tx.is_ok(), "Failed to parse segwit transaction: {:?}", tx.err());
+
+    let tx = tx.unwrap();
+    assert_eq!(tx.version, 2);
+    assert!(tx.is_segwit);
+}
+
+#[test]
+fn test_bundle_round_trip() {
+    let hex = "0100000001c997a5e56e104102fa209c6a852dd90660a20b2d9c352423edce25857fcd3704000000004847304402204e45e16932b8af514961a1d3a1a25fdf3f4f7732e9d624c6c61548ab5fb8cd410220181522ec8eca07de4860a4acdd12909d831cc56cbbac4622082221a8768d1d0901ffffffff0200ca9a3b00000000434104ae1a62fe09c5f51b13905f07f06b99a2f7159b2225f374cd378d71302fa28414e7aab37397f554a7df5f142c21c1b7303b8a0626f1baded5c72a704f7e6cd84cac00286bee0000000043410411db93e1dcdb8a016b49840f8c53bc1eb68a382e97b1482ecad7b148a6909a5cb2e0eaddfb84ccf9744464f82e160bfa9b8b64f9d4c03f999b8643f656b412a3ac00000000";
+
+    let mut tx = Transaction::from_hex(hex).unwrap();
+    tx.inputs[0].value = Some(Amount::from_sat(5_000_010_000));
+    let mut bundle = TxBundle::new(hex, &tx);
+    bundle.labels.push("first p2p payment".to_string());
+
+    let restored = TxBundle::from_json(&bundle.to_json()).unwrap();
+    assert_eq!(restored.labels, vec!["first p2p payment".to_string()]);
+
+    let tx = restored.transaction().unwrap();
+    assert_eq!(tx.inputs[0].value, Some(Amount::from_sat(5_000_010_000)));
+    assert_eq!(tx.fee_satoshis, Some(Amount::from_sat(10_000)));
+    assert_eq!(tx.outputs[1].note, None);
+
+    assert!(TxBundle::from_json(r#"{"format":"other","version":1,"tx_hex":"","prevouts":[]}"#).is_err());
+}
+
+#[test]
+fn test_bundle_notes() {
+    let hex = "0100000001c997a5e56e104102fa209c6a852dd90660a20b2d9c352423edce25857fcd3704000000004847304402204e45e16932b8af514961a1d3a1a25fdf3f4f7732e9d624c6c61548ab5fb8cd410220181522ec8eca07de4860a4acdd12909d831cc56cbbac4622082221a8768d1d0901ffffffff0200ca9a3b00000000434104ae1a62fe09c5f51b13905f07f06b99a2f7159b2225f374cd378d71302fa28414e7aab37397f554a7df5f142c21c1b7303b8a0626f1baded5c72a704f7e6cd84cac00286bee0000000043410411db93e1dcdb8a016b49840f8c53bc1eb68a382e97b1482ecad7b148a6909a5cb2e0eaddfb84ccf9744464f82e160bfa9b8b64f9d4c03f999b8643f656b412a3ac00000000";
+    let mut bundle = TxBundle::new(hex, &Transaction::from_hex(hex).unwrap());
+    bundle.set_note(NoteTarget::Output, 1, "change?");
+    bundle.set_note(NoteTarget::Input, 0, "mined by Satoshi");
+    bundle.set_note(NoteTarget::Output, 1, "change back to Satoshi");
+    assert_eq!(bundle.notes.len(), 2);
+    assert_eq!(bundle.notes[0].target, NoteTarget::Input);
+
+    let tx = TxBundle::from_json(&bundle.to_json()).unwrap().transaction().unwrap();
+    assert_eq!(tx.inputs[0].note.as_deref(), Some("mined by Satoshi"));
+    assert_eq!(tx.outputs[0].note, None);
+    assert_eq!(tx.outputs[1].note.as_deref(), Some("change back to Satoshi"));
+    assert_eq!(serde_json::to_value(&tx).unwrap()["outputs"][1]["note"], "change back to Satoshi");
+
+    bundle.set_note(NoteTarget::Output, 2, "no such output");
+    assert!(matches!(bundle.transaction(), Err(ParseError::InvalidBundle(_))));
+    assert!("inputs".parse::<NoteTarget>().is_err());
+}
+
+#[test]
+fn test_serialization_round_trip() {
+    // block 170 (first P2P payment), genesis coinbase, a segwit coinbase and a P2WPKH spend
+    let block_170 = "0100000001c997a5e56e104102fa209c6a852dd90660a20b2d9c352423edce25857fcd3704000000004847304402204e45e16932b8af514961a1d3a1a25fdf3f4f7732e9d624c6c61548ab5fb8cd410220181522ec8eca07de4860a4acdd12909d831cc56cbbac4622082221a8768d1d0901ffffffff0200ca9a3b00000000434104ae1a62fe09c5f51b13905f07f06b99a2f7159b2225f374cd378d71302fa28414e7aab37397f554a7df5f142c21c1b7303b8a0626f1baded5c72a704f7e6cd84cac00286bee0000000043410411db93e1dcdb8a016b49840f8c53bc1eb68a382e97b1482ecad7b148a6909a5cb2e0eaddfb84ccf9744464f82e160bfa9b8b64f9d4c03f999b8643f656b412a3ac00000000";
+    let segwit_coinbase = "020000000001010000000000000000000000000000000000000000000000000000000000000000ffffffff0502e8030101ffffffff0200f2052a0100000016001496ba8ba89947e739cd4e48507f9d26f47ed31c4e0000000000000000266a24aa21a9ede2f61c3f71d1defd3fa999dfa36953755c690689799962b48bebd836974e8cf90120000000000000000000000000000000000000000000000000000000000000000000000000";
+    let genesis = genesis_job().coinbase_hex();
+
+    for hex in [block_170.to_string(), segwit_coinbase.to_string(), genesis, key_reuse_tx()] {
+        let tx = Transaction::from_hex(&hex).unwrap();
+        assert_eq!(tx.to_hex(), hex);
+
+        let stripped = tx.to_bytes_without_witness();
+        let txid: String = sha256d(&stripped).iter().rev().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(txid, tx.txid);
+        assert_eq!(Transaction::from_bytes(&stripped).unwrap().txid, tx.txid);
+    }
+
+    // modify and re-encode
+    let mut tx = Transaction::from_hex(block_170).unwrap();
+    tx.locktime = LockTime::Blocks(500_000);
+    tx.outputs[0].value -= Amount::from_sat(1_000);
+    let reparsed = Transaction::from_hex(&tx.to_hex()).unwrap();
+    assert_eq!(reparsed.locktime, LockTime::Blocks(500_000));
+    assert_eq!(reparsed.outputs[0].value.to_sat(), 999_999_000);
+
+    tx.inputs[0].script_sig.bytes = vec![0x51];
+    let reparsed = Transaction::from_bytes(&tx.to_bytes()).unwrap();
+    assert_eq!(reparsed.inputs[0].script_sig.asm, "OP_1");
+    assert_eq!(reparsed.inputs[0].txid, tx.inputs[0].txid);
+}
+
+#[test]
+fn test_amount_formatting_and_parsing() {
+    let amount = Amount::from_btc_str("0.0001").unwrap();
+    assert_eq!(amount.to_sat(), 10_000);
+    assert_eq!(amount.to_string(), "0.00010000 BTC");
+    assert_eq!("21000000 BTC".parse::<Amount>().unwrap(), Amount::MAX_MONEY);
+    assert_eq!(Amount::from_btc_str(".5").unwrap().to_sat(), 50_000_000);
+
+    // 0.1 + 0.2 is exact in satoshis
+    let sum: Amount = ["0.1", "0.2"].iter().map(|s| Amount::from_btc_str(s).unwrap()).sum();
+    assert_eq!(sum.to_btc_string(), "0.30000000");
+
+    assert!(matches!(Amount::from_btc_str("0.000000001"), Err(ParseError::InvalidAmount(_))));
+    assert!(Amount::from_btc_str("-1").is_err());
+    assert!(Amount::from_btc_str(".").is_err());
+    assert_eq!(Amount::ZERO.checked_sub(Amount::from_sat(1)), None);
+
+    // sums and differences saturate, and fees over values that overflow are unknown
+    let max = Amount::from_sat(u64::MAX);
+    assert_eq!(max + Amount::from_sat(1), max);
+    assert_eq!(Amount::from_sat(1) - Amount::from_sat(2), Amount::ZERO);
+    let mut left = Amount::from_sat(5);
+    left -= Amount::from_sat(7);
+    assert_eq!(left, Amount::ZERO);
+    assert_eq!([max, max].iter().sum::<Amount>(), max);
+    let mut tx = Transaction::from_hex(&key_reuse_tx()).unwrap();
+    tx.inputs.push(tx.inputs[0].clone());
+    for input in &mut tx.inputs {
+        input.value = Some(max);
+    }
+    assert_eq!(tx.calculate_fee(), None);
+    for input in &mut tx.inputs[1..] {
+        input.value = Some(Amount::ZERO);
+    }
+    assert_eq!(tx.calculate_fee(), Some(max - tx.total_output_value()));
+
+    // serialized as plain satoshis
+    let tx = Transaction::from_hex(&key_reuse_tx()).unwrap();
+    let json = serde_json::to_value(&tx).unwrap();
+    assert_eq!(json["outputs"][0]["value"], tx.outputs[0].value.to_sat());
+}
+
+#[cfg(feature = "float-btc")]
+#[test]
+fn test_exact_btc_json() {
+    let mut tx = Transaction::from_hex(&key_reuse_tx()).unwrap();
+    tx.set_fee(Some(Amount::from_sat(12_345)));
+
+    let json = serde_json::to_value(crate::ExactBtc(&tx)).unwrap();
+    assert_eq!(json["outputs"][0]["value_btc"], "0.00100000");
+    assert_eq!(json["total_output_btc"], "0.00100000");
+    assert_eq!(json["fee_btc"], "0.00012345");
+    // only the wrapped call is affected
+    assert_eq!(serde_json::to_value(&tx).unwrap()["fee_btc"], 0.00012345);
+
+    // values nested by hand are covered by wrapping the outer one
+    let nested = serde_json::to_value(crate::ExactBtc(serde_json::json!({ "tx": &tx }))).unwrap();
+    assert_eq!(nested["tx"]["fee_btc"], "0.00012345");
+
+    // exact at MAX_MONEY, one satoshi under it, at one satoshi and past 2^53,
+    // where the float twin no longer holds every satoshi
+    for sats in [Amount::MAX_MONEY.to_sat(), Amount::MAX_MONEY.to_sat() - 1, 1, (1 << 53) + 1, u64::MAX] {
+        let mut tx = tx.clone();
+        tx.set_fee(Some(Amount::from_sat(sats)));
+        let json = serde_json::to_value(crate::ExactBtc(&tx)).unwrap();
+        assert_eq!(json["fee_btc"], Amount::from_sat(sats).to_btc_string());
+    }
+
+    // either form reads back
+    let restored: Transaction = serde_json::from_value(json).unwrap();
+    assert_eq!(restored.fee_btc, Some(0.00012345));
+    let restored: Transaction = serde_json::from_value(serde_json::to_value(&tx).unwrap()).unwrap();
+    assert_eq!(restored.outputs[0].value_btc, 0.001);
+}
+
+// ============================================================================
+// Timelock Tests
+// ============================================================================
+
+// nLockTime 800000, input #1 with a 144-block sequence lock and a CLTV(900000) witness script
+const TIMELOCK_TX: &str = "02000000000102aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa0000000000fdffffffbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb01000000009000000001a08601000000000016001489abcdefabbaabbaabbaabbaabbaabbaabbaabba000201012903a0bb0db175210279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798ac00350c00";
+
+#[test]
+fn test_locktime_interpretation() {
+    let tx = Transaction::from_hex(TIMELOCK_TX).unwrap();
+    assert_eq!(tx.locktime, LockTime::Blocks(800_000));
+    assert_eq!(tx.locktime.to_string(), "800000 (block height)");
+    let json = serde_json::to_value(tx.locktime).unwrap();
+    assert_eq!(json, serde_json::json!({"type": "blocks", "height": 800_000, "raw": 800_000}));
+
+    let time = LockTime::from_consensus(1_700_000_000);
+    assert!(matches!(time, LockTime::Time(_)));
+    assert_eq!(time.to_consensus_u32(), 1_700_000_000);
+    assert_eq!(time.to_string(), "1700000000 (2023-11-14 22:13:20 UTC)");
+    let json = serde_json::to_value(time).unwrap();
+    assert_eq!(json["time"], "2023-11-14T22:13:20Z");
+    assert_eq!(serde_json::from_value::<LockTime>(json).unwrap(), time);
+    // the bare number older JSON carried
+    assert_eq!(serde_json::from_str::<LockTime>("499999999").unwrap(), LockTime::Blocks(499_999_999));
+    assert_eq!(LockTime::ZERO.to_string(), "0 (no lock)");
+}
+
+#[test]
+fn test_sequence_semantics() {
+    let tx = Transaction::from_hex(TIMELOCK_TX).unwrap();
+    let sequence = tx.inputs[1].sequence;
+    assert_eq!(sequence.relative_locktime(), Some(RelativeLockTime::Blocks(144)));
+    assert!(sequence.is_rbf_signaling());
+    assert!(!sequence.is_final());
+
+    let time = Sequence((1 << 22) | 2);
+    assert_eq!(time.relative_locktime(), Some(RelativeLockTime::Time(2)));
+    assert_eq!(RelativeLockTime::Time(2).seconds(), Some(1024));
+    assert_eq!(serde_json::to_value(time).unwrap()["relative_locktime"], serde_json::json!({"seconds": 1024}));
+
+    // disable flag set: no BIP-68 lock, but still RBF signaling
+    assert_eq!(Sequence::ENABLE_RBF_NO_LOCKTIME.relative_locktime(), None);
+    assert!(Sequence::ENABLE_RBF_NO_LOCKTIME.is_rbf_signaling());
+    assert!(!Sequence::ENABLE_LOCKTIME_NO_RBF.is_rbf_signaling());
+    assert!(Sequence::MAX.is_final());
+    assert_eq!(serde_json::to_value(Sequence::MAX).unwrap(),
+        serde_json::json!({"raw": 0xffffffffu32, "rbf": false, "final": true, "relative_locktime": "disabled"}));
+    // a zero lock is still a lock, just one that is always met
+    assert_eq!(Sequence(0).relative_locktime(), Some(RelativeLockTime::Blocks(0)));
+    assert_eq!(RelativeLockTime::Blocks(144).to_string(), "144 blocks");
+    assert_eq!(RelativeLockTime::Time(2).to_string(), "1024 seconds (2 × 512 s)");
+
+    // the bare number older JSON carried, and the object round trip
+    assert_eq!(serde_json::from_str::<Sequence>("144").unwrap(), Sequence(144));
+    assert_eq!(serde_json::from_value::<Sequence>(serde_json::to_value(time).unwrap()).unwrap(), time);
+}
+
+#[test]
+fn test_rbf_signaling() {
+    let mut tx = Transaction::from_hex(TIMELOCK_TX).unwrap();
+    assert!(tx.is_rbf());
+
+    // 0xfffffffe still enforces nLockTime but doesn't opt in
+    for input in &mut tx.inputs {
+        input.sequence = Sequence::ENABLE_LOCKTIME_NO_RBF;
+    }
+    assert!(!tx.is_rbf());
+    tx.inputs[1].sequence = Sequence::ENABLE_RBF_NO_LOCKTIME;
+    assert!(tx.is_rbf());
+}
+
+#[test]
+fn test_unlock_schedule() {
+    let tx = Transaction::from_hex(TIMELOCK_TX).unwrap();
+    let locks = tx.timelocks();
+    assert_eq!(locks.len(), 3);
+
+    let tip = ChainTip { height: Some(850_000), time: 1_720_000_000 };
+    let schedule = tx.unlock_schedule(&tip);
+    assert_eq!(schedule[0].timelock.source, "nLockTime");
+    assert_eq!(schedule[0].status, UnlockStatus::Unlocked);
+    assert_eq!(schedule[1].timelock.source, "witness script OP_CHECKLOCKTIMEVERIFY");
+    assert_eq!(schedule[1].blocks_remaining, Some(50_000));
+    assert_eq!(schedule[2].timelock.kind, LockKind::Relative);
+    assert_eq!(schedule[2].timelock.value, 144);
+    assert_eq!(schedule[2].status, UnlockStatus::AfterConfirmation);
+}
+
+// ============================================================================
+// Analyzer Tests
+// ============================================================================
+
+// Bare CTV output, spending a CSV-staged vault witness script
+const COVENANT_TX: &str = "02000000000101aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa0000000000fdffffff01a0860100000000002220ccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccb30201014d63210279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f8179867029000b275210279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f8179868ac00000000";
+
+#[test]
+fn test_covenant_analyzer() {
+    let tx = Transaction::from_hex(COVENANT_TX).unwrap();
+    let findings = tx.analyze();
+
+    let ctv = findings.iter().find(|f| f.kind == "ctv_template").unwrap();
+    assert_eq!(ctv.output, Some(0));
+
+    let vault = findings.iter().find(|f| f.kind == "csv_staged_vault").unwrap();
+    assert_eq!(vault.input, Some(0));
+    assert!(vault.message.contains("nSequence 144"));
+
+    assert!(AnalyzerRegistry::new().run(&tx).is_empty());
+}
+
+#[test]
+fn test_heuristic_confidence_and_evidence() {
+    let tx = Transaction::from_hex(COVENANT_TX).unwrap();
+    let findings = tx.analyze();
+    // decoded facts carry no confidence; heuristics explain themselves
+    let ctv = findings.iter().find(|f| f.kind == "ctv_template").unwrap();
+    assert_eq!((ctv.confidence, ctv.evidence.len()), (None, 0));
+    let vault = findings.iter().find(|f| f.kind == "csv_staged_vault").unwrap();
+    assert_eq!(vault.confidence, Some(0.5));
+    assert!(vault.evidence.iter().any(|e| e.contains("nSequence 144")));
+    for finding in findings.iter().filter(|f| f.confidence.is_some()) {
+        assert!(!finding.evidence.is_empty(), "{} has no evidence", finding.kind);
+    }
+
+    let strict = AnalyzerRegistry::with_defaults().with_min_confidence(0.8).run(&tx);
+    assert!(strict.iter().any(|f| f.kind == "ctv_template"));
+    assert!(!strict.iter().any(|f| f.kind == "csv_staged_vault"));
+
+    let json = serde_json::to_value(ctv).unwrap();
+    assert!(json.get("confidence").is_none() && json.get("evidence").is_none());
+    let restored: Finding = serde_json::from_value(json).unwrap();
+    assert_eq!(&restored, ctv);
+}
+
+// P2WPKH spends by KEY_1 whose witness signatures are given as hex
+fn signed_spend(sigs: &[String]) -> Transaction {
+    let inputs: String = (0..sigs.len()).map(|i| format!("{:02x}{}0000000000ffffffff", i + 1, "aa".repeat(31))).collect();
+    let witnesses: String = sigs.iter().map(|sig| format!("02{:02x}{}21{}", sig.len() / 2, sig, KEY_1)).collect();
+    Transaction::from_hex(&format!(
+        "0200000000010{}{}01a086010000000000160014{}{}00000000", sigs.len(), inputs, "89".repeat(20), witnesses
+    )).unwrap()
+}
+
+#[test]
+fn test_signature_fingerprints() {
+    let low_r = format!("30440220{}0220{}01", "11".repeat(32), "22".repeat(32));
+    let high_r = format!("3045022100{}0220{}81", "91".repeat(32), "22".repeat(32));
+    let high_s = format!("30450220{}022100{}01", "11".repeat(32), "ff".repeat(32));
+
+    let tx = signed_spend(&[low_r.clone(), low_r.clone()]);
+    let summary = tx.signature_summary();
+    assert_eq!((summary.count, summary.low_r, summary.high_s), (2, 2, 0));
+    assert_eq!(summary.lengths, vec![71, 71]);
+    let grinding = tx.analyze().into_iter().find(|f| f.kind == "low_r_grinding").unwrap();
+    assert_eq!(grinding.confidence, Some(0.75));
+
+    let tx = signed_spend(&[low_r, high_r, high_s]);
+    let signatures = tx.ecdsa_signatures();
+    let (input, signature) = signatures[1];
+    assert_eq!((input, signature.location.as_str()), (1, "witness[0]"));
+    assert_eq!((signature.length, signature.low_r, signature.sighash), (72, Some(false), SighashType(0x81)));
+    assert_eq!(signatures[2].1.low_s, Some(false));
+    // the summary and fingerprints are read from the stored signatures
+    let mut stripped = tx.clone();
+    stripped.inputs[1].signatures.clear();
+    assert_eq!(stripped.signature_summary().count, 2);
+    assert!(!stripped.analyze().iter().any(|f| f.kind == "high_r"));
+    let findings = tx.analyze();
+    assert!(!findings.iter().any(|f| f.kind == "low_r_grinding"));
+    assert_eq!(findings.iter().find(|f| f.kind == "high_r").unwrap().input, Some(1));
+    assert_eq!(findings.iter().find(|f| f.kind == "high_s").unwrap().input, Some(2));
+
+    // 71 bytes of 0x30 aren't DER
+    assert!(Transaction::from_hex(&key_reuse_tx()).unwrap().ecdsa_signatures().is_empty());
+}
+
+#[test]
+fn test_input_sighash_types() {
+    let signature = |sighash: &str| format!("30440220{}0220{}{}", "11".repeat(32), "22".repeat(32), sighash);
+    let tx = signed_spend(&[signature("01"), signature("82"), signature("83"), signature("04")]);
+
+    let types: Vec<String> = tx.inputs.iter().map(|i| i.signatures[0].sighash.to_string()).collect();
+    assert_eq!(types, ["ALL", "NONE|ANYONECANPAY", "SINGLE|ANYONECANPAY", "0x04"]);
+    assert_eq!(tx.inputs[0].signatures[0].location, "witness[0]");
+    assert!(tx.inputs[1].signatures[0].sighash.anyone_can_pay());
+
+    let json = serde_json::to_value(&tx.inputs[2]).unwrap();
+    assert_eq!(json["signatures"][0]["location"], "witness[0]");
+    assert_eq!(json["signatures"][0]["sighash"], "SINGLE|ANYONECANPAY");
+    for text in types {
+        assert_eq!(text.parse::<SighashType>().unwrap().to_string(), text);
+    }
+    assert_eq!("ALL".parse::<SighashType>(), Ok(SighashType::ALL));
+
+    // legacy: the signature is the first scriptSig push
+    let tx = Transaction::from_hex("0100000001c997a5e56e104102fa209c6a852dd90660a20b2d9c352423edce25857fcd3704000000004847304402204e45e16932b8af514961a1d3a1a25fdf3f4f7732e9d624c6c61548ab5fb8cd410220181522ec8eca07de4860a4acdd12909d831cc56cbbac4622082221a8768d1d0901ffffffff0200ca9a3b00000000434104ae1a62fe09c5f51b13905f07f06b99a2f7159b2225f374cd378d71302fa28414e7aab37397f554a7df5f142c21c1b7303b8a0626f1baded5c72a704f7e6cd84cac00286bee0000000043410411db93e1dcdb8a016b49840f8c53bc1eb68a382e97b1482ecad7b148a6909a5cb2e0eaddfb84ccf9744464f82e160bfa9b8b64f9d4c03f999b8643f656b412a3ac00000000").unwrap();
+    let [signature] = tx.inputs[0].signatures.as_slice() else { panic!("one signature expected") };
+    assert_eq!((signature.location.as_str(), signature.sighash), ("script_sig.push[0]", SighashType::ALL));
+}
+
+#[test]
+fn test_schnorr_key_path_signatures() {
+    let rs = format!("{}{}", "11".repeat(32), "22".repeat(32));
+    let witnesses = [format!("0140{}", rs), format!("0141{}83", rs), format!("0240{}0350abcd", rs)];
+    let inputs: String = (0..3).map(|i| format!("{:02x}{}0000000000ffffffff", i + 1, "aa".repeat(31))).collect();
+    let tx = Transaction::from_hex(&format!(
+        "02000000000103{}01a0860100000000002251200000000000000000000000000000000000000000000000000000000000000000{}00000000",
+        inputs, witnesses.concat()
+    )).unwrap();
+
+    let sighashes: Vec<String> = tx.inputs.iter().map(|i| i.signatures[0].sighash.to_string()).collect();
+    assert_eq!(sighashes, ["DEFAULT", "SINGLE|ANYONECANPAY", "DEFAULT"]);
+    let signature = &tx.inputs[0].signatures[0];
+    assert_eq!((signature.kind, signature.location.as_str()), (SignatureKind::Schnorr, "witness[0]"));
+    assert_eq!((signature.r.clone(), signature.s.clone()), ("11".repeat(32), "22".repeat(32)));
+    assert_eq!(signature.low_s, None);
+    assert_eq!("DEFAULT".parse::<SighashType>(), Ok(SighashType::DEFAULT));
+
+    let json = serde_json::to_value(signature).unwrap();
+    assert_eq!(json["kind"], "schnorr");
+    assert!(json.get("low_s").is_none());
+    // not ECDSA, so not part of the DER fingerprints
+    assert!(tx.ecdsa_signatures().is_empty());
+}
+
+#[test]
+fn test_taproot_control_block() {
+    let key = "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+    let script = format!("20{}ac", key);
+    let control = format!("c1{}{}", key, "ab".repeat(64));
+    let witnesses = [
+        format!("0340{}22{}61{}", "11".repeat(64), script, control),
+        format!("0440{}22{}61{}0250aa", "11".repeat(64), script, control),
+        format!("0140{}", "11".repeat(64)),
+    ];
+    let inputs: String = (0..3).map(|i| format!("{:02x}{}0000000000ffffffff", i + 1, "aa".repeat(31))).collect();
+    let tx = Transaction::from_hex(&format!(
+        "02000000000103{}01a0860100000000002251200000000000000000000000000000000000000000000000000000000000000000{}00000000",
+        inputs, witnesses.concat()
+    )).unwrap();
+
+    let info = tx.inputs[0].taproot.as_ref().unwrap();
+    assert_eq!((info.leaf_version, info.output_key_parity, info.path_length), (0xc0, 1, 2));
+    assert_eq!(info.internal_key, key);
+    assert_eq!(info.leaf_script.asm, format!("{} OP_CHECKSIG", key));
+    assert_eq!(info.leaf_kinds, [TapscriptKind::SingleKey]);
+    // the annex doesn't hide the control block; a key-path spend has none
+    assert_eq!(tx.inputs[1].taproot.as_ref(), Some(info));
+    assert_eq!(tx.inputs[2].taproot, None);
+
+    let redacted = tx.redacted(Redaction::Addresses);
+    assert_eq!(redacted.inputs[0].taproot.as_ref().unwrap().internal_key, "0".repeat(64));
+    assert_eq!(redacted.inputs[0].taproot.as_ref().unwrap().leaf_script.bytes, hex::decode(format!("20{}ac", "00".repeat(32))).unwrap());
+}
+
+#[test]
+fn test_witness_script_info() {
+    use sha2::{Digest, Sha256};
+
+    // <1> <locktime> OP_CLTV OP_DROP <key> OP_CHECKSIG, spent natively
+    let tx = Transaction::from_hex(TIMELOCK_TX).unwrap();
+    let info = tx.inputs[1].witness_script.as_ref().unwrap();
+    assert!(!info.nested);
+    assert_eq!(info.script_type, ScriptType::NonStandard);
+    assert_eq!(info.kinds, [TapscriptKind::SingleKey, TapscriptKind::Timelock]);
+    assert_eq!(tx.inputs[0].witness_script, None);
+
+    // nested 2-of-2 multisig: the pushed program must hash the script
+    let sig = hex::decode(format!("30440220{}0220{}01", "11".repeat(32), "22".repeat(32))).unwrap();
+    let multisig = hex::decode(format!("5221{}21{}52ae", KEY_1, KEY_2)).unwrap();
+    let mut input = tx.inputs[1].clone();
+    input.witness = Some(vec![Vec::new(), sig.clone(), sig.clone(), multisig.clone()]);
+    input.script_sig.bytes = [&[0x22, 0x00, 0x20][..], &Sha256::digest(&multisig)].concat();
+    let info = input.witness_script_info().unwrap();
+    assert!(info.nested);
+    assert_eq!(info.script_type, ScriptType::Multisig);
+    assert_eq!(info.kinds, [TapscriptKind::Multisig { threshold: 2, keys: 2 }]);
+    input.script_sig.bytes[3] ^= 1;
+    assert_eq!(input.witness_script_info(), None);
+
+    // a P2WPKH spend reveals a key, not a script
+    input.script_sig.bytes.clear();
+    input.witness = Some(vec![sig, hex::decode(KEY_1).unwrap()]);
+    assert_eq!(input.witness_script_info(), None);
+
+    let redacted = tx.redacted(Redaction::Addresses);
+    assert_eq!(redacted.inputs[1].witness_script.as_ref().unwrap().script.bytes[..4], [0x03, 0, 0, 0]);
+}
+
+#[test]
+fn test_script_sig_parts() {
+    let sig = format!("30440220{}0220{}01", "11".repeat(32), "22".repeat(32));
+    let multisig = format!("5121{}51ae", KEY_1);
+    let script_sigs = [
+        // <sig> <pubkey>
+        format!("47{}21{}", sig, KEY_1),
+        // OP_0 <sig> <1-of-1 multisig redeem script>
+        format!("0047{}{:02x}{}", sig, multisig.len() / 2, multisig),
+    ];
+    let inputs: String = script_sigs.iter()
+        .enumerate()
+        .map(|(i, script)| format!("{:02x}{}00000000{:02x}{}ffffffff", i + 1, "aa".repeat(31), script.len() / 2, script))
+        .collect();
+    let tx = Transaction::from_hex(&format!("0100000002{}01a086010000000000160014{}00000000", inputs, "89".repeat(20))).unwrap();
+
+    let p2pkh = tx.inputs[0].script_sig_parts.as_ref().unwrap();
+    assert_eq!(p2pkh.spends, ScriptType::P2PKH);
+    assert_eq!((p2pkh.signatures.as_slice(), p2pkh.pubkeys.as_slice()), ([sig.clone()].as_slice(), [KEY_1.to_string()].as_slice()));
+    assert_eq!(p2pkh.redeem_script, None);
+
+    let p2sh = tx.inputs[1].script_sig_parts.as_ref().unwrap();
+    assert_eq!(p2sh.spends, ScriptType::P2SH);
+    assert_eq!(p2sh.signatures, [sig.as_str()]);
+    assert_eq!(p2sh.other, [""]);
+    assert!(p2sh.pubkeys.is_empty());
+    assert_eq!(p2sh.redeem_script.as_ref().unwrap().bytes, hex::decode(&multisig).unwrap());
+    assert_eq!(p2sh.redeem_script_type, Some(ScriptType::Multisig));
+    assert_eq!(p2sh.describe(), "P2SH wrapping 1-of-1 multisig");
+    assert_eq!(p2pkh.describe(), "P2PKH");
+
+    // nested segwit: the redeem script is a witness program, and for P2WSH
+    // the witness script says what it wraps
+    let mut nested = tx.inputs[1].clone();
+    let program = format!("0020{}", "33".repeat(32));
+    nested.script_sig.bytes = hex::decode(format!("22{}", program)).unwrap();
+    nested.witness = Some(vec![Vec::new(), hex::decode(&sig).unwrap(), hex::decode(&multisig).unwrap()]);
+    assert_eq!(nested.script_sig_parts().unwrap().wraps.as_deref(), Some("P2WSH wrapping 1-of-1 multisig"));
+    assert_eq!(nested.script_sig_parts().unwrap().describe(), "P2SH-P2WSH (nested segwit) wrapping 1-of-1 multisig");
+    nested.script_sig.bytes = hex::decode(format!("160014{}", "33".repeat(20))).unwrap();
+    assert_eq!(nested.nested_segwit(), Some(ScriptType::P2WPKH));
+    assert_eq!(nested.script_sig_parts().unwrap().wraps.as_deref(), Some("P2WPKH"));
+    assert_eq!(nested.script_sig_parts().unwrap().describe(), "P2SH-P2WPKH (nested segwit)");
+    // without a witness, the same push is a plain P2SH spend of a program
+    nested.witness = None;
+    assert_eq!(nested.nested_segwit(), None);
+    assert_eq!(tx.inputs[1].nested_segwit(), None);
+
+    // segwit spends have no scriptSig to split
+    assert_eq!(Transaction::from_hex(TIMELOCK_TX).unwrap().inputs[0].script_sig_parts, None);
+    let redacted = tx.redacted(Redaction::Addresses);
+    assert_eq!(redacted.inputs[0].script_sig_parts.as_ref().unwrap().pubkeys, ["0".repeat(66)]);
+}
+
+#[test]
+fn test_spent_address() {
+    let sig = format!("30440220{}0220{}01", "11".repeat(32), "22".repeat(32));
+    let redeem = format!("5121{}51ae", KEY_1);
+    let script_sigs = [
+        format!("47{}21{}", sig, KEY_1),
+        format!("0047{}{:02x}{}", sig, redeem.len() / 2, redeem),
+        String::new(),
+    ];
+    let inputs: String = script_sigs.iter()
+        .enumerate()
+        .map(|(i, script)| format!("{:02x}{}00000000{:02x}{}ffffffff", i + 1, "aa".repeat(31), script.len() / 2, script))
+        .collect();
+    // the output pays the P2WPKH address the last input spends from
+    let witness = format!("0247{}21{}", sig, KEY_1);
+    let tx = Transaction::from_hex(&format!("01000000000103{}01a086010000000000160014{}0000{}00000000",
+        inputs, "751e76e8199196d454941c45d1b3a323f1433bd6", witness)).unwrap();
+
+    let addresses: Vec<(&str, &str)> = tx.inputs.iter()
+        .map(|i| i.address.as_ref().map(|a| (a.address_type.as_str(), a.mainnet.as_str())).unwrap())
+        .collect();
+    assert_eq!(addresses[0], ("P2PKH", "1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH"));
+    let p2sh = derive_address(&[&[0xa9, 0x14][..], &hash160(&hex::decode(&redeem).unwrap()), &[0x87]].concat(), &ScriptType::P2SH).unwrap();
+    assert_eq!(addresses[1], ("P2SH", p2sh.mainnet.as_str()));
+    assert_eq!(addresses[2], ("P2WPKH", "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"));
+
+    // a P2WSH spend hashes its witness script; coinbases spend nothing
+    let timelock = Transaction::from_hex(TIMELOCK_TX).unwrap();
+    let p2wsh = timelock.inputs[1].address.as_ref().unwrap();
+    assert_eq!((p2wsh.address_type.as_str(), p2wsh.mainnet.len()), ("P2WSH", 62));
+    assert_eq!(Transaction::from_hex(&genesis_job().coinbase_hex()).unwrap().inputs[0].address.as_ref().map(|a| a.mainnet.clone()), None);
+
+    // pseudonyms keep the input address equal to that of the output paying it
+    let pseudonymous = Pseudonymizer::new(b"key").pseudonymize(&tx);
+    assert_eq!(pseudonymous.inputs[2].address.as_ref().unwrap().mainnet, pseudonymous.outputs[0].address.as_ref().unwrap().mainnet);
+    assert_ne!(pseudonymous.inputs[2].address.as_ref().unwrap().mainnet, addresses[2].1);
+}
+
+#[test]
+fn test_multisig_info() {
+    // bare 2-of-3 output
+    let script = hex::decode(format!("5221{}21{}21{}53ae", KEY_1, KEY_2, KEY_3)).unwrap();
+    let info = MultisigInfo::from_script(&script).unwrap();
+    assert_eq!((info.required, info.total), (2, 3));
+    assert_eq!(info.pubkeys, [KEY_1, KEY_2, KEY_3]);
+    assert_eq!(info.to_string(), "2-of-3");
+    // n must match the key count, and m can't exceed it
+    assert_eq!(MultisigInfo::from_script(&hex::decode(format!("5221{}52ae", KEY_1)).unwrap()), None);
+    assert_eq!(MultisigInfo::from_script(&hex::decode(format!("5221{}51ae", KEY_1)).unwrap()), None);
+
+    let tx = Transaction::from_hex(&format!("0100000001{}000000006a{}ffffffff01a086010000000000{:02x}{}00000000",
+        "aa".repeat(32), "00".repeat(106), script.len(), hex::encode(&script))).unwrap();
+    assert_eq!(tx.outputs[0].multisig.as_ref(), Some(&info));
+    let scripts = tx.multisig_scripts();
+    assert_eq!(scripts.len(), 1);
+    assert_eq!((scripts[0].location.as_str(), scripts[0].label.as_str()), ("outputs[0]", "2-of-3"));
+
+    // a P2SH redeem script and a P2WSH witness script
+    let redeem = format!("5121{}51ae", KEY_1);
+    let mut input = tx.inputs[0].clone();
+    input.script_sig.bytes = hex::decode(format!("00{:02x}{}", redeem.len() / 2, redeem)).unwrap();
+    input.script_sig_parts = input.script_sig_parts();
+    assert_eq!(input.multisig().map(|m| m.to_string()).as_deref(), Some("1-of-1"));
+    let timelock = Transaction::from_hex(TIMELOCK_TX).unwrap();
+    assert_eq!(timelock.inputs[1].multisig(), None);
+    let mut spend = timelock.inputs[1].clone();
+    spend.witness = Some(vec![Vec::new(), script.clone()]);
+    spend.witness_script = spend.witness_script_info();
+    assert_eq!(spend.multisig(), Some(info));
+
+    // redaction masks the keys but keeps the shape
+    let redacted = tx.redacted(Redaction::Addresses);
+    let masked = redacted.outputs[0].multisig.as_ref().unwrap();
+    assert_eq!(masked.to_string(), "2-of-3");
+    assert_eq!(masked.pubkeys[0], "0".repeat(66));
+}
+
+#[test]
+fn test_pubkeys() {
+    // x = p is off the curve; x = 1 lifts to a valid point
+    let fake = format!("02{}", "ff".repeat(32));
+    let uncompressed = "0479be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8";
+    let sig = format!("30440220{}0220{}01", "11".repeat(32), "22".repeat(32));
+    let outputs = [
+        format!("21{}ac", KEY_1),
+        format!("5121{}21{}52ae", KEY_2, fake),
+        format!("5120{}", "00".repeat(31) + "01"),
+        format!("6a21{}", fake),
+    ];
+    let outputs: String = outputs.iter()
+        .map(|script| format!("a086010000000000{:02x}{}", script.len() / 2, script))
+        .collect();
+    let script_sig = format!("47{}41{}", sig, uncompressed);
+    let tx = Transaction::from_hex(&format!("0100000001{}00000000{:02x}{}ffffffff04{}00000000",
+        "aa".repeat(32), script_sig.len() / 2, script_sig, outputs)).unwrap();
+
+    let keys = tx.pubkeys();
+    let summary: Vec<(String, KeyEncoding, bool)> = keys.iter().map(|k| (k.field(), k.encoding, k.is_valid())).collect();
+    assert_eq!(summary, [
+        ("inputs[0].script_sig.push[1]".to_string(), KeyEncoding::Uncompressed, true),
+        ("outputs[0].script_pubkey".to_string(), KeyEncoding::Compressed, true),
+        ("outputs[1].script_pubkey".to_string(), KeyEncoding::Compressed, true),
+        ("outputs[1].script_pubkey".to_string(), KeyEncoding::Compressed, false),
+        ("outputs[2].output_key".to_string(), KeyEncoding::XOnly, true),
+    ]);
+    assert_eq!(keys[3].issue.as_deref(), Some("not on the curve"));
+
+    let findings: Vec<Finding> = tx.analyze().into_iter().filter(|f| f.kind == "invalid_pubkey").collect();
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].output, Some(1));
+
+    assert_eq!(check_pubkey(&hex::decode(format!("05{}", &KEY_1[2..])).unwrap()).0, KeyEncoding::Unknown);
+    let hybrid = hex::decode(format!("06{}", &uncompressed[2..])).unwrap();
+    assert_eq!(check_pubkey(&hybrid), (KeyEncoding::Hybrid, Some("hybrid encoding is non-standard".to_string())));
+}
+
+#[test]
+fn test_witness_item_kinds() {
+    use WitnessItemKind::*;
+    let sig = format!("3006020101020101{}", "01");
+    let pubkey = format!("02{}", "11".repeat(32));
+    let control = format!("c0{}", "22".repeat(32));
+    let kinds = |stack: &[&str]| {
+        let stack: Vec<Vec<u8>> = stack.iter().map(|item| hex::decode(item).unwrap()).collect();
+        classify_witness(&stack)
+    };
+
+    assert_eq!(kinds(&[&sig, &pubkey]), [Signature, Pubkey]);
+    assert_eq!(kinds(&["", &sig, &sig, "5221ae"]), [Empty, Signature, Signature, WitnessScript]);
+    assert_eq!(kinds(&[&"33".repeat(64), "50aa"]), [Signature, Annex]);
+    assert_eq!(kinds(&[&"33".repeat(64), "aa", "51", &control]), [Signature, Data, Tapscript, ControlBlock]);
+
+    // JSON carries the kinds, and lists of plain hex strings still load
+    let tx = Transaction::from_hex(TIMELOCK_TX).unwrap();
+    let json = serde_json::to_value(&tx).unwrap();
+    assert_eq!(json["inputs"][1]["witness"][1]["kind"], "witness_script");
+    assert_eq!(tx.inputs[1].witness_items()[1].kind, WitnessScript);
+    let mut old = json.clone();
+    old["inputs"][1]["witness"] = serde_json::json!(["01", hex::encode(&tx.inputs[1].witness.as_ref().unwrap()[1])]);
+    let reloaded: Transaction = serde_json::from_value(old).unwrap();
+    assert_eq!(reloaded.inputs[1].witness, tx.inputs[1].witness);
+}
+
+#[test]
+fn test_tapscript_leaf_kinds() {
+    let classify = |script: String| classify_tapscript(&hex::decode(script).unwrap());
+    let key = |n: u8| format!("20{}", hex::encode([n; 32]));
+
+    // <k1> CHECKSIG <k2> CHECKSIGADD <k3> CHECKSIGADD 2 NUMEQUAL
+    let multisig = format!("{}ac{}ba{}ba529c", key(1), key(2), key(3));
+    assert_eq!(classify(multisig), [TapscriptKind::Multisig { threshold: 2, keys: 3 }]);
+    // <k1> CHECKSIGVERIFY <k2> CHECKSIG
+    assert_eq!(classify(format!("{}ad{}ac", key(1), key(2))), [TapscriptKind::Multisig { threshold: 2, keys: 2 }]);
+    // OP_SHA256 <h> OP_EQUALVERIFY <k> CHECKSIG
+    let hash_lock = format!("a820{}88{}ac", "55".repeat(32), key(1));
+    assert_eq!(classify(hash_lock), [TapscriptKind::SingleKey, TapscriptKind::HashLock]);
+    // 144 CSV DROP <k> CHECKSIG
+    let timelock = format!("029000b275{}ac", key(1));
+    assert_eq!(classify(timelock), [TapscriptKind::SingleKey, TapscriptKind::Timelock]);
+    // <k> CHECKSIG OP_0 OP_IF "ord" OP_1 "text/plain" OP_0 "hi" OP_ENDIF
+    let inscription = format!("{}ac0063036f7264510a746578742f706c61696e00026869 68", key(1)).replace(' ', "");
+    assert_eq!(classify(inscription), [TapscriptKind::SingleKey, TapscriptKind::Inscription]);
+    assert!(classify("51".to_string()).is_empty());
+    assert_eq!(TapscriptKind::Multisig { threshold: 2, keys: 3 }.to_string(), "2-of-3 multisig");
+}
+
+#[test]
+fn test_der_signature_canonicality() {
+    let r = "11".repeat(32);
+    let high_s = format!("7f{}", "ff".repeat(31));
+    let tx = signed_spend(&[
+        format!("30440220{}0220{}01", r, "22".repeat(32)),
+        format!("30440220{}0220{}01", r, high_s),
+        format!("30440220{}0220{}01", r, "82".repeat(32)),
+        format!("30450220{}022100{}01", r, "22".repeat(32)),
+        format!("30460220{}0220{}01", r, "22".repeat(32)),
+    ]);
+    let signatures: Vec<&InputSignature> = tx.inputs.iter().map(|i| &i.signatures[0]).collect();
+
+    assert!(signatures[0].is_canonical() && signatures[0].low_s == Some(true));
+    assert_eq!((signatures[0].r.as_str(), signatures[0].s.as_str()), (r.as_str(), "22".repeat(32).as_str()));
+    assert!(signatures[1].is_canonical() && signatures[1].low_s == Some(false));
+    assert_eq!(signatures[2].der_issues, ["S is negative"]);
+    assert_eq!(signatures[3].der_issues, ["S has excess zero padding"]);
+    assert_eq!(signatures[3].s, "22".repeat(32));
+    assert_eq!(signatures[4].der_issues, ["sequence length is 70 but 68 bytes follow"]);
+
+    // the strict view used for fingerprints skips what BIP66 rejects
+    assert_eq!(tx.ecdsa_signatures().len(), 2);
+    let flagged: Vec<usize> = tx.analyze().iter().filter(|f| f.kind == "non_canonical_der").filter_map(|f| f.input).collect();
+    assert_eq!(flagged, [2, 3, 4]);
+
+    assert!(InputSignature::from_der("witness[0]".to_string(), &hex::decode("3006020101020101").unwrap()).is_none());
+    let signature = InputSignature::from_der("witness[0]".to_string(), &hex::decode("3007020101020101ff01").unwrap()).unwrap();
+    assert_eq!((signature.der_issues.as_slice(), signature.length), (["1 byte(s) after S".to_string()].as_slice(), 10));
+}
+
+// ============================================================================
+// Batch Stats Tests
+// ============================================================================
+
+// Two P2WPKH inputs signed by the same key
+fn key_reuse_tx() -> String {
+    let input = |prev: &str| format!("{}0000000000ffffffff", prev.repeat(32));
+    let witness = format!("0247{}21{}", "30".repeat(71), KEY_1);
+    format!(
+        "02000000000102{}{}01a086010000000000160014{}{}{}00000000",
+        input("aa"), input("bb"), "89".repeat(20), witness, witness
+    )
+}
+
+#[test]
+fn test_batch_reuse_report() {
+    let mut stats = BatchStats::new();
+    stats.add(&Transaction::from_hex(&key_reuse_tx()).unwrap());
+    stats.add(&Transaction::from_hex(TIMELOCK_TX).unwrap());
+    stats.add(&Transaction::from_hex(TIMELOCK_TX).unwrap());
+
+    let report = stats.report();
+    assert_eq!(report.transactions, 3);
+    assert_eq!(report.script_types[&ScriptType::P2WPKH], 3);
+
+    assert_eq!(report.reused_pubkeys.len(), 1);
+    assert_eq!(report.reused_pubkeys[0].hex, KEY_1);
+    let inputs: Vec<usize> = report.reused_pubkeys[0].occurrences.iter().map(|o| o.input).collect();
+    assert_eq!(inputs, vec![0, 1]);
+
+    assert_eq!(report.reused_scripts.len(), 1);
+    assert_eq!(report.reused_scripts[0].occurrences.len(), 2);
+    assert!(report.feerates.is_none());
+}
+
+#[test]
+fn test_batch_nonce_reuse() {
+    let signature = |r: &str, s: &str| format!("30440220{}0220{}01", r.repeat(32), s.repeat(32));
+    let first = signed_spend(&[signature("11", "22")]);
+    let second = signed_spend(&[signature("44", "55"), signature("11", "33")]);
+
+    // the same transaction twice repeats a signature, not a nonce
+    let mut stats = BatchStats::new();
+    stats.add(&first);
+    stats.add(&first);
+    assert!(stats.report().nonce_reuse.is_empty());
+
+    stats.add(&second);
+    let report = stats.report();
+    assert_eq!(report.nonce_reuse.len(), 1);
+    let reuse = &report.nonce_reuse[0];
+    assert_eq!(reuse.r, "11".repeat(32));
+    let locations: Vec<(&str, usize)> = reuse.occurrences.iter().map(|o| (o.txid.as_str(), o.input)).collect();
+    assert_eq!(locations, vec![(first.txid.as_str(), 0), (second.txid.as_str(), 1)]);
+    assert_eq!(reuse.pubkeys, vec![KEY_1.to_string()]);
+    assert!(reuse.same_key);
+}
+
+#[test]
+fn test_batch_feerate_percentiles() {
+    let mut stats = BatchStats::new();
+    let mut tx = Transaction::from_hex(&key_reuse_tx()).unwrap();
+    let vsize = tx.vsize() as u64;
+    for rate in 1..=10u64 {
+        tx.inputs[0].value = Some(Amount::from_sat(50_000));
+        tx.inputs[1].value = Some(Amount::from_sat(50_000 + rate * vsize));
+        stats.add(&tx);
+    }
+
+    let feerates = stats.report().feerates.unwrap();
+    assert_eq!(feerates.samples, 10);
+    assert_eq!(feerates.p10, 1.0);
+    assert_eq!(feerates.p50, 5.0);
+    assert_eq!(feerates.p90, 9.0);
+}
+
+#[test]
+fn test_parse_batch_line() {
+    let tx = parse_batch_line(&format!("{} 60000,50000", key_reuse_tx())).unwrap();
+    assert_eq!(tx.fee_satoshis, Some(Amount::from_sat(10_000)));
+    assert_eq!(parse_batch_line(&key_reuse_tx()).unwrap().fee_satoshis, None);
+    assert_eq!(parse_batch_line(&format!("{} 60000", key_reuse_tx())).unwrap_err(), "1 input values given for 2 inputs");
+    assert!(parse_batch_line(&format!("{} 6e4,1", key_reuse_tx())).unwrap_err().starts_with("Invalid input value '6e4'"));
+}
+
+#[test]
+fn test_batch_data_carriers() {
+    // one input, a single zero-value OP_RETURN "hello world" output
+    let op_return_tx = format!(
+        "0200000001{}0000000000ffffffff0100000000000000000d6a0b68656c6c6f20776f726c6400000000",
+        "aa".repeat(32)
+    );
+    let mut stats = BatchStats::new();
+    stats.add(&Transaction::from_hex(&op_return_tx).unwrap());
+    stats.add(&Transaction::from_hex(&inscription_tx()).unwrap());
+    stats.add(&Transaction::from_hex(TIMELOCK_TX).unwrap());
+
+    let carriers = stats.report().data_carriers;
+    assert_eq!(carriers.op_return, CarrierUsage { count: 1, weight: 4 * (8 + 1 + 13) });
+    // the 82-byte tapscript and its length prefix, at witness weight
+    assert_eq!(carriers.inscriptions, CarrierUsage { count: 1, weight: 83 });
+    assert_eq!(carriers.bare_multisig, CarrierUsage::default());
+    assert_eq!(carriers.oversized_witness, CarrierUsage::default());
+    assert_eq!(carriers.transactions_with_data, 2);
+    assert_eq!(carriers.data_weight() + carriers.monetary_weight(), carriers.total_weight);
+}
+
+#[test]
+fn test_privacy_metrics() {
+    let mut tx = Transaction::from_hex(&key_reuse_tx()).unwrap();
+    tx.outputs.push(tx.outputs[0].clone());
+    tx.outputs[1].index = 1;
+    for (input, value) in tx.inputs.iter_mut().zip([50_000, 50_000]) {
+        input.value = Some(Amount::from_sat(value));
+    }
+    tx.outputs[0].value = Amount::from_sat(49_000);
+    tx.outputs[1].value = Amount::from_sat(49_000);
+
+    // two equal inputs paying two equal outputs: one joint payment or either
+    // input paying either output, 3 interpretations
+    let report = tx.privacy();
+    assert_eq!(report.equal_outputs.len(), 1);
+    assert_eq!(report.equal_outputs[0].outputs, vec![0, 1]);
+    assert!(report.round_outputs.is_empty());
+    let entropy = report.entropy.unwrap();
+    assert_eq!(entropy.interpretations, 3);
+    assert!((entropy.bits - 3f64.log2()).abs() < 1e-9);
+
+    // a round payment plus change is fully linked
+    tx.outputs[0].value = Amount::from_sat(30_000);
+    tx.outputs[1].value = Amount::from_sat(69_000);
+    let report = tx.privacy();
+    assert_eq!(report.round_outputs, vec![RoundOutput { output: 0, value: Amount::from_sat(30_000), unit: 10_000 }]);
+    assert!(report.equal_outputs.is_empty());
+    assert_eq!(report.entropy.unwrap().interpretations, 1);
+
+    tx.inputs[0].value = None;
+    assert!(tx.privacy().entropy.is_none());
+}
+
+#[test]
+fn test_plausible_flows() {
+    let mut tx = Transaction::from_hex(&key_reuse_tx()).unwrap();
+    tx.outputs.push(tx.outputs[0].clone());
+    tx.outputs[1].index = 1;
+    for (input, value) in tx.inputs.iter_mut().zip([30_000, 70_000]) {
+        input.value = Some(Amount::from_sat(value));
+    }
+    tx.outputs[0].value = Amount::from_sat(30_000);
+    tx.outputs[1].value = Amount::from_sat(69_000);
+
+    // either input #0 paid output #0 alone, or both inputs paid both outputs
+    let flows = tx.plausible_flows().unwrap();
+    assert_eq!(flows.interpretations, 2);
+    assert_eq!(flows.outputs[0].funders, vec![Funder { input: 0, probability: 1.0 }, Funder { input: 1, probability: 0.5 }]);
+    assert_eq!(flows.outputs[1].funders, vec![Funder { input: 0, probability: 0.5 }, Funder { input: 1, probability: 1.0 }]);
+
+    let mut registry = AnalyzerRegistry::new();
+    registry.register(FlowAnalyzer);
+    let findings = registry.run(&tx);
+    assert_eq!(findings.len(), 2);
+    assert_eq!(findings[0].output, Some(0));
+    assert_eq!(findings[0].message, "could have been funded by inputs #0 (100%), #1 (50%)");
+
+    // neither input covers the change alone, so everything is linked
+    tx.inputs[0].value = Some(Amount::from_sat(50_000));
+    tx.inputs[1].value = Some(Amount::from_sat(50_000));
+    let flows = tx.plausible_flows().unwrap();
+    assert_eq!(flows.interpretations, 1);
+    assert!(flows.outputs.iter().all(|o| o.funders.iter().all(|f| f.probability == 1.0)));
+
+    tx.inputs[0].value = None;
+    assert!(tx.plausible_flows().is_none());
+    assert!(registry.run(&tx).is_empty());
+}
+
+#[test]
+fn test_change_links() {
+    // two P2WPKH spends by KEY_1 paying a P2WPKH and a P2TR output
+    let p2tr = format!("225120{}", "77".repeat(32));
+    let with_outputs = |p2wpkh: &str| {
+        let hex = key_reuse_tx().replace(&format!("01a086010000000000160014{}", "89".repeat(20)),
+            &format!("02a086010000000000160014{}a086010000000000{}", p2wpkh, p2tr));
+        Transaction::from_hex(&hex).unwrap()
+    };
+
+    // output #0 is KEY_1's own P2WPKH address
+    let links = with_outputs("751e76e8199196d454941c45d1b3a323f1433bd6").provenance_links();
+    let pairs: Vec<(usize, usize, LinkKind)> = links.iter().map(|l| (l.input, l.output, l.kind)).collect();
+    assert_eq!(pairs, vec![(0, 0, LinkKind::SameKey), (1, 0, LinkKind::SameKey)]);
+    assert_eq!(links[0].detail, "pays P2WPKH of the key the input revealed");
+
+    // another P2WPKH key: only the script type ties it to the inputs
+    let tx = with_outputs(&"89".repeat(20));
+    let links = tx.provenance_links();
+    assert_eq!(links.len(), 2);
+    assert!(links.iter().all(|l| l.kind == LinkKind::SameTemplate && l.output == 0 && l.confidence == 0.6));
+    assert_eq!(tx.privacy().change_links, links);
+
+    // a single payment has no change
+    assert!(Transaction::from_hex(&key_reuse_tx()).unwrap().provenance_links().is_empty());
+}
+
+#[test]
+fn test_redaction() {
+    let mut tx = Transaction::from_hex(&key_reuse_tx()).unwrap();
+    tx.inputs[0].value = Some(Amount::from_sat(123_456));
+    tx.inputs[1].value = Some(Amount::from_sat(50_000));
+    tx.set_fee(tx.calculate_fee());
+
+    let redacted = tx.redacted(Redaction::Addresses);
+    assert_eq!(&redacted.txid[..8], &tx.txid[..8]);
+    assert!(redacted.txid[8..60].chars().all(|c| c == '*'));
+    assert_eq!(&redacted.txid[60..], &tx.txid[60..]);
+    let spent = redacted.inputs[0].txid.to_string();
+    assert_eq!((&spent[..8], &spent[60..]), (&tx.inputs[0].txid.to_string()[..8], &tx.inputs[0].txid.to_string()[60..]));
+    assert!(spent[8..60].chars().all(|c| c == '0'));
+    // keys and signatures go, sizes and types stay
+    let witness = redacted.inputs[0].witness.as_ref().unwrap();
+    assert!(witness.iter().all(|item| item.iter().all(|b| *b == 0)));
+    assert_eq!(witness[1].len(), 33);
+    let output = &redacted.outputs[0];
+    assert_eq!(output.script_pubkey.asm, format!("OP_0 {}", "00".repeat(20)));
+    assert_eq!(output.script_type, ScriptType::P2WPKH);
+    let address = &tx.outputs[0].address.as_ref().unwrap().mainnet;
+    assert_eq!(output.address.as_ref().unwrap().mainnet, format!("{}…{}", &address[..6], &address[address.len() - 4..]));
+    assert_eq!(redacted.outputs[0].value, tx.outputs[0].value);
+
+    let redacted = tx.redacted(Redaction::Values);
+    assert_eq!(redacted.txid, tx.txid);
+    assert_eq!(redacted.inputs[0].value, Some(Amount::from_sat(100_000)));
+    let (fee, bucket) = (tx.fee_satoshis.unwrap().to_sat(), redacted.fee_satoshis.unwrap().to_sat());
+    assert_eq!(bucket, 10u64.pow(bucket.ilog10()));
+    assert!(bucket <= fee && fee < bucket * 10);
+
+    let redacted = tx.redacted(Redaction::All);
+    assert!(redacted.txid.contains('*') && redacted.outputs[0].value <= tx.outputs[0].value);
+}
+
+#[test]
+fn test_pseudonymization() {
+    // RFC 4231 test case 2, split across the domain tag and the message
+    let jefe = Pseudonymizer::new(b"Jefe");
+    assert_eq!(hex::encode(jefe.mac(b"what do ya", b" want for nothing?")),
+        "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843");
+
+    let tx = Transaction::from_hex(&key_reuse_tx()).unwrap();
+    let pseudonymizer = Pseudonymizer::new(b"research key");
+    let shared = pseudonymizer.pseudonymize(&tx);
+
+    // a transaction spending this one still points at its pseudonym
+    assert_eq!(shared.txid, pseudonymizer.txid(tx.txid.parse().unwrap()).to_string());
+    assert_ne!(shared.txid, tx.txid);
+    assert_eq!(shared.inputs[0].txid, pseudonymizer.txid(tx.inputs[0].txid));
+
+    // both inputs reveal KEY_1, and still reveal one same (other) key
+    let keys: Vec<&Vec<u8>> = shared.inputs.iter().map(|i| &i.witness.as_ref().unwrap()[1]).collect();
+    assert_eq!(keys[0], keys[1]);
+    assert_ne!(hex::encode(keys[0]), KEY_1);
+    assert_eq!(keys[0].len(), 33);
+
+    let output = &shared.outputs[0];
+    assert_eq!((output.value, &output.script_type, output.script_pubkey.size), (tx.outputs[0].value, &tx.outputs[0].script_type, 22));
+    assert_ne!(output.address.as_ref().unwrap().mainnet, tx.outputs[0].address.as_ref().unwrap().mainnet);
+    assert!(output.address.as_ref().unwrap().mainnet.starts_with("bc1q"));
+
+    // stable under one key, unrelated under another
+    assert_eq!(pseudonymizer.pseudonymize(&tx).txid, shared.txid);
+    assert_ne!(Pseudonymizer::new(b"other key").pseudonymize(&tx).txid, shared.txid);
+}
+
+// ============================================================================
+// Lightning Tests
+// ============================================================================
+
+// Spends a 2-of-2 funding output with the given sequence and locktime
+fn channel_close_tx(sequence: u32, locktime: u32, outputs: &[(u64, String)]) -> String {
+    let funding_script = format!("5221{}21{}52ae", KEY_1, KEY_2);
+    let serialized: String = outputs.iter()
+        .map(|(value, script)| format!("{}{:02x}{}", hex::encode(value.to_le_bytes()), script.len() / 2, script))
+        .collect();
+    let signature = format!("47{}01", "30".repeat(70));
+    format!(
+        "02000000000101{}0000000000{}{:02x}{}0400{}{}47{}{}",
+        "aa".repeat(32), hex::encode(sequence.to_le_bytes()), outputs.len(), serialized,
+        signature, signature, funding_script, hex::encode(locktime.to_le_bytes())
+    )
+}
+
+fn p2wsh(script: &str) -> String {
+    format!("0020{}", hex::encode(Sha256::digest(hex::decode(script).unwrap())))
+}
+
+#[test]
+fn test_channel_close_classification() {
+    let to_local = format!("6321{}67029000b27521{}68ac", KEY_1, KEY_2);
+    let received_htlc = format!(
+        "76a914{}8763ac6721{}7c820120876375a914{}88527c21{}52ae67750300350cb175ac6868",
+        "11".repeat(20), KEY_1, "22".repeat(20), KEY_2
+    );
+    let outputs = vec![
+        (330, p2wsh(&format!("21{}ac73640160b268", KEY_1))),
+        (50_000, p2wsh(&to_local)),
+        (20_000, p2wsh(&received_htlc)),
+        (30_000, format!("0014{}", "33".repeat(20))),
+    ];
+    let tx = Transaction::from_hex(&channel_close_tx(0x80123456, 0x20abcdef, &outputs)).unwrap();
+
+    let close = tx.channel_close().unwrap();
+    assert_eq!(close.kind, CloseKind::ForceClose);
+    assert_eq!(close.funding_pubkeys, vec![KEY_1, KEY_2]);
+    assert_eq!(close.obscured_commitment_number, Some(0x123456abcdef));
+    let roles: Vec<&ChannelOutputRole> = close.outputs.iter().map(|o| &o.role).collect();
+    assert_eq!(roles, [&ChannelOutputRole::Anchor, &ChannelOutputRole::Unresolved, &ChannelOutputRole::Unresolved, &ChannelOutputRole::ToRemote]);
+
+    let scripts = vec![hex::decode(&to_local).unwrap(), hex::decode(&received_htlc).unwrap()];
+    let close = tx.channel_close_with_scripts(&scripts).unwrap();
+    assert_eq!(close.outputs[1].role, ChannelOutputRole::ToLocal { to_self_delay: 144 });
+    let htlcs = close.pending_htlcs();
+    assert_eq!(htlcs.len(), 1);
+    assert_eq!(htlcs[0].role, ChannelOutputRole::ReceivedHtlc { payment_hash160: "22".repeat(20), cltv_expiry: 800_000 });
+    assert!(tx.analyze().iter().any(|f| f.kind == "channel_close"));
+
+    let cooperative = Transaction::from_hex(&channel_close_tx(0xffffffff, 0, &outputs[1..])).unwrap();
+    let close = cooperative.channel_close().unwrap();
+    assert_eq!(close.kind, CloseKind::Cooperative);
+    assert!(close.outputs.iter().all(|o| o.role == ChannelOutputRole::Payout));
+    assert!(Transaction::from_hex(TIMELOCK_TX).unwrap().channel_close().is_none());
+}
+
+#[test]
+fn test_interactive_tx_detection() {
+    let new_funding = p2wsh(&format!("5221{}21{}52ae", KEY_1, KEY_3));
+    let outputs = vec![(900_000, new_funding.clone()), (50_000, format!("0014{}", "33".repeat(20)))];
+    let splice = Transaction::from_hex(&channel_close_tx(0xfffffffd, 850_000, &outputs)).unwrap();
+    let interactive = splice.interactive_tx().unwrap();
+    assert_eq!(interactive.kind, InteractiveTxKind::Splice);
+    assert_eq!((interactive.funding_input, interactive.funding_outputs), (Some(0), vec![0]));
+    let findings = splice.analyze();
+    assert!(findings.iter().any(|f| f.kind == "splice"));
+    assert!(!findings.iter().any(|f| f.kind == "channel_close"));
+
+    // no locktime and no added inputs reads as a cooperative close
+    let close = Transaction::from_hex(&channel_close_tx(0xfffffffd, 0, &outputs)).unwrap();
+    assert!(close.interactive_tx().is_none());
+    assert!(close.analyze().iter().any(|f| f.kind == "channel_close"));
+
+    // two peers' P2WPKH inputs into a P2WSH output plus two change outputs
+    let input = |n: &str| format!("{}00000000{}fdffffff", n.repeat(32), "00");
+    let witness = format!("0247{}0121{}", "30".repeat(70), KEY_1);
+    let serialized: String = [(1_000_000u64, new_funding), (40_000, format!("0014{}", "44".repeat(20))), (60_000, format!("0014{}", "55".repeat(20)))]
+        .iter()
+        .map(|(value, script)| format!("{}{:02x}{}", hex::encode(value.to_le_bytes()), script.len() / 2, script))
+        .collect();
+    let open = format!("02000000000102{}{}03{}{}{}50f80c00", input("bb"), input("cc"), serialized, witness, witness);
+    let interactive = Transaction::from_hex(&open).unwrap().interactive_tx().unwrap();
+    assert_eq!(interactive.kind, InteractiveTxKind::DualFundedOpen);
+    assert_eq!((interactive.funding_input, interactive.contributed_inputs), (None, 2));
+    assert!(Transaction::from_hex(&key_reuse_tx()).unwrap().interactive_tx().is_none());
+}
+
+#[test]
+fn test_commitment_number_decoding() {
+    // BOLT 3 test vectors: commitment number 42, obscuring factor 0x2bb038521914
+    let outputs = vec![(30_000, format!("0014{}", "33".repeat(20)))];
+    let tx = Transaction::from_hex(&channel_close_tx(0x802bb038, 0x2052193e, &outputs)).unwrap();
+    let mut close = tx.channel_close().unwrap();
+    assert_eq!(close.obscured_commitment_number, Some(0x2bb03852193e));
+    let opener = hex::decode("034f355bdcb7cc0af728ef3cceb9615d90684bb5b2ca5f859ab0f0b704075871aa").unwrap();
+    let accepter = hex::decode("032c0b7cf95324a07d05398b240174dc0c2be444d96b159aa6c7f7b1e668680991").unwrap();
+    assert_eq!(close.decode_commitment_number(&opener, &accepter), Some(42));
+    assert_eq!(close.commitment_number, Some(42));
+    assert!(!close.has_anchors());
+    assert_eq!(close.to_self_delay(), None);
+
+    let mut cooperative = Transaction::from_hex(&channel_close_tx(0xffffffff, 0, &outputs)).unwrap().channel_close().unwrap();
+    assert_eq!(cooperative.decode_commitment_number(&opener, &accepter), None);
+
+    // a simple taproot channel's commitment only shows the markers
+    let taproot = format!(
+        "02000000000101{}000000000038b02b8001{}{}0140{}3e195220",
+        "aa".repeat(32), hex::encode(30_000u64.to_le_bytes()), "160014".to_string() + &"33".repeat(20), "11".repeat(64)
+    );
+    let close = Transaction::from_hex(&taproot).unwrap().channel_close().unwrap();
+    assert_eq!(close.kind, CloseKind::ForceClose);
+    assert!(close.funding_pubkeys.is_empty());
+    assert_eq!(close.obscured_commitment_number, Some(0x2bb03852193e));
+}
+
+#[test]
+fn test_channel_funding_detection() {
+    let input = |n: &str| format!("{}00000000{}fdffffff", n.repeat(32), "00");
+    let witness = format!("0247{}0121{}", "30".repeat(70), KEY_1);
+    let funding_tx = |outputs: &[(u64, String)]| {
+        let serialized: String = outputs.iter()
+            .map(|(value, script)| format!("{}{:02x}{}", hex::encode(value.to_le_bytes()), script.len() / 2, script))
+            .collect();
+        Transaction::from_hex(&format!("02000000000101{}{:02x}{}{}50f80c00", input("bb"), outputs.len(), serialized, witness)).unwrap()
+    };
+    let channel = p2wsh(&format!("5221{}21{}52ae", KEY_1, KEY_2));
+    let change = format!("0014{}", "44".repeat(20));
+
+    let tx = funding_tx(&[(40_000, change.clone()), (1_000_000, channel.clone())]);
+    let funding = tx.channel_funding().unwrap();
+    assert_eq!((funding.funding_output, funding.capacity, funding.taproot, funding.change_output), (1, Amount::from_sat(1_000_000), false, Some(0)));
+    let finding = tx.analyze().into_iter().find(|f| f.kind == "channel_funding").unwrap();
+    assert_eq!(finding.output, Some(1));
+
+    let taproot = funding_tx(&[(500_000, format!("5120{}", "55".repeat(32)))]).channel_funding().unwrap();
+    assert!(taproot.taproot && taproot.change_output.is_none());
+
+    // too small, two candidates, or a spend of a funding output
+    assert!(funding_tx(&[(10_000, channel.clone())]).channel_funding().is_none());
+    assert!(funding_tx(&[(100_000, channel.clone()), (100_000, channel.clone())]).channel_funding().is_none());
+    let close = Transaction::from_hex(&channel_close_tx(0xffffffff, 0, &[(100_000, channel)])).unwrap();
+    assert!(close.channel_funding().is_none());
+    assert!(!close.analyze().iter().any(|f| f.kind == "channel_funding"));
+    assert!(Transaction::from_hex(TIMELOCK_TX).unwrap().channel_funding().is_none());
+}
+
+#[test]
+fn test_lightning_spends() {
+    let to_local = format!("6321{}67029000b27521{}68ac", KEY_1, KEY_2);
+    let received_htlc = format!(
+        "76a914{}8763ac6721{}7c820120876375a914{}88527c21{}52ae67750300350cb175ac6868",
+        "11".repeat(20), KEY_1, "22".repeat(20), KEY_2
+    );
+    let anchor = format!("21{}ac736460b268", KEY_1);
+    // the stack items, then the witness script
+    let witness = |items: &[&str], script: &str| {
+        let stack: String = items.iter().map(|item| format!("{:02x}{}", item.len() / 2, item)).collect();
+        format!("{:02x}{}{:02x}{}", items.len() + 1, stack, script.len() / 2, script)
+    };
+    let sig = &format!("{}01", "30".repeat(70));
+    let witnesses = [
+        // delayed sweep: <sig> <>
+        witness(&[sig, ""], &to_local),
+        // HTLC-success: 0 <remote sig> <local sig> <preimage>
+        witness(&["", sig, sig, &"44".repeat(32)], &received_htlc),
+        // anyone's sweep of an unclaimed anchor
+        witness(&[""], &anchor),
+        // penalty on a revoked to_local: <revocation sig> 1
+        witness(&[sig, "01"], &to_local),
+    ];
+    let input = |n: &str| format!("{}0000000000{}", n.repeat(32), "90000000");
+    let tx_hex = format!(
+        "02000000000104{}{}{}{}01e803000000000000160014{}{}00000000",
+        input("aa"), input("bb"), input("cc"), input("dd"), "55".repeat(20), witnesses.concat()
+    );
+    let tx = Transaction::from_hex(&tx_hex).unwrap();
+
+    let spends = tx.lightning_spends();
+    let paths: Vec<(usize, SpendPath)> = spends.iter().map(|s| (s.input, s.path)).collect();
+    assert_eq!(paths, [(0, SpendPath::Delayed), (1, SpendPath::Preimage), (2, SpendPath::Anyone), (3, SpendPath::Revocation)]);
+    assert_eq!(spends[0].role, ChannelOutputRole::ToLocal { to_self_delay: 144 });
+    assert_eq!(spends[0].describe(), "sweeps a to_local (or second-stage HTLC) output after its 144-block delay");
+    assert_eq!(spends[1].role, ChannelOutputRole::ReceivedHtlc { payment_hash160: "22".repeat(20), cltv_expiry: 800_000 });
+    assert_eq!(spends[2].role, ChannelOutputRole::Anchor);
+
+    let findings: Vec<Finding> = tx.analyze().into_iter().filter(|f| f.kind == "lightning_spend").collect();
+    assert_eq!(findings.iter().map(|f| f.input).collect::<Vec<_>>(), [Some(0), Some(1), Some(2), Some(3)]);
+    assert!(findings[3].message.starts_with("penalty"));
+    assert!(Transaction::from_hex(TIMELOCK_TX).unwrap().lightning_spends().is_empty());
+}
+
+#[test]
+fn test_htlc_recognition() {
+    // atomic swap: claim with the SHA256 preimage, refund after block 850000
+    let swap = format!("63a820{}8821{}ac670350f80cb17521{}ac68", "11".repeat(32), KEY_1, KEY_2);
+    // bare output: HASH160 preimage lock, refund after 144 blocks
+    let bare = format!("6382012088a914{}8821{}ac67029000b27521{}ac68", "22".repeat(20), KEY_2, KEY_1);
+    let witness = format!("0447{}20{}0101{:02x}{}", "30".repeat(71), "33".repeat(32), swap.len() / 2, swap);
+    let tx_hex = format!(
+        "02000000000101{}0000000000fdffffff01e803000000000000{:02x}{}{}00000000",
+        "aa".repeat(32), bare.len() / 2, bare, witness
+    );
+    let tx = Transaction::from_hex(&tx_hex).unwrap();
+
+    let htlcs = tx.htlcs();
+    assert_eq!(htlcs.len(), 2);
+    assert_eq!((htlcs[0].output, htlcs[0].hash_lock), (Some(0), HashLock::Hash160));
+    assert_eq!(htlcs[0].payment_hash, "22".repeat(20));
+    assert_eq!((htlcs[0].timeout.kind, htlcs[0].timeout.value), (LockKind::Relative, 144));
+    assert_eq!((htlcs[1].input, htlcs[1].hash_lock), (Some(0), HashLock::Sha256));
+    assert_eq!(htlcs[1].payment_hash, "11".repeat(32));
+    assert_eq!((htlcs[1].timeout.kind, htlcs[1].timeout.value), (LockKind::Absolute, 850_000));
+    assert_eq!(tx.analyze().iter().filter(|f| f.kind == "htlc").count(), 2);
+
+    // a key hash check is not a hash lock
+    assert!(Transaction::from_hex(&key_reuse_tx()).unwrap().htlcs().is_empty());
+    assert!(Transaction::from_hex(TIMELOCK_TX).unwrap().htlcs().is_empty());
+}
+
+#[test]
+fn test_fidelity_bonds() {
+    // locked until 2025-01-01 00:00 UTC, as Joinmarket does
+    let bond_script = format!("0480857467b17521{}ac", KEY_1);
+    // bare output locked until 2027-01-15, over a year past the nLockTime
+    let burn = format!("0400d2496bb17521{}ac", KEY_2);
+    let tx_hex = format!(
+        "02000000000101{}0000000000feffffff01e803000000000000{:02x}{}0247{}{:02x}{}80857467",
+        "aa".repeat(32), burn.len() / 2, burn, "30".repeat(71), bond_script.len() / 2, bond_script
+    );
+    let tx = Transaction::from_hex(&tx_hex).unwrap();
+
+    let bonds = tx.fidelity_bonds();
+    assert_eq!(bonds.len(), 1);
+    assert_eq!((bonds[0].input, bonds[0].locktime, bonds[0].unit), (Some(0), 1_735_689_600, LockUnit::Seconds));
+    assert_eq!(bonds[0].pubkey, KEY_1);
+    assert!(bonds[0].joinmarket);
+    let findings = tx.analyze();
+    let bond = findings.iter().find(|f| f.kind == "fidelity_bond").unwrap();
+    assert!(bond.message.contains("Joinmarket") && bond.message.ends_with("2025-01-01"));
+    let burn = findings.iter().find(|f| f.kind == "long_timelock").unwrap();
+    assert_eq!(burn.output, Some(0));
+    assert!(burn.message.contains("2027-01-15"));
+
+    // creating the bond only shows a P2WSH output until the script is known
+    let outputs = vec![(100_000, p2wsh(&bond_script))];
+    let funding = Transaction::from_hex(&channel_close_tx(0xffffffff, 0, &outputs)).unwrap();
+    assert!(funding.fidelity_bonds().is_empty());
+    let bonds = funding.fidelity_bonds_with_scripts(&[hex::decode(&bond_script).unwrap()]);
+    assert_eq!((bonds.len(), bonds[0].output), (1, Some(0)));
+}
+
+// ============================================================================
+// OP_RETURN Protocol Tests
+// ============================================================================
+
+#[test]
+fn test_op_return_protocols() {
+    let registry = OpReturnRegistry::with_defaults();
+    // etch A•A with divisibility 2 and symbol $, then send 100 of rune 840000:1 to output 1
+    let (protocol, runestone) = registry.decode(&hex::decode("6a5d110201041a03010102052400c0a233016401").unwrap()).unwrap();
+    assert_eq!(protocol, "runes");
+    assert_eq!(runestone.summary, "Runestone: etches A•A, 1 edict(s)");
+    assert_eq!((&runestone.fields["divisibility"], &runestone.fields["symbol"]), (&"2".to_string(), &"$".to_string()));
+    // a tag's value of 0 is not the body tag
+    let (_, mint) = registry.decode(&hex::decode("6a5d0814c0a23314011600").unwrap()).unwrap();
+    assert_eq!((mint.summary.as_str(), mint.fields["pointer"].as_str()), ("Runestone: mints 840000:1", "0"));
+    for (script, reason) in [("6a5d027e00", "unknown even tag 126"), ("6a5d0180", "truncated or overlong integer"), ("6a5d020000", "an edict cut short")] {
+        assert_eq!(registry.decode(&hex::decode(script).unwrap()).unwrap().1.fields["cenotaph"], reason);
+    }
+
+    // Class C Omni Simple Send, the tag and message in separate pushes
+    let (protocol, omni) = registry.decode(&hex::decode("6a046f6d6e6910000000000000001f000000000bebc200").unwrap()).unwrap();
+    assert_eq!((protocol, omni.summary.as_str()), ("omni", "Omni Simple Send: 2 USDT"));
+    assert!(registry.decode(&hex::decode("6a046f6d6e69").unwrap()).is_none());
+
+    // a downstream tag, and a decoder taking over from a built-in one
+    let mut custom = OpReturnRegistry::with_defaults();
+    custom.register("acme", b"ACME".to_vec(), |data| Some(ProtocolMessage::new("ACME shipment").with_field("id", String::from_utf8_lossy(data))));
+    custom.register("omni_detail", b"omni".to_vec(), |_| Some(ProtocolMessage::new("detailed")));
+    let (protocol, acme) = custom.decode(&hex::decode("6a0741434d45313233").unwrap()).unwrap();
+    assert_eq!((protocol, acme.fields["id"].as_str()), ("acme", "123"));
+    assert_eq!(custom.decode(&hex::decode("6a046f6d6e69").unwrap()).unwrap().0, "omni_detail");
+    assert!(custom.names().contains(&"acme"));
+
+    let outputs = vec![
+        (0, format!("6a20{}", "11".repeat(32))),
+        (0, format!("6a24aa21a9ed{}", "22".repeat(32))),
+        (0, "6a0474657374".to_string()),
+    ];
+    let tx = Transaction::from_hex(&channel_close_tx(0xffffffff, 0, &outputs)).unwrap();
+    let messages = tx.op_return_messages();
+    let protocols: Vec<(usize, &str)> = messages.iter().map(|m| (m.output, m.protocol.as_str())).collect();
+    assert_eq!(protocols, [(0, "opentimestamps"), (1, "witness_commitment")]);
+    // a bare 32-byte hash is left to the timestamp analyzer
+    let findings: Vec<Finding> = tx.analyze().into_iter().filter(|f| f.kind == "op_return_protocol").collect();
+    assert_eq!(findings.len(), 1);
+    assert_eq!((findings[0].output, findings[0].message.as_str()), (Some(1), "output #1: BIP141 witness commitment"));
+}
+
+#[test]
+fn test_omni_and_counterparty() {
+    let registry = OpReturnRegistry::with_defaults();
+    let decode = |script: String| registry.decode(&hex::decode(script).unwrap()).unwrap().1;
+    let omni = decode(format!("6a146f6d6e69000000370000007b{}", hex::encode(1_234u64.to_be_bytes())));
+    assert_eq!(omni.summary, "Omni Grant Tokens: 1234 units property 123");
+    assert_eq!(omni.fields["amount"], "1234 units");
+    let omni = decode("6a146f6d6e6900000000000000010000000005f5e100".to_string());
+    assert_eq!((omni.fields["asset"].as_str(), omni.fields["amount"].as_str()), ("OMNI", "1"));
+
+    // Counterparty encrypts the whole payload with the first input's txid
+    assert_eq!(hex::encode(crate::op_return::arc4(b"Key", b"Plaintext")), "bbf316e8d940af0ad3");
+    let send = [
+        &b"CNTRPRTY"[..],
+        &[2],
+        &1u64.to_be_bytes(),
+        &150_000_000u64.to_be_bytes(),
+        &[0x00; 21],
+    ].concat();
+    let issuance = [&b"CNTRPRTY"[..], &[0, 0, 0, 20], &121_892_899_915u64.to_be_bytes(), &1_000u64.to_be_bytes(), &[0]].concat();
+    let key: Vec<u8> = [0xaa; 32].to_vec();
+    let outputs: Vec<(u64, String)> = [send, issuance].iter()
+        .map(|plain| (0, format!("6a{:02x}{}", plain.len(), hex::encode(crate::op_return::arc4(&key, plain)))))
+        .collect();
+    let tx = Transaction::from_hex(&channel_close_tx(0xffffffff, 0, &outputs)).unwrap();
+    let messages = tx.op_return_messages();
+    assert_eq!(messages.len(), 2);
+    assert_eq!(messages[0].protocol, "counterparty");
+    assert_eq!(messages[0].message.summary, "Counterparty Enhanced Send: 1.5 XCP");
+    assert_eq!(messages[1].message.summary, "Counterparty Issuance: 1000 PEPECASH");
+    // without the transaction there is no key
+    assert!(registry.decode(&tx.outputs[0].script_pubkey.bytes).is_none());
+}
+
+// ============================================================================
+// Block Template Tests
+// ============================================================================
+
+#[test]
+fn test_block_template_report() {
+    let tx = Transaction::from_hex(&key_reuse_tx()).unwrap();
+    let json = serde_json::json!({
+        "result": {
+            "height": 850000,
+            "coinbasevalue": 312_510_000u64,
+            "transactions": [
+                { "data": key_reuse_tx(), "txid": tx.txid, "fee": 10_000, "sigops": 2, "weight": tx.weight },
+                { "data": key_reuse_tx(), "txid": "00".repeat(32), "fee": 5_000, "sigops": 2 },
+                { "data": "zz" }
+            ]
+        }
+    });
 
-    let tx = tx.unwrap();
-    assert_eq!(tx.version, 2);
-    assert!(tx.is_segwit);
+    let report = BlockTemplate::from_json(&json.to_string()).unwrap().analyze();
+    assert_eq!(report.height, Some(850000));
+    assert_eq!(report.transactions, 3);
+    assert_eq!(report.entries.len(), 2);
+    assert_eq!(report.total_fees, Amount::from_sat(15_000));
+    // two P2WPKH spends per transaction
+    assert_eq!(tx.sigop_cost(), 2);
+    assert_eq!(report.sigops, 4);
+    assert_eq!(report.weight, 2 * tx.weight as u64);
+    assert_eq!(report.issues.len(), 2);
+
+    assert!(BlockTemplate::from_json("{}").is_err());
+}
+
+#[test]
+fn test_sigop_cost() {
+    let sig = format!("30440220{}0220{}01", "11".repeat(32), "22".repeat(32));
+    let multisig = format!("5121{}51ae", KEY_1);
+    let script_sig = format!("0047{}{:02x}{}", sig, multisig.len() / 2, multisig);
+    let hex = format!("0100000001{}00000000{:02x}{}ffffffff01a086010000000000160014{}00000000",
+        "aa".repeat(32), script_sig.len() / 2, script_sig, "89".repeat(20));
+    let mut tx = Transaction::from_hex(&hex).unwrap();
+    // the P2SH redeem script counts its keys, scaled by 4
+    assert_eq!(tx.sigop_cost(), 4);
+
+    // a bare multisig output counts 20 keys, and OP_CHECKSIG one
+    tx.outputs[0].script_pubkey.bytes = hex::decode(&multisig).unwrap();
+    assert_eq!(tx.sigop_cost(), 84);
+    tx.outputs[0].script_pubkey.bytes = hex::decode(format!("21{}ac", KEY_1)).unwrap();
+    assert_eq!(tx.sigop_cost(), 8);
+
+    // a nested P2WSH spend counts its witness script unscaled
+    let multisig = hex::decode(&multisig).unwrap();
+    tx.inputs[0].script_sig.bytes = [&[0x22, 0x00, 0x20][..], &Sha256::digest(&multisig)].concat();
+    tx.inputs[0].witness = Some(vec![Vec::new(), hex::decode(&sig).unwrap(), multisig]);
+    assert_eq!(tx.sigop_cost(), 5);
+
+    // truncated pushes stop the count instead of failing
+    tx.outputs[0].script_pubkey.bytes = vec![0xac, 0x4c];
+    assert_eq!(tx.sigop_cost(), 5);
+
+    // a coinbase counts legacy sigops only
+    tx.inputs[0].is_coinbase = true;
+    assert_eq!(tx.sigop_cost(), 4);
+}
+
+// ============================================================================
+// Stratum Tests
+// ============================================================================
+
+// Genesis coinbase split around two 4-byte extranonces
+fn genesis_job() -> StratumJob {
+    let genesis = "01000000010000000000000000000000000000000000000000000000000000000000000000ffffffff4d04ffff001d0104455468652054696d65732030332f4a616e2f32303039204368616e63656c6c6f72206f6e206272696e6b206f66207365636f6e64206261696c6f757420666f722062616e6b73ffffffff0100f2052a01000000434104678afdb0fe5548271967f1a67130b7105cd6a828e03909a67962e0ea1f61deb649f6bc3f4cef38c4f35504e51ec112de5c384df7ba0b8d578a4c702b6bf11d5fac00000000";
+    let split = genesis.find("45546865").unwrap();
+    StratumJob {
+        coinb1: genesis[..split].to_string(),
+        extranonce1: genesis[split..split + 8].to_string(),
+        extranonce2: genesis[split + 8..split + 16].to_string(),
+        coinb2: genesis[split + 16..].to_string(),
+        merkle_branch: Vec::new(),
+    }
+}
+
+#[test]
+fn test_stratum_reconstruction() {
+    let mut job = genesis_job();
+    let result = job.reconstruct().unwrap();
+    assert_eq!(result.merkle_root, "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b");
+    assert_eq!(result.merkle_root, result.coinbase.txid);
+    assert_eq!(result.coinbase.outputs[0].value.to_sat(), 5_000_000_000);
+
+    job.merkle_branch.push("11".repeat(32));
+    let result = job.reconstruct().unwrap();
+    assert_eq!(result.merkle_root, "1c710512b7648f7002b74f946a1e4a03433270c242cf0d996a6b7b6a6fd37ddb");
+
+    job.merkle_branch.push("11".to_string());
+    assert!(job.reconstruct().is_err());
+}
+
+// ============================================================================
+// PSBT Tests
+// ============================================================================
+
+const KEY_1: &str = "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+const KEY_2: &str = "02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5";
+const KEY_3: &str = "02f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9";
+
+fn psbt_pair(out: &mut Vec<u8>, key: &[u8], value: &[u8]) {
+    out.push(key.len() as u8);
+    out.extend_from_slice(key);
+    out.push(value.len() as u8);
+    out.extend_from_slice(value);
+}
+
+const MULTISIG_UNSIGNED_TX: &str = "0200000001aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa0000000000fdffffff01a08601000000000016001489abcdefabbaabbaabbaabbaabbaabbaabbaabba00000000";
+
+// 2-of-3 P2WSH spend signed by the first cosigner only
+fn multisig_psbt() -> Vec<u8> {
+    let mut psbt = b"psbt\xff".to_vec();
+    psbt_pair(&mut psbt, &[0x00], &hex::decode(MULTISIG_UNSIGNED_TX).unwrap());
+    psbt.push(0x00);
+
+    multisig_input_fields(&mut psbt);
+    psbt.push(0x00);
+
+    psbt.push(0x00);
+    psbt
+}
+
+// The same spend as a version 2 PSBT, optionally requiring a height locktime
+fn multisig_psbt_v2(required_height: Option<u32>) -> Vec<u8> {
+    let mut psbt = b"psbt\xff".to_vec();
+    psbt_pair(&mut psbt, &[0x02], &2u32.to_le_bytes());
+    psbt_pair(&mut psbt, &[0x04], &[0x01]);
+    psbt_pair(&mut psbt, &[0x05], &[0x01]);
+    psbt_pair(&mut psbt, &[0x06], &[0x03]);
+    psbt_pair(&mut psbt, &[0xfb], &2u32.to_le_bytes());
+    psbt.push(0x00);
+
+    psbt_pair(&mut psbt, &[0x0e], &[0xaa; 32]);
+    psbt_pair(&mut psbt, &[0x0f], &0u32.to_le_bytes());
+    psbt_pair(&mut psbt, &[0x10], &0xffff_fffdu32.to_le_bytes());
+    if let Some(height) = required_height {
+        psbt_pair(&mut psbt, &[0x12], &height.to_le_bytes());
+    }
+    multisig_input_fields(&mut psbt);
+    psbt.push(0x00);
+
+    psbt_pair(&mut psbt, &[0x03], &100_000u64.to_le_bytes());
+    psbt_pair(&mut psbt, &[0x04], &hex::decode("001489abcdefabbaabbaabbaabbaabbaabbaabbaabba").unwrap());
+    psbt.push(0x00);
+    psbt
+}
+
+fn multisig_input_fields(psbt: &mut Vec<u8>) {
+    let witness_script = hex::decode(format!("5221{}21{}21{}53ae", KEY_1, KEY_2, KEY_3)).unwrap();
+    psbt_pair(psbt, &[0x05], &witness_script);
+    for (i, key) in [KEY_1, KEY_2, KEY_3].iter().enumerate() {
+        let mut derivation_key = vec![0x06];
+        derivation_key.extend(hex::decode(key).unwrap());
+        let mut origin = vec![0xf0 + i as u8, 0x00, 0x00, 0x01];
+        origin.extend_from_slice(&(0x8000_0030u32).to_le_bytes());
+        origin.extend_from_slice(&5u32.to_le_bytes());
+        psbt_pair(psbt, &derivation_key, &origin);
+    }
+    let mut sig_key = vec![0x02];
+    sig_key.extend(hex::decode(KEY_1).unwrap());
+    psbt_pair(psbt, &sig_key, &[0x30, 0x06, 0x02, 0x01, 0x01, 0x02, 0x01, 0x01, 0x01]);
+}
+
+#[test]
+fn test_psbt_multisig_progress() {
+    let psbt = Psbt::from_bytes(&multisig_psbt()).unwrap();
+    assert_eq!(psbt.version, 0);
+    assert_eq!(psbt.inputs[0].bip32_derivations[0].path, "m/48'/5");
+
+    let progress = psbt.multisig_progress().unwrap();
+    assert_eq!(progress.inputs[0].required, 2);
+    assert_eq!(progress.inputs[0].total, 3);
+    assert_eq!(progress.inputs[0].signatures, 1);
+    assert!(!progress.inputs[0].complete);
+
+    assert_eq!(progress.cosigners.len(), 3);
+    assert_eq!(progress.cosigners[0].id, "f0000001");
+    assert_eq!(progress.cosigners[0].inputs[0].status, SigningStatus::Signed);
+    assert_eq!(progress.cosigners[1].inputs[0].status, SigningStatus::Pending);
+}
+
+#[test]
+fn test_psbt_v2_normalizes_to_unsigned_tx() {
+    let v0 = Psbt::from_bytes(&multisig_psbt()).unwrap();
+    let v2 = Psbt::from_bytes(&multisig_psbt_v2(None)).unwrap();
+    assert_eq!(v2.version, 2);
+    assert_eq!(v2.unsigned_tx.txid, v0.unsigned_tx.txid);
+    assert!(v2.modifiable.unwrap().outputs);
+    assert_eq!(v2.multisig_progress().unwrap().inputs[0].signatures, 1);
+
+    let locked = Psbt::from_bytes(&multisig_psbt_v2(Some(800_000))).unwrap();
+    assert_eq!(locked.inputs[0].required_height_locktime, Some(800_000));
+    assert_eq!(locked.unsigned_tx.locktime, LockTime::Blocks(800_000));
+
+    // v2 must not carry an unsigned transaction
+    let mut mixed = multisig_psbt_v2(None);
+    let tx = hex::decode(MULTISIG_UNSIGNED_TX).unwrap();
+    let mut pair = Vec::new();
+    psbt_pair(&mut pair, &[0x00], &tx);
+    mixed.splice(5..5, pair);
+    assert!(matches!(Psbt::from_bytes(&mixed), Err(ParseError::InvalidPsbt(_))));
+}
+
+#[test]
+fn test_psbt_readiness_and_extraction() {
+    // the multisig spend with its P2WSH witness UTXO, optionally cosigned by key 2
+    let psbt_with = |second_sig: bool| {
+        let mut psbt = b"psbt\xff".to_vec();
+        psbt_pair(&mut psbt, &[0x00], &hex::decode(MULTISIG_UNSIGNED_TX).unwrap());
+        psbt.push(0x00);
+        let mut utxo = 101_000u64.to_le_bytes().to_vec();
+        utxo.push(34);
+        utxo.extend_from_slice(&[0x00, 0x20]);
+        utxo.extend_from_slice(&[0xbb; 32]);
+        psbt_pair(&mut psbt, &[0x01], &utxo);
+        multisig_input_fields(&mut psbt);
+        if second_sig {
+            let mut sig_key = vec![0x02];
+            sig_key.extend(hex::decode(KEY_2).unwrap());
+            psbt_pair(&mut psbt, &sig_key, &[0x30, 0x06, 0x02, 0x01, 0x02, 0x02, 0x01, 0x02, 0x01]);
+        }
+        psbt.push(0x00);
+        psbt.push(0x00);
+        Psbt::from_bytes(&psbt).unwrap()
+    };
+
+    let pending = psbt_with(false).readiness().unwrap();
+    assert!(!pending.complete);
+    assert_eq!(pending.inputs[0].status, InputStatus::Incomplete);
+    assert_eq!(pending.inputs[0].signatures_needed, 1);
+    let missing: Vec<_> = pending.inputs[0].missing.iter().map(|m| m.fingerprint.as_deref()).collect();
+    assert_eq!(missing, vec![Some("f1000001"), Some("f2000001")]);
+    assert_eq!(pending.fee_satoshis, Some(Amount::from_sat(1_000)));
+    assert!(psbt_with(false).extract_tx().is_err());
+
+    let signed = psbt_with(true);
+    let ready = signed.readiness().unwrap();
+    assert!(ready.complete);
+    assert_eq!(ready.inputs[0].status, InputStatus::Finalizable);
+    // the placeholder estimate is never smaller than the real signatures
+    assert!(pending.expected_vsize >= ready.expected_vsize);
+
+    let tx = signed.extract_tx().unwrap();
+    let witness = tx.inputs[0].witness.as_ref().unwrap();
+    assert_eq!(witness.len(), 4);
+    assert!(witness[0].is_empty());
+    assert_eq!(tx.txid, signed.unsigned_tx.txid);
+    assert_eq!(tx.vsize(), ready.expected_vsize);
+    assert_eq!(Transaction::from_hex(&tx.to_hex()).unwrap().wtxid, tx.wtxid);
+}
+
+#[test]
+fn test_psbt_base64_and_errors() {
+    use base64::Engine;
+    let encoded = base64::engine::general_purpose::STANDARD.encode(multisig_psbt());
+    assert!(encoded.starts_with("cHNidP"));
+    assert!(Psbt::parse(&encoded).is_ok());
+    assert!(Psbt::parse("cHNidA==").is_err());
+
+    // a final witness claiming 2^64-1 items fails to parse instead of
+    // reserving them
+    let mut psbt = b"psbt\xff".to_vec();
+    psbt_pair(&mut psbt, &[0x00], &hex::decode(MULTISIG_UNSIGNED_TX).unwrap());
+    psbt.push(0x00);
+    psbt_pair(&mut psbt, &[0x08], &[0xff; 9]);
+    psbt.extend_from_slice(&[0x00, 0x00]);
+    assert!(Psbt::from_bytes(&psbt).is_err());
+}
+
+// ============================================================================
+// Hex Normalization Tests
+// ============================================================================
+
+#[test]
+fn test_hex_codec() {
+    // long enough to cross encode chunks, in either case
+    let bytes: Vec<u8> = (0..=255u8).cycle().take(1000).collect();
+    let hex = encode_hex(&bytes);
+    assert_eq!(hex, hex::encode(&bytes));
+    assert_eq!(decode_hex(&hex).unwrap(), bytes);
+    assert_eq!(decode_hex(&hex.to_uppercase()).unwrap(), bytes);
+    assert_eq!(decode_hex("").unwrap(), Vec::<u8>::new());
+    // same errors as the `hex` crate, with or without `simd-hex`
+    assert_eq!(decode_hex("abc"), Err(hex::FromHexError::OddLength));
+    assert_eq!(decode_hex("00zz"), Err(hex::FromHexError::InvalidHexCharacter { c: 'z', index: 2 }));
+
+    let tx = Transaction::from_hex(TIMELOCK_TX).unwrap();
+    let json = serde_json::to_value(&tx).unwrap();
+    assert_eq!(json["outputs"][0]["script_pubkey"]["hex"], hex::encode(&tx.outputs[0].script_pubkey.bytes));
+    let txid = tx.inputs[0].txid;
+    assert_eq!(txid.to_string().parse::<Txid>().unwrap(), txid);
+}
+
+#[test]
+fn test_hash_backend() {
+    // FIPS 180-2 vectors hold whichever backend is compiled in
+    assert_eq!(hex::encode(sha256(b"abc")), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+    assert_eq!(hex::encode(sha256(&[0x61; 1_000_000])), "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0");
+    assert_eq!(hex::encode(sha256d(b"")), "5df6e0e2761359d30a8275058e299fcc0381534545f55cf43e41983f5d4c9456");
+    let engine = |parts: &[&[u8]]| {
+        let mut engine = Sha256Engine::default();
+        parts.iter().for_each(|part| engine.update(part));
+        engine.finish()
+    };
+    assert_eq!(engine(&[b"a", b"", b"bc"]), sha256(b"abc"));
+    assert_eq!(hex::encode(hash160(&hex::decode(KEY_1).unwrap())), "751e76e8199196d454941c45d1b3a323f1433bd6");
+}
+
+#[test]
+fn test_normalize_hex_dumps() {
+    let expected = "0100000001aabbccddeeff00112233445566778899";
+
+    let xxd = "00000000: 0100 0000 01aa bbcc ddee ff00 1122 3344  ................\n00000010: 5566 7788 99                             Uw...";
+    assert_eq!(normalize_hex(xxd).unwrap(), expected);
+
+    let hexdump = "00000000  01 00 00 00 01 aa bb cc  dd ee ff 00 11 22 33 44  |.............\"3D|\n00000010  55 66 77 88 99                                    |Uw...|\n00000015";
+    assert_eq!(normalize_hex(hexdump).unwrap(), expected);
+
+    assert_eq!(normalize_hex("0x0100000001AABBCCDD, 0xeeff00112233445566778899\n").unwrap(), expected);
+    assert!(normalize_hex("0100zz").is_err());
+    assert!(normalize_hex("010").is_err());
+
+    // a wider first token is data unless the offsets add up
+    assert_eq!(normalize_hex("deadbeef 0001").unwrap(), "deadbeef0001");
+    assert_eq!(normalize_hex("0100000001c997a5e56e104102 00000000").unwrap(), "0100000001c997a5e56e10410200000000");
+    assert_eq!(normalize_hex("00000000 0102\n00000005 0304").unwrap(), "000000000102000000050304");
+    assert_eq!(normalize_hex("00000000 0102\n00000002 0304\n00000004").unwrap(), "01020304");
+    // and a lone token is only the final offset on the last line
+    assert_eq!(normalize_hex("00000000 0102\n0304\n00000004 05").unwrap(), "00000000010203040000000405");
+
+    assert_eq!(format_hex(expected, 8, false).lines().count(), 3);
+    assert_eq!(format_hex("0102", 0, true), "01 02");
+}
+
+// ============================================================================
+// Field Span Tests
+// ============================================================================
+
+#[test]
+fn test_field_spans_cover_transaction() {
+    let hex = key_reuse_tx();
+    let bytes = hex::decode(&hex).unwrap();
+    let (tx, spans) = Transaction::from_hex_with_spans(&hex).unwrap();
+
+    // contiguous, in order, ending at the locktime
+    assert_eq!(spans[0].field, "version");
+    assert!(spans.windows(2).all(|w| w[0].end == w[1].start));
+    assert_eq!(spans.last().unwrap().field, "locktime");
+    assert_eq!(spans.last().unwrap().end, tx.raw_size);
+
+    let span = spans.iter().find(|s| s.field == "outputs[0].script_pubkey").unwrap();
+    assert_eq!(hex::encode(&bytes[span.start..span.end]), hex::encode(&tx.outputs[0].script_pubkey.bytes));
+    let span = spans.iter().find(|s| s.field == "inputs[1].witness[1]").unwrap();
+    assert_eq!(&bytes[span.start..span.end], tx.inputs[1].witness.as_ref().unwrap()[1]);
+}
+
+// ============================================================================
+// Byte Diff Tests
+// ============================================================================
+
+#[test]
+fn test_byte_diff_by_field() {
+    let a = key_reuse_tx();
+    // bump input #0's sequence and grow its signature by one byte
+    let b = a
+        .replacen("0000000000ffffffff", "0000000000fdffffff", 1)
+        .replacen(&format!("0247{}", "30".repeat(71)), &format!("0248{}", "30".repeat(72)), 1);
+
+    let a = hex::decode(a).unwrap();
+    let b = hex::decode(b).unwrap();
+    assert!(byte_diff(&a, &a).unwrap().is_identical());
+
+    let diff = byte_diff(&a, &b).unwrap();
+    assert_eq!(diff.b_size, diff.a_size + 1);
+    let fields: Vec<&str> = diff.changes.iter().map(|c| c.field.as_str()).collect();
+    assert_eq!(fields, vec!["inputs[0].sequence", "inputs[0].witness[0]_len", "inputs[0].witness[0]"]);
+    assert_eq!(diff.changes[0].differing_offsets, vec![0]);
+    assert!(diff.changes[2].differing_offsets.is_empty());
+}
+
+// ============================================================================
+// Canonical Serialization Tests
+// ============================================================================
+
+#[test]
+fn test_canonical_check() {
+    let tx = key_reuse_tx();
+    let report = check_canonical(&hex::decode(&tx).unwrap()).unwrap();
+    assert!(report.canonical, "{:?}", report.issues);
+
+    // non-minimal input count, trailing byte
+    let padded = format!("020000000001fd0200{}00", &tx[14..]);
+    let report = check_canonical(&hex::decode(&padded).unwrap()).unwrap();
+    assert!(!report.canonical);
+    assert_eq!(report.trailing_bytes, 1);
+    assert!(report.issues.iter().any(|i| i.starts_with("input_count encodes 2 in 3 bytes")));
+    assert_eq!(report.canonical_hex.as_deref(), Some(tx.as_str()));
+
+    // witness marker with only empty witnesses
+    let legacy = "01000000010000000000000000000000000000000000000000000000000000000000000000ffffffff0100ffffffff0100f2052a010000000000000000";
+    let flagged = format!("01000000000101{}00{}", &legacy[10..legacy.len() - 8], &legacy[legacy.len() - 8..]);
+    let report = check_canonical(&hex::decode(flagged).unwrap()).unwrap();
+    assert!(report.issues.iter().any(|i| i.contains("witness marker")));
+    assert_eq!(report.canonical_hex.as_deref(), Some(legacy));
 }
 
 // ============================================================================
@@ -67,6 +1798,32 @@ fn test_hash_reading() {
     assert_eq!(hash, "201f1e1d1c1b1a191817161514131211100f0e0d0c0b0a090807060504030201");
 }
 
+#[test]
+fn test_borrowed_parse_matches_owned() {
+    let display = |hash: [u8; 32]| hash.iter().rev().map(|b| format!("{:02x}", b)).collect::<String>();
+    for hex in [key_reuse_tx(), TIMELOCK_TX.to_string(), inscription_tx()] {
+        let bytes = hex::decode(&hex).unwrap();
+        let borrowed = TransactionRef::from_bytes_strict(&bytes).unwrap();
+        let owned = borrowed.to_transaction().unwrap();
+        assert_eq!(display(borrowed.txid()), owned.txid);
+        assert_eq!(display(borrowed.wtxid()), owned.wtxid);
+        assert_eq!((borrowed.size(), borrowed.weight()), (owned.raw_size, owned.weight));
+        for (input, owned_input) in borrowed.inputs.iter().zip(&owned.inputs) {
+            assert_eq!(input.prev_txid, owned_input.txid.as_bytes());
+            assert_eq!(input.script_sig, owned_input.script_sig.bytes);
+            assert_eq!(input.witness, owned_input.witness.clone().unwrap_or_default());
+        }
+        assert_eq!(borrowed.outputs[0].script_pubkey, owned.outputs[0].script_pubkey.bytes);
+    }
+
+    let padded = hex::decode(format!("{}00", key_reuse_tx())).unwrap();
+    assert!(matches!(TransactionRef::from_bytes_strict(&padded), Err(ParseError::TrailingData(1))));
+
+    let block = hex::decode(format!("{}01{}", GENESIS_HEADER, genesis_job().coinbase_hex())).unwrap();
+    let block_ref = BlockRef::from_bytes(&block).unwrap();
+    assert_eq!(display(block_ref.transactions[0].txid()), Block::from_bytes(&block).unwrap().transactions[0].txid);
+}
+
 // ============================================================================
 // Script Type Detection Tests
 // ============================================================================
@@ -124,3 +1881,685 @@ fn test_sha256d() {
     let hash = sha256d(data);
     assert_eq!(hash.len(), 32);
 }
+
+#[test]
+fn test_key_addresses() {
+    let keys = Transaction::from_hex(&key_reuse_tx()).unwrap().key_addresses();
+    assert_eq!(keys.len(), 1);
+    assert_eq!(keys[0].inputs, vec![0, 1]);
+    let forms: Vec<(&str, &str)> = keys[0].addresses.iter().map(|a| (a.address_type.as_str(), a.mainnet.as_str())).collect();
+    assert_eq!(forms, vec![
+        ("P2PKH", "1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH"),
+        ("P2WPKH", "bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4"),
+        ("P2SH-P2WPKH", "3JvL6Ymt8MVWiCNHC7oWU6nLeHNJKLZGLN"),
+        ("P2TR", "bc1pmfr3p9j00pfxjh0zmgp99y8zftmd3s5pmedqhyptwy6lm87hf5sspknck9"),
+    ]);
+
+    // BIP86 test vector; the odd-Y encoding of the same x tweaks identically
+    for prefix in ["02", "03"] {
+        let key = hex::decode(format!("{}cc8a4bc64d897bddc5fbc2f670f7a8ba0b386779106cf1223c6fc5d7cd6fc115", prefix)).unwrap();
+        let p2tr = pubkey_addresses(&key).pop().unwrap();
+        assert_eq!(p2tr.mainnet, "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr");
+    }
+
+    let uncompressed = hex::decode(format!("04{}483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8", &KEY_1[2..])).unwrap();
+    let addresses = pubkey_addresses(&uncompressed);
+    assert_eq!(addresses[0].mainnet, "1EHNa6Q4Jz2uvNExL497mE43ikXhwF6kZm");
+    assert_eq!(addresses[1].mainnet, "1BgGZ9tcN4rm9KBzDn7KprQz87SZ26SAMH");
+    // x beyond the field size
+    let mut off_curve = vec![0xff; 33];
+    off_curve[0] = 0x02;
+    assert!(pubkey_addresses(&off_curve).is_empty());
+}
+
+// ============================================================================
+// Block Tests
+// ============================================================================
+
+const GENESIS_HEADER: &str = "0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff001d1dac2b7c";
+
+#[test]
+fn test_block_from_bytes() {
+    let coinbase = genesis_job().coinbase_hex();
+    let block = Block::from_hex(&format!("{}01{}", GENESIS_HEADER, coinbase)).unwrap();
+
+    assert_eq!(block.header.hash, "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f");
+    assert_eq!(block.header.prev_block_hash, "0".repeat(64));
+    assert_eq!(block.header.time, 1231006505);
+    assert_eq!(block.header.bits, 0x1d00ffff);
+    assert_eq!(block.header.nonce, 2083236893);
+    assert_eq!(block.transactions.len(), 1);
+    assert_eq!(block.size, 285);
+    assert_eq!(block.weight, 285 * 4);
+    assert_eq!(block.compute_merkle_root().unwrap(), block.header.merkle_root);
+    assert!(block.merkle_root_valid);
+    let proof = block.merkle_proof(&block.transactions[0].txid).unwrap().unwrap();
+    assert!(proof.siblings.is_empty() && proof.verify());
+
+    assert!(matches!(BlockHeader::from_hex(&GENESIS_HEADER[..150]), Err(ParseError::InvalidBlock(_))));
+    assert!(matches!(Block::from_hex(&format!("{}00", GENESIS_HEADER)), Err(ParseError::InvalidBlock(_))));
+    assert!(matches!(Block::from_hex(&format!("{}01{}00", GENESIS_HEADER, coinbase)), Err(ParseError::TrailingData(1))));
+}
+
+#[test]
+fn test_block_witness_commitment() {
+    let commitment = "1826fec65821cd6334f2c0a325ee9bc87c04ba0adaceebb18494e75dc09f74de";
+    // segwit coinbase: witness reserved value of zeros, commitment in the second output
+    let coinbase = |commitment: &str| format!(
+        "02000000000101{}ffffffff0151ffffffff0200f2052a010000000151{}266a24aa21a9ed{}0120{}00000000",
+        "00".repeat(32), "00".repeat(8), commitment, "00".repeat(32)
+    );
+    let block = Block::from_hex(&format!("{}02{}{}", GENESIS_HEADER, coinbase(commitment), TIMELOCK_TX)).unwrap();
+    assert_eq!(block.witness_commitment.as_deref(), Some(commitment));
+    assert_eq!(block.compute_witness_commitment().unwrap().as_deref(), Some(commitment));
+    assert_eq!(block.witness_commitment_valid, Some(true));
+
+    let tampered = Block::from_hex(&format!("{}02{}{}", GENESIS_HEADER, coinbase(&"00".repeat(32)), TIMELOCK_TX)).unwrap();
+    assert_eq!(tampered.witness_commitment_valid, Some(false));
+
+    // witness data without any commitment is invalid; a legacy block has nothing to check
+    let uncommitted = Block::from_hex(&format!("{}02{}{}", GENESIS_HEADER, genesis_job().coinbase_hex(), TIMELOCK_TX)).unwrap();
+    assert_eq!(uncommitted.witness_commitment, None);
+    assert_eq!(uncommitted.witness_commitment_valid, Some(false));
+    let legacy = Block::from_hex(&format!("{}01{}", GENESIS_HEADER, genesis_job().coinbase_hex())).unwrap();
+    assert_eq!(legacy.witness_commitment_valid, None);
+}
+
+#[test]
+fn test_block_file_reader() {
+    let block = hex::decode(format!("{}01{}", GENESIS_HEADER, genesis_job().coinbase_hex())).unwrap();
+    let mut file = Vec::new();
+    for _ in 0..2 {
+        file.extend_from_slice(&MAINNET_MAGIC);
+        file.extend_from_slice(&(block.len() as u32).to_le_bytes());
+        file.extend_from_slice(&block);
+    }
+    // Core preallocates files, so the data is followed by zeros
+    file.extend_from_slice(&[0; 16]);
+
+    let blocks: Vec<Block> = BlockFileReader::new(Cursor::new(&file), MAINNET_MAGIC).collect::<Result<_, _>>().unwrap();
+    assert_eq!(blocks.len(), 2);
+    assert_eq!(blocks[1].header.hash, "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f");
+
+    let key = [1, 2, 3, 4, 5, 6, 7, 8];
+    let obfuscated: Vec<u8> = file.iter().enumerate().map(|(i, b)| b ^ key[i % 8]).collect();
+    let mut reader = BlockFileReader::new(Cursor::new(&obfuscated), MAINNET_MAGIC).with_xor_key(key);
+    assert_eq!(reader.by_ref().count(), 2);
+    assert_eq!(reader.position() as usize, 2 * (8 + block.len()) + 4);
+
+    let mut reader = BlockFileReader::new(Cursor::new(&file), TESTNET_MAGIC);
+    assert!(matches!(reader.next(), Some(Err(ParseError::InvalidBlock(_)))));
+    assert!(reader.next().is_none());
+    let truncated = &file[..8 + block.len() - 1];
+    let mut reader = BlockFileReader::new(Cursor::new(truncated), MAINNET_MAGIC);
+    assert!(matches!(reader.next(), Some(Err(ParseError::UnexpectedEof { .. }))));
+}
+
+#[test]
+fn test_block_files_checkpoint() {
+    let block = hex::decode(format!("{}01{}", GENESIS_HEADER, genesis_job().coinbase_hex())).unwrap();
+    let record = [&MAINNET_MAGIC[..], &(block.len() as u32).to_le_bytes(), &block].concat();
+    let dir = std::env::temp_dir().join(format!("btc-tx-checkpoint-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("blk00000.dat"), record.repeat(2)).unwrap();
+    std::fs::write(dir.join("blk00001.dat"), record.repeat(3)).unwrap();
+
+    let mut files = BlockFiles::open(&dir, MAINNET_MAGIC).unwrap();
+    assert_eq!(files.checkpoint(), None);
+    assert_eq!(files.by_ref().take(3).count(), 3);
+    let checkpoint = files.checkpoint().unwrap();
+    assert_eq!(checkpoint, BlockFileCheckpoint {
+        file: "blk00001.dat".to_string(),
+        offset: record.len() as u64,
+        blocks: 3,
+        last_height: None,
+    });
+
+    let mut resumed = BlockFiles::open(&dir, MAINNET_MAGIC).unwrap().resume(&checkpoint).unwrap();
+    assert_eq!(resumed.by_ref().filter(|block| block.is_ok()).count(), 2);
+    assert_eq!(resumed.checkpoint().unwrap().blocks, 5);
+
+    let missing = BlockFileCheckpoint { file: "blk00009.dat".to_string(), ..checkpoint };
+    assert!(BlockFiles::open(&dir, MAINNET_MAGIC).unwrap().resume(&missing).is_err());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn test_mapped_block_files() {
+    use crate::MappedFile;
+
+    let block = hex::decode(format!("{}01{}", GENESIS_HEADER, genesis_job().coinbase_hex())).unwrap();
+    let record = [&MAINNET_MAGIC[..], &(block.len() as u32).to_le_bytes(), &block].concat();
+    let dir = std::env::temp_dir().join(format!("btc-tx-mmap-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("blk00000.dat"), [record.repeat(2), vec![0; 16]].concat()).unwrap();
+    std::fs::write(dir.join("blk00001.dat"), record.repeat(3)).unwrap();
+
+    let mut mapped = BlockFiles::open_mapped(&dir, MAINNET_MAGIC).unwrap();
+    let hashes: Vec<String> = mapped.by_ref().map(|block| block.unwrap().header.hash).collect();
+    assert_eq!(hashes.len(), 5);
+    assert_eq!(hashes[4], "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f");
+    assert_eq!(mapped.checkpoint().unwrap().offset, 3 * record.len() as u64);
+
+    // plain records are parsed in place, not copied out of the mapping
+    let file = MappedFile::open(&dir.join("blk00001.dat")).unwrap();
+    let base = file.bytes().as_ptr();
+    let mut reader = crate::mmap::MappedBlockReader::new(file, MAINNET_MAGIC, [0; 8]);
+    let borrowed = reader.next_block().unwrap().unwrap();
+    assert_eq!(borrowed.raw.as_ptr(), base.wrapping_add(8));
+    assert_eq!(borrowed.to_block().unwrap().header.hash, hashes[4]);
+
+    // obfuscated files are decoded, and checkpoints resume either way
+    let key = [1, 2, 3, 4, 5, 6, 7, 8];
+    let obfuscate = |data: Vec<u8>| -> Vec<u8> { data.iter().enumerate().map(|(i, b)| b ^ key[i % 8]).collect() };
+    std::fs::write(dir.join("blk00000.dat"), obfuscate(record.repeat(2))).unwrap();
+    std::fs::write(dir.join("blk00001.dat"), obfuscate(record.repeat(3))).unwrap();
+    std::fs::write(dir.join("xor.dat"), key).unwrap();
+    let mut streamed = BlockFiles::open(&dir, MAINNET_MAGIC).unwrap();
+    assert_eq!(streamed.by_ref().take(3).count(), 3);
+    let resumed = BlockFiles::open_mapped(&dir, MAINNET_MAGIC).unwrap().resume(&streamed.checkpoint().unwrap()).unwrap();
+    assert_eq!(resumed.filter(|block| block.is_ok()).count(), 2);
+
+    std::fs::write(dir.join("batch.txt"), format!("{}\n", TIMELOCK_TX)).unwrap();
+    assert_eq!(MappedFile::open(&dir.join("batch.txt")).unwrap().text().unwrap().trim(), TIMELOCK_TX);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_block_height() {
+    let genesis = Block::from_hex(&format!("{}01{}", GENESIS_HEADER, genesis_job().coinbase_hex())).unwrap();
+    assert_eq!(genesis.height(), None);
+
+    // version 2 with a BIP34 push of 900,000, then with OP_1 (height 1)
+    let coinbase = |script_sig: &str| format!(
+        "0100000001{}ffffffff{:02x}{}ffffffff0100f2052a010000000151{}",
+        "00".repeat(32), script_sig.len() / 2, script_sig, "00".repeat(4)
+    );
+    let header = format!("02{}", &GENESIS_HEADER[2..]);
+    assert_eq!(Block::from_hex(&format!("{}01{}", header, coinbase("03a0bb0d00"))).unwrap().height(), Some(900_000));
+    assert_eq!(Block::from_hex(&format!("{}01{}", header, coinbase("5100"))).unwrap().height(), Some(1));
+}
+
+#[test]
+fn test_coinbase_info() {
+    let coinbase = |script_sig: &str| Transaction::from_hex(&format!(
+        "0100000001{}ffffffff{:02x}{}ffffffff0100f2052a010000000151{}",
+        "00".repeat(32), script_sig.len() / 2, script_sig, "00".repeat(4)
+    )).unwrap().inputs.remove(0).coinbase.unwrap();
+
+    // height 840,000, a pushed tag, then an 8-byte extranonce
+    let info = coinbase(&format!("0340d10c16{}08{}", "2f5669614254432f4d696e656420627920746573742f", "a1b2c3d4e5f60718"));
+    assert_eq!(info.height, Some(840_000));
+    assert_eq!(info.pool_tag.as_deref(), Some("/ViaBTC/Mined by test/"));
+    assert_eq!(info.extranonce_size, Some(8));
+
+    // a tag written as raw bytes doesn't parse as pushes, so it is found
+    // in the bytes as they are
+    let info = coinbase(&format!("0340d10c{}", hex::encode("Mined by AntPool")));
+    assert_eq!(info.pool_tag.as_deref(), Some("Mined by AntPool"));
+    assert_eq!(info.extranonce_size, None);
+
+    // regtest's OP_n heights; the genesis nBits push is no height
+    assert_eq!(coinbase("5100").height, Some(1));
+    let genesis = Transaction::from_hex(&genesis_job().coinbase_hex()).unwrap();
+    let info = genesis.inputs[0].coinbase.as_ref().unwrap();
+    assert_eq!(info.height, None);
+    assert!(info.pool_tag.as_deref().unwrap().starts_with("The Times 03/Jan/2009"));
+
+    let spend = Transaction::from_hex(TIMELOCK_TX).unwrap();
+    assert!(spend.inputs.iter().all(|input| input.coinbase.is_none()));
+}
+
+#[test]
+fn test_script_template() {
+    let p2pkh: ScriptTemplate = "OP_DUP OP_HASH160 <hash160> OP_EQUALVERIFY OP_CHECKSIG".parse().unwrap();
+    assert!(p2pkh.matches(&hex::decode(format!("76a914{}88ac", "11".repeat(20))).unwrap()));
+    assert!(!p2pkh.matches(&hex::decode(format!("76a914{}88", "11".repeat(20))).unwrap()));
+    assert!(!p2pkh.matches(&hex::decode(format!("0014{}", "11".repeat(20))).unwrap()));
+
+    let multisig: ScriptTemplate = "<num> <pubkey> ... <num> OP_CHECKMULTISIG".parse().unwrap();
+    assert!(multisig.matches(&hex::decode(format!("5221{}21{}52ae", KEY_1, KEY_2)).unwrap()));
+    assert!(multisig.matches(&hex::decode(format!("5121{}51ae", KEY_1)).unwrap()));
+    assert!(!multisig.matches(&hex::decode(format!("5120{}51ae", "11".repeat(32))).unwrap()));
+
+    let carrier: ScriptTemplate = "OP_RETURN ...".parse().unwrap();
+    assert!(carrier.matches(&[0x6a]));
+    assert!(carrier.matches(&hex::decode("6a0401020304").unwrap()));
+    let exact: ScriptTemplate = "OP_RETURN 01020304".parse().unwrap();
+    assert!(exact.matches(&hex::decode("6a0401020304").unwrap()));
+    assert!(!exact.matches(&hex::decode("6a0401020305").unwrap()));
+    assert_eq!(exact.to_string(), "OP_RETURN 01020304");
+    assert_eq!("OP_TRUE <xonly>".parse::<ScriptTemplate>().unwrap().to_string(), "OP_1 <xonly>");
+
+    assert!("OP_NOPE".parse::<ScriptTemplate>().is_err());
+    assert!("<key>".parse::<ScriptTemplate>().is_err());
+    assert!("OP_PUSHBYTES_20".parse::<ScriptTemplate>().is_err());
+    assert!("".parse::<ScriptTemplate>().is_err());
+}
+
+// Reader returning one byte per read, like a slow pipe
+struct Trickle<'a>(&'a [u8]);
+
+impl Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match (self.0.split_first(), buf.first_mut()) {
+            (Some((byte, rest)), Some(slot)) => {
+                *slot = *byte;
+                self.0 = rest;
+                Ok(1)
+            }
+            _ => Ok(0),
+        }
+    }
+}
+
+#[test]
+fn test_stream_parser() {
+    let hexes = [key_reuse_tx(), TIMELOCK_TX.to_string(), inscription_tx()];
+    let bytes = hex::decode(hexes.concat()).unwrap();
+    let txs: Vec<Transaction> = StreamParser::new(Trickle(&bytes)).collect::<Result<_, _>>().unwrap();
+    let txids: Vec<String> = hexes.iter().map(|h| Transaction::from_hex(h).unwrap().txid).collect();
+    assert_eq!(txs.iter().map(|tx| tx.txid.clone()).collect::<Vec<_>>(), txids);
+
+    let mut stream = StreamParser::new(Cursor::new(&bytes[..bytes.len() - 1]));
+    assert!(stream.next().unwrap().is_ok());
+    assert!(stream.next().unwrap().is_ok());
+    assert!(matches!(stream.next(), Some(Err(ParseError::UnexpectedEof { .. }))));
+    assert!(stream.next().is_none());
+
+    let block = hex::decode(format!("{}01{}", GENESIS_HEADER, genesis_job().coinbase_hex())).unwrap();
+    let mut stream = StreamParser::new(Cursor::new(&block));
+    let (header, count) = stream.read_block_header().unwrap().unwrap();
+    assert_eq!((header.hash.as_str(), count), ("000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f", 1));
+    assert!(stream.next_transaction().unwrap().unwrap().inputs[0].is_coinbase);
+    assert_eq!(stream.position() as usize, block.len());
+    assert!(stream.next_transaction().unwrap().is_none());
+}
+
+fn wire_message(magic: [u8; 4], command: &str, payload: &[u8]) -> Vec<u8> {
+    let mut message = magic.to_vec();
+    let mut name = [0u8; 12];
+    name[..command.len()].copy_from_slice(command.as_bytes());
+    message.extend_from_slice(&name);
+    message.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    message.extend_from_slice(&sha256d(payload)[..4]);
+    message.extend_from_slice(payload);
+    message
+}
+
+#[test]
+fn test_p2p_messages() {
+    let tx = hex::decode(TIMELOCK_TX).unwrap();
+    let block = hex::decode(format!("{}01{}", GENESIS_HEADER, genesis_job().coinbase_hex())).unwrap();
+    let mut stream = wire_message(MAINNET_MAGIC, "tx", &tx);
+    stream.extend(wire_message(MAINNET_MAGIC, "ping", &[7; 8]));
+    stream.extend(wire_message(TESTNET_MAGIC, "block", &block));
+
+    let messages = parse_messages(&stream).unwrap();
+    let commands: Vec<&str> = messages.iter().map(|m| m.command.as_str()).collect();
+    assert_eq!(commands, vec!["tx", "ping", "block"]);
+    assert!(messages.iter().all(|m| m.checksum_valid));
+    assert_eq!(messages[0].network.as_deref(), Some("mainnet"));
+    assert_eq!(messages[2].network.as_deref(), Some("testnet"));
+
+    match messages[0].decode().unwrap() {
+        WirePayload::Tx(decoded) => assert_eq!(decoded.txid, Transaction::from_bytes(&tx).unwrap().txid),
+        other => panic!("expected a transaction, got {:?}", other),
+    }
+    assert!(matches!(messages[1].decode().unwrap(), WirePayload::Unsupported));
+    assert!(matches!(messages[2].decode().unwrap(), WirePayload::Block(b) if b.merkle_root_valid));
+
+    // a corrupted payload still decodes but fails the checksum
+    let mut corrupted = wire_message(MAINNET_MAGIC, "tx", &tx);
+    *corrupted.last_mut().unwrap() ^= 1;
+    assert!(!P2pMessage::from_bytes(&corrupted).unwrap().checksum_valid);
+    assert!(matches!(P2pMessage::from_bytes(&stream), Err(ParseError::TrailingData(_))));
+    assert!(matches!(P2pMessage::from_bytes(&stream[..30]), Err(ParseError::UnexpectedEof { .. })));
+}
+
+// One input paying `count` P2WPKH outputs, the first `repeated` to the same key hash
+fn batch_tx(count: usize, repeated: usize) -> Transaction {
+    let outputs: String = (0..count)
+        .map(|i| format!("1027000000000000160014{}", hex::encode([(i.max(repeated) % 256) as u8; 20])))
+        .collect();
+    Transaction::from_hex(&format!("0100000001{}0000000000ffffffff{:02x}{}00000000", "aa".repeat(32), count, outputs)).unwrap()
+}
+
+#[test]
+fn test_batched_withdrawal() {
+    let batch = batch_tx(25, 0).batch_payout().unwrap();
+    assert_eq!((batch.inputs, batch.payments, batch.distinct_recipients), (1, 25, 25));
+    assert_eq!(batch.dominant_type, ScriptType::P2WPKH);
+    assert!(batch.uniform());
+
+    let mut registry = AnalyzerRegistry::new();
+    registry.register(BatchingAnalyzer);
+    let findings = registry.run(&batch_tx(25, 0));
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].kind, "batched_withdrawal");
+    assert_eq!(findings[0].confidence, Some(0.8));
+    assert!(batch_tx(25, 0).analyze().iter().any(|f| f.kind == "batched_withdrawal"));
+
+    // too few payments, or too many paying the same script
+    assert!(batch_tx(MIN_BATCH_OUTPUTS - 1, 0).batch_payout().is_none());
+    assert!(batch_tx(25, 4).batch_payout().is_none());
+    assert!(Transaction::from_hex(TIMELOCK_TX).unwrap().batch_payout().is_none());
+}
+
+// `count` P2WPKH spends into one P2WPKH output
+fn consolidation_tx(count: usize) -> Transaction {
+    let inputs: String = (0..count).map(|i| format!("{}{:02x}00000000ffffffff", "aa".repeat(32), i)).collect();
+    let witness = format!("02011121{}", KEY_1).repeat(count);
+    Transaction::from_hex(&format!(
+        "020000000001{:02x}{}01a086010000000000160014{}{}00000000",
+        count, inputs, "11".repeat(20), witness
+    )).unwrap()
+}
+
+#[test]
+fn test_consolidation_and_shape() {
+    let tx = consolidation_tx(6);
+    assert_eq!(tx.shape().category, ShapeCategory::FanIn);
+    assert_eq!(tx.shape().to_string(), "fan-in (6 inputs, 1 output)");
+    let consolidation = tx.consolidation().unwrap();
+    assert_eq!(consolidation.dominant_type, Some(ScriptType::P2WPKH));
+    assert_eq!(consolidation.dominant_share, 1.0);
+    assert!(consolidation.same_type_output);
+
+    let mut registry = AnalyzerRegistry::new();
+    registry.register(ConsolidationAnalyzer);
+    let findings = registry.run(&tx);
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].kind, "consolidation");
+    assert_eq!(findings[0].confidence, Some(0.9));
+
+    assert!(consolidation_tx(MIN_CONSOLIDATION_INPUTS - 1).consolidation().is_none());
+    assert_eq!(consolidation_tx(1).shape().category, ShapeCategory::OneToOne);
+    assert_eq!(consolidation_tx(2).shape().category, ShapeCategory::Simple);
+    assert_eq!(batch_tx(25, 0).shape().category, ShapeCategory::FanOut);
+    let coinbase = Transaction::from_hex(&genesis_job().coinbase_hex()).unwrap();
+    assert_eq!(coinbase.shape().category, ShapeCategory::Coinbase);
+}
+
+// LEB128, as OpenTimestamps proofs encode integers
+fn leb128(mut n: u64) -> Vec<u8> {
+    let mut out = Vec::new();
+    loop {
+        let byte = (n & 0x7f) as u8;
+        n >>= 7;
+        if n == 0 {
+            out.push(byte);
+            return out;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+#[test]
+fn test_opentimestamps_proof() {
+    use sha2::{Digest, Sha256};
+
+    let digest = Sha256::digest(b"hello world\n").to_vec();
+    let nonce = [0x42u8; 16];
+    let commitment = Sha256::digest([digest.as_slice(), &nonce].concat()).to_vec();
+    let tx = Transaction::from_hex(&format!(
+        "0100000001{}0000000000ffffffff02a086010000000000160014{}0000000000000000226a20{}00000000",
+        "aa".repeat(32), "11".repeat(20), hex::encode(&commitment)
+    )).unwrap();
+    assert_eq!(tx.timestamp_commitments(), [1]);
+
+    // digest -> append nonce -> sha256 = commitment; then the transaction
+    // around it, sha256d to the txid, attested as a one-transaction block
+    let bytes = tx.to_bytes_without_witness();
+    let at = bytes.windows(32).position(|w| w == commitment.as_slice()).unwrap();
+    let varbytes = |data: &[u8]| [leb128(data.len() as u64), data.to_vec()].concat();
+    let pending = [&[0x00][..], &[0x83, 0xdf, 0xe3, 0x0d, 0x2e, 0xf9, 0x0c, 0x8e], &varbytes(&varbytes(b"https://a.pool.opentimestamps.org"))].concat();
+    let bitcoin = [&[0x00][..], &[0x05, 0x88, 0x96, 0x0d, 0x73, 0xd7, 0x19, 0x01], &varbytes(&leb128(840_000))].concat();
+    let proof = [
+        &b"\x00OpenTimestamps\x00\x00Proof\x00\xbf\x89\xe2\xe8\x84\xe8\x92\x94\x01\x08"[..], &digest,
+        &[0xf0], &varbytes(&nonce), &[0x08],
+        &[0xff], &pending,
+        &[0xf1], &varbytes(&bytes[..at]), &[0xf0], &varbytes(&bytes[at + 32..]),
+        &[0x08, 0x08], &bitcoin,
+    ].concat();
+
+    let proof = OtsProof::from_bytes(&proof).unwrap();
+    assert_eq!(proof.hash, "sha256");
+    assert_eq!(proof.attestations(), [
+        OtsAttestation::Pending { uri: "https://a.pool.opentimestamps.org".to_string() },
+        OtsAttestation::Bitcoin { height: 840_000 },
+    ]);
+    let anchor = proof.anchor_in(&tx).unwrap();
+    assert_eq!(anchor.output, 1);
+    assert_eq!(anchor.commitment, hex::encode(&commitment));
+    assert_eq!(anchor.digest, hex::encode(&digest));
+    assert_eq!(anchor.block_height, Some(840_000));
+    assert_eq!(anchor.merkle_root.as_deref(), Some(tx.txid.as_str()));
+    assert!(proof.anchor_in(&consolidation_tx(1)).is_none());
+
+    let mut registry = AnalyzerRegistry::new();
+    registry.register(TimestampAnalyzer);
+    let findings = registry.run(&tx);
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].kind, "timestamp_commitment");
+    assert_eq!(findings[0].confidence, Some(0.55));
+
+    assert!(matches!(OtsProof::from_bytes(b"not a proof"), Err(ParseError::InvalidTimestampProof(_))));
+}
+
+#[test]
+fn test_coin_age() {
+    let mut tx = Transaction::from_hex(TIMELOCK_TX).unwrap();
+    tx.inputs[0].value = Some(Amount::from_sat(200_000_000));
+
+    let age: CoinAge = tx.coin_age(850_000, &[Some(849_280), Some(850_100)]);
+    assert_eq!(age.inputs[0].age_blocks, Some(720));
+    assert_eq!(age.inputs[0].age_days, Some(5.0));
+    assert_eq!(age.inputs[0].value_days_destroyed, Some(10.0));
+    // confirmed "after" the spend height counts as fresh
+    assert_eq!(age.inputs[1].age_blocks, Some(0));
+    assert_eq!(age.max_age_days, Some(5.0));
+    // input 1's value is unknown
+    assert_eq!(age.value_days_destroyed, None);
+
+    tx.inputs[1].value = Some(Amount::from_sat(1_000));
+    assert_eq!(tx.coin_age(850_000, &[Some(849_280), Some(850_100)]).value_days_destroyed, Some(10.0));
+    // a missing height leaves the total open
+    let partial = tx.coin_age(850_000, &[Some(849_280)]);
+    assert_eq!(partial.inputs[1].age_blocks, None);
+    assert_eq!(partial.value_days_destroyed, None);
+}
+
+#[test]
+fn test_network_parameters() {
+    assert_eq!(Network::from_magic(TESTNET4_MAGIC), Some(Network::Testnet4));
+    assert_eq!(Network::from_magic([0; 4]), None);
+    for network in Network::ALL {
+        assert_eq!(network.to_string().parse::<Network>(), Ok(network));
+    }
+    assert!("testnet5".parse::<Network>().is_err());
+    assert_eq!(Network::Testnet4.bech32_hrp(), "tb");
+    assert_eq!(Network::Regtest.bech32_hrp(), "bcrt");
+
+    let genesis = BlockHeader::from_hex("0100000000000000000000000000000000000000000000000000000000000000000000004e7b2b9128fe0291db0693af2ae418b767e657cd407e80cb1434221eaea7a07a046f3566ffff001dbb0c7817").unwrap();
+    assert_eq!(genesis.hash, Network::Testnet4.genesis_hash());
+    assert_eq!(genesis.genesis_of(), Some(Network::Testnet4));
+    assert_eq!(BlockHeader::from_hex(GENESIS_HEADER).unwrap().genesis_of(), Some(Network::Mainnet));
+}
+
+#[test]
+fn test_siphash24_vectors() {
+    // reference vectors: key 00..0f, messages 00..(n-1)
+    let (k0, k1) = (0x0706050403020100, 0x0f0e0d0c0b0a0908);
+    let message: Vec<u8> = (0..15).collect();
+    assert_eq!(siphash24(k0, k1, &[]), 0x726fdb47dd0e0e31);
+    assert_eq!(siphash24(k0, k1, &message), 0xa129ca6149be45e5);
+}
+
+#[test]
+fn test_compact_block() {
+    let header = BlockHeader::from_hex(GENESIS_HEADER).unwrap();
+    assert_eq!(hex::encode(header.to_bytes().unwrap()), GENESIS_HEADER);
+    let coinbase = genesis_job().coinbase_hex();
+    let pool = [Transaction::from_hex(TIMELOCK_TX).unwrap(), Transaction::from_hex(MULTISIG_UNSIGNED_TX).unwrap()];
+
+    // the coinbase prefilled at index 0, then both pool transactions by short id
+    let mut template = CompactBlock { header, nonce: 42, short_ids: Vec::new(), prefilled: Vec::new() };
+    template.short_ids = pool.iter().map(|tx| template.short_id(&tx.wtxid).unwrap()).collect();
+    let short_ids: String = template.short_ids.iter().map(|id| hex::encode(&id.to_le_bytes()[..6])).collect();
+    let payload = format!("{}{}02{}0100{}", GENESIS_HEADER, hex::encode(42u64.to_le_bytes()), short_ids, coinbase);
+
+    let block = CompactBlock::from_hex(&payload).unwrap();
+    assert_eq!(block.short_ids, template.short_ids);
+    assert_eq!(block.transaction_count(), 3);
+    assert_eq!(block.prefilled[0].index, 0);
+
+    let full = block.reconstruct(&pool, false).unwrap();
+    assert!(full.missing.is_empty() && full.collisions.is_empty());
+    assert_eq!(full.transactions[2].as_ref().unwrap().txid, pool[1].txid);
+    let partial = block.reconstruct(&pool[..1], false).unwrap();
+    assert_eq!(partial.missing, vec![2]);
+    assert!(matches!(CompactBlock::from_hex(&format!("{}00", payload)), Err(ParseError::TrailingData(1))));
+}
+
+#[test]
+fn test_merkle_root_and_proofs() {
+    // block 100,000
+    let txids: Vec<String> = [
+        "8c14f0db3df150123e6f3dbbf30f8b955a8249b62ac1d1ff16284aefa3d06d87",
+        "fff2525b8931402dd09222c50775608f75787bd2b87e56995a7bdd30f79702c4",
+        "6359f0868171b1d194cbee1af2f16ea598ae8fad666d9b012c8ed2b79a236ec4",
+        "e9a66845e05d5abc0ad04ec80f774a7e585c6e8db975962d069a522137b80c1d",
+    ].iter().map(|s| s.to_string()).collect();
+    let root = "f3e94742aca4b5ef85488dc37c06c3282295ffec960994b2c0d5ac2a25a95766";
+    assert_eq!(merkle_root(&txids).unwrap().as_deref(), Some(root));
+
+    for index in 0..txids.len() {
+        let proof = merkle_proof(&txids, index).unwrap().unwrap();
+        assert_eq!(proof.merkle_root, root);
+        assert_eq!(proof.siblings.len(), 2);
+        assert!(proof.verify());
+    }
+
+    // odd levels duplicate their last hash
+    let odd = &txids[..3];
+    let proof = merkle_proof(odd, 2).unwrap().unwrap();
+    assert_eq!(proof.siblings[0], odd[2]);
+    assert!(proof.verify());
+
+    let mut tampered = merkle_proof(&txids, 1).unwrap().unwrap();
+    tampered.index = 0;
+    assert!(!tampered.verify());
+    assert!(merkle_proof(&txids, 4).unwrap().is_none());
+}
+
+#[test]
+fn test_header_target_and_difficulty() {
+    let genesis = BlockHeader::from_hex(GENESIS_HEADER).unwrap();
+    assert_eq!(genesis.target, format!("00000000ffff{}", "0".repeat(52)));
+    assert_eq!(genesis.difficulty, 1.0);
+    assert!(genesis.meets_target());
+
+    // block 100,000
+    assert!((bits_to_difficulty(0x1b04864c) - 14484.162361).abs() < 1e-6);
+    assert_eq!(hex::encode(bits_to_target(0x1b04864c).unwrap()), format!("{}04864c{}", "0".repeat(10), "0".repeat(48)));
+    // regtest, and exponents below 3
+    assert_eq!(hex::encode(bits_to_target(0x207fffff).unwrap()), format!("7fffff{}", "0".repeat(58)));
+    assert_eq!(hex::encode(bits_to_target(0x02008000).unwrap()), format!("{}80", "0".repeat(62)));
+
+    assert!(bits_to_target(0x04923456).is_err());
+    assert!(bits_to_target(0x23000001).is_err());
+}
+
+// ============================================================================
+// Inscription Tests
+// ============================================================================
+
+// Taproot script-path spend revealing a text inscription in two body pushes
+fn inscription_tx() -> String {
+    let content_type = b"text/plain;charset=utf-8";
+    // <key> OP_CHECKSIG OP_FALSE OP_IF "ord" 1 <content type> OP_0 "Hello" " world" OP_ENDIF
+    let tapscript = format!(
+        "20{}ac0063036f72640101{:02x}{}000548656c6c6f0620776f726c6468",
+        "11".repeat(32), content_type.len(), hex::encode(content_type)
+    );
+    let witness = format!(
+        "0340{}{:02x}{}21c0{}",
+        "22".repeat(64), tapscript.len() / 2, tapscript, "33".repeat(32)
+    );
+    format!(
+        "02000000000101{}0000000000fdffffff01220200000000000022512044{}{}00000000",
+        "aa".repeat(32), "44".repeat(31), witness
+    )
+}
+
+#[test]
+fn test_inscription_envelope() {
+    let tx = Transaction::from_hex(&inscription_tx()).unwrap();
+    let inscriptions = tx.inscriptions();
+    assert_eq!(inscriptions.len(), 1);
+
+    let inscription = &inscriptions[0];
+    assert_eq!(inscription.id, format!("{}i0", tx.txid));
+    assert_eq!(inscription.input, 0);
+    assert_eq!(inscription.content_type.as_deref(), Some("text/plain;charset=utf-8"));
+    assert_eq!(inscription.body, b"Hello world");
+    assert_eq!(inscription.file_extension(), "txt");
+
+    assert!(tx.analyze().iter().any(|f| f.kind == "inscription" && f.input == Some(0)));
+    assert!(Transaction::from_hex(TIMELOCK_TX).unwrap().inscriptions().is_empty());
+}
+
+// ============================================================================
+// Elements Tests
+// ============================================================================
+
+#[cfg(feature = "elements")]
+#[test]
+fn test_elements_transaction() {
+    use crate::{ConfidentialAsset, ConfidentialValue, ElementsTransaction};
+
+    // input #0 issues 1,000,000 units; input #1 is a peg-in of output 1
+    let inputs = format!(
+        "02{}0000008000ffffffff{}{}0100000000000f424000{}0100004000ffffffff",
+        "aa".repeat(32), "00".repeat(32), "bb".repeat(32), "cc".repeat(32)
+    );
+    let explicit = format!("01{}", "dd".repeat(32));
+    let outputs = format!(
+        "03{}010000000005f5e1000016001489{}0a{}08{}02{}16001489{}{}0100000000000003e80000",
+        explicit, "89".repeat(19), "ee".repeat(32), "ee".repeat(32), "ee".repeat(32), "89".repeat(19), explicit
+    );
+    let body = format!("{}{}00000000", inputs, outputs);
+    let witness = ["0000", "0201ab01cd", "00", "0000", "00", "0101ef", "0000", "03aabbcc02ddee", "0000"].concat();
+    let hex = format!("0200000001{}{}", body, witness);
+    let tx = ElementsTransaction::from_hex(&hex).unwrap();
+
+    let issuance = tx.inputs[0].issuance.as_ref().unwrap();
+    assert_eq!(issuance.amount, ConfidentialValue::Explicit(Amount::from_sat(1_000_000)));
+    assert!(!issuance.is_reissuance);
+    assert_eq!(tx.inputs[0].witness, ["ab", "cd"]);
+    assert_eq!((tx.inputs[1].vout, tx.inputs[1].is_pegin), (1, true));
+    assert_eq!(tx.inputs[1].pegin_witness, ["ef"]);
+
+    assert_eq!(tx.outputs[0].value, ConfidentialValue::Explicit(Amount::ONE_BTC));
+    assert_eq!(tx.outputs[0].asset, ConfidentialAsset::Explicit("dd".repeat(32)));
+    assert!(matches!(tx.outputs[1].value, ConfidentialValue::Confidential(_)));
+    assert!(tx.outputs[1].nonce.as_deref().unwrap().starts_with("02"));
+    assert_eq!((tx.outputs[1].surjection_proof_size, tx.outputs[1].range_proof_size), (3, 2));
+    assert!(tx.outputs[2].is_fee);
+    assert_eq!(tx.fee, Amount::from_sat(1_000));
+
+    for field in ["outputs[1].asset: confidential, not unblinded", "outputs[1].value: confidential, not unblinded", "wtxid: not computed"] {
+        assert!(tx.unsupported.iter().any(|u| u == field), "{} missing from {:?}", field, tx.unsupported);
+    }
+    assert!(tx.unsupported.iter().any(|u| u.starts_with("inputs[1].pegin_witness")));
+
+    // txid: the serialization without witnesses, flags byte zeroed
+    let mut txid = sha256d(&hex::decode(format!("0200000000{}", body)).unwrap());
+    txid.reverse();
+    assert_eq!(tx.txid, hex::encode(txid));
+    assert_eq!(tx.weight, (4 + 1 + body.len() / 2) * 3 + hex.len() / 2);
+
+    assert!(ElementsTransaction::from_hex(&format!("{}00", hex)).is_err());
+}
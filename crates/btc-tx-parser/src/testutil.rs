@@ -0,0 +1,244 @@
+//! Seeded generators of random, well-formed transactions for stress tests.
+//! The same seed always gives the same transactions, on every platform and
+//! release, so a failure can be reproduced from the seed and index alone.
+//! Keys and signatures only have the right shape; they don't verify.
+
+use crate::parser::Parser;
+
+// Script templates an output (or the output an input spends) can take
+#[derive(Debug, Clone, Copy)]
+enum Template {
+    P2pk,
+    P2pkh,
+    P2sh,
+    P2wpkh,
+    P2wsh,
+    P2tr,
+    Multisig,
+    OpReturn,
+}
+
+const TEMPLATES: [Template; 8] = [
+    Template::P2pk,
+    Template::P2pkh,
+    Template::P2sh,
+    Template::P2wpkh,
+    Template::P2wsh,
+    Template::P2tr,
+    Template::Multisig,
+    Template::OpReturn,
+];
+
+// Endless stream of serialized transactions from one seed
+#[derive(Debug, Clone)]
+pub struct TxGenerator {
+    state: u64,
+}
+
+impl TxGenerator {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    // The next transaction's consensus bytes: 1-5 inputs spending assorted
+    // templates, 1-6 outputs, legacy or segwit
+    pub fn next_tx(&mut self) -> Vec<u8> {
+        let segwit = self.chance(2);
+        let mut inputs: Vec<Template> = (0..self.range(1, 5)).map(|_| self.spendable()).collect();
+        if segwit {
+            // a segwit transaction needs at least one witness
+            inputs[0] = [Template::P2wpkh, Template::P2wsh, Template::P2tr][self.range(0, 2) as usize];
+        }
+        let mut tx = Vec::new();
+        let version: u32 = if self.chance(4) { 1 } else { 2 };
+        tx.extend_from_slice(&version.to_le_bytes());
+        if segwit {
+            tx.extend_from_slice(&[0x00, 0x01]);
+        }
+
+        Parser::write_varint(&mut tx, inputs.len() as u64);
+        let mut witnesses = Vec::new();
+        for (i, &spent) in inputs.iter().enumerate() {
+            tx.extend(self.bytes(32));
+            tx.extend_from_slice(&(self.range(0, 3) as u32).to_le_bytes());
+            let with_witness = segwit && (i == 0 || self.chance(2));
+            let (script_sig, witness) = self.spend(spent, with_witness);
+            push_bytes(&mut tx, &script_sig);
+            let sequence = match self.range(0, 3) {
+                0 => 0xffff_ffff,
+                1 => 0xffff_fffd,
+                2 => 0xffff_fffe,
+                _ => self.next_u64() as u32,
+            };
+            tx.extend_from_slice(&sequence.to_le_bytes());
+            witnesses.push(witness);
+        }
+
+        let outputs = self.range(1, 6);
+        Parser::write_varint(&mut tx, outputs);
+        for _ in 0..outputs {
+            let template = TEMPLATES[self.range(0, TEMPLATES.len() as u64 - 1) as usize];
+            let value = match template {
+                Template::OpReturn => 0,
+                _ => self.range(546, 2_100_000_000_000_000),
+            };
+            tx.extend_from_slice(&value.to_le_bytes());
+            let script = self.script_pubkey(template);
+            push_bytes(&mut tx, &script);
+        }
+
+        if segwit {
+            for witness in witnesses {
+                Parser::write_varint(&mut tx, witness.len() as u64);
+                for item in witness {
+                    push_bytes(&mut tx, &item);
+                }
+            }
+        }
+        let locktime: u32 = if self.chance(2) { 0 } else { self.next_u64() as u32 };
+        tx.extend_from_slice(&locktime.to_le_bytes());
+        tx
+    }
+
+    // SplitMix64
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // Uniform in low..=high (the slight modulo bias doesn't matter here)
+    fn range(&mut self, low: u64, high: u64) -> u64 {
+        low + self.next_u64() % (high - low + 1)
+    }
+
+    // True one time in `n`
+    fn chance(&mut self, n: u64) -> bool {
+        self.next_u64().is_multiple_of(n)
+    }
+
+    fn bytes(&mut self, len: usize) -> Vec<u8> {
+        (0..len).map(|_| self.next_u64() as u8).collect()
+    }
+
+    fn spendable(&mut self) -> Template {
+        // everything but OP_RETURN
+        TEMPLATES[self.range(0, TEMPLATES.len() as u64 - 2) as usize]
+    }
+
+    fn pubkey(&mut self) -> Vec<u8> {
+        let mut key = self.bytes(33);
+        key[0] = 0x02 | (key[0] & 1);
+        key
+    }
+
+    // DER-shaped ECDSA signature with SIGHASH_ALL
+    fn signature(&mut self) -> Vec<u8> {
+        let mut sig = vec![0x30, 0x44, 0x02, 0x20];
+        sig.extend(self.bytes(32));
+        sig.extend([0x02, 0x20]);
+        sig.extend(self.bytes(32));
+        // positive, minimally encoded integers
+        sig[4] &= 0x7f;
+        sig[4] |= 0x01;
+        sig[38] &= 0x7f;
+        sig[38] |= 0x01;
+        sig.push(0x01);
+        sig
+    }
+
+    fn multisig_script(&mut self) -> Vec<u8> {
+        let total = self.range(1, 3) as u8;
+        let required = self.range(1, total.into()) as u8;
+        let mut script = vec![0x50 + required];
+        for _ in 0..total {
+            let key = self.pubkey();
+            script.push(key.len() as u8);
+            script.extend(key);
+        }
+        script.extend([0x50 + total, 0xae]);
+        script
+    }
+
+    fn script_pubkey(&mut self, template: Template) -> Vec<u8> {
+        match template {
+            Template::P2pk => [vec![33], self.pubkey(), vec![0xac]].concat(),
+            Template::P2pkh => [vec![0x76, 0xa9, 20], self.bytes(20), vec![0x88, 0xac]].concat(),
+            Template::P2sh => [vec![0xa9, 20], self.bytes(20), vec![0x87]].concat(),
+            Template::P2wpkh => [vec![0x00, 20], self.bytes(20)].concat(),
+            Template::P2wsh => [vec![0x00, 32], self.bytes(32)].concat(),
+            Template::P2tr => [vec![0x51, 32], self.bytes(32)].concat(),
+            Template::Multisig => self.multisig_script(),
+            Template::OpReturn => {
+                let len = self.range(0, 75) as usize;
+                [vec![0x6a, len as u8], self.bytes(len)].concat()
+            }
+        }
+    }
+
+    // scriptSig and witness stack spending `template`; native segwit
+    // templates are spent through P2SH when `witness` is false
+    fn spend(&mut self, template: Template, witness: bool) -> (Vec<u8>, Vec<Vec<u8>>) {
+        let mut script_sig = Vec::new();
+        let mut stack = Vec::new();
+        match template {
+            Template::P2pk => push_data(&mut script_sig, &self.signature()),
+            Template::P2pkh => {
+                push_data(&mut script_sig, &self.signature());
+                push_data(&mut script_sig, &self.pubkey());
+            }
+            Template::P2sh | Template::Multisig => {
+                let redeem = self.multisig_script();
+                script_sig.push(0x00);
+                for _ in 0..redeem[0] - 0x50 {
+                    push_data(&mut script_sig, &self.signature());
+                }
+                if matches!(template, Template::P2sh) {
+                    push_data(&mut script_sig, &redeem);
+                }
+            }
+            Template::P2wpkh | Template::P2wsh | Template::P2tr if !witness => {
+                // no witness to put it in: a P2SH multisig spend instead
+                return self.spend(Template::P2sh, false);
+            }
+            Template::P2wpkh => stack = vec![self.signature(), self.pubkey()],
+            Template::P2wsh => {
+                let script = self.multisig_script();
+                stack.push(Vec::new());
+                for _ in 0..script[0] - 0x50 {
+                    stack.push(self.signature());
+                }
+                stack.push(script);
+            }
+            Template::P2tr => stack = vec![self.bytes(64)],
+            Template::OpReturn => {}
+        }
+        // legacy spends leave the stack empty
+        (script_sig, stack)
+    }
+}
+
+impl Iterator for TxGenerator {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Vec<u8>> {
+        Some(self.next_tx())
+    }
+}
+
+// Varint length, then the bytes
+fn push_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    Parser::write_varint(buf, bytes.len() as u64);
+    buf.extend_from_slice(bytes);
+}
+
+// Minimal script push of `data` (up to 255 bytes)
+fn push_data(script: &mut Vec<u8>, data: &[u8]) {
+    if data.len() > 75 {
+        script.push(0x4c);
+    }
+    script.push(data.len() as u8);
+    script.extend_from_slice(data);
+}
@@ -0,0 +1,194 @@
+//! Timelock discovery (nLockTime, BIP-68 sequences, CLTV/CSV in revealed scripts)
+//! and the resulting unlock schedule
+
+use serde::{Deserialize, Serialize};
+use crate::script::opcodes::*;
+use crate::script::{decode_script_num, instructions, revealed_scripts, Instruction};
+use crate::types::Transaction;
+
+pub(crate) const LOCKTIME_THRESHOLD: u64 = 500_000_000;
+pub(crate) const SEQUENCE_DISABLE_FLAG: u32 = 1 << 31;
+pub(crate) const SEQUENCE_TYPE_FLAG: u32 = 1 << 22;
+pub(crate) const SEQUENCE_MASK: u32 = 0x0000_ffff;
+
+// Average block interval used for estimates
+pub const BLOCK_INTERVAL_SECS: u64 = 600;
+
+// A single timelock found in the transaction
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Timelock {
+    // where the lock was found, e.g. "nLockTime" or "witness script OP_CHECKSEQUENCEVERIFY"
+    pub source: String,
+    // input the lock belongs to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub input: Option<usize>,
+    // absolute or relative
+    pub kind: LockKind,
+    // blocks or seconds
+    pub unit: LockUnit,
+    // height/timestamp (absolute) or block count/seconds (relative)
+    pub value: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LockKind {
+    Absolute,
+    Relative,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LockUnit {
+    Blocks,
+    Seconds,
+}
+
+// Current chain state to evaluate locks against
+#[derive(Debug, Clone, Copy)]
+pub struct ChainTip {
+    // current block height, if known
+    pub height: Option<u32>,
+    // current unix time
+    pub time: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnlockStatus {
+    Unlocked,
+    Locked,
+    // relative locks count from the spent output's confirmation
+    AfterConfirmation,
+    // height lock without a known current height
+    Unknown,
+}
+
+// Timelock with its evaluated unlock time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleEntry {
+    pub timelock: Timelock,
+    pub status: UnlockStatus,
+    // blocks still to be mined (height locks)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blocks_remaining: Option<u64>,
+    // estimated unix time the lock expires
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unlocks_at: Option<u64>,
+}
+
+impl Transaction {
+    // All timelocks that constrain this transaction or the scripts it reveals
+    pub fn timelocks(&self) -> Vec<Timelock> {
+        let mut locks = Vec::new();
+
+        // nLockTime is only enforced when some input is non-final
+        if !self.locktime.is_zero() && self.inputs.iter().any(|i| !i.sequence.is_final()) {
+            locks.push(absolute("nLockTime".to_string(), None, self.locktime.to_consensus_u32() as u64));
+        }
+
+        for input in &self.inputs {
+            if self.version >= 2 && !input.is_coinbase {
+                if let Some(lock) = relative("nSequence".to_string(), Some(input.index), input.sequence.0 as i64) {
+                    locks.push(lock);
+                }
+            }
+
+            for (origin, script) in revealed_scripts(input) {
+                let ins = match instructions(&script) {
+                    Some(ins) => ins,
+                    None => continue,
+                };
+                for pair in ins.windows(2) {
+                    let value = match pair[0] {
+                        Instruction::Push(data) => decode_script_num(data),
+                        Instruction::Op(op) if (OP_1..=OP_16).contains(&op) => Some((op - OP_1 + 1) as i64),
+                        _ => None,
+                    };
+                    let value = match value {
+                        Some(v) if v >= 0 => v,
+                        _ => continue,
+                    };
+                    match pair[1] {
+                        Instruction::Op(OP_CHECKLOCKTIMEVERIFY) => locks.push(absolute(
+                            format!("{} OP_CHECKLOCKTIMEVERIFY", origin),
+                            Some(input.index),
+                            value as u64,
+                        )),
+                        Instruction::Op(OP_CHECKSEQUENCEVERIFY) => {
+                            if let Some(lock) = relative(
+                                format!("{} OP_CHECKSEQUENCEVERIFY", origin),
+                                Some(input.index),
+                                value,
+                            ) {
+                                locks.push(lock);
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        locks
+    }
+
+    // Timelocks evaluated against the chain tip, soonest unlock first
+    pub fn unlock_schedule(&self, tip: &ChainTip) -> Vec<ScheduleEntry> {
+        let mut entries: Vec<ScheduleEntry> = self.timelocks()
+            .into_iter()
+            .map(|timelock| evaluate(timelock, tip))
+            .collect();
+
+        entries.sort_by_key(|e| (e.unlocks_at.is_none(), e.unlocks_at, e.timelock.value));
+        entries
+    }
+}
+
+pub(crate) fn absolute(source: String, input: Option<usize>, value: u64) -> Timelock {
+    Timelock {
+        source,
+        input,
+        kind: LockKind::Absolute,
+        unit: if value < LOCKTIME_THRESHOLD { LockUnit::Blocks } else { LockUnit::Seconds },
+        value,
+    }
+}
+
+// BIP-68 encoding, shared by nSequence and CSV arguments
+pub(crate) fn relative(source: String, input: Option<usize>, encoded: i64) -> Option<Timelock> {
+    let encoded = u32::try_from(encoded).ok()?;
+    if encoded & SEQUENCE_DISABLE_FLAG != 0 {
+        return None;
+    }
+    let amount = (encoded & SEQUENCE_MASK) as u64;
+    let (unit, value) = if encoded & SEQUENCE_TYPE_FLAG != 0 {
+        (LockUnit::Seconds, amount * 512)
+    } else {
+        (LockUnit::Blocks, amount)
+    };
+    if value == 0 {
+        return None;
+    }
+    Some(Timelock { source, input, kind: LockKind::Relative, unit, value })
+}
+
+fn evaluate(timelock: Timelock, tip: &ChainTip) -> ScheduleEntry {
+    let (status, blocks_remaining, unlocks_at) = match (timelock.kind, timelock.unit) {
+        (LockKind::Relative, _) => (UnlockStatus::AfterConfirmation, None, None),
+        (LockKind::Absolute, LockUnit::Seconds) => {
+            let status = if tip.time >= timelock.value { UnlockStatus::Unlocked } else { UnlockStatus::Locked };
+            (status, None, Some(timelock.value))
+        }
+        (LockKind::Absolute, LockUnit::Blocks) => match tip.height {
+            Some(height) => {
+                let remaining = timelock.value.saturating_sub(height as u64);
+                let status = if remaining == 0 { UnlockStatus::Unlocked } else { UnlockStatus::Locked };
+                (status, Some(remaining), Some(tip.time + remaining * BLOCK_INTERVAL_SECS))
+            }
+            None => (UnlockStatus::Unknown, None, None),
+        },
+    };
+
+    ScheduleEntry { timelock, status, blocks_remaining, unlocks_at }
+}
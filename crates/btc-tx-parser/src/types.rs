@@ -1,8 +1,23 @@
-use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use crate::amount::Amount;
+use crate::error::ParseError;
+use crate::hexcodec::{decode_hex, write_hex};
+use crate::locktime::LockTime;
+use crate::multisig::MultisigInfo;
+use crate::sequence::Sequence;
+use crate::signature::InputSignature;
 use crate::script::ScriptType;
+use crate::script_sig::ScriptSigParts;
+use crate::coinbase::CoinbaseInfo;
+use crate::taproot::TaprootSpendInfo;
+use crate::witness::WitnessScriptInfo;
 
-// Bitcoin transaction
-#[derive(Debug, Clone, Serialize, Deserialize)]
+// Bitcoin transaction. Built outside the parser, start from Default and set
+// the amounts with `set_total_output`/`set_fee`, so the float twins follow
+// whether or not `float-btc` is on.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Transaction {
     // version
     pub version: i32,
@@ -12,8 +27,8 @@ pub struct Transaction {
     pub inputs: Vec<TxInput>,
     // outputs
     pub outputs: Vec<TxOutput>,
-    // locktime
-    pub locktime: u32,
+    // nLockTime, as a height or a time
+    pub locktime: LockTime,
     // txid (hex)
     pub txid: String,
     // wtxid (hex)
@@ -23,14 +38,17 @@ pub struct Transaction {
     // weight units
     pub weight: usize,
     // total outputs in satoshis
-    pub total_output_satoshis: u64,
-    // total outputs in BTC
+    pub total_output_satoshis: Amount,
+    // total outputs in BTC (lossy)
+    #[cfg(feature = "float-btc")]
+    #[serde(with = "crate::amount::btc_json")]
     pub total_output_btc: f64,
     // fee in satoshis
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub fee_satoshis: Option<u64>,
-    // fee in BTC
-    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee_satoshis: Option<Amount>,
+    // fee in BTC (lossy)
+    #[cfg(feature = "float-btc")]
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "crate::amount::btc_json::option")]
     pub fee_btc: Option<f64>,
 }
 
@@ -40,21 +58,42 @@ pub struct TxInput {
     // input index
     pub index: usize,
     // previous txid
-    pub txid: String,
+    pub txid: Txid,
     // previous output index
     pub vout: u32,
     // scriptSig
     pub script_sig: Script,
-    // sequence
-    pub sequence: u32,
+    // nSequence (RBF signal, relative locktime)
+    pub sequence: Sequence,
     // witness stack
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub witness: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none", with = "crate::witness::json")]
+    pub witness: Option<Vec<Vec<u8>>>,
     // input value (satoshis)
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub value: Option<u64>,
+    pub value: Option<Amount>,
     // coinbase flag
     pub is_coinbase: bool,
+    // signatures in the scriptSig and witness, with their sighash type
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub signatures: Vec<InputSignature>,
+    // control block of a taproot script-path spend
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub taproot: Option<TaprootSpendInfo>,
+    // signatures, keys and redeem script of a legacy scriptSig
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub script_sig_parts: Option<ScriptSigParts>,
+    // the revealed witness script of a P2WSH spend, classified
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub witness_script: Option<WitnessScriptInfo>,
+    // address of the spent output, when the input reveals its key or script
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address: Option<AddressInfo>,
+    // height, pool tag and extranonce read from a coinbase scriptSig
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub coinbase: Option<CoinbaseInfo>,
+    // analyst note, restored from a bundle
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
 }
 
 // Transaction output
@@ -63,8 +102,10 @@ pub struct TxOutput {
     // output index
     pub index: usize,
     // value in satoshis
-    pub value: u64,
-    // value in BTC
+    pub value: Amount,
+    // value in BTC (lossy)
+    #[cfg(feature = "float-btc")]
+    #[serde(with = "crate::amount::btc_json")]
     pub value_btc: f64,
     // scriptPubKey
     pub script_pubkey: Script,
@@ -73,13 +114,34 @@ pub struct TxOutput {
     // derived address
     #[serde(skip_serializing_if = "Option::is_none")]
     pub address: Option<AddressInfo>,
+    // threshold and keys of a bare multisig output
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub multisig: Option<MultisigInfo>,
+    // spent or not, from a chain backend (--check-spent)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spend: Option<OutputSpend>,
+    // analyst note, restored from a bundle
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+// Spending status of an output, as Esplora's /tx/:txid/outspends gives it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputSpend {
+    pub spent: bool,
+    // the spending transaction and its input, once spent
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub txid: Option<Txid>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vin: Option<u32>,
 }
 
 // Script data
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Script {
-    // hex bytes
-    pub hex: String,
+    // raw bytes (hex in JSON)
+    #[serde(rename = "hex", with = "hex_bytes")]
+    pub bytes: Vec<u8>,
     // asm
     pub asm: String,
     // size in bytes
@@ -97,9 +159,118 @@ pub struct AddressInfo {
     pub address_type: String,
 }
 
+// 32-byte hash kept in internal byte order; displayed and serialized
+// reversed, as block explorers show txids
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Txid(pub [u8; 32]);
+
+impl Txid {
+    // Bytes as they appear on the wire
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    // The all-zero hash coinbase inputs point at
+    pub fn is_null(&self) -> bool {
+        self.0 == [0; 32]
+    }
+}
+
+impl fmt::Display for Txid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut reversed = self.0;
+        reversed.reverse();
+        write_hex(f, &reversed)
+    }
+}
+
+impl FromStr for Txid {
+    type Err = ParseError;
+
+    // Display (reversed) hex
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut bytes: [u8; 32] = decode_hex(s)?
+            .try_into()
+            .map_err(|_| ParseError::InvalidTransaction(format!("txid must be 32 bytes: {}", s)))?;
+        bytes.reverse();
+        Ok(Txid(bytes))
+    }
+}
+
+impl Serialize for Txid {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Txid {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+// Bytes as a hex string
+mod hex_bytes {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use crate::hexcodec::{decode_hex, Hex};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&Hex(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        decode_hex(&String::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+    }
+}
+
 impl Transaction {
     // convert satoshis to BTC
     pub fn satoshis_to_btc(satoshis: u64) -> f64 {
-        satoshis as f64 / 100_000_000.0
+        Amount::from_sat(satoshis).to_btc()
+    }
+
+    // Set the fee (and its float twin)
+    pub fn set_fee(&mut self, fee: Option<Amount>) {
+        self.fee_satoshis = fee;
+        #[cfg(feature = "float-btc")]
+        {
+            self.fee_btc = fee.map(Amount::to_btc);
+        }
+    }
+
+    // Set the output total (and its float twin)
+    pub fn set_total_output(&mut self, total: Amount) {
+        self.total_output_satoshis = total;
+        #[cfg(feature = "float-btc")]
+        {
+            self.total_output_btc = total.to_btc();
+        }
+    }
+}
+
+impl TxOutput {
+    // An output with no address, multisig or note, whichever features are on
+    pub fn new(index: usize, value: Amount, script_pubkey: Script, script_type: ScriptType) -> Self {
+        TxOutput {
+            index,
+            value,
+            #[cfg(feature = "float-btc")]
+            value_btc: value.to_btc(),
+            script_pubkey,
+            script_type,
+            address: None,
+            multisig: None,
+            spend: None,
+            note: None,
+        }
+    }
+
+    // Set the value (and its float twin)
+    pub fn set_value(&mut self, value: Amount) {
+        self.value = value;
+        #[cfg(feature = "float-btc")]
+        {
+            self.value_btc = value.to_btc();
+        }
     }
 }
@@ -0,0 +1,187 @@
+//! Witness stack items named by their role in the spend: signatures, keys,
+//! the revealed script, taproot control blocks and annexes. Roles are read
+//! off the stack's shape, since the spent output's type isn't known. The
+//! witness script of a P2WSH spend is also disassembled and classified.
+
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use crate::hash::sha256;
+use crate::multisig::MultisigInfo;
+use crate::script::{detect_script_type, instructions, is_control_block, looks_like_pubkey, looks_like_signature, script_to_asm, Instruction, ScriptType};
+use crate::taproot::{classify_witness_script, TapscriptKind};
+use crate::types::{Script, TxInput};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WitnessItemKind {
+    // DER + sighash byte, or a 64/65-byte BIP-340 signature in a taproot spend
+    Signature,
+    Pubkey,
+    // last item of a segwit v0 script spend
+    WitnessScript,
+    // leaf script of a taproot script-path spend
+    Tapscript,
+    ControlBlock,
+    // taproot annex (0x50 prefix, last item)
+    Annex,
+    // empty push, e.g. the CHECKMULTISIG dummy or a skipped signature
+    Empty,
+    // anything else: preimages, script arguments
+    Data,
+}
+
+impl fmt::Display for WitnessItemKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            WitnessItemKind::Signature => "signature",
+            WitnessItemKind::Pubkey => "pubkey",
+            WitnessItemKind::WitnessScript => "witness script",
+            WitnessItemKind::Tapscript => "tapscript",
+            WitnessItemKind::ControlBlock => "control block",
+            WitnessItemKind::Annex => "annex",
+            WitnessItemKind::Empty => "empty",
+            WitnessItemKind::Data => "data",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WitnessItem {
+    pub hex: String,
+    pub kind: WitnessItemKind,
+    pub size: usize,
+}
+
+// The script a P2WSH output's 32-byte hash commits to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WitnessScriptInfo {
+    pub script: Script,
+    // standard template of the script itself, e.g. multisig; mostly nonstandard
+    pub script_type: ScriptType,
+    // what the script checks; empty when nothing is recognized
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub kinds: Vec<TapscriptKind>,
+    // P2SH-wrapped (the scriptSig pushes the witness program)
+    pub nested: bool,
+    // threshold and keys when the script is a multisig
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub multisig: Option<MultisigInfo>,
+}
+
+impl TxInput {
+    // Witness stack with each item's role; empty without a witness
+    pub fn witness_items(&self) -> Vec<WitnessItem> {
+        describe_stack(self.witness.as_deref().unwrap_or_default())
+    }
+
+    // Last witness item of a P2WSH spend, native or nested. A nested spend
+    // must push the program hashing to the script; a native one can't be
+    // checked without the spent output, so it goes by the stack's shape.
+    pub fn witness_script_info(&self) -> Option<WitnessScriptInfo> {
+        if self.is_coinbase {
+            return None;
+        }
+        let stack = self.witness.as_deref()?;
+        let script = stack.last()?;
+        let nested = match instructions(&self.script_sig.bytes)?.as_slice() {
+            [] => false,
+            [Instruction::Push(program)] if program.len() == 34 && program[..2] == [0x00, 0x20] => {
+                if program[2..] != sha256(script)[..] {
+                    return None;
+                }
+                true
+            }
+            _ => return None,
+        };
+        if !nested && classify_witness(stack).last() != Some(&WitnessItemKind::WitnessScript) {
+            return None;
+        }
+        Some(WitnessScriptInfo {
+            script: Script {
+                asm: script_to_asm(script),
+                size: script.len(),
+                bytes: script.clone(),
+            },
+            script_type: detect_script_type(script),
+            kinds: classify_witness_script(script),
+            nested,
+            multisig: MultisigInfo::from_script(script),
+        })
+    }
+}
+
+fn describe_stack(stack: &[Vec<u8>]) -> Vec<WitnessItem> {
+    stack.iter()
+        .zip(classify_witness(stack))
+        .map(|(item, kind)| WitnessItem { hex: hex::encode(item), kind, size: item.len() })
+        .collect()
+}
+
+// One kind per stack item, in order
+pub(crate) fn classify_witness(stack: &[Vec<u8>]) -> Vec<WitnessItemKind> {
+    let has_annex = stack.len() >= 2 && stack.last().is_some_and(|a| a.first() == Some(&0x50));
+    let items = if has_annex { &stack[..stack.len() - 1] } else { stack };
+    let script_path = items.len() >= 2 && is_control_block(&items[items.len() - 1]);
+    let key_path = items.len() == 1 && matches!(items[0].len(), 64 | 65);
+    let taproot = script_path || key_path;
+
+    let mut kinds: Vec<WitnessItemKind> = items.iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let last = i + 1 == items.len();
+            if script_path && last {
+                WitnessItemKind::ControlBlock
+            } else if script_path && i + 2 == items.len() {
+                WitnessItemKind::Tapscript
+            } else if item.is_empty() {
+                WitnessItemKind::Empty
+            } else if taproot && matches!(item.len(), 64 | 65) || !taproot && looks_like_signature(item) {
+                WitnessItemKind::Signature
+            } else if looks_like_pubkey(item) {
+                WitnessItemKind::Pubkey
+            } else if !taproot && last && items.len() >= 2 {
+                WitnessItemKind::WitnessScript
+            } else {
+                WitnessItemKind::Data
+            }
+        })
+        .collect();
+    if has_annex {
+        kinds.push(WitnessItemKind::Annex);
+    }
+    kinds
+}
+
+// Witness stack in JSON: a list of items with their role and size. Lists of
+// plain hex strings, as older JSON carried, are accepted too.
+pub(crate) mod json {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use super::describe_stack;
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Encoded {
+        Raw(String),
+        Decoded { hex: String },
+    }
+
+    pub fn serialize<S: Serializer>(witness: &Option<Vec<Vec<u8>>>, serializer: S) -> Result<S::Ok, S::Error> {
+        match witness {
+            Some(stack) => serializer.collect_seq(describe_stack(stack)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Vec<Vec<u8>>>, D::Error> {
+        Option::<Vec<Encoded>>::deserialize(deserializer)?
+            .map(|items| {
+                items.into_iter()
+                    .map(|item| match item {
+                        Encoded::Raw(hex) | Encoded::Decoded { hex } => hex::decode(hex),
+                    })
+                    .collect::<Result<_, _>>()
+            })
+            .transpose()
+            .map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,152 @@
+// This file is @generated by prost-build.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Transaction {
+    #[prost(int32, tag = "1")]
+    pub version: i32,
+    #[prost(bool, tag = "2")]
+    pub is_segwit: bool,
+    #[prost(message, repeated, tag = "3")]
+    pub inputs: ::prost::alloc::vec::Vec<TxInput>,
+    #[prost(message, repeated, tag = "4")]
+    pub outputs: ::prost::alloc::vec::Vec<TxOutput>,
+    #[prost(uint32, tag = "5")]
+    pub locktime: u32,
+    #[prost(string, tag = "6")]
+    pub txid: ::prost::alloc::string::String,
+    #[prost(string, tag = "7")]
+    pub wtxid: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "8")]
+    pub raw_size: u64,
+    #[prost(uint64, tag = "9")]
+    pub weight: u64,
+    #[prost(uint64, tag = "10")]
+    pub total_output_satoshis: u64,
+    /// total_output_satoshis in BTC (lossy); ignored when read back
+    #[prost(double, tag = "11")]
+    pub total_output_btc: f64,
+    #[prost(uint64, optional, tag = "12")]
+    pub fee_satoshis: ::core::option::Option<u64>,
+    /// fee_satoshis in BTC (lossy); ignored when read back
+    #[prost(double, optional, tag = "13")]
+    pub fee_btc: ::core::option::Option<f64>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TxInput {
+    #[prost(uint64, tag = "1")]
+    pub index: u64,
+    /// previous txid (display byte order)
+    #[prost(string, tag = "2")]
+    pub txid: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "3")]
+    pub vout: u32,
+    #[prost(message, optional, tag = "4")]
+    pub script_sig: ::core::option::Option<Script>,
+    #[prost(uint32, tag = "5")]
+    pub sequence: u32,
+    /// witness stack items (hex); only meaningful when has_witness is set
+    #[prost(string, repeated, tag = "6")]
+    pub witness: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    #[prost(bool, tag = "7")]
+    pub has_witness: bool,
+    /// value of the spent output, when known
+    #[prost(uint64, optional, tag = "8")]
+    pub value: ::core::option::Option<u64>,
+    #[prost(bool, tag = "9")]
+    pub is_coinbase: bool,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TxOutput {
+    #[prost(uint64, tag = "1")]
+    pub index: u64,
+    #[prost(uint64, tag = "2")]
+    pub value: u64,
+    /// value in BTC (lossy); ignored when read back
+    #[prost(double, tag = "3")]
+    pub value_btc: f64,
+    #[prost(message, optional, tag = "4")]
+    pub script_pubkey: ::core::option::Option<Script>,
+    #[prost(enumeration = "ScriptType", tag = "5")]
+    pub script_type: i32,
+    #[prost(message, optional, tag = "6")]
+    pub address: ::core::option::Option<AddressInfo>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Script {
+    #[prost(string, tag = "1")]
+    pub hex: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub asm: ::prost::alloc::string::String,
+    #[prost(uint64, tag = "3")]
+    pub size: u64,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AddressInfo {
+    #[prost(string, tag = "1")]
+    pub mainnet: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub testnet: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub address_type: ::prost::alloc::string::String,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DecodeRequest {
+    /// raw transaction hex
+    #[prost(string, tag = "1")]
+    pub hex: ::prost::alloc::string::String,
+    /// values of the spent outputs, in input order, for fee calculation
+    #[prost(uint64, repeated, tag = "2")]
+    pub input_values: ::prost::alloc::vec::Vec<u64>,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum ScriptType {
+    Unspecified = 0,
+    P2pkh = 1,
+    P2sh = 2,
+    P2wpkh = 3,
+    P2wsh = 4,
+    P2tr = 5,
+    P2pk = 6,
+    Multisig = 7,
+    OpReturn = 8,
+    WitnessUnknown = 9,
+    Nonstandard = 10,
+}
+impl ScriptType {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Unspecified => "SCRIPT_TYPE_UNSPECIFIED",
+            Self::P2pkh => "SCRIPT_TYPE_P2PKH",
+            Self::P2sh => "SCRIPT_TYPE_P2SH",
+            Self::P2wpkh => "SCRIPT_TYPE_P2WPKH",
+            Self::P2wsh => "SCRIPT_TYPE_P2WSH",
+            Self::P2tr => "SCRIPT_TYPE_P2TR",
+            Self::P2pk => "SCRIPT_TYPE_P2PK",
+            Self::Multisig => "SCRIPT_TYPE_MULTISIG",
+            Self::OpReturn => "SCRIPT_TYPE_OP_RETURN",
+            Self::WitnessUnknown => "SCRIPT_TYPE_WITNESS_UNKNOWN",
+            Self::Nonstandard => "SCRIPT_TYPE_NONSTANDARD",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "SCRIPT_TYPE_UNSPECIFIED" => Some(Self::Unspecified),
+            "SCRIPT_TYPE_P2PKH" => Some(Self::P2pkh),
+            "SCRIPT_TYPE_P2SH" => Some(Self::P2sh),
+            "SCRIPT_TYPE_P2WPKH" => Some(Self::P2wpkh),
+            "SCRIPT_TYPE_P2WSH" => Some(Self::P2wsh),
+            "SCRIPT_TYPE_P2TR" => Some(Self::P2tr),
+            "SCRIPT_TYPE_P2PK" => Some(Self::P2pk),
+            "SCRIPT_TYPE_MULTISIG" => Some(Self::Multisig),
+            "SCRIPT_TYPE_OP_RETURN" => Some(Self::OpReturn),
+            "SCRIPT_TYPE_WITNESS_UNKNOWN" => Some(Self::WitnessUnknown),
+            "SCRIPT_TYPE_NONSTANDARD" => Some(Self::Nonstandard),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,207 @@
+//! Conversions between btc_tx_parser types and the generated messages
+
+use btc_tx_parser as parser;
+use thiserror::Error;
+use crate::v1;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ConvertError {
+    #[error("Output #{0} has no script type")]
+    MissingScriptType(usize),
+
+    #[error("Missing field: {0}")]
+    MissingField(&'static str),
+
+    #[error("Invalid hex in field: {0}")]
+    InvalidHex(&'static str),
+}
+
+impl From<&parser::Transaction> for v1::Transaction {
+    fn from(tx: &parser::Transaction) -> Self {
+        Self {
+            version: tx.version,
+            is_segwit: tx.is_segwit,
+            inputs: tx.inputs.iter().map(v1::TxInput::from).collect(),
+            outputs: tx.outputs.iter().map(v1::TxOutput::from).collect(),
+            locktime: tx.locktime.to_consensus_u32(),
+            txid: tx.txid.clone(),
+            wtxid: tx.wtxid.clone(),
+            raw_size: tx.raw_size as u64,
+            weight: tx.weight as u64,
+            total_output_satoshis: tx.total_output_satoshis.to_sat(),
+            total_output_btc: tx.total_output_satoshis.to_btc(),
+            fee_satoshis: tx.fee_satoshis.map(parser::Amount::to_sat),
+            fee_btc: tx.fee_satoshis.map(parser::Amount::to_btc),
+        }
+    }
+}
+
+impl From<&parser::TxInput> for v1::TxInput {
+    fn from(input: &parser::TxInput) -> Self {
+        Self {
+            index: input.index as u64,
+            txid: input.txid.to_string(),
+            vout: input.vout,
+            script_sig: Some(v1::Script::from(&input.script_sig)),
+            sequence: input.sequence.0,
+            witness: input.witness.iter().flatten().map(hex::encode).collect(),
+            has_witness: input.witness.is_some(),
+            value: input.value.map(parser::Amount::to_sat),
+            is_coinbase: input.is_coinbase,
+        }
+    }
+}
+
+impl From<&parser::TxOutput> for v1::TxOutput {
+    fn from(output: &parser::TxOutput) -> Self {
+        Self {
+            index: output.index as u64,
+            value: output.value.to_sat(),
+            value_btc: output.value.to_btc(),
+            script_pubkey: Some(v1::Script::from(&output.script_pubkey)),
+            script_type: v1::ScriptType::from(&output.script_type) as i32,
+            address: output.address.as_ref().map(|a| v1::AddressInfo {
+                mainnet: a.mainnet.clone(),
+                testnet: a.testnet.clone(),
+                address_type: a.address_type.clone(),
+            }),
+        }
+    }
+}
+
+impl From<&parser::Script> for v1::Script {
+    fn from(script: &parser::Script) -> Self {
+        Self {
+            hex: hex::encode(&script.bytes),
+            asm: script.asm.clone(),
+            size: script.size as u64,
+        }
+    }
+}
+
+impl From<&parser::ScriptType> for v1::ScriptType {
+    fn from(script_type: &parser::ScriptType) -> Self {
+        match script_type {
+            parser::ScriptType::P2PKH => v1::ScriptType::P2pkh,
+            parser::ScriptType::P2SH => v1::ScriptType::P2sh,
+            parser::ScriptType::P2WPKH => v1::ScriptType::P2wpkh,
+            parser::ScriptType::P2WSH => v1::ScriptType::P2wsh,
+            parser::ScriptType::P2TR => v1::ScriptType::P2tr,
+            parser::ScriptType::P2PK => v1::ScriptType::P2pk,
+            parser::ScriptType::Multisig => v1::ScriptType::Multisig,
+            parser::ScriptType::OpReturn => v1::ScriptType::OpReturn,
+            parser::ScriptType::WitnessUnknown => v1::ScriptType::WitnessUnknown,
+            parser::ScriptType::NonStandard => v1::ScriptType::Nonstandard,
+        }
+    }
+}
+
+impl TryFrom<v1::Transaction> for parser::Transaction {
+    type Error = ConvertError;
+
+    // The BTC fields of the message are ignored; the twins, if the parser
+    // keeps them, are derived from the satoshi amounts
+    fn try_from(tx: v1::Transaction) -> Result<Self, Self::Error> {
+        let mut parsed = Self {
+            version: tx.version,
+            is_segwit: tx.is_segwit,
+            inputs: tx.inputs.into_iter().map(parser::TxInput::try_from).collect::<Result<_, _>>()?,
+            outputs: tx.outputs.into_iter().map(parser::TxOutput::try_from).collect::<Result<_, _>>()?,
+            locktime: parser::LockTime::from_consensus(tx.locktime),
+            txid: tx.txid,
+            wtxid: tx.wtxid,
+            raw_size: tx.raw_size as usize,
+            weight: tx.weight as usize,
+            ..Default::default()
+        };
+        parsed.set_total_output(parser::Amount::from_sat(tx.total_output_satoshis));
+        parsed.set_fee(tx.fee_satoshis.map(parser::Amount::from_sat));
+        Ok(parsed)
+    }
+}
+
+impl TryFrom<v1::TxInput> for parser::TxInput {
+    type Error = ConvertError;
+
+    fn try_from(input: v1::TxInput) -> Result<Self, Self::Error> {
+        let mut input = Self {
+            index: input.index as usize,
+            txid: input.txid.parse().map_err(|_| ConvertError::InvalidHex("txid"))?,
+            vout: input.vout,
+            script_sig: input.script_sig.ok_or(ConvertError::MissingField("script_sig"))?.try_into()?,
+            sequence: parser::Sequence(input.sequence),
+            witness: input.has_witness
+                .then(|| input.witness.iter().map(hex::decode).collect::<Result<_, _>>())
+                .transpose()
+                .map_err(|_| ConvertError::InvalidHex("witness"))?,
+            value: input.value.map(parser::Amount::from_sat),
+            is_coinbase: input.is_coinbase,
+            signatures: Vec::new(),
+            taproot: None,
+            script_sig_parts: None,
+            witness_script: None,
+            address: None,
+            coinbase: None,
+            note: None,
+        };
+        input.signatures = input.parse_signatures();
+        input.taproot = input.taproot_spend_info();
+        input.script_sig_parts = input.script_sig_parts();
+        input.witness_script = input.witness_script_info();
+        input.address = input.spent_address();
+        input.coinbase = input.coinbase_info();
+        Ok(input)
+    }
+}
+
+impl TryFrom<v1::TxOutput> for parser::TxOutput {
+    type Error = ConvertError;
+
+    fn try_from(output: v1::TxOutput) -> Result<Self, Self::Error> {
+        let index = output.index as usize;
+        let script_type = match v1::ScriptType::try_from(output.script_type) {
+            Ok(v1::ScriptType::Unspecified) | Err(_) => return Err(ConvertError::MissingScriptType(index)),
+            Ok(script_type) => parser::ScriptType::from(script_type),
+        };
+        let script_pubkey: parser::Script = output.script_pubkey.ok_or(ConvertError::MissingField("script_pubkey"))?.try_into()?;
+        let multisig = parser::MultisigInfo::from_script(&script_pubkey.bytes);
+        let mut parsed = Self::new(index, parser::Amount::from_sat(output.value), script_pubkey, script_type);
+        parsed.multisig = multisig;
+        parsed.address = output.address.map(|a| parser::AddressInfo {
+            mainnet: a.mainnet,
+            testnet: a.testnet,
+            address_type: a.address_type,
+        });
+        Ok(parsed)
+    }
+}
+
+impl TryFrom<v1::Script> for parser::Script {
+    type Error = ConvertError;
+
+    fn try_from(script: v1::Script) -> Result<Self, Self::Error> {
+        Ok(Self {
+            bytes: hex::decode(&script.hex).map_err(|_| ConvertError::InvalidHex("script"))?,
+            asm: script.asm,
+            size: script.size as usize,
+        })
+    }
+}
+
+// Only called with a specified type
+impl From<v1::ScriptType> for parser::ScriptType {
+    fn from(script_type: v1::ScriptType) -> Self {
+        match script_type {
+            v1::ScriptType::P2pkh => parser::ScriptType::P2PKH,
+            v1::ScriptType::P2sh => parser::ScriptType::P2SH,
+            v1::ScriptType::P2wpkh => parser::ScriptType::P2WPKH,
+            v1::ScriptType::P2wsh => parser::ScriptType::P2WSH,
+            v1::ScriptType::P2tr => parser::ScriptType::P2TR,
+            v1::ScriptType::P2pk => parser::ScriptType::P2PK,
+            v1::ScriptType::Multisig => parser::ScriptType::Multisig,
+            v1::ScriptType::OpReturn => parser::ScriptType::OpReturn,
+            v1::ScriptType::WitnessUnknown => parser::ScriptType::WitnessUnknown,
+            v1::ScriptType::Nonstandard | v1::ScriptType::Unspecified => parser::ScriptType::NonStandard,
+        }
+    }
+}
@@ -0,0 +1,18 @@
+//! Protobuf schema (`proto/btc_tx/v1/transaction.proto`) and prost types for
+//! decoded transactions, with conversions to and from the parser's types
+
+mod convert;
+
+#[cfg(test)]
+mod tests;
+
+pub use convert::ConvertError;
+
+// Generated from proto/btc_tx/v1/transaction.proto by prost-build; checked in
+// so building needs no protoc. A test fails when it drifts from the schema;
+// `PROTO_BLESS=1 cargo test -p btc-tx-proto` regenerates it.
+#[path = "btc_tx.v1.rs"]
+pub mod v1;
+
+// The schema source, for services that serve reflection or write it out
+pub const TRANSACTION_PROTO: &str = include_str!("../proto/btc_tx/v1/transaction.proto");
@@ -0,0 +1,73 @@
+//Tests for btc-tx-proto crate
+
+use crate::{v1, ConvertError};
+use btc_tx_parser::Transaction;
+use prost::Message;
+use prost_types::field_descriptor_proto::Type;
+use prost_types::FileDescriptorSet;
+
+const SEGWIT_TX: &str = "020000000001010000000000000000000000000000000000000000000000000000000000000000ffffffff0502e8030101ffffffff0200f2052a0100000016001496ba8ba89947e739cd4e48507f9d26f47ed31c4e0000000000000000266a24aa21a9ede2f61c3f71d1defd3fa999dfa36953755c690689799962b48bebd836974e8cf90120000000000000000000000000000000000000000000000000000000000000000000000000";
+
+#[test]
+fn test_proto_round_trip() {
+    let tx = Transaction::from_hex(SEGWIT_TX).unwrap();
+    let message = v1::Transaction::from(&tx);
+    assert_eq!(message.outputs[0].script_type(), v1::ScriptType::P2wpkh);
+    assert!(message.inputs[0].has_witness);
+
+    let decoded = v1::Transaction::decode(message.encode_to_vec().as_slice()).unwrap();
+    assert_eq!(decoded, message);
+
+    let restored = Transaction::try_from(decoded).unwrap();
+    assert_eq!(restored.txid, tx.txid);
+    assert_eq!(restored.inputs[0].witness, tx.inputs[0].witness);
+    assert_eq!(restored.outputs[1].script_type, tx.outputs[1].script_type);
+
+    // the BTC fields are derived from satoshis, not read back
+    let mut lossy = message.clone();
+    lossy.total_output_btc = 1e300;
+    lossy.outputs[0].value_btc = f64::NAN;
+    let restored = Transaction::try_from(lossy).unwrap();
+    assert_eq!(v1::Transaction::from(&restored), message);
+
+    let mut broken = message.clone();
+    broken.outputs[1].script_type = v1::ScriptType::Unspecified as i32;
+    assert_eq!(Transaction::try_from(broken).unwrap_err(), ConvertError::MissingScriptType(1));
+
+    let mut broken = message.clone();
+    broken.inputs[0].witness[0] = "zz".to_string();
+    assert_eq!(Transaction::try_from(broken).unwrap_err(), ConvertError::InvalidHex("witness"));
+}
+
+// btc_tx.v1.rs is checked in so builds need no protoc. This regenerates it
+// from the schema (parsed by protox-parse, so no protoc here either) and
+// fails on any drift; PROTO_BLESS=1 writes the regenerated file instead.
+#[test]
+fn test_generated_code_is_current() {
+    let mut file = protox_parse::parse("btc_tx/v1/transaction.proto", crate::TRANSACTION_PROTO).unwrap();
+    // protox-parse leaves names as written; protoc would qualify them
+    let package = format!(".{}", file.package());
+    let messages: Vec<String> = file.message_type.iter().map(|m| m.name().to_string()).collect();
+    for field in file.message_type.iter_mut().flat_map(|m| m.field.iter_mut()) {
+        if let Some(name) = field.type_name.clone() {
+            field.set_type(if messages.contains(&name) { Type::Message } else { Type::Enum });
+            field.type_name = Some(format!("{}.{}", package, name));
+        }
+    }
+    for method in file.service.iter_mut().flat_map(|s| s.method.iter_mut()) {
+        method.input_type = Some(format!("{}.{}", package, method.input_type()));
+        method.output_type = Some(format!("{}.{}", package, method.output_type()));
+    }
+
+    let dir = std::env::temp_dir().join(format!("btc-tx-proto-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    prost_build::Config::new().out_dir(&dir).compile_fds(FileDescriptorSet { file: vec![file] }).unwrap();
+    let generated = std::fs::read_to_string(dir.join("btc_tx.v1.rs")).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let checked_in = concat!(env!("CARGO_MANIFEST_DIR"), "/src/btc_tx.v1.rs");
+    if std::env::var_os("PROTO_BLESS").is_some() {
+        std::fs::write(checked_in, &generated).unwrap();
+    }
+    assert!(generated == std::fs::read_to_string(checked_in).unwrap(), "src/btc_tx.v1.rs is stale; run with PROTO_BLESS=1");
+}
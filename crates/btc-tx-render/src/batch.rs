@@ -0,0 +1,114 @@
+//! Batch statistics, the `pipe --cache` summary cache and block template
+//! reports
+
+use btc_tx_parser::{BatchReport, TemplateReport};
+use colored::Colorize;
+use std::io::{self, Write};
+use std::path::Path;
+use crate::theme::{paint, Role};
+
+pub fn write_batch_report(out: &mut dyn Write, report: &BatchReport) -> io::Result<()> {
+    writeln!(out, "{}", paint("Batch Statistics", Role::Heading).bold())?;
+    writeln!(out, "{}", paint(&"─".repeat(60), Role::Muted))?;
+    writeln!(out, "  {} {} ({} segwit)", paint("Transactions:", Role::Label).bold(), report.transactions, report.segwit_transactions)?;
+    writeln!(out, "  {} {}", paint("Inputs:", Role::Label).bold(), report.inputs)?;
+    writeln!(out, "  {} {}", paint("Outputs:", Role::Label).bold(), report.outputs)?;
+    writeln!(out, "  {} {}", paint("Total Output:", Role::Label).bold(), report.total_output_satoshis)?;
+    writeln!(out, "  {} {} vbytes", paint("Total Size:", Role::Label).bold(), report.total_vsize)?;
+    if let Some(feerates) = &report.feerates {
+        writeln!(out, "  {} p10 {:.2} / p50 {:.2} / p90 {:.2} sat/vB ({} txs with input values)",
+            paint("Feerates:", Role::Label).bold(), feerates.p10, feerates.p50, feerates.p90, feerates.samples)?;
+    }
+    writeln!(out, "  {}", paint("Output Types:", Role::Label).bold())?;
+    for (script_type, count) in &report.script_types {
+        writeln!(out, "    {:<40} {}", script_type.to_string(), count)?;
+    }
+    writeln!(out)?;
+
+    let carriers = &report.data_carriers;
+    let share = |weight: u64| if carriers.total_weight == 0 { 0.0 } else { weight as f64 * 100.0 / carriers.total_weight as f64 };
+    writeln!(out, "{}", paint("Block Space", Role::Heading).bold())?;
+    writeln!(out, "{}", paint(&"─".repeat(60), Role::Muted))?;
+    writeln!(out, "  {} {} WU ({} txs carry data)", paint("Total Weight:", Role::Label).bold(),
+        carriers.total_weight, carriers.transactions_with_data)?;
+    for (label, usage) in [
+        ("OP_RETURN outputs", carriers.op_return),
+        ("Inscriptions", carriers.inscriptions),
+        ("Bare multisig outputs", carriers.bare_multisig),
+        ("Oversized witness items", carriers.oversized_witness),
+    ] {
+        writeln!(out, "    {:<26} {:>6} {:>12} WU {:>6.2}%", label, usage.count, usage.weight, share(usage.weight))?;
+    }
+    writeln!(out, "    {:<26} {:>6} {:>12} WU {:>6.2}%", "Monetary", "", carriers.monetary_weight(), share(carriers.monetary_weight()))?;
+    writeln!(out)?;
+
+    for (title, items) in [("Reused Public Keys", &report.reused_pubkeys), ("Reused Scripts", &report.reused_scripts)] {
+        writeln!(out, "{} ({})", paint(title, Role::Heading).bold(), items.len())?;
+        writeln!(out, "{}", paint(&"─".repeat(60), Role::Muted))?;
+        for item in items {
+            let shown = if item.hex.len() > 66 { format!("{}…", &item.hex[..66]) } else { item.hex.clone() };
+            writeln!(out, "  {} {}", paint(&shown, Role::Hash), paint(&format!("x{}", item.occurrences.len()), Role::Bad).bold())?;
+            for occurrence in &item.occurrences {
+                writeln!(out, "    {}:{}", paint(&occurrence.txid, Role::Muted), occurrence.input)?;
+            }
+        }
+        writeln!(out)?;
+    }
+
+    writeln!(out, "{} ({})", paint("Nonce Reuse", Role::Heading).bold(), report.nonce_reuse.len())?;
+    writeln!(out, "{}", paint(&"─".repeat(60), Role::Muted))?;
+    for reuse in &report.nonce_reuse {
+        let verdict = if reuse.same_key { "private key recoverable" } else { "different keys" };
+        writeln!(out, "  {} {} {}", paint(&format!("r={}", reuse.r), Role::Hash),
+            paint(&format!("x{}", reuse.occurrences.len()), Role::Bad).bold(), paint(verdict, Role::Bad))?;
+        for occurrence in &reuse.occurrences {
+            writeln!(out, "    {}:{}", paint(&occurrence.txid, Role::Muted), occurrence.input)?;
+        }
+        for pubkey in &reuse.pubkeys {
+            writeln!(out, "    {} {}", paint("key", Role::Label), paint(pubkey, Role::Hash))?;
+        }
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+// Where the summary cache lives and how much it holds
+pub fn write_cache_stats(out: &mut dyn Write, path: &Path, entries: usize, bytes_on_disk: u64) -> io::Result<()> {
+    writeln!(out, "{} {}", paint("Path:", Role::Label).bold(), path.display())?;
+    writeln!(out, "{} {}", paint("Entries:", Role::Label).bold(), entries)?;
+    writeln!(out, "{} {} bytes", paint("On Disk:", Role::Label).bold(), bytes_on_disk)
+}
+
+pub fn write_template_report(out: &mut dyn Write, report: &TemplateReport) -> io::Result<()> {
+    writeln!(out, "{}", paint("Block Template", Role::Heading).bold())?;
+    writeln!(out, "{}", paint(&"─".repeat(60), Role::Muted))?;
+    if let Some(height) = report.height {
+        writeln!(out, "  {} {}", paint("Height:", Role::Label).bold(), height)?;
+    }
+    writeln!(out, "  {} {}", paint("Transactions:", Role::Label).bold(), report.transactions)?;
+    writeln!(out, "  {} {} sats ({})", paint("Total Fees:", Role::Label).bold(),
+        report.total_fees.to_sat(), report.total_fees)?;
+    if let Some(value) = report.coinbase_value {
+        writeln!(out, "  {} {} sats", paint("Coinbase Value:", Role::Label).bold(), value.to_sat())?;
+    }
+    writeln!(out, "  {} {} / {} WU ({:.2}%)", paint("Weight:", Role::Label).bold(),
+        report.weight, report.weight_limit, report.weight_utilization)?;
+    writeln!(out, "  {} {} / {} ({:.2}%)", paint("Sigops:", Role::Label).bold(),
+        report.sigops, report.sigop_limit, report.sigop_utilization)?;
+
+    let mut by_feerate: Vec<_> = report.entries.iter().filter(|e| e.feerate.is_some()).collect();
+    by_feerate.sort_by(|a, b| a.feerate.unwrap_or_default().total_cmp(&b.feerate.unwrap_or_default()));
+    if let (Some(low), Some(high)) = (by_feerate.first(), by_feerate.last()) {
+        writeln!(out, "  {} {:.2} – {:.2} sat/vB", paint("Feerates:", Role::Label).bold(),
+            low.feerate.unwrap_or_default(), high.feerate.unwrap_or_default())?;
+    }
+    writeln!(out)?;
+
+    writeln!(out, "{} ({})", paint("Issues", Role::Heading).bold(), report.issues.len())?;
+    writeln!(out, "{}", paint(&"─".repeat(60), Role::Muted))?;
+    for issue in &report.issues {
+        writeln!(out, "  {} {}", paint("!", Role::Bad).bold(), issue)?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
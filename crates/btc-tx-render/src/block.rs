@@ -0,0 +1,120 @@
+//! Block header breakdown and block listings
+
+use btc_tx_parser::{Block, BlockHeader, CompactBlock, P2pMessage, Reconstruction, StratumCoinbase, WirePayload};
+use colored::Colorize;
+use std::io::{self, Write};
+use crate::theme::{paint, Role};
+use crate::timelock::format_unix_time;
+use crate::transaction::group_thousands;
+
+pub fn write_block_header(out: &mut dyn Write, header: &BlockHeader) -> io::Result<()> {
+    writeln!(out)?;
+    writeln!(out, "{}", paint("Block Header", Role::Heading).bold())?;
+    writeln!(out, "{}", paint(&"─".repeat(60), Role::Muted))?;
+    writeln!(out, "  {} {}", paint("Hash:", Role::Label).bold(), paint(&header.hash, Role::Hash))?;
+    if let Some(network) = header.genesis_of() {
+        writeln!(out, "  {} block 0 of {}", paint("Genesis:", Role::Label).bold(), network)?;
+    }
+    writeln!(out, "  {} {} (0x{:08x})", paint("Version:", Role::Label).bold(), header.version, header.version)?;
+    writeln!(out, "  {} {}", paint("Previous:", Role::Label).bold(), paint(&header.prev_block_hash, Role::Hash))?;
+    writeln!(out, "  {} {}", paint("Merkle Root:", Role::Label).bold(), paint(&header.merkle_root, Role::Hash))?;
+    writeln!(out, "  {} {} ({})", paint("Time:", Role::Label).bold(), header.time, format_unix_time(header.time as u64))?;
+    writeln!(out, "  {} 0x{:08x}", paint("Bits:", Role::Label).bold(), header.bits)?;
+    writeln!(out, "  {} {}", paint("Nonce:", Role::Label).bold(), header.nonce)?;
+    writeln!(out, "  {} {}", paint("Target:", Role::Label).bold(), header.target)?;
+    writeln!(out, "  {} {}", paint("Difficulty:", Role::Label).bold(), paint(&format_difficulty(header.difficulty), Role::Amount))?;
+    let pow = if header.meets_target() {
+        paint("hash meets target", Role::Good)
+    } else {
+        paint("hash above target", Role::Bad).bold()
+    };
+    writeln!(out, "  {} {}", paint("Proof of Work:", Role::Label).bold(), pow)?;
+    writeln!(out)?;
+    Ok(())
+}
+
+// One line per block (hash, time, transactions, size, weight), flagging
+// commitments that do not match; with `txs`, the txids follow indented
+pub fn write_block_summary(out: &mut dyn Write, block: &Block, txs: bool) -> io::Result<()> {
+    write!(out, "{}  {}  {:>5} txs  {:>9} bytes  {:>9} WU", paint(&block.header.hash, Role::Hash),
+        format_unix_time(block.header.time as u64), block.transactions.len(),
+        group_thousands(block.size as u64), group_thousands(block.weight as u64))?;
+    if !block.merkle_root_valid {
+        write!(out, "  {}", paint("merkle root mismatch", Role::Bad).bold())?;
+    }
+    if block.witness_commitment_valid == Some(false) {
+        write!(out, "  {}", paint("witness commitment mismatch", Role::Bad).bold())?;
+    }
+    writeln!(out)?;
+    if txs {
+        for tx in &block.transactions {
+            writeln!(out, "    {}", paint(&tx.txid, Role::Muted))?;
+        }
+    }
+    Ok(())
+}
+
+// Compact block contents, and how far a reconstruction got if one was attempted
+pub fn write_compact_block(out: &mut dyn Write, block: &CompactBlock, reconstruction: Option<&Reconstruction>) -> io::Result<()> {
+    writeln!(out)?;
+    writeln!(out, "{}", paint("Compact Block", Role::Heading).bold())?;
+    writeln!(out, "{}", paint(&"─".repeat(60), Role::Muted))?;
+    writeln!(out, "  {} {}", paint("Hash:", Role::Label).bold(), paint(&block.header.hash, Role::Hash))?;
+    writeln!(out, "  {} {}", paint("Nonce:", Role::Label).bold(), block.nonce)?;
+    writeln!(out, "  {} {} ({} short ids, {} prefilled)", paint("Transactions:", Role::Label).bold(),
+        block.transaction_count(), block.short_ids.len(), block.prefilled.len())?;
+    for prefilled in &block.prefilled {
+        writeln!(out, "    #{:<5} {}", prefilled.index, paint(&prefilled.transaction.txid, Role::Muted))?;
+    }
+
+    if let Some(reconstruction) = reconstruction {
+        let filled = reconstruction.transactions.iter().filter(|tx| tx.is_some()).count();
+        let role = if filled == reconstruction.transactions.len() { Role::Good } else { Role::Warn };
+        writeln!(out, "  {} {}", paint("Reconstructed:", Role::Label).bold(),
+            paint(&format!("{} of {} transactions", filled, reconstruction.transactions.len()), role))?;
+        for (label, positions) in [("Missing:", &reconstruction.missing), ("Collisions:", &reconstruction.collisions)] {
+            if !positions.is_empty() {
+                let list: Vec<String> = positions.iter().map(|p| format!("#{}", p)).collect();
+                writeln!(out, "  {} {}", paint(label, Role::Label).bold(), list.join(", "))?;
+            }
+        }
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+// Thousands separators for large difficulties, a few decimals for small ones
+fn format_difficulty(difficulty: f64) -> String {
+    if difficulty < 1000.0 {
+        format!("{:.8}", difficulty)
+    } else {
+        group_thousands(difficulty.round() as u64)
+    }
+}
+
+// Merkle root of a rebuilt stratum job; the coinbase itself is rendered
+// like any other transaction
+pub fn write_stratum_coinbase(out: &mut dyn Write, result: &StratumCoinbase, branch_len: usize) -> io::Result<()> {
+    writeln!(out, "{} {}", paint("Merkle Root:", Role::Label).bold(), paint(&result.merkle_root, Role::Hash))?;
+    writeln!(out, "{} {}", paint("Header Order:", Role::Label).bold(), result.merkle_root_header)?;
+    writeln!(out, "{} {} branch hash(es)", paint("Branch:", Role::Label).bold(), branch_len)
+}
+
+// Message header line, then the payload for blocks, compact blocks and
+// undecoded commands; a transaction payload is left to the caller, which
+// renders it in the chosen format
+pub fn write_p2p_message(out: &mut dyn Write, message: &P2pMessage, payload: &WirePayload) -> io::Result<()> {
+    let checksum = if message.checksum_valid {
+        paint("checksum ok", Role::Good)
+    } else {
+        paint("checksum mismatch", Role::Bad).bold()
+    };
+    writeln!(out, "{} {} ({}, {} bytes, {})", paint("Message:", Role::Label).bold(), paint(&message.command, Role::Accent).bold(),
+        message.network.as_deref().unwrap_or(&message.magic), message.length, checksum)?;
+    match payload {
+        WirePayload::Tx(_) => Ok(()),
+        WirePayload::Block(block) => write_block_summary(out, block, true),
+        WirePayload::CompactBlock(block) => write_compact_block(out, block, None),
+        WirePayload::Unsupported => writeln!(out, "  {}", paint("payload not decoded", Role::Muted)),
+    }
+}
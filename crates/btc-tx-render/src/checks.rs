@@ -0,0 +1,59 @@
+//! Pass/fail reports: policy checks, canonical serialization and the
+//! stress self-check
+
+use btc_tx_parser::{CanonicalReport, PolicyReport, RuleStatus, StressReport};
+use colored::Colorize;
+use std::io::{self, Write};
+use crate::diff::write_byte_diff;
+use crate::theme::{paint, Role};
+
+pub fn write_policy_report(out: &mut dyn Write, report: &PolicyReport) -> io::Result<()> {
+    writeln!(out, "Policy check: {} against {}", report.txid, report.profile.as_deref().unwrap_or("profile"))?;
+    for result in &report.results {
+        let status = match result.status {
+            RuleStatus::Pass => paint("PASS", Role::Good).bold(),
+            RuleStatus::Fail => paint("FAIL", Role::Bad).bold(),
+            RuleStatus::Skipped => paint("SKIP", Role::Warn).bold(),
+        };
+        writeln!(out, "  [{}] {}: {}", status, result.rule, result.detail)?;
+    }
+    let verdict = if report.passed { paint("compliant", Role::Good).bold() } else { paint("violations found", Role::Bad).bold() };
+    writeln!(out, "Result: {}", verdict)
+}
+
+// Verdict, and for non-canonical input the issues, the field diff and the
+// canonical hex
+pub fn write_canonical_report(out: &mut dyn Write, report: &CanonicalReport) -> io::Result<()> {
+    if report.canonical {
+        return writeln!(out, "{}", paint("Canonical serialization", Role::Good).bold());
+    }
+    writeln!(out, "{}", paint("Not canonical", Role::Bad).bold())?;
+    for issue in &report.issues {
+        writeln!(out, "  {} {}", paint("!", Role::Bad).bold(), issue)?;
+    }
+    writeln!(out)?;
+    write_byte_diff(out, &report.diff)?;
+    if let Some(hex) = &report.canonical_hex {
+        writeln!(out, "\n{}\n{}", paint("Canonical hex:", Role::Label).bold(), hex)?;
+    }
+    Ok(())
+}
+
+pub fn write_stress_report(out: &mut dyn Write, report: &StressReport) -> io::Result<()> {
+    writeln!(out, "Stress test: {} transactions ({} bytes) from seed {}", report.count, report.bytes, report.seed)?;
+    writeln!(out, "  Parse:     {:.3}s, {:.0} tx/s, {:.1} MiB/s", report.parse_secs, report.parse_tx_per_sec, report.parse_mib_per_sec)?;
+    writeln!(out, "  Serialize: {:.3}s", report.serialize_secs)?;
+    for mismatch in &report.mismatches {
+        writeln!(out, "  [{}] #{}: {}", paint("FAIL", Role::Bad).bold(), mismatch.index, mismatch.problem)?;
+        writeln!(out, "        {}", mismatch.hex)?;
+    }
+    if report.mismatch_count > report.mismatches.len() as u64 {
+        writeln!(out, "  ... and {} more", report.mismatch_count - report.mismatches.len() as u64)?;
+    }
+    let verdict = if report.passed() {
+        paint("all round-trips match", Role::Good).bold()
+    } else {
+        paint(&format!("{} mismatches", report.mismatch_count), Role::Bad).bold()
+    };
+    writeln!(out, "Result: {}", verdict)
+}
@@ -0,0 +1,37 @@
+//! Field-by-field byte diff
+
+use btc_tx_parser::{ByteDiff, FieldBytes};
+use colored::Colorize;
+use std::io::{self, Write};
+use crate::theme::{paint, Role};
+
+pub fn write_byte_diff(out: &mut dyn Write, diff: &ByteDiff) -> io::Result<()> {
+    writeln!(out, "Byte diff: {} bytes vs {} bytes", diff.a_size, diff.b_size)?;
+    if diff.is_identical() {
+        writeln!(out, "  {}", paint("Serializations are identical", Role::Good))?;
+        return Ok(());
+    }
+
+    for change in &diff.changes {
+        let location = |side: &Option<FieldBytes>| match side {
+            Some(bytes) => format!("@{} ({} bytes)", bytes.offset, bytes.hex.len() / 2),
+            None => "absent".to_string(),
+        };
+        writeln!(out, "  {}  a {}  b {}", paint(&change.field, Role::Label).bold(), location(&change.a), location(&change.b))?;
+        for (sign, side) in [("-", &change.a), ("+", &change.b)] {
+            let Some(bytes) = side else { continue };
+            let shown: String = (0..bytes.hex.len() / 2)
+                .map(|i| {
+                    let byte = &bytes.hex[i * 2..i * 2 + 2];
+                    if change.differing_offsets.contains(&i) {
+                        paint(byte, Role::Bad).bold().to_string()
+                    } else {
+                        byte.to_string()
+                    }
+                })
+                .collect();
+            writeln!(out, "    {} {}", sign, shown)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,70 @@
+//! Elements/Liquid transaction view (`elements` feature)
+
+use btc_tx_parser::{ConfidentialAsset, ConfidentialValue, ElementsTransaction};
+use colored::Colorize;
+use std::io::{self, Write};
+use crate::theme::{paint, Role};
+use crate::transaction::group_thousands;
+
+pub fn write_elements(out: &mut dyn Write, tx: &ElementsTransaction) -> io::Result<()> {
+    writeln!(out)?;
+    writeln!(out, "{}", paint("Elements Transaction", Role::Heading).bold())?;
+    writeln!(out, "{}", paint(&"─".repeat(60), Role::Muted))?;
+    writeln!(out, "  {} {}", paint("TXID:", Role::Label).bold(), paint(&tx.txid, Role::Hash))?;
+    writeln!(out, "  {} {}, {} {}", paint("Version:", Role::Label).bold(), tx.version, paint("Locktime:", Role::Label).bold(), tx.locktime)?;
+    writeln!(out, "  {} {} bytes, {} WU", paint("Size:", Role::Label).bold(), tx.size, tx.weight)?;
+    writeln!(out, "  {} {} sats", paint("Fee:", Role::Label).bold(), paint(&group_thousands(tx.fee.to_sat()), Role::Amount))?;
+    writeln!(out)?;
+
+    writeln!(out, "{} ({})", paint("Inputs", Role::Heading).bold(), tx.inputs.len())?;
+    writeln!(out, "{}", paint(&"─".repeat(60), Role::Muted))?;
+    for input in &tx.inputs {
+        let kind = match (input.is_coinbase, input.is_pegin) {
+            (true, _) => " (coinbase)",
+            (_, true) => " (peg-in)",
+            _ => "",
+        };
+        writeln!(out, "  #{:<3} {}:{}{}", input.index, paint(&input.txid, Role::Hash), input.vout, kind)?;
+        if let Some(issuance) = &input.issuance {
+            let what = if issuance.is_reissuance { "reissues" } else { "issues" };
+            writeln!(out, "       {} {} units, {} inflation keys", what, describe_value(&issuance.amount), describe_value(&issuance.inflation_keys))?;
+        }
+        if !input.witness.is_empty() || !input.pegin_witness.is_empty() {
+            writeln!(out, "       {}", paint(&format!("witness {} items, peg-in witness {} items", input.witness.len(), input.pegin_witness.len()), Role::Muted))?;
+        }
+    }
+    writeln!(out)?;
+
+    writeln!(out, "{} ({})", paint("Outputs", Role::Heading).bold(), tx.outputs.len())?;
+    writeln!(out, "{}", paint(&"─".repeat(60), Role::Muted))?;
+    for output in &tx.outputs {
+        let script = if output.is_fee { "fee".to_string() } else { output.script_type.to_string() };
+        writeln!(out, "  #{:<3} {:<26} {}", output.index, describe_value(&output.value), paint(&script, Role::Label))?;
+        let asset = match &output.asset {
+            ConfidentialAsset::Null => paint("none", Role::Muted),
+            ConfidentialAsset::Explicit(id) => paint(id, Role::Hash),
+            ConfidentialAsset::Confidential(_) => paint("confidential", Role::Warn),
+        };
+        writeln!(out, "       {} {}", paint("asset", Role::Muted), asset)?;
+    }
+    writeln!(out)?;
+
+    if !tx.unsupported.is_empty() {
+        writeln!(out, "{} ({})", paint("Not Decoded", Role::Heading).bold(), tx.unsupported.len())?;
+        writeln!(out, "{}", paint(&"─".repeat(60), Role::Muted))?;
+        for field in &tx.unsupported {
+            writeln!(out, "  {}", paint(field, Role::Warn))?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+// In units of the asset, which are satoshis only for L-BTC
+fn describe_value(value: &ConfidentialValue) -> String {
+    match value {
+        ConfidentialValue::Null => "no".to_string(),
+        ConfidentialValue::Explicit(amount) => group_thousands(amount.to_sat()),
+        ConfidentialValue::Confidential(_) => "confidential".to_string(),
+    }
+}
@@ -0,0 +1,45 @@
+//! Bipartite "plausible flows" view: inputs down the side, outputs across
+//! the top, each cell the share of interpretations linking the two
+
+use btc_tx_parser::{PlausibleFlows, Transaction};
+use colored::Colorize;
+use std::io::{self, Write};
+use crate::theme::{paint, Role};
+use crate::transaction::group_thousands;
+
+pub fn write_plausible_flows(out: &mut dyn Write, tx: &Transaction, flows: &PlausibleFlows) -> io::Result<()> {
+    writeln!(out)?;
+    writeln!(out, "{}", paint("Plausible Flows", Role::Heading).bold())?;
+    writeln!(out, "{}", paint(&"─".repeat(60), Role::Muted))?;
+    writeln!(out, "  {} interpretation(s) fit the values. Each cell is the share of them", flows.interpretations)?;
+    writeln!(out, "  in which the input paid for the output; 100% is a certain link.")?;
+    writeln!(out)?;
+
+    let header: String = flows.outputs.iter()
+        .map(|o| {
+            let label = format!("out #{}", o.output);
+            format!("{:>10}", label)
+        })
+        .collect();
+    writeln!(out, "  {:<23}{}", "", paint(&header, Role::Label).bold())?;
+    let values: String = flows.outputs.iter().map(|o| format!("{:>10}", group_thousands(o.value.to_sat()))).collect();
+    writeln!(out, "  {:<23}{}", "", paint(&values, Role::Amount))?;
+
+    for input in &tx.inputs {
+        let value = input.value.map(|v| group_thousands(v.to_sat())).unwrap_or_default();
+        write!(out, "  {} {}", paint(&format!("in #{:<3}", input.index), Role::Label).bold(),
+            paint(&format!("{:>15}", value), Role::Amount))?;
+        for output in &flows.outputs {
+            let probability = output.funders.iter().find(|f| f.input == input.index).map_or(0.0, |f| f.probability);
+            let cell = match probability {
+                0.0 => paint(&format!("{:>10}", "·"), Role::Muted),
+                1.0 => paint(&format!("{:>10}", "100%"), Role::Warn).bold(),
+                p => paint(&format!("{:>9.0}%", p * 100.0), Role::Accent),
+            };
+            write!(out, "{}", cell)?;
+        }
+        writeln!(out)?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
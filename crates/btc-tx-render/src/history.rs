@@ -0,0 +1,41 @@
+//! Address funding and spending timeline
+
+use btc_tx_parser::{AddressActivity, Amount};
+use colored::Colorize;
+use std::io::{self, Write};
+use crate::theme::{paint, Role};
+use crate::transaction::group_thousands;
+
+// One line per transaction, newest first, e.g.
+// "  840,000  f4184fc5...831e9e16  -10.00000000 BTC  in: output #1, out: input #0"
+pub fn write_address_history(out: &mut dyn Write, address: &str, history: &[AddressActivity]) -> io::Result<()> {
+    writeln!(out, "History of {} ({} transaction(s), newest first)", address, history.len())?;
+    for activity in history {
+        let height = activity.height
+            .map(|h| group_thousands(h as u64))
+            .unwrap_or_else(|| "mempool".to_string());
+        let txid = format!("{}...{}", &activity.txid[..8], &activity.txid[activity.txid.len() - 8..]);
+        let net = activity.net_sats();
+        let role = if net < 0 { Role::Bad } else { Role::Good };
+        let amount = format!("{}{}", if net < 0 { "-" } else { "+" }, Amount::from_sat(net.unsigned_abs()));
+        let mut parts = Vec::new();
+        if !activity.funded_outputs.is_empty() {
+            parts.push(format!("in: {}", list("output", &activity.funded_outputs)));
+        }
+        if !activity.spent_inputs.is_empty() {
+            parts.push(format!("out: {}", list("input", &activity.spent_inputs)));
+        }
+        writeln!(out, "  {:>9}  {}  {}  {}", height, paint(&txid, Role::Hash), paint(&format!("{:>17}", amount), role), paint(&parts.join(", "), Role::Muted))?;
+    }
+    let net: i64 = history.iter().map(AddressActivity::net_sats).sum();
+    let sign = if net < 0 { "-" } else { "+" };
+    writeln!(out, "  Net over these transactions: {}", paint(&format!("{}{}", sign, Amount::from_sat(net.unsigned_abs())), Role::Amount).bold())?;
+    Ok(())
+}
+
+// "output #0" or "outputs #0, #2"
+fn list(kind: &str, indices: &[usize]) -> String {
+    let numbers: Vec<String> = indices.iter().map(|i| format!("#{}", i)).collect();
+    let plural = if indices.len() == 1 { "" } else { "s" };
+    format!("{}{} {}", kind, plural, numbers.join(", "))
+}
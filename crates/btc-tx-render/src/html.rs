@@ -0,0 +1,181 @@
+//! Self-contained HTML report for attaching transaction analyses to tickets
+
+use btc_tx_parser::{Finding, Transaction};
+use std::fmt::Write;
+
+const STYLE: &str = "
+body { font-family: -apple-system, 'Segoe UI', sans-serif; margin: 2em auto; max-width: 1000px; color: #222; }
+h1 { border-bottom: 2px solid #f7931a; padding-bottom: .3em; }
+h2 { color: #444; margin-top: 1.6em; }
+table { border-collapse: collapse; width: 100%; font-size: 13px; }
+th, td { border: 1px solid #ddd; padding: 4px 8px; text-align: left; vertical-align: top; }
+th { background: #f6f6f6; }
+code { font-family: 'SFMono-Regular', Consolas, monospace; word-break: break-all; }
+.meta { color: #777; font-size: 12px; }
+.note td { background: #fffbe6; font-style: italic; }
+";
+
+// Build the full HTML document for a transaction
+pub fn render_html(tx: &Transaction, findings: &[Finding], labels: &[String], generated_at: &str) -> String {
+    let mut html = String::new();
+
+    let _ = writeln!(html, "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">");
+    let _ = writeln!(html, "<title>Transaction report {}</title>", escape(&tx.txid));
+    let _ = writeln!(html, "<style>{}</style>\n</head>\n<body>", STYLE);
+    let _ = writeln!(html, "<h1>Bitcoin Transaction Report</h1>");
+    let _ = writeln!(html, "<p class=\"meta\">Generated {} by btc-tx-inspector {}</p>",
+        escape(generated_at), env!("CARGO_PKG_VERSION"));
+
+    if !labels.is_empty() {
+        let _ = writeln!(html, "<p><strong>Labels:</strong> {}</p>",
+            labels.iter().map(|l| escape(l)).collect::<Vec<_>>().join(", "));
+    }
+
+    let _ = writeln!(html, "<h2>Transaction Info</h2>\n<table>");
+    row(&mut html, "TXID", &format!("<code>{}</code>", escape(&tx.txid)));
+    if tx.is_segwit {
+        row(&mut html, "WTXID", &format!("<code>{}</code>", escape(&tx.wtxid)));
+    }
+    row(&mut html, "Version", &tx.version.to_string());
+    row(&mut html, "SegWit", if tx.is_segwit { "Yes" } else { "No" });
+    row(&mut html, "Size", &format!("{} bytes", tx.raw_size));
+    row(&mut html, "Virtual Size", &format!("{} vbytes", tx.vsize()));
+    row(&mut html, "Weight", &format!("{} WU", tx.weight));
+    row(&mut html, "Locktime", &tx.locktime.to_string());
+    row(&mut html, "Total Output", &format!("{} sats ({})", tx.total_output_satoshis.to_sat(), tx.total_output_satoshis));
+    if let Some(fee) = tx.fee_satoshis {
+        row(&mut html, "Fee", &format!("{} sats ({:.2} sat/vB)", fee.to_sat(), fee.to_sat() as f64 / tx.vsize() as f64));
+    }
+    let _ = writeln!(html, "</table>");
+
+    let _ = writeln!(html, "<h2>Flow</h2>");
+    html.push_str(&render_flow_svg(tx));
+
+    let _ = writeln!(html, "<h2>Inputs ({})</h2>", tx.inputs.len());
+    let _ = writeln!(html, "<table>\n<tr><th>#</th><th>Spends</th><th>Value</th><th>Sequence</th><th>ScriptSig / Witness</th></tr>");
+    for input in &tx.inputs {
+        let spends = if input.is_coinbase {
+            "Coinbase".to_string()
+        } else {
+            format!("<code>{}:{}</code>", escape(&input.txid.to_string()), input.vout)
+        };
+        let spends = match &input.address {
+            Some(address) => format!("{}<br><code>{}</code>", spends, escape(&address.mainnet)),
+            None => spends,
+        };
+        let value = input.value.map(|v| format!("{} sats", v)).unwrap_or_else(|| "unknown".to_string());
+        let mut unlocking = format!("<code>{}</code>", escape(&input.script_sig.asm));
+        if let Some(coinbase) = input.coinbase.as_ref().map(crate::transaction::describe_coinbase).filter(|text| !text.is_empty()) {
+            let _ = write!(unlocking, "<br><em>{}</em>", escape(&coinbase));
+        }
+        if let Some(parts) = input.script_sig_parts.as_ref().filter(|parts| parts.wraps.is_some()) {
+            let _ = write!(unlocking, "<br><em>{}</em>", escape(&parts.describe()));
+        }
+        if let Some(witness) = &input.witness {
+            for item in witness {
+                let _ = write!(unlocking, "<br><code>{}</code>", hex::encode(item));
+            }
+        }
+        let _ = writeln!(html, "<tr><td>{}</td><td>{}</td><td>{}</td><td>0x{:08x}<br>{}</td><td>{}</td></tr>",
+            input.index, spends, value, input.sequence, crate::transaction::describe_sequence(tx, input.sequence), unlocking);
+        if let Some(note) = &input.note {
+            let _ = writeln!(html, "<tr class=\"note\"><td></td><td colspan=\"4\">Note: {}</td></tr>", escape(note));
+        }
+    }
+    let _ = writeln!(html, "</table>");
+
+    let _ = writeln!(html, "<h2>Outputs ({})</h2>", tx.outputs.len());
+    let _ = writeln!(html, "<table>\n<tr><th>#</th><th>Value</th><th>Type</th><th>Address</th><th>Script</th></tr>");
+    for output in &tx.outputs {
+        let address = output.address.as_ref().map(|a| escape(&a.mainnet)).unwrap_or_default();
+        let _ = writeln!(html, "<tr><td>{}</td><td>{} sats</td><td>{}</td><td><code>{}</code></td><td><code>{}</code></td></tr>",
+            output.index, output.value, escape(&output.script_type.to_string()), address, escape(&output.script_pubkey.asm));
+        if let Some(note) = &output.note {
+            let _ = writeln!(html, "<tr class=\"note\"><td></td><td colspan=\"4\">Note: {}</td></tr>", escape(note));
+        }
+    }
+    let _ = writeln!(html, "</table>");
+
+    let _ = writeln!(html, "<h2>Analysis</h2>");
+    if findings.is_empty() {
+        let _ = writeln!(html, "<p class=\"meta\">No findings.</p>");
+    } else {
+        let _ = writeln!(html, "<table>\n<tr><th>Finding</th><th>Location</th><th>Details</th></tr>");
+        for finding in findings {
+            let location = match (finding.input, finding.output) {
+                (Some(i), _) => format!("input #{}", i),
+                (_, Some(o)) => format!("output #{}", o),
+                _ => "transaction".to_string(),
+            };
+            // the evidence behind a heuristic shows as a tooltip
+            let _ = writeln!(html, "<tr><td>{}</td><td>{}</td><td title=\"{}\">{}{}</td></tr>",
+                escape(&finding.kind), location, escape(&finding.evidence.join("\n")),
+                escape(&finding.message), escape(&crate::transaction::describe_confidence(finding)));
+        }
+        let _ = writeln!(html, "</table>");
+    }
+
+    let _ = writeln!(html, "<h2>Raw Data</h2>\n<pre><code>{}</code></pre>",
+        escape(&serde_json::to_string_pretty(tx).unwrap_or_default()));
+    let _ = writeln!(html, "</body>\n</html>");
+    html
+}
+
+// Inputs on the left, outputs on the right, edges through the transaction
+// node; probable change links go straight from input to output, dashed
+fn render_flow_svg(tx: &Transaction) -> String {
+    const ROW: usize = 28;
+    const WIDTH: usize = 900;
+    let rows = tx.inputs.len().max(tx.outputs.len()).max(1);
+    let height = rows * ROW + 20;
+    let center = (WIDTH / 2, height / 2);
+
+    let mut svg = String::new();
+    let _ = writeln!(svg, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" font-size=\"12\" font-family=\"monospace\">", WIDTH, height);
+
+    for (i, input) in tx.inputs.iter().enumerate() {
+        let y = 20 + i * ROW;
+        let label = if input.is_coinbase {
+            "coinbase".to_string()
+        } else {
+            format!("{}…:{}", &input.txid.to_string()[..12], input.vout)
+        };
+        let _ = writeln!(svg, "<text x=\"10\" y=\"{}\">{}</text>", y + 4, escape(&label));
+        let _ = writeln!(svg, "<line x1=\"200\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"#999\"/>", y, center.0 - 30, center.1);
+    }
+
+    for (i, output) in tx.outputs.iter().enumerate() {
+        let y = 20 + i * ROW;
+        let address = output.address.as_ref()
+            .map(|a| a.mainnet.chars().take(20).collect::<String>())
+            .unwrap_or_else(|| "[script]".to_string());
+        let _ = writeln!(svg, "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"#f7931a\"/>", center.0 + 30, center.1, WIDTH - 330, y);
+        let _ = writeln!(svg, "<text x=\"{}\" y=\"{}\">{} → {}</text>", WIDTH - 320, y + 4, output.value, escape(&address));
+    }
+
+    // probable change: dashed, bowing away from the transaction node
+    for link in tx.provenance_links() {
+        let (y1, y2) = (20 + link.input * ROW, 20 + link.output * ROW);
+        let bend = if (y1 + y2) / 2 < center.1 { 0 } else { height };
+        let _ = writeln!(svg, "<path d=\"M 200 {} C {} {}, {} {}, {} {}\" fill=\"none\" stroke=\"#3b82f6\" stroke-dasharray=\"6 4\" opacity=\"{:.2}\"><title>{} ({:.0}% confidence)</title></path>",
+            y1, center.0 - 80, bend, center.0 + 80, bend, WIDTH - 330, y2, link.confidence, escape(&link.detail), link.confidence * 100.0);
+        let _ = writeln!(svg, "<text x=\"{}\" y=\"{}\" fill=\"#3b82f6\" text-anchor=\"middle\">{:.0}%</text>",
+            center.0, (y1 + y2 + 6 * bend) / 8, link.confidence * 100.0);
+    }
+
+    let _ = writeln!(svg, "<rect x=\"{}\" y=\"{}\" width=\"60\" height=\"24\" rx=\"4\" fill=\"#f7931a\"/>", center.0 - 30, center.1 - 12);
+    let _ = writeln!(svg, "<text x=\"{}\" y=\"{}\" fill=\"#fff\" text-anchor=\"middle\">tx</text>", center.0, center.1 + 4);
+    let _ = writeln!(svg, "</svg>");
+    svg
+}
+
+fn row(html: &mut String, key: &str, value: &str) {
+    let _ = writeln!(html, "<tr><th>{}</th><td>{}</td></tr>", key, value);
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
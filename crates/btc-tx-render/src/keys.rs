@@ -0,0 +1,42 @@
+//! Addresses of the public keys revealed by a transaction's inputs, for
+//! pivoting to other coins of the same key, and every key in the
+//! transaction with its encoding checked
+
+use btc_tx_parser::{KeyAddresses, PubkeyInfo};
+use colored::Colorize;
+use std::io::{self, Write};
+use crate::theme::{paint, Role};
+
+pub fn write_key_addresses(out: &mut dyn Write, keys: &[KeyAddresses]) -> io::Result<()> {
+    writeln!(out)?;
+    writeln!(out, "{} ({})", paint("Revealed Keys", Role::Heading).bold(), keys.len())?;
+    writeln!(out, "{}", paint(&"─".repeat(60), Role::Muted))?;
+    for key in keys {
+        let inputs: Vec<String> = key.inputs.iter().map(|i| format!("#{}", i)).collect();
+        writeln!(out, "  {} {}", paint(&key.pubkey, Role::Hash), paint(&format!("(input {})", inputs.join(", ")), Role::Muted))?;
+        if key.addresses.is_empty() {
+            writeln!(out, "    {}", paint("not a valid secp256k1 point", Role::Warn))?;
+        }
+        for address in &key.addresses {
+            writeln!(out, "    {} {}", paint(&format!("{:<20}", address.address_type), Role::Label), paint(&address.mainnet, Role::Hash))?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+pub fn write_pubkeys(out: &mut dyn Write, keys: &[PubkeyInfo]) -> io::Result<()> {
+    let invalid = keys.iter().filter(|k| !k.is_valid()).count();
+    writeln!(out)?;
+    writeln!(out, "{} ({}, {} invalid)", paint("Public Keys", Role::Heading).bold(), keys.len(), invalid)?;
+    writeln!(out, "{}", paint(&"─".repeat(60), Role::Muted))?;
+    for key in keys {
+        writeln!(out, "  {} {}", paint(&key.field(), Role::Label), paint(&format!("({})", key.encoding), Role::Muted))?;
+        writeln!(out, "    {}", paint(&key.pubkey, Role::Hash))?;
+        if let Some(issue) = &key.issue {
+            writeln!(out, "    {}", paint(issue, Role::Bad))?;
+        }
+    }
+    writeln!(out)?;
+    Ok(())
+}
@@ -0,0 +1,148 @@
+//! Text renderers for transactions and the reports built on them
+//!
+//! Every renderer writes to an `io::Write` sink, so the same output can go to
+//! stdout (CLI), a buffer (tests, WASM) or a response body. Colors come from
+//! the `colored` crate; call `colored::control::set_override(false)` for plain
+//! text.
+
+mod transaction;
+mod psbt;
+mod timelock;
+mod batch;
+mod checks;
+mod diff;
+mod block;
+mod lightning;
+mod flows;
+mod history;
+mod keys;
+mod html;
+mod theme;
+#[cfg(feature = "elements")]
+mod elements;
+
+#[cfg(test)]
+mod tests;
+
+use btc_tx_parser::{ExactBtc, Finding, PrivacyReport, Transaction, TxShape};
+use serde::Serialize;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+pub use transaction::{write_pretty, write_summary, write_ascii, write_findings, write_shape, write_timestamp_anchor, write_privacy, describe_privacy, describe_signature, describe_sequence, describe_coinbase, describe_payload, describe_htlc, describe_spend, write_extracted_inscriptions, describe_confidence, LARGE_DATA_BYTES};
+pub use psbt::{write_psbt, write_psbt_readiness};
+pub use timelock::{write_unlock_schedule, write_coin_age, format_unix_time};
+pub use batch::{write_batch_report, write_cache_stats, write_template_report};
+pub use checks::{write_policy_report, write_canonical_report, write_stress_report};
+pub use diff::write_byte_diff;
+pub use block::{write_block_header, write_block_summary, write_compact_block, write_stratum_coinbase, write_p2p_message};
+pub use lightning::write_channel_close;
+pub use flows::write_plausible_flows;
+pub use history::write_address_history;
+pub use keys::{write_key_addresses, write_pubkeys};
+pub use html::render_html;
+pub use theme::{paint, set_theme, theme, Role, Theme};
+#[cfg(feature = "elements")]
+pub use elements::write_elements;
+
+// Transaction output formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Pretty,
+    Json,
+    Summary,
+    Ascii,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "pretty" => Ok(Format::Pretty),
+            "json" => Ok(Format::Json),
+            "summary" => Ok(Format::Summary),
+            "ascii" => Ok(Format::Ascii),
+            other => Err(format!("Unknown format '{}' (expected pretty, json, summary or ascii)", other)),
+        }
+    }
+}
+
+// How JSON is written: indented or on one line, and with the float BTC
+// twins as numbers or exact strings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct JsonStyle {
+    pub compact: bool,
+    pub exact_btc: bool,
+}
+
+// Transaction JSON with analyzer findings alongside the parsed fields
+#[derive(Serialize)]
+pub struct AnalyzedTransaction<'a> {
+    #[serde(flatten)]
+    pub tx: &'a Transaction,
+    pub analysis: &'a [Finding],
+    pub tx_shape: TxShape,
+    pub privacy: PrivacyReport,
+}
+
+// Force ANSI colors on or off for every renderer
+pub fn set_color(enabled: bool) {
+    colored::control::set_override(enabled);
+}
+
+// Render into a String; convenient where no stream is at hand (e.g. WASM)
+pub fn render_to_string(tx: &Transaction, format: Format, analyze: bool, style: JsonStyle) -> String {
+    let mut buffer = Vec::new();
+    // writing to a Vec cannot fail
+    let _ = write_transaction(&mut buffer, tx, format, analyze, style);
+    String::from_utf8_lossy(&buffer).into_owned()
+}
+
+// Render a transaction in the chosen format, with analyzer findings if requested
+pub fn write_transaction(out: &mut dyn Write, tx: &Transaction, format: Format, analyze: bool, style: JsonStyle) -> io::Result<()> {
+    let findings = analyze.then(|| tx.analyze());
+    write_transaction_with_findings(out, tx, format, findings.as_deref(), style)
+}
+
+// Like `write_transaction`, with findings from a caller-configured registry
+pub fn write_transaction_with_findings(out: &mut dyn Write, tx: &Transaction, format: Format, findings: Option<&[Finding]>, style: JsonStyle) -> io::Result<()> {
+    match (format, findings) {
+        (Format::Pretty, findings) => {
+            write_pretty(out, tx)?;
+            if let Some(findings) = findings {
+                write_findings(out, findings)?;
+                write_shape(out, &tx.shape())?;
+                write_privacy(out, &tx.privacy())?;
+            }
+        }
+        (Format::Json, Some(findings)) => {
+            write_json(out, &AnalyzedTransaction { tx, analysis: findings, tx_shape: tx.shape(), privacy: tx.privacy() }, style)?
+        }
+        (Format::Json, None) => write_json(out, tx, style)?,
+        (Format::Summary, findings) => {
+            write_summary(out, tx)?;
+            if let Some(findings) = findings {
+                writeln!(out, "\nAnalysis:")?;
+                for finding in findings {
+                    writeln!(out, "  [{}] {}{}", finding.kind, finding.message, describe_confidence(finding))?;
+                }
+                writeln!(out, "\nShape: {}", tx.shape())?;
+                writeln!(out, "Privacy: {}", describe_privacy(&tx.privacy()))?;
+            }
+        }
+        (Format::Ascii, _) => write_ascii(out, tx)?,
+    }
+    Ok(())
+}
+
+// JSON output
+pub fn write_json<T: serde::Serialize + ?Sized>(out: &mut dyn Write, value: &T, style: JsonStyle) -> io::Result<()> {
+    match (style.compact, style.exact_btc) {
+        (true, false) => serde_json::to_writer(&mut *out, value)?,
+        (true, true) => serde_json::to_writer(&mut *out, &ExactBtc(value))?,
+        (false, false) => serde_json::to_writer_pretty(&mut *out, value)?,
+        (false, true) => serde_json::to_writer_pretty(&mut *out, &ExactBtc(value))?,
+    }
+    writeln!(out)
+}
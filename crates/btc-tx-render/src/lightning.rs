@@ -0,0 +1,80 @@
+//! Lightning channel close breakdown
+
+use btc_tx_parser::{ChannelClose, ChannelOutputRole, CloseKind};
+use colored::Colorize;
+use std::io::{self, Write};
+use crate::theme::{paint, Role};
+use crate::transaction::group_thousands;
+
+pub fn write_channel_close(out: &mut dyn Write, close: &ChannelClose) -> io::Result<()> {
+    writeln!(out)?;
+    writeln!(out, "{}", paint("Channel Close", Role::Heading).bold())?;
+    writeln!(out, "{}", paint(&"─".repeat(60), Role::Muted))?;
+    let kind = match close.kind {
+        CloseKind::Cooperative => paint("cooperative", Role::Good),
+        CloseKind::ForceClose => paint("force close (commitment transaction)", Role::Warn).bold(),
+    };
+    writeln!(out, "  {} {}", paint("Type:", Role::Label).bold(), kind)?;
+    writeln!(out, "  {} input #{}", paint("Funding:", Role::Label).bold(), close.funding_input)?;
+    for key in &close.funding_pubkeys {
+        writeln!(out, "    {}", paint(key, Role::Hash))?;
+    }
+    if close.funding_pubkeys.is_empty() {
+        writeln!(out, "    {}", paint("simple taproot channel (MuSig2 key spend)", Role::Muted))?;
+    }
+    if let Some(capacity) = close.capacity {
+        writeln!(out, "  {} {} sats", paint("Capacity:", Role::Label).bold(), paint(&group_thousands(capacity.to_sat()), Role::Amount))?;
+    }
+    if let Some(number) = close.obscured_commitment_number {
+        writeln!(out, "  {} 0x{:012x}", paint("Obscured Commitment #:", Role::Label).bold(), number)?;
+    }
+    if let Some(number) = close.commitment_number {
+        writeln!(out, "  {} {}", paint("Commitment #:", Role::Label).bold(), number)?;
+    }
+    if let Some(delay) = close.to_self_delay() {
+        writeln!(out, "  {} {} blocks", paint("to_self_delay:", Role::Label).bold(), delay)?;
+    }
+    if close.kind == CloseKind::ForceClose {
+        let channel_type = if close.has_anchors() { "anchor outputs" } else { "no anchors (legacy or static_remotekey)" };
+        writeln!(out, "  {} {}", paint("Channel Type:", Role::Label).bold(), channel_type)?;
+    }
+    writeln!(out)?;
+
+    writeln!(out, "{}", paint("Outputs", Role::Heading).bold())?;
+    writeln!(out, "{}", paint(&"─".repeat(60), Role::Muted))?;
+    for output in &close.outputs {
+        writeln!(out, "  #{:<3} {:>14} sats  {}", output.output,
+            paint(&group_thousands(output.value.to_sat()), Role::Amount), describe_role(&output.role))?;
+    }
+    writeln!(out)?;
+
+    if close.kind == CloseKind::ForceClose {
+        let htlcs = close.pending_htlcs();
+        writeln!(out, "{} ({})", paint("Pending HTLCs", Role::Heading).bold(), htlcs.len())?;
+        writeln!(out, "{}", paint(&"─".repeat(60), Role::Muted))?;
+        if close.outputs.iter().any(|o| o.role == ChannelOutputRole::Unresolved) {
+            writeln!(out, "  {}", paint("supply witness scripts to tell HTLCs from to_local outputs", Role::Muted))?;
+        }
+        for htlc in htlcs {
+            let timeout = match &htlc.role {
+                ChannelOutputRole::ReceivedHtlc { cltv_expiry, .. } => format!("expires at block {}", cltv_expiry),
+                _ => "expiry set by the HTLC-timeout transaction's locktime".to_string(),
+            };
+            writeln!(out, "  #{:<3} {:>14} sats  {}", htlc.output, paint(&group_thousands(htlc.value.to_sat()), Role::Amount), timeout)?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+fn describe_role(role: &ChannelOutputRole) -> String {
+    match role {
+        ChannelOutputRole::Payout => "payout".to_string(),
+        ChannelOutputRole::ToLocal { to_self_delay } => format!("to_local (delayed {} blocks)", to_self_delay),
+        ChannelOutputRole::ToRemote => "to_remote".to_string(),
+        ChannelOutputRole::Anchor => "anchor".to_string(),
+        ChannelOutputRole::OfferedHtlc { payment_hash160 } => format!("offered HTLC {}", payment_hash160),
+        ChannelOutputRole::ReceivedHtlc { payment_hash160, .. } => format!("received HTLC {}", payment_hash160),
+        ChannelOutputRole::Unresolved => "to_local, to_remote or HTLC (script unknown)".to_string(),
+    }
+}
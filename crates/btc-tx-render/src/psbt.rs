@@ -0,0 +1,116 @@
+//! PSBT summary, multisig progress matrix and readiness report
+
+use btc_tx_parser::{InputStatus, MultisigProgress, Psbt, PsbtReadiness, SigningStatus};
+use colored::Colorize;
+use std::io::{self, Write};
+use crate::theme::{paint, Role};
+
+pub fn write_psbt(out: &mut dyn Write, psbt: &Psbt, progress: Option<&MultisigProgress>) -> io::Result<()> {
+    writeln!(out)?;
+    writeln!(out, "{}", paint("Partially Signed Transaction", Role::Heading).bold())?;
+    writeln!(out, "{}", paint(&"─".repeat(60), Role::Muted))?;
+    writeln!(out, "  {} {}", paint("PSBT Version:", Role::Label).bold(), psbt.version)?;
+    writeln!(out, "  {} {}", paint("Unsigned TXID:", Role::Label).bold(), paint(&psbt.unsigned_tx.txid, Role::Hash))?;
+    if let Some(modifiable) = psbt.modifiable {
+        let parts: Vec<&str> = [(modifiable.inputs, "inputs"), (modifiable.outputs, "outputs")]
+            .iter()
+            .filter(|(allowed, _)| *allowed)
+            .map(|(_, part)| *part)
+            .collect();
+        let parts = if parts.is_empty() { "none".to_string() } else { parts.join(", ") };
+        let single = if modifiable.has_sighash_single { " (SIGHASH_SINGLE in use)" } else { "" };
+        writeln!(out, "  {} {}{}", paint("Modifiable:", Role::Label).bold(), parts, single)?;
+    }
+    if psbt.version >= 2 {
+        writeln!(out, "  {} {}", paint("Locktime:", Role::Label).bold(), psbt.unsigned_tx.locktime)?;
+    }
+    writeln!(out, "  {} {} input(s), {} output(s)", paint("Shape:", Role::Label).bold(),
+        psbt.inputs.len(), psbt.outputs.len())?;
+    for input in &psbt.inputs {
+        let tx_input = &psbt.unsigned_tx.inputs[input.index];
+        let value = input.utxo.as_ref()
+            .map(|u| format!("{} sats ({})", u.value, u.script_type))
+            .unwrap_or_else(|| "unknown UTXO".to_string());
+        writeln!(out, "  {} #{} {}:{} {}", paint("Input", Role::Label).bold(), input.index,
+            paint(&tx_input.txid.to_string(), Role::Hash), tx_input.vout, paint(&value, Role::Muted))?;
+    }
+
+    let progress = match progress {
+        Some(progress) => progress,
+        None => {
+            writeln!(out)?;
+            return Ok(());
+        }
+    };
+
+    writeln!(out)?;
+    writeln!(out, "{}", paint("Multisig Signing Progress", Role::Heading).bold())?;
+    writeln!(out, "{}", paint(&"─".repeat(60), Role::Muted))?;
+    let header: String = progress.inputs.iter().map(|i| format!(" in#{:<4}", i.index)).collect();
+    writeln!(out, "  {:<18}{}", "cosigner", header)?;
+    for cosigner in &progress.cosigners {
+        let id = if cosigner.id.len() > 16 { format!("{}…", &cosigner.id[..15]) } else { cosigner.id.clone() };
+        let cells: String = progress.inputs.iter()
+            .map(|input| {
+                let cell = match cosigner.inputs.iter().find(|c| c.index == input.index).map(|c| c.status) {
+                    Some(SigningStatus::Signed) => paint("✔", Role::Good).bold(),
+                    Some(SigningStatus::Pending) => paint("·", Role::Warn),
+                    Some(SigningStatus::Finalized) => paint("■", Role::Good),
+                    None => " ".normal(),
+                };
+                format!(" {:<7}", cell)
+            })
+            .collect();
+        writeln!(out, "  {:<18}{}", id, cells)?;
+    }
+    writeln!(out)?;
+    for input in &progress.inputs {
+        let state = if input.finalized {
+            paint("finalized", Role::Good).bold()
+        } else if input.complete {
+            paint("ready to finalize", Role::Good)
+        } else {
+            paint(&format!("needs {} more", input.required - input.signatures), Role::Warn)
+        };
+        writeln!(out, "  {} #{}: {}-of-{}, {}/{} signatures, {}", paint("Input", Role::Label).bold(),
+            input.index, input.required, input.total, input.signatures, input.required, state)?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+// What is left before the PSBT can be broadcast, and the extracted transaction
+pub fn write_psbt_readiness(out: &mut dyn Write, readiness: &PsbtReadiness) -> io::Result<()> {
+    writeln!(out)?;
+    writeln!(out, "{}", paint("PSBT Readiness", Role::Heading).bold())?;
+    writeln!(out, "{}", paint(&"─".repeat(60), Role::Muted))?;
+    for input in &readiness.inputs {
+        let script_type = input.script_type.as_ref().map(|t| format!(" ({})", t)).unwrap_or_default();
+        let state = match input.status {
+            InputStatus::Finalized => paint("finalized", Role::Good).bold(),
+            InputStatus::Finalizable => paint("ready to finalize", Role::Good),
+            InputStatus::Incomplete => paint(&format!("needs {} more signature(s)", input.signatures_needed), Role::Warn),
+            InputStatus::Unsupported => paint("cannot finalize (unknown UTXO or script)", Role::Bad),
+        };
+        writeln!(out, "  {} #{}{}: {}", paint("Input", Role::Label).bold(), input.index, script_type, state)?;
+        for missing in &input.missing {
+            let who = missing.fingerprint.as_deref().map(|f| format!(" [{}]", f)).unwrap_or_default();
+            writeln!(out, "      {} {}{}", paint("waiting for", Role::Muted), paint(&missing.pubkey, Role::Hash), who)?;
+        }
+    }
+    writeln!(out)?;
+    writeln!(out, "  {} {} vbytes ({} WU)", paint("Expected vSize:", Role::Label).bold(),
+        readiness.expected_vsize, readiness.expected_weight)?;
+    if let (Some(fee), Some(feerate)) = (readiness.fee_satoshis, readiness.feerate) {
+        writeln!(out, "  {} {} sats ({:.2} sat/vB)", paint("Fee:", Role::Label).bold(), paint(&fee.to_string(), Role::Amount), feerate)?;
+    }
+    if readiness.complete {
+        writeln!(out, "  {} {}", paint("Final TXID:", Role::Label).bold(), paint(&readiness.transaction.txid, Role::Hash))?;
+        let hex = readiness.transaction.to_hex();
+        writeln!(out, "  {} {}", paint("Network TX:", Role::Label).bold(), hex)?;
+    } else {
+        writeln!(out, "  {} {}", paint("Status:", Role::Label).bold(), paint("not ready to broadcast", Role::Warn).bold())?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
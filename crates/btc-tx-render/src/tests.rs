@@ -0,0 +1,149 @@
+//Tests for btc-tx-render crate
+
+use crate::{render_html, render_to_string, write_address_history, write_ascii, write_pretty, write_summary, Format, JsonStyle, Role, Theme};
+use colored::Color;
+use btc_tx_parser::{AddressActivity, Amount, OutputSpend, Transaction};
+
+// block 170: the first payment between two people
+const P2P_TX: &str = "0100000001c997a5e56e104102fa209c6a852dd90660a20b2d9c352423edce25857fcd3704000000004847304402204e45e16932b8af514961a1d3a1a25fdf3f4f7732e9d624c6c61548ab5fb8cd410220181522ec8eca07de4860a4acdd12909d831cc56cbbac4622082221a8768d1d0901ffffffff0200ca9a3b00000000434104ae1a62fe09c5f51b13905f07f06b99a2f7159b2225f374cd378d71302fa28414e7aab37397f554a7df5f142c21c1b7303b8a0626f1baded5c72a704f7e6cd84cac00286bee0000000043410411db93e1dcdb8a016b49840f8c53bc1eb68a382e97b1482ecad7b148a6909a5cb2e0eaddfb84ccf9744464f82e160bfa9b8b64f9d4c03f999b8643f656b412a3ac00000000";
+
+// ============================================================================
+// Text Renderer Tests
+// ============================================================================
+
+#[test]
+fn test_summary_to_sink() {
+    let tx = Transaction::from_hex(P2P_TX).unwrap();
+    let mut out = Vec::new();
+    write_summary(&mut out, &tx).unwrap();
+
+    let text = String::from_utf8(out).unwrap();
+    assert!(text.starts_with("Transaction: f4184fc596403b9d638783cf57adfe4c75c605f6356fbc91338530e9831e9e16\n"));
+    assert!(text.contains("  #0: 10.00000000 BTC -> "));
+}
+
+#[test]
+fn test_spend_badges() {
+    let mut tx = Transaction::from_hex(P2P_TX).unwrap();
+    let mut out = Vec::new();
+    write_ascii(&mut out, &tx).unwrap();
+    assert!(!String::from_utf8(out).unwrap().contains("[U]"));
+
+    // as --check-spent would fill them in
+    let spender = "cc".repeat(32);
+    tx.outputs[0].spend = Some(OutputSpend { spent: true, txid: Some(spender.parse().unwrap()), vin: Some(1) });
+    tx.outputs[1].spend = Some(OutputSpend { spent: false, txid: None, vin: None });
+
+    colored::control::set_override(false);
+    let mut out = Vec::new();
+    write_pretty(&mut out, &tx).unwrap();
+    let text = String::from_utf8(out).unwrap();
+    assert!(text.contains(&format!("Status: spent by {}:1", spender)));
+    assert!(text.contains("Status: unspent"));
+
+    let mut out = Vec::new();
+    write_ascii(&mut out, &tx).unwrap();
+    let text = String::from_utf8(out).unwrap();
+    assert!(text.contains("[S] 10.0000 BTC -> "));
+    assert!(text.contains("[U] 40.0000 BTC -> "));
+    assert!(text.contains("[S] spent, [U] unspent"));
+}
+
+#[test]
+fn test_address_history_timeline() {
+    let activity = |txid: &str, height, funded_outputs: Vec<usize>, funded, spent_inputs: Vec<usize>, spent| AddressActivity {
+        txid: txid.repeat(32),
+        height,
+        funded_outputs,
+        funded: Amount::from_sat(funded),
+        spent_inputs,
+        spent: Amount::from_sat(spent),
+    };
+    let history = [
+        activity("bb", None, vec![], 0, vec![0, 2], 150_000_000),
+        activity("aa", Some(840_000), vec![1], 200_000_000, vec![], 0),
+    ];
+
+    colored::control::set_override(false);
+    let mut out = Vec::new();
+    write_address_history(&mut out, "bc1qexample", &history).unwrap();
+    let text = String::from_utf8(out).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines[0], "History of bc1qexample (2 transaction(s), newest first)");
+    assert_eq!(lines[1], "    mempool  bbbbbbbb...bbbbbbbb    -1.50000000 BTC  out: inputs #0, #2");
+    assert_eq!(lines[2], "    840,000  aaaaaaaa...aaaaaaaa    +2.00000000 BTC  in: output #1");
+    assert_eq!(lines[3], "  Net over these transactions: +0.50000000 BTC");
+}
+
+#[test]
+fn test_format_parsing_and_json() {
+    assert_eq!("ASCII".parse::<Format>(), Ok(Format::Ascii));
+    assert!("xml".parse::<Format>().is_err());
+
+    let tx = Transaction::from_hex(P2P_TX).unwrap();
+    let json = render_to_string(&tx, Format::Json, true, JsonStyle { compact: true, exact_btc: false });
+    assert_eq!(json.lines().count(), 1);
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["txid"], tx.txid);
+    assert!(value["analysis"].is_array());
+
+    // exact BTC strings reach through the analyzed wrapper too
+    let json = render_to_string(&tx, Format::Json, true, JsonStyle { compact: false, exact_btc: true });
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value["outputs"][0]["value_btc"], "10.00000000");
+}
+
+#[test]
+fn test_large_data_is_summarized() {
+    // one input with a 1,000-byte witness item, one OP_RETURN output pushing 300 bytes
+    let hex = format!(
+        "02000000000101{}0000000000ffffffff010000000000000000fd30016a4d2c01{}01fde803{}00000000",
+        "aa".repeat(32), "cd".repeat(300), "ab".repeat(1000)
+    );
+    let tx = Transaction::from_hex(&hex).unwrap();
+    let payloads = tx.payloads(256);
+    let fields: Vec<&str> = payloads.iter().map(|p| p.field.as_str()).collect();
+    assert_eq!(fields, vec!["outputs[0].script_pubkey.push[0]", "inputs[0].witness[0]"]);
+
+    let mut out = Vec::new();
+    write_pretty(&mut out, &tx).unwrap();
+    let text = String::from_utf8(out).unwrap();
+    assert!(text.contains(&format!("[0] 1,000 bytes, sha256={}…", &payloads[1].sha256[..16])));
+    assert!(text.contains("OP_RETURN <300 bytes, sha256="));
+    assert!(!text.contains(&"ab".repeat(32)));
+}
+
+// ============================================================================
+// Theme Tests
+// ============================================================================
+
+#[test]
+fn test_theme_palettes() {
+    assert_eq!("colorblind-safe".parse::<Theme>(), Ok(Theme::ColorblindSafe));
+    assert!("solarized".parse::<Theme>().is_err());
+
+    assert_eq!(Theme::Default.color(Role::Good), Some(Color::Green));
+    assert_eq!(Theme::Default.color(Role::Bad), Some(Color::Red));
+
+    // good and bad must not rely on telling red from green
+    let good = Theme::ColorblindSafe.color(Role::Good);
+    let bad = Theme::ColorblindSafe.color(Role::Bad);
+    assert_ne!(good, bad);
+    assert!([good, bad].iter().all(|c| !matches!(c, Some(Color::Red | Color::Green))));
+
+    assert_eq!(Theme::HighContrast.color(Role::Muted), None);
+    assert_eq!(Theme::Monochrome.color(Role::Heading), None);
+}
+
+// ============================================================================
+// HTML Report Tests
+// ============================================================================
+
+#[test]
+fn test_html_report_escapes_labels() {
+    let tx = Transaction::from_hex(P2P_TX).unwrap();
+    let html = render_html(&tx, &[], &["<script>".to_string()], "2024-01-01T00:00:00Z");
+    assert!(html.contains("&lt;script&gt;"));
+    assert!(!html.contains("<script>"));
+    assert!(html.contains("<svg"));
+}
@@ -0,0 +1,115 @@
+//! Color themes
+//!
+//! Renderers style text by what it is (`Role`) rather than by color, and the
+//! active theme decides the color. Emphasis such as bold is left to the
+//! renderer, so it survives every theme including monochrome.
+
+use colored::{Color, ColoredString, Colorize};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Theme {
+    // the original palette, tuned for dark terminals
+    #[default]
+    Default,
+    // no white/grey/yellow text, so light backgrounds stay readable
+    HighContrast,
+    // blue/orange instead of green/red for good/bad states
+    ColorblindSafe,
+    // terminal foreground only; bold still marks emphasis
+    Monochrome,
+}
+
+// What a piece of text is, independent of how it is colored
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Banner,
+    Heading,
+    Label,
+    Muted,
+    Hash,
+    Amount,
+    Type,
+    Good,
+    Warn,
+    Bad,
+    Accent,
+}
+
+static ACTIVE: AtomicU8 = AtomicU8::new(0);
+
+impl Theme {
+    const ALL: [Theme; 4] = [Theme::Default, Theme::HighContrast, Theme::ColorblindSafe, Theme::Monochrome];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Theme::Default => "default",
+            Theme::HighContrast => "high-contrast",
+            Theme::ColorblindSafe => "colorblind-safe",
+            Theme::Monochrome => "monochrome",
+        }
+    }
+
+    // Color for a role; None keeps the terminal's foreground
+    pub fn color(self, role: Role) -> Option<Color> {
+        match self {
+            Theme::Default => Some(match role {
+                Role::Banner => Color::BrightBlue,
+                Role::Heading | Role::Type => Color::Cyan,
+                Role::Label => Color::White,
+                Role::Muted => Color::BrightBlack,
+                Role::Hash | Role::Warn => Color::Yellow,
+                Role::Amount | Role::Good => Color::Green,
+                Role::Bad => Color::Red,
+                Role::Accent => Color::Magenta,
+            }),
+            Theme::HighContrast => match role {
+                Role::Label | Role::Muted => None,
+                Role::Banner | Role::Heading | Role::Type => Some(Color::Blue),
+                Role::Hash | Role::Warn | Role::Accent => Some(Color::Magenta),
+                Role::Amount | Role::Good => Some(Color::Green),
+                Role::Bad => Some(Color::Red),
+            },
+            Theme::ColorblindSafe => match role {
+                Role::Label => None,
+                Role::Banner | Role::Amount | Role::Good => Some(Color::Blue),
+                Role::Heading | Role::Type | Role::Hash => Some(Color::Cyan),
+                Role::Muted => Some(Color::BrightBlack),
+                // orange where the terminal offers it
+                Role::Warn | Role::Bad => Some(Color::TrueColor { r: 230, g: 159, b: 0 }),
+                Role::Accent => Some(Color::Magenta),
+            },
+            Theme::Monochrome => None,
+        }
+    }
+}
+
+impl FromStr for Theme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Theme::ALL.into_iter()
+            .find(|theme| theme.name() == s.to_ascii_lowercase())
+            .ok_or_else(|| format!("Unknown theme '{}' (expected default, high-contrast, colorblind-safe or monochrome)", s))
+    }
+}
+
+// Select the theme used by every renderer
+pub fn set_theme(theme: Theme) {
+    ACTIVE.store(theme as u8, Ordering::Relaxed);
+}
+
+pub fn theme() -> Theme {
+    Theme::ALL[ACTIVE.load(Ordering::Relaxed) as usize]
+}
+
+// Style text for its role under the active theme
+pub fn paint(text: &str, role: Role) -> ColoredString {
+    match theme().color(role) {
+        Some(color) => text.color(color),
+        None => text.normal(),
+    }
+}
@@ -0,0 +1,78 @@
+//! Timelock unlock schedule and coin age
+
+use btc_tx_parser::{ChainTip, CoinAge, LockKind, LockUnit, ScheduleEntry, Transaction, UnlockStatus};
+use colored::Colorize;
+use std::io::{self, Write};
+use crate::theme::{paint, Role};
+use crate::transaction::group_thousands;
+
+pub fn write_unlock_schedule(out: &mut dyn Write, tx: &Transaction, tip: &ChainTip, schedule: &[ScheduleEntry]) -> io::Result<()> {
+    writeln!(out, "Unlock schedule for {}", tx.txid)?;
+    let height = tip.height.map(|h| h.to_string()).unwrap_or_else(|| "unknown".to_string());
+    writeln!(out, "  Evaluated at height {}, {}", height, format_unix_time(tip.time))?;
+    if schedule.is_empty() {
+        writeln!(out, "  No timelocks found.")?;
+        return Ok(());
+    }
+
+    for entry in schedule {
+        let lock = &entry.timelock;
+        let target = match (lock.kind, lock.unit) {
+            (LockKind::Absolute, LockUnit::Blocks) => format!("height {}", lock.value),
+            (LockKind::Absolute, LockUnit::Seconds) => format_unix_time(lock.value),
+            (LockKind::Relative, LockUnit::Blocks) => format!("{} blocks after confirmation", lock.value),
+            (LockKind::Relative, LockUnit::Seconds) => format!("{} seconds after confirmation", lock.value),
+        };
+        let status = match entry.status {
+            UnlockStatus::Unlocked => paint("UNLOCKED", Role::Good).bold(),
+            UnlockStatus::Locked => paint("LOCKED", Role::Bad).bold(),
+            UnlockStatus::AfterConfirmation => paint("RELATIVE", Role::Warn).bold(),
+            UnlockStatus::Unknown => paint("UNKNOWN", Role::Muted).bold(),
+        };
+        let owner = lock.input.map(|i| format!("input #{} ", i)).unwrap_or_default();
+        let mut line = format!("  [{}] {}{}: {}", status, owner, lock.source, target);
+        if entry.status == UnlockStatus::Locked {
+            if let Some(blocks) = entry.blocks_remaining {
+                line.push_str(&format!(" ({} blocks to go)", blocks));
+            }
+            if let Some(at) = entry.unlocks_at {
+                line.push_str(&format!(", ~{}", format_unix_time(at)));
+            }
+        }
+        writeln!(out, "{}", line)?;
+    }
+    Ok(())
+}
+
+pub fn write_coin_age(out: &mut dyn Write, tx: &Transaction, age: &CoinAge) -> io::Result<()> {
+    writeln!(out, "Coin age for {}", tx.txid)?;
+    writeln!(out, "  Spent at height {}", age.spend_height)?;
+    for input in &age.inputs {
+        let value = input.value
+            .map(|v| format!("{} sats", group_thousands(v.to_sat())))
+            .unwrap_or_else(|| "value unknown".to_string());
+        let line = match (input.confirmed_height, input.age_blocks, input.age_days) {
+            (Some(height), Some(blocks), Some(days)) => {
+                let destroyed = input.value_days_destroyed
+                    .map(|vdd| format!(", {:.2} BTC-days destroyed", vdd))
+                    .unwrap_or_default();
+                format!("{}, confirmed at {}: {} blocks ({:.1} days){}", value, height, group_thousands(blocks as u64), days, destroyed)
+            }
+            _ if tx.inputs[input.index].is_coinbase => "coinbase".to_string(),
+            _ => format!("{}, {}", value, paint("confirmation height unknown", Role::Muted)),
+        };
+        writeln!(out, "  input #{} {}", input.index, line)?;
+    }
+    let total = match age.value_days_destroyed {
+        Some(total) => paint(&format!("{:.2} BTC-days", total), Role::Amount).bold(),
+        None => paint("incomplete (needs every input's value and height)", Role::Warn).bold(),
+    };
+    writeln!(out, "  Value-days destroyed: {}", total)?;
+    Ok(())
+}
+
+pub fn format_unix_time(time: u64) -> String {
+    chrono::DateTime::from_timestamp(time as i64, 0)
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or_else(|| "invalid timestamp".to_string())
+}
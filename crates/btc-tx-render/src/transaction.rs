@@ -0,0 +1,507 @@
+//! Pretty, summary and ASCII views of a single transaction
+
+use btc_tx_parser::{CoinbaseInfo, Finding, Inscription, InputSignature, SignatureKind, Htlc, LockKind, LockUnit, OutputSpend, Payload, PrivacyReport, Sequence, TapscriptKind, TimestampAnchor, Transaction, TxShape, MAX_ENTROPY_INPUTS, MAX_ENTROPY_OUTPUTS};
+use colored::Colorize;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use crate::theme::{paint, Role};
+use crate::timelock::format_unix_time;
+
+// Data at least this large is summarized by size and hash instead of printed
+pub const LARGE_DATA_BYTES: usize = 256;
+
+pub fn write_findings(out: &mut dyn Write, findings: &[Finding]) -> io::Result<()> {
+    writeln!(out, "{}", paint("Analysis", Role::Heading).bold())?;
+    writeln!(out, "{}", paint(&"─".repeat(60), Role::Muted))?;
+    if findings.is_empty() {
+        writeln!(out, "  {}", paint("No findings", Role::Muted))?;
+    }
+    for finding in findings {
+        let location = match (finding.input, finding.output) {
+            (Some(i), _) => format!("input #{}", i),
+            (_, Some(o)) => format!("output #{}", o),
+            _ => "transaction".to_string(),
+        };
+        writeln!(out, "  {} {} {}{}", paint(&format!("[{}]", finding.kind), Role::Accent).bold(),
+            paint(&location, Role::Label).bold(), finding.message, paint(&describe_confidence(finding), Role::Muted))?;
+        for evidence in &finding.evidence {
+            writeln!(out, "      {} {}", paint("·", Role::Muted), paint(evidence, Role::Muted))?;
+        }
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+pub fn write_shape(out: &mut dyn Write, shape: &TxShape) -> io::Result<()> {
+    writeln!(out, "{}", paint("Shape", Role::Heading).bold())?;
+    writeln!(out, "{}", paint(&"─".repeat(60), Role::Muted))?;
+    writeln!(out, "  {}", shape)?;
+    writeln!(out)
+}
+
+pub fn write_timestamp_anchor(out: &mut dyn Write, anchor: &TimestampAnchor) -> io::Result<()> {
+    writeln!(out, "{}", paint("Timestamp Anchor", Role::Heading).bold())?;
+    writeln!(out, "{}", paint(&"─".repeat(60), Role::Muted))?;
+    writeln!(out, "  {} #{} commits to the proof's digest", paint("Output:", Role::Label).bold(), anchor.output)?;
+    writeln!(out, "  {} {}", paint("Commitment:", Role::Label).bold(), paint(&anchor.commitment, Role::Hash))?;
+    writeln!(out, "  {} {}", paint("File Digest:", Role::Label).bold(), paint(&anchor.digest, Role::Hash))?;
+    match (anchor.block_height, &anchor.merkle_root) {
+        (Some(height), Some(root)) => {
+            writeln!(out, "  {} block {}", paint("Attested:", Role::Label).bold(), paint(&height.to_string(), Role::Good))?;
+            writeln!(out, "  {} {}", paint("Merkle Root:", Role::Label).bold(), paint(root, Role::Hash))?;
+        }
+        _ => writeln!(out, "  {} {}", paint("Attested:", Role::Label).bold(), paint("no Bitcoin attestation yet (upgrade the proof)", Role::Warn))?,
+    }
+    writeln!(out)
+}
+
+// "2-of-3 multisig, timelock", or "unrecognized"
+fn describe_script_kinds(kinds: &[TapscriptKind]) -> String {
+    match kinds {
+        [] => "unrecognized".to_string(),
+        kinds => kinds.iter().map(|k| k.to_string()).collect::<Vec<_>>().join(", "),
+    }
+}
+
+// " (70% confidence)" for heuristic findings, empty for facts
+pub fn describe_confidence(finding: &Finding) -> String {
+    finding.confidence
+        .map(|c| format!(" ({:.0}% confidence)", c * 100.0))
+        .unwrap_or_default()
+}
+
+pub fn write_privacy(out: &mut dyn Write, report: &PrivacyReport) -> io::Result<()> {
+    writeln!(out, "{}", paint("Privacy", Role::Heading).bold())?;
+    writeln!(out, "{}", paint(&"─".repeat(60), Role::Muted))?;
+    match &report.entropy {
+        Some(entropy) => {
+            let role = if entropy.interpretations > 1 { Role::Good } else { Role::Warn };
+            writeln!(out, "  {} {} ({} interpretation(s) of which inputs paid which outputs)",
+                paint("Entropy:", Role::Label).bold(), paint(&format!("{:.2} bits", entropy.bits), role), entropy.interpretations)?;
+        }
+        None => {
+            let reason = format!("needs every input value, at most {} inputs and {} outputs", MAX_ENTROPY_INPUTS, MAX_ENTROPY_OUTPUTS);
+            writeln!(out, "  {} {}", paint("Entropy:", Role::Label).bold(), paint(&reason, Role::Muted))?;
+        }
+    }
+    for round in &report.round_outputs {
+        writeln!(out, "  {} output #{} is a multiple of {} sats, likely a payment",
+            paint("Round amount:", Role::Label).bold(), round.output, group_thousands(round.unit))?;
+    }
+    for equal in &report.equal_outputs {
+        let outputs: Vec<String> = equal.outputs.iter().map(|o| format!("#{}", o)).collect();
+        writeln!(out, "  {} {} outputs of {} sats ({})", paint("Equal outputs:", Role::Label).bold(),
+            equal.outputs.len(), group_thousands(equal.value.to_sat()), outputs.join(", "))?;
+    }
+    for link in &report.change_links {
+        writeln!(out, "  {} input #{} ⇢ output #{}: {} {}", paint("Change link:", Role::Label).bold(),
+            link.input, link.output, link.detail, paint(&format!("({:.0}% confidence)", link.confidence * 100.0), Role::Muted))?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+// "71 bytes, low R, SIGHASH_ALL"
+pub fn describe_signature(signature: &InputSignature) -> String {
+    format!("{} bytes, {} R{}, SIGHASH_{}", signature.length,
+        if signature.low_r == Some(false) { "high" } else { "low" },
+        if signature.low_s == Some(false) { ", high S" } else { "" },
+        signature.sighash)
+}
+
+// One-line form for summaries
+pub fn describe_privacy(report: &PrivacyReport) -> String {
+    let entropy = match &report.entropy {
+        Some(entropy) => format!("entropy {:.2} bits ({} interpretations)", entropy.bits, entropy.interpretations),
+        None => "entropy unknown".to_string(),
+    };
+    let equal: usize = report.equal_outputs.iter().map(|e| e.outputs.len()).sum();
+    format!("{}, {} round output(s), {} equal-value output(s)", entropy, report.round_outputs.len(), equal)
+}
+
+// Full colored breakdown
+pub fn write_pretty(out: &mut dyn Write, tx: &Transaction) -> io::Result<()> {
+    let payloads = tx.payloads(LARGE_DATA_BYTES);
+    let htlcs = tx.htlcs();
+    writeln!(out)?;
+    writeln!(out, "{}", paint("═══════════════════════════════════════════════════════════════", Role::Banner))?;
+    writeln!(out, "{}", paint("                    BITCOIN TRANSACTION", Role::Banner).bold())?;
+    writeln!(out, "{}", paint("═══════════════════════════════════════════════════════════════", Role::Banner))?;
+    writeln!(out)?;
+
+    writeln!(out, "{}", paint("Transaction Info", Role::Heading).bold())?;
+    writeln!(out, "  {} {}", paint("TXID:", Role::Label).bold(), paint(&tx.txid, Role::Hash))?;
+    if tx.is_segwit {
+        writeln!(out, "  {} {}", paint("WTXID:", Role::Label).bold(), paint(&tx.wtxid, Role::Hash))?;
+    }
+    writeln!(out, "  {} {}", paint("Version:", Role::Label).bold(), tx.version)?;
+    writeln!(out, "  {} {}", paint("SegWit:", Role::Label).bold(), if tx.is_segwit { paint("Yes", Role::Good) } else { paint("No", Role::Label) })?;
+    writeln!(out, "  {} {}", paint("RBF:", Role::Label).bold(), if tx.is_rbf() { paint("Yes (opt-in)", Role::Warn) } else { paint("No", Role::Label) })?;
+    writeln!(out, "  {} {} bytes", paint("Size:", Role::Label).bold(), tx.raw_size)?;
+    writeln!(out, "  {} {} vbytes", paint("Virtual Size:", Role::Label).bold(), tx.vsize())?;
+    writeln!(out, "  {} {} WU", paint("Weight:", Role::Label).bold(), tx.weight)?;
+    writeln!(out, "  {} {}", paint("Locktime:", Role::Label).bold(), tx.locktime)?;
+    writeln!(out)?;
+
+    writeln!(out, "{} ({})", paint("Inputs", Role::Heading).bold(), tx.inputs.len())?;
+    writeln!(out, "{}", paint(&"─".repeat(60), Role::Muted))?;
+    for input in &tx.inputs {
+        writeln!(out, "  {} #{}", paint("Input", Role::Label).bold(), input.index)?;
+        if let Some(note) = &input.note {
+            writeln!(out, "    {} {}", paint("Note:", Role::Label), paint(note, Role::Accent))?;
+        }
+        if input.is_coinbase {
+            writeln!(out, "    {} {}", paint("Type:", Role::Label), paint("Coinbase", Role::Accent).bold())?;
+            if let Some(coinbase) = input.coinbase.as_ref().map(describe_coinbase).filter(|text| !text.is_empty()) {
+                writeln!(out, "    {} {}", paint("Coinbase Data:", Role::Label), coinbase)?;
+            }
+        } else {
+            writeln!(out, "    {} {}:{}", paint("Spends:", Role::Label), paint(&input.txid.to_string(), Role::Hash), input.vout)?;
+        }
+        if let Some(address) = &input.address {
+            writeln!(out, "    {} {} ({})", paint("Address:", Role::Label), paint(&address.mainnet, Role::Hash), address.address_type)?;
+        }
+        if let Some(value) = input.value {
+            writeln!(out, "    {} {} sats ({})", 
+                paint("Value:", Role::Label), 
+                paint(&value.to_sat().to_string(), Role::Amount),
+                value
+            )?;
+        }
+        writeln!(out, "    {} 0x{:08x} ({})", paint("Sequence:", Role::Label), input.sequence, describe_sequence(tx, input.sequence))?;
+        for htlc in htlcs.iter().filter(|h| h.input == Some(input.index)) {
+            writeln!(out, "    {} {}", paint("Spends HTLC:", Role::Label), paint(&describe_htlc(htlc), Role::Accent))?;
+        }
+        if !input.script_sig.bytes.is_empty() {
+            writeln!(out, "    {} {} bytes", paint("ScriptSig:", Role::Label), input.script_sig.size)?;
+            let asm = summarize_asm(&input.script_sig.asm, &payloads, &format!("inputs[{}].script_sig.", input.index));
+            if asm.len() < 100 {
+                writeln!(out, "      {}", paint(&asm, Role::Muted))?;
+            }
+            if let Some(parts) = &input.script_sig_parts {
+                let counts: Vec<String> = [(parts.signatures.len(), "signature"), (parts.pubkeys.len(), "pubkey"), (parts.other.len(), "other push")]
+                    .into_iter()
+                    .filter(|&(count, _)| count > 0)
+                    .map(|(count, noun)| plural(count, noun))
+                    .collect();
+                let pushed = if counts.is_empty() { "redeem script only".to_string() } else { counts.join(", ") };
+                writeln!(out, "      {} {}: {}", paint("spends", Role::Muted), parts.describe(), paint(&pushed, Role::Accent))?;
+                if let Some(redeem) = &parts.redeem_script {
+                    writeln!(out, "      {} {} bytes", paint("redeem script", Role::Muted), redeem.size)?;
+                    writeln!(out, "        {}", paint(&summarize_asm(&redeem.asm, &[], ""), Role::Muted))?;
+                }
+            }
+        }
+        if let Some(witness) = &input.witness {
+            writeln!(out, "    {} {} items", paint("Witness:", Role::Label), witness.len())?;
+            for (i, item) in witness.iter().enumerate() {
+                let field = format!("inputs[{}].witness[{}]", input.index, i);
+                let location = format!("witness[{}]", i);
+                let schnorr = input.signatures.iter().find(|s| s.kind == SignatureKind::Schnorr && s.location == location);
+                if let Some(signature) = schnorr {
+                    writeln!(out, "      [{}] {}", i, paint(&format!("BIP-340 signature, SIGHASH_{}", signature.sighash), Role::Muted))?;
+                } else if let Some(payload) = payloads.iter().find(|p| p.field == field) {
+                    writeln!(out, "      [{}] {}", i, paint(&describe_payload(payload), Role::Muted))?;
+                } else if item.len() < 50 {
+                    writeln!(out, "      [{}] {}", i, paint(&hex::encode(item), Role::Muted))?;
+                } else {
+                    writeln!(out, "      [{}] {}...", i, &paint(&hex::encode(&item[..32]), Role::Muted))?;
+                }
+            }
+        }
+        if let Some(taproot) = &input.taproot {
+            let parity = if taproot.output_key_parity == 0 { "even" } else { "odd" };
+            writeln!(out, "    {} script path, leaf version 0x{:02x}, depth {}, {} output key", paint("Taproot:", Role::Label),
+                taproot.leaf_version, taproot.path_length, parity)?;
+            writeln!(out, "      {} {}", paint("internal key", Role::Muted), paint(&taproot.internal_key, Role::Hash))?;
+            writeln!(out, "      {} {} bytes, {}", paint("leaf script", Role::Muted), taproot.leaf_script.size,
+                paint(&describe_script_kinds(&taproot.leaf_kinds), Role::Accent))?;
+            writeln!(out, "        {}", paint(&summarize_asm(&taproot.leaf_script.asm, &[], ""), Role::Muted))?;
+        }
+        if let Some(witness_script) = &input.witness_script {
+            let wrapping = if witness_script.nested { "P2SH-P2WSH" } else { "P2WSH" };
+            writeln!(out, "    {} {}, {} bytes, {}", paint("Witness Script:", Role::Label), wrapping, witness_script.script.size,
+                paint(&describe_script_kinds(&witness_script.kinds), Role::Accent))?;
+            writeln!(out, "      {}", paint(&summarize_asm(&witness_script.script.asm, &[], ""), Role::Muted))?;
+        }
+        for signature in input.signatures.iter().filter(|s| s.is_strict_ecdsa()) {
+            let role = if signature.low_r == Some(true) && signature.low_s == Some(true) { Role::Muted } else { Role::Warn };
+            writeln!(out, "    {} {}", paint("Signature:", Role::Label), paint(&describe_signature(signature), role))?;
+        }
+        for signature in input.signatures.iter().filter(|s| !s.is_canonical()) {
+            let text = format!("{}: non-canonical DER ({})", signature.location, signature.der_issues.join(", "));
+            writeln!(out, "    {} {}", paint("Signature:", Role::Label), paint(&text, Role::Bad))?;
+        }
+        writeln!(out)?;
+    }
+
+    writeln!(out, "{} ({})", paint("Outputs", Role::Heading).bold(), tx.outputs.len())?;
+    writeln!(out, "{}", paint(&"─".repeat(60), Role::Muted))?;
+    for output in &tx.outputs {
+        writeln!(out, "  {} #{}", paint("Output", Role::Label).bold(), output.index)?;
+        if let Some(note) = &output.note {
+            writeln!(out, "    {} {}", paint("Note:", Role::Label), paint(note, Role::Accent))?;
+        }
+        writeln!(out, "    {} {} sats ({})", 
+            paint("Value:", Role::Label), 
+            paint(&output.value.to_sat().to_string(), Role::Good).bold(),
+            output.value
+        )?;
+        if let Some(spend) = &output.spend {
+            let role = if spend.spent { Role::Warn } else { Role::Good };
+            writeln!(out, "    {} {}", paint("Status:", Role::Label), paint(&describe_spend(spend), role))?;
+        }
+        let script_type = match &output.multisig {
+            Some(multisig) => format!("{} ({})", output.script_type, multisig),
+            None => output.script_type.to_string(),
+        };
+        writeln!(out, "    {} {}", paint("Type:", Role::Label), paint(&script_type, Role::Type))?;
+        if let Some(htlc) = htlcs.iter().find(|h| h.output == Some(output.index)) {
+            writeln!(out, "    {} {}", paint("Label:", Role::Label), paint(&format!("HTLC ({})", describe_htlc(htlc)), Role::Accent))?;
+        }
+        if let Some(addr) = &output.address {
+            writeln!(out, "    {} {}", paint("Address:", Role::Label), paint(&addr.mainnet, Role::Hash))?;
+            writeln!(out, "    {} {}", paint("Testnet:", Role::Label), paint(&addr.testnet, Role::Muted))?;
+        }
+        writeln!(out, "    {} {} bytes", paint("Script:", Role::Label), output.script_pubkey.size)?;
+        let asm = summarize_asm(&output.script_pubkey.asm, &payloads, &format!("outputs[{}].script_pubkey.", output.index));
+        if asm.len() < 100 {
+            writeln!(out, "      {}", paint(&asm, Role::Muted))?;
+        }
+        writeln!(out)?;
+    }
+
+    writeln!(out, "{}", paint("Summary", Role::Heading).bold())?;
+    writeln!(out, "{}", paint(&"─".repeat(60), Role::Muted))?;
+    writeln!(out, "  {} {} sats ({})", 
+        paint("Total Output:", Role::Label).bold(),
+        paint(&tx.total_output_satoshis.to_sat().to_string(), Role::Amount),
+        tx.total_output_satoshis
+    )?;
+    if let Some(fee) = tx.fee_satoshis {
+        writeln!(out, "  {} {} sats ({})", 
+            paint("Fee:", Role::Label).bold(),
+            paint(&fee.to_sat().to_string(), Role::Bad),
+            fee
+        )?;
+        let fee_rate = fee.to_sat() as f64 / tx.vsize() as f64;
+        writeln!(out, "  {} {:.2} sat/vB", paint("Fee Rate:", Role::Label).bold(), fee_rate)?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+// Human-readable summary
+pub fn write_summary(out: &mut dyn Write, tx: &Transaction) -> io::Result<()> {
+    writeln!(out, "Transaction: {}", tx.txid)?;
+    writeln!(out, "  Version: {}, SegWit: {}, RBF: {}", tx.version, tx.is_segwit, tx.is_rbf())?;
+    writeln!(out, "  {} input(s), {} output(s)", tx.inputs.len(), tx.outputs.len())?;
+    writeln!(out, "  Size: {} bytes, vSize: {} vbytes", tx.raw_size, tx.vsize())?;
+    writeln!(out, "  Total output: {} ({} sats)", tx.total_output_satoshis, tx.total_output_satoshis.to_sat())?;
+    
+    if let Some(fee) = tx.fee_satoshis {
+        writeln!(out, "  Fee: {} ({} sats)", fee, fee.to_sat())?;
+    }
+
+    writeln!(out, "\nOutputs:")?;
+    for output in &tx.outputs {
+        let addr = output.address.as_ref()
+            .map(|a| a.mainnet.clone())
+            .unwrap_or_else(|| "[non-standard]".to_string());
+        writeln!(out, "  #{}: {} -> {} ({})", 
+            output.index, 
+            output.value, 
+            addr,
+            output.script_type
+        )?;
+    }
+
+    let notes = note_lines(tx);
+    if !notes.is_empty() {
+        writeln!(out, "\nNotes:")?;
+        for line in notes {
+            writeln!(out, "  {}", line)?;
+        }
+    }
+    Ok(())
+}
+
+// "input #0: ..." for every annotated input and output
+fn note_lines(tx: &Transaction) -> Vec<String> {
+    let inputs = tx.inputs.iter().filter_map(|i| i.note.as_ref().map(|note| format!("input #{}: {}", i.index, note)));
+    let outputs = tx.outputs.iter().filter_map(|o| o.note.as_ref().map(|note| format!("output #{}: {}", o.index, note)));
+    inputs.chain(outputs).collect()
+}
+
+// ASCII art visualization
+pub fn write_ascii(out: &mut dyn Write, tx: &Transaction) -> io::Result<()> {
+    writeln!(out)?;
+    writeln!(out, "┌─────────────────────────────────────────────────────────────────────┐")?;
+    writeln!(out, "│ TX: {}...{} │", &tx.txid[..16], &tx.txid[tx.txid.len()-8..])?;
+    writeln!(out, "├─────────────────────────────────────────────────────────────────────┤")?;
+    
+    let input_count = tx.inputs.len();
+    let output_count = tx.outputs.len();
+    let max_rows = input_count.max(output_count);
+
+    for i in 0..max_rows {
+        let input_str = if i < input_count {
+            let input = &tx.inputs[i];
+            if input.is_coinbase {
+                "  [COINBASE]".to_string()
+            } else {
+                let value_str = input.value
+                    .map(|v| format!("{:.4} BTC", v.to_btc()))
+                    .unwrap_or_else(|| "? BTC".to_string());
+                format!("  {}:{} ({})", &input.txid.to_string()[..8], input.vout, value_str)
+            }
+        } else {
+            String::new()
+        };
+
+        let output_str = if i < output_count {
+            let output = &tx.outputs[i];
+            let addr = output.address.as_ref()
+                .map(|a| if a.mainnet.len() > 20 { 
+                    format!("{}...", &a.mainnet[..20]) 
+                } else { 
+                    a.mainnet.clone() 
+                })
+                .unwrap_or_else(|| "[script]".to_string());
+            let badge = match &output.spend {
+                Some(spend) if spend.spent => "[S] ",
+                Some(_) => "[U] ",
+                None => "",
+            };
+            format!("{}{:.4} BTC -> {}", badge, output.value.to_btc(), addr)
+        } else {
+            String::new()
+        };
+
+        let arrow = if i == max_rows / 2 { "═══►" } else { "    " };
+        
+        writeln!(out, "│ {:30} {} {:34} │", 
+            if input_str.len() > 30 { format!("{}...", &input_str[..27]) } else { input_str },
+            arrow,
+            if output_str.len() > 34 { format!("{}...", &output_str[..31]) } else { output_str }
+        )?;
+    }
+
+    writeln!(out, "├─────────────────────────────────────────────────────────────────────┤")?;
+    
+    let total = format!("Total: {}", tx.total_output_satoshis);
+    let fee = tx.fee_satoshis
+        .map(|f| format!(" | Fee: {} sats", f.to_sat()))
+        .unwrap_or_default();
+    
+    writeln!(out, "│ {:<67} │", format!("{}{}", total, fee))?;
+    if tx.outputs.iter().any(|o| o.spend.is_some()) {
+        writeln!(out, "│ {:<67} │", "[S] spent, [U] unspent")?;
+    }
+    for line in note_lines(tx) {
+        let line: String = if line.chars().count() > 67 { line.chars().take(64).chain("...".chars()).collect() } else { line };
+        writeln!(out, "│ {:<67} │", line)?;
+    }
+    writeln!(out, "└─────────────────────────────────────────────────────────────────────┘")?;
+    writeln!(out)?;
+    Ok(())
+}
+
+// One line per inscription body written to disk, `paths` in the same order
+pub fn write_extracted_inscriptions(out: &mut dyn Write, inscriptions: &[Inscription], paths: &[PathBuf]) -> io::Result<()> {
+    for (inscription, path) in inscriptions.iter().zip(paths) {
+        writeln!(out, "{} {} ({}, {} bytes) -> {}",
+            paint("Wrote", Role::Good).bold(),
+            paint(&inscription.id, Role::Hash),
+            inscription.content_type.as_deref().unwrap_or("no content type"),
+            inscription.size,
+            path.display())?;
+    }
+    Ok(())
+}
+
+// "spent by <txid>:<vin>" or "unspent"
+pub fn describe_spend(spend: &OutputSpend) -> String {
+    match (spend.spent, &spend.txid, spend.vin) {
+        (false, _, _) => "unspent".to_string(),
+        (true, Some(txid), Some(vin)) => format!("spent by {}:{}", txid, vin),
+        (true, Some(txid), None) => format!("spent by {}", txid),
+        (true, None, _) => "spent".to_string(),
+    }
+}
+
+// Hash lock and refund timeout, e.g. "SHA256 ab12..., refund at block 850000"
+pub fn describe_htlc(htlc: &Htlc) -> String {
+    let timeout = match (htlc.timeout.kind, htlc.timeout.unit) {
+        (LockKind::Absolute, LockUnit::Blocks) => format!("refund at block {}", htlc.timeout.value),
+        (LockKind::Absolute, LockUnit::Seconds) => format!("refund at {}", format_unix_time(htlc.timeout.value)),
+        (LockKind::Relative, LockUnit::Blocks) => format!("refund {} blocks after confirmation", htlc.timeout.value),
+        (LockKind::Relative, LockUnit::Seconds) => format!("refund {}s after confirmation", htlc.timeout.value),
+    };
+    format!("{} {}, {}", htlc.hash_lock, htlc.payment_hash, timeout)
+}
+
+// BIP-68 reading of an input's nSequence, e.g. "relative lock 144 blocks"
+pub fn describe_sequence(tx: &Transaction, sequence: Sequence) -> String {
+    match sequence.relative_locktime() {
+        None => "relative lock disabled".to_string(),
+        Some(lock) if tx.version < 2 => format!("relative lock {}, not enforced before version 2", lock),
+        Some(lock) => format!("relative lock {}", lock),
+    }
+}
+
+// "height 840,000, tag \"/ViaBTC/\", extranonce 8 bytes"; empty when
+// nothing was recognized
+pub fn describe_coinbase(info: &CoinbaseInfo) -> String {
+    let mut parts = Vec::new();
+    if let Some(height) = info.height {
+        parts.push(format!("height {}", group_thousands(height as u64)));
+    }
+    if let Some(tag) = &info.pool_tag {
+        parts.push(format!("tag {:?}", tag));
+    }
+    if let Some(size) = info.extranonce_size {
+        parts.push(format!("extranonce {} byte{}", size, if size == 1 { "" } else { "s" }));
+    }
+    parts.join(", ")
+}
+
+// "83,412 bytes, sha256=1f0e…"
+pub fn describe_payload(payload: &Payload) -> String {
+    format!("{} bytes, sha256={}…", group_thousands(payload.size as u64), &payload.sha256[..16])
+}
+
+// Replace large pushes in an ASM string with their descriptions; `prefix`
+// selects the payloads of this script, which appear in push order
+fn summarize_asm(asm: &str, payloads: &[Payload], prefix: &str) -> String {
+    let mut large = payloads.iter().filter(|p| p.field.starts_with(prefix));
+    asm.split(' ')
+        .map(|token| {
+            if token.len() < LARGE_DATA_BYTES * 2 {
+                return token.to_string();
+            }
+            match large.next() {
+                Some(payload) => format!("<{}>", describe_payload(payload)),
+                None => format!("<{} bytes>", group_thousands(token.len() as u64 / 2)),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// "1 signature", "2 signatures"
+fn plural(count: usize, noun: &str) -> String {
+    format!("{} {}{}", count, noun, if count == 1 { "" } else { "s" })
+}
+
+pub(crate) fn group_thousands(n: u64) -> String {
+    let digits = n.to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
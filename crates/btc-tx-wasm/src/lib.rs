@@ -1,26 +1,148 @@
 //! WebAssembly bindings for Bitcoin transaction parser
 
 use wasm_bindgen::prelude::*;
-use btc_tx_parser::Transaction;
+use btc_tx_parser::{parse_batch_line, Amount, ExactBtc, BatchStats, Block, MerkleProof, NoteTarget, Psbt, Transaction, TxBundle};
 #[wasm_bindgen(start)]
 pub fn init() {
     console_error_panic_hook::set_once();
 }
+// `exact_btc` writes the BTC amounts (`value_btc`, `total_output_btc`,
+// `fee_btc`) as exact strings like "0.00010000" instead of numbers
 #[wasm_bindgen]
-pub fn parse_transaction(hex: &str) -> Result<JsValue, JsValue> {
+pub fn parse_transaction(hex: &str, exact_btc: Option<bool>) -> Result<JsValue, JsValue> {
     let tx = Transaction::from_hex(hex)
         .map_err(|e| JsValue::from_str(&format!("Parse error: {}", e)))?;
 
-    serde_wasm_bindgen::to_value(&tx)
+    match exact_btc {
+        Some(true) => serde_wasm_bindgen::to_value(&ExactBtc(&tx)),
+        _ => serde_wasm_bindgen::to_value(&tx),
+    }
+    .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+}
+#[wasm_bindgen]
+pub fn parse_transaction_json(hex: &str, exact_btc: Option<bool>) -> Result<String, JsValue> {
+    let tx = Transaction::from_hex(hex)
+        .map_err(|e| JsValue::from_str(&format!("Parse error: {}", e)))?;
+
+    match exact_btc {
+        Some(true) => serde_json::to_string_pretty(&ExactBtc(&tx)),
+        _ => serde_json::to_string_pretty(&tx),
+    }
+    .map_err(|e| JsValue::from_str(&format!("JSON error: {}", e)))
+}
+
+// Decoded transaction plus the byte range of every field, for highlighting the
+// raw hex: { transaction, spans: [{ field, start, end }] }
+#[wasm_bindgen]
+pub fn parse_transaction_with_spans(hex: &str) -> Result<JsValue, JsValue> {
+    let (transaction, spans) = Transaction::from_hex_with_spans(hex)
+        .map_err(|e| JsValue::from_str(&format!("Parse error: {}", e)))?;
+
+    serde_wasm_bindgen::to_value(&serde_json::json!({ "transaction": transaction, "spans": spans }))
+        .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+}
+
+// Plain-text rendering in one of the CLI formats (pretty, json, summary, ascii)
+#[wasm_bindgen]
+pub fn render_transaction(hex: &str, format: &str, analyze: bool, exact_btc: Option<bool>) -> Result<String, JsValue> {
+    let format: btc_tx_render::Format = format.parse().map_err(|e: String| JsValue::from_str(&e))?;
+    let tx = Transaction::from_hex(hex)
+        .map_err(|e| JsValue::from_str(&format!("Parse error: {}", e)))?;
+
+    btc_tx_render::set_color(false);
+    let style = btc_tx_render::JsonStyle { compact: false, exact_btc: exact_btc.unwrap_or(false) };
+    Ok(btc_tx_render::render_to_string(&tx, format, analyze, style))
+}
+
+// PSBT (base64 or hex) readiness: per-input finalization status, missing
+// signatures, expected vsize/fee, and the extracted transaction as `hex`
+#[wasm_bindgen]
+pub fn psbt_readiness(psbt: &str) -> Result<JsValue, JsValue> {
+    let readiness = Psbt::parse(psbt)
+        .and_then(|psbt| psbt.readiness())
+        .map_err(|e| JsValue::from_str(&format!("Parse error: {}", e)))?;
+    let hex = readiness.transaction.to_hex();
+
+    serde_wasm_bindgen::to_value(&serde_json::json!({ "readiness": readiness, "hex": hex }))
+        .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+}
+
+// Public keys revealed by the inputs: [{ pubkey, inputs, addresses: [{ mainnet, testnet, address_type }] }]
+#[wasm_bindgen]
+pub fn key_addresses(hex: &str) -> Result<JsValue, JsValue> {
+    let tx = Transaction::from_hex(hex)
+        .map_err(|e| JsValue::from_str(&format!("Parse error: {}", e)))?;
+
+    serde_wasm_bindgen::to_value(&tx.key_addresses())
+        .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+}
+
+// Every public key in the transaction with its encoding checked:
+// [{ input | output, location, pubkey, encoding, issue? }]
+#[wasm_bindgen]
+pub fn list_pubkeys(hex: &str) -> Result<JsValue, JsValue> {
+    let tx = Transaction::from_hex(hex)
+        .map_err(|e| JsValue::from_str(&format!("Parse error: {}", e)))?;
+
+    serde_wasm_bindgen::to_value(&tx.pubkeys())
+        .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+}
+
+// Multisig scripts in bare outputs, redeem scripts and witness scripts:
+// [{ location, label: "2-of-3", required, total, pubkeys }]
+#[wasm_bindgen]
+pub fn multisig_scripts(hex: &str) -> Result<JsValue, JsValue> {
+    let tx = Transaction::from_hex(hex)
+        .map_err(|e| JsValue::from_str(&format!("Parse error: {}", e)))?;
+
+    serde_wasm_bindgen::to_value(&tx.multisig_scripts())
         .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
 }
+
+// Inscriptions revealed by a transaction: [{ id, input, content_type, content_encoding, size }]
+#[wasm_bindgen]
+pub fn list_inscriptions(hex: &str) -> Result<JsValue, JsValue> {
+    let tx = Transaction::from_hex(hex)
+        .map_err(|e| JsValue::from_str(&format!("Parse error: {}", e)))?;
+
+    serde_wasm_bindgen::to_value(&tx.inscriptions())
+        .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+}
+
+// Body of the inscription at `index` (as a Uint8Array), for previewing it
+// with its content type, e.g. through a Blob URL
 #[wasm_bindgen]
-pub fn parse_transaction_json(hex: &str) -> Result<String, JsValue> {
+pub fn inscription_content(hex: &str, index: usize) -> Result<Vec<u8>, JsValue> {
     let tx = Transaction::from_hex(hex)
         .map_err(|e| JsValue::from_str(&format!("Parse error: {}", e)))?;
 
-    serde_json::to_string_pretty(&tx)
-        .map_err(|e| JsValue::from_str(&format!("JSON error: {}", e)))
+    tx.inscriptions()
+        .into_iter()
+        .nth(index)
+        .map(|inscription| inscription.body)
+        .ok_or_else(|| JsValue::from_str(&format!("No inscription #{}", index)))
+}
+
+// Inclusion proof for `txid` in a raw block, plus whether the block's merkle
+// root matches its header: { proof, merkle_root_valid }
+#[wasm_bindgen]
+pub fn block_merkle_proof(block_hex: &str, txid: &str) -> Result<JsValue, JsValue> {
+    let block = Block::from_hex(block_hex)
+        .map_err(|e| JsValue::from_str(&format!("Parse error: {}", e)))?;
+    let proof = block.merkle_proof(txid)
+        .map_err(|e| JsValue::from_str(&format!("Parse error: {}", e)))?
+        .ok_or_else(|| JsValue::from_str(&format!("Transaction {} is not in the block", txid)))?;
+
+    serde_wasm_bindgen::to_value(&serde_json::json!({ "proof": proof, "merkle_root_valid": block.merkle_root_valid }))
+        .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+}
+
+// Check a proof object as returned by `block_merkle_proof`
+#[wasm_bindgen]
+pub fn verify_merkle_proof(proof: JsValue) -> Result<bool, JsValue> {
+    let proof: MerkleProof = serde_wasm_bindgen::from_value(proof)
+        .map_err(|e| JsValue::from_str(&format!("Invalid proof: {}", e)))?;
+    Ok(proof.verify())
 }
 
 // Get simplified transaction summary
@@ -35,9 +157,10 @@ pub fn get_transaction_summary(hex: &str) -> Result<TransactionSummary, JsValue>
         txid: tx.txid.clone(),
         version: tx.version,
         is_segwit: tx.is_segwit,
+        is_rbf: tx.is_rbf(),
         input_count: tx.inputs.len(),
         output_count: tx.outputs.len(),
-        total_output_btc: tx.total_output_btc,
+        total_output_btc: tx.total_output_satoshis.to_btc(),
         size_bytes: tx.raw_size,
         vsize_bytes: vsize,
         weight: tx.weight,
@@ -50,6 +173,7 @@ pub struct TransactionSummary {
     txid: String,
     version: i32,
     is_segwit: bool,
+    is_rbf: bool,
     input_count: usize,
     output_count: usize,
     total_output_btc: f64,
@@ -75,6 +199,11 @@ impl TransactionSummary {
         self.is_segwit
     }
 
+    #[wasm_bindgen(getter)]
+    pub fn is_rbf(&self) -> bool {
+        self.is_rbf
+    }
+
     #[wasm_bindgen(getter)]
     pub fn input_count(&self) -> usize {
         self.input_count
@@ -119,3 +248,77 @@ pub fn get_txid(hex: &str) -> Result<String, JsValue> {
         .map_err(|e| JsValue::from_str(&format!("Parse error: {}", e)))?;
     Ok(tx.txid)
 }
+
+// Package a transaction (and optional input values) as a .txbundle JSON string
+#[wasm_bindgen]
+pub fn create_bundle(hex: &str, input_values: Option<Vec<u64>>) -> Result<String, JsValue> {
+    let mut tx = Transaction::from_hex(hex)
+        .map_err(|e| JsValue::from_str(&format!("Parse error: {}", e)))?;
+
+    if let Some(values) = input_values {
+        for (input, value) in tx.inputs.iter_mut().zip(values) {
+            input.value = Some(Amount::from_sat(value));
+        }
+    }
+
+    Ok(TxBundle::new(hex, &tx).to_json())
+}
+
+// Re-render a .txbundle offline, restoring input values and fee
+#[wasm_bindgen]
+pub fn load_bundle(json: &str) -> Result<JsValue, JsValue> {
+    let tx = TxBundle::from_json(json)
+        .and_then(|bundle| bundle.transaction())
+        .map_err(|e| JsValue::from_str(&format!("Bundle error: {}", e)))?;
+
+    serde_wasm_bindgen::to_value(&tx)
+        .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+}
+
+// Attach a note to an input or output ("input"/"output") of a .txbundle,
+// returning the updated bundle JSON
+#[wasm_bindgen]
+pub fn annotate_bundle(json: &str, target: &str, index: usize, text: &str) -> Result<String, JsValue> {
+    let mut bundle = TxBundle::from_json(json)
+        .map_err(|e| JsValue::from_str(&format!("Bundle error: {}", e)))?;
+    let target: NoteTarget = target.parse().map_err(|e: String| JsValue::from_str(&e))?;
+    bundle.set_note(target, index, text);
+    // reject notes on inputs or outputs the transaction doesn't have
+    bundle.transaction().map_err(|e| JsValue::from_str(&format!("Bundle error: {}", e)))?;
+    Ok(bundle.to_json())
+}
+
+// Plain-text rendering of a .txbundle, notes included
+#[wasm_bindgen]
+pub fn render_bundle(json: &str, format: &str, analyze: bool, exact_btc: Option<bool>) -> Result<String, JsValue> {
+    let format: btc_tx_render::Format = format.parse().map_err(|e: String| JsValue::from_str(&e))?;
+    let tx = TxBundle::from_json(json)
+        .and_then(|bundle| bundle.transaction())
+        .map_err(|e| JsValue::from_str(&format!("Bundle error: {}", e)))?;
+
+    btc_tx_render::set_color(false);
+    let style = btc_tx_render::JsonStyle { compact: false, exact_btc: exact_btc.unwrap_or(false) };
+    Ok(btc_tx_render::render_to_string(&tx, format, analyze, style))
+}
+
+// Aggregate stats over a pasted dataset for dashboard charts, one
+// `<raw_tx_hex> [v1,v2,...]` line per transaction as in the CLI's `stats`:
+// { report, errors: [{ line, error }] }. Lines with input values feed the
+// feerate percentiles; blank lines and `#` comments are skipped.
+#[wasm_bindgen]
+pub fn analyze_batch(lines: Vec<String>) -> Result<JsValue, JsValue> {
+    let mut stats = BatchStats::new();
+    let mut errors = Vec::new();
+    for (number, line) in lines.iter().map(|l| l.trim()).enumerate() {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match parse_batch_line(line) {
+            Ok(tx) => stats.add(&tx),
+            Err(e) => errors.push(serde_json::json!({ "line": number + 1, "error": e })),
+        }
+    }
+
+    serde_wasm_bindgen::to_value(&serde_json::json!({ "report": stats.report(), "errors": errors }))
+        .map_err(|e| JsValue::from_str(&format!("Serialization error: {}", e)))
+}